@@ -1,19 +1,22 @@
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt::Write,
     io::Cursor,
     iter::{self, Iterator},
     mem,
+    ops::Range,
     path::{Path, PathBuf},
     sync::{
         Arc,
         atomic::{AtomicBool, AtomicUsize, Ordering},
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
+use anyhow::Context as _;
 use fancy_regex::Regex as FancyRegex;
-use ignore::WalkState;
+use ignore::{Match, WalkState, overrides::Override};
 use log::{debug, warn};
 use tokio::{
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
@@ -21,32 +24,46 @@ use tokio::{
 };
 
 use crate::{
+    command_palette::{CommandPaletteEntry, CommandPaletteState},
     commands::{
-        Command, CommandGeneral, CommandSearchFields, CommandSearchFocusFields,
-        CommandSearchFocusResults, KeyMap, display_conflict_errors,
+        Command, CommandFilePicker, CommandGeneral, CommandResults, CommandSearchFields,
+        CommandSearchFocusFields, CommandSearchFocusResults, KeyMap, display_conflict_errors,
+        display_text_input_shadows,
     },
-    config::Config,
+    config::{self, Config, Keys},
     errors::AppError,
-    fields::{FieldName, SearchFieldValues, SearchFields},
+    fields::{Field, FieldName, SearchFieldValues, SearchFields, TextField},
     file_content::{FileContentProvider, default_file_content_provider},
+    file_picker::FilePickerState,
+    git, headless_command,
     keyboard::{KeyCode, KeyEvent, KeyModifiers},
     line_reader::{BufReadExt, LineEnding},
-    replace::{self, PerformingReplacementState, ReplaceState},
-    replace::{replace_all_if_match, replacement_for_match, replacement_for_match_in_haystack},
+    mouse::{MouseEvent, MouseEventKind, ScreenArea},
+    presets::{self, Preset},
+    replace::{self, PerformingReplacementState, ReplaceState, SymlinkHandling},
+    replace::{
+        replace_all_if_match_with_spans, replacement_for_match, replacement_for_match_in_haystack,
+    },
     search::Searcher,
     search::{
-        FileSearcher, MatchContent, ParsedSearchConfig, SearchResult, SearchResultWithReplacement,
-        SearchType, contains_search, search_multiline,
+        self, FileSearcher, LineFilter, MatchContent, ParsedSearchConfig, SearchPause,
+        SearchResult, SearchResultWithReplacement, SearchType, contains_search, match_counts,
+        search_multiline,
     },
-    utils::{Either, Either::Left, Either::Right, ceil_div},
+    stdin_format::{self, StdinFormat},
+    utils::{self, Either, Either::Left, Either::Right, ceil_div},
     validation::{
         DirConfig, SearchConfig, ValidationErrorHandler, ValidationResult,
-        validate_search_configuration,
+        validate_dir_configuration, validate_search_configuration,
     },
 };
 
 const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
 const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(300);
+/// The maximum number of distinct matches listed in the match summary
+const MAX_MATCH_SUMMARY_ENTRIES: usize = 20;
+/// The width of the `[x]` checkbox at the start of each line of the search results list
+const RESULT_CHECKBOX_WIDTH: u16 = 3;
 
 /// Spawn a task that sleeps for `delay` and then runs `on_fire`. Used to
 /// debounce both search and preview-replacement refreshes.
@@ -62,7 +79,8 @@ where
 
 #[derive(Debug, Clone)]
 pub enum InputSource {
-    Directory(PathBuf),
+    /// Search files within these directories, each of which is walked in turn
+    Directory(Vec<PathBuf>),
     Stdin(Arc<String>),
 }
 
@@ -91,8 +109,28 @@ impl EventHandlingResult {
 
 #[derive(Debug)]
 pub enum BackgroundProcessingEvent {
+    /// Sent by a search task before it starts searching
+    SearchStarted,
     AddSearchResult(SearchResult),
     AddSearchResults(Vec<SearchResult>),
+    SearchProgress {
+        files_scanned: usize,
+    },
+    /// Files that looked binary but were searched anyway, as binary detection was disabled
+    BinaryFilesIncluded(usize),
+    /// Directories that weren't searched because they were beyond the maximum depth
+    DirsBeyondMaxDepth {
+        num_dirs: usize,
+        max_depth: usize,
+    },
+    /// Files that were skipped because searching them with an advanced regex took longer than
+    /// `search.regex_timeout_ms`
+    RegexTooSlow(usize),
+    /// Lines that weren't searched because they weren't valid UTF-8, with the number of such lines
+    /// in each file
+    NonUtf8LinesSkipped(Vec<(PathBuf, usize)>),
+    /// Number of `rg --json` matches skipped because their path wasn't valid UTF-8
+    NonUtf8PathsSkipped(usize),
     SearchCompleted,
     ReplacementCompleted(ReplaceState),
     UpdateReplacements {
@@ -107,8 +145,14 @@ pub enum BackgroundProcessingEvent {
 
 #[derive(Debug)]
 pub enum AppEvent {
-    PerformSearch { generation: u64 },
-    DismissToast { generation: u64 },
+    PerformSearch {
+        generation: u64,
+    },
+    DismissToast {
+        generation: u64,
+    },
+    /// The files that would be searched have been listed, to be shown on the file picker screen
+    FilesListed(Vec<PathBuf>),
 }
 
 #[derive(Debug)]
@@ -127,6 +171,9 @@ pub struct ExitAndReplaceState {
 #[derive(Debug)]
 pub enum Event {
     LaunchEditor((PathBuf, usize)),
+    /// Open the config file in the editor
+    EditConfig,
+    CopyToClipboard(String),
     ExitAndReplace(ExitAndReplaceState),
     Rerender,
     Internal(InternalEvent),
@@ -195,6 +242,31 @@ impl SearchPhase {
     }
 }
 
+/// Identifies a search result across searches by its path and line number
+type ResultKey = (Option<PathBuf>, usize);
+
+/// State of the results from before a search was re-run, applied to matching results as they come in
+#[derive(Debug)]
+struct ResultsToRestore {
+    /// Whether each result was included
+    inclusions: HashMap<ResultKey, bool>,
+    /// The result that was selected, if it hasn't yet been found again
+    selected: Option<ResultKey>,
+}
+
+/// Results held back from `SearchState::results` because their file already has `display.max_matches_per_file`
+/// results shown, so that a single file with many matches doesn't dominate the list
+#[derive(Debug)]
+struct HeldBackResults {
+    max_per_file: usize,
+    /// Number of results shown from each file
+    num_shown: HashMap<Option<PathBuf>, usize>,
+    /// The results held back from each file, in the order they were found
+    held_back: HashMap<Option<PathBuf>, Vec<SearchResult>>,
+    /// Files that have been expanded, whose results are all shown
+    expanded: HashSet<Option<PathBuf>>,
+}
+
 #[derive(Debug)]
 pub struct SearchState {
     pub results: Vec<SearchResultWithReplacement>,
@@ -210,6 +282,18 @@ pub struct SearchState {
     pub last_render: Instant,
     pub phase: SearchPhase,
     pub cancelled: Arc<AtomicBool>,
+    /// Number of files searched so far. Only updated when searching files, not stdin.
+    pub files_scanned: Option<usize>,
+    /// State of the results from before a refresh. Boxed to keep the `Screen` enum compact.
+    results_to_restore: Option<Box<ResultsToRestore>>,
+    /// Number of results dropped because their replacement was identical to the original text.
+    /// Only non-zero when `AppRunConfig::skip_noop` is set
+    pub num_noops_skipped: usize,
+    /// How many lines the preview has been scrolled from the selected result, along with the position
+    /// of that result, so that the preview is centred again once another result is selected
+    preview_scroll: (usize, isize),
+    /// Only set if `display.max_matches_per_file` is set. Boxed to keep the `Screen` enum compact.
+    held_back_results: Option<Box<HeldBackResults>>,
 }
 
 impl SearchState {
@@ -230,7 +314,83 @@ impl SearchState {
                 started: Instant::now(),
             },
             cancelled,
+            files_scanned: None,
+            results_to_restore: None,
+            num_noops_skipped: 0,
+            preview_scroll: (0, 0),
+            held_back_results: None,
+        }
+    }
+
+    /// Shows at most `max` results from each file, holding back the rest until the file is expanded
+    fn set_max_matches_per_file(&mut self, max: usize) {
+        self.held_back_results = Some(Box::new(HeldBackResults {
+            max_per_file: max,
+            num_shown: HashMap::new(),
+            held_back: HashMap::new(),
+            expanded: HashSet::new(),
+        }));
+    }
+
+    /// Returns `result` if it should be shown, otherwise holds it back because its file already has
+    /// the maximum number of results shown
+    fn hold_back_if_over_max(&mut self, result: SearchResult) -> Option<SearchResult> {
+        let Some(held_back) = &mut self.held_back_results else {
+            return Some(result);
+        };
+        if held_back.expanded.contains(&result.path) {
+            return Some(result);
+        }
+        let num_shown = held_back.num_shown.entry(result.path.clone()).or_default();
+        if *num_shown < held_back.max_per_file {
+            *num_shown += 1;
+            return Some(result);
         }
+        held_back
+            .held_back
+            .entry(result.path.clone())
+            .or_default()
+            .push(result);
+        None
+    }
+
+    /// The number of results from the file at `path` that are held back by `display.max_matches_per_file`
+    pub fn num_held_back(&self, path: &Option<PathBuf>) -> usize {
+        self.held_back_results
+            .as_ref()
+            .and_then(|held_back| held_back.held_back.get(path))
+            .map_or(0, Vec::len)
+    }
+
+    /// Removes and returns the results held back from the file of the selected result, so that they can
+    /// be shown, and shows any further results found in that file
+    fn take_held_back_for_selected_file(&mut self) -> Option<(Option<PathBuf>, Vec<SearchResult>)> {
+        let path = self
+            .results
+            .get(self.primary_selected_pos())?
+            .search_result
+            .path
+            .clone();
+        let held_back = self.held_back_results.as_mut()?;
+        held_back.expanded.insert(path.clone());
+        let results = held_back.held_back.remove(&path)?;
+        Some((path, results))
+    }
+
+    /// Inserts `results` after the last result shown from the file at `path`
+    fn insert_after_file(
+        &mut self,
+        path: Option<&PathBuf>,
+        results: Vec<SearchResultWithReplacement>,
+    ) {
+        let idx = self
+            .results
+            .iter()
+            .rposition(|res| res.search_result.path.as_ref() == path)
+            .map_or(self.results.len(), |idx| idx + 1);
+        self.results.splice(idx..idx, results);
+        // Results after the insertion point have moved, so keep only the primary selection
+        self.selected = Selected::Single(self.primary_selected_pos());
     }
 
     fn move_selected_up_by(&mut self, n: usize) {
@@ -304,6 +464,25 @@ impl SearchState {
         });
     }
 
+    /// Selects the result at `idx`, and also toggles whether it is included if `toggle_inclusion` is set
+    fn click_result(&mut self, idx: usize, toggle_inclusion: bool) {
+        let Some(result) = self.results.get_mut(idx) else {
+            return;
+        };
+        if toggle_inclusion {
+            result.search_result.included = !result.search_result.included;
+        }
+        self.selected = Selected::Single(idx);
+    }
+
+    /// Moves the primary selection by `delta` results, stopping at either end of the list rather than
+    /// wrapping around
+    fn scroll_selected_by(&mut self, delta: isize) {
+        let end = self.results.len().saturating_sub(1);
+        let pos = self.primary_selected_pos().saturating_add_signed(delta);
+        self.move_primary_sel(pos.min(end));
+    }
+
     fn toggle_all_selected(&mut self) {
         let all_included = self.results.iter().all(|res| res.search_result.included);
         self.results
@@ -311,6 +490,78 @@ impl SearchState {
             .for_each(|res| res.search_result.included = !all_included);
     }
 
+    fn toggle_file_inclusion(&mut self) {
+        let Some(selected) = self.results.get(self.primary_selected_pos()) else {
+            return;
+        };
+        let path = selected.search_result.path.clone();
+        let all_included = self
+            .results
+            .iter()
+            .filter(|res| res.search_result.path == path)
+            .all(|res| res.search_result.included);
+        self.results
+            .iter_mut()
+            .filter(|res| res.search_result.path == path)
+            .for_each(|res| res.search_result.included = !all_included);
+    }
+
+    /// Includes only the `occurrence`th result, counting from 1 and ordering results by path and then line
+    /// number, and moves the selection to it. If there are fewer results than `occurrence`, every result is
+    /// excluded and `false` is returned
+    fn include_only_occurrence(&mut self, occurrence: usize) -> bool {
+        let mut order = (0..self.results.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (
+                &self.results[a].search_result,
+                &self.results[b].search_result,
+            );
+            (&a.path, a.start_line_number()).cmp(&(&b.path, b.start_line_number()))
+        });
+        let target = occurrence
+            .checked_sub(1)
+            .and_then(|pos| order.get(pos).copied());
+        for (idx, res) in self.results.iter_mut().enumerate() {
+            res.search_result.included = Some(idx) == target;
+        }
+        if let Some(idx) = target {
+            self.move_primary_sel(idx);
+        }
+        target.is_some()
+    }
+
+    /// Moves the primary selection to the next result (or previous, if `forward` is false) whose path relative
+    /// to `roots`, or whose line, contains `term`, wrapping around at either end of the results.
+    /// Returns `false` if no result contains `term`.
+    fn select_find_match(&mut self, roots: &[PathBuf], term: &str, forward: bool) -> bool {
+        let len = self.results.len();
+        let start = self.primary_selected_pos();
+        let found = (1..=len)
+            .map(|offset| {
+                if forward {
+                    (start + offset) % len
+                } else {
+                    (start + len - offset % len) % len
+                }
+            })
+            .find(|&idx| result_contains_term(&self.results[idx], roots, term));
+        match found {
+            Some(idx) => {
+                self.move_primary_sel(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Selects the results from `start` to `end` inclusive, with the selection anchored at `start`
+    fn select_range(&mut self, start: usize, end: usize) {
+        self.selected = Selected::Multi(MultiSelected {
+            anchor: start,
+            primary: end,
+        });
+    }
+
     // TODO: add tests
     fn selected_range(&self) -> (usize, usize) {
         match &self.selected {
@@ -344,6 +595,35 @@ impl SearchState {
         }
     }
 
+    /// How many lines the preview has been scrolled from the selected result, where negative values
+    /// scroll towards the start of the file
+    pub fn preview_scroll_offset(&self) -> isize {
+        let (pos, offset) = self.preview_scroll;
+        if pos == self.primary_selected_pos() {
+            offset
+        } else {
+            0
+        }
+    }
+
+    /// Sets how far the preview has been scrolled from the selected result. Used by the UI to keep the
+    /// preview within the file
+    pub fn set_preview_scroll_offset(&mut self, offset: isize) {
+        self.preview_scroll = (self.primary_selected_pos(), offset);
+    }
+
+    fn scroll_preview_by(&mut self, delta: isize) {
+        self.set_preview_scroll_offset(self.preview_scroll_offset().saturating_add(delta));
+    }
+
+    /// The number of results that are included in the replacement
+    pub fn num_included(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|res| res.search_result.included)
+            .count()
+    }
+
     pub fn primary_selected_pos(&self) -> usize {
         match self.selected {
             Selected::Single(sel) => sel,
@@ -471,7 +751,8 @@ pub struct DirSearchKey {
     exclude_globs: String,
     include_hidden: bool,
     include_git_folders: bool,
-    directory: PathBuf,
+    include_binary: bool,
+    directories: Vec<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -479,7 +760,8 @@ pub struct SearchFieldsState {
     pub focussed_section: FocussedSection,
     pub search_state: Option<SearchState>, // Becomes Some when search begins
     pub search_debounce_timer: Option<JoinHandle<()>>,
-    pub preview_update_state: Option<PreviewUpdateStatus>,
+    /// Boxed to keep the `Screen` enum compact.
+    pub preview_update_state: Option<Box<PreviewUpdateStatus>>,
     /// Key of the most recently scheduled/run search. Cleared whenever
     /// `search_state` is cleared (e.g. on empty text) so that re-typing the
     /// same query runs the search again. Boxed to keep the `Screen` enum
@@ -552,6 +834,7 @@ impl SearchFieldsState {
 
 #[derive(Debug)]
 pub enum Screen {
+    FilePicker(FilePickerState),
     SearchFields(SearchFieldsState),
     PerformingReplacement(PerformingReplacementState),
     Results(ReplaceState),
@@ -561,6 +844,7 @@ impl Screen {
     fn name(&self) -> &str {
         // TODO: is there a better way of doing this?
         match &self {
+            Screen::FilePicker(_) => "FilePicker",
             Screen::SearchFields(_) => "SearchFields",
             Screen::PerformingReplacement(_) => "PerformingReplacement",
             Screen::Results(_) => "Results",
@@ -581,6 +865,98 @@ pub enum Popup {
     Error,
     Help,
     Text { title: String, body: String },
+    SavePreset(TextField),
+    FindInResults(TextField),
+    SelectRange(TextField),
+    CommandPalette(CommandPaletteState),
+}
+
+/// Parses a range of results entered in the select-range prompt, such as `50,80`, into zero-based
+/// indices. Results are numbered from 1 as in the results list, and a single number selects just that result
+fn parse_result_range(input: &str, num_results: usize) -> Result<(usize, usize), String> {
+    let input = input.trim();
+    let range = input.strip_prefix(':').unwrap_or(input);
+    let (start, end) = range.split_once(',').unwrap_or((range, range));
+    let parse = |num: &str| {
+        num.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid range \"{input}\", expected e.g. 50,80"))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start == 0 || end == 0 || start.max(end) > num_results {
+        return Err(format!("Range must be between 1 and {num_results}"));
+    }
+    Ok((start - 1, end - 1))
+}
+
+/// Lists the distinct matched texts along with how many times each occurs, given counts ordered by frequency
+fn match_summary(counts: &[(String, usize)], search_complete: bool) -> String {
+    let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+    let mut summary = format!(
+        "{} distinct match{} across {total} occurrence{}",
+        counts.len(),
+        if counts.len() == 1 { "" } else { "es" },
+        if total == 1 { "" } else { "s" },
+    );
+    let count_width = counts
+        .first()
+        .map_or(0, |(_, count)| count.to_string().len());
+    for (idx, (text, count)) in counts.iter().enumerate() {
+        if idx == 0 {
+            summary.push('\n');
+        }
+        if idx == MAX_MATCH_SUMMARY_ENTRIES {
+            write!(summary, "\n... and {} more", counts.len() - idx).unwrap();
+            break;
+        }
+        let text = text
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+            .replace('\t', "\\t");
+        write!(summary, "\n{count:>count_width$}  {text}").unwrap();
+    }
+    if !search_complete {
+        summary.push_str("\n\nThe search is still running, so these counts may be incomplete.");
+    }
+    summary
+}
+
+/// Formats `results` as `path:line:text` lines, as output by `grep -n`, with a line for each line of
+/// a multiline match. Results from stdin that don't refer to a file are formatted as `line:text`
+fn grep_output(results: &[&SearchResultWithReplacement], roots: &[PathBuf]) -> String {
+    let mut output = String::new();
+    for res in results {
+        let prefix = match &res.search_result.path {
+            Some(path) => format!("{}:", utils::relative_path_in_roots(roots, path)),
+            None => String::new(),
+        };
+        match &res.search_result.content {
+            MatchContent::Line {
+                line_number,
+                content,
+                ..
+            } => writeln!(output, "{prefix}{line_number}:{content}").unwrap(),
+            MatchContent::ByteRange { lines, .. } => {
+                for (line_number, line) in lines {
+                    writeln!(output, "{prefix}{line_number}:{}", line.content).unwrap();
+                }
+            }
+        }
+    }
+    output
+}
+
+fn result_contains_term(
+    result: &SearchResultWithReplacement,
+    roots: &[PathBuf],
+    term: &str,
+) -> bool {
+    let path_matches = result
+        .search_result
+        .path
+        .as_ref()
+        .is_some_and(|path| utils::relative_path_in_roots(roots, path).contains(term));
+    path_matches || result.search_result.content.matched_text().contains(term)
 }
 
 #[derive(Debug, Clone)]
@@ -594,6 +970,7 @@ struct Toast {
 pub struct AppRunConfig {
     pub include_hidden: bool,
     pub include_git_folders: bool,
+    pub include_binary: bool,
     pub advanced_regex: bool,
     pub multiline: bool,
     pub immediate_search: bool,
@@ -601,6 +978,42 @@ pub struct AppRunConfig {
     pub print_results: bool,
     pub print_on_exit: bool,
     pub interpret_escape_sequences: bool,
+    pub line_filter: LineFilter,
+    pub initial_focus: Option<FieldName>,
+    /// How stdin content is interpreted. Ignored when searching a directory
+    pub stdin_format: StdinFormat,
+    /// List the files that would be searched and let the user approve them before searching.
+    /// Ignored when processing stdin
+    pub interactive_filter: bool,
+    /// Drop results whose replacement would leave the text unchanged, e.g. when replacing with a
+    /// back-reference to the matched text
+    pub skip_noop: bool,
+    /// How many levels of directories to descend into, where 1 searches only the files directly
+    /// within each directory. Ignored when processing stdin
+    pub max_depth: Option<usize>,
+    /// If set, only files modified at or after this time are searched. Ignored when processing stdin
+    pub modified_since: Option<SystemTime>,
+    /// Only search files that are tracked by git. Ignored when processing stdin
+    pub git_tracked_only: bool,
+    /// If set, once the search completes only the result at this position (counting from 1, with results
+    /// ordered by path and then line number) is included in the replacement
+    pub occurrence: Option<usize>,
+    /// Exclude results from the replacement as they are found, rather than including them, so that they
+    /// can be included individually
+    pub select_none: bool,
+    /// If set, comma-separated globs, with the same semantics as the include files field, that a result's file
+    /// must match for the result to be included in the replacement when it is found. Results from other files
+    /// are still shown, but are excluded. Ignored when processing stdin
+    pub auto_include: Option<String>,
+    /// Treat the replacement text as a template in which `{}` is replaced with the whole match, so
+    /// that each match can be wrapped with a prefix and suffix
+    pub wrap_matches: bool,
+    /// Remove lines that are left empty by the replacement, rather than leaving a blank line. Only
+    /// applies when searching line-by-line
+    pub delete_empty_lines: bool,
+    /// What to do when a file being replaced in is a symbolic link, which can only happen when
+    /// processing framed stdin
+    pub symlinks: SymlinkHandling,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -609,6 +1022,7 @@ impl Default for AppRunConfig {
         Self {
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
             advanced_regex: false,
             multiline: false,
             immediate_search: false,
@@ -616,6 +1030,20 @@ impl Default for AppRunConfig {
             print_results: false,
             print_on_exit: false,
             interpret_escape_sequences: false,
+            line_filter: LineFilter::default(),
+            initial_focus: None,
+            stdin_format: StdinFormat::Plain,
+            interactive_filter: false,
+            skip_noop: false,
+            max_depth: None,
+            modified_since: None,
+            git_tracked_only: false,
+            occurrence: None,
+            select_none: false,
+            auto_include: None,
+            wrap_matches: false,
+            delete_empty_lines: false,
+            symlinks: SymlinkHandling::Follow,
         }
     }
 }
@@ -655,6 +1083,12 @@ pub struct UIState {
     toast: Option<Toast>,
     errors: Vec<AppError>,
     hints: HintState,
+    /// Text to find within the search results, set using the find-in-results prompt. Occurrences are
+    /// highlighted in the results list, and the selection can be moved between results containing it.
+    pub find_term: Option<String>,
+    /// Where the search results list was last rendered, if it was, so that mouse events can be mapped
+    /// to results. Updated by UI, not app
+    pub results_list_area: Option<ScreenArea>,
 }
 
 impl UIState {
@@ -665,6 +1099,8 @@ impl UIState {
             toast: None,
             errors: Vec::new(),
             hints: HintState::default(),
+            find_term: None,
+            results_list_area: None,
         }
     }
 
@@ -691,6 +1127,15 @@ pub struct App {
     pub event_channels: EventChannels,
     pub ui_state: UIState,
     file_content_provider: Arc<dyn FileContentProvider>,
+    /// The files approved on the file picker screen, if shown. Only these files are searched
+    approved_files: Option<Arc<HashSet<PathBuf>>>,
+    /// Pauses the search that is currently running. Replaced whenever a new search starts
+    pub search_pause: Arc<SearchPause>,
+    /// The globs from `AppRunConfig::auto_include`, built for each directory searched
+    auto_include: Option<Vec<Override>>,
+    /// The files tracked by git in the searched directories, when only searching those. Listed once
+    /// up front, as listing them means running git
+    tracked_files: Option<Arc<HashSet<PathBuf>>>,
 }
 
 impl std::fmt::Debug for App {
@@ -704,6 +1149,10 @@ impl std::fmt::Debug for App {
             .field("run_config", &self.run_config)
             .field("event_channels", &self.event_channels)
             .field("ui_state", &self.ui_state)
+            .field("approved_files", &self.approved_files)
+            .field("search_pause", &self.search_pause)
+            .field("auto_include", &self.auto_include)
+            .field("tracked_files", &self.tracked_files)
             .finish_non_exhaustive()
     }
 }
@@ -714,6 +1163,7 @@ enum SearchStrategy {
     Text {
         haystack: Arc<String>,
         config: ParsedSearchConfig,
+        format: StdinFormat,
     },
 }
 
@@ -725,27 +1175,76 @@ enum ReplacementCacheKey {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum PreviewOutcome {
-    Replacement(String),
+    /// The replacement text, along with the spans of each match for line-mode results
+    Replacement(String, Vec<Range<usize>>),
     NoMatch,
     Error(String),
 }
 
+/// Whether a result should be included in the replacement when it is found, which is the case unless
+/// `--select-none` was passed or its file doesn't match the `--auto-include` globs
+fn initially_included(
+    run_config: &AppRunConfig,
+    auto_include: Option<&[Override]>,
+    result: &SearchResult,
+) -> bool {
+    if run_config.select_none {
+        return false;
+    }
+    match (auto_include, &result.path) {
+        (Some(overrides), Some(path)) => overrides
+            .iter()
+            .any(|overrides| matches_overrides(overrides, path)),
+        _ => true,
+    }
+}
+
+/// Whether `path`, or any of its parent directories within the overrides' root, matches `overrides`, so that
+/// globs such as `tests/` match the files within matching directories as they do when walking. As when
+/// walking, paths within a directory matched by a negated glob such as `!tests/` never match, and if every
+/// glob is negated then all other paths match
+fn matches_overrides(overrides: &Override, path: &Path) -> bool {
+    let only_negated = overrides.num_whitelists() == 0;
+    let mut whitelisted = false;
+    for (i, ancestor) in path
+        .ancestors()
+        .take_while(|ancestor| {
+            ancestor.starts_with(overrides.path()) && *ancestor != overrides.path()
+        })
+        .enumerate()
+    {
+        let is_dir = i > 0;
+        match overrides.matched(ancestor, is_dir) {
+            // Files that don't match any glob are also ignored when some globs aren't negated, so
+            // the file itself is only ruled out by a negated glob when there are no others
+            Match::Ignore(_) if is_dir || only_negated => return false,
+            Match::Whitelist(_) => whitelisted = true,
+            Match::Ignore(_) | Match::None => {}
+        }
+    }
+    whitelisted || only_negated
+}
+
 fn result_with_outcome(
     search_result: SearchResult,
     outcome: PreviewOutcome,
 ) -> Option<SearchResultWithReplacement> {
     match outcome {
-        PreviewOutcome::Replacement(replacement) => Some(SearchResultWithReplacement {
-            search_result,
-            replacement,
-            replace_result: None,
-            preview_error: None,
-        }),
+        PreviewOutcome::Replacement(replacement, match_spans) => {
+            Some(SearchResultWithReplacement {
+                search_result,
+                replacement,
+                replace_result: None,
+                preview_error: None,
+                match_spans,
+            })
+        }
         PreviewOutcome::Error(error) => Some(SearchResultWithReplacement {
             search_result,
             replacement: String::new(),
             replace_result: None,
             preview_error: Some(error),
+            match_spans: vec![],
         }),
         PreviewOutcome::NoMatch => None,
     }
@@ -753,14 +1252,16 @@ fn result_with_outcome(
 
 fn apply_outcome(result: &mut SearchResultWithReplacement, outcome: PreviewOutcome) -> bool {
     match outcome {
-        PreviewOutcome::Replacement(replacement) => {
+        PreviewOutcome::Replacement(replacement, match_spans) => {
             result.replacement = replacement;
             result.preview_error = None;
+            result.match_spans = match_spans;
             true
         }
         PreviewOutcome::Error(error) => {
             result.replacement.clear();
             result.preview_error = Some(error);
+            result.match_spans.clear();
             true
         }
         PreviewOutcome::NoMatch => false,
@@ -795,10 +1296,14 @@ impl<'a> ReplacementContext<'a> {
 
     fn replacement_for_search_result(&mut self, res: &SearchResult) -> PreviewOutcome {
         match &res.content {
-            MatchContent::Line { content, .. } => {
-                replace_all_if_match(content, self.searcher.search(), self.searcher.replace())
-                    .map_or(PreviewOutcome::NoMatch, PreviewOutcome::Replacement)
-            }
+            MatchContent::Line { content, .. } => replace_all_if_match_with_spans(
+                content,
+                self.searcher.search(),
+                self.searcher.replace(),
+            )
+            .map_or(PreviewOutcome::NoMatch, |(replacement, match_spans)| {
+                PreviewOutcome::Replacement(replacement, match_spans)
+            }),
             MatchContent::ByteRange {
                 content,
                 byte_start,
@@ -815,11 +1320,14 @@ impl<'a> ReplacementContext<'a> {
                 }
 
                 if contains_search(content, self.searcher.search()) {
-                    return PreviewOutcome::Replacement(replacement_for_match(
-                        content,
-                        self.searcher.search(),
-                        self.searcher.replace(),
-                    ));
+                    return PreviewOutcome::Replacement(
+                        replacement_for_match(
+                            content,
+                            self.searcher.search(),
+                            self.searcher.replace(),
+                        ),
+                        vec![],
+                    );
                 }
 
                 PreviewOutcome::NoMatch
@@ -851,7 +1359,7 @@ impl<'a> ReplacementContext<'a> {
         if let Some(map) = self.replacement_map_for_result(res, haystack.as_str())
             && let Some(replacement) = map.get(&(byte_start, byte_end))
         {
-            return PreviewOutcome::Replacement(replacement.clone());
+            return PreviewOutcome::Replacement(replacement.clone(), vec![]);
         }
 
         // NOTE: advanced regex lookarounds require the full haystack. If we run the
@@ -865,7 +1373,7 @@ impl<'a> ReplacementContext<'a> {
             byte_start,
             byte_end,
         ) {
-            return PreviewOutcome::Replacement(replacement);
+            return PreviewOutcome::Replacement(replacement, vec![]);
         }
 
         PreviewOutcome::NoMatch
@@ -946,6 +1454,24 @@ fn build_replacement_map(
     map
 }
 
+/// Formats the keys bound to a command, e.g. `<j> / <down>`. Only the first key is shown when
+/// `compact` is set, to keep the keymap bar at the bottom of the screen short.
+fn display_keys(keys: &Keys, compact: bool) -> String {
+    let shown = if compact {
+        keys.get(..1).unwrap_or_default()
+    } else {
+        keys
+    };
+    if shown.is_empty() {
+        return "<n/a>".to_string();
+    }
+    shown
+        .iter()
+        .map(|key| format!("<{key}>"))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
 fn generate_escape_deprecation_message(quit_keymap: Option<KeyEvent>) -> String {
     let quit_keymap_str = quit_keymap.map_or("".to_string(), |keymap| {
         let optional_help = if let KeyEvent {
@@ -978,7 +1504,7 @@ macro_rules! get_bg_receiver {
                 processing_receiver,
                 ..
             }) => Some(processing_receiver),
-            Screen::Results(_) => None,
+            Screen::FilePicker(_) | Screen::Results(_) => None,
         }
     };
 }
@@ -999,22 +1525,47 @@ impl<'a> App {
         input_source: InputSource,
         search_field_values: &SearchFieldValues<'a>,
         app_run_config: AppRunConfig,
-        config: Config,
+        mut config: Config,
     ) -> anyhow::Result<Self> {
-        let search_fields = SearchFields::with_values(
+        let mut search_fields = SearchFields::with_values(
             search_field_values,
             config.search.disable_prepopulated_fields,
         );
+        search_fields.reorder_fields(&config.search.field_order);
+        search_fields.hide_fields(
+            &config.search.hidden_fields,
+            config.search.disable_prepopulated_fields,
+        );
+        if let Some(name) = &app_run_config.initial_focus {
+            search_fields.focus(name, config.search.disable_prepopulated_fields);
+        }
 
         let mut search_fields_state = SearchFieldsState::default();
         if app_run_config.immediate_search {
             search_fields_state.focussed_section = FocussedSection::SearchResults;
         }
 
-        let key_map = KeyMap::from_config(&config.keys).map_err(display_conflict_errors)?;
+        let key_map = KeyMap::from_config(&mut config.keys).map_err(display_conflict_errors)?;
+
+        let search_immediately = app_run_config.immediate_search
+            || !search_field_values.search.value.is_empty()
+            || (matches!(input_source, InputSource::Stdin(_))
+                && app_run_config.stdin_format == StdinFormat::RgJson);
 
-        let search_immediately =
-            app_run_config.immediate_search || !search_field_values.search.value.is_empty();
+        let auto_include = match (&app_run_config.auto_include, &input_source) {
+            (Some(globs), InputSource::Directory(directories)) => {
+                Some(utils::build_overrides(directories, globs)?)
+            }
+            _ => None,
+        };
+        let tracked_files = match &input_source {
+            InputSource::Directory(directories) if app_run_config.git_tracked_only => Some(Arc::new(
+                git::tracked_files_in(directories).context(
+                    "Only searching files tracked by git requires each searched directory to be within a git repository",
+                )?,
+            )),
+            _ => None,
+        };
 
         let mut app = Self {
             config,
@@ -1026,15 +1577,81 @@ impl<'a> App {
             event_channels: EventChannels::new(),
             ui_state: UIState::new(Screen::SearchFields(search_fields_state)),
             file_content_provider: default_file_content_provider(),
+            approved_files: None,
+            search_pause: Arc::default(),
+            auto_include,
+            tracked_files,
         };
 
-        if search_immediately {
+        if !app.key_map.text_input_shadows.is_empty() {
+            let message = display_text_input_shadows(&app.key_map.text_input_shadows);
+            warn!("{message}");
+            app.set_popup(Popup::Text {
+                title: "Key mapping warning".to_string(),
+                body: message,
+            });
+        }
+
+        if app.run_config.interactive_filter
+            && matches!(app.input_source, InputSource::Directory(_))
+        {
+            app.open_file_picker();
+        } else if search_immediately {
             app.perform_search_background();
         }
 
         Ok(app)
     }
 
+    /// Shows the file picker screen, listing the files that would be searched in the background. If
+    /// the globs are invalid then the search fields are shown instead, with the errors
+    fn open_file_picker(&mut self) {
+        let Some(dir_config) = self.dir_config() else {
+            return;
+        };
+        let mut error_handler = AppErrorHandler::new();
+        let result = validate_dir_configuration(dir_config, &mut error_handler);
+        error_handler.apply_to_app(self);
+        let dir_config = match result {
+            Ok(ValidationResult::Success(dir_config)) => dir_config,
+            Ok(ValidationResult::ValidationErrors) => return,
+            Err(e) => {
+                warn!("Couldn't list files to search: {e}");
+                return;
+            }
+        };
+
+        self.ui_state.current_screen = Screen::FilePicker(FilePickerState::default());
+        let event_sender = self.event_channels.sender.clone();
+        task::spawn_blocking(move || {
+            let files = dir_config.list_files();
+            // Ignore error - the app may have been reset while listing
+            let _ = event_sender.send(Event::Internal(InternalEvent::App(AppEvent::FilesListed(
+                files,
+            ))));
+        });
+    }
+
+    /// Searches only the files approved on the file picker screen. Ignored while the files are
+    /// still being listed
+    fn confirm_file_picker(&mut self) -> EventHandlingResult {
+        let Screen::FilePicker(state) = &self.ui_state.current_screen else {
+            panic!(
+                "Expected FilePicker screen, found {}",
+                self.ui_state.current_screen.name()
+            );
+        };
+        let Some(approved_files) = state.approved_files() else {
+            return EventHandlingResult::None;
+        };
+        self.approved_files = Some(Arc::new(approved_files));
+        self.ui_state.current_screen = Screen::SearchFields(SearchFieldsState::default());
+        if !self.search_is_empty() {
+            self.perform_search_background();
+        }
+        EventHandlingResult::Rerender
+    }
+
     pub fn set_file_content_provider(&mut self, provider: Arc<dyn FileContentProvider>) {
         self.file_content_provider = provider;
     }
@@ -1081,6 +1698,13 @@ impl<'a> App {
                 self.dismiss_toast_if_generation_matches(generation);
                 EventHandlingResult::Rerender
             }
+            AppEvent::FilesListed(files) => {
+                let Screen::FilePicker(state) = &mut self.ui_state.current_screen else {
+                    return EventHandlingResult::None;
+                };
+                state.set_files(files);
+                EventHandlingResult::Rerender
+            }
         }
     }
 
@@ -1092,6 +1716,8 @@ impl<'a> App {
         {
             state.cancel();
         }
+        // A paused search can't see the cancellation until it is resumed
+        self.search_pause.resume();
     }
 
     fn cancel_replacement(&mut self) {
@@ -1158,7 +1784,7 @@ impl<'a> App {
 
         if !self.errors().is_empty() {
             self.set_popup(Popup::Error);
-        } else if self.search_fields.search().text().is_empty() {
+        } else if self.search_is_empty() {
             self.add_error(AppError {
                 name: "Search field must not be empty".to_string(),
                 long: "Please enter some search text".to_string(),
@@ -1214,7 +1840,7 @@ impl<'a> App {
             return;
         }
 
-        if self.search_fields.search().text().is_empty() {
+        if self.search_is_empty() {
             self.clear_search_state_and_key();
             return;
         }
@@ -1238,6 +1864,7 @@ impl<'a> App {
         self.cancel_search();
         self.file_content_provider.clear();
         let key = self.current_search_key();
+        let search_is_empty = self.search_is_empty();
         let Screen::SearchFields(ref mut search_fields_state) = self.ui_state.current_screen else {
             log::warn!(
                 "Called perform_search_unwrap on screen {}",
@@ -1250,7 +1877,7 @@ impl<'a> App {
         // Empty searches are short-circuited upstream (`enter_chars_into_field`
         // clears state and returns early); any remaining path that reaches
         // here with empty text should produce no search and no state.
-        if self.search_fields.search().text().is_empty() {
+        if search_is_empty {
             search_fields_state.search_state = None;
             search_fields_state.last_scheduled_key = None;
             return;
@@ -1259,11 +1886,15 @@ impl<'a> App {
         let (background_processing_sender, background_processing_receiver) =
             mpsc::unbounded_channel();
         let cancelled = Arc::new(AtomicBool::new(false));
-        let search_state = SearchState::new(
+        let mut search_state = SearchState::new(
             background_processing_sender.clone(),
             background_processing_receiver,
             Arc::clone(&cancelled),
         );
+        if let Some(max) = self.config.display.max_matches_per_file {
+            search_state.set_max_matches_per_file(max);
+        }
+        self.search_pause = Arc::default();
 
         let strategy = match &self.searcher {
             Some(Searcher::FileSearcher(file_searcher)) => {
@@ -1276,6 +1907,7 @@ impl<'a> App {
                 SearchStrategy::Text {
                     haystack: Arc::clone(stdin),
                     config: search_config.clone(),
+                    format: self.run_config.stdin_format,
                 }
             }
             None => {
@@ -1288,12 +1920,53 @@ impl<'a> App {
             background_processing_sender,
             self.event_channels.sender.clone(),
             cancelled,
+            Arc::clone(&self.search_pause),
+            self.config.ui.render_interval(),
         );
 
         search_fields_state.search_state = Some(search_state);
         search_fields_state.last_scheduled_key = Some(Box::new(key));
     }
 
+    /// Re-runs the current search, e.g. to pick up changes made to files in an editor, carrying
+    /// over whether each result was included to any result at the same path and line
+    fn refresh_search(&mut self) {
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(search_state),
+            ..
+        }) = &self.ui_state.current_screen
+        else {
+            return;
+        };
+        let result_key = |res: &SearchResultWithReplacement| {
+            (
+                res.search_result.path.clone(),
+                res.search_result.start_line_number(),
+            )
+        };
+        let to_restore = ResultsToRestore {
+            inclusions: search_state
+                .results
+                .iter()
+                .map(|res| (result_key(res), res.search_result.included))
+                .collect(),
+            selected: search_state
+                .results
+                .get(search_state.primary_selected_pos())
+                .map(result_key),
+        };
+
+        self.perform_search_already_validated();
+
+        if let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(search_state),
+            ..
+        }) = &mut self.ui_state.current_screen
+        {
+            search_state.results_to_restore = Some(Box::new(to_restore));
+        }
+    }
+
     #[allow(clippy::needless_pass_by_value)]
     fn update_all_replacements(&mut self, cancelled: Arc<AtomicBool>) -> EventHandlingResult {
         if cancelled.load(Ordering::Relaxed) {
@@ -1407,6 +2080,24 @@ impl<'a> App {
                             self.event_channels.sender.clone(),
                             Some(file_searcher),
                             self.file_content_provider.clone(),
+                            self.run_config.delete_empty_lines,
+                            self.run_config.symlinks,
+                            self.config.ui.render_interval(),
+                        );
+                    }
+                    // Results from framed stdin refer to lines in files, so are replaced in place
+                    Searcher::TextSearcher { .. } if self.run_config.stdin_format.is_framed() => {
+                        replace::perform_replacement(
+                            state.results,
+                            background_processing_sender.clone(),
+                            cancelled.clone(),
+                            replacements_completed.clone(),
+                            self.event_channels.sender.clone(),
+                            None,
+                            self.file_content_provider.clone(),
+                            self.run_config.delete_empty_lines,
+                            self.run_config.symlinks,
+                            self.config.ui.render_interval(),
                         );
                     }
                     Searcher::TextSearcher { search_config } => {
@@ -1462,35 +2153,99 @@ impl<'a> App {
         true
     }
 
+    fn show_non_utf8_lines_toast(&mut self, files: &[(PathBuf, usize)]) {
+        let roots = match &self.input_source {
+            InputSource::Directory(directories) => directories.as_slice(),
+            InputSource::Stdin(_) => &[],
+        };
+        let files = files
+            .iter()
+            .map(|(path, num_lines)| (utils::relative_path_in_roots(roots, path), *num_lines))
+            .collect::<Vec<_>>();
+        // Listing the files takes longer to read than the other toasts
+        self.show_toast(
+            search::non_utf8_lines_message(&files),
+            Duration::from_secs(5),
+        );
+    }
+
     pub fn handle_background_processing_event(
         &mut self,
         event: BackgroundProcessingEvent,
     ) -> EventHandlingResult {
         match event {
+            // Only of interest to hosts embedding the app
+            BackgroundProcessingEvent::SearchStarted => EventHandlingResult::None,
             BackgroundProcessingEvent::AddSearchResult(result) => {
                 self.add_search_results(iter::once(result))
             }
             BackgroundProcessingEvent::AddSearchResults(results) => {
                 self.add_search_results(results)
             }
-            BackgroundProcessingEvent::SearchCompleted => {
+            BackgroundProcessingEvent::SearchProgress { files_scanned } => {
                 if let Screen::SearchFields(SearchFieldsState {
                     search_state: Some(state),
-                    focussed_section,
                     ..
                 }) = &mut self.ui_state.current_screen
+                    && !state.cancelled.load(Ordering::Relaxed)
                 {
-                    state.set_complete_now();
-                    if state.phase.is_complete()
-                        && self.run_config.immediate_replace
-                        && *focussed_section == FocussedSection::SearchResults
-                    {
-                        self.perform_replacement();
-                    }
+                    state.files_scanned = Some(files_scanned);
                 }
+                EventHandlingResult::None
+            }
+            BackgroundProcessingEvent::BinaryFilesIncluded(num_files) => {
+                self.show_toast(
+                    format!(
+                        "Searched {num_files} binary file{}",
+                        if num_files == 1 { "" } else { "s" }
+                    ),
+                    Duration::from_secs(3),
+                );
+                EventHandlingResult::Rerender
+            }
+            BackgroundProcessingEvent::DirsBeyondMaxDepth {
+                num_dirs,
+                max_depth,
+            } => {
+                self.show_toast(
+                    format!(
+                        "Skipped {num_dirs} director{} beyond max depth of {max_depth}",
+                        if num_dirs == 1 { "y" } else { "ies" }
+                    ),
+                    Duration::from_secs(3),
+                );
+                EventHandlingResult::Rerender
+            }
+            BackgroundProcessingEvent::NonUtf8LinesSkipped(files) => {
+                self.show_non_utf8_lines_toast(&files);
+                EventHandlingResult::Rerender
+            }
+            BackgroundProcessingEvent::NonUtf8PathsSkipped(num_matches) => {
+                self.show_toast(
+                    stdin_format::non_utf8_paths_message(num_matches),
+                    Duration::from_secs(3),
+                );
+                EventHandlingResult::Rerender
+            }
+            BackgroundProcessingEvent::RegexTooSlow(num_files) => {
+                self.show_toast(
+                    format!(
+                        "Regex too slow: skipped {num_files} file{}",
+                        if num_files == 1 { "" } else { "s" }
+                    ),
+                    Duration::from_secs(3),
+                );
                 EventHandlingResult::Rerender
             }
-            BackgroundProcessingEvent::ReplacementCompleted(replace_state) => {
+            BackgroundProcessingEvent::SearchCompleted => self.handle_search_completed(),
+            BackgroundProcessingEvent::ReplacementCompleted(mut replace_state) => {
+                if let Screen::PerformingReplacement(PerformingReplacementState {
+                    previous_counts: Some((num_successes, num_ignored)),
+                    ..
+                }) = self.ui_state.current_screen
+                {
+                    replace_state.add_previous_counts(num_successes, num_ignored);
+                }
                 if self.run_config.print_results {
                     EventHandlingResult::new_exit_stats(replace_state)
                 } else {
@@ -1509,54 +2264,222 @@ impl<'a> App {
         }
     }
 
-    fn add_search_results<I>(&mut self, results: I) -> EventHandlingResult
-    where
-        I: IntoIterator<Item = SearchResult>,
-    {
-        // Skip stale batches. When the user edits the search, we flip the
-        // current state's cancelled flag — any batches the superseded task
-        // had already queued must not be appended, and must not be run
-        // through the new searcher (which would match against unrelated
-        // positions and emit bogus previews).
+    fn handle_search_completed(&mut self) -> EventHandlingResult {
+        let mut missing_occurrence = None;
         if let Screen::SearchFields(SearchFieldsState {
             search_state: Some(state),
-            ..
-        }) = &self.ui_state.current_screen
-            && state.cancelled.load(Ordering::Relaxed)
-        {
-            return EventHandlingResult::None;
-        }
-
-        let mut rerender = false;
-        let searcher = self
-            .searcher
-            .as_ref()
-            .expect("searcher should not be None when adding search results");
-        let mut context = Self::replacement_context(
-            &self.input_source,
-            searcher,
-            Arc::clone(&self.file_content_provider),
-        );
-        if let Screen::SearchFields(SearchFieldsState {
-            search_state: Some(search_in_progress_state),
+            focussed_section,
             ..
         }) = &mut self.ui_state.current_screen
         {
-            let mut results_with_replacements = Vec::new();
-            for res in results {
-                let outcome = context.replacement_for_search_result(&res);
-                if let Some(updated) = result_with_outcome(res, outcome) {
-                    results_with_replacements.push(updated);
-                }
+            state.set_complete_now();
+            if state.phase.is_complete()
+                && let Some(occurrence) = self.run_config.occurrence
+                && !state.include_only_occurrence(occurrence)
+            {
+                missing_occurrence = Some((occurrence, state.results.len()));
             }
-            search_in_progress_state
-                .results
-                .append(&mut results_with_replacements);
-
-            // Slightly random duration so that time taken isn't a round number
-            if search_in_progress_state.last_render.elapsed() >= Duration::from_millis(92) {
-                rerender = true;
-                search_in_progress_state.last_render = Instant::now();
+            if state.phase.is_complete()
+                && self.run_config.immediate_replace
+                && *focussed_section == FocussedSection::SearchResults
+            {
+                self.perform_replacement();
+            }
+        }
+        if let Some((occurrence, num_results)) = missing_occurrence {
+            self.show_toast(
+                format!(
+                    "Found {num_results} result{}, so there is no occurrence {occurrence} to replace",
+                    if num_results == 1 { "" } else { "s" }
+                ),
+                Duration::from_secs(3),
+            );
+        }
+        EventHandlingResult::Rerender
+    }
+
+    /// Copies the matched text of the primary selected result, or its replacement, to the clipboard
+    fn copy_selected_result_text(&mut self, replacement: bool) -> EventHandlingResult {
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(state),
+            ..
+        }) = &self.ui_state.current_screen
+        else {
+            return EventHandlingResult::None;
+        };
+        let Some(selected) = state.results.get(state.primary_selected_pos()) else {
+            return EventHandlingResult::None;
+        };
+        let (text, name) = if replacement {
+            (selected.replacement.clone(), "replacement")
+        } else {
+            (
+                selected.search_result.content.matched_text().to_owned(),
+                "matched text",
+            )
+        };
+        self.event_channels
+            .sender
+            .send(Event::CopyToClipboard(text))
+            .expect("Failed to send event");
+        self.show_toast(
+            format!("Copied {name} to the clipboard"),
+            Duration::from_millis(1500),
+        );
+        EventHandlingResult::Rerender
+    }
+
+    /// Copies the results included in the replacement to the clipboard in the format output by `grep -n`
+    fn copy_results_as_grep(&mut self) -> EventHandlingResult {
+        let roots = match &self.input_source {
+            InputSource::Directory(directories) => directories.clone(),
+            InputSource::Stdin(_) => vec![PathBuf::from(".")],
+        };
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(state),
+            ..
+        }) = &self.ui_state.current_screen
+        else {
+            return EventHandlingResult::None;
+        };
+        let included = state
+            .results
+            .iter()
+            .filter(|res| res.search_result.included)
+            .collect::<Vec<_>>();
+        if included.is_empty() {
+            self.show_toast(
+                "No results are included to copy".to_owned(),
+                Duration::from_secs(2),
+            );
+            return EventHandlingResult::Rerender;
+        }
+        let message = format!(
+            "Copied {} result{} to the clipboard as grep output",
+            included.len(),
+            if included.len() == 1 { "" } else { "s" }
+        );
+        self.event_channels
+            .sender
+            .send(Event::CopyToClipboard(grep_output(&included, &roots)))
+            .expect("Failed to send event");
+        self.show_toast(message, Duration::from_millis(1500));
+        EventHandlingResult::Rerender
+    }
+
+    /// Shows the results held back by `display.max_matches_per_file` from the file of the selected result
+    fn expand_selected_file(&mut self) -> EventHandlingResult {
+        let Some(searcher) = self.searcher.as_ref() else {
+            return EventHandlingResult::None;
+        };
+        let mut context = Self::replacement_context(
+            &self.input_source,
+            searcher,
+            Arc::clone(&self.file_content_provider),
+        );
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(state),
+            ..
+        }) = &mut self.ui_state.current_screen
+        else {
+            return EventHandlingResult::None;
+        };
+        let Some((path, held_back)) = state.take_held_back_for_selected_file() else {
+            return EventHandlingResult::None;
+        };
+
+        let mut results = Vec::with_capacity(held_back.len());
+        for mut res in held_back {
+            if !initially_included(&self.run_config, self.auto_include.as_deref(), &res) {
+                res.included = false;
+            }
+            let outcome = context.replacement_for_search_result(&res);
+            if let Some(updated) = result_with_outcome(res, outcome) {
+                if self.run_config.skip_noop && updated.is_noop() {
+                    state.num_noops_skipped += 1;
+                    continue;
+                }
+                results.push(updated);
+            }
+        }
+        state.insert_after_file(path.as_ref(), results);
+        EventHandlingResult::Rerender
+    }
+
+    fn add_search_results<I>(&mut self, results: I) -> EventHandlingResult
+    where
+        I: IntoIterator<Item = SearchResult>,
+    {
+        // Skip stale batches. When the user edits the search, we flip the
+        // current state's cancelled flag — any batches the superseded task
+        // had already queued must not be appended, and must not be run
+        // through the new searcher (which would match against unrelated
+        // positions and emit bogus previews).
+        if let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(state),
+            ..
+        }) = &self.ui_state.current_screen
+            && state.cancelled.load(Ordering::Relaxed)
+        {
+            return EventHandlingResult::None;
+        }
+
+        let mut rerender = false;
+        let render_interval = self.config.ui.render_interval();
+        let searcher = self
+            .searcher
+            .as_ref()
+            .expect("searcher should not be None when adding search results");
+        let mut context = Self::replacement_context(
+            &self.input_source,
+            searcher,
+            Arc::clone(&self.file_content_provider),
+        );
+        if let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(search_in_progress_state),
+            ..
+        }) = &mut self.ui_state.current_screen
+        {
+            let mut results_with_replacements = Vec::new();
+            for res in results {
+                let Some(mut res) = search_in_progress_state.hold_back_if_over_max(res) else {
+                    continue;
+                };
+                if !initially_included(&self.run_config, self.auto_include.as_deref(), &res) {
+                    res.included = false;
+                }
+                let key = (res.path.clone(), res.start_line_number());
+                let mut was_selected = false;
+                if let Some(to_restore) = &mut search_in_progress_state.results_to_restore {
+                    if let Some(&included) = to_restore.inclusions.get(&key) {
+                        res.included = included;
+                    }
+                    was_selected = to_restore.selected.as_ref() == Some(&key);
+                }
+                let outcome = context.replacement_for_search_result(&res);
+                if let Some(updated) = result_with_outcome(res, outcome) {
+                    if self.run_config.skip_noop && updated.is_noop() {
+                        search_in_progress_state.num_noops_skipped += 1;
+                        continue;
+                    }
+                    if was_selected {
+                        let idx = search_in_progress_state.results.len()
+                            + results_with_replacements.len();
+                        search_in_progress_state.move_primary_sel(idx);
+                        if let Some(to_restore) = &mut search_in_progress_state.results_to_restore {
+                            to_restore.selected = None;
+                        }
+                    }
+                    results_with_replacements.push(updated);
+                }
+            }
+            search_in_progress_state
+                .results
+                .append(&mut results_with_replacements);
+
+            if search_in_progress_state.last_render.elapsed() >= render_interval {
+                rerender = true;
+                search_in_progress_state.last_render = Instant::now();
             }
         }
         if rerender {
@@ -1581,6 +2504,17 @@ impl<'a> App {
                 self.perform_search_foreground();
                 EventHandlingResult::Rerender
             }
+            CommandSearchFocusFields::FocusResults => {
+                let search_fields_state = self
+                    .ui_state
+                    .current_screen
+                    .unwrap_search_fields_state_mut();
+                if search_fields_state.search_state.is_none() {
+                    return EventHandlingResult::None;
+                }
+                search_fields_state.focussed_section = FocussedSection::SearchResults;
+                EventHandlingResult::Rerender
+            }
             CommandSearchFocusFields::FocusPreviousField => {
                 self.search_fields
                     .focus_prev(self.config.search.disable_prepopulated_fields);
@@ -1591,12 +2525,263 @@ impl<'a> App {
                     .focus_next(self.config.search.disable_prepopulated_fields);
                 EventHandlingResult::Rerender
             }
+            CommandSearchFocusFields::SavePreset => {
+                self.set_popup(Popup::SavePreset(TextField::new("")));
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusFields::ClearFields => self.clear_search_fields(),
             CommandSearchFocusFields::EnterChars(key_code, key_modifiers) => {
                 self.enter_chars_into_field(key_code, key_modifiers)
             }
         }
     }
 
+    fn handle_save_preset_popup_key(&mut self, key_event: KeyEvent) -> EventHandlingResult {
+        let Some(Popup::SavePreset(name_field)) = &mut self.ui_state.popup else {
+            return EventHandlingResult::None;
+        };
+        match key_event.code {
+            KeyCode::Esc => self.clear_popup(),
+            KeyCode::Enter => {
+                let name = name_field.text().trim().to_owned();
+                if name.is_empty() {
+                    return EventHandlingResult::None;
+                }
+                self.clear_popup();
+                self.save_preset(&name);
+            }
+            _ => name_field.handle_keys(key_event.code, key_event.modifiers),
+        }
+        EventHandlingResult::Rerender
+    }
+
+    fn handle_find_in_results_popup_key(&mut self, key_event: KeyEvent) -> EventHandlingResult {
+        let Some(Popup::FindInResults(term_field)) = &mut self.ui_state.popup else {
+            return EventHandlingResult::None;
+        };
+        match key_event.code {
+            KeyCode::Esc => self.clear_popup(),
+            KeyCode::Enter => {
+                let term = term_field.text().to_owned();
+                self.clear_popup();
+                if term.is_empty() {
+                    self.ui_state.find_term = None;
+                } else {
+                    self.ui_state.find_term = Some(term);
+                    return self.select_find_match(true);
+                }
+            }
+            _ => term_field.handle_keys(key_event.code, key_event.modifiers),
+        }
+        EventHandlingResult::Rerender
+    }
+
+    fn handle_select_range_popup_key(&mut self, key_event: KeyEvent) -> EventHandlingResult {
+        let Some(Popup::SelectRange(range_field)) = &mut self.ui_state.popup else {
+            return EventHandlingResult::None;
+        };
+        match key_event.code {
+            KeyCode::Esc => self.clear_popup(),
+            KeyCode::Enter => {
+                let input = range_field.text().to_owned();
+                self.clear_popup();
+                let search_state = self.get_search_state_unwrap();
+                match parse_result_range(&input, search_state.results.len()) {
+                    Ok((start, end)) => search_state.select_range(start, end),
+                    Err(error) => self.show_toast(error, Duration::from_secs(3)),
+                }
+            }
+            _ => range_field.handle_keys(key_event.code, key_event.modifiers),
+        }
+        EventHandlingResult::Rerender
+    }
+
+    fn handle_command_palette_popup_key(&mut self, key_event: KeyEvent) -> EventHandlingResult {
+        let Some(Popup::CommandPalette(palette)) = &mut self.ui_state.popup else {
+            return EventHandlingResult::None;
+        };
+        match (key_event.code, key_event.modifiers) {
+            (KeyCode::Esc, _) => self.clear_popup(),
+            (KeyCode::Enter, _) => {
+                let key = palette.selected_entry().map(|entry| entry.key);
+                self.clear_popup();
+                if let Some(key) = key {
+                    // Run the command as if its key had been pressed
+                    return self.handle_key_event(key);
+                }
+            }
+            (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                palette.move_selected_down();
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                palette.move_selected_up();
+            }
+            _ => {
+                palette
+                    .query
+                    .handle_keys(key_event.code, key_event.modifiers);
+                palette.reset_selected();
+            }
+        }
+        EventHandlingResult::Rerender
+    }
+
+    fn select_find_match(&mut self, forward: bool) -> EventHandlingResult {
+        let roots = match &self.input_source {
+            InputSource::Directory(directories) => directories.clone(),
+            InputSource::Stdin(_) => vec![PathBuf::from(".")],
+        };
+        let Some(term) = self.ui_state.find_term.clone() else {
+            return EventHandlingResult::None;
+        };
+        if !self
+            .get_search_state_unwrap()
+            .select_find_match(&roots, &term, forward)
+        {
+            self.show_toast(
+                format!("No results contain \"{term}\""),
+                Duration::from_secs(2),
+            );
+        }
+        EventHandlingResult::Rerender
+    }
+
+    /// The `scooter --no-tui` command that performs the current search and replacement
+    pub fn headless_command(&self) -> String {
+        let directories = match &self.input_source {
+            InputSource::Directory(directories) => Some(directories.as_slice()),
+            InputSource::Stdin(_) => None,
+        };
+        headless_command::headless_command(&self.search_fields, &self.run_config, directories)
+    }
+
+    fn show_headless_command(&mut self) {
+        let command = self.headless_command();
+        let mut body =
+            format!("{command}\n\nCopied to the clipboard, if supported by your terminal.");
+        let has_excluded_results = matches!(
+            &self.ui_state.current_screen,
+            Screen::SearchFields(SearchFieldsState { search_state: Some(state), .. })
+                if state.results.iter().any(|res| !res.search_result.included)
+        );
+        if has_excluded_results {
+            body.push_str(
+                "\n\nResults that have been excluded from replacement are not excluded by this command.",
+            );
+        }
+        if self.approved_files.is_some() {
+            body.push_str(
+                "\n\nFiles that were rejected in the file picker are not excluded by this command.",
+            );
+        }
+        self.event_channels
+            .sender
+            .send(Event::CopyToClipboard(command))
+            .expect("Failed to send event");
+        self.set_popup(Popup::Text {
+            title: "Equivalent command".to_string(),
+            body,
+        });
+    }
+
+    fn show_match_summary(&mut self) {
+        let Some(searcher) = &self.searcher else {
+            return;
+        };
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(state),
+            ..
+        }) = &self.ui_state.current_screen
+        else {
+            return;
+        };
+        let counts = match_counts(
+            state.results.iter().map(|res| &res.search_result),
+            searcher.search(),
+        );
+        let body = match_summary(&counts, state.phase.is_complete());
+        self.set_popup(Popup::Text {
+            title: "Match summary".to_string(),
+            body,
+        });
+    }
+
+    fn show_error_details(&mut self) -> EventHandlingResult {
+        let Screen::Results(replace_state) = &self.ui_state.current_screen else {
+            return EventHandlingResult::None;
+        };
+        let Some((path_display, message)) = replace_state.selected_error_details() else {
+            return EventHandlingResult::None;
+        };
+        self.set_popup(Popup::Text {
+            title: "Error details".to_string(),
+            body: format!("{path_display}\n\n{message}"),
+        });
+        EventHandlingResult::Rerender
+    }
+
+    fn retry_failed_replacements(&mut self) -> EventHandlingResult {
+        if !matches!(&self.ui_state.current_screen, Screen::Results(state) if !state.errors.is_empty())
+        {
+            return EventHandlingResult::None;
+        }
+        // Results with a preview error have no replacement, so they can only be retried safely
+        // when the replacements can be validated against the search and replace fields
+        let Ok(Some(Searcher::FileSearcher(file_searcher))) = self.validate_fields() else {
+            self.add_error(AppError {
+                name: "Can't retry replacements".to_string(),
+                long: "Retrying is only supported when searching through files".to_string(),
+            });
+            return EventHandlingResult::Rerender;
+        };
+        let Screen::Results(replace_state) = &mut self.ui_state.current_screen else {
+            unreachable!("Checked above that the results screen is shown");
+        };
+        let previous_counts = (replace_state.num_successes, replace_state.num_ignored);
+        let errors = replace_state.take_errors_for_retry();
+
+        let (background_processing_sender, background_processing_receiver) =
+            mpsc::unbounded_channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let total_replacements = errors.len();
+        let replacements_completed = Arc::new(AtomicUsize::new(0));
+        replace::perform_replacement(
+            errors,
+            background_processing_sender,
+            cancelled.clone(),
+            replacements_completed.clone(),
+            self.event_channels.sender.clone(),
+            Some(file_searcher),
+            self.file_content_provider.clone(),
+            self.run_config.delete_empty_lines,
+            self.run_config.symlinks,
+            self.config.ui.render_interval(),
+        );
+
+        let mut state = PerformingReplacementState::new(
+            background_processing_receiver,
+            cancelled,
+            replacements_completed,
+            total_replacements,
+        );
+        state.previous_counts = Some(previous_counts);
+        self.ui_state.current_screen = Screen::PerformingReplacement(state);
+        EventHandlingResult::Rerender
+    }
+
+    fn save_preset(&mut self, name: &str) {
+        let preset = Preset::from_search_fields(name, &self.search_fields);
+        match presets::save_preset(&config::presets_file(), preset) {
+            Ok(()) => {
+                self.show_toast(format!("Saved preset \"{name}\""), Duration::from_secs(2));
+            }
+            Err(e) => self.add_error(AppError {
+                name: "Failed to save preset".to_string(),
+                long: e.to_string(),
+            }),
+        }
+    }
+
     fn enter_chars_into_field(
         &mut self,
         key_code: KeyCode,
@@ -1618,12 +2803,25 @@ impl<'a> App {
         if let FieldName::Replace = self.search_fields.highlighted_field().name {
             return self.handle_replacement_config_change();
         }
+        self.handle_search_inputs_change()
+    }
 
+    fn clear_search_fields(&mut self) -> EventHandlingResult {
+        let Screen::SearchFields(_) = self.ui_state.current_screen else {
+            return EventHandlingResult::None;
+        };
+        self.search_fields
+            .clear(self.config.search.disable_prepopulated_fields);
+        self.handle_search_inputs_change()
+    }
+
+    /// Re-schedules (or clears) the live search after the search fields have been edited
+    fn handle_search_inputs_change(&mut self) -> EventHandlingResult {
         // Empty search: cancel any in-flight work, drop results, and skip the
         // debounce entirely. Rendering the "Search is empty" banner from live
         // text (see view.rs) means this produces no transient "Still
         // searching…" flash.
-        if self.search_fields.search().text().is_empty() {
+        if self.search_is_empty() {
             self.ui_state
                 .current_screen
                 .unwrap_search_fields_state_mut()
@@ -1665,6 +2863,7 @@ impl<'a> App {
             state.cancel();
             state.set_pending();
         }
+        self.search_pause.resume();
         let generation = sfs.next_search_generation();
         sfs.last_scheduled_key = Some(Box::new(key));
         sfs.pending_search_generation = Some(generation);
@@ -1748,10 +2947,19 @@ impl<'a> App {
             });
         });
         search_fields_state.preview_update_state =
-            Some(PreviewUpdateStatus::new(handle, cancelled));
+            Some(Box::new(PreviewUpdateStatus::new(handle, cancelled)));
     }
 
     fn handle_replacement_config_change(&mut self) -> EventHandlingResult {
+        if self.run_config.skip_noop {
+            // Which results are no-ops depends on the replacement, so search again rather than
+            // updating the replacements of the existing results
+            self.ui_state
+                .current_screen
+                .unwrap_search_fields_state_mut()
+                .last_scheduled_key = None;
+            return self.handle_search_inputs_change();
+        }
         self.ui_state
             .current_screen
             .unwrap_search_fields_state_mut()
@@ -1763,6 +2971,79 @@ impl<'a> App {
         EventHandlingResult::Rerender
     }
 
+    /// Moves focus to the field with the given name, leaving any search running so that its results
+    /// can be returned to. Fields that are hidden, or locked after being set via the CLI, can't be
+    /// focussed
+    fn focus_field(&mut self, field_name: &FieldName) -> EventHandlingResult {
+        let disable_prepopulated_fields = self.config.search.disable_prepopulated_fields;
+        self.search_fields
+            .focus(field_name, disable_prepopulated_fields);
+        if self.search_fields.highlighted_field().name != *field_name {
+            let hidden = self
+                .search_fields
+                .fields
+                .iter()
+                .any(|field| field.name == *field_name && field.hidden);
+            let reason = if hidden {
+                "is hidden"
+            } else {
+                "was set from the command line"
+            };
+            self.show_toast(
+                format!("{} {reason}", field_name.title()),
+                Duration::from_secs(2),
+            );
+            return EventHandlingResult::Rerender;
+        }
+        self.ui_state
+            .current_screen
+            .unwrap_search_fields_state_mut()
+            .focussed_section = FocussedSection::SearchFields;
+        EventHandlingResult::Rerender
+    }
+
+    /// Toggles a checkbox field while the search results are focussed and re-runs the search. If the
+    /// search becomes invalid, focus moves back to the fields so that the error is visible
+    fn toggle_checkbox_from_results(&mut self, field_name: &FieldName) -> EventHandlingResult {
+        let disable_prepopulated_fields = self.config.search.disable_prepopulated_fields;
+        let Some(field) = self
+            .search_fields
+            .fields
+            .iter_mut()
+            .find(|field| field.name == *field_name)
+        else {
+            return EventHandlingResult::None;
+        };
+        if field.set_by_cli && disable_prepopulated_fields {
+            self.show_toast(
+                format!("{} was set from the command line", field_name.title()),
+                Duration::from_secs(2),
+            );
+            return EventHandlingResult::Rerender;
+        }
+        let Field::Checkbox(checkbox) = &mut field.field else {
+            return EventHandlingResult::None;
+        };
+        checkbox.checked = !checkbox.checked;
+        let checked = checkbox.checked;
+        self.show_toggle_toast(field_name.title(), checked);
+
+        self.ui_state
+            .current_screen
+            .unwrap_search_fields_state_mut()
+            .cancel_pending_async_work();
+        if self.revalidate_and_store_searcher() {
+            self.refresh_search();
+        } else {
+            self.invalidate_search_state_and_key();
+            self.ui_state
+                .current_screen
+                .unwrap_search_fields_state_mut()
+                .focussed_section = FocussedSection::SearchFields;
+        }
+        EventHandlingResult::Rerender
+    }
+
     fn get_search_state_unwrap(&mut self) -> &mut SearchState {
         self.ui_state
             .current_screen
@@ -1773,7 +3054,7 @@ impl<'a> App {
     }
 
     /// Should only be called on `Screen::SearchFields`, and when focussed section is `FocussedSection::SearchResults`
-    #[allow(clippy::needless_pass_by_value)]
+    #[allow(clippy::too_many_lines, clippy::needless_pass_by_value)]
     fn handle_command_search_results(
         &mut self,
         event: CommandSearchFocusResults,
@@ -1818,6 +3099,9 @@ impl<'a> App {
                 }
                 EventHandlingResult::Rerender
             }
+            CommandSearchFocusResults::CopyMatchedText => self.copy_selected_result_text(false),
+            CommandSearchFocusResults::CopyReplacement => self.copy_selected_result_text(true),
+            CommandSearchFocusResults::CopyResultsAsGrep => self.copy_results_as_grep(),
             CommandSearchFocusResults::MoveDown => {
                 self.get_search_state_unwrap().move_selected_down();
                 EventHandlingResult::Rerender
@@ -1852,6 +3136,14 @@ impl<'a> App {
                 self.get_search_state_unwrap().move_selected_bottom();
                 EventHandlingResult::Rerender
             }
+            CommandSearchFocusResults::ScrollPreviewDown => {
+                self.get_search_state_unwrap().scroll_preview_by(1);
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusResults::ScrollPreviewUp => {
+                self.get_search_state_unwrap().scroll_preview_by(-1);
+                EventHandlingResult::Rerender
+            }
             CommandSearchFocusResults::ToggleSelectedInclusion => {
                 self.get_search_state_unwrap().toggle_selected_inclusion();
                 EventHandlingResult::Rerender
@@ -1860,6 +3152,35 @@ impl<'a> App {
                 self.get_search_state_unwrap().toggle_all_selected();
                 EventHandlingResult::Rerender
             }
+            CommandSearchFocusResults::ToggleFileInclusion => {
+                self.get_search_state_unwrap().toggle_file_inclusion();
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusResults::ExpandFile => self.expand_selected_file(),
+            CommandSearchFocusResults::FindInResults => {
+                let term = self.ui_state.find_term.clone().unwrap_or_default();
+                self.set_popup(Popup::FindInResults(TextField::new(&term)));
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusResults::FindNext => self.select_find_match(true),
+            CommandSearchFocusResults::FindPrevious => self.select_find_match(false),
+            CommandSearchFocusResults::SelectRange => {
+                self.set_popup(Popup::SelectRange(TextField::new("")));
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusResults::ShowMatchSummary => {
+                self.show_match_summary();
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusResults::ToggleFixedStrings => {
+                self.toggle_checkbox_from_results(&FieldName::FixedStrings)
+            }
+            CommandSearchFocusResults::ToggleMatchCase => {
+                self.toggle_checkbox_from_results(&FieldName::MatchCase)
+            }
+            CommandSearchFocusResults::ToggleWholeWord => {
+                self.toggle_checkbox_from_results(&FieldName::WholeWord)
+            }
             CommandSearchFocusResults::ToggleMultiselectMode => {
                 self.get_search_state_unwrap().toggle_multiselect_mode();
                 EventHandlingResult::Rerender
@@ -1868,9 +3189,60 @@ impl<'a> App {
                 self.get_search_state_unwrap().flip_multiselect_direction();
                 EventHandlingResult::Rerender
             }
+            CommandSearchFocusResults::RefreshSearch => {
+                self.refresh_search();
+                EventHandlingResult::Rerender
+            }
+            CommandSearchFocusResults::CyclePathStyle => {
+                self.config.display.path_style = self.config.display.path_style.next();
+                self.show_toast(
+                    format!("Path style: {}", self.config.display.path_style.as_str()),
+                    Duration::from_millis(1500),
+                );
+                EventHandlingResult::Rerender
+            }
+        }
+    }
+
+    /// Handles a mouse event in the search results list: clicking a result selects it (toggling whether
+    /// it is included if the click is on its checkbox), and scrolling moves the selection
+    pub fn handle_mouse_event(&mut self, event: MouseEvent) -> EventHandlingResult {
+        if !self.config.display.mouse || self.show_popup() {
+            return EventHandlingResult::None;
+        }
+        let Some(area) = self
+            .ui_state
+            .results_list_area
+            .filter(|area| area.contains(event.column, event.row))
+        else {
+            return EventHandlingResult::None;
+        };
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(search_state),
+            focussed_section,
+            ..
+        }) = &mut self.ui_state.current_screen
+        else {
+            return EventHandlingResult::None;
+        };
+
+        match event.kind {
+            MouseEventKind::LeftClick => {
+                let idx = search_state.view_offset + usize::from(event.row - area.y);
+                if idx >= search_state.results.len() {
+                    return EventHandlingResult::None;
+                }
+                let on_checkbox = event.column - area.x < RESULT_CHECKBOX_WIDTH;
+                search_state.click_result(idx, on_checkbox);
+                *focussed_section = FocussedSection::SearchResults;
+            }
+            MouseEventKind::ScrollUp => search_state.scroll_selected_by(-1),
+            MouseEventKind::ScrollDown => search_state.scroll_selected_by(1),
         }
+        EventHandlingResult::Rerender
     }
 
+    #[allow(clippy::too_many_lines)]
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> EventHandlingResult {
         let command = match self.handle_special_cases(key_event) {
             Left(command) => command,
@@ -1893,16 +3265,52 @@ impl<'a> App {
                     self.set_popup(Popup::Help);
                     return EventHandlingResult::Rerender;
                 }
+                CommandGeneral::ShowCommandPalette => {
+                    let entries = self.command_palette_entries();
+                    self.set_popup(Popup::CommandPalette(CommandPaletteState::new(entries)));
+                    return EventHandlingResult::Rerender;
+                }
+                CommandGeneral::EditConfig => {
+                    self.event_channels
+                        .sender
+                        .send(Event::EditConfig)
+                        .expect("Failed to send event");
+                    return EventHandlingResult::Rerender;
+                }
             }
         }
 
         match &mut self.ui_state.current_screen {
+            Screen::FilePicker(file_picker_state) => {
+                let Command::FilePicker(command) = command else {
+                    panic!("Expected FilePicker command, found {command:?}");
+                };
+                match command {
+                    CommandFilePicker::Confirm => return self.confirm_file_picker(),
+                    CommandFilePicker::MoveDown => file_picker_state.move_selected_down(),
+                    CommandFilePicker::MoveUp => file_picker_state.move_selected_up(),
+                    CommandFilePicker::MoveTop => file_picker_state.move_selected_top(),
+                    CommandFilePicker::MoveBottom => file_picker_state.move_selected_bottom(),
+                    CommandFilePicker::ToggleSelectedInclusion => {
+                        file_picker_state.toggle_selected_inclusion();
+                    }
+                    CommandFilePicker::ToggleAllSelected => {
+                        file_picker_state.toggle_all_selected();
+                    }
+                }
+                EventHandlingResult::Rerender
+            }
             Screen::SearchFields(search_fields_state) => {
                 let Command::SearchFields(command) = command else {
                     panic!("Expected SearchFields command, found {command:?}");
                 };
 
                 match command {
+                    CommandSearchFields::TogglePreview => {
+                        self.config.display.show_preview = !self.config.display.show_preview;
+                        self.show_toggle_toast("Preview", self.config.display.show_preview);
+                        EventHandlingResult::Rerender
+                    }
                     CommandSearchFields::TogglePreviewWrapping => {
                         self.config.preview.wrap_text = !self.config.preview.wrap_text;
                         self.show_toggle_toast("Text wrapping", self.config.preview.wrap_text);
@@ -1918,6 +3326,25 @@ impl<'a> App {
                         EventHandlingResult::Rerender
                     }
                     CommandSearchFields::ToggleMultiline => {
+                        // Framed stdin is always searched line by line
+                        if matches!(self.input_source, InputSource::Stdin(_))
+                            && self.run_config.stdin_format.is_framed()
+                        {
+                            return EventHandlingResult::None;
+                        }
+                        // Whole files are searched in multiline mode, so line-based filters can't
+                        // be applied, as is enforced when passing `--multiline`
+                        if !self.run_config.multiline
+                            && (self.run_config.line_filter != LineFilter::default()
+                                || self.run_config.delete_empty_lines)
+                        {
+                            self.show_toast(
+                                "Multiline can't be combined with line filters or deleting empty lines"
+                                    .to_owned(),
+                                Duration::from_secs(3),
+                            );
+                            return EventHandlingResult::Rerender;
+                        }
                         self.run_config.multiline = !self.run_config.multiline;
                         if self.run_config.multiline {
                             self.ui_state.hints.has_shown_multiline_hint = false;
@@ -1926,6 +3353,15 @@ impl<'a> App {
                         self.perform_search_background();
                         EventHandlingResult::Rerender
                     }
+                    CommandSearchFields::ToggleAdvancedRegex => {
+                        self.run_config.advanced_regex = !self.run_config.advanced_regex;
+                        self.show_toggle_toast("Advanced regex", self.run_config.advanced_regex);
+                        // The search text may only have been invalid in the other regex engine, and is
+                        // validated again when searching
+                        self.search_fields.search_mut().clear_error();
+                        self.perform_search_background();
+                        EventHandlingResult::Rerender
+                    }
                     CommandSearchFields::ToggleInterpretEscapeSequences => {
                         self.run_config.interpret_escape_sequences =
                             !self.run_config.interpret_escape_sequences;
@@ -1935,6 +3371,46 @@ impl<'a> App {
                         );
                         self.handle_replacement_config_change()
                     }
+                    CommandSearchFields::ToggleNearbyReplacements => {
+                        self.config.preview.show_nearby_replacements =
+                            !self.config.preview.show_nearby_replacements;
+                        self.show_toggle_toast(
+                            "Nearby replacements",
+                            self.config.preview.show_nearby_replacements,
+                        );
+                        EventHandlingResult::Rerender
+                    }
+                    CommandSearchFields::TogglePauseSearch => {
+                        let is_running =
+                            search_fields_state
+                                .search_state
+                                .as_ref()
+                                .is_some_and(|state| {
+                                    matches!(state.phase, SearchPhase::Running { .. })
+                                });
+                        if !is_running {
+                            return EventHandlingResult::None;
+                        }
+                        let message = if self.search_pause.is_paused() {
+                            self.search_pause.resume();
+                            "Search resumed"
+                        } else {
+                            self.search_pause.pause();
+                            "Search paused"
+                        };
+                        self.show_toast(message.to_owned(), Duration::from_millis(1500));
+                        EventHandlingResult::Rerender
+                    }
+                    CommandSearchFields::ShowHeadlessCommand => {
+                        self.show_headless_command();
+                        EventHandlingResult::Rerender
+                    }
+                    CommandSearchFields::FocusIncludeFiles => {
+                        self.focus_field(&FieldName::IncludeFiles)
+                    }
+                    CommandSearchFields::FocusExcludeFiles => {
+                        self.focus_field(&FieldName::ExcludeFiles)
+                    }
                     CommandSearchFields::SearchFocusFields(command) => {
                         if !matches!(
                             search_fields_state.focussed_section,
@@ -1966,6 +3442,11 @@ impl<'a> App {
                 let Command::Results(command) = command else {
                     panic!("Expected SearchFields event, found {command:?}");
                 };
+                match command {
+                    CommandResults::ShowErrorDetails => return self.show_error_details(),
+                    CommandResults::RetryFailed => return self.retry_failed_replacements(),
+                    _ => {}
+                }
                 replace_state.handle_command_results(command)
             }
         }
@@ -1981,6 +3462,18 @@ impl<'a> App {
 
         // Quit should take precedent over closing popup etc.
         if !matches!(maybe_event, Some(Command::General(CommandGeneral::Quit))) {
+            if matches!(self.ui_state.popup, Some(Popup::SavePreset(_))) {
+                return Right(self.handle_save_preset_popup_key(key_event));
+            }
+            if matches!(self.ui_state.popup, Some(Popup::FindInResults(_))) {
+                return Right(self.handle_find_in_results_popup_key(key_event));
+            }
+            if matches!(self.ui_state.popup, Some(Popup::SelectRange(_))) {
+                return Right(self.handle_select_range_popup_key(key_event));
+            }
+            if matches!(self.ui_state.popup, Some(Popup::CommandPalette(_))) {
+                return Right(self.handle_command_palette_popup_key(key_event));
+            }
             if self.ui_state.popup.is_some() {
                 self.clear_popup();
                 return Right(EventHandlingResult::Rerender);
@@ -2021,12 +3514,13 @@ impl<'a> App {
 
     pub fn current_search_key(&self) -> SearchKey {
         let dir = match &self.input_source {
-            InputSource::Directory(directory) => Some(DirSearchKey {
+            InputSource::Directory(directories) => Some(DirSearchKey {
                 include_globs: self.search_fields.include_files().text().to_owned(),
                 exclude_globs: self.search_fields.exclude_files().text().to_owned(),
                 include_hidden: self.run_config.include_hidden,
                 include_git_folders: self.run_config.include_git_folders,
-                directory: directory.clone(),
+                include_binary: self.run_config.include_binary,
+                directories: directories.clone(),
             }),
             InputSource::Stdin(_) => None,
         };
@@ -2041,7 +3535,30 @@ impl<'a> App {
         }
     }
 
+    /// The directory configuration described by the search fields, or `None` when processing stdin
+    fn dir_config(&self) -> Option<DirConfig<'_>> {
+        match &self.input_source {
+            InputSource::Directory(directories) => Some(DirConfig {
+                include_globs: Some(self.search_fields.include_files().text()),
+                exclude_globs: Some(self.search_fields.exclude_files().text()),
+                include_hidden: self.run_config.include_hidden,
+                include_git_folders: self.run_config.include_git_folders,
+                include_binary: self.run_config.include_binary,
+                binary_detection: self.config.search.binary_detection(),
+                max_depth: self.run_config.max_depth,
+                modified_since: self.run_config.modified_since,
+                tracked_files: self.tracked_files.clone(),
+                regex_timeout: self.config.search.regex_timeout(),
+                directories: directories.clone(),
+            }),
+            InputSource::Stdin(_) => None,
+        }
+    }
+
     pub fn validate_fields(&mut self) -> anyhow::Result<Option<Searcher>> {
+        // The replacement is checked against the search pattern, so clear any stale error in case
+        // the search text has changed since the last validation
+        self.search_fields.replace_mut().clear_error();
         let search_config = SearchConfig {
             search_text: self.search_fields.search().text(),
             replacement_text: self.search_fields.replace().text(),
@@ -2051,105 +3568,176 @@ impl<'a> App {
             match_case: self.search_fields.match_case().checked,
             multiline: self.run_config.multiline,
             interpret_escape_sequences: self.run_config.interpret_escape_sequences,
+            wrap_matches: self.run_config.wrap_matches,
+            delete_empty_lines: self.run_config.delete_empty_lines,
         };
-        let dir_config = match &self.input_source {
-            InputSource::Directory(directory) => Some(DirConfig {
-                include_globs: Some(self.search_fields.include_files().text()),
-                exclude_globs: Some(self.search_fields.exclude_files().text()),
-                include_hidden: self.run_config.include_hidden,
-                include_git_folders: self.run_config.include_git_folders,
-                directory: directory.clone(),
-            }),
-            InputSource::Stdin(_) => None,
-        };
+        let dir_config = self.dir_config();
 
         let mut error_handler = AppErrorHandler::new();
         let result = validate_search_configuration(search_config, dir_config, &mut error_handler)?;
         error_handler.apply_to_app(self);
 
         let maybe_searcher = match result {
-            ValidationResult::Success((search_config, dir_config)) => match &self.input_source {
-                InputSource::Directory(_) => {
-                    let file_searcher = FileSearcher::new(
-                        search_config,
-                        dir_config.expect("Found None dir_config when searching through files"),
-                    );
-                    Some(Searcher::FileSearcher(file_searcher))
+            ValidationResult::Success((mut search_config, dir_config)) => {
+                search_config.line_filter = self.run_config.line_filter.clone();
+                match &self.input_source {
+                    InputSource::Directory(_) => {
+                        let mut dir_config =
+                            dir_config.expect("Found None dir_config when searching through files");
+                        dir_config.approved_files.clone_from(&self.approved_files);
+                        let file_searcher = FileSearcher::new(search_config, dir_config);
+                        Some(Searcher::FileSearcher(file_searcher))
+                    }
+                    InputSource::Stdin(_) => Some(Searcher::TextSearcher { search_config }),
                 }
-                InputSource::Stdin(_) => Some(Searcher::TextSearcher { search_config }),
-            },
+            }
             ValidationResult::ValidationErrors => None,
         };
         Ok(maybe_searcher)
     }
 
+    fn search_files(
+        file_searcher: &FileSearcher,
+        cancelled: &AtomicBool,
+        paused: &SearchPause,
+        files_scanned: Option<&AtomicUsize>,
+        sender: &UnboundedSender<BackgroundProcessingEvent>,
+    ) {
+        file_searcher.walk_files(Some(cancelled), Some(paused), files_scanned, || {
+            let sender = sender.clone();
+            Box::new(move |results| {
+                // Ignore error - likely state reset, thread about to be killed
+                let _ = sender.send(BackgroundProcessingEvent::AddSearchResults(results));
+                WalkState::Continue
+            })
+        });
+        let num_binary_files = file_searcher.num_binary_files_included();
+        if num_binary_files > 0 {
+            let _ = sender.send(BackgroundProcessingEvent::BinaryFilesIncluded(
+                num_binary_files,
+            ));
+        }
+        let num_dirs = file_searcher.num_dirs_beyond_max_depth();
+        if let Some(max_depth) = file_searcher.max_depth()
+            && num_dirs > 0
+        {
+            let _ = sender.send(BackgroundProcessingEvent::DirsBeyondMaxDepth {
+                num_dirs,
+                max_depth,
+            });
+        }
+        let non_utf8_lines = file_searcher.non_utf8_lines().files();
+        if !non_utf8_lines.is_empty() {
+            let _ = sender.send(BackgroundProcessingEvent::NonUtf8LinesSkipped(
+                non_utf8_lines,
+            ));
+        }
+        let num_too_slow = file_searcher.num_files_regex_too_slow();
+        if num_too_slow > 0 {
+            let _ = sender.send(BackgroundProcessingEvent::RegexTooSlow(num_too_slow));
+        }
+    }
+
+    fn search_text(
+        haystack: &str,
+        config: &ParsedSearchConfig,
+        format: StdinFormat,
+        cancelled: &AtomicBool,
+        paused: &SearchPause,
+        sender: &UnboundedSender<BackgroundProcessingEvent>,
+    ) {
+        if format.is_framed() {
+            let framed_search =
+                stdin_format::search_framed(haystack, format, &config.search, &config.line_filter);
+            for result in framed_search.results {
+                paused.wait_while_paused();
+                if cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                // Ignore error - likely state reset, thread about to be killed
+                let _ = sender.send(BackgroundProcessingEvent::AddSearchResult(result));
+            }
+            if framed_search.num_non_utf8_paths > 0 {
+                let _ = sender.send(BackgroundProcessingEvent::NonUtf8PathsSkipped(
+                    framed_search.num_non_utf8_paths,
+                ));
+            }
+        } else if config.multiline {
+            // When multiline is enabled, search the entire haystack at once
+            for result in search_multiline(haystack, &config.search, None) {
+                paused.wait_while_paused();
+                if cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                // Ignore error - likely state reset, thread about to be killed
+                let _ = sender.send(BackgroundProcessingEvent::AddSearchResult(result));
+            }
+        } else {
+            // Default line-by-line search
+            let cursor = Cursor::new(haystack.as_bytes());
+            for (idx, line_result) in cursor.lines_with_endings().enumerate() {
+                paused.wait_while_paused();
+                if cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let (line_ending, line) = match read_line(line_result) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        debug!("Error when reading line {idx}: {e}");
+                        continue;
+                    }
+                };
+                if contains_search(&line, &config.search) && config.line_filter.allows(&line) {
+                    let line_number = idx + 1;
+                    let result = SearchResult::new_line(None, line_number, line, line_ending, true);
+                    // Ignore error - likely state reset, thread about to be killed
+                    let _ = sender.send(BackgroundProcessingEvent::AddSearchResult(result));
+                }
+            }
+        }
+    }
+
     fn spawn_search_task(
         strategy: SearchStrategy,
         background_processing_sender: UnboundedSender<BackgroundProcessingEvent>,
         event_sender: UnboundedSender<Event>,
         cancelled: Arc<AtomicBool>,
+        paused: Arc<SearchPause>,
+        render_interval: Duration,
     ) -> JoinHandle<()> {
         tokio::spawn(async move {
+            // Ignore error - likely state reset, thread about to be killed
+            let _ = background_processing_sender.send(BackgroundProcessingEvent::SearchStarted);
             let sender_for_search = background_processing_sender.clone();
-            let mut search_handle = task::spawn_blocking(move || {
-                match strategy {
-                    SearchStrategy::Files(file_searcher) => {
-                        file_searcher.walk_files(Some(&cancelled), || {
-                            let sender = sender_for_search.clone();
-                            Box::new(move |results| {
-                                // Ignore error - likely state reset, thread about to be killed
-                                let _ = sender
-                                    .send(BackgroundProcessingEvent::AddSearchResults(results));
-                                WalkState::Continue
-                            })
-                        });
-                    }
-                    SearchStrategy::Text { haystack, config } => {
-                        // When multiline is enabled, search the entire haystack at once
-                        if config.multiline {
-                            for result in search_multiline(&haystack, &config.search, None) {
-                                if cancelled.load(Ordering::Relaxed) {
-                                    break;
-                                }
-                                // Ignore error - likely state reset, thread about to be killed
-                                let _ = sender_for_search
-                                    .send(BackgroundProcessingEvent::AddSearchResult(result));
-                            }
-                        } else {
-                            // Default line-by-line search
-                            let cursor = Cursor::new(haystack.as_bytes());
-                            for (idx, line_result) in cursor.lines_with_endings().enumerate() {
-                                if cancelled.load(Ordering::Relaxed) {
-                                    break;
-                                }
-
-                                let (line_ending, line) = match read_line(line_result) {
-                                    Ok(res) => res,
-                                    Err(e) => {
-                                        debug!("Error when reading line {idx}: {e}");
-                                        continue;
-                                    }
-                                };
-                                if contains_search(&line, &config.search) {
-                                    let line_number = idx + 1;
-                                    let result = SearchResult::new_line(
-                                        None,
-                                        line_number,
-                                        line,
-                                        line_ending,
-                                        true,
-                                    );
-                                    // Ignore error - likely state reset, thread about to be killed
-                                    let _ = sender_for_search
-                                        .send(BackgroundProcessingEvent::AddSearchResult(result));
-                                }
-                            }
-                        }
-                    }
-                }
+            let files_scanned = match strategy {
+                SearchStrategy::Files(_) => Some(Arc::new(AtomicUsize::new(0))),
+                SearchStrategy::Text { .. } => None,
+            };
+            let files_scanned_for_search = files_scanned.clone();
+            let mut search_handle = task::spawn_blocking(move || match strategy {
+                SearchStrategy::Files(file_searcher) => Self::search_files(
+                    &file_searcher,
+                    &cancelled,
+                    &paused,
+                    files_scanned_for_search.as_deref(),
+                    &sender_for_search,
+                ),
+                SearchStrategy::Text {
+                    haystack,
+                    config,
+                    format,
+                } => Self::search_text(
+                    &haystack,
+                    &config,
+                    format,
+                    &cancelled,
+                    &paused,
+                    &sender_for_search,
+                ),
             });
 
-            let mut rerender_interval = tokio::time::interval(Duration::from_millis(92)); // Slightly random duration so that time taken isn't a round number
+            let mut rerender_interval = tokio::time::interval(render_interval);
             rerender_interval.tick().await;
 
             loop {
@@ -2161,11 +3749,24 @@ impl<'a> App {
                         break;
                     },
                     _ = rerender_interval.tick() => {
+                        if let Some(files_scanned) = &files_scanned {
+                            let _ = background_processing_sender.send(
+                                BackgroundProcessingEvent::SearchProgress {
+                                    files_scanned: files_scanned.load(Ordering::Relaxed),
+                                },
+                            );
+                        }
                         let _ = event_sender.send(Event::Rerender);
                     }
                 }
             }
 
+            if let Some(files_scanned) = &files_scanned {
+                let _ =
+                    background_processing_sender.send(BackgroundProcessingEvent::SearchProgress {
+                        files_scanned: files_scanned.load(Ordering::Relaxed),
+                    });
+            }
             if let Err(err) =
                 background_processing_sender.send(BackgroundProcessingEvent::SearchCompleted)
             {
@@ -2244,8 +3845,30 @@ impl<'a> App {
         self.keymaps_impl(true)
     }
 
-    #[allow(clippy::too_many_lines)]
     fn keymaps_impl(&self, compact: bool) -> Vec<(String, String)> {
+        self.keymap_entries(compact)
+            .into_iter()
+            .map(|(from, to, _)| (from, to))
+            .collect()
+    }
+
+    /// The commands listed in the command palette: those available on the current screen, other than
+    /// opening the palette itself
+    fn command_palette_entries(&self) -> Vec<CommandPaletteEntry> {
+        let palette_keys = &self.config.keys.general.show_command_palette;
+        self.keymap_entries(false)
+            .into_iter()
+            .filter_map(|(keys, description, key)| {
+                let key = key.filter(|key| !palette_keys.contains(key))?;
+                Some(CommandPaletteEntry::new(keys, description, key))
+            })
+            .collect()
+    }
+
+    /// The keys available on the current screen, along with their descriptions and the first key bound
+    /// to each command, if any
+    #[allow(clippy::too_many_lines)]
+    fn keymap_entries(&self, compact: bool) -> Vec<(String, String, Option<KeyEvent>)> {
         enum Show {
             Both,
             FullOnly,
@@ -2253,31 +3876,69 @@ impl<'a> App {
             CompactOnly,
         }
 
+        // Keys are read from the same config that the dispatch `KeyMap` is built from, so that
+        // remapped keys are always shown
         macro_rules! keymap {
             ($($path:tt).+, $name:expr, $show:expr $(,)?) => {
                 (
-                    format!("<{}>", self.config.keys.$($path).+.first()
-                        .map_or_else(|| "n/a".to_string(), std::string::ToString::to_string)),
+                    display_keys(&self.config.keys.$($path).+, compact),
                     $name,
                     $show,
+                    self.config.keys.$($path).+.first().copied(),
                 )
             };
         }
 
         let current_screen_keys = match &self.ui_state.current_screen {
+            Screen::FilePicker(_) => vec![
+                keymap!(file_picker.toggle_selected_inclusion, "toggle", Show::Both),
+                keymap!(
+                    file_picker.toggle_all_selected,
+                    "toggle all",
+                    Show::FullOnly
+                ),
+                keymap!(file_picker.confirm, "search approved files", Show::Both),
+                keymap!(file_picker.move_down, "down", Show::FullOnly),
+                keymap!(file_picker.move_up, "up", Show::FullOnly),
+                keymap!(file_picker.move_top, "jump to top", Show::FullOnly),
+                keymap!(file_picker.move_bottom, "jump to bottom", Show::FullOnly),
+            ],
             Screen::SearchFields(search_fields_state) => {
                 let mut keys = vec![];
                 match search_fields_state.focussed_section {
                     FocussedSection::SearchFields => {
+                        // The compact hints are those most relevant to the focussed field: moving
+                        // between text fields, or toggling a checkbox
+                        let checkbox_focussed = matches!(
+                            self.search_fields.highlighted_field().field,
+                            Field::Checkbox(_)
+                        );
+                        let (text_field_show, checkbox_show) = if checkbox_focussed {
+                            (Show::FullOnly, Show::Both)
+                        } else {
+                            (Show::Both, Show::FullOnly)
+                        };
                         keys.extend([
                             keymap!(search.fields.trigger_search, "jump to results", Show::Both),
+                            keymap!(
+                                search.fields.focus_results,
+                                "back to results",
+                                Show::FullOnly,
+                            ),
                             keymap!(search.fields.focus_next_field, "focus next", Show::Both),
                             keymap!(
                                 search.fields.focus_previous_field,
                                 "focus previous",
-                                Show::FullOnly,
+                                text_field_show,
                             ),
-                            ("<space>".to_string(), "toggle checkbox", Show::FullOnly), // TODO(key-remap): add to config?
+                            (
+                                "<space>".to_string(),
+                                "toggle checkbox",
+                                checkbox_show,
+                                None,
+                            ), // TODO(key-remap): add to config?
+                            keymap!(search.fields.save_preset, "save preset", Show::FullOnly),
+                            keymap!(search.fields.clear_fields, "clear fields", Show::FullOnly),
                         ]);
                         if self.config.search.disable_prepopulated_fields {
                             keys.push(keymap!(
@@ -2303,6 +3964,43 @@ impl<'a> App {
                                 "toggle all",
                                 Show::FullOnly,
                             ),
+                            keymap!(
+                                search.results.toggle_file_inclusion,
+                                "toggle file",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.find_in_results,
+                                "find in results",
+                                Show::FullOnly,
+                            ),
+                            keymap!(search.results.find_next, "next match", Show::FullOnly),
+                            keymap!(
+                                search.results.find_previous,
+                                "previous match",
+                                Show::FullOnly,
+                            ),
+                            keymap!(search.results.select_range, "select range", Show::FullOnly),
+                            keymap!(
+                                search.results.show_match_summary,
+                                "match summary",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.toggle_fixed_strings,
+                                "toggle fixed strings",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.toggle_match_case,
+                                "toggle match case",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.toggle_whole_word,
+                                "toggle whole word",
+                                Show::FullOnly,
+                            ),
                             keymap!(
                                 search.results.toggle_multiselect_mode,
                                 "toggle multi-select mode",
@@ -2318,6 +4016,31 @@ impl<'a> App {
                                 "open in editor",
                                 Show::FullOnly,
                             ),
+                            keymap!(
+                                search.results.copy_matched_text,
+                                "copy matched text",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.copy_replacement,
+                                "copy replacement",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.copy_results_as_grep,
+                                "copy results as grep output",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.refresh_search,
+                                "refresh results",
+                                Show::FullOnly,
+                            ),
+                            keymap!(
+                                search.results.cycle_path_style,
+                                "cycle path style",
+                                Show::FullOnly,
+                            ),
                             keymap!(
                                 search.results.back_to_fields,
                                 "back to search fields",
@@ -2347,7 +4070,24 @@ impl<'a> App {
                             ),
                             keymap!(search.results.move_top, "jump to top", Show::FullOnly),
                             keymap!(search.results.move_bottom, "jump to bottom", Show::FullOnly),
+                            keymap!(
+                                search.results.scroll_preview_down,
+                                "scroll preview down",
+                                Show::FullOnly
+                            ),
+                            keymap!(
+                                search.results.scroll_preview_up,
+                                "scroll preview up",
+                                Show::FullOnly
+                            ),
                         ]);
+                        if self.config.display.max_matches_per_file.is_some() {
+                            keys.push(keymap!(
+                                search.results.expand_file,
+                                "show all results in file",
+                                Show::FullOnly,
+                            ));
+                        }
                         if self.search_has_completed() {
                             keys.push(keymap!(
                                 search.results.trigger_replacement,
@@ -2357,6 +4097,11 @@ impl<'a> App {
                         }
                     }
                 }
+                keys.push(keymap!(
+                    search.toggle_preview,
+                    "toggle preview",
+                    Show::FullOnly,
+                ));
                 keys.push(keymap!(
                     search.toggle_preview_wrapping,
                     "toggle text wrapping in preview",
@@ -2374,11 +4119,41 @@ impl<'a> App {
                     "toggle multiline",
                     Show::FullOnly,
                 ));
+                keys.push(keymap!(
+                    search.toggle_advanced_regex,
+                    "toggle advanced regex",
+                    Show::FullOnly,
+                ));
                 keys.push(keymap!(
                     search.toggle_interpret_escape_sequences,
                     "toggle escape sequences",
                     Show::FullOnly,
                 ));
+                keys.push(keymap!(
+                    search.toggle_nearby_replacements,
+                    "toggle nearby replacements in preview",
+                    Show::FullOnly,
+                ));
+                keys.push(keymap!(
+                    search.toggle_pause_search,
+                    "pause or resume search",
+                    Show::FullOnly,
+                ));
+                keys.push(keymap!(
+                    search.show_headless_command,
+                    "show equivalent command",
+                    Show::FullOnly,
+                ));
+                keys.push(keymap!(
+                    search.focus_include_files,
+                    "focus files to include",
+                    Show::FullOnly,
+                ));
+                keys.push(keymap!(
+                    search.focus_exclude_files,
+                    "focus files to exclude",
+                    Show::FullOnly,
+                ));
                 keys
             }
             Screen::PerformingReplacement(_) => vec![],
@@ -2387,6 +4162,8 @@ impl<'a> App {
                     vec![
                         keymap!(results.scroll_errors_down, "down", Show::Both),
                         keymap!(results.scroll_errors_up, "up", Show::Both),
+                        keymap!(results.show_error_details, "error details", Show::Both),
+                        keymap!(results.retry_failed, "retry failed", Show::FullOnly),
                     ]
                 } else {
                     vec![]
@@ -2413,28 +4190,36 @@ impl<'a> App {
             keymap!(
                 general.reset,
                 "reset",
-                if on_search_results {
+                // The compact hints on the search screen are kept to those for the focussed element,
+                // along with help and quit
+                if matches!(self.ui_state.current_screen, Screen::SearchFields(_)) {
                     Show::FullOnly
                 } else {
                     Show::Both
                 },
             ),
             keymap!(general.show_help_menu, "help", Show::Both),
-            ("<esc>".to_string(), esc_help.as_str(), Show::FullOnly),
+            keymap!(
+                general.show_command_palette,
+                "command palette",
+                Show::FullOnly
+            ),
+            keymap!(general.edit_config, "edit config", Show::FullOnly),
+            ("<esc>".to_string(), esc_help.as_str(), Show::FullOnly, None),
             keymap!(general.quit, "quit", Show::Both),
         ];
 
         let all_keys = current_screen_keys.into_iter().chain(additional_keys);
 
         all_keys
-            .filter_map(move |(from, to, show)| {
+            .filter_map(move |(from, to, show, key)| {
                 let include = match show {
                     Show::Both => true,
                     Show::CompactOnly => compact,
                     Show::FullOnly => !compact,
                 };
                 if include {
-                    Some((from, to.to_owned()))
+                    Some((from, to.to_owned(), key))
                 } else {
                     None
                 }
@@ -2471,6 +4256,14 @@ impl<'a> App {
         }
     }
 
+    /// Whether there is nothing to search for. Matches taken from `rg --json` output on stdin can be
+    /// replaced without any search text
+    pub fn search_is_empty(&self) -> bool {
+        self.search_fields.search().text().is_empty()
+            && !(matches!(self.input_source, InputSource::Stdin(_))
+                && self.run_config.stdin_format == StdinFormat::RgJson)
+    }
+
     pub fn search_has_completed(&self) -> bool {
         if let Screen::SearchFields(SearchFieldsState {
             search_state: Some(state),
@@ -2519,6 +4312,7 @@ fn read_line(
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct AppErrorHandler {
     search_errors: Option<(String, String)>,
+    replace_errors: Option<(String, String)>,
     include_errors: Option<(String, String)>,
     exclude_errors: Option<(String, String)>,
 }
@@ -2527,6 +4321,7 @@ impl AppErrorHandler {
     fn new() -> Self {
         Self {
             search_errors: None,
+            replace_errors: None,
             include_errors: None,
             exclude_errors: None,
         }
@@ -2539,6 +4334,12 @@ impl AppErrorHandler {
                 .set_error(error.clone(), detail.clone());
         }
 
+        if let Some((error, detail)) = &self.replace_errors {
+            app.search_fields
+                .replace_mut()
+                .set_error(error.clone(), detail.clone());
+        }
+
         if let Some((error, detail)) = &self.include_errors {
             app.search_fields
                 .include_files_mut()
@@ -2558,6 +4359,10 @@ impl ValidationErrorHandler for AppErrorHandler {
         self.search_errors = Some((error.to_owned(), detail.to_string()));
     }
 
+    fn handle_replace_text_error(&mut self, error: &str, detail: &str) {
+        self.replace_errors = Some((error.to_owned(), detail.to_string()));
+    }
+
     fn handle_include_files_error(&mut self, error: &str, detail: &str) {
         self.include_errors = Some((error.to_owned(), detail.to_string()));
     }
@@ -2572,7 +4377,7 @@ mod tests {
     use crate::{
         line_reader::LineEnding,
         replace::{ReplaceResult, ReplaceStats},
-        search::{SearchResult, SearchResultWithReplacement},
+        search::{ByteRangeParams, Line, SearchResult, SearchResultWithReplacement},
     };
     use rand::RngExt;
 
@@ -2586,6 +4391,8 @@ mod tests {
                 search: SearchType::Fixed("foo".to_string()),
                 replace: "bar".to_string(),
                 multiline: false,
+                line_filter: LineFilter::default(),
+                delete_empty_lines: false,
             },
         };
         let mut context = ReplacementContext::new(
@@ -2602,6 +4409,33 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn replacement_context_returns_match_spans_for_line_results() {
+        let input_source = InputSource::Stdin(Arc::new(String::new()));
+        let searcher = Searcher::TextSearcher {
+            search_config: ParsedSearchConfig {
+                search: SearchType::Fixed("foo".to_string()),
+                replace: "bar".to_string(),
+                multiline: false,
+                line_filter: LineFilter::default(),
+                delete_empty_lines: false,
+            },
+        };
+        let mut context = ReplacementContext::new(
+            &input_source,
+            &searcher,
+            searcher.search().needs_haystack_context(),
+            default_file_content_provider(),
+        );
+        let result =
+            SearchResult::new_line(None, 1, "foo baz foo".to_string(), LineEnding::Lf, true);
+
+        assert_eq!(
+            context.replacement_for_search_result(&result),
+            PreviewOutcome::Replacement("bar baz bar".to_string(), vec![0..3, 8..11])
+        );
+    }
+
     fn random_num() -> usize {
         let mut rng = rand::rng();
         rng.random_range(1..10000)
@@ -2620,6 +4454,7 @@ mod tests {
             replacement: "bar".to_owned(),
             replace_result: None,
             preview_error: None,
+            match_spans: vec![],
         }
     }
 
@@ -2636,6 +4471,7 @@ mod tests {
                 replacement: format!("replacement {i}").to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             })
             .collect()
     }
@@ -2661,9 +4497,30 @@ mod tests {
             phase: SearchPhase::Running {
                 started: Instant::now(),
             },
+            files_scanned: None,
+            results_to_restore: None,
+            num_noops_skipped: 0,
+            preview_scroll: (0, 0),
+            held_back_results: None,
         }
     }
 
+    #[test]
+    fn test_preview_scroll_offset_resets_when_selection_changes() {
+        let mut search_state = build_test_search_state(5);
+        search_state.scroll_preview_by(3);
+        search_state.scroll_preview_by(-1);
+        assert_eq!(search_state.preview_scroll_offset(), 2);
+
+        search_state.move_selected_down();
+        assert_eq!(search_state.preview_scroll_offset(), 0);
+        search_state.scroll_preview_by(-4);
+        assert_eq!(search_state.preview_scroll_offset(), -4);
+
+        search_state.move_selected_up();
+        assert_eq!(search_state.preview_scroll_offset(), 0);
+    }
+
     #[test]
     fn test_toggle_all_selected_when_all_selected() {
         let mut search_state = build_test_search_state_with_results(vec![
@@ -2732,6 +4589,265 @@ mod tests {
         );
     }
 
+    fn result_in_file(path: &str, included: bool) -> SearchResultWithReplacement {
+        let mut result = search_result_with_replacement(included);
+        result.search_result.path = Some(PathBuf::from(path));
+        result
+    }
+
+    fn inclusions(search_state: &SearchState) -> Vec<bool> {
+        search_state
+            .results
+            .iter()
+            .map(|res| res.search_result.included)
+            .collect()
+    }
+
+    #[test]
+    fn test_max_matches_per_file_holds_back_results() {
+        let mut search_state = build_test_search_state_with_results(vec![]);
+        search_state.set_max_matches_per_file(2);
+        for path in ["a.txt", "a.txt", "a.txt", "b.txt", "a.txt"] {
+            let result = result_in_file(path, true);
+            if let Some(res) = search_state.hold_back_if_over_max(result.search_result.clone()) {
+                search_state.results.push(SearchResultWithReplacement {
+                    search_result: res,
+                    ..result
+                });
+            }
+        }
+        assert_eq!(search_state.results.len(), 3);
+        assert_eq!(search_state.num_held_back(&Some(PathBuf::from("a.txt"))), 2);
+        assert_eq!(search_state.num_held_back(&Some(PathBuf::from("b.txt"))), 0);
+
+        search_state.selected = Selected::Single(0);
+        let (path, held_back) = search_state.take_held_back_for_selected_file().unwrap();
+        assert_eq!(path, Some(PathBuf::from("a.txt")));
+        assert_eq!(held_back.len(), 2);
+        assert_eq!(search_state.num_held_back(&path), 0);
+        search_state.insert_after_file(
+            path.as_ref(),
+            held_back
+                .into_iter()
+                .map(|res| SearchResultWithReplacement {
+                    search_result: res,
+                    ..search_result_with_replacement(true)
+                })
+                .collect(),
+        );
+        let paths = search_state
+            .results
+            .iter()
+            .map(|res| res.search_result.path.as_ref().unwrap().to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["a.txt", "a.txt", "a.txt", "a.txt", "b.txt"]);
+
+        // Further results from an expanded file are shown
+        assert!(
+            search_state
+                .hold_back_if_over_max(result_in_file("a.txt", true).search_result)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_toggle_file_inclusion_excludes_file_when_all_included() {
+        let mut search_state = build_test_search_state_with_results(vec![
+            result_in_file("a.txt", true),
+            result_in_file("a.txt", true),
+            result_in_file("b.txt", true),
+            result_in_file("a.txt", true),
+        ]);
+        search_state.selected = Selected::Single(1);
+        search_state.toggle_file_inclusion();
+        assert_eq!(inclusions(&search_state), vec![false, false, true, false]);
+    }
+
+    #[test]
+    fn test_toggle_file_inclusion_includes_file_when_some_excluded() {
+        let mut search_state = build_test_search_state_with_results(vec![
+            result_in_file("a.txt", false),
+            result_in_file("b.txt", false),
+            result_in_file("b.txt", true),
+        ]);
+        search_state.selected = Selected::Single(2);
+        search_state.toggle_file_inclusion();
+        assert_eq!(inclusions(&search_state), vec![false, true, true]);
+    }
+
+    #[test]
+    fn test_match_summary() {
+        let counts = (0..25)
+            .map(|idx| (format!("match\t{idx}"), 25 - idx))
+            .collect::<Vec<_>>();
+        let summary = match_summary(&counts, false);
+        let lines = summary.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "25 distinct matches across 325 occurrences");
+        assert_eq!(lines[2], "25  match\\t0");
+        assert_eq!(lines[21], " 6  match\\t19");
+        assert_eq!(lines[22], "... and 5 more");
+        assert_eq!(
+            lines[24],
+            "The search is still running, so these counts may be incomplete."
+        );
+
+        assert_eq!(
+            match_summary(&[("foo".to_owned(), 1)], true),
+            "1 distinct match across 1 occurrence\n\n1  foo"
+        );
+        assert_eq!(
+            match_summary(&[], true),
+            "0 distinct matches across 0 occurrences"
+        );
+    }
+
+    #[test]
+    fn test_grep_output() {
+        let line_result =
+            |path: Option<&str>, line_number: usize, content: &str| SearchResultWithReplacement {
+                search_result: SearchResult::new_line(
+                    path.map(|path| PathBuf::from("/root").join(path)),
+                    line_number,
+                    content.to_owned(),
+                    LineEnding::Lf,
+                    true,
+                ),
+                ..search_result_with_replacement(true)
+            };
+        let multiline = SearchResultWithReplacement {
+            search_result: SearchResult::new_byte_range(ByteRangeParams {
+                path: Some(PathBuf::from("/root/b.txt")),
+                lines: vec![
+                    (
+                        4,
+                        Line {
+                            content: "foo {".to_owned(),
+                            line_ending: LineEnding::Lf,
+                        },
+                    ),
+                    (
+                        5,
+                        Line {
+                            content: "}".to_owned(),
+                            line_ending: LineEnding::Lf,
+                        },
+                    ),
+                ],
+                match_start_in_first_line: 4,
+                match_end_in_last_line: 1,
+                byte_start: 20,
+                byte_end: 23,
+                content: "{\n}".to_owned(),
+                included: true,
+            }),
+            ..search_result_with_replacement(true)
+        };
+        let results = [
+            line_result(Some("src/a.rs"), 3, "let foo = 1;"),
+            multiline,
+            line_result(None, 7, "foo: bar"),
+        ];
+
+        assert_eq!(
+            grep_output(
+                &results.iter().collect::<Vec<_>>(),
+                &[PathBuf::from("/root")]
+            ),
+            "src/a.rs:3:let foo = 1;\nb.txt:4:foo {\nb.txt:5:}\n7:foo: bar\n"
+        );
+    }
+
+    #[test]
+    fn test_include_only_occurrence_orders_by_path() {
+        let mut search_state = build_test_search_state_with_results(vec![
+            result_in_file("c.txt", false),
+            result_in_file("a.txt", true),
+            result_in_file("b.txt", true),
+        ]);
+        assert!(search_state.include_only_occurrence(2));
+        assert_eq!(inclusions(&search_state), vec![false, false, true]);
+        assert_eq!(search_state.primary_selected_pos(), 2);
+
+        assert!(!search_state.include_only_occurrence(4));
+        assert_eq!(inclusions(&search_state), vec![false, false, false]);
+    }
+
+    #[test]
+    fn test_toggle_file_inclusion_when_no_results() {
+        let mut search_state = build_test_search_state_with_results(vec![]);
+        search_state.toggle_file_inclusion();
+        assert_eq!(inclusions(&search_state), vec![] as Vec<bool>);
+    }
+
+    fn find_test_search_state() -> SearchState {
+        build_test_search_state_with_results(vec![
+            result_in_file("src/main.rs", true),
+            result_in_file("src/lib.rs", true),
+            result_in_file("README.md", true),
+            result_in_file("src/lib.rs", true),
+        ])
+    }
+
+    #[test]
+    fn test_select_find_match_forward_wraps_around() {
+        let mut search_state = find_test_search_state();
+        for expected in [1, 3, 1] {
+            assert!(search_state.select_find_match(&[], "lib", true));
+            assert_eq!(search_state.primary_selected_pos(), expected);
+        }
+    }
+
+    #[test]
+    fn test_select_find_match_backward_wraps_around() {
+        let mut search_state = find_test_search_state();
+        for expected in [3, 1, 3] {
+            assert!(search_state.select_find_match(&[], "lib", false));
+            assert_eq!(search_state.primary_selected_pos(), expected);
+        }
+    }
+
+    #[test]
+    fn test_select_find_match_matches_line_content() {
+        let mut search_state = find_test_search_state();
+        assert!(search_state.select_find_match(&[], "foo", true));
+        assert_eq!(search_state.primary_selected_pos(), 1);
+    }
+
+    #[test]
+    fn test_select_find_match_without_match() {
+        let mut search_state = find_test_search_state();
+        search_state.selected = Selected::Single(2);
+        assert!(!search_state.select_find_match(&[], "missing", true));
+        assert_eq!(search_state.primary_selected_pos(), 2);
+    }
+
+    #[test]
+    fn test_parse_result_range() {
+        assert_eq!(parse_result_range("50,80", 100), Ok((49, 79)));
+        assert_eq!(parse_result_range(" :3, 1 ", 3), Ok((2, 0)));
+        assert_eq!(parse_result_range("7", 10), Ok((6, 6)));
+        assert_eq!(
+            parse_result_range("1,11", 10),
+            Err("Range must be between 1 and 10".to_owned())
+        );
+        assert_eq!(
+            parse_result_range("0,5", 10),
+            Err("Range must be between 1 and 10".to_owned())
+        );
+        assert_eq!(
+            parse_result_range("a,5", 10),
+            Err("Invalid range \"a,5\", expected e.g. 50,80".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_select_range() {
+        let mut search_state = build_test_search_state(10);
+        search_state.select_range(2, 5);
+        assert_eq!(search_state.primary_selected_pos(), 5);
+        assert!((0..10).all(|idx| search_state.is_selected(idx) == (2..=5).contains(&idx)));
+    }
+
     fn success_result() -> SearchResultWithReplacement {
         let line_num = random_num();
         SearchResultWithReplacement {
@@ -2745,6 +4861,7 @@ mod tests {
             replacement: "bar".to_owned(),
             replace_result: Some(ReplaceResult::Success),
             preview_error: None,
+            match_spans: vec![],
         }
     }
 
@@ -2761,6 +4878,7 @@ mod tests {
             replacement: "bar".to_owned(),
             replace_result: None,
             preview_error: None,
+            match_spans: vec![],
         }
     }
 
@@ -2777,6 +4895,7 @@ mod tests {
             replacement: "bar".to_owned(),
             replace_result: Some(ReplaceResult::Error("error".to_owned())),
             preview_error: None,
+            match_spans: vec![],
         }
     }
 
@@ -2794,6 +4913,7 @@ mod tests {
             ReplaceStats {
                 num_successes: 3,
                 errors: vec![],
+                changed_paths: vec![PathBuf::from("random/file")],
             }
         );
     }
@@ -2818,6 +4938,7 @@ mod tests {
             ReplaceStats {
                 num_successes: 2,
                 errors: vec![error_result],
+                changed_paths: vec![PathBuf::from("random/file")],
             }
         );
     }
@@ -3116,7 +5237,7 @@ mod tests {
     #[test]
     fn test_key_handling_quit_takes_precedent() {
         let mut app = App::new(
-            InputSource::Directory(std::env::current_dir().unwrap()),
+            InputSource::Directory(vec![std::env::current_dir().unwrap()]),
             &SearchFieldValues::default(),
             AppRunConfig::default(),
             Config::default(),
@@ -3133,7 +5254,7 @@ mod tests {
     #[test]
     fn test_key_handling_unmapped_key_closes_popup() {
         let mut app = App::new(
-            InputSource::Directory(std::env::current_dir().unwrap()),
+            InputSource::Directory(vec![std::env::current_dir().unwrap()]),
             &SearchFieldValues::default(),
             AppRunConfig::default(),
             Config::default(),
@@ -3148,6 +5269,49 @@ mod tests {
         assert!(app.popup().is_none());
     }
 
+    #[test]
+    fn test_save_preset_popup_captures_text_input() {
+        let mut app = App::new(
+            InputSource::Directory(vec![std::env::current_dir().unwrap()]),
+            &SearchFieldValues::default(),
+            AppRunConfig::default(),
+            Config::default(),
+        )
+        .unwrap();
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::ALT));
+        assert!(matches!(app.popup(), Some(Popup::SavePreset(_))));
+
+        for c in "my preset".chars() {
+            app.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        let Some(Popup::SavePreset(name_field)) = app.popup() else {
+            panic!("Expected save preset popup, found {:?}", app.popup());
+        };
+        assert_eq!(name_field.text(), "my preset");
+        assert_eq!(app.search_fields.search().text(), "");
+
+        let res = app.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(matches!(res, EventHandlingResult::Rerender));
+        assert!(app.popup().is_none());
+    }
+
+    #[test]
+    fn test_save_preset_popup_ignores_empty_name() {
+        let mut app = App::new(
+            InputSource::Directory(vec![std::env::current_dir().unwrap()]),
+            &SearchFieldValues::default(),
+            AppRunConfig::default(),
+            Config::default(),
+        )
+        .unwrap();
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::ALT));
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+
+        let res = app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(res, EventHandlingResult::None));
+        assert!(matches!(app.popup(), Some(Popup::SavePreset(_))));
+    }
+
     #[test]
     fn test_escape_deprecation_message_with_default() {
         let keymap = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
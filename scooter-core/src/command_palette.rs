@@ -0,0 +1,196 @@
+use crate::{fields::TextField, keyboard::KeyEvent};
+
+/// A command listed in the command palette
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandPaletteEntry {
+    /// The keys bound to the command, formatted for display
+    pub keys: String,
+    pub description: String,
+    /// The key that is pressed on the user's behalf to run the command
+    pub(crate) key: KeyEvent,
+}
+
+impl CommandPaletteEntry {
+    pub(crate) fn new(keys: String, description: String, key: KeyEvent) -> Self {
+        Self {
+            keys,
+            description,
+            key,
+        }
+    }
+}
+
+/// State of the command palette popup, which lists the commands available on the current screen
+/// and narrows them down to those fuzzy matching the query
+#[derive(Debug)]
+pub struct CommandPaletteState {
+    pub query: TextField,
+    entries: Vec<CommandPaletteEntry>,
+    /// Position of the selected entry within the matching entries
+    selected: usize,
+}
+
+impl CommandPaletteState {
+    pub(crate) fn new(entries: Vec<CommandPaletteEntry>) -> Self {
+        Self {
+            query: TextField::new(""),
+            entries,
+            selected: 0,
+        }
+    }
+
+    /// The entries matching the query, best match first. Entries that match equally well keep
+    /// their original order
+    pub fn matching_entries(&self) -> Vec<&CommandPaletteEntry> {
+        let mut matches = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                fuzzy_match_score(self.query.text(), &entry.description).map(|score| (score, entry))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(score, _)| *score);
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub(crate) fn selected_entry(&self) -> Option<&CommandPaletteEntry> {
+        self.matching_entries().get(self.selected).copied()
+    }
+
+    pub(crate) fn move_selected_down(&mut self) {
+        let end = self.matching_entries().len().saturating_sub(1);
+        self.selected = if self.selected >= end {
+            0
+        } else {
+            self.selected + 1
+        };
+    }
+
+    pub(crate) fn move_selected_up(&mut self) {
+        self.selected = if self.selected == 0 {
+            self.matching_entries().len().saturating_sub(1)
+        } else {
+            self.selected - 1
+        };
+    }
+
+    /// Should be called whenever the query changes, as the matching entries may have changed
+    pub(crate) fn reset_selected(&mut self) {
+        self.selected = 0;
+    }
+}
+
+/// Scores how well `text` matches `query`, ignoring case, where lower scores are better matches.
+/// Returns `None` if the characters of `query` don't all appear in `text` in order. Text containing
+/// the query as a single substring beats text that only contains its characters spread out, and
+/// matches closer together or nearer the start of the text are preferred
+fn fuzzy_match_score(query: &str, text: &str) -> Option<(bool, usize, usize)> {
+    let query = query.to_lowercase();
+    let text = text.to_lowercase();
+    if let Some(start) = text.find(&query) {
+        return Some((false, 0, start));
+    }
+
+    let mut text_chars = text.char_indices();
+    let mut first = None;
+    let mut last = 0;
+    for query_char in query.chars() {
+        let (idx, _) = text_chars.find(|(_, c)| *c == query_char)?;
+        first.get_or_insert(idx);
+        last = idx;
+    }
+    let first = first.unwrap_or(0);
+    Some((true, last - first, first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::{KeyCode, KeyModifiers};
+
+    fn entry(description: &str) -> CommandPaletteEntry {
+        CommandPaletteEntry::new(
+            String::new(),
+            description.to_owned(),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        )
+    }
+
+    fn matching(state: &CommandPaletteState) -> Vec<&str> {
+        state
+            .matching_entries()
+            .into_iter()
+            .map(|entry| entry.description.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_fuzzy_match_score() {
+        assert_eq!(fuzzy_match_score("", "anything"), Some((false, 0, 0)));
+        assert_eq!(
+            fuzzy_match_score("Prev", "toggle preview"),
+            Some((false, 0, 7))
+        );
+        assert_eq!(
+            fuzzy_match_score("tgp", "toggle preview"),
+            Some((true, 7, 0))
+        );
+        assert_eq!(fuzzy_match_score("pt", "toggle preview"), None);
+    }
+
+    #[test]
+    fn test_matching_entries() {
+        let mut state = CommandPaletteState::new(vec![
+            entry("toggle preview"),
+            entry("scroll preview down"),
+            entry("reset"),
+            entry("open in editor"),
+        ]);
+        assert_eq!(
+            matching(&state),
+            vec![
+                "toggle preview",
+                "scroll preview down",
+                "reset",
+                "open in editor"
+            ]
+        );
+
+        for c in "pre".chars() {
+            state.query.enter_char(c);
+        }
+        assert_eq!(
+            matching(&state),
+            vec!["toggle preview", "scroll preview down"]
+        );
+
+        state.query.clear();
+        for c in "oe".chars() {
+            state.query.enter_char(c);
+        }
+        assert_eq!(
+            matching(&state),
+            vec!["open in editor", "toggle preview", "scroll preview down"]
+        );
+    }
+
+    #[test]
+    fn test_move_selected() {
+        let mut state = CommandPaletteState::new(vec![entry("a"), entry("b"), entry("c")]);
+        assert_eq!(state.selected_entry(), Some(&entry("a")));
+        state.move_selected_up();
+        assert_eq!(state.selected_entry(), Some(&entry("c")));
+        state.move_selected_down();
+        assert_eq!(state.selected(), 0);
+        state.move_selected_down();
+        assert_eq!(state.selected_entry(), Some(&entry("b")));
+
+        state.query.enter_char('z');
+        state.reset_selected();
+        assert_eq!(state.selected_entry(), None);
+    }
+}
@@ -2,13 +2,14 @@ use std::collections::HashMap;
 
 use crate::{
     app::{FocussedSection, Screen},
-    config::KeysConfig,
+    config::{Keys, KeysConfig},
     keyboard::{KeyCode, KeyEvent, KeyModifiers},
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum Command {
     General(CommandGeneral),
+    FilePicker(CommandFilePicker),
     SearchFields(CommandSearchFields),
     PerformingReplacement(CommandPerformingReplacement),
     Results(CommandResults),
@@ -20,15 +21,36 @@ pub(crate) enum CommandGeneral {
     Quit,
     Reset,
     ShowHelpMenu,
+    ShowCommandPalette,
+    EditConfig,
+}
+
+// Events applicable only to `FilePicker` screen
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum CommandFilePicker {
+    Confirm,
+    MoveDown,
+    MoveUp,
+    MoveTop,
+    MoveBottom,
+    ToggleSelectedInclusion,
+    ToggleAllSelected,
 }
 
 // Events applicable only to `SearchFields` screen
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum CommandSearchFields {
+    TogglePreview,
     TogglePreviewWrapping,
     ToggleHiddenFiles,
     ToggleMultiline,
+    ToggleAdvancedRegex,
     ToggleInterpretEscapeSequences,
+    ToggleNearbyReplacements,
+    TogglePauseSearch,
+    ShowHeadlessCommand,
+    FocusIncludeFiles,
+    FocusExcludeFiles,
     SearchFocusFields(CommandSearchFocusFields),
     SearchFocusResults(CommandSearchFocusResults),
 }
@@ -38,8 +60,11 @@ pub(crate) enum CommandSearchFields {
 pub(crate) enum CommandSearchFocusFields {
     UnlockPrepopulatedFields,
     TriggerSearch,
+    FocusResults,
     FocusNextField,
     FocusPreviousField,
+    SavePreset,
+    ClearFields,
     EnterChars(KeyCode, KeyModifiers),
 }
 
@@ -49,6 +74,11 @@ pub(crate) enum CommandSearchFocusResults {
     TriggerReplacement,
     BackToFields,
     OpenInEditor,
+    CopyMatchedText,
+    CopyReplacement,
+    CopyResultsAsGrep,
+    ExpandFile,
+    RefreshSearch,
 
     MoveDown,
     MoveUp,
@@ -58,12 +88,29 @@ pub(crate) enum CommandSearchFocusResults {
     MoveUpFullPage,
     MoveTop,
     MoveBottom,
+    ScrollPreviewDown,
+    ScrollPreviewUp,
 
     ToggleSelectedInclusion,
     ToggleAllSelected,
+    ToggleFileInclusion,
     ToggleMultiselectMode,
 
+    FindInResults,
+    FindNext,
+    FindPrevious,
+
+    SelectRange,
+
+    ShowMatchSummary,
+
+    ToggleFixedStrings,
+    ToggleMatchCase,
+    ToggleWholeWord,
+
     FlipMultiselectDirection,
+
+    CyclePathStyle,
 }
 
 // Events applicable only to `PerformingReplacement` screen
@@ -75,18 +122,23 @@ pub(crate) enum CommandPerformingReplacement {}
 pub(crate) enum CommandResults {
     ScrollErrorsDown,
     ScrollErrorsUp,
+    ShowErrorDetails,
+    RetryFailed,
     Quit,
 }
 
 #[derive(Debug)]
 pub(crate) struct KeyMap {
     general: HashMap<KeyEvent, CommandGeneral>,
+    file_picker: HashMap<KeyEvent, CommandFilePicker>,
     search_fields: HashMap<KeyEvent, CommandSearchFocusFields>,
     search_results: HashMap<KeyEvent, CommandSearchFocusResults>,
     search_common: HashMap<KeyEvent, CommandSearchFields>,
     #[allow(clippy::zero_sized_map_values)]
     performing_replacement: HashMap<KeyEvent, CommandPerformingReplacement>,
     results: HashMap<KeyEvent, CommandResults>,
+    /// Bindings that prevent a character from being typed into the search fields
+    pub(crate) text_input_shadows: Vec<TextInputShadow>,
 }
 
 /// Represents a key binding conflict detected during `KeyMap` construction
@@ -97,10 +149,26 @@ pub(crate) struct KeyConflict {
     pub(crate) commands: Vec<String>,
 }
 
+/// Represents a binding of a plain character that is active while the search fields are focussed,
+/// and so takes precedence over typing that character. Not an error, but worth warning about.
+#[derive(Debug)]
+pub(crate) struct TextInputShadow {
+    pub(crate) key: KeyEvent,
+    pub(crate) context: String,
+    pub(crate) command: String,
+}
+
 impl KeyMap {
     /// Build a `KeyMap` from `KeysConfig`, detecting any conflicts
+    ///
+    /// Commands whose default keys were added after users may already have bound the same keys to
+    /// other commands are listed under `give_way`. While they are left on their defaults, any of
+    /// those keys that are bound elsewhere are dropped rather than reported as conflicts, and are
+    /// removed from `keys_config` so that they aren't shown as bound to those commands.
     #[allow(clippy::too_many_lines)]
-    pub(crate) fn from_config(keys_config: &KeysConfig) -> Result<Self, Vec<KeyConflict>> {
+    pub(crate) fn from_config(keys_config: &mut KeysConfig) -> Result<Self, Vec<KeyConflict>> {
+        let defaults = KeysConfig::from_preset(keys_config.preset);
+
         macro_rules! build_map {
             ($($path:tt).+, $conflicts:expr, [
                 $(($field:ident, $command:expr)),* $(,)?
@@ -115,6 +183,56 @@ impl KeyMap {
                 )*
                 map
             }};
+            ($($path:tt).+, $conflicts:expr, $bound_elsewhere:expr, [
+                $(($field:ident, $command:expr)),* $(,)?
+            ], give_way: [
+                $(($new_field:ident, $new_command:expr)),* $(,)?
+            ]) => {{
+                let context = stringify!($($path).+);
+                let config = &mut keys_config.$($path).+;
+                let defaults = &defaults.$($path).+;
+                let mut map = HashMap::new();
+                $(
+                    for key in &config.$field {
+                        Self::insert_and_detect(&mut map, *key, $command, context, $conflicts);
+                    }
+                )*
+                $(
+                    if config.$new_field != defaults.$new_field {
+                        for key in &config.$new_field {
+                            Self::insert_and_detect(
+                                &mut map, *key, $new_command, context, $conflicts,
+                            );
+                        }
+                    }
+                )*
+                // Filter every default before inserting any, so that defaults still conflict with
+                // each other
+                let bound_elsewhere: Option<&HashMap<KeyEvent, CommandSearchFields>> =
+                    $bound_elsewhere;
+                let mut default_bindings = Vec::new();
+                $(
+                    if config.$new_field == defaults.$new_field {
+                        config.$new_field = Keys::new(
+                            config
+                                .$new_field
+                                .iter()
+                                .copied()
+                                .filter(|key| {
+                                    !map.contains_key(key)
+                                        && !bound_elsewhere.is_some_and(|m| m.contains_key(key))
+                                })
+                                .collect(),
+                        );
+                        default_bindings
+                            .extend(config.$new_field.iter().map(|key| (*key, $new_command)));
+                    }
+                )*
+                for (key, command) in default_bindings {
+                    Self::insert_and_detect(&mut map, key, command, context, $conflicts);
+                }
+                map
+            }};
         }
 
         let mut conflicts = Vec::new();
@@ -122,111 +240,153 @@ impl KeyMap {
         let general = build_map!(
             general,
             &mut conflicts,
+            None,
             [
                 (quit, CommandGeneral::Quit),
                 (reset, CommandGeneral::Reset),
                 (show_help_menu, CommandGeneral::ShowHelpMenu),
+            ],
+            give_way: [
+                (show_command_palette, CommandGeneral::ShowCommandPalette),
+                (edit_config, CommandGeneral::EditConfig),
             ]
         );
 
-        let search_common = build_map!(
-            search,
+        let file_picker = build_map!(
+            file_picker,
             &mut conflicts,
             [
+                (confirm, CommandFilePicker::Confirm),
+                (move_down, CommandFilePicker::MoveDown),
+                (move_up, CommandFilePicker::MoveUp),
+                (move_top, CommandFilePicker::MoveTop),
+                (move_bottom, CommandFilePicker::MoveBottom),
                 (
-                    toggle_preview_wrapping,
-                    CommandSearchFields::TogglePreviewWrapping
+                    toggle_selected_inclusion,
+                    CommandFilePicker::ToggleSelectedInclusion
                 ),
+                (toggle_all_selected, CommandFilePicker::ToggleAllSelected),
+            ]
+        );
+
+        let search_common = build_map!(
+            search,
+            &mut conflicts,
+            None,
+            [
+                (toggle_preview_wrapping, CommandSearchFields::TogglePreviewWrapping),
                 (toggle_hidden_files, CommandSearchFields::ToggleHiddenFiles),
                 (toggle_multiline, CommandSearchFields::ToggleMultiline),
                 (
                     toggle_interpret_escape_sequences,
                     CommandSearchFields::ToggleInterpretEscapeSequences
                 ),
+            ],
+            give_way: [
+                (toggle_preview, CommandSearchFields::TogglePreview),
+                (toggle_advanced_regex, CommandSearchFields::ToggleAdvancedRegex),
+                (toggle_nearby_replacements, CommandSearchFields::ToggleNearbyReplacements),
+                (toggle_pause_search, CommandSearchFields::TogglePauseSearch),
+                (show_headless_command, CommandSearchFields::ShowHeadlessCommand),
+                (focus_include_files, CommandSearchFields::FocusIncludeFiles),
+                (focus_exclude_files, CommandSearchFields::FocusExcludeFiles),
             ]
         );
 
         let search_fields = build_map!(
             search.fields,
             &mut conflicts,
+            Some(&search_common),
             [
-                (
-                    unlock_prepopulated_fields,
-                    CommandSearchFocusFields::UnlockPrepopulatedFields
-                ),
+                (unlock_prepopulated_fields, CommandSearchFocusFields::UnlockPrepopulatedFields),
                 (trigger_search, CommandSearchFocusFields::TriggerSearch),
                 (focus_next_field, CommandSearchFocusFields::FocusNextField),
-                (
-                    focus_previous_field,
-                    CommandSearchFocusFields::FocusPreviousField
-                ),
+                (focus_previous_field, CommandSearchFocusFields::FocusPreviousField),
+            ],
+            give_way: [
+                (focus_results, CommandSearchFocusFields::FocusResults),
+                (save_preset, CommandSearchFocusFields::SavePreset),
+                (clear_fields, CommandSearchFocusFields::ClearFields),
             ]
         );
 
         let search_results = build_map!(
             search.results,
             &mut conflicts,
+            Some(&search_common),
             [
-                (
-                    trigger_replacement,
-                    CommandSearchFocusResults::TriggerReplacement
-                ),
+                (trigger_replacement, CommandSearchFocusResults::TriggerReplacement),
                 (back_to_fields, CommandSearchFocusResults::BackToFields),
                 (open_in_editor, CommandSearchFocusResults::OpenInEditor),
                 (move_down, CommandSearchFocusResults::MoveDown),
                 (move_up, CommandSearchFocusResults::MoveUp),
-                (
-                    move_down_half_page,
-                    CommandSearchFocusResults::MoveDownHalfPage
-                ),
-                (
-                    move_down_full_page,
-                    CommandSearchFocusResults::MoveDownFullPage
-                ),
+                (move_down_half_page, CommandSearchFocusResults::MoveDownHalfPage),
+                (move_down_full_page, CommandSearchFocusResults::MoveDownFullPage),
                 (move_up_half_page, CommandSearchFocusResults::MoveUpHalfPage),
                 (move_up_full_page, CommandSearchFocusResults::MoveUpFullPage),
                 (move_top, CommandSearchFocusResults::MoveTop),
                 (move_bottom, CommandSearchFocusResults::MoveBottom),
-                (
-                    toggle_selected_inclusion,
-                    CommandSearchFocusResults::ToggleSelectedInclusion
-                ),
-                (
-                    toggle_all_selected,
-                    CommandSearchFocusResults::ToggleAllSelected
-                ),
-                (
-                    toggle_multiselect_mode,
-                    CommandSearchFocusResults::ToggleMultiselectMode
-                ),
-                (
-                    flip_multiselect_direction,
-                    CommandSearchFocusResults::FlipMultiselectDirection
-                ),
+                (toggle_selected_inclusion, CommandSearchFocusResults::ToggleSelectedInclusion),
+                (toggle_all_selected, CommandSearchFocusResults::ToggleAllSelected),
+                (toggle_multiselect_mode, CommandSearchFocusResults::ToggleMultiselectMode),
+                (flip_multiselect_direction, CommandSearchFocusResults::FlipMultiselectDirection),
+            ],
+            give_way: [
+                (copy_matched_text, CommandSearchFocusResults::CopyMatchedText),
+                (copy_replacement, CommandSearchFocusResults::CopyReplacement),
+                (copy_results_as_grep, CommandSearchFocusResults::CopyResultsAsGrep),
+                (expand_file, CommandSearchFocusResults::ExpandFile),
+                (refresh_search, CommandSearchFocusResults::RefreshSearch),
+                (scroll_preview_down, CommandSearchFocusResults::ScrollPreviewDown),
+                (scroll_preview_up, CommandSearchFocusResults::ScrollPreviewUp),
+                (toggle_file_inclusion, CommandSearchFocusResults::ToggleFileInclusion),
+                (cycle_path_style, CommandSearchFocusResults::CyclePathStyle),
+                (select_range, CommandSearchFocusResults::SelectRange),
+                (show_match_summary, CommandSearchFocusResults::ShowMatchSummary),
+                (toggle_fixed_strings, CommandSearchFocusResults::ToggleFixedStrings),
+                (toggle_match_case, CommandSearchFocusResults::ToggleMatchCase),
+                (toggle_whole_word, CommandSearchFocusResults::ToggleWholeWord),
+                (find_in_results, CommandSearchFocusResults::FindInResults),
+                (find_next, CommandSearchFocusResults::FindNext),
+                (find_previous, CommandSearchFocusResults::FindPrevious),
             ]
         );
 
         let results = build_map!(
             results,
             &mut conflicts,
+            None,
             [
                 (scroll_errors_down, CommandResults::ScrollErrorsDown),
                 (scroll_errors_up, CommandResults::ScrollErrorsUp),
                 (quit, CommandResults::Quit),
+            ],
+            give_way: [
+                (show_error_details, CommandResults::ShowErrorDetails),
+                (retry_failed, CommandResults::RetryFailed),
             ]
         );
 
         #[allow(clippy::zero_sized_map_values)]
         let performing_replacement = HashMap::new();
 
+        // All of these maps are checked before falling back to text input when the search fields
+        // are focussed
+        let mut text_input_shadows = Vec::new();
+        Self::detect_text_input_shadows(&general, "general", &mut text_input_shadows);
+        Self::detect_text_input_shadows(&search_common, "search", &mut text_input_shadows);
+        Self::detect_text_input_shadows(&search_fields, "search.fields", &mut text_input_shadows);
+
         if conflicts.is_empty() {
             Ok(Self {
                 general,
+                file_picker,
                 search_fields,
                 search_results,
                 search_common,
                 performing_replacement,
                 results,
+                text_input_shadows,
             })
         } else {
             Err(conflicts)
@@ -242,24 +402,6 @@ impl KeyMap {
         conflicts: &mut Vec<KeyConflict>,
     ) {
         if let Some(existing) = map.insert(key, command) {
-            // Convert snake_case Debug names to human-readable format
-            let format_command = |cmd: &T| -> String {
-                let debug_str = format!("{cmd:?}");
-                // Convert PascalCase to snake_case
-                debug_str
-                    .chars()
-                    .enumerate()
-                    .flat_map(|(i, c)| {
-                        if i > 0 && c.is_uppercase() {
-                            vec!['_', c]
-                        } else {
-                            vec![c]
-                        }
-                    })
-                    .collect::<String>()
-                    .to_lowercase()
-            };
-
             conflicts.push(KeyConflict {
                 key,
                 context: context.to_string(),
@@ -271,10 +413,37 @@ impl KeyMap {
         }
     }
 
+    /// Record any bindings in `map` that would be typed as text if they weren't bound
+    fn detect_text_input_shadows<T: std::fmt::Debug>(
+        map: &HashMap<KeyEvent, T>,
+        context: &str,
+        shadows: &mut Vec<TextInputShadow>,
+    ) {
+        let mut found: Vec<_> = map
+            .iter()
+            .filter(|(key, _)| {
+                matches!(key.code, KeyCode::Char(_))
+                    && (key.modifiers - KeyModifiers::SHIFT).is_empty()
+            })
+            .map(|(key, command)| TextInputShadow {
+                key: *key,
+                context: context.to_string(),
+                command: format_command(command),
+            })
+            .collect();
+        // Sort for a stable order, as `HashMap` iteration order is arbitrary
+        found.sort_by_key(|shadow| shadow.key.to_string());
+        shadows.extend(found);
+    }
+
     /// Look up a command for the given key event and screen context
     pub(crate) fn lookup(&self, screen: &Screen, key_event: KeyEvent) -> Option<Command> {
         // Check screen-specific commands
         if let Some(cmd) = match screen {
+            Screen::FilePicker(_) => self
+                .file_picker
+                .get(&key_event)
+                .map(|cmd| Command::FilePicker(*cmd)),
             Screen::SearchFields(state) => {
                 // Check common SearchFields commands first
                 if let Some(cmd) = self.search_common.get(&key_event) {
@@ -314,6 +483,22 @@ impl KeyMap {
     }
 }
 
+/// Convert `PascalCase` Debug names of commands to `snake_case`, matching the config
+fn format_command<T: std::fmt::Debug>(cmd: &T) -> String {
+    format!("{cmd:?}")
+        .chars()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            if i > 0 && c.is_uppercase() {
+                vec!['_', c]
+            } else {
+                vec![c]
+            }
+        })
+        .collect::<String>()
+        .to_lowercase()
+}
+
 pub(crate) fn display_conflict_errors(conflicts: Vec<KeyConflict>) -> anyhow::Error {
     use std::fmt::Write;
 
@@ -332,3 +517,23 @@ pub(crate) fn display_conflict_errors(conflicts: Vec<KeyConflict>) -> anyhow::Er
     }
     anyhow::anyhow!(error_msg)
 }
+
+pub(crate) fn display_text_input_shadows(shadows: &[TextInputShadow]) -> String {
+    use std::fmt::Write;
+
+    let mut msg = String::from(
+        "The following keys are bound to commands while the search fields are focussed, so can't be typed into them:\n\n",
+    );
+    for shadow in shadows {
+        writeln!(
+            &mut msg,
+            "  '{}' in [keys.{}] ({})",
+            shadow.key, shadow.context, shadow.command
+        )
+        .unwrap();
+    }
+    msg.push_str(
+        "\nConsider adding a modifier, e.g. 'A-' or 'C-', to these bindings in your config.",
+    );
+    msg
+}
@@ -1,13 +1,21 @@
-use anyhow::anyhow;
+use anyhow::{Context, anyhow, bail};
 use etcetera::base_strategy::{BaseStrategy, choose_base_strategy};
-use serde::{Deserialize, Deserializer, de};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 use std::{
-    fs,
+    borrow::Cow,
+    fs, mem,
     path::{Path, PathBuf},
     sync::OnceLock,
+    time::Duration,
 };
 use two_face::re_exports::syntect::highlighting::{Theme, ThemeSet};
 
+use crate::{
+    fields::FieldName,
+    search::{BinaryDetection, DEFAULT_BINARY_SAMPLE_SIZE},
+    utils,
+};
+
 mod keys;
 pub use keys::*;
 
@@ -33,6 +41,28 @@ pub fn set_config_dir_override(dir: &Path) {
         .expect("Config dir override should only be set once");
 }
 
+static CONFIG_PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Selects the named profile from the config file, taking precedence over its `default_profile`
+pub fn set_config_profile_override(name: &str) {
+    CONFIG_PROFILE_OVERRIDE
+        .set(name.to_owned())
+        .expect("Config profile override should only be set once");
+}
+
+static LOCAL_CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Layers any local config files found in `dir` or its ancestors over the global config, so that
+/// projects can set their own options
+pub fn set_local_config_dir(dir: &Path) {
+    LOCAL_CONFIG_DIR
+        .set(dir.to_path_buf())
+        .expect("Local config dir should only be set once");
+}
+
+/// The name of the project-local config files that are layered over the global config
+pub const LOCAL_CONFIG_FILE_NAME: &str = ".scooter.toml";
+
 fn config_dir() -> PathBuf {
     if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
         return dir.clone();
@@ -41,15 +71,43 @@ fn config_dir() -> PathBuf {
     strategy.config_dir().join(APP_NAME)
 }
 
-fn config_file() -> PathBuf {
+pub fn config_file() -> PathBuf {
     config_dir().join("config.toml")
 }
 
+/// The default config, with every option documented and commented out. Generated from the config structs by
+/// `cargo xtask default-config`
+const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// Returns the path of the global config file, first creating it with every option documented and
+/// commented out, set to its default value, if it doesn't exist
+pub fn create_config_file_if_missing() -> anyhow::Result<PathBuf> {
+    let path = config_file();
+    write_default_config_if_missing(&path)?;
+    Ok(path)
+}
+
+fn write_default_config_if_missing(path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory {}", parent.display()))?;
+    }
+    fs::write(path, DEFAULT_CONFIG)
+        .with_context(|| format!("Failed to create config file {}", path.display()))
+}
+
+pub fn presets_file() -> PathBuf {
+    config_dir().join("presets.toml")
+}
+
 fn themes_folder() -> PathBuf {
     config_dir().join("themes/")
 }
 
-#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     #[serde(default)]
@@ -57,14 +115,26 @@ pub struct Config {
     #[serde(default)]
     pub preview: PreviewConfig,
     #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
     pub style: StyleConfig,
     #[serde(default)]
+    pub colors: ColorsConfig,
+    #[serde(default)]
     pub search: SearchConfig,
     #[serde(default)]
     pub keys: KeysConfig,
 }
 
 impl Config {
+    /// The config formatted as TOML, including the values of any options that weren't set, so that
+    /// the effective config can be inspected with `--print-config`
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
     /// Returns `None` if the user wants syntax highlighting disabled, otherwise `Some(theme)` where `theme`
     /// is the user's selected theme or otherwise the default
     pub fn get_theme(&self) -> Option<&Theme> {
@@ -77,17 +147,192 @@ impl Config {
 }
 
 pub fn load_config() -> anyhow::Result<Config> {
-    let config_file = &config_file();
+    load_layered_config(
+        &config_file(),
+        LOCAL_CONFIG_DIR.get().map(PathBuf::as_path),
+        CONFIG_PROFILE_OVERRIDE.get().map(String::as_str),
+    )
+}
+
+/// Loads the global config file, and then layers over it any local config files in `local_config_dir`
+/// and its ancestors, with files in nearer directories taking precedence. The selected profile, which
+/// may be defined in any of the files, is then applied on top
+fn load_layered_config(
+    config_file: &Path,
+    local_config_dir: Option<&Path>,
+    profile: Option<&str>,
+) -> anyhow::Result<Config> {
+    let mut files = local_config_dir.map(local_config_files).unwrap_or_default();
     if fs::exists(config_file)? {
-        let contents = fs::read_to_string(config_file)?;
-        let config = toml::from_str(&contents)?;
-        Ok(config)
-    } else {
-        Ok(Config::default())
+        files.insert(0, config_file.to_path_buf());
+    }
+    if files.is_empty() {
+        if let Some(profile) = profile {
+            bail!("Unknown config profile \"{profile}\": no config file found")
+        }
+        return Ok(Config::default());
+    }
+
+    let mut table = toml::Table::new();
+    let mut local_default_profile_file = None;
+    for file in files {
+        let contents = fs::read_to_string(&file)?;
+        let layer: toml::Table = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", file.display()))?;
+        if file != config_file {
+            check_local_layer(&layer, &file)?;
+            if layer.contains_key(DEFAULT_PROFILE_KEY) {
+                local_default_profile_file = Some(file.clone());
+            }
+        }
+        merge_tables(&mut table, layer);
+    }
+
+    // A profile chosen with `profile` is trusted, but one chosen by a local config file must not run
+    // commands from the global config that the user hasn't opted into
+    if profile.is_none()
+        && let Some(file) = local_default_profile_file
+        && let Some(toml::Value::String(name)) = table.get(DEFAULT_PROFILE_KEY)
+        && table
+            .get(PROFILES_KEY)
+            .and_then(|profiles| profiles.get(name))
+            .is_some_and(|profile| profile.get(EDITOR_OPEN_KEY).is_some())
+    {
+        bail!(
+            "`{DEFAULT_PROFILE_KEY}` in {} can't select profile \"{name}\", as it sets `{EDITOR_OPEN_KEY}`",
+            file.display()
+        );
     }
+    config_from_table(table, profile)
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+/// Local config files may come from untrusted projects, so mustn't be able to run commands, either
+/// directly or from a profile
+fn check_local_layer(layer: &toml::Table, file: &Path) -> anyhow::Result<()> {
+    if layer.contains_key(EDITOR_OPEN_KEY) {
+        bail!(
+            "`{EDITOR_OPEN_KEY}` can only be set in the global config, not in {}",
+            file.display()
+        );
+    }
+    if let Some(toml::Value::Table(profiles)) = layer.get(PROFILES_KEY) {
+        for (name, profile) in profiles {
+            if profile.get(EDITOR_OPEN_KEY).is_some() {
+                bail!(
+                    "`{EDITOR_OPEN_KEY}` can only be set in the global config, not in profile \"{name}\" of {}",
+                    file.display()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The directory to look for local config files from when searching `directories`: the nearest
+/// ancestor that they all share, so that the same config is used whatever order the directories are
+/// given in. A local config file within only some of the directories is therefore not used. `None` if
+/// the directories have no common ancestor, e.g. if they are on different drives
+pub fn local_config_dir(directories: &[PathBuf]) -> Option<PathBuf> {
+    let mut directories = directories
+        .iter()
+        .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.clone()));
+    let first = directories.next()?;
+    directories.try_fold(first, |common, dir| {
+        common
+            .ancestors()
+            .find(|ancestor| dir.starts_with(ancestor))
+            .map(Path::to_path_buf)
+    })
+}
+
+/// The local config files in `dir` and its ancestors, outermost first
+fn local_config_files(dir: &Path) -> Vec<PathBuf> {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let mut files = dir
+        .ancestors()
+        .map(|dir| dir.join(LOCAL_CONFIG_FILE_NAME))
+        .filter(|file| file.is_file())
+        .collect::<Vec<_>>();
+    files.reverse();
+    files
+}
+
+const DEFAULT_PROFILE_KEY: &str = "default_profile";
+const PROFILES_KEY: &str = "profiles";
+const EDITOR_OPEN_KEY: &str = "editor_open";
+const KEYS_KEY: &str = "keys";
+const KEYS_PRESET_KEY: &str = "preset";
+
+/// Converts the parsed contents of the config files into a config. Any `[profiles.<name>]` tables hold
+/// overrides for the rest of the config, and the profile named by `profile` (or otherwise by
+/// `default_profile`, if set) is applied on top of the top-level options
+fn config_from_table(mut table: toml::Table, profile: Option<&str>) -> anyhow::Result<Config> {
+    let default_profile = match table.remove(DEFAULT_PROFILE_KEY) {
+        Some(toml::Value::String(name)) => Some(name),
+        Some(_) => bail!("`{DEFAULT_PROFILE_KEY}` must be the name of a profile"),
+        None => None,
+    };
+    let mut profiles = match table.remove(PROFILES_KEY) {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => bail!("`{PROFILES_KEY}` must be a table of named profiles"),
+        None => toml::Table::new(),
+    };
+
+    if let Some(name) = profile.or(default_profile.as_deref()) {
+        let Some(overrides) = profiles.remove(name) else {
+            let available = profiles
+                .keys()
+                .map(|name| format!("\"{name}\""))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if available.is_empty() {
+                bail!("Unknown config profile \"{name}\": no profiles are defined");
+            }
+            bail!("Unknown config profile \"{name}\", expected one of: {available}");
+        };
+        let toml::Value::Table(overrides) = overrides else {
+            bail!("Config profile \"{name}\" must be a table");
+        };
+        merge_tables(&mut table, overrides);
+    }
+    if let Some(toml::Value::Table(keys)) = table.get_mut(KEYS_KEY) {
+        apply_keys_preset(keys)?;
+    }
+
+    Ok(table.try_into()?)
+}
+
+/// Replaces `keys` with the keys of the preset it selects, if any, with the keys it sets applied on top
+fn apply_keys_preset(keys: &mut toml::Table) -> anyhow::Result<()> {
+    let Some(preset) = keys.get(KEYS_PRESET_KEY) else {
+        return Ok(());
+    };
+    let preset: KeysPreset = preset
+        .clone()
+        .try_into()
+        .with_context(|| format!("Invalid `{KEYS_KEY}.{KEYS_PRESET_KEY}`"))?;
+    let mut preset_keys = toml::Table::try_from(KeysConfig::from_preset(preset))?;
+    merge_tables(&mut preset_keys, mem::take(keys));
+    *keys = preset_keys;
+    Ok(())
+}
+
+/// Recursively merges `overrides` into `base`, so that a profile only needs to set the options it
+/// changes
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_tables(base, overrides);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, default)]
 #[derive(Default)]
 pub struct EditorOpenConfig {
@@ -105,8 +350,9 @@ pub struct EditorOpenConfig {
     pub exit: bool,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct PreviewConfig {
     /// Whether to apply syntax highlighting to the preview. Defaults to `true`.
     pub syntax_highlighting: bool,
@@ -123,10 +369,43 @@ pub struct PreviewConfig {
     /// wget -P ~/.config/scooter/themes https://github.com/catppuccin/bat/raw/main/themes/Catppuccin%20Macchiato.tmTheme
     /// ```
     /// and then set `syntax_highlighting_theme = "Catppuccin Macchiato"`.
-    #[serde(deserialize_with = "deserialize_syntax_highlighting_theme")]
+    #[serde(
+        deserialize_with = "deserialize_syntax_highlighting_theme",
+        serialize_with = "serialize_syntax_highlighting_theme"
+    )]
     pub syntax_highlighting_theme: Theme,
     /// Wrap text onto the next line if it is wider than the preview window. Defaults to `false`. (Can be toggled in the UI using `ctrl+l`.)
     pub wrap_text: bool,
+    /// Show the lines surrounding the selected result with the replacements from other selected results in the same file applied,
+    /// rather than showing the original file content. Defaults to `false`. (Can be toggled in the UI using `alt+p`.)
+    pub show_nearby_replacements: bool,
+    /// Whether to show the line number of each line in the preview, to the left of the line. Lines added by a replacement
+    /// have no line number, as they aren't in the file yet. Defaults to `false`.
+    pub line_numbers: bool,
+    /// The text shown before each unchanged line in the preview, in place of the `-` and `+` markers of removed and added
+    /// lines. Can't contain control characters such as newlines. Defaults to `"  "`.
+    #[serde(deserialize_with = "deserialize_preview_prefix")]
+    pub line_prefix: String,
+    /// The text shown at the start of each continuation line when `wrap_text` is enabled. Can't contain control characters
+    /// such as newlines. Defaults to `"  ↪ "`.
+    #[serde(deserialize_with = "deserialize_preview_prefix")]
+    pub wrapped_prefix: String,
+    /// How the lines of the selected result are shown in the preview. One of `"stacked"` (the original lines, followed by the
+    /// lines with the replacement made) or `"side_by_side"` (the original lines on the left, aligned with the replaced lines on
+    /// the right). Side-by-side falls back to stacked when the preview is less than 80 columns wide, and cuts off lines that
+    /// don't fit in their half of the preview. Defaults to `"stacked"`.
+    pub layout: PreviewLayout,
+    /// How control characters, such as terminal escape codes, are shown in the preview. One of `"strip"` (replace them with `�`),
+    /// `"escape"` (show them in caret notation, e.g. `^[` for escape, so that you can see exactly what is in the file) or `"raw"`
+    /// (pass them to the terminal unchanged, which may garble the display). Defaults to `"strip"`.
+    pub control_chars: ControlChars,
+    /// The maximum number of files, or sections of files, whose syntax highlighting is cached to speed up the preview
+    /// when moving between results. Must be at least `1`. Defaults to `200`.
+    #[serde(deserialize_with = "deserialize_highlight_cache_entries")]
+    pub highlight_cache_entries: usize,
+    /// The maximum total number of lines across all cached syntax highlighting. Once exceeded, the least recently used
+    /// entries are dropped, although the most recent entry is always kept. Defaults to `500000`.
+    pub highlight_cache_lines: usize,
 }
 
 impl Default for PreviewConfig {
@@ -135,6 +414,71 @@ impl Default for PreviewConfig {
             syntax_highlighting: true,
             syntax_highlighting_theme: load_theme("base16-eighties.dark").unwrap(),
             wrap_text: false,
+            show_nearby_replacements: false,
+            line_numbers: false,
+            line_prefix: "  ".to_owned(),
+            wrapped_prefix: "  ↪ ".to_owned(),
+            layout: PreviewLayout::default(),
+            control_chars: ControlChars::default(),
+            highlight_cache_entries: DEFAULT_HIGHLIGHT_CACHE_ENTRIES,
+            highlight_cache_lines: DEFAULT_HIGHLIGHT_CACHE_LINES,
+        }
+    }
+}
+
+const DEFAULT_HIGHLIGHT_CACHE_ENTRIES: usize = 200;
+const DEFAULT_HIGHLIGHT_CACHE_LINES: usize = 500_000;
+
+fn deserialize_highlight_cache_entries<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries = usize::deserialize(deserializer)?;
+    if entries == 0 {
+        return Err(de::Error::custom(
+            "highlight_cache_entries must be at least 1",
+        ));
+    }
+    Ok(entries)
+}
+
+fn deserialize_preview_prefix<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let prefix = String::deserialize(deserializer)?;
+    if prefix.chars().any(char::is_control) {
+        return Err(de::Error::custom(
+            "preview prefixes can't contain control characters",
+        ));
+    }
+    Ok(prefix)
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewLayout {
+    #[default]
+    Stacked,
+    SideBySide,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlChars {
+    #[default]
+    Strip,
+    Escape,
+    Raw,
+}
+
+impl ControlChars {
+    /// Prepares `text` for display in the preview
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Strip => utils::strip_control_chars(text),
+            Self::Escape => utils::escape_control_chars(text),
+            Self::Raw => Cow::Borrowed(text),
         }
     }
 }
@@ -158,7 +502,204 @@ where
     load_theme(&theme_name).map_err(de::Error::custom)
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+/// Serializes a theme as the name it is loaded by, i.e. the reverse of
+/// [`deserialize_syntax_highlighting_theme`]
+fn serialize_syntax_highlighting_theme<S>(theme: &Theme, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let theme_name = get_theme_set()
+        .themes
+        .iter()
+        .find_map(|(name, loaded)| (loaded == theme).then_some(name.as_str()))
+        .or(theme.name.as_deref())
+        .unwrap_or_default();
+    serializer.serialize_str(theme_name)
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct DisplayConfig {
+    /// How file paths are displayed in the search results list. One of `"relative"` (relative to the directory being searched),
+    /// `"absolute"` or `"filename"` (the file name only). Defaults to `"relative"`. (Can be cycled in the UI using `p` when
+    /// the search results are focussed.)
+    pub path_style: PathStyle,
+    /// Which part of a file path is cut off, and replaced with `…`, when it is too long to fit in the search results list.
+    /// One of `"start"`, which keeps the end of the path visible, `"end"`, which keeps the start visible, or `"middle"`,
+    /// which keeps both the start and end visible. Defaults to `"start"`.
+    pub truncate: Truncate,
+    /// The relative widths of the search results list and the preview, written as `"<list>:<preview>"`. Defaults to `"2:3"`.
+    /// Set the preview part to `0`, e.g. `"1:0"`, to hide the preview entirely.
+    pub list_preview_ratio: SplitRatio,
+    /// The relative heights of the search results list and the preview on narrow terminals, where the preview is shown
+    /// below the list, written as `"<list>:<preview>"`. If not set, the list is 5 lines tall and the preview takes up the remaining space.
+    pub vertical_list_preview_ratio: Option<SplitRatio>,
+    /// Whether to show the preview of the selected search result. Defaults to `true`. (Can be toggled in the UI using `alt+v`.)
+    pub show_preview: bool,
+    /// Hide the preview when the terminal is narrower than this many columns, so that the search results list can use all of
+    /// the available space. Defaults to `0`, meaning that the preview is never hidden automatically.
+    pub min_preview_width: u16,
+    /// Where the search fields are shown relative to the search results. One of `"top"` or `"bottom"`, where `"bottom"`
+    /// shows the results above the fields, which can be useful on tall terminals. Defaults to `"top"`.
+    pub fields_position: FieldsPosition,
+    /// Whether to respond to the mouse: clicking a search result selects it, clicking its checkbox toggles whether
+    /// it is included, and scrolling moves the selection. While enabled, the terminal can't be used to select and
+    /// copy text with the mouse. Defaults to `false`.
+    pub mouse: bool,
+    /// The maximum number of search results shown from a single file, so that a file with many matches doesn't dominate
+    /// the results list. The number of further results in the file is shown after its last result, and only the results
+    /// shown are replaced, unless the file is expanded with `X` to show all of its results. Must be at least `1`. If not set,
+    /// all results are shown.
+    #[serde(deserialize_with = "deserialize_max_matches_per_file")]
+    pub max_matches_per_file: Option<usize>,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            path_style: PathStyle::default(),
+            truncate: Truncate::default(),
+            list_preview_ratio: SplitRatio {
+                list: 2,
+                preview: 3,
+            },
+            vertical_list_preview_ratio: None,
+            show_preview: true,
+            min_preview_width: 0,
+            fields_position: FieldsPosition::default(),
+            mouse: false,
+            max_matches_per_file: None,
+        }
+    }
+}
+
+fn deserialize_max_matches_per_file<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let max = usize::deserialize(deserializer)?;
+    if max == 0 {
+        return Err(de::Error::custom("max_matches_per_file must be at least 1"));
+    }
+    Ok(Some(max))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct UiConfig {
+    /// The minimum number of milliseconds between redraws while searching or performing replacements. Lower values give
+    /// smoother updates, while higher values reduce the number of redraws, which can help on slow or remote terminals.
+    /// Must be at least `1`. Defaults to `92`.
+    #[serde(deserialize_with = "deserialize_render_interval_ms")]
+    pub render_interval_ms: u64,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            // Slightly random duration so that time taken isn't a round number
+            render_interval_ms: 92,
+        }
+    }
+}
+
+impl UiConfig {
+    pub fn render_interval(&self) -> Duration {
+        Duration::from_millis(self.render_interval_ms)
+    }
+}
+
+fn deserialize_render_interval_ms<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let interval = u64::deserialize(deserializer)?;
+    if interval == 0 {
+        return Err(de::Error::custom("render_interval_ms must be at least 1"));
+    }
+    Ok(interval)
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldsPosition {
+    #[default]
+    Top,
+    Bottom,
+}
+
+/// The relative sizes of the search results list and the preview
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct SplitRatio {
+    pub list: u16,
+    pub preview: u16,
+}
+
+impl From<SplitRatio> for String {
+    fn from(ratio: SplitRatio) -> Self {
+        format!("{}:{}", ratio.list, ratio.preview)
+    }
+}
+
+impl TryFrom<String> for SplitRatio {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        let parse_part = |part: Option<&str>| part.and_then(|p| p.trim().parse::<u16>().ok());
+        let mut parts = value.split(':');
+        match (
+            parse_part(parts.next()),
+            parse_part(parts.next()),
+            parts.next(),
+        ) {
+            (Some(list), Some(preview), None) if list > 0 => Ok(Self { list, preview }),
+            _ => Err(anyhow!(
+                "invalid ratio \"{value}\": expected two whole numbers separated by a colon, e.g. \"2:3\", with a non-zero list part"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PathStyle {
+    #[default]
+    Relative,
+    Absolute,
+    Filename,
+}
+
+impl PathStyle {
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Relative => Self::Absolute,
+            Self::Absolute => Self::Filename,
+            Self::Filename => Self::Relative,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Absolute => "absolute",
+            Self::Filename => "filename",
+        }
+    }
+}
+
+/// Which part of text that is too long to display is cut off
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Truncate {
+    #[default]
+    Start,
+    End,
+    Middle,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, default)]
 pub struct StyleConfig {
     /// Force enable or disable true color. `true` forces true color (supported by most modern terminals but not e.g. Apple Terminal), while `false` forces 256 colors (supported by almost all terminals including Apple Terminal).
@@ -174,6 +715,128 @@ impl Default for StyleConfig {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct ColorsConfig {
+    /// The color of removed lines in the preview, used for the `-` marker and behind the removed text. Colors can be one of the
+    /// terminal's named colors, e.g. `"red"`, `"light-blue"` or `"dark-gray"`, or a hex code such as `"#ff5f5f"`. Defaults to `"red"`.
+    pub diff_old: Colour,
+    /// The color of added lines in the preview, used for the `+` marker and behind the added text. Defaults to `"green"`.
+    pub diff_new: Colour,
+    /// The color of the text that has changed within a line, which is shown on a background of `diff_old` or `diff_new`. Defaults to `"black"`.
+    pub diff_highlight: Colour,
+    /// The color of the text within removed and added lines that is unaffected by the replacement, so that only the changed text is
+    /// shown in `diff_old` and `diff_new`. Defaults to the terminal's default text color.
+    pub diff_unchanged: Option<Colour>,
+}
+
+impl Default for ColorsConfig {
+    fn default() -> Self {
+        Self {
+            diff_old: Colour::Red,
+            diff_new: Colour::Green,
+            diff_highlight: Colour::Black,
+            diff_unchanged: None,
+        }
+    }
+}
+
+/// A colour set in the config file, either one of the 16 named terminal colours or an RGB hex code
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Colour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+    Rgb(u8, u8, u8),
+}
+
+impl TryFrom<String> for Colour {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        if let Some(hex) = value.strip_prefix('#') {
+            let channel = |idx: usize| {
+                hex.get(idx..idx + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Self::Rgb(r, g, b)),
+                _ => Err(anyhow!(
+                    "invalid color \"{value}\": expected a hex code in the form \"#rrggbb\""
+                )),
+            };
+        }
+
+        let name = value
+            .to_lowercase()
+            .replace(['-', '_', ' '], "")
+            .replace("grey", "gray");
+        let colour = match name.as_str() {
+            "black" => Self::Black,
+            "red" => Self::Red,
+            "green" => Self::Green,
+            "yellow" => Self::Yellow,
+            "blue" => Self::Blue,
+            "magenta" => Self::Magenta,
+            "cyan" => Self::Cyan,
+            "gray" => Self::Gray,
+            "darkgray" => Self::DarkGray,
+            "lightred" => Self::LightRed,
+            "lightgreen" => Self::LightGreen,
+            "lightyellow" => Self::LightYellow,
+            "lightblue" => Self::LightBlue,
+            "lightmagenta" => Self::LightMagenta,
+            "lightcyan" => Self::LightCyan,
+            "white" => Self::White,
+            _ => {
+                return Err(anyhow!(
+                    "invalid color \"{value}\": expected a color name such as \"red\" or \"light-blue\", or a hex code such as \"#ff5f5f\""
+                ));
+            }
+        };
+        Ok(colour)
+    }
+}
+
+impl From<Colour> for String {
+    fn from(colour: Colour) -> Self {
+        let name = match colour {
+            Colour::Black => "black",
+            Colour::Red => "red",
+            Colour::Green => "green",
+            Colour::Yellow => "yellow",
+            Colour::Blue => "blue",
+            Colour::Magenta => "magenta",
+            Colour::Cyan => "cyan",
+            Colour::Gray => "gray",
+            Colour::DarkGray => "dark-gray",
+            Colour::LightRed => "light-red",
+            Colour::LightGreen => "light-green",
+            Colour::LightYellow => "light-yellow",
+            Colour::LightBlue => "light-blue",
+            Colour::LightMagenta => "light-magenta",
+            Colour::LightCyan => "light-cyan",
+            Colour::White => "white",
+            Colour::Rgb(r, g, b) => return format!("#{r:02x}{g:02x}{b:02x}"),
+        };
+        name.to_owned()
+    }
+}
+
 #[cfg(windows)]
 fn detect_true_colour() -> bool {
     true
@@ -199,14 +862,40 @@ fn detect_true_colour() -> bool {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, default)]
 pub struct SearchConfig {
     /// Whether to disable fields set by CLI flags. Set to `false` to allow editing of these pre-populated fields. Defaults to `true`.
     pub disable_prepopulated_fields: bool,
     /// Whether to interpret escape sequences in replacement text. When enabled, `\n` becomes a newline,
-    /// `\t` becomes a tab, and `\\` becomes a literal backslash. Defaults to `false`.
+    /// `\t` becomes a tab, `\\` becomes a literal backslash, and `\u{XXXX}` becomes the character with hex code point
+    /// `XXXX`, e.g. `\u{200B}` for a zero-width space. Defaults to `false`.
     pub interpret_escape_sequences: bool,
+    /// Number of bytes at the start of each file that are inspected to decide whether it is a binary file,
+    /// in which case it is skipped. Set to `0` to only skip files based on their extension. Defaults to `8192`.
+    pub binary_sample_size: usize,
+    /// File extensions that are always treated as text, even if they would otherwise be detected as binary,
+    /// e.g. `["svg", "csv"]`. Defaults to `[]`.
+    pub text_extensions: Vec<String>,
+    /// Search fields to hide from the search screen, e.g. `["whole_word", "match_case"]`. Hidden fields are skipped
+    /// when moving focus between fields, and keep their default values (or the values passed via CLI flags). Can
+    /// contain any of `replace`, `fixed_strings`, `whole_word`, `match_case`, `include_files` and `exclude_files`.
+    /// Defaults to `[]`.
+    #[serde(deserialize_with = "deserialize_hidden_fields")]
+    pub hidden_fields: Vec<FieldName>,
+    /// The order of the search fields, which is used both when showing them and when moving focus between them,
+    /// e.g. `["include_files", "exclude_files", "search"]`. Fields that aren't listed come after those that are, in
+    /// their default order. Can contain any of `search`, `replace`, `fixed_strings`, `whole_word`, `match_case`,
+    /// `include_files` and `exclude_files`. Defaults to `[]`, i.e. the default order.
+    #[serde(deserialize_with = "deserialize_field_order")]
+    pub field_order: Vec<FieldName>,
+    /// The maximum number of milliseconds that an advanced regex (enabled with `--advanced-regex`) may spend searching a
+    /// single file. Patterns that backtrack catastrophically can otherwise hang the search: once this is exceeded, the
+    /// file is skipped and reported as having a regex that was too slow. This is checked after each line, or after the
+    /// whole file when searching across lines, and files are also skipped if a single match backtracks more than a
+    /// million times, so that one long line can't hang the search. Must be at least `1`. If not set, there is no limit.
+    #[serde(deserialize_with = "deserialize_regex_timeout_ms")]
+    pub regex_timeout_ms: Option<u64>,
 }
 
 impl Default for SearchConfig {
@@ -214,13 +903,73 @@ impl Default for SearchConfig {
         Self {
             disable_prepopulated_fields: true,
             interpret_escape_sequences: false,
+            binary_sample_size: DEFAULT_BINARY_SAMPLE_SIZE,
+            text_extensions: vec![],
+            hidden_fields: vec![],
+            field_order: vec![],
+            regex_timeout_ms: None,
+        }
+    }
+}
+
+fn deserialize_hidden_fields<'de, D>(deserializer: D) -> Result<Vec<FieldName>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hidden_fields = Vec::<FieldName>::deserialize(deserializer)?;
+    if hidden_fields.contains(&FieldName::Search) {
+        return Err(de::Error::custom("the search text field can't be hidden"));
+    }
+    Ok(hidden_fields)
+}
+
+fn deserialize_field_order<'de, D>(deserializer: D) -> Result<Vec<FieldName>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let field_order = Vec::<FieldName>::deserialize(deserializer)?;
+    for (idx, name) in field_order.iter().enumerate() {
+        if field_order[..idx].contains(name) {
+            return Err(de::Error::custom(format!(
+                "\"{}\" appears more than once in field_order",
+                name.title()
+            )));
+        }
+    }
+    Ok(field_order)
+}
+
+fn deserialize_regex_timeout_ms<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timeout = u64::deserialize(deserializer)?;
+    if timeout == 0 {
+        return Err(de::Error::custom("regex_timeout_ms must be at least 1"));
+    }
+    Ok(Some(timeout))
+}
+
+impl SearchConfig {
+    pub fn regex_timeout(&self) -> Option<Duration> {
+        self.regex_timeout_ms.map(Duration::from_millis)
+    }
+
+    pub fn binary_detection(&self) -> BinaryDetection {
+        BinaryDetection {
+            sample_size: self.binary_sample_size,
+            text_extensions: self.text_extensions.clone(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::commands::KeyMap;
+    use crate::{
+        commands::KeyMap,
+        keyboard::{KeyCode, KeyEvent, KeyModifiers},
+        keys,
+    };
 
     use super::*;
 
@@ -280,9 +1029,10 @@ syntax_highlighting = false
     }
 
     #[test]
+    #[allow(clippy::too_many_lines)]
     fn test_full_config() -> anyhow::Result<()> {
         let config: Config = toml::from_str(
-            r#"
+            r##"
 [editor_open]
 command = "nvim %file +%line"
 exit = true
@@ -291,14 +1041,47 @@ exit = true
 syntax_highlighting = false
 syntax_highlighting_theme = "Solarized (light)"
 wrap_text = true
+show_nearby_replacements = true
+line_numbers = true
+line_prefix = "│ "
+wrapped_prefix = "│ … "
+layout = "side_by_side"
+control_chars = "escape"
+highlight_cache_entries = 50
+highlight_cache_lines = 10000
+
+[display]
+path_style = "absolute"
+truncate = "middle"
+list_preview_ratio = "1:1"
+vertical_list_preview_ratio = "1:2"
+show_preview = false
+min_preview_width = 60
+fields_position = "bottom"
+mouse = true
+max_matches_per_file = 20
+
+[ui]
+render_interval_ms = 50
 
 [style]
 true_color = false
 
+[colors]
+diff_old = "light-red"
+diff_new = "#00af5f"
+diff_highlight = "White"
+diff_unchanged = "gray"
+
 [search]
 disable_prepopulated_fields = false
 interpret_escape_sequences = true
-"#,
+binary_sample_size = 1024
+text_extensions = ["svg", "csv"]
+hidden_fields = ["whole_word", "match_case"]
+field_order = ["include_files", "search"]
+regex_timeout_ms = 500
+"##,
         )?;
 
         assert_eq!(
@@ -322,11 +1105,50 @@ interpret_escape_sequences = true
                     syntax_highlighting: false,
                     syntax_highlighting_theme: load_theme("Solarized (light)").unwrap(),
                     wrap_text: true,
+                    show_nearby_replacements: true,
+                    line_numbers: true,
+                    line_prefix: "│ ".to_owned(),
+                    wrapped_prefix: "│ … ".to_owned(),
+                    layout: PreviewLayout::SideBySide,
+                    control_chars: ControlChars::Escape,
+                    highlight_cache_entries: 50,
+                    highlight_cache_lines: 10_000,
+                },
+                display: DisplayConfig {
+                    path_style: PathStyle::Absolute,
+                    truncate: Truncate::Middle,
+                    list_preview_ratio: SplitRatio {
+                        list: 1,
+                        preview: 1,
+                    },
+                    vertical_list_preview_ratio: Some(SplitRatio {
+                        list: 1,
+                        preview: 2,
+                    }),
+                    show_preview: false,
+                    min_preview_width: 60,
+                    fields_position: FieldsPosition::Bottom,
+                    mouse: true,
+                    max_matches_per_file: Some(20),
+                },
+                ui: UiConfig {
+                    render_interval_ms: 50,
                 },
                 style: StyleConfig { true_color: false },
+                colors: ColorsConfig {
+                    diff_old: Colour::LightRed,
+                    diff_new: Colour::Rgb(0x00, 0xaf, 0x5f),
+                    diff_highlight: Colour::White,
+                    diff_unchanged: Some(Colour::Gray),
+                },
                 search: SearchConfig {
                     disable_prepopulated_fields: false,
                     interpret_escape_sequences: true,
+                    binary_sample_size: 1024,
+                    text_extensions: vec!["svg".to_owned(), "csv".to_owned()],
+                    hidden_fields: vec![FieldName::WholeWord, FieldName::MatchCase],
+                    field_order: vec![FieldName::IncludeFiles, FieldName::Search],
+                    regex_timeout_ms: Some(500),
                 },
                 keys: KeysConfig::default(),
             }
@@ -335,6 +1157,174 @@ interpret_escape_sequences = true
         Ok(())
     }
 
+    #[test]
+    fn test_hidden_fields_cannot_include_search() {
+        let err = toml::from_str::<Config>(
+            r#"
+[search]
+hidden_fields = ["replace", "search"]
+"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("the search text field can't be hidden"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_highlight_cache_entries_cannot_be_zero() {
+        let err = toml::from_str::<Config>(
+            r"
+[preview]
+highlight_cache_entries = 0
+",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("highlight_cache_entries must be at least 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_max_matches_per_file_cannot_be_zero() {
+        let err = toml::from_str::<Config>(
+            r"
+[display]
+max_matches_per_file = 0
+",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("max_matches_per_file must be at least 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_regex_timeout_ms_cannot_be_zero() {
+        let err = toml::from_str::<Config>(
+            r"
+[search]
+regex_timeout_ms = 0
+",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("regex_timeout_ms must be at least 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_render_interval_ms_cannot_be_zero() {
+        let err = toml::from_str::<Config>(
+            r"
+[ui]
+render_interval_ms = 0
+",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("render_interval_ms must be at least 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_preview_prefixes_cannot_contain_control_characters() {
+        let err = toml::from_str::<Config>(
+            r#"
+[preview]
+wrapped_prefix = "\n  "
+"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("preview prefixes can't contain control characters"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_field_order_cannot_contain_duplicates() {
+        let err = toml::from_str::<Config>(
+            r#"
+[search]
+field_order = ["include_files", "search", "include_files"]
+"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("\"Files to include\" appears more than once in field_order"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_split_ratio_parsing() {
+        for (input, expected) in [
+            ("2:3", Some((2, 3))),
+            (" 1 : 0 ", Some((1, 0))),
+            ("0:1", None),
+            ("2", None),
+            ("1:2:3", None),
+            ("a:b", None),
+            ("-1:2", None),
+        ] {
+            let ratio = SplitRatio::try_from(input.to_string()).ok();
+            assert_eq!(
+                ratio.map(|r| (r.list, r.preview)),
+                expected,
+                "unexpected result parsing {input:?}"
+            );
+        }
+
+        let result: Result<Config, _> = toml::from_str(
+            r#"
+[display]
+list_preview_ratio = "3"
+"#,
+        );
+        assert!(result.unwrap_err().to_string().contains("invalid ratio"));
+    }
+
+    #[test]
+    fn test_colour_parsing() {
+        for (input, expected) in [
+            ("red", Some(Colour::Red)),
+            ("DarkGrey", Some(Colour::DarkGray)),
+            ("light_magenta", Some(Colour::LightMagenta)),
+            ("#FF8000", Some(Colour::Rgb(255, 128, 0))),
+            ("#ff80", None),
+            ("#gg0000", None),
+            ("#ff800000", None),
+            ("purple", None),
+        ] {
+            assert_eq!(
+                Colour::try_from(input.to_string()).ok(),
+                expected,
+                "unexpected result parsing {input:?}"
+            );
+        }
+
+        let result: Result<Config, _> = toml::from_str(
+            r#"
+[colors]
+diff_old = "reddish"
+"#,
+        );
+        assert!(result.unwrap_err().to_string().contains("invalid color"));
+    }
+
     #[test]
     fn test_missing_editor_exit_field() -> anyhow::Result<()> {
         let config: Config = toml::from_str(
@@ -348,6 +1338,397 @@ command = "vim %file +%line"
         Ok(())
     }
 
+    fn parse_config(contents: &str, profile: Option<&str>) -> anyhow::Result<Config> {
+        config_from_table(toml::from_str(contents)?, profile)
+    }
+
+    const PROFILES_CONFIG: &str = r#"
+default_profile = "docs"
+
+[search]
+interpret_escape_sequences = true
+text_extensions = ["txt"]
+
+[profiles.docs.search]
+text_extensions = ["md"]
+
+[profiles.cleanup.preview]
+wrap_text = true
+"#;
+
+    #[test]
+    fn test_config_profiles() -> anyhow::Result<()> {
+        let config = parse_config(PROFILES_CONFIG, None)?;
+        assert_eq!(config.search.text_extensions, vec!["md".to_string()]);
+        assert!(config.search.interpret_escape_sequences);
+        assert!(!config.preview.wrap_text);
+
+        let config = parse_config(PROFILES_CONFIG, Some("cleanup"))?;
+        assert_eq!(config.search.text_extensions, vec!["txt".to_string()]);
+        assert!(config.search.interpret_escape_sequences);
+        assert!(config.preview.wrap_text);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_preset() -> anyhow::Result<()> {
+        let config = parse_config(
+            r#"
+[keys]
+preset = "emacs"
+
+[keys.search.results]
+move_top = "g"
+"#,
+            None,
+        )?;
+        assert_eq!(config.keys.preset, KeysPreset::Emacs);
+        assert_eq!(
+            config.keys.search.results.move_down,
+            KeysConfig::from_preset(KeysPreset::Emacs)
+                .search
+                .results
+                .move_down
+        );
+        assert_eq!(
+            config.keys.search.results.move_top,
+            keys![KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            config.keys.search.results.move_bottom,
+            keys![KeyEvent::new(KeyCode::Char('>'), KeyModifiers::ALT)]
+        );
+
+        let config = parse_config("[keys]\npreset = \"default\"\n", None)?;
+        assert_eq!(config.keys, KeysConfig::default());
+
+        // The vim preset uses the vim-style default bindings
+        let config = parse_config("[keys]\npreset = \"vim\"\n", None)?;
+        assert_eq!(config.keys.preset, KeysPreset::Vim);
+        assert_eq!(
+            config.keys,
+            KeysConfig {
+                preset: KeysPreset::Vim,
+                ..KeysConfig::default()
+            }
+        );
+        let results = &config.keys.search.results;
+        assert_eq!(
+            results.move_top,
+            keys![KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            results.move_bottom,
+            keys![KeyEvent::new(KeyCode::Char('G'), KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            results.move_down_half_page,
+            keys![KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_keys_preset() {
+        let err = parse_config("[keys]\npreset = \"nano\"\n", None).unwrap_err();
+        assert!(err.to_string().contains("Invalid `keys.preset`"));
+    }
+
+    /// Every key bound to a command in `keys`, not including nested sections
+    fn bound_keys<T: Serialize>(keys: &T) -> Vec<toml::Value> {
+        toml::Table::try_from(keys)
+            .unwrap()
+            .into_iter()
+            .flat_map(|(_, value)| match value {
+                toml::Value::Array(keys) => keys,
+                toml::Value::String(_) => vec![value],
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keys_presets_are_valid() {
+        for preset in [KeysPreset::Default, KeysPreset::Vim, KeysPreset::Emacs] {
+            let mut keys = KeysConfig::from_preset(preset);
+            let key_map = KeyMap::from_config(&mut keys);
+            assert!(
+                key_map.is_ok_and(|key_map| key_map.text_input_shadows.is_empty()),
+                "{preset:?} keybindings should be valid and not shadow text input"
+            );
+
+            // Keys available on the whole search screen take precedence over those of the results
+            let search_keys = bound_keys(&keys.search);
+            let shadowed = bound_keys(&keys.search.results)
+                .into_iter()
+                .filter(|key| search_keys.contains(key))
+                .collect::<Vec<_>>();
+            assert!(
+                shadowed.is_empty(),
+                "{preset:?} keybindings for search results are shadowed: {shadowed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_to_toml() -> anyhow::Result<()> {
+        let config = parse_config(
+            r##"
+[preview]
+syntax_highlighting_theme = "Solarized (light)"
+
+[display]
+list_preview_ratio = "1:0"
+
+[colors]
+diff_old = "light-red"
+diff_unchanged = "#0a0b0c"
+
+[search]
+hidden_fields = ["whole_word"]
+
+[keys.general]
+quit = ["C-c", "C-q"]
+
+[profiles.docs.search]
+text_extensions = ["md"]
+"##,
+            Some("docs"),
+        )?;
+
+        let toml = config.to_toml()?;
+        for expected in [
+            "syntax_highlighting_theme = \"Solarized (light)\"",
+            "list_preview_ratio = \"1:0\"",
+            "diff_old = \"light-red\"",
+            "diff_new = \"green\"",
+            "diff_unchanged = \"#0a0b0c\"",
+            "text_extensions = [\"md\"]",
+            "hidden_fields = [\"whole_word\"]",
+            "quit = [\n    \"C-c\",\n    \"C-q\",\n]",
+        ] {
+            assert!(toml.contains(expected), "{expected} not found in:\n{toml}");
+        }
+        assert_eq!(toml::from_str::<Config>(&toml)?, config);
+        assert_eq!(
+            toml::from_str::<Config>(&Config::default().to_toml()?)?,
+            Config::default()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_default_config_if_missing() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let config_file = temp_dir.path().join("scooter").join("config.toml");
+
+        write_default_config_if_missing(&config_file)?;
+        let contents = fs::read_to_string(&config_file)?;
+        assert_eq!(
+            load_layered_config(&config_file, None, None)?,
+            Config::default()
+        );
+        assert!(contents.contains(
+            "# [preview]\n\n## Whether to apply syntax highlighting to the preview. Defaults to `true`.\n# syntax_highlighting = true\n"
+        ));
+
+        // Options detected from the environment are left without a value, so the file is the same everywhere
+        assert!(contents.contains("\n# true_color =\n"));
+
+        // Uncommenting the options gives the defaults, other than those without a default value
+        let uncommented = contents
+            .lines()
+            .skip(2)
+            .filter(|line| !line.starts_with("##") && !line.ends_with(" ="))
+            .map(|line| line.strip_prefix("# ").unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(toml::from_str::<Config>(&uncommented)?, Config::default());
+
+        // Existing config files are left untouched
+        fs::write(&config_file, "[preview]\nwrap_text = true\n")?;
+        write_default_config_if_missing(&config_file)?;
+        assert_eq!(
+            fs::read_to_string(&config_file)?,
+            "[preview]\nwrap_text = true\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_without_profiles() -> anyhow::Result<()> {
+        let config = parse_config("[preview]\nwrap_text = true", None)?;
+        assert!(config.preview.wrap_text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_config_profile() {
+        let err = parse_config(PROFILES_CONFIG, Some("other")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown config profile \"other\", expected one of: \"cleanup\", \"docs\""
+        );
+
+        let err = parse_config("default_profile = \"docs\"", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown config profile \"docs\": no profiles are defined"
+        );
+    }
+
+    #[test]
+    fn test_config_profile_unknown_field_rejected() {
+        let result = parse_config(
+            PROFILES_CONFIG.replace("wrap_text", "foo").as_str(),
+            Some("cleanup"),
+        );
+        assert!(result.is_err());
+        // Unselected profiles aren't validated
+        assert!(parse_config(PROFILES_CONFIG.replace("wrap_text", "foo").as_str(), None).is_ok());
+    }
+
+    #[test]
+    fn test_local_config_files_are_layered() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let global_config = temp_dir.path().join("config.toml");
+        fs::write(
+            &global_config,
+            "[search]\ntext_extensions = [\"txt\"]\ninterpret_escape_sequences = true\n\n[preview]\nwrap_text = true",
+        )?;
+        let project = temp_dir.path().join("project");
+        let sub_dir = project.join("sub").join("dir");
+        fs::create_dir_all(&sub_dir)?;
+        fs::write(
+            project.join(LOCAL_CONFIG_FILE_NAME),
+            "[search]\ntext_extensions = [\"md\"]\n\n[profiles.docs.preview]\nwrap_text = false",
+        )?;
+        fs::write(
+            project.join("sub").join(LOCAL_CONFIG_FILE_NAME),
+            "[search]\ntext_extensions = [\"rs\"]",
+        )?;
+
+        let config = load_layered_config(&global_config, None, None)?;
+        assert_eq!(config.search.text_extensions, vec!["txt".to_string()]);
+
+        let config = load_layered_config(&global_config, Some(&sub_dir), None)?;
+        assert_eq!(config.search.text_extensions, vec!["rs".to_string()]);
+        assert!(config.search.interpret_escape_sequences);
+        assert!(config.preview.wrap_text);
+
+        let config = load_layered_config(&global_config, Some(&project), Some("docs"))?;
+        assert_eq!(config.search.text_extensions, vec!["md".to_string()]);
+        assert!(!config.preview.wrap_text);
+
+        // Local config is used even without a global config file
+        let config =
+            load_layered_config(&temp_dir.path().join("missing.toml"), Some(&sub_dir), None)?;
+        assert_eq!(config.search.text_extensions, vec!["rs".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_config_dir_with_multiple_directories() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let global_config = temp_dir.path().join("config.toml");
+        fs::create_dir(temp_dir.path().join("project"))?;
+        // Directories are compared by their canonical paths
+        let project = temp_dir.path().join("project").canonicalize()?;
+        let (a, b) = (project.join("a"), project.join("b").join("c"));
+        fs::create_dir_all(&a)?;
+        fs::create_dir_all(&b)?;
+        fs::write(
+            project.join(LOCAL_CONFIG_FILE_NAME),
+            "[search]\ntext_extensions = [\"md\"]",
+        )?;
+        fs::write(
+            a.join(LOCAL_CONFIG_FILE_NAME),
+            "[search]\ntext_extensions = [\"rs\"]",
+        )?;
+
+        assert_eq!(local_config_dir(&[]), None);
+        assert_eq!(local_config_dir(std::slice::from_ref(&a)), Some(a.clone()));
+        assert_eq!(local_config_dir(&[a.join("."), a.clone()]), Some(a.clone()));
+
+        // Only the config files shared by every directory apply, whatever their order
+        for directories in [[a.clone(), b.clone()], [b.clone(), a.clone()]] {
+            let dir = local_config_dir(&directories);
+            assert_eq!(dir, Some(project.clone()));
+            let config = load_layered_config(&global_config, dir.as_deref(), None)?;
+            assert_eq!(config.search.text_extensions, vec!["md".to_string()]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_config_cannot_set_editor_command() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let local_config = temp_dir.path().join(LOCAL_CONFIG_FILE_NAME);
+        fs::write(&local_config, "[editor_open]\ncommand = \"rm %file\"")?;
+
+        let err = load_layered_config(
+            &temp_dir.path().join("config.toml"),
+            Some(temp_dir.path()),
+            None,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("`editor_open` can only be set in the global config"),
+            "{err}"
+        );
+
+        // The same file is allowed as the global config
+        let config = load_layered_config(&local_config, None, None)?;
+        assert_eq!(config.editor_open.command.as_deref(), Some("rm %file"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_config_cannot_set_editor_command_with_profile() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let global_config = temp_dir.path().join("config.toml");
+        let project = temp_dir.path().join("project");
+        fs::create_dir(&project)?;
+        let local_config = project.join(LOCAL_CONFIG_FILE_NAME);
+
+        fs::write(
+            &local_config,
+            "default_profile = \"x\"\n[profiles.x.editor_open]\ncommand = \"rm %file\"",
+        )?;
+        let err = load_layered_config(&global_config, Some(&project), None).unwrap_err();
+        assert!(
+            err.to_string().starts_with(
+                "`editor_open` can only be set in the global config, not in profile \"x\""
+            ),
+            "{err}"
+        );
+
+        // A profile that sets the editor command in the global config can't be selected locally
+        fs::write(
+            &global_config,
+            "[profiles.x.editor_open]\ncommand = \"vi %file\"\n[profiles.y.search]\nfield_order = []",
+        )?;
+        fs::write(&local_config, "default_profile = \"x\"")?;
+        let err = load_layered_config(&global_config, Some(&project), None).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("can't select profile \"x\", as it sets `editor_open`"),
+            "{err}"
+        );
+
+        // ...but can be with the profile override, and other profiles can be selected locally
+        let config = load_layered_config(&global_config, Some(&project), Some("x"))?;
+        assert_eq!(config.editor_open.command.as_deref(), Some("vi %file"));
+        fs::write(&local_config, "default_profile = \"y\"")?;
+        let config = load_layered_config(&global_config, Some(&project), None)?;
+        assert_eq!(config.editor_open.command, None);
+        Ok(())
+    }
+
     #[test]
     fn test_get_theme_none() {
         let config = Config::default();
@@ -365,8 +1746,14 @@ command = "vim %file +%line"
                 syntax_highlighting: false,
                 syntax_highlighting_theme: load_theme("base16-ocean.dark").unwrap(),
                 wrap_text: false,
+                show_nearby_replacements: false,
+                control_chars: ControlChars::default(),
+                ..PreviewConfig::default()
             },
+            display: DisplayConfig::default(),
+            ui: UiConfig::default(),
             style: StyleConfig::default(),
+            colors: ColorsConfig::default(),
             search: SearchConfig::default(),
             keys: KeysConfig::default(),
         };
@@ -381,8 +1768,14 @@ command = "vim %file +%line"
                 syntax_highlighting: true,
                 syntax_highlighting_theme: load_theme("base16-ocean.dark").unwrap(),
                 wrap_text: false,
+                show_nearby_replacements: false,
+                control_chars: ControlChars::default(),
+                ..PreviewConfig::default()
             },
+            display: DisplayConfig::default(),
+            ui: UiConfig::default(),
             style: StyleConfig::default(),
+            colors: ColorsConfig::default(),
             search: SearchConfig::default(),
             keys: KeysConfig::default(),
         };
@@ -440,7 +1833,7 @@ trigger_search = "D-ret"
 
     #[test]
     fn test_key_conflict_within_same_section() {
-        let config: Config = toml::from_str(
+        let mut config: Config = toml::from_str(
             r#"
 [keys.search.fields]
 trigger_search = "enter"
@@ -448,7 +1841,7 @@ focus_next_field = "enter"
 "#,
         )
         .unwrap();
-        let result = crate::commands::KeyMap::from_config(&config.keys);
+        let result = crate::commands::KeyMap::from_config(&mut config.keys);
         assert!(result.is_err());
         let conflicts = result.unwrap_err();
         assert_eq!(conflicts.len(), 1);
@@ -458,7 +1851,7 @@ focus_next_field = "enter"
 
     #[test]
     fn test_key_conflict_with_multiple_mappings() {
-        let config: Config = toml::from_str(
+        let mut config: Config = toml::from_str(
             r#"
 [keys.search.results]
 move_down = ["j", "down"]
@@ -466,7 +1859,7 @@ move_up = ["k", "down"]
 "#,
         )
         .unwrap();
-        let result = crate::commands::KeyMap::from_config(&config.keys);
+        let result = crate::commands::KeyMap::from_config(&mut config.keys);
         assert!(result.is_err());
         let conflicts = result.unwrap_err();
         assert_eq!(conflicts.len(), 1);
@@ -474,6 +1867,53 @@ move_up = ["k", "down"]
         assert_eq!(conflicts[0].key.to_string(), "down");
     }
 
+    #[test]
+    fn test_default_keys_give_way_to_other_bindings() {
+        let config: Config = toml::from_str(
+            r#"
+[keys.search]
+toggle_multiline = "/"
+
+[keys.search.results]
+move_down = ["n", "y"]
+find_next = "m"
+
+[keys.results]
+quit = "e"
+"#,
+        )
+        .unwrap();
+        let mut keys = config.keys;
+        assert!(KeyMap::from_config(&mut keys).is_ok());
+        let defaults = KeysSearchFocusResults::default();
+        assert!(keys.search.results.find_in_results.is_empty());
+        assert!(keys.search.results.copy_matched_text.is_empty());
+        assert!(keys.search.results.show_match_summary.is_empty());
+        assert_eq!(
+            keys.search.results.find_next,
+            keys![KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE)]
+        );
+        assert_eq!(keys.search.results.find_previous, defaults.find_previous);
+        assert_eq!(
+            keys.search.results.copy_replacement,
+            defaults.copy_replacement
+        );
+        assert!(keys.results.show_error_details.is_empty());
+
+        // Keys set in the config still conflict with each other
+        let mut config: Config = toml::from_str(
+            r#"
+[keys.search.results]
+find_next = "m"
+show_match_summary = ["m", "M"]
+"#,
+        )
+        .unwrap();
+        let conflicts = KeyMap::from_config(&mut config.keys).unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].key.to_string(), "m");
+    }
+
     #[test]
     fn test_no_conflict_between_different_screens() {
         // Same key can be used in different screen contexts without conflict
@@ -515,16 +1955,58 @@ move_down = ["A-r", "l"]
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_text_input_shadows_detected() {
+        let mut config: Config = toml::from_str(
+            r#"
+[keys.general]
+quit = ["q", "C-c"]
+
+[keys.search]
+toggle_multiline = "M"
+
+[keys.search.fields]
+save_preset = ["A-s", "p"]
+
+[keys.search.results]
+move_down = "n"
+"#,
+        )
+        .unwrap();
+        let key_map = KeyMap::from_config(&mut config.keys).unwrap();
+        let shadows: Vec<_> = key_map
+            .text_input_shadows
+            .iter()
+            .map(|s| (s.key.to_string(), s.context.as_str(), s.command.as_str()))
+            .collect();
+        assert_eq!(
+            shadows,
+            vec![
+                ("q".to_string(), "general", "quit"),
+                ("M".to_string(), "search", "toggle_multiline"),
+                ("p".to_string(), "search.fields", "save_preset"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_keybindings_do_not_shadow_text_input() {
+        let key_map = KeyMap::from_config(&mut Config::default().keys).unwrap();
+        assert!(key_map.text_input_shadows.is_empty());
+    }
+
     #[test]
     fn test_all_default_keybindings_are_valid() {
-        let config = Config::default();
+        let mut config = Config::default();
         // If KeyMap construction succeeds, all defaults are valid
-        let key_map_result = KeyMap::from_config(&config.keys);
+        let key_map_result = KeyMap::from_config(&mut config.keys);
         assert!(
             key_map_result.is_ok(),
             "Default keybindings should be valid: {:?}",
             key_map_result.unwrap_err()
         );
+        // No default gives way to another
+        assert_eq!(config.keys, Config::default().keys);
     }
 
     #[test]
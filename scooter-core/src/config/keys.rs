@@ -105,10 +105,16 @@ macro_rules! keys {
 #[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct KeysConfig {
+    #[serde(default)]
+    /// Built-in keys that the keys set below are applied on top of: "default" or "vim" (both vim-style movement), or "emacs" (`C-n`/`C-p`/`C-v`/`A-<`/`A->`, no `j`/`k`/`g`/`G`)
+    pub preset: KeysPreset,
     #[serde(default)]
     /// Commands available on all screens
     pub general: KeysGeneral,
     #[serde(default)]
+    /// Commands available on the file picker screen, which is shown before searching when using `--interactive-filter`
+    pub file_picker: KeysFilePicker,
+    #[serde(default)]
     /// Commands available on the search screen
     pub search: KeysSearch,
     #[serde(default)]
@@ -119,6 +125,68 @@ pub struct KeysConfig {
     pub results: KeysResults,
 }
 
+/// A built-in set of keybindings, which the keys set in the config are applied on top of
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeysPreset {
+    /// The default keys, which use vim-style movement such as `j`/`k`, `g`/`G` and `C-d`/`C-u`
+    #[default]
+    Default,
+    /// The same keys as `Default`, for configs that name the style explicitly
+    Vim,
+    Emacs,
+}
+
+impl KeysConfig {
+    /// The keys of `preset`, before any keys set in the config are applied
+    pub fn from_preset(preset: KeysPreset) -> Self {
+        let mut keys = Self {
+            preset,
+            ..Self::default()
+        };
+        match preset {
+            KeysPreset::Default | KeysPreset::Vim => {}
+            KeysPreset::Emacs => {
+                let (down, up) = (
+                    keys![
+                        KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL),
+                        KeyEvent::new(KeyCode::Down, KeyModifiers::NONE),
+                    ],
+                    keys![
+                        KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL),
+                        KeyEvent::new(KeyCode::Up, KeyModifiers::NONE),
+                    ],
+                );
+                keys.set_move_down_up(&down, &up);
+
+                let top = keys![KeyEvent::new(KeyCode::Char('<'), KeyModifiers::ALT)];
+                let bottom = keys![KeyEvent::new(KeyCode::Char('>'), KeyModifiers::ALT)];
+                keys.file_picker.move_top = top.clone();
+                keys.file_picker.move_bottom = bottom.clone();
+                let results = &mut keys.search.results;
+                results.move_top = top;
+                results.move_bottom = bottom;
+                // `A-v` isn't used for moving up a page, as it toggles the preview
+                results.move_down_full_page = keys![
+                    KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL),
+                    KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE),
+                ];
+            }
+        }
+        keys
+    }
+
+    /// Sets the keys for moving down and up through each list
+    fn set_move_down_up(&mut self, down: &Keys, up: &Keys) {
+        self.file_picker.move_down = down.clone();
+        self.file_picker.move_up = up.clone();
+        self.search.results.move_down = down.clone();
+        self.search.results.move_up = up.clone();
+        self.results.scroll_errors_down = down.clone();
+        self.results.scroll_errors_up = up.clone();
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, default)]
 pub struct KeysGeneral {
@@ -128,6 +196,10 @@ pub struct KeysGeneral {
     pub reset: Keys,
     /// Show the help menu containing keymaps
     pub show_help_menu: Keys,
+    /// Show a list of the commands available on the current screen, which can be searched to find and run a command
+    pub show_command_palette: Keys,
+    /// Open the config file in the editor, creating it if it doesn't exist. Changes take effect when scooter is next started
+    pub edit_config: Keys,
 }
 
 impl Default for KeysGeneral {
@@ -136,6 +208,49 @@ impl Default for KeysGeneral {
             quit: keys![KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)],
             reset: keys![KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)],
             show_help_menu: keys![KeyEvent::new(KeyCode::Char('h'), KeyModifiers::CONTROL)],
+            show_command_palette: keys![KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL)],
+            edit_config: keys![KeyEvent::new(KeyCode::Char(','), KeyModifiers::ALT)],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct KeysFilePicker {
+    /// Search the approved files
+    pub confirm: Keys,
+    /// Navigate to the file below
+    pub move_down: Keys,
+    /// Navigate to the file above
+    pub move_up: Keys,
+    /// Navigate to the first file
+    pub move_top: Keys,
+    /// Navigate to the last file
+    pub move_bottom: Keys,
+    /// Toggle whether the currently highlighted file will be searched
+    pub toggle_selected_inclusion: Keys,
+    /// Toggle whether all files will be searched
+    pub toggle_all_selected: Keys,
+}
+
+impl Default for KeysFilePicker {
+    fn default() -> Self {
+        Self {
+            confirm: keys![KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)],
+            move_down: keys![
+                KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Down, KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL),
+            ],
+            move_up: keys![
+                KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Up, KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL),
+            ],
+            move_top: keys![KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)],
+            move_bottom: keys![KeyEvent::new(KeyCode::Char('G'), KeyModifiers::NONE)],
+            toggle_selected_inclusion: keys![KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)],
+            toggle_all_selected: keys![KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE)],
         }
     }
 }
@@ -143,14 +258,28 @@ impl Default for KeysGeneral {
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields, default)]
 pub struct KeysSearch {
+    /// Toggle whether the preview of the selected search result is shown
+    pub toggle_preview: Keys,
     /// Toggle wrapping of lines that don't fit within the width of the preview
     pub toggle_preview_wrapping: Keys,
     /// Toggle inclusion of hidden files and directories, such as those whose name starts with a dot (.)
     pub toggle_hidden_files: Keys,
     /// Toggle multiline search mode, which allows patterns to match across line boundaries
     pub toggle_multiline: Keys,
-    /// Toggle interpretation of escape sequences in replacement text (\n becomes newline, \t becomes tab, \\ becomes backslash)
+    /// Toggle advanced regex mode, which supports features such as look-ahead and look-behind at the cost of performance
+    pub toggle_advanced_regex: Keys,
+    /// Toggle interpretation of escape sequences in replacement text (\n becomes newline, \t becomes tab, \\ becomes backslash, \u{200B} becomes zero-width space)
     pub toggle_interpret_escape_sequences: Keys,
+    /// Toggle whether the replacements from other selected results in the same file are applied to the lines surrounding the selected result in the preview
+    pub toggle_nearby_replacements: Keys,
+    /// Pause the search that is currently running, so that the results can be viewed without more coming in, or resume it if it is paused
+    pub toggle_pause_search: Keys,
+    /// Show the `scooter --no-tui` command that performs the current search and replacement, and copy it to the clipboard
+    pub show_headless_command: Keys,
+    /// Move focus straight to the "Files to include" field, from either the search fields or the search results
+    pub focus_include_files: Keys,
+    /// Move focus straight to the "Files to exclude" field, from either the search fields or the search results
+    pub focus_exclude_files: Keys,
     #[serde(default)]
     /// Commands available on the search screen, when the search fields are focussed
     pub fields: KeysSearchFocusFields,
@@ -162,16 +291,23 @@ pub struct KeysSearch {
 impl Default for KeysSearch {
     fn default() -> Self {
         Self {
+            toggle_preview: keys![KeyEvent::new(KeyCode::Char('v'), KeyModifiers::ALT)],
             toggle_preview_wrapping: keys![KeyEvent::new(
                 KeyCode::Char('l'),
                 KeyModifiers::CONTROL
             )],
             toggle_hidden_files: keys![KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL)],
             toggle_multiline: keys![KeyEvent::new(KeyCode::Char('m'), KeyModifiers::ALT)],
+            toggle_advanced_regex: keys![KeyEvent::new(KeyCode::Char('r'), KeyModifiers::ALT)],
             toggle_interpret_escape_sequences: keys![KeyEvent::new(
                 KeyCode::Char('e'),
                 KeyModifiers::ALT
             )],
+            toggle_nearby_replacements: keys![KeyEvent::new(KeyCode::Char('p'), KeyModifiers::ALT)],
+            toggle_pause_search: keys![KeyEvent::new(KeyCode::Char('z'), KeyModifiers::ALT)],
+            show_headless_command: keys![KeyEvent::new(KeyCode::Char('x'), KeyModifiers::ALT)],
+            focus_include_files: keys![KeyEvent::new(KeyCode::Char('i'), KeyModifiers::ALT)],
+            focus_exclude_files: keys![KeyEvent::new(KeyCode::Char('o'), KeyModifiers::ALT)],
             fields: KeysSearchFocusFields::default(),
             results: KeysSearchFocusResults::default(),
         }
@@ -185,10 +321,16 @@ pub struct KeysSearchFocusFields {
     pub unlock_prepopulated_fields: Keys,
     /// Trigger a search
     pub trigger_search: Keys,
+    /// Move focus back to the results of the current search, leaving it running (or completed) rather than searching again. Does nothing if there is no search yet.
+    pub focus_results: Keys,
     /// Focus on the next field
     pub focus_next_field: Keys,
     /// Focus on the previous field
     pub focus_previous_field: Keys,
+    /// Save the current contents of the search fields as a named preset, which is written to `presets.toml` in the scooter config directory
+    pub save_preset: Keys,
+    /// Clear the text in all search fields and reset checkboxes to their defaults, without performing a full reset. Fields populated via CLI args are left untouched while they are locked.
+    pub clear_fields: Keys,
 }
 
 impl Default for KeysSearchFocusFields {
@@ -196,8 +338,11 @@ impl Default for KeysSearchFocusFields {
         Self {
             unlock_prepopulated_fields: keys![KeyEvent::new(KeyCode::Char('u'), KeyModifiers::ALT)],
             trigger_search: keys![KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)],
+            focus_results: keys![KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL)],
             focus_next_field: keys![KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE)],
             focus_previous_field: keys![KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT)],
+            save_preset: keys![KeyEvent::new(KeyCode::Char('s'), KeyModifiers::ALT)],
+            clear_fields: keys![KeyEvent::new(KeyCode::Char('c'), KeyModifiers::ALT)],
         }
     }
 }
@@ -207,10 +352,20 @@ impl Default for KeysSearchFocusFields {
 pub struct KeysSearchFocusResults {
     /// Trigger a replacement
     pub trigger_replacement: Keys,
-    /// Move focus back to the search fields
+    /// Move focus back to the search fields. The current search keeps running, so its results can be returned to with `focus_results`.
     pub back_to_fields: Keys,
     /// Open the currently selected search result in your editor. The editor command can be overriden using the `editor_open` section of your config.
     pub open_in_editor: Keys,
+    /// Copy the text matched by the currently selected search result to the clipboard. For searches that aren't in
+    /// multiline mode, this is the whole line containing the match.
+    pub copy_matched_text: Keys,
+    /// Copy the replacement for the currently selected search result to the clipboard
+    pub copy_replacement: Keys,
+    /// Copy the results that are included in the replacement to the clipboard as `path:line:text` lines, as output by
+    /// `grep -n`. The original text of each line is copied, rather than its replacement.
+    pub copy_results_as_grep: Keys,
+    /// Re-run the current search against the files on disk, e.g. after editing a file. Results which still match keep their included/excluded state, and the selected result stays selected.
+    pub refresh_search: Keys,
 
     /// Navigate to the search result below
     pub move_down: Keys,
@@ -228,16 +383,50 @@ pub struct KeysSearchFocusResults {
     pub move_top: Keys,
     /// Navigate to the last search result
     pub move_bottom: Keys,
+    /// Scroll the preview down through the file, keeping the selected result selected
+    pub scroll_preview_down: Keys,
+    /// Scroll the preview up through the file, keeping the selected result selected
+    pub scroll_preview_up: Keys,
 
     /// Toggle whether the currently highlighted result will be replaced or ignored
     pub toggle_selected_inclusion: Keys,
     /// Toggle whether all results will be replaced or ignored
     pub toggle_all_selected: Keys,
+    /// Toggle whether all results in the same file as the currently highlighted result will be
+    /// replaced or ignored
+    pub toggle_file_inclusion: Keys,
+    /// Show all the results in the same file as the currently highlighted result, including any held back by
+    /// the `max_matches_per_file` option in the `display` section
+    pub expand_file: Keys,
     /// Toggle whether multiselect mode is enabled
     pub toggle_multiselect_mode: Keys,
 
     /// Flip the direction of the multiselect selection
     pub flip_multiselect_direction: Keys,
+
+    /// Cycle between relative, absolute and file name-only paths in the search results list
+    pub cycle_path_style: Keys,
+
+    /// Open a prompt to enter text to find within the search results. The default keys of this, `find_next` and `find_previous` are left unbound if already bound to another command on the search screen
+    pub find_in_results: Keys,
+    /// Navigate to the next search result whose path or line contains the text being found
+    pub find_next: Keys,
+    /// Navigate to the previous search result whose path or line contains the text being found
+    pub find_previous: Keys,
+
+    /// Open a prompt to enter a range of results to select, such as `50,80`, using the numbers shown
+    /// next to each result
+    pub select_range: Keys,
+
+    /// Show the distinct texts matched by the search, along with how many times each occurs, most frequent first
+    pub show_match_summary: Keys,
+
+    /// Toggle the "Fixed strings" field and re-run the search, keeping the selected result selected
+    pub toggle_fixed_strings: Keys,
+    /// Toggle the "Match case" field and re-run the search, keeping the selected result selected
+    pub toggle_match_case: Keys,
+    /// Toggle the "Match whole word" field and re-run the search, keeping the selected result selected
+    pub toggle_whole_word: Keys,
 }
 
 impl Default for KeysSearchFocusResults {
@@ -249,6 +438,10 @@ impl Default for KeysSearchFocusResults {
                 KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL),
             ],
             open_in_editor: keys![KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE)],
+            copy_matched_text: keys![KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE)],
+            copy_replacement: keys![KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::NONE)],
+            copy_results_as_grep: keys![KeyEvent::new(KeyCode::Char('E'), KeyModifiers::NONE)],
+            refresh_search: keys![KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)],
 
             move_down: keys![
                 KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE),
@@ -272,12 +465,30 @@ impl Default for KeysSearchFocusResults {
             ],
             move_top: keys![KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE)],
             move_bottom: keys![KeyEvent::new(KeyCode::Char('G'), KeyModifiers::NONE)],
+            scroll_preview_down: keys![KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)],
+            scroll_preview_up: keys![KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL)],
 
             toggle_selected_inclusion: keys![KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)],
             toggle_all_selected: keys![KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE)],
+            toggle_file_inclusion: keys![KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE)],
+            expand_file: keys![KeyEvent::new(KeyCode::Char('X'), KeyModifiers::NONE)],
             toggle_multiselect_mode: keys![KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE)],
 
             flip_multiselect_direction: keys![KeyEvent::new(KeyCode::Char(';'), KeyModifiers::ALT)],
+
+            cycle_path_style: keys![KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE)],
+
+            find_in_results: keys![KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE)],
+            find_next: keys![KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE)],
+            find_previous: keys![KeyEvent::new(KeyCode::Char('N'), KeyModifiers::NONE)],
+
+            select_range: keys![KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE)],
+
+            show_match_summary: keys![KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE)],
+
+            toggle_fixed_strings: keys![KeyEvent::new(KeyCode::Char('F'), KeyModifiers::NONE)],
+            toggle_match_case: keys![KeyEvent::new(KeyCode::Char('C'), KeyModifiers::NONE)],
+            toggle_whole_word: keys![KeyEvent::new(KeyCode::Char('W'), KeyModifiers::NONE)],
         }
     }
 }
@@ -294,6 +505,10 @@ pub struct KeysResults {
     pub scroll_errors_down: Keys,
     /// Navigate to the error above
     pub scroll_errors_up: Keys,
+    /// Show the full error message for the error at the top of the list, which may be truncated in the list
+    pub show_error_details: Keys,
+    /// Re-attempt the failed replacements, re-reading each file and replacing wherever the original text is unchanged
+    pub retry_failed: Keys,
     /// Exit scooter. This is in addition to the `quit` command in the `general` section.
     pub quit: Keys,
 }
@@ -311,6 +526,8 @@ impl Default for KeysResults {
                 KeyEvent::new(KeyCode::Up, KeyModifiers::NONE),
                 KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL),
             ],
+            show_error_details: keys![KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE)],
+            retry_failed: keys![KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)],
             quit: keys![
                 KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
                 KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE),
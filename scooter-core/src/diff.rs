@@ -7,6 +7,8 @@ pub enum DiffColour {
     Red,
     Green,
     Black,
+    /// Used for text that is the same in the old and new lines, so that the changes stand out
+    Neutral,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -46,12 +48,12 @@ pub fn line_diff<'a>(old_line: &'a str, new_line: &'a str) -> (Vec<Diff>, Vec<Di
             ChangeTag::Equal => {
                 old_spans.push(Diff {
                     text: text.clone(),
-                    fg_colour: DiffColour::Red,
+                    fg_colour: DiffColour::Neutral,
                     bg_colour: None,
                 });
                 new_spans.push(Diff {
                     text,
-                    fg_colour: DiffColour::Green,
+                    fg_colour: DiffColour::Neutral,
                     bg_colour: None,
                 });
             }
@@ -61,23 +63,41 @@ pub fn line_diff<'a>(old_line: &'a str, new_line: &'a str) -> (Vec<Diff>, Vec<Di
     (old_spans, new_spans)
 }
 
+/// A unified diff of the lines changed between `old` and `new`, as output by `diff -u`, with `old_name`
+/// and `new_name` in the headers. Empty if there are no changes
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(old_name, new_name)
+        .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(
+            unified_diff("foo\nbar\n", "baz\nbar\n", "old.txt", "new.txt"),
+            "--- old.txt\n+++ new.txt\n@@ -1,2 +1,2 @@\n-foo\n+baz\n bar\n"
+        );
+        assert_eq!(unified_diff("foo\n", "foo\n", "old.txt", "new.txt"), "");
+    }
+
     #[test]
     fn test_identical_lines() {
         let (old_actual, new_actual) = line_diff("hello", "hello");
 
         let old_expected = vec![Diff {
             text: "hello".to_owned(),
-            fg_colour: DiffColour::Red,
+            fg_colour: DiffColour::Neutral,
             bg_colour: None,
         }];
 
         let new_expected = vec![Diff {
             text: "hello".to_owned(),
-            fg_colour: DiffColour::Green,
+            fg_colour: DiffColour::Neutral,
             bg_colour: None,
         }];
 
@@ -92,7 +112,7 @@ mod tests {
         let old_expected = vec![
             Diff {
                 text: "h".to_owned(),
-                fg_colour: DiffColour::Red,
+                fg_colour: DiffColour::Neutral,
                 bg_colour: None,
             },
             Diff {
@@ -102,7 +122,7 @@ mod tests {
             },
             Diff {
                 text: "llo".to_owned(),
-                fg_colour: DiffColour::Red,
+                fg_colour: DiffColour::Neutral,
                 bg_colour: None,
             },
         ];
@@ -110,7 +130,7 @@ mod tests {
         let new_expected = vec![
             Diff {
                 text: "h".to_owned(),
-                fg_colour: DiffColour::Green,
+                fg_colour: DiffColour::Neutral,
                 bg_colour: None,
             },
             Diff {
@@ -120,7 +140,7 @@ mod tests {
             },
             Diff {
                 text: "llo".to_owned(),
-                fg_colour: DiffColour::Green,
+                fg_colour: DiffColour::Neutral,
                 bg_colour: None,
             },
         ];
@@ -166,14 +186,14 @@ mod tests {
 
         let old_expected = vec![Diff {
             text: "hello".to_owned(),
-            fg_colour: DiffColour::Red,
+            fg_colour: DiffColour::Neutral,
             bg_colour: None,
         }];
 
         let new_expected = vec![
             Diff {
                 text: "hello".to_owned(),
-                fg_colour: DiffColour::Green,
+                fg_colour: DiffColour::Neutral,
                 bg_colour: None,
             },
             Diff {
@@ -193,7 +213,7 @@ mod tests {
 
         let old_expected = vec![Diff {
             text: "hello".to_owned(),
-            fg_colour: DiffColour::Red,
+            fg_colour: DiffColour::Neutral,
             bg_colour: None,
         }];
 
@@ -205,7 +225,7 @@ mod tests {
             },
             Diff {
                 text: "hello".to_owned(),
-                fg_colour: DiffColour::Green,
+                fg_colour: DiffColour::Neutral,
                 bg_colour: None,
             },
         ];
@@ -1,5 +1,6 @@
 // TODO: make most of the stuff in here pub(crate)
 
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "steel")]
 use steel_derive::Steel;
 use unicode_width::UnicodeWidthStr;
@@ -202,7 +203,7 @@ impl TextField {
         self.error = None;
     }
 
-    fn handle_keys(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+    pub fn handle_keys(&mut self, code: KeyCode, modifiers: KeyModifiers) {
         match (code, modifiers) {
             (KeyCode::Char('w'), KeyModifiers::CONTROL)
             | (KeyCode::Backspace, KeyModifiers::ALT) => {
@@ -272,7 +273,8 @@ impl CheckboxField {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum FieldName {
     Search,
     Replace,
@@ -302,6 +304,9 @@ pub struct SearchField {
     pub name: FieldName,
     pub field: Field,
     pub set_by_cli: bool,
+    /// Whether the field is omitted from the search screen (and from focus), as configured with
+    /// `search.hidden_fields`
+    pub hidden: bool,
 }
 
 impl SearchField {
@@ -311,6 +316,7 @@ impl SearchField {
             name,
             field,
             set_by_cli,
+            hidden: false,
         }
     }
 
@@ -320,6 +326,7 @@ impl SearchField {
             name,
             field,
             set_by_cli,
+            hidden: false,
         }
     }
 
@@ -353,6 +360,11 @@ impl SearchField {
         }
     }
 
+    /// Whether the field can be focussed, i.e. it isn't hidden or locked after being populated via the CLI
+    fn is_focusable(&self, disable_prepopulated_fields: bool) -> bool {
+        !(self.hidden || (self.set_by_cli && disable_prepopulated_fields))
+    }
+
     pub fn error(&self) -> Option<FieldError> {
         match &self.field {
             Field::Text(f) => f.error.clone(),
@@ -421,6 +433,7 @@ impl SearchFields {
     define_field_accessor!(exclude_files, FieldName::ExcludeFiles, Text, &TextField);
 
     define_field_accessor_mut!(search_mut, FieldName::Search, Text, &mut TextField);
+    define_field_accessor_mut!(replace_mut, FieldName::Replace, Text, &mut TextField);
     define_field_accessor_mut!(
         include_files_mut,
         FieldName::IncludeFiles,
@@ -483,21 +496,73 @@ impl SearchFields {
         }
     }
 
+    /// Resets every field to its default value, skipping fields that were populated
+    /// via the CLI and are still locked
+    pub fn clear(&mut self, disable_prepopulated_fields: bool) {
+        let defaults = Self::with_values(&SearchFieldValues::default(), false);
+        for field in &mut self.fields {
+            if field.set_by_cli && disable_prepopulated_fields {
+                continue;
+            }
+            let default = defaults
+                .fields
+                .iter()
+                .find(|default| default.name == field.name)
+                .expect("Couldn't find field");
+            field.field = default.field.clone();
+        }
+        self.highlighted =
+            Self::initial_highlight_position(&self.fields, disable_prepopulated_fields);
+    }
+
     fn initial_highlight_position(
         fields: &[SearchField],
         disable_prepopulated_fields: bool,
     ) -> usize {
-        if disable_prepopulated_fields {
-            fields
+        fields
+            .iter()
+            .position(|field| field.is_focusable(disable_prepopulated_fields))
+            .unwrap_or(0)
+    }
+
+    /// Reorders the fields, which affects both the order in which they are shown and the order in which
+    /// focus moves between them. Fields in `order` come first, followed by any remaining fields in
+    /// their default order. The highlighted field stays the same
+    pub fn reorder_fields(&mut self, order: &[FieldName]) {
+        let highlighted = self.highlighted_field().name.clone();
+        self.fields.sort_by_key(|field| {
+            order
                 .iter()
-                .enumerate()
-                .find_map(|(idx, field)| if !field.set_by_cli { Some(idx) } else { None })
-                .unwrap_or(0)
-        } else {
-            0
+                .position(|name| *name == field.name)
+                .unwrap_or(order.len())
+        });
+        self.highlighted = self
+            .fields
+            .iter()
+            .position(|field| field.name == highlighted)
+            .expect("Couldn't find field");
+    }
+
+    /// Hides the given fields from the search screen, moving focus away from the highlighted field
+    /// if it is now hidden. Hidden fields keep their values
+    pub fn hide_fields(&mut self, names: &[FieldName], disable_prepopulated_fields: bool) {
+        for field in &mut self.fields {
+            field.hidden = names.contains(&field.name);
+        }
+        if self.highlighted_field().hidden {
+            self.highlighted =
+                Self::initial_highlight_position(&self.fields, disable_prepopulated_fields);
         }
     }
 
+    /// The fields shown on the search screen, along with their indices into `fields`
+    pub fn visible_fields(&self) -> impl Iterator<Item = (usize, &SearchField)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| !field.hidden)
+    }
+
     pub fn highlighted_field(&self) -> &SearchField {
         &self.fields[self.highlighted]
     }
@@ -515,14 +580,22 @@ impl SearchFields {
 
         let initial = self.highlighted;
         let mut next = (initial + step).rem_euclid(self.fields.len());
-        if disable_prepopulated_fields {
-            while self.fields[next].set_by_cli && next != initial {
-                next = (next + step).rem_euclid(self.fields.len());
-            }
+        while !self.fields[next].is_focusable(disable_prepopulated_fields) && next != initial {
+            next = (next + step).rem_euclid(self.fields.len());
         }
         self.highlighted = next;
     }
 
+    /// Focuses the field with the given name, unless it is hidden, or was populated via the CLI and is
+    /// still locked
+    pub fn focus(&mut self, name: &FieldName, disable_prepopulated_fields: bool) {
+        if let Some(idx) = self.fields.iter().position(|field| {
+            field.name == *name && field.is_focusable(disable_prepopulated_fields)
+        }) {
+            self.highlighted = idx;
+        }
+    }
+
     pub fn focus_next(&mut self, disable_prepopulated_fields: bool) {
         self.focus_impl(false, disable_prepopulated_fields);
     }
@@ -1158,6 +1231,116 @@ mod tests {
         assert_eq!(search_fields.highlighted_field().name, FieldName::MatchCase);
     }
 
+    #[test]
+    fn test_focus_with_hidden_fields() {
+        let mut search_fields = SearchFields::with_values(
+            &SearchFieldValues {
+                search: FieldValue::new("prepopulated", true),
+                ..SearchFieldValues::default()
+            },
+            true,
+        );
+        assert_eq!(search_fields.highlighted_field().name, FieldName::Replace);
+
+        search_fields.hide_fields(&[FieldName::Replace, FieldName::WholeWord], true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::FixedStrings
+        );
+
+        search_fields.focus_next(true);
+        assert_eq!(search_fields.highlighted_field().name, FieldName::MatchCase);
+
+        search_fields.focus_prev(true);
+        search_fields.focus_prev(true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::ExcludeFiles
+        );
+
+        search_fields.focus(&FieldName::WholeWord, true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::ExcludeFiles
+        );
+
+        search_fields.clear(true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::FixedStrings
+        );
+        assert_eq!(
+            search_fields
+                .visible_fields()
+                .map(|(_, field)| field.name.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                FieldName::Search,
+                FieldName::FixedStrings,
+                FieldName::MatchCase,
+                FieldName::IncludeFiles,
+                FieldName::ExcludeFiles,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reorder_fields() {
+        let mut search_fields = SearchFields::with_values(
+            &SearchFieldValues {
+                search: FieldValue::new("prepopulated", true),
+                include_files: FieldValue::new("*.rs", false),
+                ..SearchFieldValues::default()
+            },
+            true,
+        );
+        search_fields.reorder_fields(&[
+            FieldName::IncludeFiles,
+            FieldName::ExcludeFiles,
+            FieldName::Search,
+        ]);
+        assert_eq!(
+            search_fields
+                .fields
+                .iter()
+                .map(|field| field.name.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                FieldName::IncludeFiles,
+                FieldName::ExcludeFiles,
+                FieldName::Search,
+                FieldName::Replace,
+                FieldName::FixedStrings,
+                FieldName::WholeWord,
+                FieldName::MatchCase,
+            ]
+        );
+        assert_eq!(search_fields.highlighted_field().name, FieldName::Replace);
+        assert_eq!(search_fields.search().text(), "prepopulated");
+        assert_eq!(search_fields.include_files().text(), "*.rs");
+
+        search_fields.focus_next(true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::FixedStrings
+        );
+        search_fields.focus_prev(true);
+        search_fields.focus_prev(true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::ExcludeFiles
+        );
+
+        search_fields.clear(true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::IncludeFiles
+        );
+        assert_eq!(search_fields.search().text(), "prepopulated");
+        assert_eq!(search_fields.include_files().text(), "");
+        assert!(search_fields.match_case().checked);
+    }
+
     #[test]
     fn test_focus_with_unlocked_disabled_fields() {
         let mut search_fields = SearchFields::with_values(
@@ -1247,4 +1430,90 @@ mod tests {
         assert_eq!(search_fields.highlighted, 0);
         assert_eq!(search_fields.highlighted_field().name, FieldName::Search);
     }
+
+    #[test]
+    fn test_clear_resets_fields_to_defaults() {
+        let mut search_fields = SearchFields::with_values(
+            &SearchFieldValues {
+                search: FieldValue::new("foo", false),
+                replace: FieldValue::new("bar", false),
+                fixed_strings: FieldValue::new(true, false),
+                match_whole_word: FieldValue::new(true, false),
+                match_case: FieldValue::new(false, false),
+                include_files: FieldValue::new("*.rs", false),
+                exclude_files: FieldValue::new("target", false),
+            },
+            false,
+        );
+        search_fields.highlighted = 4;
+
+        search_fields.clear(false);
+
+        assert_eq!(
+            search_fields,
+            SearchFields::with_values(&SearchFieldValues::default(), false)
+        );
+    }
+
+    #[test]
+    fn test_clear_skips_locked_prepopulated_fields() {
+        let mut search_fields = SearchFields::with_values(
+            &SearchFieldValues {
+                search: FieldValue::new("cli_search", true),
+                replace: FieldValue::new("bar", false),
+                fixed_strings: FieldValue::new(true, true),
+                match_whole_word: FieldValue::new(true, false),
+                match_case: FieldValue::new(false, false),
+                include_files: FieldValue::new("*.rs", false),
+                exclude_files: FieldValue::new("", false),
+            },
+            true,
+        );
+
+        search_fields.clear(true);
+
+        assert_eq!(search_fields.search().text(), "cli_search");
+        assert!(search_fields.fixed_strings().checked);
+        assert_eq!(search_fields.replace().text(), "");
+        assert!(!search_fields.whole_word().checked);
+        assert!(search_fields.match_case().checked);
+        assert_eq!(search_fields.include_files().text(), "");
+        assert_eq!(search_fields.highlighted_field().name, FieldName::Replace);
+
+        search_fields.clear(false);
+        assert_eq!(search_fields.search().text(), "");
+        assert!(!search_fields.fixed_strings().checked);
+    }
+
+    #[test]
+    fn test_focus_named_field() {
+        let mut search_fields = SearchFields::with_values(
+            &SearchFieldValues {
+                search: FieldValue::new("cli_search", true),
+                include_files: FieldValue::new("*.rs", true),
+                ..Default::default()
+            },
+            true,
+        );
+        assert_eq!(search_fields.highlighted_field().name, FieldName::Replace);
+
+        search_fields.focus(&FieldName::ExcludeFiles, true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::ExcludeFiles
+        );
+
+        // Locked fields can't be focused
+        search_fields.focus(&FieldName::IncludeFiles, true);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::ExcludeFiles
+        );
+
+        search_fields.focus(&FieldName::IncludeFiles, false);
+        assert_eq!(
+            search_fields.highlighted_field().name,
+            FieldName::IncludeFiles
+        );
+    }
 }
@@ -0,0 +1,161 @@
+use std::{collections::HashSet, path::PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PickerFile {
+    pub path: PathBuf,
+    /// Whether the file will be searched
+    pub included: bool,
+}
+
+/// State of the screen shown before searching when using `--interactive-filter`, on which the
+/// files that would be searched can be approved or rejected
+#[derive(Debug, Default)]
+pub struct FilePickerState {
+    /// The files that would be searched, or `None` while they are still being listed
+    pub files: Option<Vec<PickerFile>>,
+    selected: usize,
+    pub view_offset: usize,
+    pub num_displayed: Option<usize>,
+}
+
+impl FilePickerState {
+    /// Sets the listed files, all of which are initially included
+    pub fn set_files(&mut self, paths: Vec<PathBuf>) {
+        self.files = Some(
+            paths
+                .into_iter()
+                .map(|path| PickerFile {
+                    path,
+                    included: true,
+                })
+                .collect(),
+        );
+        self.selected = 0;
+        self.view_offset = 0;
+    }
+
+    fn files_len(&self) -> usize {
+        self.files.as_ref().map_or(0, Vec::len)
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub(crate) fn move_selected_down(&mut self) {
+        let end = self.files_len().saturating_sub(1);
+        self.selected = if self.selected >= end {
+            0
+        } else {
+            self.selected + 1
+        };
+    }
+
+    pub(crate) fn move_selected_up(&mut self) {
+        self.selected = if self.selected == 0 {
+            self.files_len().saturating_sub(1)
+        } else {
+            self.selected - 1
+        };
+    }
+
+    pub(crate) fn move_selected_top(&mut self) {
+        self.selected = 0;
+    }
+
+    pub(crate) fn move_selected_bottom(&mut self) {
+        self.selected = self.files_len().saturating_sub(1);
+    }
+
+    pub(crate) fn toggle_selected_inclusion(&mut self) {
+        if let Some(file) = self
+            .files
+            .as_mut()
+            .and_then(|files| files.get_mut(self.selected))
+        {
+            file.included = !file.included;
+        }
+    }
+
+    pub(crate) fn toggle_all_selected(&mut self) {
+        let Some(files) = &mut self.files else {
+            return;
+        };
+        let all_included = files.iter().all(|file| file.included);
+        for file in files {
+            file.included = !all_included;
+        }
+    }
+
+    pub fn num_included(&self) -> usize {
+        self.files
+            .iter()
+            .flatten()
+            .filter(|file| file.included)
+            .count()
+    }
+
+    /// The paths of the included files, or `None` if the files are still being listed
+    pub fn approved_files(&self) -> Option<HashSet<PathBuf>> {
+        let files = self.files.as_ref()?;
+        Some(
+            files
+                .iter()
+                .filter(|file| file.included)
+                .map(|file| file.path.clone())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker_with_files(names: &[&str]) -> FilePickerState {
+        let mut state = FilePickerState::default();
+        state.set_files(names.iter().map(PathBuf::from).collect());
+        state
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        let mut state = picker_with_files(&["a", "b", "c"]);
+        state.move_selected_up();
+        assert_eq!(state.selected(), 2);
+        state.move_selected_down();
+        assert_eq!(state.selected(), 0);
+        state.move_selected_bottom();
+        assert_eq!(state.selected(), 2);
+        state.move_selected_top();
+        assert_eq!(state.selected(), 0);
+    }
+
+    #[test]
+    fn test_toggling_inclusion() {
+        let mut state = picker_with_files(&["a", "b", "c"]);
+        assert_eq!(state.num_included(), 3);
+
+        state.move_selected_down();
+        state.toggle_selected_inclusion();
+        assert_eq!(
+            state.approved_files(),
+            Some(HashSet::from([PathBuf::from("a"), PathBuf::from("c")]))
+        );
+
+        state.toggle_all_selected();
+        assert_eq!(state.num_included(), 3);
+        state.toggle_all_selected();
+        assert_eq!(state.approved_files(), Some(HashSet::new()));
+    }
+
+    #[test]
+    fn test_no_files_while_listing() {
+        let mut state = FilePickerState::default();
+        state.move_selected_down();
+        state.toggle_selected_inclusion();
+        state.toggle_all_selected();
+        assert_eq!(state.selected(), 0);
+        assert_eq!(state.approved_files(), None);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    path::{self, Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, bail};
+
+/// Lists the files within `dir` that are tracked by git, by running `git ls-files` in `dir`. The
+/// returned paths are `dir` joined with each file's path relative to it, so they match the paths
+/// found when walking `dir`. Fails if `dir` isn't within a git repository or git can't be run
+pub fn tracked_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let stdout = run_git(dir, ["ls-files", "-z"])?;
+    stdout
+        .split(|&byte| byte == b'\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| Ok(dir.join(path_from_bytes(path)?)))
+        .collect()
+}
+
+/// Lists the files tracked by git within each of `dirs`, as with [`tracked_files`]. Fails if any of
+/// `dirs` isn't within a git repository or git can't be run
+pub fn tracked_files_in(dirs: &[PathBuf]) -> anyhow::Result<HashSet<PathBuf>> {
+    let mut files = HashSet::new();
+    for dir in dirs {
+        files.extend(tracked_files(dir)?);
+    }
+    Ok(files)
+}
+
+/// Returns the root of the git repository containing `dir`. Fails if `dir` isn't within a git
+/// repository or git can't be run
+pub fn repository_root(dir: &Path) -> anyhow::Result<PathBuf> {
+    let stdout = run_git(dir, ["rev-parse", "--show-toplevel"])?;
+    path_from_bytes(stdout.strip_suffix(b"\n").unwrap_or(&stdout))
+}
+
+/// Stages `paths`, which are relative to the current directory or absolute, in the git
+/// repository at `repository` by running `git add`
+pub fn stage(repository: &Path, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let paths = absolute_paths(paths)?;
+    run_git(
+        repository,
+        ["add", "--"].into_iter().map(Into::into).chain(paths),
+    )?;
+    Ok(())
+}
+
+/// Commits the staged changes to `paths`, and only those, in the git repository at `repository`
+/// with the given message
+pub fn commit(repository: &Path, paths: &[PathBuf], message: &str) -> anyhow::Result<()> {
+    let paths = absolute_paths(paths)?;
+    run_git(
+        repository,
+        ["commit", "-q", "-m", message, "--"]
+            .into_iter()
+            .map(Into::into)
+            .chain(paths),
+    )?;
+    Ok(())
+}
+
+fn absolute_paths(paths: &[PathBuf]) -> anyhow::Result<Vec<OsString>> {
+    paths
+        .iter()
+        .map(|path| {
+            path::absolute(path)
+                .map(PathBuf::into_os_string)
+                .with_context(|| format!("Failed to resolve {}", path.display()))
+        })
+        .collect()
+}
+
+/// Converts a path output by git into a `PathBuf`. Paths on Unix can be any bytes, so are used as
+/// they are, rather than requiring them to be valid UTF-8
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn path_from_bytes(path: &[u8]) -> anyhow::Result<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Ok(PathBuf::from(OsStr::from_bytes(path)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(path: &[u8]) -> anyhow::Result<PathBuf> {
+    let path = std::str::from_utf8(path).context("git output wasn't valid UTF-8")?;
+    Ok(PathBuf::from(path))
+}
+
+/// Runs git in `dir` with the given arguments, returning its output. On failure, the error
+/// contains git's error message
+fn run_git<I, S>(dir: &Path, args: I) -> anyhow::Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        let reason = stderr.strip_prefix("fatal: ").unwrap_or(stderr);
+        bail!("{}: {reason}", dir.display());
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_tracked_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git(root, &["init", "-q"]);
+        fs::create_dir(root.join("src")).unwrap();
+        for file in ["README.md", "src/main.rs", "untracked.txt"] {
+            fs::write(root.join(file), "foo\n").unwrap();
+        }
+        git(root, &["add", "README.md", "src/main.rs"]);
+
+        let mut files = tracked_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, [root.join("README.md"), root.join("src/main.rs")]);
+
+        // Paths are relative to the directory listed, even when it's within the repository
+        assert_eq!(
+            tracked_files(&root.join("src")).unwrap(),
+            [root.join("src").join("main.rs")]
+        );
+
+        assert_eq!(
+            tracked_files_in(&[root.join("src"), root.to_path_buf()]).unwrap(),
+            HashSet::from([root.join("README.md"), root.join("src/main.rs")])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tracked_files_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git(root, &["init", "-q"]);
+        let latin1 = root.join(OsStr::from_bytes(b"caf\xE9.txt"));
+        fs::write(&latin1, "foo\n").unwrap();
+        fs::write(root.join("README.md"), "foo\n").unwrap();
+        git(root, &["add", "."]);
+
+        let mut files = tracked_files(root).unwrap();
+        files.sort();
+        assert_eq!(files, [root.join("README.md"), latin1]);
+    }
+
+    #[test]
+    fn test_stage_and_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git(root, &["init", "-q"]);
+        git(root, &["config", "user.name", "scooter"]);
+        git(root, &["config", "user.email", "scooter@example.com"]);
+        fs::create_dir(root.join("src")).unwrap();
+        for file in ["README.md", "src/main.rs", "other.txt"] {
+            fs::write(root.join(file), "foo\n").unwrap();
+        }
+        git(root, &["add", "other.txt"]);
+
+        assert_eq!(
+            repository_root(&root.join("src"))
+                .unwrap()
+                .canonicalize()
+                .unwrap(),
+            root.canonicalize().unwrap()
+        );
+
+        let paths = [root.join("README.md"), root.join("src/main.rs")];
+        stage(root, &paths).unwrap();
+        commit(root, &paths, "Replace foo").unwrap();
+
+        let committed = run_git(root, ["show", "--name-only", "--format=%s"]).unwrap();
+        assert_eq!(committed, b"Replace foo\n\nREADME.md\nsrc/main.rs\n");
+        // Changes to other files that were already staged aren't committed
+        let staged = run_git(root, ["diff", "--cached", "--name-only"]).unwrap();
+        assert_eq!(staged, b"other.txt\n");
+    }
+
+    #[test]
+    fn test_tracked_files_outside_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let err = tracked_files(temp_dir.path()).unwrap_err();
+        assert!(
+            err.to_string().contains("not a git repository"),
+            "unexpected error: {err}"
+        );
+    }
+}
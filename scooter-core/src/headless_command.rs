@@ -0,0 +1,314 @@
+use std::{borrow::Cow, path::PathBuf};
+
+use crate::{
+    app::AppRunConfig, fields::SearchFields, modified_since, replace::SymlinkHandling,
+    stdin_format::StdinFormat,
+};
+
+/// Builds a `scooter --no-tui` command line that performs the same search and replacement as the
+/// given fields and toggles. `directories` should be `None` when processing stdin, in which case
+/// the command expects the same content to be piped in. Values are quoted for POSIX shells, and
+/// attached to their flags with `=` so that values starting with `-` aren't read as flags
+pub fn headless_command(
+    search_fields: &SearchFields,
+    run_config: &AppRunConfig,
+    directories: Option<&[PathBuf]>,
+) -> String {
+    let mut args = vec!["scooter".to_owned(), "--no-tui".to_owned()];
+    let mut push_value = |flag: &str, value: &str| {
+        args.push(format!("{flag}={}", shell_quote(value)));
+    };
+
+    push_value("--search-text", search_fields.search().text());
+    if run_config.wrap_matches {
+        push_value("--wrap", search_fields.replace().text());
+    } else if !search_fields.replace().text().is_empty() {
+        push_value("--replace-text", search_fields.replace().text());
+    }
+    if directories.is_some() {
+        for (flag, globs) in [
+            ("--files-to-include", search_fields.include_files().text()),
+            ("--files-to-exclude", search_fields.exclude_files().text()),
+        ] {
+            if !globs.is_empty() {
+                push_value(flag, globs);
+            }
+        }
+    }
+    if directories.is_some()
+        && let Some(max_depth) = run_config.max_depth
+    {
+        push_value("--max-depth", &max_depth.to_string());
+    }
+    if directories.is_some()
+        && let Some(modified_since) = run_config.modified_since
+    {
+        push_value(
+            "--modified-since",
+            &modified_since::format_timestamp(modified_since),
+        );
+    }
+    if let Some(require) = &run_config.line_filter.require {
+        push_value("--require", require.as_str());
+    }
+    if let Some(exclude) = &run_config.line_filter.exclude {
+        push_value("--exclude-line", exclude.as_str());
+    }
+    if directories.is_some() {
+        let line_filter = &run_config.line_filter;
+        if let Some(columns) = line_filter.columns {
+            push_value("--columns", &columns.to_string());
+        }
+        if let Some(source) = line_filter.line_ranges.as_ref().and_then(|r| r.source()) {
+            push_value("--line-ranges", &source.to_string_lossy());
+        }
+    }
+    if directories.is_none() {
+        match run_config.stdin_format {
+            StdinFormat::Plain => {}
+            StdinFormat::Headers => push_value("--stdin-format", "headers"),
+            StdinFormat::Grep => push_value("--stdin-format", "grep"),
+            StdinFormat::RgJson => push_value("--stdin-format", "rg-json"),
+        }
+        if run_config.symlinks == SymlinkHandling::Skip {
+            push_value("--symlinks", "skip");
+        }
+    }
+
+    let is_dir = directories.is_some();
+    let flags = [
+        ("--fixed-strings", search_fields.fixed_strings().checked),
+        ("--match-whole-word", search_fields.whole_word().checked),
+        ("--case-insensitive", !search_fields.match_case().checked),
+        ("--advanced-regex", run_config.advanced_regex),
+        ("--multiline", run_config.multiline),
+        ("--delete-empty-result-lines", run_config.delete_empty_lines),
+        (
+            "--interpret-escape-sequences",
+            run_config.interpret_escape_sequences,
+        ),
+        ("--hidden", is_dir && run_config.include_hidden),
+        (
+            "--include-git-folders",
+            is_dir && run_config.include_git_folders,
+        ),
+        ("--text", is_dir && run_config.include_binary),
+        ("--git-tracked-only", is_dir && run_config.git_tracked_only),
+    ];
+    args.extend(
+        flags
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(flag, _)| flag.to_owned()),
+    );
+
+    // The current directory is searched by default, so there's no need to pass it
+    if let Some(directories) = directories
+        && directories != [PathBuf::from(".")]
+    {
+        args.extend(
+            directories
+                .iter()
+                .map(|dir| shell_quote(&dir.to_string_lossy()).into_owned()),
+        );
+    }
+
+    args.join(" ")
+}
+
+/// Quotes `value` for use as a single argument in a POSIX shell, leaving it as-is if it only contains
+/// characters that have no special meaning
+pub fn shell_quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%^".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("'{}'", value.replace('\'', r"'\''")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, SystemTime},
+    };
+
+    use regex::Regex;
+
+    use super::*;
+    use crate::{
+        fields::{FieldValue, SearchFieldValues},
+        search::{ColumnRange, LineFilter, LineRanges},
+    };
+
+    fn search_fields(values: &SearchFieldValues<'_>) -> SearchFields {
+        SearchFields::with_values(values, false)
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("foo/bar.rs"), "foo/bar.rs");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("foo bar"), "'foo bar'");
+        assert_eq!(shell_quote(r"\d+$"), r"'\d+$'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("*.rs"), "'*.rs'");
+    }
+
+    #[test]
+    fn test_directory_command() {
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("fo+", false),
+            replace: FieldValue::new("bar baz", false),
+            fixed_strings: FieldValue::new(false, false),
+            match_whole_word: FieldValue::new(true, false),
+            match_case: FieldValue::new(false, false),
+            include_files: FieldValue::new("*.rs", false),
+            ..SearchFieldValues::default()
+        });
+        let run_config = AppRunConfig {
+            include_hidden: true,
+            git_tracked_only: true,
+            max_depth: Some(2),
+            modified_since: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_706_659_200)),
+            line_filter: LineFilter {
+                require: Some(Regex::new("fn ").unwrap()),
+                exclude: None,
+                columns: Some(ColumnRange {
+                    start: 1,
+                    end: Some(4),
+                }),
+                line_ranges: None,
+            },
+            ..AppRunConfig::default()
+        };
+        assert_eq!(
+            headless_command(
+                &fields,
+                &run_config,
+                Some(&[PathBuf::from("src"), PathBuf::from("my dir")])
+            ),
+            "scooter --no-tui --search-text=fo+ --replace-text='bar baz' --files-to-include='*.rs' \
+             --max-depth=2 --modified-since=2024-01-31T00:00:00Z --require='fn ' --columns=1:4 --match-whole-word --case-insensitive --hidden --git-tracked-only src 'my dir'"
+        );
+    }
+
+    #[test]
+    fn test_line_ranges_command() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let ranges_path = temp_dir.path().join("ranges.txt");
+        std::fs::write(&ranges_path, format!("{}:1-2\n", ranges_path.display())).unwrap();
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            ..SearchFieldValues::default()
+        });
+        let run_config = AppRunConfig {
+            line_filter: LineFilter {
+                line_ranges: Some(Arc::new(LineRanges::from_file(&ranges_path).unwrap())),
+                ..LineFilter::default()
+            },
+            ..AppRunConfig::default()
+        };
+        assert_eq!(
+            headless_command(&fields, &run_config, Some(&[PathBuf::from(".")])),
+            format!(
+                "scooter --no-tui --search-text=foo --line-ranges={}",
+                shell_quote(&ranges_path.to_string_lossy())
+            )
+        );
+        assert_eq!(
+            headless_command(&fields, &run_config, None),
+            "scooter --no-tui --search-text=foo"
+        );
+    }
+
+    #[test]
+    fn test_values_starting_with_hyphen() {
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("-foo", false),
+            replace: FieldValue::new("--bar baz", false),
+            ..SearchFieldValues::default()
+        });
+        assert_eq!(
+            headless_command(
+                &fields,
+                &AppRunConfig::default(),
+                Some(&[PathBuf::from(".")])
+            ),
+            "scooter --no-tui --search-text=-foo --replace-text='--bar baz'"
+        );
+    }
+
+    #[test]
+    fn test_current_directory_is_omitted() {
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            ..SearchFieldValues::default()
+        });
+        assert_eq!(
+            headless_command(
+                &fields,
+                &AppRunConfig::default(),
+                Some(&[PathBuf::from(".")])
+            ),
+            "scooter --no-tui --search-text=foo"
+        );
+    }
+
+    #[test]
+    fn test_wrap_command() {
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("TODO", false),
+            replace: FieldValue::new("/* {} */", false),
+            fixed_strings: FieldValue::new(true, false),
+            ..SearchFieldValues::default()
+        });
+        let run_config = AppRunConfig {
+            wrap_matches: true,
+            ..AppRunConfig::default()
+        };
+        assert_eq!(
+            headless_command(&fields, &run_config, Some(&[PathBuf::from(".")])),
+            "scooter --no-tui --search-text=TODO --wrap='/* {} */' --fixed-strings"
+        );
+    }
+
+    #[test]
+    fn test_delete_empty_lines_command() {
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("debug()", false),
+            fixed_strings: FieldValue::new(true, false),
+            ..SearchFieldValues::default()
+        });
+        let run_config = AppRunConfig {
+            delete_empty_lines: true,
+            ..AppRunConfig::default()
+        };
+        assert_eq!(
+            headless_command(&fields, &run_config, Some(&[PathBuf::from(".")])),
+            "scooter --no-tui --search-text='debug()' --fixed-strings --delete-empty-result-lines"
+        );
+    }
+
+    #[test]
+    fn test_stdin_command() {
+        let fields = search_fields(&SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("bar", false),
+            include_files: FieldValue::new("*.rs", false),
+            ..SearchFieldValues::default()
+        });
+        let run_config = AppRunConfig {
+            include_hidden: true,
+            multiline: true,
+            stdin_format: StdinFormat::Grep,
+            symlinks: SymlinkHandling::Skip,
+            ..AppRunConfig::default()
+        };
+        assert_eq!(
+            headless_command(&fields, &run_config, None),
+            "scooter --no-tui --search-text=foo --replace-text=bar --stdin-format=grep --symlinks=skip --multiline"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, bail};
+use serde::Deserialize;
+
+/// An independent search and replace, read from a `[[jobs]]` table of a TOML file passed with `--jobs`
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// Name used for the job in the summary. Defaults to the search text and its replacement
+    pub name: Option<String>,
+    pub search: String,
+    #[serde(default)]
+    pub replace: String,
+    /// Search with a plain string rather than regex. Also enabled for every job by `--fixed-strings`
+    #[serde(default)]
+    pub fixed_strings: bool,
+    /// Also enabled for every job by `--match-whole-word`
+    #[serde(default)]
+    pub match_whole_word: bool,
+    /// Also enabled for every job by `--case-insensitive`
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Glob patterns, separated by commas, that file paths must match, as with `--files-to-include`
+    #[serde(default)]
+    pub files_to_include: String,
+    /// Glob patterns, separated by commas, that file paths must not match, as with `--files-to-exclude`
+    #[serde(default)]
+    pub files_to_exclude: String,
+}
+
+impl Job {
+    /// The name shown for the job in the summary
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{} -> {}", self.search, self.replace),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobsFile {
+    jobs: Vec<Job>,
+}
+
+/// Parses a jobs file, in which each `[[jobs]]` table is a search and replace to run, such as
+///
+/// ```toml
+/// [[jobs]]
+/// name = "Rename crate"
+/// search = "old_crate::"
+/// replace = "new_crate::"
+/// fixed_strings = true
+/// files_to_include = "*.rs"
+/// ```
+pub fn parse_jobs(toml: &str) -> anyhow::Result<Vec<Job>> {
+    let JobsFile { jobs } = toml::from_str(toml).context("Failed to parse jobs file")?;
+    if jobs.is_empty() {
+        bail!("Jobs file doesn't contain any jobs");
+    }
+    if let Some(idx) = jobs.iter().position(|job| job.search.is_empty()) {
+        bail!("Job {} has empty search text", idx + 1);
+    }
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jobs() {
+        let toml = r#"
+            [[jobs]]
+            name = "Rename crate"
+            search = "old_crate::"
+            replace = "new_crate::"
+            fixed_strings = true
+            files_to_include = "*.rs"
+
+            [[jobs]]
+            search = "fo+"
+            case_insensitive = true
+        "#;
+        let jobs = parse_jobs(toml).unwrap();
+        assert_eq!(
+            jobs,
+            vec![
+                Job {
+                    name: Some("Rename crate".to_owned()),
+                    search: "old_crate::".to_owned(),
+                    replace: "new_crate::".to_owned(),
+                    fixed_strings: true,
+                    files_to_include: "*.rs".to_owned(),
+                    ..Job::default()
+                },
+                Job {
+                    search: "fo+".to_owned(),
+                    case_insensitive: true,
+                    ..Job::default()
+                },
+            ]
+        );
+        assert_eq!(jobs[0].display_name(), "Rename crate");
+        assert_eq!(jobs[1].display_name(), "fo+ -> ");
+    }
+
+    #[test]
+    fn test_parse_jobs_errors() {
+        for (toml, expected) in [
+            ("jobs = []", "Jobs file doesn't contain any jobs"),
+            (
+                "[[jobs]]\nsearch = \"foo\"\n\n[[jobs]]\nsearch = \"\"",
+                "Job 2 has empty search text",
+            ),
+            ("[[jobs]]\nreplace = \"foo\"", "Failed to parse jobs file"),
+            (
+                "[[jobs]]\nsearch = \"foo\"\nmultiline = true",
+                "Failed to parse jobs file",
+            ),
+        ] {
+            assert_eq!(parse_jobs(toml).unwrap_err().to_string(), expected);
+        }
+    }
+}
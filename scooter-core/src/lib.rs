@@ -1,14 +1,24 @@
 pub mod app;
+pub mod command_palette;
 pub mod commands;
 pub mod config;
 pub mod diff;
 pub mod errors;
 pub mod fields;
 pub mod file_content;
+pub mod file_picker;
+pub mod git;
+pub mod headless_command;
+pub mod jobs;
 pub mod keyboard;
 pub mod line_reader;
+pub mod modified_since;
+pub mod mouse;
+pub mod presets;
 pub mod replace;
+pub mod replacement_pairs;
 pub mod run;
 pub mod search;
+pub mod stdin_format;
 pub mod utils;
 pub mod validation;
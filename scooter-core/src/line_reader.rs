@@ -130,6 +130,37 @@ pub fn split_line_ending(line: &[u8]) -> (&[u8], LineEnding) {
     }
 }
 
+/// The byte order mark that some editors, particularly on Windows, write at the start of UTF-8 files
+pub const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Consumes a UTF-8 byte order mark from the start of `reader`, if present, so that it isn't
+/// treated as part of the first line. Returns whether a BOM was found, so that callers writing
+/// the content back out can restore it.
+pub fn skip_utf8_bom<R: BufRead>(reader: &mut R) -> std::io::Result<bool> {
+    let has_bom = reader.fill_buf()?.starts_with(UTF8_BOM);
+    if has_bom {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(has_bom)
+}
+
+/// Splits a UTF-8 byte order mark, if present, from the start of `content`.
+///
+/// # Examples
+///
+/// ```
+/// use scooter_core::line_reader::strip_utf8_bom;
+///
+/// assert_eq!(strip_utf8_bom("\u{FEFF}hello"), ("hello", true));
+/// assert_eq!(strip_utf8_bom("hello"), ("hello", false));
+/// ```
+pub fn strip_utf8_bom(content: &str) -> (&str, bool) {
+    match content.strip_prefix('\u{FEFF}') {
+        Some(stripped) => (stripped, true),
+        None => (content, false),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,4 +337,28 @@ mod tests {
 
         assert!(lines.next().is_none());
     }
+
+    #[test]
+    fn test_skip_utf8_bom() {
+        let mut cursor = Cursor::new(b"\xEF\xBB\xBFfoo\nbar\n".to_vec());
+        assert!(skip_utf8_bom(&mut cursor).unwrap());
+
+        let lines: Vec<_> = cursor.lines_with_endings().map(Result::unwrap).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (b"foo".to_vec(), LineEnding::Lf),
+                (b"bar".to_vec(), LineEnding::Lf)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_utf8_bom_without_bom() {
+        for content in [&b"foo\n"[..], b"", b"\xEF\xBB"] {
+            let mut cursor = Cursor::new(content);
+            assert!(!skip_utf8_bom(&mut cursor).unwrap());
+            assert_eq!(cursor.position(), 0);
+        }
+    }
 }
@@ -0,0 +1,229 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Parses the cutoff passed to `--modified-since`, which is either a duration before `now`, made
+/// up of a number and a unit (`s`, `m`, `h`, `d` or `w`) such as `30m` or `2d`, or a UTC date or
+/// date and time such as `2024-01-31` or `2024-01-31T09:30:00Z`
+pub fn parse_modified_since(s: &str, now: SystemTime) -> anyhow::Result<SystemTime> {
+    let s = s.trim();
+    if s.contains('-') {
+        return parse_timestamp(s);
+    }
+
+    let duration = parse_duration(s).ok_or_else(|| {
+        anyhow!("Invalid time \"{s}\", expected a duration such as 2d or a date such as 2024-01-31")
+    })?;
+    now.checked_sub(duration)
+        .ok_or_else(|| anyhow!("Duration \"{s}\" is too long"))
+}
+
+/// Formats `time` as a UTC timestamp, such as `2024-01-31T09:30:00Z`, that can be parsed by
+/// [`parse_modified_since`]. Times before 1970 are clamped to the Unix epoch
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil_from_days(secs / SECS_PER_DAY);
+    let secs_of_day = secs % SECS_PER_DAY;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit_start = s.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = s.split_at(unit_start);
+    let amount: u64 = amount.parse().ok()?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => SECS_PER_DAY,
+        "w" => 7 * SECS_PER_DAY,
+        _ => return None,
+    };
+    amount.checked_mul(unit_secs).map(Duration::from_secs)
+}
+
+fn parse_timestamp(s: &str) -> anyhow::Result<SystemTime> {
+    let invalid = || {
+        anyhow!("Invalid date \"{s}\", expected e.g. 2024-01-31 or 2024-01-31T09:30:00Z (in UTC)")
+    };
+
+    let (date, time) = match s.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time.strip_suffix('Z').unwrap_or(time))),
+        None => (s, None),
+    };
+
+    let [year, month, day] = parse_numbers(date, '-').ok_or_else(invalid)?;
+    if year < 1970 {
+        bail!("Date \"{s}\" must not be before 1970");
+    }
+    let days = days_from_civil(year, month, day).ok_or_else(invalid)?;
+
+    let secs_of_day = match time {
+        Some(time) => {
+            let (hour, minute, second) =
+                if let Some([hour, minute, second]) = parse_numbers(time, ':') {
+                    (hour, minute, second)
+                } else {
+                    let [hour, minute] = parse_numbers(time, ':').ok_or_else(invalid)?;
+                    (hour, minute, 0)
+                };
+            if hour > 23 || minute > 59 || second > 59 {
+                return Err(invalid());
+            }
+            hour * 3600 + minute * 60 + second
+        }
+        None => 0,
+    };
+
+    Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(days * SECS_PER_DAY + secs_of_day))
+}
+
+/// Parses exactly `N` numbers separated by `separator`
+fn parse_numbers<const N: usize>(s: &str, separator: char) -> Option<[u64; N]> {
+    let numbers = s
+        .split(separator)
+        .map(|part| {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                None
+            } else {
+                part.parse().ok()
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    numbers.try_into().ok()
+}
+
+/// The number of days from 1970-01-01 to the given date, or `None` if the date doesn't exist
+fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let is_leap_year =
+        year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days_in_month = match month {
+        2 if is_leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if day > days_in_month {
+        return None;
+    }
+
+    // Count years from March, so that leap days fall at the end of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    (era * 146_097 + day_of_era).checked_sub(719_468)
+}
+
+/// The date that is `days` days after 1970-01-01, as `(year, month, day)`
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs_since_epoch(time: SystemTime) -> u64 {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn test_parse_durations() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(30 * SECS_PER_DAY);
+        for (input, secs_before_now) in [
+            ("90s", 90),
+            ("30m", 30 * 60),
+            ("2h", 2 * 60 * 60),
+            (" 1d ", SECS_PER_DAY),
+            ("2w", 14 * SECS_PER_DAY),
+        ] {
+            let cutoff = parse_modified_since(input, now).unwrap();
+            assert_eq!(
+                now.duration_since(cutoff).unwrap().as_secs(),
+                secs_before_now,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamps() {
+        let now = SystemTime::now();
+        for (input, expected_secs) in [
+            ("1970-01-01", 0),
+            ("2024-01-31", 1_706_659_200),
+            ("2024-02-29T09:30", 1_709_199_000),
+            ("2024-02-29 09:30:15", 1_709_199_015),
+            ("2000-03-01T00:00:00Z", 951_868_800),
+        ] {
+            let cutoff = parse_modified_since(input, now).unwrap();
+            assert_eq!(secs_since_epoch(cutoff), expected_secs, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let now = SystemTime::now();
+        for input in [
+            "",
+            "5",
+            "5y",
+            "d",
+            "-1d",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-01-31T24:00",
+            "2024-1",
+            "1969-12-31",
+        ] {
+            assert!(parse_modified_since(input, now).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp_round_trips() {
+        for secs in [0, 951_868_800, 1_709_199_015, 4_102_444_799] {
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            let formatted = format_timestamp(time);
+            assert_eq!(
+                parse_modified_since(&formatted, SystemTime::now()).unwrap(),
+                time,
+                "{formatted}"
+            );
+        }
+        assert_eq!(
+            format_timestamp(SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_199_015)),
+            "2024-02-29T09:30:15Z"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+/// A mouse event, with the terminal cell in which it happened
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub column: u16,
+    pub row: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseEventKind {
+    LeftClick,
+    ScrollUp,
+    ScrollDown,
+}
+
+#[cfg(feature = "term")]
+impl MouseEvent {
+    /// Converts a crossterm mouse event, returning `None` for events that aren't handled, such as
+    /// right clicks or drags
+    pub fn from_crossterm(event: crossterm::event::MouseEvent) -> Option<Self> {
+        use crossterm::event::{MouseButton, MouseEventKind as CMouseEventKind};
+
+        let kind = match event.kind {
+            CMouseEventKind::Down(MouseButton::Left) => MouseEventKind::LeftClick,
+            CMouseEventKind::ScrollUp => MouseEventKind::ScrollUp,
+            CMouseEventKind::ScrollDown => MouseEventKind::ScrollDown,
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            column: event.column,
+            row: event.row,
+        })
+    }
+}
+
+/// A rectangular area of the terminal, in cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScreenArea {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl ScreenArea {
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        (self.x..self.x.saturating_add(self.width)).contains(&column)
+            && (self.y..self.y.saturating_add(self.height)).contains(&row)
+    }
+}
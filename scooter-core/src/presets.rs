@@ -0,0 +1,214 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{fs, io::Write, path::Path};
+use tempfile::NamedTempFile;
+
+use crate::fields::{SearchFieldValues, SearchFields};
+
+/// A named snapshot of the search fields, saved from the search screen. Every field other than the
+/// name may be omitted from the presets file, in which case the field's usual default is used
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub search: String,
+    #[serde(default)]
+    pub replace: String,
+    #[serde(default)]
+    pub fixed_strings: bool,
+    #[serde(default)]
+    pub match_whole_word: bool,
+    #[serde(default = "default_match_case")]
+    pub match_case: bool,
+    #[serde(default)]
+    pub include_files: String,
+    #[serde(default)]
+    pub exclude_files: String,
+}
+
+fn default_match_case() -> bool {
+    SearchFieldValues::default().match_case.value
+}
+
+impl Preset {
+    pub fn from_search_fields(name: &str, search_fields: &SearchFields) -> Self {
+        Self {
+            name: name.to_owned(),
+            search: search_fields.search().text().to_owned(),
+            replace: search_fields.replace().text().to_owned(),
+            fixed_strings: search_fields.fixed_strings().checked,
+            match_whole_word: search_fields.whole_word().checked,
+            match_case: search_fields.match_case().checked,
+            include_files: search_fields.include_files().text().to_owned(),
+            exclude_files: search_fields.exclude_files().text().to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PresetsFile {
+    #[serde(default, rename = "preset")]
+    presets: Vec<Preset>,
+}
+
+pub fn load_presets(path: &Path) -> anyhow::Result<Vec<Preset>> {
+    if !fs::exists(path)? {
+        return Ok(vec![]);
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read presets file: {}", path.display()))?;
+    let file: PresetsFile = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse presets file: {}", path.display()))?;
+    Ok(file.presets)
+}
+
+/// Loads the preset called `name` from the presets file at `path`
+pub fn find_preset(path: &Path, name: &str) -> anyhow::Result<Preset> {
+    load_presets(path)?
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .with_context(|| format!("No preset named \"{name}\" in {}", path.display()))
+}
+
+/// Saves `preset` to the presets file at `path`, overwriting any existing preset with the same name.
+/// The file is written to a temporary file first and then moved into place, so that existing presets
+/// are never lost if writing fails part way through.
+pub fn save_preset(path: &Path, preset: Preset) -> anyhow::Result<()> {
+    let mut presets = load_presets(path)?;
+    if let Some(existing) = presets.iter_mut().find(|p| p.name == preset.name) {
+        *existing = preset;
+    } else {
+        presets.push(preset);
+    }
+
+    let contents = toml::to_string_pretty(&PresetsFile { presets })?;
+
+    let parent_dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent_dir)?;
+    let mut temp_file = NamedTempFile::new_in(parent_dir)?;
+    temp_file.write_all(contents.as_bytes())?;
+    temp_file.flush()?;
+    temp_file.persist(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn preset(name: &str, search: &str) -> Preset {
+        Preset {
+            name: name.to_owned(),
+            search: search.to_owned(),
+            replace: "bar".to_owned(),
+            fixed_strings: true,
+            match_whole_word: false,
+            match_case: true,
+            include_files: "*.rs".to_owned(),
+            exclude_files: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_load_presets_missing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let presets = load_presets(&temp_dir.path().join("presets.toml")).unwrap();
+        assert!(presets.is_empty());
+    }
+
+    #[test]
+    fn test_save_preset_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("presets.toml");
+
+        save_preset(&path, preset("first", "foo")).unwrap();
+
+        assert_eq!(load_presets(&path).unwrap(), vec![preset("first", "foo")]);
+    }
+
+    #[test]
+    fn test_save_preset_preserves_existing_presets() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("presets.toml");
+
+        save_preset(&path, preset("first", "foo")).unwrap();
+        save_preset(&path, preset("second", "baz")).unwrap();
+
+        assert_eq!(
+            load_presets(&path).unwrap(),
+            vec![preset("first", "foo"), preset("second", "baz")]
+        );
+    }
+
+    #[test]
+    fn test_save_preset_overwrites_preset_with_same_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("presets.toml");
+
+        save_preset(&path, preset("first", "foo")).unwrap();
+        save_preset(&path, preset("second", "baz")).unwrap();
+        save_preset(&path, preset("first", "qux")).unwrap();
+
+        assert_eq!(
+            load_presets(&path).unwrap(),
+            vec![preset("first", "qux"), preset("second", "baz")]
+        );
+    }
+
+    #[test]
+    fn test_find_preset() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("presets.toml");
+        save_preset(&path, preset("first", "foo")).unwrap();
+        save_preset(&path, preset("second", "baz")).unwrap();
+
+        assert_eq!(
+            find_preset(&path, "second").unwrap(),
+            preset("second", "baz")
+        );
+        assert!(
+            find_preset(&path, "third")
+                .unwrap_err()
+                .to_string()
+                .starts_with("No preset named \"third\"")
+        );
+    }
+
+    #[test]
+    fn test_load_presets_fills_missing_fields_with_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("presets.toml");
+        fs::write(&path, "[[preset]]\nname = \"minimal\"\nsearch = \"foo\"\n").unwrap();
+
+        assert_eq!(
+            load_presets(&path).unwrap(),
+            vec![Preset {
+                name: "minimal".to_owned(),
+                search: "foo".to_owned(),
+                replace: String::new(),
+                fixed_strings: false,
+                match_whole_word: false,
+                match_case: true,
+                include_files: String::new(),
+                exclude_files: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_load_presets_requires_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("presets.toml");
+        fs::write(&path, "[[preset]]\nsearch = \"foo\"\n").unwrap();
+
+        let error = load_presets(&path).unwrap_err();
+        assert!(
+            format!("{error:#}").contains("missing field `name`"),
+            "unexpected error: {error:#}"
+        );
+    }
+}
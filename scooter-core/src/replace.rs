@@ -1,10 +1,13 @@
-use anyhow::Context;
+use anyhow::{Context, bail};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
     fs::{self, File},
-    io::{BufReader, BufWriter, Write},
+    io::{self, BufReader, BufWriter, Write},
+    mem,
     num::NonZero,
+    ops::Range,
     path::{Path, PathBuf},
     sync::{
         Arc,
@@ -23,11 +26,11 @@ use crate::{
     app::{BackgroundProcessingEvent, Event, EventHandlingResult},
     commands::CommandResults,
     file_content::FileContentProvider,
-    line_reader::BufReadExt,
+    line_reader::{self, BufReadExt},
     replace,
     search::{
-        self, FileSearcher, MatchContent, MatchMode, SearchResult, SearchResultWithReplacement,
-        SearchType,
+        self, BinaryFiles, FileSearcher, LineFilter, MatchContent, MatchMode, NonUtf8Lines,
+        SearchResult, SearchResultWithReplacement, SearchType,
     },
 };
 
@@ -50,6 +53,49 @@ fn create_temp_file_in_with_permissions(
     Ok(NamedTempFile::new_in(parent_dir)?)
 }
 
+/// What to do when a file that replacements are being made in is a symbolic link
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkHandling {
+    /// Make the replacements in the file that the link points to, leaving the link itself in place
+    #[default]
+    Follow,
+    /// Leave the file unchanged, reporting an error for each of its results
+    Skip,
+}
+
+impl SymlinkHandling {
+    /// Returns the path of the file that replacements in `path` should be written to
+    fn resolve(self, path: &Path) -> anyhow::Result<Cow<'_, Path>> {
+        if !fs::symlink_metadata(path)?.file_type().is_symlink() {
+            return Ok(Cow::Borrowed(path));
+        }
+        match self {
+            // Writing through the link itself would replace it with a regular file
+            Self::Follow => Ok(Cow::Owned(fs::canonicalize(path)?)),
+            Self::Skip => bail!("Skipped, as the file is a symbolic link"),
+        }
+    }
+}
+
+/// Writes `content` to `path`. If the file already exists, the content is written to a temporary
+/// file in the same directory which then replaces the original, so the file is never left partially
+/// written and its permissions are preserved
+pub fn write_file_atomically(path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    if !path.exists() {
+        fs::write(path, content)?;
+        return Ok(());
+    }
+    let parent_dir = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut temp_file = create_temp_file_in_with_permissions(parent_dir, path)?;
+    temp_file.write_all(content)?;
+    temp_file.as_file().sync_all()?;
+    temp_file.persist(path)?;
+    Ok(())
+}
+
 pub fn split_results(
     results: Vec<SearchResultWithReplacement>,
 ) -> (
@@ -67,7 +113,7 @@ pub fn split_results(
     (replaceable, preview_errored, num_ignored)
 }
 
-fn group_results(
+pub(crate) fn group_results(
     included: Vec<SearchResultWithReplacement>,
 ) -> HashMap<Option<PathBuf>, Vec<SearchResultWithReplacement>> {
     let mut path_groups = HashMap::<Option<PathBuf>, Vec<SearchResultWithReplacement>>::new();
@@ -80,12 +126,15 @@ fn group_results(
     path_groups
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_replace_included<T: Fn(SearchResultWithReplacement) + Send + Sync + 'static>(
     search_results: Vec<SearchResultWithReplacement>,
     cancelled: Arc<AtomicBool>,
     replacements_completed: Arc<AtomicUsize>,
     validation_search_config: Option<FileSearcher>,
     file_content_provider: Arc<dyn FileContentProvider>,
+    delete_empty_lines: bool,
+    symlinks: SymlinkHandling,
     on_completion: T,
 ) -> usize {
     let (included, preview_errored, num_ignored) = split_results(search_results);
@@ -134,11 +183,8 @@ pub fn spawn_replace_included<T: Fn(SearchResultWithReplacement) + Send + Sync +
                     }
                     return;
                 }
-                if let Err(file_err) = replace_in_file(&mut results) {
-                    for res in &mut results {
-                        res.replace_result = Some(ReplaceResult::Error(file_err.to_string()));
-                    }
-                }
+                // On failure, `replace_in_file` marks every result with the error
+                let _ = replace_in_file(&mut results, delete_empty_lines, symlinks);
                 if let Some(path) = path.as_ref() {
                     file_content_provider.invalidate(path);
                 }
@@ -248,6 +294,8 @@ pub struct ReplaceState {
     pub num_ignored: usize,
     pub errors: Vec<SearchResultWithReplacement>,
     pub replacement_errors_pos: usize,
+    /// Files in which at least one replacement was made, sorted and without duplicates
+    pub changed_paths: Vec<PathBuf>,
 }
 
 impl ReplaceState {
@@ -263,10 +311,37 @@ impl ReplaceState {
                 self.scroll_replacement_errors_up();
                 EventHandlingResult::Rerender
             }
+            // Handled by the app, as these open a popup or start a replacement
+            CommandResults::ShowErrorDetails | CommandResults::RetryFailed => {
+                EventHandlingResult::None
+            }
             CommandResults::Quit => EventHandlingResult::Exit(None),
         }
     }
 
+    /// Takes the failed replacements so that they can be retried, clearing their results
+    pub(crate) fn take_errors_for_retry(&mut self) -> Vec<SearchResultWithReplacement> {
+        self.replacement_errors_pos = 0;
+        let mut errors = mem::take(&mut self.errors);
+        for error in &mut errors {
+            error.replace_result = None;
+        }
+        errors
+    }
+
+    /// Adds the successes and ignored results from a previous run to this one
+    pub(crate) fn add_previous_counts(&mut self, num_successes: usize, num_ignored: usize) {
+        self.num_successes += num_successes;
+        self.num_ignored += num_ignored;
+    }
+
+    /// The location and full message of the error at the top of the errors list
+    pub fn selected_error_details(&self) -> Option<(String, String)> {
+        let error = self.errors.get(self.replacement_errors_pos)?;
+        let (path_display, message) = error.display_error();
+        Some((path_display, message.to_owned()))
+    }
+
     pub fn scroll_replacement_errors_up(&mut self) {
         if self.replacement_errors_pos == 0 {
             self.replacement_errors_pos = self.errors.len();
@@ -290,6 +365,9 @@ pub struct PerformingReplacementState {
     pub replacement_started: Instant,
     pub num_replacements_completed: Arc<AtomicUsize>,
     pub total_replacements: usize,
+    /// When retrying failed replacements, the successes and ignored results from the original
+    /// run, which are added to the results of the retry
+    pub previous_counts: Option<(usize, usize)>,
 }
 
 impl PerformingReplacementState {
@@ -305,10 +383,12 @@ impl PerformingReplacementState {
             replacement_started: Instant::now(),
             num_replacements_completed,
             total_replacements,
+            previous_counts: None,
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn perform_replacement(
     search_results: Vec<SearchResultWithReplacement>,
     background_processing_sender: UnboundedSender<BackgroundProcessingEvent>,
@@ -317,6 +397,9 @@ pub fn perform_replacement(
     event_sender: UnboundedSender<Event>,
     validation_search_config: Option<FileSearcher>,
     file_content_provider: Arc<dyn FileContentProvider>,
+    delete_empty_lines: bool,
+    symlinks: SymlinkHandling,
+    render_interval: Duration,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
@@ -326,12 +409,14 @@ pub fn perform_replacement(
             replacements_completed,
             validation_search_config,
             file_content_provider,
+            delete_empty_lines,
+            symlinks,
             move |result| {
                 let _ = tx.send(result); // Ignore error if receiver is dropped
             },
         );
 
-        let mut rerender_interval = tokio::time::interval(Duration::from_millis(92)); // Slightly random duration so that time taken isn't a round number
+        let mut rerender_interval = tokio::time::interval(render_interval);
 
         let mut replacement_results = Vec::new();
         loop {
@@ -356,6 +441,7 @@ pub fn perform_replacement(
                 num_ignored,
                 errors: stats.errors,
                 replacement_errors_pos: 0,
+                changed_paths: stats.changed_paths,
             },
         ));
     })
@@ -405,9 +491,32 @@ fn mark_conflicting_replacements(results: &mut [SearchResultWithReplacement]) {
     }
 }
 
+/// Replaces each included result in the file that the results belong to.
+///
+/// Only the replaced text changes: every other byte is written back exactly as read, including
+/// each line's original line ending, trailing whitespace, trailing blank lines, and whether or
+/// not the file ends with a newline.
+///
+/// If `delete_empty_lines` is set, lines left empty by a line-mode replacement are removed along
+/// with their line ending (see [`removes_line`]).
+///
+/// If the file is a symbolic link, `symlinks` determines whether the file it points to is replaced
+/// in or the file is skipped.
+///
+/// Results whose text has changed since the search are marked with an error and left as they are,
+/// while the file's other replacements are still made. The new contents are written to a temporary
+/// file which then atomically replaces the original, so the file is never left partially written.
+/// If an error is returned, the file is left unchanged and every result is marked with the error.
+/// Files in which no replacement changes any text, for instance because the file has changed since
+/// the search, are never rewritten.
+///
 /// NOTE: this should only be called with search results from the same file
 // TODO: enforce the above via types
-pub fn replace_in_file(results: &mut [SearchResultWithReplacement]) -> anyhow::Result<()> {
+pub fn replace_in_file(
+    results: &mut [SearchResultWithReplacement],
+    delete_empty_lines: bool,
+    symlinks: SymlinkHandling,
+) -> anyhow::Result<()> {
     let file_path = match results {
         [r, ..] => r.search_result.path.clone(),
         [] => return Ok(()),
@@ -415,18 +524,44 @@ pub fn replace_in_file(results: &mut [SearchResultWithReplacement]) -> anyhow::R
     assert!(results.iter().all(|r| r.search_result.path == file_path));
 
     let file_path = file_path.expect("File path must be present when searching in files");
-
-    match search::match_mode_of_results(results).expect("replace_in_file called with empty results")
-    {
-        MatchMode::Line => replace_line_mode(&file_path, results),
-        MatchMode::ByteRange => replace_byte_mode(&file_path, results),
+    let replaced = symlinks.resolve(&file_path).and_then(|file_path| {
+        match search::match_mode_of_results(results)
+            .expect("replace_in_file called with empty results")
+        {
+            MatchMode::Line => replace_line_mode(&file_path, results, delete_empty_lines),
+            MatchMode::ByteRange => replace_byte_mode(&file_path, results),
+        }
+    });
+    if let Err(e) = &replaced {
+        // The file is only replaced once every replacement has been written, so none of them
+        // have been made
+        for res in results.iter_mut() {
+            res.replace_result = Some(ReplaceResult::Error(e.to_string()));
+        }
     }
+    replaced
+}
+
+/// Flushes `writer` and syncs its contents to disk, then moves `temp_file` (which `writer` writes
+/// to) into place at `file_path`, so that the file is replaced either in full or not at all
+fn persist_temp_file(
+    writer: BufWriter<File>,
+    temp_file: NamedTempFile,
+    file_path: &Path,
+) -> anyhow::Result<()> {
+    let output = writer
+        .into_inner()
+        .map_err(io::IntoInnerError::into_error)?;
+    output.sync_all()?;
+    temp_file.persist(file_path)?;
+    Ok(())
 }
 
 /// Line-mode replacement: Replace ALL occurrences on the line
 fn replace_line_mode(
     file_path: &Path,
     results: &mut [SearchResultWithReplacement],
+    delete_empty_lines: bool,
 ) -> anyhow::Result<()> {
     debug_assert!(
         results.iter().all(|r| r.preview_error.is_none()),
@@ -440,12 +575,17 @@ fn replace_line_mode(
     let parent_dir = file_path.parent().unwrap_or(Path::new("."));
     let temp_output_file = create_temp_file_in_with_permissions(parent_dir, file_path)?;
 
-    {
+    let (writer, changed) = {
         let input = File::open(file_path)?;
-        let reader = BufReader::new(input);
+        let mut reader = BufReader::new(input);
 
         let output = File::create(temp_output_file.path())?;
         let mut writer = BufWriter::new(output);
+        let mut changed = false;
+
+        if line_reader::skip_utf8_bom(&mut reader)? {
+            writer.write_all(line_reader::UTF8_BOM)?;
+        }
 
         for (idx, line_result) in reader.lines_with_endings().enumerate() {
             let line_number = idx + 1;
@@ -457,8 +597,13 @@ fn replace_line_mode(
                 };
 
                 if line_bytes == content.as_bytes() {
-                    line_bytes = res.replacement.as_bytes().to_vec();
                     res.replace_result = Some(ReplaceResult::Success);
+                    if removes_line(content, &res.replacement, delete_empty_lines) {
+                        changed = true;
+                        continue;
+                    }
+                    changed |= res.replacement != *content;
+                    line_bytes = res.replacement.as_bytes().to_vec();
                 } else {
                     res.replace_result = Some(ReplaceResult::Error(
                         "File changed since last search".to_owned(),
@@ -470,11 +615,23 @@ fn replace_line_mode(
             writer.write_all(&line_bytes)?;
         }
 
-        writer.flush()?;
+        (writer, changed)
+    };
+
+    if !changed {
+        // Leave the file untouched, rather than rewriting it with identical contents and so
+        // updating its modification time
+        return Ok(());
     }
+    persist_temp_file(writer, temp_output_file, file_path)
+}
 
-    temp_output_file.persist(file_path)?;
-    Ok(())
+/// Whether a line with the given `content` should be removed entirely, rather than replaced with
+/// `replacement`, because `delete_empty_lines` is set and the replacement has left it empty. Lines
+/// that still contain text after their matches are replaced, such as the indentation before a
+/// match, are kept, as are lines that were already empty
+pub fn removes_line(content: &str, replacement: &str, delete_empty_lines: bool) -> bool {
+    delete_empty_lines && replacement.is_empty() && !content.is_empty()
 }
 
 /// Byte-mode replacement: Replace only the specific byte range for each match
@@ -508,11 +665,12 @@ fn replace_byte_mode(
     let parent_dir = file_path.parent().unwrap_or(Path::new("."));
     let temp_output_file = create_temp_file_in_with_permissions(parent_dir, file_path)?;
 
-    {
+    let (writer, changed) = {
         let mut input = File::open(file_path)?;
         let output = File::create(temp_output_file.path())?;
         let mut writer = BufWriter::new(output);
         let mut current_pos: usize = 0;
+        let mut changed = false;
 
         for result in to_replace {
             let MatchContent::ByteRange {
@@ -555,6 +713,7 @@ fn replace_byte_mode(
                 writer.write_all(&actual_bytes)?;
             } else {
                 result.replace_result = Some(ReplaceResult::Success);
+                changed |= result.replacement != *content;
                 writer.write_all(result.replacement.as_bytes())?;
             }
             current_pos = *byte_end;
@@ -562,11 +721,13 @@ fn replace_byte_mode(
 
         // Copy remaining bytes
         std::io::copy(&mut input, &mut writer)?;
-        writer.flush()?;
-    }
+        (writer, changed)
+    };
 
-    temp_output_file.persist(file_path)?;
-    Ok(())
+    if !changed {
+        return Ok(());
+    }
+    persist_temp_file(writer, temp_output_file, file_path)
 }
 
 /// Performs search and replace operations in a file
@@ -580,23 +741,46 @@ fn replace_byte_mode(
 /// * `search` - The search pattern (fixed string, regex, or advanced regex)
 /// * `replace` - The replacement string
 /// * `multiline` - Whether to enable multiline replacement (whole-text matching)
+/// * `line_filter` - Filter restricting which lines are replaced in
+/// * `binary_files` - How to handle files that look like binary files
+/// * `delete_empty_lines` - Whether to remove lines left empty by the replacement. Ignored when
+///   `multiline` is set
+/// * `regex_timeout` - How long an advanced regex may spend searching the file before the replacement
+///   is abandoned with [`search::RegexTooSlow`]. Ignored when `multiline` is set
+/// * `non_utf8_lines` - Where to record lines that are skipped because they aren't valid UTF-8, if
+///   anywhere. Ignored when `multiline` is set
 ///
 /// # Returns
 ///
 /// * `Ok(true)` if replacements were made in the file
 /// * `Ok(false)` if no replacements were made (no matches found)
 /// * `Err` if any errors occurred during the operation
+#[allow(clippy::too_many_arguments)]
 pub fn replace_all_in_file(
     file_path: &Path,
     search: &SearchType,
     replace: &str,
     multiline: bool,
+    line_filter: &LineFilter,
+    binary_files: BinaryFiles<'_>,
+    delete_empty_lines: bool,
+    regex_timeout: Option<Duration>,
+    non_utf8_lines: Option<&NonUtf8Lines>,
 ) -> anyhow::Result<bool> {
     if multiline {
-        return replace_in_memory(file_path, search, replace);
+        return replace_in_memory(file_path, search, replace, binary_files);
     }
 
-    replace_line_by_line(file_path, search, replace)
+    replace_line_by_line(
+        file_path,
+        search,
+        replace,
+        line_filter,
+        binary_files,
+        delete_empty_lines,
+        regex_timeout,
+        non_utf8_lines,
+    )
 }
 
 pub fn add_replacement(
@@ -618,18 +802,36 @@ pub fn add_replacement_with_haystack(
             replace_all_if_match(search_result.content.matched_text(), search, replace)?
         }
         MatchContent::ByteRange {
+            lines,
+            match_start_in_first_line,
+            match_end_in_last_line,
             byte_start,
             byte_end,
             ..
         } => {
-            if let Some(haystack) = haystack {
+            let in_context = if let Some(haystack) = haystack {
                 replacement_for_match_in_haystack(search, replace, haystack, *byte_start, *byte_end)
-                    .unwrap_or_else(|| {
-                        replacement_for_match(search_result.content.matched_text(), search, replace)
-                    })
             } else {
+                // Without the full haystack, the lines containing the match still give enough
+                // context for anchors, word boundaries and lookarounds within those lines
+                let mut context = String::new();
+                let mut last_line_start = 0;
+                for (_, line) in lines {
+                    last_line_start = context.len();
+                    context.push_str(&line.content);
+                    context.push_str(line.line_ending.as_str());
+                }
+                replacement_for_match_in_haystack(
+                    search,
+                    replace,
+                    &context,
+                    *match_start_in_first_line,
+                    last_line_start + match_end_in_last_line,
+                )
+            };
+            in_context.unwrap_or_else(|| {
                 replacement_for_match(search_result.content.matched_text(), search, replace)
-            }
+            })
         }
     };
     Some(SearchResultWithReplacement {
@@ -637,15 +839,30 @@ pub fn add_replacement_with_haystack(
         replacement,
         replace_result: None,
         preview_error: None,
+        match_spans: vec![],
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn replace_line_by_line(
     file_path: &Path,
     search: &SearchType,
     replace: &str,
+    line_filter: &LineFilter,
+    binary_files: BinaryFiles<'_>,
+    delete_empty_lines: bool,
+    regex_timeout: Option<Duration>,
+    non_utf8_lines: Option<&NonUtf8Lines>,
 ) -> anyhow::Result<bool> {
-    let search_results = search::search_file(file_path, search, false)?;
+    let search_results = search::search_file_with_options(
+        file_path,
+        search,
+        false,
+        line_filter,
+        binary_files,
+        regex_timeout,
+        non_utf8_lines,
+    )?;
     if !search_results.is_empty() {
         let mut replacement_results = search_results
             .into_iter()
@@ -655,24 +872,44 @@ fn replace_line_by_line(
                 })
             })
             .collect::<Vec<_>>();
-        replace_in_file(&mut replacement_results)?;
+        // Files found by walking a directory are never symbolic links, as links aren't followed
+        replace_in_file(
+            &mut replacement_results,
+            delete_empty_lines,
+            SymlinkHandling::Follow,
+        )?;
         return Ok(true);
     }
 
     Ok(false)
 }
 
-fn replace_in_memory(file_path: &Path, search: &SearchType, replace: &str) -> anyhow::Result<bool> {
-    let content = fs::read_to_string(file_path).with_context(|| {
+fn replace_in_memory(
+    file_path: &Path,
+    search: &SearchType,
+    replace: &str,
+    binary_files: BinaryFiles<'_>,
+) -> anyhow::Result<bool> {
+    let content =
+        fs::read(file_path).with_context(|| format!("Failed to read {}", file_path.display()))?;
+    if binary_files.skips_content(file_path, &content) {
+        return Ok(false);
+    }
+    let content = String::from_utf8(content).with_context(|| {
         format!(
             "Failed to read file as UTF-8 for in-memory replacement: {}",
             file_path.display()
         )
     })?;
-    if let Some(new_content) = replace_all_if_match(&content, search, replace) {
+    let (content, has_bom) = line_reader::strip_utf8_bom(&content);
+    if let Some(new_content) = replace_all_if_match(content, search, replace) {
         let parent_dir = file_path.parent().unwrap_or(Path::new("."));
         let mut temp_file = create_temp_file_in_with_permissions(parent_dir, file_path)?;
+        if has_bom {
+            temp_file.write_all(line_reader::UTF8_BOM)?;
+        }
         temp_file.write_all(new_content.as_bytes())?;
+        temp_file.as_file().sync_all()?;
         temp_file.persist(file_path)?;
         Ok(true)
     } else {
@@ -714,6 +951,20 @@ pub fn replace_all_if_match(line: &str, search: &SearchType, replace: &str) -> O
     }
 }
 
+/// Calculate replacement text for a line containing matches, along with the byte range of each
+/// match within the line.
+///
+/// Behaves like [`replace_all_if_match`], and additionally returns the spans so that previews can
+/// highlight exactly what the pattern matched.
+pub fn replace_all_if_match_with_spans(
+    line: &str,
+    search: &SearchType,
+    replace: &str,
+) -> Option<(String, Vec<Range<usize>>)> {
+    let replacement = replace_all_if_match(line, search, replace)?;
+    Some((replacement, search::match_spans(line, search)))
+}
+
 /// Calculate replacement text for a specific matched substring.
 ///
 /// This is used in byte-mode multiline search where we track individual matches
@@ -787,6 +1038,23 @@ pub fn replacement_for_match_in_haystack(
     }
 }
 
+/// Convert a template such as `/* {} */`, in which `{}` stands for the whole match, into a
+/// replacement for `search`.
+///
+/// Fixed-string searches always match the search text itself, so it is substituted directly. For
+/// patterns, `{}` becomes a reference to the whole match and any `$` in the template is escaped,
+/// so that the rest of the template is inserted literally.
+pub fn wrap_template_replacement(template: &str, search: &SearchType) -> String {
+    match search {
+        SearchType::Fixed(fixed_str) => template.replace("{}", fixed_str),
+        SearchType::Pattern(_) | SearchType::PatternAdvanced(_) => template
+            .split("{}")
+            .map(|part| part.replace('$', "$$"))
+            .collect::<Vec<_>>()
+            .join("${0}"),
+    }
+}
+
 /// Interpret escape sequences in replacement text.
 ///
 /// Converts:
@@ -794,6 +1062,7 @@ pub fn replacement_for_match_in_haystack(
 /// - `\r` → carriage return
 /// - `\t` → tab
 /// - `\\` → literal backslash
+/// - `\u{XXXX}` → the Unicode character with hex code point `XXXX`, e.g. `\u{200B}` for a zero-width space
 ///
 /// Other escape sequences are left as-is (e.g., `\x` remains `\x`).
 pub fn interpret_escapes(s: &str) -> String {
@@ -819,6 +1088,17 @@ pub fn interpret_escapes(s: &str) -> String {
                     chars.next();
                     result.push('\\');
                 }
+                Some('u') => {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    if let Some(unicode_char) = parse_unicode_escape(&mut lookahead) {
+                        chars = lookahead;
+                        result.push(unicode_char);
+                    } else {
+                        // Leave invalid Unicode escapes as-is
+                        result.push('\\');
+                    }
+                }
                 _ => {
                     // Leave unrecognized escape sequences as-is
                     result.push('\\');
@@ -832,10 +1112,32 @@ pub fn interpret_escapes(s: &str) -> String {
     result
 }
 
+/// Parses the `{XXXX}` following `\u`, consuming it from `chars`, and returns the character with that
+/// hex code point. Returns `None` if it isn't made up of 1 to 6 hex digits that form a valid character
+fn parse_unicode_escape(chars: &mut impl Iterator<Item = char>) -> Option<char> {
+    if chars.next()? != '{' {
+        return None;
+    }
+    let mut hex = String::new();
+    loop {
+        match chars.next()? {
+            '}' => break,
+            c if c.is_ascii_hexdigit() && hex.len() < 6 => hex.push(c),
+            _ => return None,
+        }
+    }
+    if hex.is_empty() {
+        return None;
+    }
+    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ReplaceStats {
     pub num_successes: usize,
     pub errors: Vec<SearchResultWithReplacement>,
+    /// Files in which at least one replacement was made, sorted and without duplicates
+    pub changed_paths: Vec<PathBuf>,
 }
 
 pub fn calculate_statistics<I>(results: I) -> ReplaceStats
@@ -844,6 +1146,7 @@ where
 {
     let mut num_successes = 0;
     let mut errors = vec![];
+    let mut changed_paths = BTreeSet::new();
 
     results.into_iter().for_each(|mut res| {
         assert!(
@@ -857,6 +1160,9 @@ where
         match &res.replace_result {
             Some(ReplaceResult::Success) => {
                 num_successes += 1;
+                if let Some(path) = res.search_result.path {
+                    changed_paths.insert(path);
+                }
             }
             None => {
                 res.replace_result = Some(ReplaceResult::Error(
@@ -873,6 +1179,7 @@ where
     ReplaceStats {
         num_successes,
         errors,
+        changed_paths: changed_paths.into_iter().collect(),
     }
 }
 
@@ -889,11 +1196,13 @@ mod tests {
     use crate::{
         line_reader::LineEnding,
         replace::{
-            ReplaceResult, add_replacement, replace_all_if_match, replace_all_in_file,
-            replace_in_file, replace_in_memory, replace_line_by_line,
+            ReplaceResult, SymlinkHandling, add_replacement, removes_line, replace_all_if_match,
+            replace_all_if_match_with_spans, replace_all_in_file, replace_in_file,
+            replace_in_memory, replace_line_by_line, write_file_atomically,
         },
         search::{
-            MatchContent, SearchResult, SearchResultWithReplacement, SearchType, search_file,
+            BinaryDetection, BinaryFiles, LineFilter, MatchContent, SearchResult,
+            SearchResultWithReplacement, SearchType, search_file,
         },
     };
 
@@ -903,7 +1212,7 @@ mod tests {
         replace::{self, ReplaceState},
     };
 
-    use super::{interpret_escapes, replacement_for_match_in_haystack};
+    use super::{interpret_escapes, replacement_for_match_in_haystack, wrap_template_replacement};
 
     fn line_content(result: &SearchResult) -> (&str, LineEnding) {
         match &result.content {
@@ -970,6 +1279,24 @@ mod tests {
             assert_eq!(interpret_escapes(r"\u0041"), "\\u0041");
         }
 
+        #[test]
+        fn test_unicode() {
+            assert_eq!(interpret_escapes(r"\u{200B}"), "\u{200B}");
+            assert_eq!(interpret_escapes(r"a\u{a0}b"), "a\u{a0}b");
+            assert_eq!(interpret_escapes(r"\u{1F600}\n"), "😀\n");
+            assert_eq!(interpret_escapes(r"\\u{200B}"), "\\u{200B}");
+        }
+
+        #[test]
+        fn test_invalid_unicode_escapes_left_as_is() {
+            assert_eq!(interpret_escapes(r"\u{}"), "\\u{}");
+            assert_eq!(interpret_escapes(r"\u{200B"), "\\u{200B");
+            assert_eq!(interpret_escapes(r"\u{xyz}"), "\\u{xyz}");
+            assert_eq!(interpret_escapes(r"\u{1234567}"), "\\u{1234567}");
+            // Surrogates aren't valid characters
+            assert_eq!(interpret_escapes(r"\u{D800}\t"), "\\u{D800}\t");
+        }
+
         #[test]
         fn test_trailing_backslash() {
             assert_eq!(interpret_escapes(r"foo\"), "foo\\");
@@ -990,6 +1317,50 @@ mod tests {
         }
     }
 
+    mod wrap_template_replacement_tests {
+        use super::*;
+        use fancy_regex::Regex as FancyRegex;
+        use regex::Regex;
+
+        #[test]
+        fn test_fixed_string() {
+            let search = SearchType::Fixed("TODO".to_string());
+            let replacement = wrap_template_replacement("/* {} */", &search);
+            assert_eq!(replacement, "/* TODO */");
+            assert_eq!(
+                replace_all_if_match("// TODO: fix", &search, &replacement),
+                Some("// /* TODO */: fix".to_string())
+            );
+        }
+
+        #[test]
+        fn test_regex_escapes_dollars() {
+            let search = SearchType::Pattern(Regex::new(r"\d+").unwrap());
+            let replacement = wrap_template_replacement("$[{}] ({})", &search);
+            assert_eq!(replacement, "$$[${0}] (${0})");
+            assert_eq!(
+                replace_all_if_match("a 12 b 3", &search, &replacement),
+                Some("a $[12] (12) b $[3] (3)".to_string())
+            );
+        }
+
+        #[test]
+        fn test_advanced_regex() {
+            let search = SearchType::PatternAdvanced(FancyRegex::new(r"foo(?=bar)").unwrap());
+            let replacement = wrap_template_replacement("<{}>", &search);
+            assert_eq!(
+                replace_all_if_match("foobar foobaz", &search, &replacement),
+                Some("<foo>bar foobaz".to_string())
+            );
+        }
+
+        #[test]
+        fn test_no_placeholder() {
+            let search = SearchType::Pattern(Regex::new("foo").unwrap());
+            assert_eq!(wrap_template_replacement("$1", &search), "$$1");
+        }
+    }
+
     mod replacement_for_match_in_haystack_tests {
         use super::*;
         use fancy_regex::Regex as FancyRegex;
@@ -1061,8 +1432,8 @@ mod tests {
         use crate::file_content::FileContentProvider;
         use crate::line_reader::LineEnding;
         use crate::search::{
-            ByteRangeParams, FileSearcher, Line, ParsedDirConfig, ParsedSearchConfig, SearchResult,
-            SearchResultWithReplacement, SearchType,
+            BinaryDetection, ByteRangeParams, FileSearcher, Line, LineFilter, ParsedDirConfig,
+            ParsedSearchConfig, SearchResult, SearchResultWithReplacement, SearchRoot, SearchType,
         };
         use fancy_regex::Regex as FancyRegex;
         use ignore::overrides::Override;
@@ -1089,11 +1460,22 @@ mod tests {
                 search,
                 replace: replace.to_string(),
                 multiline: true,
+                line_filter: LineFilter::default(),
+                delete_empty_lines: false,
             };
             let dir_config = ParsedDirConfig {
-                overrides: Override::empty(),
-                root_dir: PathBuf::from("."),
+                roots: vec![SearchRoot {
+                    overrides: Override::empty(),
+                    dir: PathBuf::from("."),
+                }],
                 include_hidden: false,
+                include_binary: false,
+                binary_detection: BinaryDetection::default(),
+                max_depth: None,
+                modified_since: None,
+                tracked_files: None,
+                approved_files: None,
+                regex_timeout: None,
             };
             FileSearcher::new(search_config, dir_config)
         }
@@ -1124,6 +1506,7 @@ mod tests {
                 replacement: replacement.to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -1223,6 +1606,7 @@ mod tests {
             replacement: replacement.to_string(),
             replace_result,
             preview_error: None,
+            match_spans: vec![],
         }
     }
 
@@ -1389,6 +1773,7 @@ mod tests {
                 ),
             ],
             replacement_errors_pos: 1,
+            changed_paths: vec![],
         };
 
         state.scroll_replacement_errors_up();
@@ -1436,6 +1821,7 @@ mod tests {
                 ),
             ],
             replacement_errors_pos: 1,
+            changed_paths: vec![],
         };
 
         state.scroll_replacement_errors_down();
@@ -1474,6 +1860,7 @@ mod tests {
                 ),
             ],
             replacement_errors_pos: 0,
+            changed_paths: vec![],
         };
 
         let result = state.handle_command_results(CommandResults::ScrollErrorsDown);
@@ -1488,6 +1875,44 @@ mod tests {
         assert!(matches!(result, EventHandlingResult::Exit(None)));
     }
 
+    #[test]
+    fn test_selected_error_details() {
+        let mut state = ReplaceState {
+            num_successes: 0,
+            num_ignored: 0,
+            errors: vec![
+                create_search_result_with_replacement(
+                    "file1.txt",
+                    1,
+                    "error1",
+                    LineEnding::Lf,
+                    "repl1",
+                    true,
+                    Some(ReplaceResult::Error("err1".to_string())),
+                ),
+                create_search_result_with_replacement(
+                    "file2.txt",
+                    2,
+                    "error2",
+                    LineEnding::Lf,
+                    "repl2",
+                    true,
+                    Some(ReplaceResult::Error("err2".to_string())),
+                ),
+            ],
+            replacement_errors_pos: 1,
+            changed_paths: vec![],
+        };
+        assert_eq!(
+            state.selected_error_details(),
+            Some(("file2.txt:2".to_string(), "err2".to_string()))
+        );
+
+        state.errors.clear();
+        state.replacement_errors_pos = 0;
+        assert_eq!(state.selected_error_details(), None);
+    }
+
     #[test]
     fn test_calculate_statistics_all_success() {
         let results = vec![
@@ -1710,7 +2135,7 @@ mod tests {
         ];
 
         // Perform replacement
-        let result = replace_in_file(&mut results);
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_ok());
 
         // Verify replacements were marked as successful
@@ -1754,7 +2179,7 @@ mod tests {
         ];
 
         // Perform replacement
-        let result = replace_in_file(&mut results);
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_ok());
 
         // Verify replacements were marked as successful
@@ -1799,7 +2224,7 @@ mod tests {
         ];
 
         // Perform replacement
-        let result = replace_in_file(&mut results);
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_ok());
 
         // Verify replacements were marked as successful
@@ -1847,7 +2272,7 @@ mod tests {
         ];
 
         // Perform replacement
-        let result = replace_in_file(&mut results);
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_ok());
 
         // Verify replacements were marked as successful
@@ -1863,6 +2288,192 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_replace_in_file_preserves_trailing_whitespace() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "line 1  \nold text\t \nline 3 \n");
+
+        let mut results = vec![create_search_result_with_replacement(
+            file_path.to_str().unwrap(),
+            2,
+            "old text\t ",
+            LineEnding::Lf,
+            "new text\t ",
+            true,
+            None,
+        )];
+
+        replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+
+        assert_file_content(&file_path, "line 1  \nnew text\t \nline 3 \n");
+    }
+
+    #[test]
+    fn test_replace_in_file_deletes_empty_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(
+            &temp_dir,
+            "test.txt",
+            "line 1\r\ndebug();\r\n  debug();\nline 4\ndebug();",
+        );
+
+        let path = file_path.to_str().unwrap();
+        let mut results = vec![
+            create_search_result_with_replacement(
+                path,
+                2,
+                "debug();",
+                LineEnding::CrLf,
+                "",
+                true,
+                None,
+            ),
+            create_search_result_with_replacement(
+                path,
+                3,
+                "  debug();",
+                LineEnding::Lf,
+                "  ",
+                true,
+                None,
+            ),
+            create_search_result_with_replacement(
+                path,
+                5,
+                "debug();",
+                LineEnding::None,
+                "",
+                true,
+                None,
+            ),
+        ];
+
+        replace_in_file(&mut results, true, SymlinkHandling::Follow).unwrap();
+        assert!(
+            results
+                .iter()
+                .all(|res| res.replace_result == Some(ReplaceResult::Success))
+        );
+
+        // Only lines left completely empty are removed
+        assert_file_content(&file_path, "line 1\r\n  \nline 4\n");
+    }
+
+    #[test]
+    fn test_removes_line() {
+        assert!(removes_line("foo", "", true));
+        assert!(!removes_line("foo", "", false));
+        assert!(!removes_line("  foo", "  ", true));
+        assert!(!removes_line("", "", true));
+    }
+
+    #[test]
+    fn test_replace_in_file_last_line_without_final_newline() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "line 1\r\nline 2\nold text");
+
+        let mut results = vec![create_search_result_with_replacement(
+            file_path.to_str().unwrap(),
+            3,
+            "old text",
+            LineEnding::None,
+            "new text",
+            true,
+            None,
+        )];
+
+        replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+
+        assert_file_content(&file_path, "line 1\r\nline 2\nnew text");
+    }
+
+    #[test]
+    fn test_replace_in_file_preserves_trailing_blank_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "old text\nline 2\n\n  \n\r\n\n");
+
+        let mut results = vec![create_search_result_with_replacement(
+            file_path.to_str().unwrap(),
+            1,
+            "old text",
+            LineEnding::Lf,
+            "new text",
+            true,
+            None,
+        )];
+
+        replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+
+        assert_file_content(&file_path, "new text\nline 2\n\n  \n\r\n\n");
+    }
+
+    #[test]
+    fn test_replace_in_file_uses_line_ending_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "line 1\nold text\r\nline 3\n");
+
+        // The ending recorded in the search result is ignored in favour of the one on disk
+        let mut results = vec![create_search_result_with_replacement(
+            file_path.to_str().unwrap(),
+            2,
+            "old text",
+            LineEnding::Lf,
+            "new text",
+            true,
+            None,
+        )];
+
+        replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+
+        assert_file_content(&file_path, "line 1\nnew text\r\nline 3\n");
+    }
+
+    #[test]
+    fn test_replace_in_file_replacement_inserts_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(
+            &temp_dir,
+            "test.txt",
+            "line 1\nold text\nline 3\nold text\r\nline 5\n",
+        );
+
+        let mut results = vec![
+            create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                2,
+                "old text",
+                LineEnding::Lf,
+                "new\ntext",
+                true,
+                None,
+            ),
+            create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                4,
+                "old text",
+                LineEnding::CrLf,
+                "first\nsecond\nthird",
+                true,
+                None,
+            ),
+        ];
+
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
+        assert!(result.is_ok());
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+        assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
+
+        // Later results still match by their original line numbers
+        assert_file_content(
+            &file_path,
+            "line 1\nnew\ntext\nline 3\nfirst\nsecond\nthird\r\nline 5\n",
+        );
+    }
+
     #[test]
     fn test_replace_in_file_line_mismatch() {
         let temp_dir = TempDir::new().unwrap();
@@ -1879,21 +2490,59 @@ mod tests {
             None,
         )];
 
-        // Perform replacement
-        let result = replace_in_file(&mut results);
+        // Perform replacement
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
+        assert!(result.is_ok());
+
+        // Verify replacement was marked as error
+        assert_eq!(
+            results[0].replace_result,
+            Some(ReplaceResult::Error(
+                "File changed since last search".to_owned()
+            ))
+        );
+
+        // Verify file content is unchanged
+        let new_content = std::fs::read_to_string(&file_path).unwrap();
+        assert_eq!(new_content, "line 1\nactual text\nline 3\n");
+    }
+
+    #[test]
+    fn test_replace_in_file_line_mismatch_still_replaces_other_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "old text\nactual text\nline 3\n");
+
+        let mut results = vec![
+            create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                1,
+                "old text",
+                LineEnding::Lf,
+                "new text",
+                true,
+                None,
+            ),
+            create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                2,
+                "expected text",
+                LineEnding::Lf,
+                "new text",
+                true,
+                None,
+            ),
+        ];
+
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_ok());
-
-        // Verify replacement was marked as error
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
         assert_eq!(
-            results[0].replace_result,
+            results[1].replace_result,
             Some(ReplaceResult::Error(
                 "File changed since last search".to_owned()
             ))
         );
-
-        // Verify file content is unchanged
-        let new_content = std::fs::read_to_string(&file_path).unwrap();
-        assert_eq!(new_content, "line 1\nactual text\nline 3\n");
+        assert_file_content(&file_path, "new text\nactual text\nline 3\n");
     }
 
     #[test]
@@ -1908,7 +2557,7 @@ mod tests {
             None,
         )];
 
-        let result = replace_in_file(&mut results);
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_err());
     }
 
@@ -1924,10 +2573,48 @@ mod tests {
             None,
         )];
 
-        let result = replace_in_file(&mut results);
+        let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_replace_in_file_error_leaves_no_partial_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "old 1\nold 2\n");
+        let mut results = vec![
+            create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                1,
+                "old 1",
+                LineEnding::Lf,
+                "new 1",
+                true,
+                None,
+            ),
+            create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                2,
+                "old 2",
+                LineEnding::Lf,
+                "new 2",
+                true,
+                None,
+            ),
+        ];
+        std::fs::remove_file(&file_path).unwrap();
+
+        let err = replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap_err();
+
+        for res in &results {
+            assert_eq!(
+                res.replace_result,
+                Some(ReplaceResult::Error(err.to_string()))
+            );
+        }
+        // The temporary file is cleaned up rather than left in place of the original
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
     // Tests for replace_in_memory
     #[test]
     fn test_replace_in_memory() {
@@ -1940,7 +2627,12 @@ mod tests {
             "This is a test.\nIt contains search_term that should be replaced.\nMultiple lines with search_term here.",
         );
 
-        let result = replace_in_memory(&file_path, &fixed_search("search_term"), "replacement");
+        let result = replace_in_memory(
+            &file_path,
+            &fixed_search("search_term"),
+            "replacement",
+            BinaryFiles::Search,
+        );
         assert!(result.is_ok());
         assert!(result.unwrap()); // Should return true for modifications
 
@@ -1956,7 +2648,12 @@ mod tests {
             "Number: 123, Code: 456, ID: 789",
         );
 
-        let result = replace_in_memory(&regex_path, &regex_search(r"\d{3}"), "XXX");
+        let result = replace_in_memory(
+            &regex_path,
+            &regex_search(r"\d{3}"),
+            "XXX",
+            BinaryFiles::Search,
+        );
         assert!(result.is_ok());
         assert!(result.unwrap());
 
@@ -1972,7 +2669,12 @@ mod tests {
             "This is a test file with no matches.",
         );
 
-        let result = replace_in_memory(&file_path, &fixed_search("nonexistent"), "replacement");
+        let result = replace_in_memory(
+            &file_path,
+            &fixed_search("nonexistent"),
+            "replacement",
+            BinaryFiles::Search,
+        );
         assert!(result.is_ok());
         assert!(!result.unwrap()); // Should return false for no modifications
 
@@ -1985,7 +2687,12 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let file_path = create_test_file(&temp_dir, "empty.txt", "");
 
-        let result = replace_in_memory(&file_path, &fixed_search("anything"), "replacement");
+        let result = replace_in_memory(
+            &file_path,
+            &fixed_search("anything"),
+            "replacement",
+            BinaryFiles::Search,
+        );
         assert!(result.is_ok());
         assert!(!result.unwrap());
 
@@ -1999,6 +2706,7 @@ mod tests {
             Path::new("/nonexistent/path/file.txt"),
             &fixed_search("test"),
             "replacement",
+            BinaryFiles::Search,
         );
         assert!(result.is_err());
     }
@@ -2015,8 +2723,16 @@ mod tests {
             "This is line one.\nThis contains search_pattern to replace.\nAnother line with search_pattern here.\nFinal line.",
         );
 
-        let result =
-            replace_line_by_line(&file_path, &fixed_search("search_pattern"), "replacement");
+        let result = replace_line_by_line(
+            &file_path,
+            &fixed_search("search_pattern"),
+            "replacement",
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        );
         assert!(result.is_ok());
         assert!(result.unwrap()); // Check that replacement happened
 
@@ -2032,7 +2748,16 @@ mod tests {
             "Line with numbers: 123 and 456.\nAnother line with 789.",
         );
 
-        let result = replace_line_by_line(&regex_path, &regex_search(r"\d{3}"), "XXX");
+        let result = replace_line_by_line(
+            &regex_path,
+            &regex_search(r"\d{3}"),
+            "XXX",
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        );
         assert!(result.is_ok());
         assert!(result.unwrap());
 
@@ -2051,7 +2776,16 @@ mod tests {
             "This is a test file with no matching patterns.",
         );
 
-        let result = replace_line_by_line(&file_path, &fixed_search("nonexistent"), "replacement");
+        let result = replace_line_by_line(
+            &file_path,
+            &fixed_search("nonexistent"),
+            "replacement",
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        );
         assert!(result.is_ok());
         assert!(!result.unwrap());
 
@@ -2064,7 +2798,16 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let file_path = create_test_file(&temp_dir, "empty.txt", "");
 
-        let result = replace_line_by_line(&file_path, &fixed_search("anything"), "replacement");
+        let result = replace_line_by_line(
+            &file_path,
+            &fixed_search("anything"),
+            "replacement",
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        );
         assert!(result.is_ok());
         assert!(!result.unwrap());
 
@@ -2078,6 +2821,11 @@ mod tests {
             Path::new("/nonexistent/path/file.txt"),
             &fixed_search("test"),
             "replacement",
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
         );
         assert!(result.is_err());
     }
@@ -2092,7 +2840,17 @@ mod tests {
             "This is a test file.\nIt has some content to replace.\nThe word replace should be replaced.",
         );
 
-        let result = replace_all_in_file(&file_path, &fixed_search("replace"), "modify", false);
+        let result = replace_all_in_file(
+            &file_path,
+            &fixed_search("replace"),
+            "modify",
+            false,
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        );
         assert!(result.is_ok());
         assert!(result.unwrap());
 
@@ -2102,6 +2860,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_replace_all_in_file_preserves_final_newline_state() {
+        for (content, expected) in [
+            ("foo\nbar foo\n", "baz\nbar baz\n"),
+            ("foo\nbar foo", "baz\nbar baz"),
+            ("foo \nbar\n\n\n", "baz \nbar\n\n\n"),
+        ] {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = create_test_file(&temp_dir, "test.txt", content);
+
+            for multiline in [false, true] {
+                std::fs::write(&file_path, content).unwrap();
+                let replaced = replace_all_in_file(
+                    &file_path,
+                    &fixed_search("foo"),
+                    "baz",
+                    multiline,
+                    &LineFilter::default(),
+                    BinaryFiles::Skip(&BinaryDetection::default()),
+                    false,
+                    None,
+                    None,
+                )
+                .unwrap();
+                assert!(replaced);
+                assert_file_content(&file_path, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_replace_all_in_file_round_trips_mixed_line_endings() {
+        let content = "foo 1\r\nbar\nfoo 2\nbar\r\nfoo 3\r\n\r\nfoo 4";
+        let expected = "baz 1\r\nbar\nbaz 2\nbar\r\nbaz 3\r\n\r\nbaz 4";
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", content);
+
+        for multiline in [false, true] {
+            std::fs::write(&file_path, content).unwrap();
+            let replaced = replace_all_in_file(
+                &file_path,
+                &fixed_search("foo"),
+                "baz",
+                multiline,
+                &LineFilter::default(),
+                BinaryFiles::Skip(&BinaryDetection::default()),
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+            assert!(replaced);
+            assert_eq!(std::fs::read(&file_path).unwrap(), expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_write_file_atomically() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("output.txt");
+
+        write_file_atomically(&file_path, b"first\n").unwrap();
+        assert_file_content(&file_path, "first\n");
+
+        write_file_atomically(&file_path, b"second\n").unwrap();
+        assert_file_content(&file_path, "second\n");
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_replace_all_in_file_preserves_bom() {
+        let content = "\u{FEFF}foo bar\r\nbaz foo\r\n";
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", content);
+
+        let replaced = replace_all_in_file(
+            &file_path,
+            &regex_search("^foo"),
+            "qux",
+            false,
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(replaced);
+        assert_file_content(&file_path, "\u{FEFF}qux bar\r\nbaz foo\r\n");
+
+        std::fs::write(&file_path, content).unwrap();
+        let replaced = replace_all_in_file(
+            &file_path,
+            &regex_search(r"\Afoo"),
+            "qux",
+            true,
+            &LineFilter::default(),
+            BinaryFiles::Skip(&BinaryDetection::default()),
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(replaced);
+        assert_file_content(&file_path, "\u{FEFF}qux bar\r\nbaz foo\r\n");
+    }
+
+    #[test]
+    fn test_replace_in_file_byte_mode_preserves_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "\u{FEFF}foo\nbar foo\n");
+        let search = fixed_search("foo\nbar");
+
+        let mut results: Vec<_> =
+            crate::search::search_file(&file_path, &search, true, &LineFilter::default())
+                .unwrap()
+                .into_iter()
+                .filter_map(|r| add_replacement(r, &search, "baz"))
+                .collect();
+        assert_eq!(results.len(), 1);
+
+        replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
+        assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+        assert_file_content(&file_path, "\u{FEFF}baz foo\n");
+    }
+
     #[test]
     fn test_unicode_in_file() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -2112,7 +2996,7 @@ mod tests {
 
         let search = SearchType::Pattern(Regex::new(r"\p{Greek}+").unwrap());
         let replacement = "GREEK";
-        let results = search_file(temp_file.path(), &search, false)
+        let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
             .unwrap()
             .into_iter()
             .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2123,7 +3007,7 @@ mod tests {
 
         let search = SearchType::Pattern(Regex::new(r"🚀").unwrap());
         let replacement = "ROCKET";
-        let results = search_file(temp_file.path(), &search, false)
+        let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
             .unwrap()
             .into_iter()
             .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2152,7 +3036,7 @@ mod tests {
 
             let search = test_helpers::create_fixed_search("search");
             let replacement = "replace";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2177,7 +3061,7 @@ mod tests {
 
             let search = test_helpers::create_fixed_search("test");
             let replacement = "replaced";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2202,7 +3086,7 @@ mod tests {
 
             let search = SearchType::Fixed("nonexistent".to_string());
             let replacement = "replace";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2221,7 +3105,7 @@ mod tests {
 
             let search = SearchType::Pattern(Regex::new(r"\d+").unwrap());
             let replacement = "XXX";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2245,7 +3129,7 @@ mod tests {
             let search =
                 SearchType::PatternAdvanced(FancyRegex::new(r"(?<=\d{3})abc(?=\d{3})").unwrap());
             let replacement = "REPLACED";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2264,7 +3148,7 @@ mod tests {
 
             let search = SearchType::Fixed("".to_string());
             let replacement = "replace";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2281,7 +3165,7 @@ mod tests {
 
             let search = SearchType::Fixed("line".to_string());
             let replacement = "X";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2300,7 +3184,7 @@ mod tests {
         fn test_search_file_nonexistent() {
             let nonexistent_path = PathBuf::from("/this/file/does/not/exist.txt");
             let search = test_helpers::create_fixed_search("test");
-            let results = search_file(&nonexistent_path, &search, false);
+            let results = search_file(&nonexistent_path, &search, false, &LineFilter::default());
             assert!(results.is_err());
         }
 
@@ -2314,7 +3198,7 @@ mod tests {
 
             let search = SearchType::Fixed("世界".to_string());
             let replacement = "World";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2334,7 +3218,7 @@ mod tests {
 
             let search = test_helpers::create_fixed_search("test");
             let replacement = "replace";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2359,7 +3243,7 @@ mod tests {
 
             let search = SearchType::Fixed("target".to_string());
             let replacement = "found";
-            let results = search_file(temp_file.path(), &search, false)
+            let results = search_file(temp_file.path(), &search, false, &LineFilter::default())
                 .unwrap()
                 .into_iter()
                 .filter_map(|r| add_replacement(r, &search, replacement))
@@ -2419,6 +3303,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2439,6 +3325,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2459,6 +3347,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2483,6 +3373,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2503,6 +3395,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2523,6 +3417,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2543,6 +3439,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2567,6 +3465,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2587,6 +3487,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2607,6 +3509,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2631,6 +3535,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2651,6 +3557,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2671,6 +3579,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2679,6 +3589,33 @@ mod tests {
                         Some("earthWIDE".to_string())
                     );
                 }
+
+                #[test]
+                fn test_replacement_case_is_kept() {
+                    let search_config = SearchConfig {
+                        search_text: "warning",
+                        fixed_strings: true,
+                        match_whole_word: false,
+                        match_case: false,
+                        replacement_text: "Notice",
+                        advanced_regex: false,
+                        multiline: false,
+                        interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
+                    };
+                    let parsed = test_helpers::must_parse_search_config(search_config);
+
+                    // Only matching ignores case: the replacement is written exactly as typed
+                    assert_eq!(
+                        replace_all_if_match(
+                            "warning, Warning, WARNING",
+                            &parsed.search,
+                            &parsed.replace
+                        ),
+                        Some("Notice, Notice, Notice".to_string())
+                    );
+                }
             }
         }
 
@@ -2702,6 +3639,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2723,6 +3662,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2744,6 +3685,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2769,6 +3712,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2790,6 +3735,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2811,6 +3758,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2832,6 +3781,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2858,6 +3809,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2879,6 +3832,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2900,6 +3855,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2925,6 +3882,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2946,6 +3905,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2967,6 +3928,8 @@ mod tests {
                         advanced_regex: false,
                         multiline: false,
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -2975,6 +3938,34 @@ mod tests {
                         Some("SSN: XXX-XX-XXXX".to_string())
                     );
                 }
+
+                #[test]
+                fn test_replacement_case_is_kept() {
+                    let search_config = SearchConfig {
+                        search_text: r"warning: (\w+)",
+                        fixed_strings: false,
+                        match_whole_word: false,
+                        match_case: false,
+                        replacement_text: "Notice ($1)",
+                        advanced_regex: false,
+                        multiline: false,
+                        interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
+                    };
+                    let parsed = test_helpers::must_parse_search_config(search_config);
+
+                    // Literal text in the replacement is written exactly as typed, while capture
+                    // groups keep the case of the text they matched
+                    assert_eq!(
+                        replace_all_if_match(
+                            "WARNING: Disk; warning: cpu",
+                            &parsed.search,
+                            &parsed.replace
+                        ),
+                        Some("Notice (Disk); Notice (cpu)".to_string())
+                    );
+                }
             }
         }
 
@@ -2997,6 +3988,8 @@ mod tests {
                         match_case: true,
                         replacement_text: "domain",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3022,6 +4015,8 @@ mod tests {
                         match_case: true,
                         replacement_text: "report",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3043,6 +4038,8 @@ mod tests {
                         match_case: true,
                         replacement_text: "earth",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3068,6 +4065,8 @@ mod tests {
                         match_case: false,
                         replacement_text: "domain",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3093,6 +4092,8 @@ mod tests {
                         match_case: false,
                         replacement_text: "earth",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3118,6 +4119,8 @@ mod tests {
                         match_case: true,
                         replacement_text: "XX:XX",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3143,6 +4146,8 @@ mod tests {
                         match_case: true,
                         replacement_text: "earth",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3168,6 +4173,8 @@ mod tests {
                         match_case: false,
                         replacement_text: "ERROR",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3193,6 +4200,8 @@ mod tests {
                         match_case: false,
                         replacement_text: "GREEK",
                         interpret_escape_sequences: false,
+                        wrap_matches: false,
+                        delete_empty_lines: false,
                     };
                     let parsed = test_helpers::must_parse_search_config(search_config);
 
@@ -3215,6 +4224,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3234,6 +4245,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3249,6 +4262,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3268,6 +4283,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3283,6 +4300,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3298,6 +4317,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3317,6 +4338,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3332,6 +4355,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3351,6 +4376,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3366,6 +4393,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3385,6 +4414,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3400,6 +4431,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3419,6 +4452,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3434,6 +4469,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3454,6 +4491,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3469,6 +4508,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3489,6 +4530,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3504,6 +4547,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3524,6 +4569,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3539,6 +4586,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3554,6 +4603,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3569,6 +4620,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3590,6 +4643,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3606,6 +4661,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3622,6 +4679,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3642,6 +4701,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3657,6 +4718,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3677,6 +4740,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3692,6 +4757,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3712,6 +4779,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3732,6 +4801,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3752,6 +4823,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3772,6 +4845,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3787,6 +4862,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3807,6 +4884,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3827,6 +4906,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3842,6 +4923,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let parsed = test_helpers::must_parse_search_config(search_config);
             assert_eq!(
@@ -3849,6 +4932,35 @@ mod tests {
                 None
             );
         }
+
+        #[test]
+        fn test_replace_all_if_match_with_spans() {
+            let search_config = SearchConfig {
+                search_text: "world",
+                fixed_strings: true,
+                match_whole_word: true,
+                match_case: false,
+                replacement_text: "earth",
+                advanced_regex: false,
+                multiline: false,
+                interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
+            };
+            let parsed = test_helpers::must_parse_search_config(search_config);
+            assert_eq!(
+                replace_all_if_match_with_spans(
+                    "World worldwide world",
+                    &parsed.search,
+                    &parsed.replace
+                ),
+                Some(("earth worldwide earth".to_string(), vec![0..5, 16..21]))
+            );
+            assert_eq!(
+                replace_all_if_match_with_spans("worldwide", &parsed.search, &parsed.replace),
+                None
+            );
+        }
     }
 
     #[cfg(unix)]
@@ -3880,7 +4992,7 @@ mod tests {
                 None,
             )];
 
-            replace_in_file(&mut results).unwrap();
+            replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
             assert_permissions_preserved(&file_path, 0o644);
         }
 
@@ -3890,7 +5002,9 @@ mod tests {
             let file_path = create_test_file(&temp_dir, "test.txt", "old text\n");
             std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o755)).unwrap();
 
-            let result = replace_in_memory(&file_path, &fixed_search("old"), "new").unwrap();
+            let result =
+                replace_in_memory(&file_path, &fixed_search("old"), "new", BinaryFiles::Search)
+                    .unwrap();
             assert!(result);
             assert_permissions_preserved(&file_path, 0o755);
         }
@@ -3911,10 +5025,21 @@ mod tests {
                 None,
             )];
 
-            replace_in_file(&mut results).unwrap();
+            replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
             assert_permissions_preserved(&file_path, 0o600);
         }
 
+        #[test]
+        fn test_write_file_atomically_preserves_permissions() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = create_test_file(&temp_dir, "test.txt", "old text\n");
+            std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+            write_file_atomically(&file_path, b"new text\n").unwrap();
+            assert_file_content(&file_path, "new text\n");
+            assert_permissions_preserved(&file_path, 0o755);
+        }
+
         #[test]
         fn test_replace_preserves_permissive_permissions() {
             let temp_dir = TempDir::new().unwrap();
@@ -3931,11 +5056,91 @@ mod tests {
                 None,
             )];
 
-            replace_in_file(&mut results).unwrap();
+            replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
             assert_permissions_preserved(&file_path, 0o777);
         }
     }
 
+    #[cfg(unix)]
+    mod symlink_tests {
+        use std::os::unix::fs::symlink;
+
+        use super::*;
+
+        /// Creates `target.txt` containing `old text`, along with `link.txt` pointing to it, and returns
+        /// the paths of the target and the link along with a result to replace in the link
+        fn create_linked_file(
+            temp_dir: &TempDir,
+        ) -> (PathBuf, PathBuf, Vec<SearchResultWithReplacement>) {
+            let target = create_test_file(temp_dir, "target.txt", "old text\n");
+            let link = temp_dir.path().join("link.txt");
+            symlink(&target, &link).unwrap();
+            let results = vec![create_search_result_with_replacement(
+                link.to_str().unwrap(),
+                1,
+                "old text",
+                LineEnding::Lf,
+                "new text",
+                true,
+                None,
+            )];
+            (target, link, results)
+        }
+
+        #[test]
+        fn test_follow_replaces_in_target_and_keeps_link() {
+            let temp_dir = TempDir::new().unwrap();
+            let (target, link, mut results) = create_linked_file(&temp_dir);
+
+            replace_in_file(&mut results, false, SymlinkHandling::Follow).unwrap();
+
+            assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
+            assert_file_content(&target, "new text\n");
+            assert!(
+                std::fs::symlink_metadata(&link)
+                    .unwrap()
+                    .file_type()
+                    .is_symlink()
+            );
+            assert_eq!(std::fs::read_link(&link).unwrap(), target);
+        }
+
+        #[test]
+        fn test_skip_leaves_target_and_link_unchanged() {
+            let temp_dir = TempDir::new().unwrap();
+            let (target, link, mut results) = create_linked_file(&temp_dir);
+
+            let err = replace_in_file(&mut results, false, SymlinkHandling::Skip).unwrap_err();
+
+            assert_eq!(err.to_string(), "Skipped, as the file is a symbolic link");
+            assert_eq!(
+                results[0].replace_result,
+                Some(ReplaceResult::Error(err.to_string()))
+            );
+            assert_file_content(&target, "old text\n");
+            assert_eq!(std::fs::read_link(&link).unwrap(), target);
+        }
+
+        #[test]
+        fn test_regular_files_are_replaced_when_skipping_symlinks() {
+            let temp_dir = TempDir::new().unwrap();
+            let file_path = create_test_file(&temp_dir, "test.txt", "old text\n");
+            let mut results = vec![create_search_result_with_replacement(
+                file_path.to_str().unwrap(),
+                1,
+                "old text",
+                LineEnding::Lf,
+                "new text",
+                true,
+                None,
+            )];
+
+            replace_in_file(&mut results, false, SymlinkHandling::Skip).unwrap();
+
+            assert_file_content(&file_path, "new text\n");
+        }
+    }
+
     mod multiline_replace_tests {
         use super::*;
         use crate::search::{ByteRangeParams, Line, search_multiline};
@@ -3976,6 +5181,7 @@ mod tests {
                 replacement: replacement.to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -4063,6 +5269,7 @@ mod tests {
                 replacement: replacement.to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -4137,7 +5344,7 @@ mod tests {
                 "REPLACED\n",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
 
@@ -4178,7 +5385,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -4220,7 +5427,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // First succeeds, second conflicts
@@ -4282,7 +5489,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // First succeeds (9-11)
@@ -4312,7 +5519,7 @@ mod tests {
                 create_single_line_byte_range_result(&file_path, 1, line_content, 8, 11, 8, "ZZZ"),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // All three should succeed (no byte overlap)
@@ -4336,7 +5543,7 @@ mod tests {
                 create_single_line_byte_range_result(&file_path, 1, line_content, 2, 6, 2, "YYY"),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // First should succeed
@@ -4366,7 +5573,7 @@ mod tests {
                 create_single_line_byte_range_result(&file_path, 1, line_content, 2, 6, 2, "YYY"),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // First should succeed
@@ -4400,6 +5607,7 @@ mod tests {
                     replacement: "XXX\n".to_string(),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 },
                 SearchResultWithReplacement {
                     search_result: SearchResult::new_line(
@@ -4412,10 +5620,11 @@ mod tests {
                     replacement: "YYY\n".to_string(),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 },
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // Both should succeed (no overlap: line 2 > line 1)
@@ -4455,7 +5664,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -4500,7 +5709,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert!(matches!(
@@ -4549,7 +5758,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert!(matches!(
@@ -4581,7 +5790,7 @@ mod tests {
                 "END", // No newline - replacement should not have trailing newline
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
 
@@ -4608,7 +5817,7 @@ mod tests {
                 "REPLACEMENT",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
 
@@ -4655,7 +5864,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -4683,7 +5892,7 @@ mod tests {
                 "REPLACED\n",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert!(matches!(
                 results[0].replace_result,
@@ -4712,7 +5921,7 @@ mod tests {
                 "REPLACED\n",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             // replace_result is None because we hit EOF - calculate_statistics will mark as error
             assert!(results[0].replace_result.is_none());
@@ -4755,7 +5964,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -4790,7 +5999,7 @@ mod tests {
                 ),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -4843,11 +6052,12 @@ mod tests {
                     replacement: "REPLACED".to_string(),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 })
                 .collect();
 
             // Attempt to replace - this will call mark_conflicting_replacements internally
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // All three should succeed (no conflicts due to non-overlapping byte offsets)
@@ -4891,10 +6101,11 @@ mod tests {
                     replacement: "REPLACED".to_string(),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 })
                 .collect();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
 
             // All three should succeed (no conflicts due to non-overlapping byte offsets)
@@ -4950,6 +6161,7 @@ mod tests {
                 replacement: "REPLACED".to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -5208,6 +6420,7 @@ mod tests {
                 replacement: replacement.to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -5227,7 +6440,7 @@ mod tests {
                 "rust",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello rust");
@@ -5245,7 +6458,7 @@ mod tests {
                 create_byte_range_result(file_path.to_str().unwrap(), 1, 1, 12, 15, "qux", "DDD"),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -5269,7 +6482,7 @@ mod tests {
                 "hi",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hi world");
@@ -5300,9 +6513,10 @@ mod tests {
                 replacement: "X".to_string(),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "helloX world");
@@ -5324,7 +6538,7 @@ mod tests {
                 "everyone",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello everyone");
@@ -5350,7 +6564,7 @@ mod tests {
             // Change the file content before replacement
             std::fs::write(&file_path, "hello earth").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert!(matches!(
                 &results[0].replace_result,
@@ -5379,7 +6593,7 @@ mod tests {
             // Truncate file to only "hello" (5 bytes + null at position 5 would be beyond)
             std::fs::write(&file_path, "hello").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             // replace_result is None because we hit EOF - calculate_statistics will mark as error
             assert!(results[0].replace_result.is_none());
@@ -5407,7 +6621,7 @@ mod tests {
 
             std::fs::write(&file_path, "hello world hi wo").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             // Second result is None because we hit EOF - calculate_statistics will mark as error
@@ -5435,7 +6649,7 @@ mod tests {
             // Truncate file to "hello wo" (8 bytes) - partial match
             std::fs::write(&file_path, "hello wo").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             // replace_result is None because we hit EOF - calculate_statistics will mark as error
             assert!(results[0].replace_result.is_none());
@@ -5459,7 +6673,7 @@ mod tests {
             // second is partially there, third is gone
             std::fs::write(&file_path, "foo bar b").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             // First replacement should succeed
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
@@ -5483,7 +6697,7 @@ mod tests {
             // Change content at second match position
             std::fs::write(&file_path, "foo bar qux").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             // First replacement should succeed
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
@@ -5512,7 +6726,7 @@ mod tests {
                 "everyone",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello everyone");
@@ -5535,7 +6749,7 @@ mod tests {
                 "REPLACED\n",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "line1\nREPLACED\nline3\n");
@@ -5558,7 +6772,7 @@ mod tests {
                 "REPLACED\n",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "REPLACED\nline3\n");
@@ -5583,7 +6797,7 @@ mod tests {
             // Empty the file
             std::fs::write(&file_path, "").unwrap();
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             // replace_result is None because we hit EOF - calculate_statistics will mark as error
             assert!(results[0].replace_result.is_none());
@@ -5607,7 +6821,7 @@ mod tests {
                 "rust",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello rust and more");
@@ -5629,7 +6843,7 @@ mod tests {
                 "",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello ");
@@ -5653,7 +6867,7 @@ mod tests {
                 "world",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello world test");
@@ -5675,7 +6889,7 @@ mod tests {
                 "世界",
             )];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_file_content(&file_path, "hello 世界 test");
@@ -5693,7 +6907,7 @@ mod tests {
                 create_byte_range_result(file_path.to_str().unwrap(), 1, 1, 8, 11, "ccc", "語"),
             ];
 
-            let result = replace_in_file(&mut results);
+            let result = replace_in_file(&mut results, false, SymlinkHandling::Follow);
             assert!(result.is_ok());
             assert_eq!(results[0].replace_result, Some(ReplaceResult::Success));
             assert_eq!(results[1].replace_result, Some(ReplaceResult::Success));
@@ -0,0 +1,132 @@
+use std::mem;
+
+use anyhow::bail;
+
+/// A search text and its replacement, read from a row of a CSV file passed with `--replace-from-csv`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplacementPair {
+    pub search: String,
+    pub replace: String,
+}
+
+/// Parses CSV content in which each row is a search text followed by its replacement, such as
+/// `old_name,new_name`. Fields can be wrapped in double quotes to include commas, newlines or
+/// (doubled) double quotes, and blank lines are skipped. There is no header row
+pub fn parse_replacement_pairs(csv: &str) -> anyhow::Result<Vec<ReplacementPair>> {
+    parse_records(csv)?
+        .into_iter()
+        .map(|(line, fields)| {
+            let Ok([search, replace]) = <[String; 2]>::try_from(fields) else {
+                bail!("Line {line} of CSV should have 2 fields: the search text and replacement");
+            };
+            if search.is_empty() {
+                bail!("Line {line} of CSV has empty search text");
+            }
+            Ok(ReplacementPair { search, replace })
+        })
+        .collect()
+}
+
+/// Splits CSV content into records, each with the line number on which it starts
+fn parse_records(csv: &str) -> anyhow::Result<Vec<(usize, Vec<String>)>> {
+    let mut records = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut in_quotes = false;
+
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(mem::take(&mut field));
+                if !matches!(fields.as_slice(), [only] if only.is_empty()) {
+                    records.push((record_line, mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        bail!("Line {record_line} of CSV has an unterminated quoted field");
+    }
+    fields.push(field);
+    if !matches!(fields.as_slice(), [only] if only.is_empty()) {
+        records.push((record_line, fields));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(search: &str, replace: &str) -> ReplacementPair {
+        ReplacementPair {
+            search: search.to_owned(),
+            replace: replace.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse_replacement_pairs() {
+        let csv = "foo,bar\r\n\nold_name,\n\"a,b\",\"say \"\"hi\"\"\"\n\"multi\nline\",x";
+        assert_eq!(
+            parse_replacement_pairs(csv).unwrap(),
+            vec![
+                pair("foo", "bar"),
+                pair("old_name", ""),
+                pair("a,b", "say \"hi\""),
+                pair("multi\nline", "x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_replacement_pairs_errors() {
+        for (csv, expected) in [
+            (
+                "foo,bar\nbaz\n",
+                "Line 2 of CSV should have 2 fields: the search text and replacement",
+            ),
+            (
+                "foo,bar,baz",
+                "Line 1 of CSV should have 2 fields: the search text and replacement",
+            ),
+            ("\n,bar", "Line 2 of CSV has empty search text"),
+            (
+                "foo,bar\n\"baz,qux\n",
+                "Line 2 of CSV has an unterminated quoted field",
+            ),
+        ] {
+            assert_eq!(
+                parse_replacement_pairs(csv).unwrap_err().to_string(),
+                expected
+            );
+        }
+    }
+}
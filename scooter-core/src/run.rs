@@ -1,39 +1,693 @@
-use std::io::Cursor;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+    fs,
+    io::Cursor,
+    mem,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, anyhow, bail};
+use ignore::WalkState;
 
 use crate::{
+    diff,
+    jobs::Job,
     line_reader::BufReadExt,
-    replace::replace_all_if_match,
-    search::{FileSearcher, ParsedDirConfig, ParsedSearchConfig},
+    replace::{self, ReplaceResult, SymlinkHandling, add_replacement, replace_all_if_match},
+    replacement_pairs::ReplacementPair,
+    search::{self, BinaryFiles, FileSearcher, LineFilter, ParsedDirConfig, ParsedSearchConfig},
+    stdin_format::{self, StdinFormat},
     validation::{
         DirConfig, SearchConfig, SimpleErrorHandler, ValidationResult,
         validate_search_configuration,
     },
 };
 
-// Perform a find-and-replace recursively in a given directory
+/// Results of a find-and-replace performed in files. Its `Display` implementation gives the summary
+/// printed when running without the TUI, so that callers can instead format the results themselves
+#[derive(Debug)]
+pub struct HeadlessResults {
+    pub files_updated: FilesUpdated,
+    /// Files in which at least one replacement was made, sorted. When replacing in a shadow directory
+    /// these are the copies, as the originals are left untouched
+    pub changed_files: Vec<PathBuf>,
+    /// Replacements that couldn't be made, ordered by path and then line
+    pub errors: Vec<HeadlessError>,
+    /// Number of files searched that looked like binary files
+    pub num_binary_files: usize,
+    /// Number of directories that weren't searched because they were beyond `max_depth`
+    pub num_dirs_beyond_max_depth: usize,
+    pub max_depth: Option<usize>,
+    /// Number of files skipped because searching them with an advanced regex took longer than the
+    /// `regex_timeout` of the [`DirConfig`]
+    pub num_files_regex_too_slow: usize,
+    /// Files containing lines that weren't searched because they weren't valid UTF-8, with the
+    /// number of such lines in each, sorted by path
+    pub non_utf8_lines: Vec<(PathBuf, usize)>,
+    /// Number of `rg --json` matches skipped because their path wasn't valid UTF-8
+    pub num_non_utf8_paths: usize,
+}
+
+/// The files updated by a find-and-replace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FilesUpdated {
+    /// Number of files updated in place
+    Count(usize),
+    /// Files updated in a shadow directory, at their paths relative to it, along with a unified diff
+    /// of each original against its updated copy. See [`find_and_replace_in_shadow`]
+    Shadow {
+        dir: PathBuf,
+        files: Vec<PathBuf>,
+        diff: String,
+    },
+    /// Number of files updated for each pair in turn. See [`find_and_replace_pairs`]
+    Pairs(Vec<(ReplacementPair, usize)>),
+}
+
+/// A replacement that couldn't be made
+#[derive(Debug)]
+pub struct HeadlessError {
+    pub path: PathBuf,
+    /// The line that couldn't be replaced, or `None` if replacements couldn't be made anywhere in the
+    /// file, e.g. because it couldn't be read
+    pub line: Option<usize>,
+    pub error: anyhow::Error,
+}
+
+impl Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl HeadlessError {
+    fn file_errors(errors: Vec<(PathBuf, anyhow::Error)>) -> impl Iterator<Item = Self> {
+        errors.into_iter().map(|(path, error)| Self {
+            path,
+            line: None,
+            error,
+        })
+    }
+}
+
+impl HeadlessResults {
+    fn new(files_updated: FilesUpdated) -> Self {
+        Self {
+            files_updated,
+            changed_files: vec![],
+            errors: vec![],
+            num_binary_files: 0,
+            num_dirs_beyond_max_depth: 0,
+            max_depth: None,
+            num_files_regex_too_slow: 0,
+            non_utf8_lines: vec![],
+            num_non_utf8_paths: 0,
+        }
+    }
+
+    fn with_changed_files(self, changed_files: Vec<PathBuf>) -> Self {
+        Self {
+            changed_files,
+            ..self
+        }
+    }
+
+    fn with_searcher_counts(self, searcher: &FileSearcher) -> Self {
+        Self {
+            num_binary_files: searcher.num_binary_files_included(),
+            num_dirs_beyond_max_depth: searcher.num_dirs_beyond_max_depth(),
+            max_depth: searcher.max_depth(),
+            num_files_regex_too_slow: searcher.num_files_regex_too_slow(),
+            non_utf8_lines: searcher.non_utf8_lines().files(),
+            ..self
+        }
+    }
+}
+
+impl Display for HeadlessResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.files_updated {
+            FilesUpdated::Count(num_files) => {
+                writeln!(f, "Success: {}", files_updated(*num_files))?;
+            }
+            FilesUpdated::Shadow { dir, files, .. } => {
+                writeln!(
+                    f,
+                    "Success: {} in shadow copy at {}",
+                    files_updated(files.len()),
+                    dir.display()
+                )?;
+                for path in files {
+                    writeln!(f, "  {}", path.display())?;
+                }
+            }
+            FilesUpdated::Pairs(pairs) => {
+                writeln!(f, "Success:")?;
+                for (pair, num_files) in pairs {
+                    writeln!(
+                        f,
+                        "  {} -> {}: {}",
+                        pair.search,
+                        pair.replace,
+                        files_updated(*num_files)
+                    )?;
+                }
+            }
+        }
+        if self.num_binary_files > 0 {
+            writeln!(
+                f,
+                "Searched {num_binary_files} binary file{prefix}",
+                num_binary_files = self.num_binary_files,
+                prefix = if self.num_binary_files != 1 { "s" } else { "" },
+            )?;
+        }
+        if let Some(max_depth) = self.max_depth
+            && self.num_dirs_beyond_max_depth > 0
+        {
+            writeln!(
+                f,
+                "Skipped {num_dirs} director{suffix} beyond max depth of {max_depth}",
+                num_dirs = self.num_dirs_beyond_max_depth,
+                suffix = if self.num_dirs_beyond_max_depth == 1 {
+                    "y"
+                } else {
+                    "ies"
+                },
+            )?;
+        }
+        if !self.non_utf8_lines.is_empty() {
+            let files = self
+                .non_utf8_lines
+                .iter()
+                .map(|(path, num_lines)| (path.display().to_string(), *num_lines))
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", search::non_utf8_lines_message(&files))?;
+        }
+        if self.num_non_utf8_paths > 0 {
+            writeln!(
+                f,
+                "{}",
+                stdin_format::non_utf8_paths_message(self.num_non_utf8_paths)
+            )?;
+        }
+        if self.num_files_regex_too_slow > 0 {
+            writeln!(
+                f,
+                "Regex too slow: skipped {num_files} file{suffix}",
+                num_files = self.num_files_regex_too_slow,
+                suffix = if self.num_files_regex_too_slow == 1 {
+                    ""
+                } else {
+                    "s"
+                },
+            )?;
+        }
+        if !self.errors.is_empty() {
+            writeln!(f, "Errors: {}", self.errors.len())?;
+            for error in &self.errors {
+                writeln!(f, "  {error}")?;
+            }
+        }
+        // The diff can be long, so comes after the rest of the summary
+        if let FilesUpdated::Shadow { diff, .. } = &self.files_updated
+            && !diff.is_empty()
+        {
+            write!(f, "\n{diff}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Results of running several jobs with [`find_and_replace_jobs`]. Its `Display` implementation gives
+/// the summary printed when running without the TUI
+#[derive(Debug)]
+pub struct JobsResults {
+    /// Results of each job that was run, in order
+    pub jobs: Vec<JobResults>,
+    /// Number of jobs that weren't run because an earlier job had errors
+    pub num_jobs_skipped: usize,
+}
+
+/// Results of a single job run with [`find_and_replace_jobs`]
+#[derive(Debug)]
+pub struct JobResults {
+    pub name: String,
+    pub num_files_updated: usize,
+    /// Replacements that couldn't be made, ordered by path
+    pub errors: Vec<HeadlessError>,
+}
+
+impl JobsResults {
+    /// Whether any job had replacements that couldn't be made
+    pub fn has_errors(&self) -> bool {
+        self.jobs.iter().any(|job| !job.errors.is_empty())
+    }
+}
+
+impl Display for JobsResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for job in &self.jobs {
+            write!(f, "{}: {}", job.name, files_updated(job.num_files_updated))?;
+            if job.errors.is_empty() {
+                writeln!(f)?;
+                continue;
+            }
+            writeln!(
+                f,
+                ", {num_errors} error{suffix}",
+                num_errors = job.errors.len(),
+                suffix = if job.errors.len() != 1 { "s" } else { "" },
+            )?;
+            for error in &job.errors {
+                writeln!(f, "  {error}")?;
+            }
+        }
+        if self.num_jobs_skipped > 0 {
+            writeln!(
+                f,
+                "Skipped {num_jobs} job{suffix} after errors",
+                num_jobs = self.num_jobs_skipped,
+                suffix = if self.num_jobs_skipped != 1 { "s" } else { "" },
+            )?;
+        }
+        let total = self.jobs.iter().map(|job| job.num_files_updated).sum();
+        writeln!(f, "Total: {}", files_updated(total))
+    }
+}
+
+// Perform a find-and-replace recursively in the given directories
 pub fn find_and_replace(
     search_config: SearchConfig<'_>,
     dir_config: DirConfig<'_>,
-) -> anyhow::Result<String> {
-    let (parsed_search_config, parsed_dir_config) = parse_config(search_config, Some(dir_config))?;
+    line_filter: &LineFilter,
+) -> anyhow::Result<HeadlessResults> {
+    let (parsed_search_config, parsed_dir_config) =
+        parse_config(search_config, Some(dir_config), line_filter)?;
     let searcher = FileSearcher::new(
         parsed_search_config,
         parsed_dir_config.expect("Found None dir_config when search_type is Files"),
     );
-    let num_files_replaced = searcher.walk_files_and_replace(None);
+    let (changed_files, errors) = searcher.walk_files_and_replace(None);
 
-    Ok(format!(
-        "Success: {num_files_replaced} file{prefix} updated\n",
-        prefix = if num_files_replaced != 1 { "s" } else { "" },
+    Ok(HeadlessResults {
+        errors: HeadlessError::file_errors(errors).collect(),
+        ..HeadlessResults::new(FilesUpdated::Count(changed_files.len()))
+            .with_changed_files(changed_files)
+            .with_searcher_counts(&searcher)
+    })
+}
+
+/// Perform a find-and-replace as with [`find_and_replace`], but on copies of the files with matches
+/// rather than the originals, which are left untouched. Copies are placed in `shadow_dir`, which
+/// must be empty or not yet exist, at their path relative to the searched directory (or, when
+/// searching several directories, at their path as found). The results include a diff of each
+/// original against its updated copy
+pub fn find_and_replace_in_shadow(
+    search_config: SearchConfig<'_>,
+    dir_config: DirConfig<'_>,
+    line_filter: &LineFilter,
+    shadow_dir: &Path,
+) -> anyhow::Result<HeadlessResults> {
+    if fs::read_dir(shadow_dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        bail!("Shadow directory {} must be empty", shadow_dir.display());
+    }
+
+    let (parsed_search_config, parsed_dir_config) =
+        parse_config(search_config, Some(dir_config), line_filter)?;
+    let parsed_dir_config =
+        parsed_dir_config.expect("Found None dir_config when search_type is Files");
+    let roots = parsed_dir_config
+        .roots
+        .iter()
+        .map(|root| root.dir.clone())
+        .collect::<Vec<_>>();
+    let binary_detection =
+        (!parsed_dir_config.include_binary).then(|| parsed_dir_config.binary_detection.clone());
+    let searcher = FileSearcher::new(parsed_search_config, parsed_dir_config);
+
+    let matched_files = Arc::new(Mutex::new(vec![]));
+    searcher.walk_files(None, None, None, || {
+        let matched_files = matched_files.clone();
+        Box::new(move |results| {
+            if let Some(path) = results.into_iter().find_map(|result| result.path) {
+                matched_files.lock().unwrap().push(path);
+            }
+            WalkState::Continue
+        })
+    });
+    let mut matched_files = mem::take(&mut *matched_files.lock().unwrap());
+    matched_files.sort();
+
+    let mut updated_files = vec![];
+    let mut diff = String::new();
+    let mut errors = vec![];
+    for path in matched_files {
+        let relative_path = shadow_path(&path, &roots);
+        let copy = shadow_dir.join(&relative_path);
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::copy(&path, &copy)
+            .with_context(|| format!("Failed to copy {} to {}", path.display(), copy.display()))?;
+        // Copies aren't listed in the line ranges, so are given those of the original
+        let line_filter = match &line_filter.line_ranges {
+            Some(line_ranges) => LineFilter {
+                line_ranges: Some(Arc::new(line_ranges.for_copy(&path, &copy)?)),
+                ..line_filter.clone()
+            },
+            None => line_filter.clone(),
+        };
+        match replace::replace_all_in_file(
+            &copy,
+            searcher.search(),
+            searcher.replace(),
+            searcher.multiline(),
+            &line_filter,
+            binary_detection
+                .as_ref()
+                .map_or(BinaryFiles::Search, BinaryFiles::Skip),
+            searcher.delete_empty_lines(),
+            searcher.regex_timeout(),
+            // Already counted when searching the original files
+            None,
+        ) {
+            Ok(true) => {
+                diff.push_str(&shadow_diff(&path, &copy)?);
+                updated_files.push(relative_path);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                log::error!(
+                    "Found error when performing replacement in {}: {e}",
+                    copy.display()
+                );
+                errors.push((copy, e));
+            }
+        }
+    }
+
+    let changed_files = updated_files
+        .iter()
+        .map(|path| shadow_dir.join(path))
+        .collect();
+    Ok(HeadlessResults {
+        errors: HeadlessError::file_errors(errors).collect(),
+        ..HeadlessResults::new(FilesUpdated::Shadow {
+            dir: shadow_dir.to_path_buf(),
+            files: updated_files,
+            diff,
+        })
+        .with_changed_files(changed_files)
+        .with_searcher_counts(&searcher)
+    })
+}
+
+/// A unified diff of the file at `original` against its updated shadow copy at `copy`. Lines that
+/// aren't valid UTF-8 are diffed with their invalid bytes replaced
+fn shadow_diff(original: &Path, copy: &Path) -> anyhow::Result<String> {
+    let read =
+        |path: &Path| fs::read(path).with_context(|| format!("Failed to read {}", path.display()));
+    let (original_content, copy_content) = (read(original)?, read(copy)?);
+    Ok(diff::unified_diff(
+        &String::from_utf8_lossy(&original_content),
+        &String::from_utf8_lossy(&copy_content),
+        &original.display().to_string(),
+        &copy.display().to_string(),
     ))
 }
 
+/// The path at which to place the copy of `path`, found when walking `roots`, within the shadow
+/// directory. Parent (`..`) and root components are dropped so that copies can't escape it
+fn shadow_path(path: &Path, roots: &[PathBuf]) -> PathBuf {
+    let relative_path = match roots {
+        [root] => path.strip_prefix(root).unwrap_or(path),
+        _ => path,
+    };
+    relative_path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
+
+/// Perform a find-and-replace recursively in the given directories for each pair in turn, in the
+/// order given, so later pairs apply to text produced by earlier ones. The search text and
+/// replacement of `search_config` are ignored, but its other options apply to every pair.
+/// All pairs are validated before any replacements are made
+pub fn find_and_replace_pairs(
+    pairs: &[ReplacementPair],
+    search_config: &SearchConfig<'_>,
+    dir_config: &DirConfig<'_>,
+    line_filter: &LineFilter,
+) -> anyhow::Result<HeadlessResults> {
+    let searchers = pairs
+        .iter()
+        .map(|pair| {
+            let (parsed_search_config, parsed_dir_config) = parse_config(
+                pair_search_config(pair, search_config),
+                Some(dir_config.clone()),
+                line_filter,
+            )
+            .with_context(|| format!("Invalid search text \"{}\"", pair.search))?;
+            Ok(FileSearcher::new(
+                parsed_search_config,
+                parsed_dir_config.expect("Found None dir_config when search_type is Files"),
+            ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut num_files_updated = vec![];
+    let mut changed_files = BTreeSet::new();
+    let mut errors = vec![];
+    let mut num_binary_files = 0;
+    let mut num_dirs_beyond_max_depth = 0;
+    let mut num_files_regex_too_slow = 0;
+    let mut non_utf8_lines = BTreeMap::new();
+    for (pair, searcher) in pairs.iter().zip(searchers) {
+        let (files_replaced, pair_errors) = searcher.walk_files_and_replace(None);
+        let num_files_replaced = files_replaced.len();
+        changed_files.extend(files_replaced);
+        errors.extend(HeadlessError::file_errors(pair_errors));
+        num_binary_files = num_binary_files.max(searcher.num_binary_files_included());
+        num_dirs_beyond_max_depth =
+            num_dirs_beyond_max_depth.max(searcher.num_dirs_beyond_max_depth());
+        num_files_regex_too_slow =
+            num_files_regex_too_slow.max(searcher.num_files_regex_too_slow());
+        // Each pair searches the same files, so the lines skipped in them are only counted once
+        for (path, num_lines) in searcher.non_utf8_lines().files() {
+            let max_num_lines = non_utf8_lines.entry(path).or_default();
+            *max_num_lines = num_lines.max(*max_num_lines);
+        }
+        num_files_updated.push((pair.clone(), num_files_replaced));
+    }
+
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(HeadlessResults {
+        files_updated: FilesUpdated::Pairs(num_files_updated),
+        changed_files: changed_files.into_iter().collect(),
+        errors,
+        num_binary_files,
+        num_dirs_beyond_max_depth,
+        max_depth: dir_config.max_depth,
+        num_files_regex_too_slow,
+        non_utf8_lines: non_utf8_lines.into_iter().collect(),
+        num_non_utf8_paths: 0,
+    })
+}
+
+/// Perform a find-and-replace recursively in the given directories for each job in turn, in the order
+/// given, so later jobs apply to text produced by earlier ones. Each job has its own search text,
+/// replacement and globs, while the other options of `search_config` and `dir_config` apply to every
+/// job. A job's `fixed_strings`, `match_whole_word` and `case_insensitive` enable those options for
+/// that job alone. All jobs are validated before any replacements are made, and if `stop_on_error`
+/// is set then no further jobs are run once a job has replacements that couldn't be made
+pub fn find_and_replace_jobs(
+    jobs: &[Job],
+    search_config: &SearchConfig<'_>,
+    dir_config: &DirConfig<'_>,
+    line_filter: &LineFilter,
+    stop_on_error: bool,
+) -> anyhow::Result<JobsResults> {
+    let searchers = jobs
+        .iter()
+        .map(|job| {
+            let (job_search_config, job_dir_config) = job_configs(job, search_config, dir_config);
+            let (parsed_search_config, parsed_dir_config) =
+                parse_config(job_search_config, Some(job_dir_config), line_filter)
+                    .with_context(|| format!("Invalid job \"{}\"", job.display_name()))?;
+            Ok(FileSearcher::new(
+                parsed_search_config,
+                parsed_dir_config.expect("Found None dir_config when search_type is Files"),
+            ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut results = JobsResults {
+        jobs: vec![],
+        num_jobs_skipped: 0,
+    };
+    for (idx, (job, searcher)) in jobs.iter().zip(searchers).enumerate() {
+        let (files_updated, errors) = searcher.walk_files_and_replace(None);
+        let mut errors = HeadlessError::file_errors(errors).collect::<Vec<_>>();
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+        let failed = !errors.is_empty();
+        results.jobs.push(JobResults {
+            name: job.display_name(),
+            num_files_updated: files_updated.len(),
+            errors,
+        });
+        if failed && stop_on_error {
+            results.num_jobs_skipped = jobs.len() - idx - 1;
+            break;
+        }
+    }
+    Ok(results)
+}
+
+fn job_configs<'a>(
+    job: &'a Job,
+    search_config: &SearchConfig<'a>,
+    dir_config: &DirConfig<'a>,
+) -> (SearchConfig<'a>, DirConfig<'a>) {
+    let job_search_config = SearchConfig {
+        search_text: &job.search,
+        replacement_text: &job.replace,
+        fixed_strings: search_config.fixed_strings || job.fixed_strings,
+        match_whole_word: search_config.match_whole_word || job.match_whole_word,
+        match_case: search_config.match_case && !job.case_insensitive,
+        ..search_config.clone()
+    };
+    let job_dir_config = DirConfig {
+        include_globs: Some(&job.files_to_include),
+        exclude_globs: Some(&job.files_to_exclude),
+        ..dir_config.clone()
+    };
+    (job_search_config, job_dir_config)
+}
+
+/// Perform a find-and-replace in a string slice for each pair in turn, in the order given. See
+/// [`find_and_replace_pairs`]
+pub fn find_and_replace_text_pairs(
+    content: &str,
+    pairs: &[ReplacementPair],
+    search_config: &SearchConfig<'_>,
+    line_filter: &LineFilter,
+) -> anyhow::Result<String> {
+    pairs.iter().try_fold(content.to_owned(), |content, pair| {
+        find_and_replace_text(
+            &content,
+            pair_search_config(pair, search_config),
+            line_filter,
+        )
+        .with_context(|| format!("Invalid search text \"{}\"", pair.search))
+    })
+}
+
+fn pair_search_config<'a>(
+    pair: &'a ReplacementPair,
+    search_config: &SearchConfig<'a>,
+) -> SearchConfig<'a> {
+    SearchConfig {
+        search_text: &pair.search,
+        replacement_text: &pair.replace,
+        ..search_config.clone()
+    }
+}
+
+/// Perform a find-and-replace in the files referenced by framed content, such as the output of
+/// `grep -rn`, rather than in the content itself
+pub fn find_and_replace_framed_text(
+    content: &str,
+    format: StdinFormat,
+    search_config: SearchConfig<'_>,
+    line_filter: &LineFilter,
+    symlinks: SymlinkHandling,
+) -> anyhow::Result<HeadlessResults> {
+    let (parsed_search_config, _) = parse_config(search_config, None, line_filter)?;
+    let framed_search = stdin_format::search_framed(
+        content,
+        format,
+        &parsed_search_config.search,
+        &parsed_search_config.line_filter,
+    );
+    let results = framed_search
+        .results
+        .into_iter()
+        .filter_map(|result| {
+            add_replacement(
+                result,
+                &parsed_search_config.search,
+                &parsed_search_config.replace,
+            )
+        })
+        .collect();
+
+    let mut changed_files = vec![];
+    let mut errors = vec![];
+    for (path, mut results) in replace::group_results(results) {
+        let path = path.unwrap_or_default();
+        let path_display = path.display();
+        if let Err(e) = replace::replace_in_file(
+            &mut results,
+            parsed_search_config.delete_empty_lines,
+            symlinks,
+        ) {
+            log::error!("Found error when performing replacement in {path_display}: {e}");
+            errors.push(HeadlessError {
+                path,
+                line: None,
+                error: e,
+            });
+            continue;
+        }
+        let stats = replace::calculate_statistics(results);
+        for error in &stats.errors {
+            if let Some(ReplaceResult::Error(e)) = &error.replace_result {
+                let line = error.search_result.start_line_number();
+                log::error!("Failed to replace on line {line} of {path_display}: {e}");
+                errors.push(HeadlessError {
+                    path: path.clone(),
+                    line: Some(line),
+                    error: anyhow!("{e}"),
+                });
+            }
+        }
+        if stats.num_successes > 0 {
+            changed_files.push(path);
+        }
+    }
+
+    changed_files.sort();
+    errors.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    Ok(HeadlessResults {
+        errors,
+        num_non_utf8_paths: framed_search.num_non_utf8_paths,
+        ..HeadlessResults::new(FilesUpdated::Count(changed_files.len()))
+            .with_changed_files(changed_files)
+    })
+}
+
+fn files_updated(num_files_replaced: usize) -> String {
+    format!(
+        "{num_files_replaced} file{prefix} updated",
+        prefix = if num_files_replaced != 1 { "s" } else { "" },
+    )
+}
+
 /// Perform a find-and-replace in a string slice
 pub fn find_and_replace_text(
     content: &str,
     search_config: SearchConfig<'_>,
+    line_filter: &LineFilter,
 ) -> anyhow::Result<String> {
-    let (parsed_search_config, _) = parse_config(search_config, None)?;
+    let (parsed_search_config, _) = parse_config(search_config, None, line_filter)?;
 
     // When multiline mode is enabled, perform replacement on the entire content
     if parsed_search_config.multiline {
@@ -59,7 +713,15 @@ pub fn find_and_replace_text(
             &line,
             &parsed_search_config.search,
             &parsed_search_config.replace,
-        ) {
+        ) && parsed_search_config.line_filter.allows(&line)
+        {
+            if replace::removes_line(
+                &line,
+                &replaced_line,
+                parsed_search_config.delete_empty_lines,
+            ) {
+                continue;
+            }
             result.push_str(&replaced_line);
         } else {
             result.push_str(&line);
@@ -74,11 +736,15 @@ pub fn find_and_replace_text(
 fn parse_config(
     search_config: SearchConfig<'_>,
     dir_config: Option<DirConfig<'_>>,
+    line_filter: &LineFilter,
 ) -> anyhow::Result<(ParsedSearchConfig, Option<ParsedDirConfig>)> {
     let mut error_handler = SimpleErrorHandler::new();
 
     match validate_search_configuration(search_config, dir_config, &mut error_handler)? {
-        ValidationResult::Success(parsed) => Ok(parsed),
+        ValidationResult::Success((mut parsed_search_config, parsed_dir_config)) => {
+            parsed_search_config.line_filter = line_filter.clone();
+            Ok((parsed_search_config, parsed_dir_config))
+        }
         ValidationResult::ValidationErrors => Err(anyhow::anyhow!(
             "{}",
             error_handler
@@ -91,12 +757,21 @@ fn parse_config(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::validation::SearchConfig;
+    use crate::{search::BinaryDetection, validation::SearchConfig};
 
     fn build_config<'a>(
         search_text: &'a str,
         replacement_text: &'a str,
         multiline: bool,
+    ) -> SearchConfig<'a> {
+        build_config_with_escapes(search_text, replacement_text, multiline, false)
+    }
+
+    fn build_config_with_escapes<'a>(
+        search_text: &'a str,
+        replacement_text: &'a str,
+        multiline: bool,
+        interpret_escape_sequences: bool,
     ) -> SearchConfig<'a> {
         SearchConfig {
             search_text,
@@ -106,16 +781,125 @@ mod tests {
             match_whole_word: false,
             match_case: true,
             multiline,
-            interpret_escape_sequences: false,
+            interpret_escape_sequences,
+            wrap_matches: false,
+            delete_empty_lines: false,
         }
     }
 
+    #[test]
+    fn test_replacement_summary_display() {
+        let summary = HeadlessResults::new(FilesUpdated::Count(1));
+        assert_eq!(summary.to_string(), "Success: 1 file updated\n");
+
+        let summary = HeadlessResults {
+            files_updated: FilesUpdated::Shadow {
+                dir: PathBuf::from("shadow"),
+                files: vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")],
+                diff: "--- a.txt\n+++ shadow/a.txt\n@@ -1 +1 @@\n-foo\n+bar\n".to_owned(),
+            },
+            changed_files: vec![],
+            errors: vec![],
+            num_binary_files: 1,
+            num_dirs_beyond_max_depth: 3,
+            max_depth: Some(2),
+            num_files_regex_too_slow: 2,
+            non_utf8_lines: vec![(PathBuf::from("c.txt"), 3)],
+            num_non_utf8_paths: 1,
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Success: 2 files updated in shadow copy at shadow\n  a.txt\n  b.txt\n\
+             Searched 1 binary file\nSkipped 3 directories beyond max depth of 2\n\
+             Skipped 3 lines of invalid UTF-8 in 1 file\n  c.txt: 3 lines\n\
+             Skipped 1 match in files with paths that aren't valid UTF-8\nRegex too slow: skipped 2 files\n\n\
+             --- a.txt\n+++ shadow/a.txt\n@@ -1 +1 @@\n-foo\n+bar\n"
+        );
+
+        let summary = HeadlessResults {
+            files_updated: FilesUpdated::Pairs(vec![
+                (
+                    ReplacementPair {
+                        search: "foo".to_owned(),
+                        replace: "bar".to_owned(),
+                    },
+                    0,
+                ),
+                (
+                    ReplacementPair {
+                        search: "baz".to_owned(),
+                        replace: "qux".to_owned(),
+                    },
+                    2,
+                ),
+            ]),
+            changed_files: vec![],
+            errors: vec![],
+            num_binary_files: 2,
+            num_dirs_beyond_max_depth: 1,
+            max_depth: None,
+            num_files_regex_too_slow: 0,
+            non_utf8_lines: vec![],
+            num_non_utf8_paths: 0,
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Success:\n  foo -> bar: 0 files updated\n  baz -> qux: 2 files updated\n\
+             Searched 2 binary files\n"
+        );
+    }
+
+    #[test]
+    fn test_replacement_summary_display_with_errors() {
+        let summary = HeadlessResults {
+            errors: vec![
+                HeadlessError {
+                    path: PathBuf::from("a.txt"),
+                    line: Some(3),
+                    error: anyhow!("File changed since last search"),
+                },
+                HeadlessError {
+                    path: PathBuf::from("b.txt"),
+                    line: None,
+                    error: anyhow!("Permission denied"),
+                },
+            ],
+            num_binary_files: 1,
+            ..HeadlessResults::new(FilesUpdated::Count(2))
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Success: 2 files updated\nSearched 1 binary file\nErrors: 2\n  \
+             a.txt:3: File changed since last search\n  \
+             b.txt: Permission denied\n"
+        );
+    }
+
+    #[test]
+    fn test_shadow_path() {
+        let roots = [PathBuf::from("./a")];
+        assert_eq!(
+            shadow_path(Path::new("./a/src/file.rs"), &roots),
+            PathBuf::from("src/file.rs")
+        );
+
+        let roots = [PathBuf::from("a"), PathBuf::from("../b")];
+        assert_eq!(
+            shadow_path(Path::new("a/file.rs"), &roots),
+            PathBuf::from("a/file.rs")
+        );
+        assert_eq!(
+            shadow_path(Path::new("../b/file.rs"), &roots),
+            PathBuf::from("b/file.rs")
+        );
+    }
+
     #[test]
     fn find_and_replace_text_line_mode_does_not_replace_newlines() {
         let content = "foo\nbar\n";
         let config = build_config("\n", "X", false);
 
-        let result = find_and_replace_text(content, config).unwrap();
+        let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
 
         assert_eq!(result, content);
     }
@@ -125,7 +909,7 @@ mod tests {
         let content = "foo\r\nbar\r\n";
         let config = build_config("bar", "baz", false);
 
-        let result = find_and_replace_text(content, config).unwrap();
+        let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
 
         assert_eq!(result, "foo\r\nbaz\r\n");
     }
@@ -135,8 +919,379 @@ mod tests {
         let content = "foo\nbar\nbaz";
         let config = build_config("foo\nbar", "qux", true);
 
-        let result = find_and_replace_text(content, config).unwrap();
+        let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
 
         assert_eq!(result, "qux\nbaz");
     }
+
+    #[test]
+    fn find_and_replace_text_line_mode_escaped_newline_inserts_lines() {
+        let content = "foo\nbar\r\nbaz\n";
+        let config = build_config_with_escapes("bar", "bar\\nqux", false, true);
+
+        let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
+
+        assert_eq!(result, "foo\nbar\nqux\r\nbaz\n");
+    }
+
+    #[test]
+    fn find_and_replace_text_unicode_escapes_in_regex_and_replacement() {
+        let content = "foo\u{200B}bar\nbaz\u{200B}\n";
+        for advanced_regex in [false, true] {
+            let config = SearchConfig {
+                fixed_strings: false,
+                advanced_regex,
+                ..build_config_with_escapes(r"\u{200B}", r"\u{a0}", false, true)
+            };
+
+            let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
+
+            assert_eq!(result, "foo\u{a0}bar\nbaz\u{a0}\n");
+        }
+    }
+
+    #[test]
+    fn find_and_replace_text_line_mode_escaped_newline_kept_literal_without_flag() {
+        let content = "foo\nbar\n";
+        let config = build_config("bar", "bar\\nqux", false);
+
+        let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
+
+        assert_eq!(result, "foo\nbar\\nqux\n");
+    }
+
+    #[test]
+    fn find_and_replace_text_line_mode_require_skips_other_lines() {
+        let content = "foo = 1\n// foo\nbar = foo\n";
+        let config = build_config("foo", "baz", false);
+        let line_filter = LineFilter {
+            require: Some(regex::Regex::new("=").unwrap()),
+            exclude: None,
+            columns: None,
+            line_ranges: None,
+        };
+
+        let result = find_and_replace_text(content, config, &line_filter).unwrap();
+
+        assert_eq!(result, "baz = 1\n// foo\nbar = baz\n");
+    }
+
+    #[test]
+    fn find_and_replace_text_line_mode_exclude_line_skips_matching_lines() {
+        let content = "foo = 1\n// foo = 2\nbar = foo\n";
+        let config = build_config("foo", "baz", false);
+        let line_filter = LineFilter {
+            require: Some(regex::Regex::new("=").unwrap()),
+            exclude: Some(regex::Regex::new("^//").unwrap()),
+            columns: None,
+            line_ranges: None,
+        };
+
+        let result = find_and_replace_text(content, config, &line_filter).unwrap();
+
+        assert_eq!(result, "baz = 1\n// foo = 2\nbar = baz\n");
+    }
+
+    #[test]
+    fn find_and_replace_text_line_mode_deletes_empty_lines() {
+        let content = "foo\nfoo bar\nbaz\r\nfoo\r\n\nfoo";
+        let config = SearchConfig {
+            delete_empty_lines: true,
+            ..build_config("foo", "", false)
+        };
+
+        let result = find_and_replace_text(content, config, &LineFilter::default()).unwrap();
+
+        assert_eq!(result, " bar\nbaz\r\n\n");
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<ReplacementPair> {
+        pairs
+            .iter()
+            .map(|(search, replace)| ReplacementPair {
+                search: (*search).to_owned(),
+                replace: (*replace).to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_and_replace_text_pairs_applies_pairs_in_order() {
+        let content = "foo bar\nbaz\n";
+        let config = build_config("", "", false);
+
+        let result = find_and_replace_text_pairs(
+            content,
+            &pairs(&[("foo", "bar"), ("bar", "qux"), ("baz", "foo")]),
+            &config,
+            &LineFilter::default(),
+        )
+        .unwrap();
+
+        assert_eq!(result, "qux qux\nfoo\n");
+    }
+
+    #[test]
+    fn find_and_replace_framed_text_replaces_rg_json_submatches() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.txt");
+        std::fs::write(&file, "one\nfoo bar foo\nfoobar\n").unwrap();
+        let rg_match = |path,
+                        lines: &str,
+                        line_number,
+                        absolute_offset,
+                        submatches: &[(usize, usize)]| {
+            let submatches = submatches
+                .iter()
+                .map(|&(start, end)| {
+                    serde_json::json!({"match": {"text": &lines[start..end]}, "start": start, "end": end})
+                })
+                .collect::<Vec<_>>();
+            serde_json::json!({
+                "type": "match",
+                "data": {
+                    "path": path,
+                    "lines": {"text": lines},
+                    "line_number": line_number,
+                    "absolute_offset": absolute_offset,
+                    "submatches": submatches,
+                },
+            })
+        };
+        // Only the submatches ripgrep reported are replaced, e.g. not the `foo` in `foobar`
+        let content = [
+            rg_match(
+                serde_json::json!({"text": file}),
+                "foo bar foo\n",
+                2,
+                4,
+                &[(0, 3), (8, 11)],
+            ),
+            rg_match(
+                serde_json::json!({"bytes": "3/4="}),
+                "foo\n",
+                1,
+                0,
+                &[(0, 3)],
+            ),
+        ]
+        .map(|message| message.to_string())
+        .join("\n");
+
+        let results = find_and_replace_framed_text(
+            &content,
+            StdinFormat::RgJson,
+            build_config("", "baz", false),
+            &LineFilter::default(),
+            SymlinkHandling::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "one\nbaz bar baz\nfoobar\n"
+        );
+        assert_eq!(results.changed_files, vec![file]);
+        assert!(results.errors.is_empty());
+        assert_eq!(results.num_non_utf8_paths, 1);
+    }
+
+    #[test]
+    fn find_and_replace_pairs_validates_all_pairs_before_replacing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.txt");
+        std::fs::write(&file, "foo\n").unwrap();
+        let config = SearchConfig {
+            fixed_strings: false,
+            ..build_config("", "", false)
+        };
+        let dir_config = DirConfig {
+            include_globs: None,
+            exclude_globs: None,
+            directories: vec![temp_dir.path().to_path_buf()],
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+
+        let result = find_and_replace_pairs(
+            &pairs(&[("foo", "bar"), ("(", "baz")]),
+            &config,
+            &dir_config,
+            &LineFilter::default(),
+        );
+
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid search text \"(\"")
+        );
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "foo\n");
+    }
+
+    #[test]
+    fn find_and_replace_jobs_applies_jobs_in_order_with_their_own_globs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let rs_file = temp_dir.path().join("a.rs");
+        let txt_file = temp_dir.path().join("b.txt");
+        std::fs::write(&rs_file, "foo FOO\n").unwrap();
+        std::fs::write(&txt_file, "foo\n").unwrap();
+        let config = SearchConfig {
+            fixed_strings: false,
+            ..build_config("", "", false)
+        };
+        let dir_config = DirConfig {
+            include_globs: None,
+            exclude_globs: None,
+            directories: vec![temp_dir.path().to_path_buf()],
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+        let jobs = [
+            Job {
+                name: Some("Rust only".to_owned()),
+                search: "foo".to_owned(),
+                replace: "bar".to_owned(),
+                case_insensitive: true,
+                files_to_include: "*.rs".to_owned(),
+                ..Job::default()
+            },
+            Job {
+                search: "bar|foo".to_owned(),
+                replace: "baz".to_owned(),
+                ..Job::default()
+            },
+        ];
+
+        let results =
+            find_and_replace_jobs(&jobs, &config, &dir_config, &LineFilter::default(), true)
+                .unwrap();
+
+        assert_eq!(
+            results.to_string(),
+            "Rust only: 1 file updated\nbar|foo -> baz: 2 files updated\nTotal: 3 files updated\n"
+        );
+        assert!(!results.has_errors());
+        assert_eq!(std::fs::read_to_string(&rs_file).unwrap(), "baz baz\n");
+        assert_eq!(std::fs::read_to_string(&txt_file).unwrap(), "baz\n");
+
+        let invalid_jobs = [
+            jobs[0].clone(),
+            Job {
+                search: "(".to_owned(),
+                ..Job::default()
+            },
+        ];
+        let result = find_and_replace_jobs(
+            &invalid_jobs,
+            &config,
+            &dir_config,
+            &LineFilter::default(),
+            false,
+        );
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid job \"( -> \"")
+        );
+        assert_eq!(std::fs::read_to_string(&rs_file).unwrap(), "baz baz\n");
+    }
+
+    #[test]
+    fn test_jobs_results_display_with_errors() {
+        let results = JobsResults {
+            jobs: vec![JobResults {
+                name: "rename".to_owned(),
+                num_files_updated: 2,
+                errors: vec![
+                    HeadlessError {
+                        path: PathBuf::from("a.txt"),
+                        line: Some(3),
+                        error: anyhow!("File changed since last search"),
+                    },
+                    HeadlessError {
+                        path: PathBuf::from("b.txt"),
+                        line: None,
+                        error: anyhow!("Permission denied"),
+                    },
+                ],
+            }],
+            num_jobs_skipped: 1,
+        };
+        assert!(results.has_errors());
+        assert_eq!(
+            results.to_string(),
+            "rename: 2 files updated, 2 errors\n  \
+            a.txt:3: File changed since last search\n  \
+            b.txt: Permission denied\n\
+            Skipped 1 job after errors\n\
+            Total: 2 files updated\n"
+        );
+    }
+
+    #[test]
+    fn find_and_replace_framed_text_replaces_in_referenced_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let a = temp_dir.path().join("a.txt");
+        let b = temp_dir.path().join("b.txt");
+        std::fs::write(&a, "foo\nbar foo\n").unwrap();
+        std::fs::write(&b, "foo\n").unwrap();
+        // The line for `b.txt` doesn't match the file, so is left alone and reported as an error
+        let content = format!(
+            "{a}:2:bar foo\n{b}:1:stale foo\n",
+            a = a.display(),
+            b = b.display()
+        );
+        let config = build_config("foo", "baz", false);
+
+        let result = find_and_replace_framed_text(
+            &content,
+            StdinFormat::Grep,
+            config,
+            &LineFilter::default(),
+            SymlinkHandling::Follow,
+        )
+        .unwrap();
+
+        assert_eq!(result.files_updated, FilesUpdated::Count(1));
+        let errors = result
+            .errors
+            .iter()
+            .map(|error| (error.path.clone(), error.line, error.error.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![(
+                b.clone(),
+                Some(1),
+                "File changed since last search".to_owned()
+            )]
+        );
+        assert_eq!(
+            result.errors[0].to_string(),
+            format!("{}:1: File changed since last search", b.display())
+        );
+        assert_eq!(
+            result.to_string(),
+            format!(
+                "Success: 1 file updated\nErrors: 1\n  {}:1: File changed since last search\n",
+                b.display()
+            )
+        );
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "foo\nbar baz\n");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "foo\n");
+    }
 }
@@ -1,11 +1,20 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fmt::{self, Write as _};
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::num::NonZero;
+use std::ops::{Range, RangeInclusive};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::str::FromStr;
+use std::sync::{
+    Arc, Condvar, Mutex,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 use std::thread::{self};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Context;
+use anyhow::{Context, anyhow, bail};
 use content_inspector::{ContentType, inspect};
 use fancy_regex::Regex as FancyRegex;
 use ignore::overrides::Override;
@@ -13,7 +22,7 @@ use ignore::{WalkBuilder, WalkState};
 use regex::Regex;
 
 use crate::{
-    line_reader::{BufReadExt, LineEnding},
+    line_reader::{self, BufReadExt, LineEnding},
     replace::{self, ReplaceResult},
 };
 
@@ -247,6 +256,9 @@ pub struct SearchResultWithReplacement {
     pub replacement: String,
     pub replace_result: Option<ReplaceResult>,
     pub preview_error: Option<String>,
+    /// Byte ranges of each match within the content of a line-mode result, used to highlight
+    /// the matched text in the preview. Empty for byte-range results, which track their match directly
+    pub match_spans: Vec<Range<usize>>,
 }
 
 impl SearchResultWithReplacement {
@@ -271,6 +283,12 @@ impl SearchResultWithReplacement {
 
         (path_display, error)
     }
+
+    /// Whether performing the replacement would leave the matched text unchanged
+    pub fn is_noop(&self) -> bool {
+        self.preview_error.is_none()
+            && self.replacement == self.search_result.content.matched_text()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -297,6 +315,39 @@ impl SearchType {
     }
 }
 
+/// Allows a running search to be paused and resumed from another thread. While paused, the search
+/// threads wait before searching each file (or line, when searching text), so no further results are
+/// found until the search is resumed.
+#[derive(Debug, Default)]
+pub struct SearchPause {
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl SearchPause {
+    pub fn pause(&self) {
+        *self.paused.lock().unwrap() = true;
+    }
+
+    /// Resumes the search, waking any threads waiting in [`Self::wait_while_paused`]
+    pub fn resume(&self) {
+        *self.paused.lock().unwrap() = false;
+        self.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+
+    /// Blocks the current thread until the search is resumed, returning immediately if it isn't paused
+    pub fn wait_while_paused(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        while *paused {
+            paused = self.resumed.wait(paused).unwrap();
+        }
+    }
+}
+
 /// A function that processes search results for a file and determines whether to continue searching.
 type FileVisitor = Box<dyn FnMut(Vec<SearchResult>) -> WalkState + Send>;
 
@@ -312,6 +363,10 @@ impl FileSearcher {
     pub fn multiline(&self) -> bool {
         self.search_config.multiline
     }
+
+    pub fn delete_empty_lines(&self) -> bool {
+        self.search_config.delete_empty_lines
+    }
 }
 
 /// Options for regex pattern conversion
@@ -331,22 +386,453 @@ pub struct ParsedSearchConfig {
     pub replace: String,
     /// Whether to search and replace across multiple lines
     pub multiline: bool,
+    /// Additional conditions that a line must satisfy for a match on it to be included. Only applies
+    /// when searching line-by-line
+    pub line_filter: LineFilter,
+    /// Whether to remove lines that are left empty by the replacement, rather than leaving a blank
+    /// line. Only applies when searching line-by-line
+    pub delete_empty_lines: bool,
+}
+
+/// Conditions, beyond matching the search pattern, that a line must satisfy to produce a result.
+///
+/// Lines are evaluated in order: the search pattern must match, then `require` (if set) must match,
+/// and finally `exclude` (if set) must not match.
+#[derive(Clone, Debug, Default)]
+pub struct LineFilter {
+    /// If set, lines must also match this pattern
+    pub require: Option<Regex>,
+    /// If set, lines matching this pattern are dropped, even if they match `require`
+    pub exclude: Option<Regex>,
+    /// If set, only matches starting within these columns produce results, each of which is a
+    /// separate result so that other matches on the same line are left unchanged
+    pub columns: Option<ColumnRange>,
+    /// If set, only the listed lines of the listed files produce results
+    pub line_ranges: Option<Arc<LineRanges>>,
+}
+
+/// An inclusive range of 1-based character columns within a line, as passed to `--columns`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnRange {
+    pub start: usize,
+    /// `None` if the range extends to the end of the line
+    pub end: Option<usize>,
+}
+
+impl ColumnRange {
+    pub fn contains(&self, column: usize) -> bool {
+        column >= self.start && self.end.is_none_or(|end| column <= end)
+    }
+}
+
+impl FromStr for ColumnRange {
+    type Err = anyhow::Error;
+
+    /// Parses `START:END`, where either bound can be omitted to leave that side of the range open,
+    /// e.g. `1:4`, `:4` or `10:`
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (start, end) = s.split_once(':').ok_or_else(|| {
+            anyhow!("Invalid column range \"{s}\", expected START:END such as 1:4")
+        })?;
+        let parse_column = |column: &str| match column.trim().parse::<usize>() {
+            Ok(0) => bail!("Columns start from 1"),
+            Ok(column) => Ok(column),
+            Err(e) => bail!("Invalid column \"{column}\": {e}"),
+        };
+        let start = if start.trim().is_empty() {
+            1
+        } else {
+            parse_column(start)?
+        };
+        let end = if end.trim().is_empty() {
+            None
+        } else {
+            Some(parse_column(end)?)
+        };
+        if end.is_some_and(|end| end < start) {
+            bail!("Column range \"{s}\" ends before it starts");
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl fmt::Display for ColumnRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.start)?;
+        if let Some(end) = self.end {
+            write!(f, "{end}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The lines to search in each file, as passed to `--line-ranges`, e.g. from a lint report. Files
+/// that aren't listed aren't searched
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineRanges {
+    /// Inclusive ranges of 1-based line numbers, keyed by the canonical path of each file
+    files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+    /// The name of each file in `files`, so that files with other names can be ruled out without
+    /// resolving their paths
+    file_names: HashSet<OsString>,
+    /// The file the ranges were read from, if any
+    source: Option<PathBuf>,
+}
+
+impl LineRanges {
+    /// Reads line ranges from the file at `path`, in the format described in [`Self::parse`], with
+    /// relative paths resolved against the current directory
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self {
+            source: Some(path.to_path_buf()),
+            ..Self::parse(&content, &std::env::current_dir()?)?
+        })
+    }
+
+    /// Parses one file per line as `PATH:RANGES`, where `RANGES` is a comma-separated list of line
+    /// numbers and inclusive ranges of them, e.g. `src/main.rs:10-20,40`. Files listed more than once
+    /// have their ranges combined. Blank lines and lines starting with `#` are ignored, and relative
+    /// paths are resolved against `base_dir`
+    pub fn parse(content: &str, base_dir: &Path) -> anyhow::Result<Self> {
+        let mut files = HashMap::<PathBuf, Vec<RangeInclusive<usize>>>::new();
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (path, ranges) = line.rsplit_once(':').ok_or_else(|| {
+                anyhow!("Line {}: expected PATH:RANGES, found \"{line}\"", idx + 1)
+            })?;
+            let path = base_dir
+                .join(path)
+                .canonicalize()
+                .with_context(|| format!("Line {}: failed to find {path}", idx + 1))?;
+            let ranges = ranges
+                .split(',')
+                .map(parse_line_range)
+                .collect::<anyhow::Result<Vec<_>>>()
+                .with_context(|| format!("Line {}: invalid line ranges", idx + 1))?;
+            files.entry(path).or_default().extend(ranges);
+        }
+        Ok(Self::new(files, None))
+    }
+
+    fn new(files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>, source: Option<PathBuf>) -> Self {
+        let file_names = files
+            .keys()
+            .filter_map(|path| path.file_name().map(ToOwned::to_owned))
+            .collect();
+        Self {
+            files,
+            file_names,
+            source,
+        }
+    }
+
+    /// The file the ranges were read from, if they were read with [`Self::from_file`]
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    /// The ranges of lines to search in the file at `path`, or `None` if it isn't listed
+    pub fn for_file(&self, path: &Path) -> Option<&[RangeInclusive<usize>]> {
+        // Most files searched aren't listed, so are ruled out before resolving their path
+        if !self.file_names.contains(path.file_name()?) {
+            return None;
+        }
+        let path = path.canonicalize().ok()?;
+        self.files.get(&path).map(Vec::as_slice)
+    }
+
+    /// Line ranges that list `copy` with the ranges of `original`, e.g. for a copy made with
+    /// `--shadow`, which isn't listed itself
+    pub fn for_copy(&self, original: &Path, copy: &Path) -> anyhow::Result<Self> {
+        let mut files = HashMap::new();
+        if let Some(ranges) = self.for_file(original) {
+            let copy = copy
+                .canonicalize()
+                .with_context(|| format!("Failed to find {}", copy.display()))?;
+            files.insert(copy, ranges.to_vec());
+        }
+        Ok(Self::new(files, self.source.clone()))
+    }
+}
+
+/// Parses a line number such as `40`, or an inclusive range of them such as `10-20`
+fn parse_line_range(s: &str) -> anyhow::Result<RangeInclusive<usize>> {
+    let parse_line = |line: &str| match line.trim().parse::<usize>() {
+        Ok(0) => bail!("Line numbers start from 1"),
+        Ok(line) => Ok(line),
+        Err(e) => bail!("Invalid line number \"{}\": {e}", line.trim()),
+    };
+    let (start, end) = if let Some((start, end)) = s.split_once('-') {
+        (parse_line(start)?, parse_line(end)?)
+    } else {
+        let line = parse_line(s)?;
+        (line, line)
+    };
+    if end < start {
+        bail!("Line range \"{}\" ends before it starts", s.trim());
+    }
+    Ok(start..=end)
+}
+
+impl LineFilter {
+    /// Returns true if a line that matches the search pattern should produce a result
+    pub fn allows(&self, line: &str) -> bool {
+        self.require.as_ref().is_none_or(|re| re.is_match(line))
+            && !self.exclude.as_ref().is_some_and(|re| re.is_match(line))
+    }
+}
+
+impl PartialEq for LineFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.require.as_ref().map(Regex::as_str) == other.require.as_ref().map(Regex::as_str)
+            && self.exclude.as_ref().map(Regex::as_str) == other.exclude.as_ref().map(Regex::as_str)
+            && self.columns == other.columns
+            && self.line_ranges == other.line_ranges
+    }
 }
 
+impl Eq for LineFilter {}
+
+/// A directory to search from, along with the inclusion/exclusion patterns to apply within it
 #[derive(Clone, Debug)]
-pub struct ParsedDirConfig {
-    /// Configuration for file inclusion/exclusion patterns
+pub struct SearchRoot {
+    /// Configuration for file inclusion/exclusion patterns, relative to `dir`
     pub overrides: Override,
-    /// The root directory to start searching from
-    pub root_dir: PathBuf,
+    /// The directory to start searching from
+    pub dir: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParsedDirConfig {
+    /// The directories to search, each of which is walked in turn
+    pub roots: Vec<SearchRoot>,
     /// Whether to include hidden files/directories in the search
     pub include_hidden: bool,
+    /// Whether to search files that look like binary files rather than skipping them
+    pub include_binary: bool,
+    /// How to decide whether a file looks like a binary file
+    pub binary_detection: BinaryDetection,
+    /// How many levels of directories to descend into, where 1 searches only the files directly
+    /// within each root. `None` means no limit
+    pub max_depth: Option<usize>,
+    /// If set, only files modified at or after this time are searched
+    pub modified_since: Option<SystemTime>,
+    /// If set, only these files are searched, e.g. those tracked by git with `--git-tracked-only`.
+    /// Paths must be as found when walking `roots`
+    pub tracked_files: Option<Arc<HashSet<PathBuf>>>,
+    /// If set, only these files are searched, e.g. those approved with `--interactive-filter`.
+    /// Paths must be as found when walking `roots`
+    pub approved_files: Option<Arc<HashSet<PathBuf>>>,
+    /// If set, searching a file with an advanced regex is abandoned once it has taken longer than
+    /// this, and the file is skipped. See [`RegexTooSlow`]
+    pub regex_timeout: Option<Duration>,
+}
+
+impl ParsedDirConfig {
+    /// Whether the file is among `tracked_files`, if set
+    fn is_tracked(&self, path: &Path) -> bool {
+        self.tracked_files
+            .as_ref()
+            .is_none_or(|tracked_files| tracked_files.contains(path))
+    }
+
+    /// Whether the file was modified at or after `modified_since`. Files whose modification time
+    /// can't be read are skipped when `modified_since` is set
+    fn is_modified_since_cutoff(&self, entry: &ignore::DirEntry) -> bool {
+        let Some(cutoff) = self.modified_since else {
+            return true;
+        };
+        let modified = entry
+            .metadata()
+            .context("Failed to read metadata")
+            .and_then(|metadata| {
+                metadata
+                    .modified()
+                    .context("Failed to read modification time")
+            });
+        match modified {
+            Ok(modified) => modified >= cutoff,
+            Err(e) => {
+                log::warn!("Skipping {}: {e}", entry.path().display());
+                false
+            }
+        }
+    }
+
+    /// Builds a walker for each root directory. Each root is walked separately so that glob
+    /// patterns are matched relative to the root they apply to
+    fn build_walkers(&self) -> Vec<ignore::WalkParallel> {
+        let num_threads = thread::available_parallelism()
+            .map(NonZero::get)
+            .unwrap_or(4)
+            .min(12);
+
+        self.roots
+            .iter()
+            .map(|root| {
+                WalkBuilder::new(&root.dir)
+                    .hidden(!self.include_hidden)
+                    .max_depth(self.max_depth)
+                    .overrides(root.overrides.clone())
+                    .threads(num_threads)
+                    .build_parallel()
+            })
+            .collect()
+    }
+
+    /// Lists the files that would be searched, sorted by path. Only the path of each file is
+    /// checked, so files that are skipped during a search because their content looks binary are
+    /// still listed. `approved_files` is ignored.
+    pub fn list_files(&self) -> Vec<PathBuf> {
+        let files = Mutex::new(vec![]);
+        for walker in self.build_walkers() {
+            walker.run(|| {
+                let files = &files;
+                Box::new(move |result| {
+                    let Ok(entry) = result else {
+                        return WalkState::Continue;
+                    };
+                    if entry.file_type().is_some_and(|ft| ft.is_file())
+                        && (self.include_binary
+                            || !self.binary_detection.has_binary_extension(entry.path()))
+                        && self.is_modified_since_cutoff(&entry)
+                        && self.is_tracked(entry.path())
+                    {
+                        files.lock().unwrap().push(entry.into_path());
+                    }
+                    WalkState::Continue
+                })
+            });
+        }
+        let mut files = files.into_inner().unwrap();
+        files.sort();
+        files
+    }
+}
+
+/// The default number of bytes inspected when checking whether a file's content is binary
+pub const DEFAULT_BINARY_SAMPLE_SIZE: usize = 8192;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryDetection {
+    /// Number of bytes at the start of each file that are inspected for binary content. Content
+    /// inspection is skipped entirely if this is 0, leaving only the extension-based check
+    pub sample_size: usize,
+    /// Extensions (case-insensitive, with or without a leading `.`) of files that are always
+    /// treated as text
+    pub text_extensions: Vec<String>,
+}
+
+impl Default for BinaryDetection {
+    fn default() -> Self {
+        Self {
+            sample_size: DEFAULT_BINARY_SAMPLE_SIZE,
+            text_extensions: vec![],
+        }
+    }
+}
+
+impl BinaryDetection {
+    fn is_text_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext_str| {
+                self.text_extensions
+                    .iter()
+                    .any(|text_ext| ext_str.eq_ignore_ascii_case(text_ext.trim_start_matches('.')))
+            })
+    }
+
+    /// Whether the file looks binary from its path alone
+    fn has_binary_extension(&self, path: &Path) -> bool {
+        is_likely_binary(path) && !self.is_text_extension(path)
+    }
+
+    /// Whether `content`, read from the start of the file at `path`, looks like binary content
+    fn is_binary_content(&self, path: &Path, content: &[u8]) -> bool {
+        if self.sample_size == 0 || self.is_text_extension(path) {
+            return false;
+        }
+        let probe = &content[..content.len().min(self.sample_size)];
+        matches!(inspect(probe), ContentType::BINARY)
+    }
+
+    /// Inspects the start of `file` to check whether it looks like binary content, leaving the
+    /// file positioned at the start
+    fn starts_with_binary_content(&self, path: &Path, file: &mut File) -> std::io::Result<bool> {
+        if self.sample_size == 0 || self.is_text_extension(path) {
+            return Ok(false);
+        }
+        let mut probe = Vec::with_capacity(self.sample_size);
+        let _ = file
+            .by_ref()
+            .take(self.sample_size as u64)
+            .read_to_end(&mut probe);
+        file.seek(SeekFrom::Start(0))?;
+        Ok(self.is_binary_content(path, &probe))
+    }
+}
+
+/// How files that look like binary files are handled when searching them
+#[derive(Clone, Copy, Debug)]
+pub enum BinaryFiles<'a> {
+    /// Skip files whose content looks binary
+    Skip(&'a BinaryDetection),
+    /// Search files whatever their content, counting those that look binary
+    Count(&'a BinaryDetection, &'a AtomicUsize),
+    /// Search files whatever their content
+    Search,
+}
+
+impl BinaryFiles<'_> {
+    /// Whether the file at `path`, which has just been opened as `file`, should be skipped because
+    /// its content looks binary. The start of the file is only inspected when needed, and the file
+    /// is left positioned at the start
+    fn skips_file(&self, path: &Path, file: &mut File) -> std::io::Result<bool> {
+        match self {
+            Self::Skip(detection) => detection.starts_with_binary_content(path, file),
+            Self::Count(detection, included) => {
+                if detection.has_binary_extension(path)
+                    || detection.starts_with_binary_content(path, file)?
+                {
+                    included.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(false)
+            }
+            Self::Search => Ok(false),
+        }
+    }
+
+    /// As with [`Self::skips_file`], for the file at `path` that has already been read as `content`
+    pub(crate) fn skips_content(&self, path: &Path, content: &[u8]) -> bool {
+        match self {
+            Self::Skip(detection) => detection.is_binary_content(path, content),
+            Self::Count(detection, included) => {
+                if detection.has_binary_extension(path)
+                    || detection.is_binary_content(path, content)
+                {
+                    included.fetch_add(1, Ordering::Relaxed);
+                }
+                false
+            }
+            Self::Search => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct FileSearcher {
     search_config: ParsedSearchConfig,
     dir_config: ParsedDirConfig,
+    binary_files_included: Arc<AtomicUsize>,
+    dirs_beyond_max_depth: Arc<AtomicUsize>,
+    files_regex_too_slow: Arc<AtomicUsize>,
+    non_utf8_lines: Arc<NonUtf8Lines>,
 }
 
 impl FileSearcher {
@@ -354,25 +840,122 @@ impl FileSearcher {
         Self {
             search_config,
             dir_config,
+            binary_files_included: Arc::new(AtomicUsize::new(0)),
+            dirs_beyond_max_depth: Arc::new(AtomicUsize::new(0)),
+            files_regex_too_slow: Arc::new(AtomicUsize::new(0)),
+            non_utf8_lines: Arc::default(),
         }
     }
 
-    fn build_walker(&self) -> ignore::WalkParallel {
-        let num_threads = thread::available_parallelism()
-            .map(NonZero::get)
-            .unwrap_or(4)
-            .min(12);
+    /// The number of files that looked like binary files but were searched anyway during the most
+    /// recent walk, because `include_binary` was set
+    pub fn num_binary_files_included(&self) -> usize {
+        self.binary_files_included.load(Ordering::Relaxed)
+    }
+
+    /// The number of directories that weren't searched during the most recent walk because they
+    /// were beyond `max_depth`
+    pub fn num_dirs_beyond_max_depth(&self) -> usize {
+        self.dirs_beyond_max_depth.load(Ordering::Relaxed)
+    }
 
-        WalkBuilder::new(&self.dir_config.root_dir)
-            .hidden(!self.dir_config.include_hidden)
-            .overrides(self.dir_config.overrides.clone())
-            .threads(num_threads)
-            .build_parallel()
+    /// The number of files that were skipped during the most recent walk because searching them
+    /// took longer than `regex_timeout`
+    pub fn num_files_regex_too_slow(&self) -> usize {
+        self.files_regex_too_slow.load(Ordering::Relaxed)
     }
 
-    /// Walks through files in the configured directory and processes matches.
+    /// The lines that were skipped during the most recent walk because they weren't valid UTF-8
+    pub fn non_utf8_lines(&self) -> &NonUtf8Lines {
+        &self.non_utf8_lines
+    }
+
+    /// The maximum depth that directories are searched to, if any
+    pub fn max_depth(&self) -> Option<usize> {
+        self.dir_config.max_depth
+    }
+
+    /// The time after which searching a file with an advanced regex is abandoned, if any
+    pub fn regex_timeout(&self) -> Option<Duration> {
+        self.dir_config.regex_timeout
+    }
+
+    fn is_searchable(&self, entry: &ignore::DirEntry) -> bool {
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            // Directories at the maximum depth are listed but not descended into
+            if entry.depth() > 0 && Some(entry.depth()) == self.dir_config.max_depth {
+                self.dirs_beyond_max_depth.fetch_add(1, Ordering::Relaxed);
+            }
+            return false;
+        }
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            return false;
+        }
+        if let Some(approved_files) = &self.dir_config.approved_files
+            && !approved_files.contains(entry.path())
+        {
+            return false;
+        }
+        if !self.dir_config.is_modified_since_cutoff(entry) {
+            return false;
+        }
+        if !self.dir_config.is_tracked(entry.path()) {
+            return false;
+        }
+        // Files that are searched anyway are counted when their content is read
+        self.dir_config.include_binary
+            || !self
+                .dir_config
+                .binary_detection
+                .has_binary_extension(entry.path())
+    }
+
+    /// How files that look like binary files are handled when searching them
+    fn binary_files(&self) -> BinaryFiles<'_> {
+        let detection = &self.dir_config.binary_detection;
+        if self.dir_config.include_binary {
+            BinaryFiles::Count(detection, &self.binary_files_included)
+        } else {
+            BinaryFiles::Skip(detection)
+        }
+    }
+
+    fn reset_walk_counts(&self) {
+        self.binary_files_included.store(0, Ordering::Relaxed);
+        self.dirs_beyond_max_depth.store(0, Ordering::Relaxed);
+        self.files_regex_too_slow.store(0, Ordering::Relaxed);
+        self.non_utf8_lines.reset();
+    }
+
+    /// Records that searching `path` was abandoned if `error` is a [`RegexTooSlow`], returning
+    /// whether it was
+    fn record_regex_too_slow(&self, path: &Path, error: &anyhow::Error) -> bool {
+        if error.downcast_ref::<RegexTooSlow>().is_none() {
+            return false;
+        }
+        log::warn!("Skipping {}: {error}", path.display());
+        self.files_regex_too_slow.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    fn log_walk_counts(&self) {
+        let num_included = self.num_binary_files_included();
+        if num_included > 0 {
+            log::warn!("Searched {num_included} file(s) that looked like binary files");
+        }
+        let num_dirs = self.num_dirs_beyond_max_depth();
+        if num_dirs > 0 {
+            log::info!("Skipped {num_dirs} director(ies) beyond the maximum depth");
+        }
+        let num_too_slow = self.num_files_regex_too_slow();
+        if num_too_slow > 0 {
+            log::warn!("Skipped {num_too_slow} file(s) where the regex was too slow");
+        }
+    }
+
+    /// Walks through files in the configured directories and processes matches.
     ///
-    /// This method traverses the filesystem starting from the `root_dir` specified in the `FileSearcher`,
+    /// This method traverses the filesystem starting from each of the `roots` specified in the `FileSearcher`,
     /// respecting the configured overrides (include/exclude patterns) and hidden file settings.
     /// It uses parallel processing when possible for better performance.
     ///
@@ -381,6 +964,12 @@ impl FileSearcher {
     /// * `cancelled` - An optional atomic boolean that can be used to signal cancellation from another thread.
     ///   If this is set to `true` during execution, the search will stop as soon as possible.
     ///
+    /// * `paused` - An optional [`SearchPause`] that can be used to pause the search from another thread.
+    ///   Note that the search must be resumed before it can be cancelled.
+    ///
+    /// * `files_scanned` - An optional counter that is incremented for each file searched, whether or not
+    ///   it contained matches. Useful for reporting progress while the walk is running.
+    ///
     /// * `file_handler` - A closure that returns a `FileVisitor`.
     ///   The returned `FileVisitor` is a function that processes search results for each file with matches.
     ///
@@ -393,22 +982,36 @@ impl FileSearcher {
     /// };
     /// use regex::Regex;
     /// use ignore::{WalkState, overrides::Override};
-    /// use scooter_core::search::{FileSearcher, ParsedSearchConfig, ParsedDirConfig, SearchResult, SearchType};
+    /// use scooter_core::search::{
+    ///     BinaryDetection, FileSearcher, LineFilter, ParsedSearchConfig, ParsedDirConfig,
+    ///     SearchResult, SearchRoot, SearchType,
+    /// };
     ///
     /// let search_config = ParsedSearchConfig {
     ///     search: SearchType::Pattern(Regex::new("pattern").unwrap()),
     ///     replace: "replacement".to_string(),
     ///     multiline: false,
+    ///     line_filter: LineFilter::default(),
+    ///     delete_empty_lines: false,
     /// };
     /// let dir_config = ParsedDirConfig {
-    ///     overrides: Override::empty(),
-    ///     root_dir: PathBuf::from("."),
+    ///     roots: vec![SearchRoot {
+    ///         overrides: Override::empty(),
+    ///         dir: PathBuf::from("."),
+    ///     }],
     ///     include_hidden: false,
+    ///     include_binary: false,
+    ///     binary_detection: BinaryDetection::default(),
+    ///     max_depth: None,
+    ///     modified_since: None,
+    ///     tracked_files: None,
+    ///     approved_files: None,
+    ///     regex_timeout: None,
     /// };
     /// let searcher = FileSearcher::new(search_config, dir_config);
     /// let cancelled = AtomicBool::new(false);
     ///
-    /// searcher.walk_files(Some(&cancelled), move || {
+    /// searcher.walk_files(Some(&cancelled), None, None, move || {
     ///     Box::new(move |results| {
     ///         if process(results).is_err() {
     ///             WalkState::Quit
@@ -423,52 +1026,84 @@ impl FileSearcher {
     ///     Ok(())
     /// }
     /// ```
-    pub fn walk_files<F>(&self, cancelled: Option<&AtomicBool>, mut file_handler: F)
-    where
+    pub fn walk_files<F>(
+        &self,
+        cancelled: Option<&AtomicBool>,
+        paused: Option<&SearchPause>,
+        files_scanned: Option<&AtomicUsize>,
+        mut file_handler: F,
+    ) where
         F: FnMut() -> FileVisitor + Send,
     {
-        let walker = self.build_walker();
-        walker.run(|| {
-            let mut on_file_found = file_handler();
-            Box::new(move |result| {
-                if let Some(cancelled) = cancelled
-                    && cancelled.load(Ordering::Relaxed)
-                {
-                    return WalkState::Quit;
-                }
+        self.reset_walk_counts();
+        // Set when a visitor quits, so that any remaining roots aren't walked
+        let quit = AtomicBool::new(false);
+        for walker in self.dir_config.build_walkers() {
+            if quit.load(Ordering::Relaxed) {
+                break;
+            }
+            walker.run(|| {
+                let mut on_file_found = file_handler();
+                let quit = &quit;
+                Box::new(move |result| {
+                    if let Some(paused) = paused {
+                        paused.wait_while_paused();
+                    }
+                    if let Some(cancelled) = cancelled
+                        && cancelled.load(Ordering::Relaxed)
+                    {
+                        quit.store(true, Ordering::Relaxed);
+                        return WalkState::Quit;
+                    }
 
-                let Ok(entry) = result else {
-                    return WalkState::Continue;
-                };
+                    let Ok(entry) = result else {
+                        return WalkState::Continue;
+                    };
 
-                if is_searchable(&entry) {
-                    let results = match search_file(
-                        entry.path(),
-                        &self.search_config.search,
-                        self.search_config.multiline,
-                    ) {
-                        Ok(r) => r,
-                        Err(e) => {
-                            log::warn!(
-                                "Skipping {} due to error when searching: {e}",
-                                entry.path().display()
-                            );
-                            return WalkState::Continue;
+                    if self.is_searchable(&entry) {
+                        let results = match search_file_with_options(
+                            entry.path(),
+                            &self.search_config.search,
+                            self.search_config.multiline,
+                            &self.search_config.line_filter,
+                            self.binary_files(),
+                            self.regex_timeout(),
+                            Some(&self.non_utf8_lines),
+                        ) {
+                            Ok(r) => r,
+                            Err(e) if self.record_regex_too_slow(entry.path(), &e) => {
+                                return WalkState::Continue;
+                            }
+                            Err(e) => {
+                                log::warn!(
+                                    "Skipping {} due to error when searching: {e}",
+                                    entry.path().display()
+                                );
+                                return WalkState::Continue;
+                            }
+                        };
+                        if let Some(files_scanned) = files_scanned {
+                            files_scanned.fetch_add(1, Ordering::Relaxed);
                         }
-                    };
 
-                    if !results.is_empty() {
-                        return on_file_found(results);
+                        if !results.is_empty() {
+                            let state = on_file_found(results);
+                            if matches!(state, WalkState::Quit) {
+                                quit.store(true, Ordering::Relaxed);
+                            }
+                            return state;
+                        }
                     }
-                }
-                WalkState::Continue
-            })
-        });
+                    WalkState::Continue
+                })
+            });
+        }
+        self.log_walk_counts();
     }
 
-    /// Walks through files in the configured directory and replaces matches.
+    /// Walks through files in the configured directories and replaces matches.
     ///
-    /// This method traverses the filesystem starting from the `root_dir` specified in the `FileSearcher`,
+    /// This method traverses the filesystem starting from each of the `roots` specified in the `FileSearcher`,
     /// respecting the configured overrides (include/exclude patterns) and hidden file settings.
     /// It replaces all matches of the search pattern with the replacement text in each file.
     ///
@@ -479,50 +1114,73 @@ impl FileSearcher {
     ///
     /// # Returns
     ///
-    /// The number of files that had replacements performed in them.
-    pub fn walk_files_and_replace(&self, cancelled: Option<&AtomicBool>) -> usize {
-        let num_files_replaced_in = std::sync::Arc::new(AtomicUsize::new(0));
-
-        let walker = self.build_walker();
-        walker.run(|| {
-            let counter = num_files_replaced_in.clone();
-
-            Box::new(move |result| {
-                if let Some(cancelled) = cancelled
-                    && cancelled.load(Ordering::Relaxed)
-                {
-                    return WalkState::Quit;
-                }
+    /// The files that had replacements performed in them, sorted, along with each file in which
+    /// replacements couldn't be made and the reason why.
+    pub fn walk_files_and_replace(
+        &self,
+        cancelled: Option<&AtomicBool>,
+    ) -> (Vec<PathBuf>, Vec<(PathBuf, anyhow::Error)>) {
+        let files_replaced_in = Mutex::new(vec![]);
+        let errors = Mutex::new(vec![]);
+
+        self.reset_walk_counts();
+        for walker in self.dir_config.build_walkers() {
+            if cancelled.is_some_and(|cancelled| cancelled.load(Ordering::Relaxed)) {
+                break;
+            }
+            walker.run(|| {
+                let files_replaced_in = &files_replaced_in;
+                let errors = &errors;
+
+                Box::new(move |result| {
+                    if let Some(cancelled) = cancelled
+                        && cancelled.load(Ordering::Relaxed)
+                    {
+                        return WalkState::Quit;
+                    }
 
-                let Ok(entry) = result else {
-                    return WalkState::Continue;
-                };
+                    let Ok(entry) = result else {
+                        return WalkState::Continue;
+                    };
 
-                if is_searchable(&entry) {
-                    match replace::replace_all_in_file(
-                        entry.path(),
-                        self.search(),
-                        self.replace(),
-                        self.multiline(),
-                    ) {
-                        Ok(replaced_in_file) => {
-                            if replaced_in_file {
-                                counter.fetch_add(1, Ordering::Relaxed);
+                    if self.is_searchable(&entry) {
+                        match replace::replace_all_in_file(
+                            entry.path(),
+                            self.search(),
+                            self.replace(),
+                            self.multiline(),
+                            &self.search_config.line_filter,
+                            self.binary_files(),
+                            self.delete_empty_lines(),
+                            self.regex_timeout(),
+                            Some(&self.non_utf8_lines),
+                        ) {
+                            Ok(replaced_in_file) => {
+                                if replaced_in_file {
+                                    files_replaced_in.lock().unwrap().push(entry.into_path());
+                                }
+                            }
+                            Err(e) if self.record_regex_too_slow(entry.path(), &e) => {}
+                            Err(e) => {
+                                log::error!(
+                                    "Found error when performing replacement in {path_display}: {e}",
+                                    path_display = entry.path().display()
+                                );
+                                errors.lock().unwrap().push((entry.into_path(), e));
                             }
-                        }
-                        Err(e) => {
-                            log::error!(
-                                "Found error when performing replacement in {path_display}: {e}",
-                                path_display = entry.path().display()
-                            );
                         }
                     }
-                }
-                WalkState::Continue
-            })
-        });
+                    WalkState::Continue
+                })
+            });
+        }
+        self.log_walk_counts();
 
-        num_files_replaced_in.load(Ordering::Relaxed)
+        let mut files_replaced_in = files_replaced_in.into_inner().unwrap();
+        files_replaced_in.sort();
+        let mut errors = errors.into_inner().unwrap();
+        errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+        (files_replaced_in, errors)
     }
 }
 
@@ -541,51 +1199,282 @@ fn is_likely_binary(path: &Path) -> bool {
         })
 }
 
-fn is_searchable(entry: &ignore::DirEntry) -> bool {
-    entry.file_type().is_some_and(|ft| ft.is_file()) && !is_likely_binary(entry.path())
+pub fn contains_search(haystack: &str, needle: &SearchType) -> bool {
+    match needle {
+        SearchType::Fixed(fixed_str) => haystack.contains(fixed_str),
+        SearchType::Pattern(pattern) => pattern.is_match(haystack),
+        SearchType::PatternAdvanced(pattern) => pattern.is_match(haystack).is_ok_and(|r| r),
+    }
+}
+
+/// Returns the byte range of every match of `needle` within `haystack`
+pub fn match_spans(haystack: &str, needle: &SearchType) -> Vec<Range<usize>> {
+    match needle {
+        SearchType::Fixed(fixed_str) => haystack
+            .match_indices(fixed_str.as_str())
+            .map(|(start, matched)| start..start + matched.len())
+            .collect(),
+        SearchType::Pattern(pattern) => pattern.find_iter(haystack).map(|m| m.range()).collect(),
+        SearchType::PatternAdvanced(pattern) => pattern
+            .find_iter(haystack)
+            .filter_map(Result::ok)
+            .map(|m| m.range())
+            .collect(),
+    }
+}
+
+/// Counts how many times each distinct matched text occurs across `results`, most frequent first, with ties
+/// ordered by text. For line-mode results, each match of `search` within the line is counted separately
+pub fn match_counts<'a>(
+    results: impl IntoIterator<Item = &'a SearchResult>,
+    search: &SearchType,
+) -> Vec<(String, usize)> {
+    let mut counts = HashMap::<&str, usize>::new();
+    for result in results {
+        match &result.content {
+            MatchContent::Line { content, .. } => {
+                for span in match_spans(content, search) {
+                    *counts.entry(&content[span]).or_default() += 1;
+                }
+            }
+            MatchContent::ByteRange { content, .. } => *counts.entry(content).or_default() += 1,
+        }
+    }
+
+    let mut counts = counts
+        .into_iter()
+        .map(|(text, count)| (text.to_owned(), count))
+        .collect::<Vec<_>>();
+    counts.sort_by(|(text_a, count_a), (text_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| text_a.cmp(text_b))
+    });
+    counts
+}
+
+pub fn search_file(
+    path: &Path,
+    search: &SearchType,
+    multiline: bool,
+    line_filter: &LineFilter,
+) -> anyhow::Result<Vec<SearchResult>> {
+    search_file_with_options(
+        path,
+        search,
+        multiline,
+        line_filter,
+        BinaryFiles::Skip(&BinaryDetection::default()),
+        None,
+        None,
+    )
+}
+
+/// Counts the lines skipped in each file when searching files line by line because they weren't
+/// valid UTF-8, such as lines of files in other encodings
+#[derive(Debug, Default)]
+pub struct NonUtf8Lines {
+    files: Mutex<BTreeMap<PathBuf, usize>>,
+}
+
+impl NonUtf8Lines {
+    /// The number of lines skipped
+    pub fn num_lines(&self) -> usize {
+        self.files.lock().unwrap().values().sum()
+    }
+
+    /// The number of files in which at least one line was skipped
+    pub fn num_files(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+
+    /// Each file in which at least one line was skipped, with the number of lines skipped in it,
+    /// sorted by path
+    pub fn files(&self) -> Vec<(PathBuf, usize)> {
+        self.files
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, num_lines)| (path.clone(), *num_lines))
+            .collect()
+    }
+
+    fn record(&self, path: &Path, num_lines: usize) {
+        log::warn!(
+            "Skipped {num_lines} line(s) of {} containing invalid UTF-8",
+            path.display()
+        );
+        *self
+            .files
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_default() += num_lines;
+    }
+
+    fn reset(&self) {
+        self.files.lock().unwrap().clear();
+    }
+}
+
+/// Describes the lines skipped because they weren't valid UTF-8, followed by a line for each file
+/// giving the number skipped in it, e.g. "Skipped 3 lines of invalid UTF-8 in 1 file\n  a.txt: 3 lines"
+pub fn non_utf8_lines_message(files: &[(String, usize)]) -> String {
+    let plural = |num: usize| if num == 1 { "" } else { "s" };
+    let num_lines = files.iter().map(|(_, num_lines)| num_lines).sum::<usize>();
+    let mut message = format!(
+        "Skipped {num_lines} line{} of invalid UTF-8 in {} file{}",
+        plural(num_lines),
+        files.len(),
+        plural(files.len()),
+    );
+    for (path, num_lines) in files {
+        write!(
+            message,
+            "\n  {path}: {num_lines} line{}",
+            plural(*num_lines)
+        )
+        .unwrap();
+    }
+    message
+}
+
+/// The error returned when searching a file with an advanced regex takes longer than the time
+/// allowed, which can happen with patterns that backtrack catastrophically
+#[derive(Debug)]
+pub enum RegexTooSlow {
+    /// Searching the file took longer than this
+    Timeout(Duration),
+    /// A single match backtracked more than fancy-regex allows
+    BacktrackLimitExceeded,
+}
+
+impl fmt::Display for RegexTooSlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(timeout) => write!(
+                f,
+                "regex too slow: searching took longer than {}ms",
+                timeout.as_millis()
+            ),
+            Self::BacktrackLimitExceeded => {
+                write!(f, "regex too slow: matching backtracked too many times")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegexTooSlow {}
+
+/// Tracks how long a file has been searched for, so that the search can be abandoned once it
+/// exceeds the time allowed
+struct RegexBudget {
+    started: Instant,
+    timeout: Duration,
+}
+
+impl RegexBudget {
+    /// Starts timing a search, returning `None` if there is no timeout or `search` can't
+    /// backtrack catastrophically, i.e. isn't an advanced regex
+    fn start(search: &SearchType, timeout: Option<Duration>) -> Option<Self> {
+        timeout
+            .filter(|_| matches!(search, SearchType::PatternAdvanced(_)))
+            .map(|timeout| Self {
+                started: Instant::now(),
+                timeout,
+            })
+    }
+
+    fn check(&self) -> Result<(), RegexTooSlow> {
+        if self.started.elapsed() > self.timeout {
+            return Err(RegexTooSlow::Timeout(self.timeout));
+        }
+        Ok(())
+    }
+
+    /// Fails if `error` is from a match being abandoned for backtracking too many times, which
+    /// would otherwise be treated as no match. This stops a single line, which can't be
+    /// interrupted by [`Self::check`], from backtracking catastrophically for ever
+    fn check_error(error: &fancy_regex::Error) -> Result<(), RegexTooSlow> {
+        if matches!(
+            error,
+            fancy_regex::Error::RuntimeError(fancy_regex::RuntimeError::BacktrackLimitExceeded)
+        ) {
+            return Err(RegexTooSlow::BacktrackLimitExceeded);
+        }
+        Ok(())
+    }
 }
 
-pub fn contains_search(haystack: &str, needle: &SearchType) -> bool {
-    match needle {
-        SearchType::Fixed(fixed_str) => haystack.contains(fixed_str),
-        SearchType::Pattern(pattern) => pattern.is_match(haystack),
-        SearchType::PatternAdvanced(pattern) => pattern.is_match(haystack).is_ok_and(|r| r),
+/// Whether `haystack` contains a match of `search`, as with [`contains_search`], but failing if
+/// there is a `budget` and a match backtracks too many times
+fn contains_search_within(
+    haystack: &str,
+    search: &SearchType,
+    budget: Option<&RegexBudget>,
+) -> Result<bool, RegexTooSlow> {
+    match (budget, search) {
+        (Some(_), SearchType::PatternAdvanced(pattern)) => match pattern.is_match(haystack) {
+            Ok(is_match) => Ok(is_match),
+            Err(e) => RegexBudget::check_error(&e).map(|()| false),
+        },
+        _ => Ok(contains_search(haystack, search)),
     }
 }
 
-pub fn search_file(
+/// Searches a file as with [`search_file`], handling files whose content looks binary as set by
+/// `binary_files`. When such files are searched anyway, lines that aren't valid UTF-8 are still
+/// skipped when searching line by line, and multiline searches fail.
+///
+/// If `regex_timeout` is set and `search` is an advanced regex, the search fails with
+/// [`RegexTooSlow`] once it has taken longer than `regex_timeout`. This is checked after each line,
+/// or once the whole file has been searched when `multiline` is set. It also fails if a single
+/// match backtracks more than fancy-regex allows, so that one line can't hang the search.
+///
+/// Lines that aren't valid UTF-8 are skipped when searching line by line, and recorded in
+/// `non_utf8_lines` if set.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn search_file_with_options(
     path: &Path,
     search: &SearchType,
     multiline: bool,
+    line_filter: &LineFilter,
+    binary_files: BinaryFiles<'_>,
+    regex_timeout: Option<Duration>,
+    non_utf8_lines: Option<&NonUtf8Lines>,
 ) -> anyhow::Result<Vec<SearchResult>> {
     if search.is_empty() {
         return Ok(vec![]);
     }
+    let line_ranges = match &line_filter.line_ranges {
+        Some(line_ranges) => match line_ranges.for_file(path) {
+            Some(ranges) => Some(ranges),
+            None => return Ok(vec![]),
+        },
+        None => None,
+    };
+    let budget = RegexBudget::start(search, regex_timeout);
     let mut file = File::open(path)?;
 
-    // Fast upfront binary sniff (8 KiB)
-    let mut probe = [0u8; 8192];
-    let read = file.read(&mut probe).unwrap_or(0);
-    if matches!(inspect(&probe[..read]), ContentType::BINARY) {
+    // Fast upfront binary sniff
+    if binary_files.skips_file(path, &mut file)? {
         return Ok(Vec::new());
     }
-    file.seek(SeekFrom::Start(0))?;
 
     if multiline {
-        let content = std::fs::read_to_string(path).with_context(|| {
-            format!(
-                "Failed to read file as UTF-8 for multiline search: {}",
-                path.display()
-            )
-        })?;
-        return Ok(search_multiline(&content, search, Some(path)));
+        return search_whole_file(path, search, budget.as_ref());
     }
 
     // Line-by-line search for non-multiline mode
-    let reader = BufReader::with_capacity(16384, file);
+    let mut reader = BufReader::with_capacity(16384, file);
+    // The byte offset of the current line within the file, used for the results of `--columns`
+    let mut line_start_byte = if line_reader::skip_utf8_bom(&mut reader)? {
+        line_reader::UTF8_BOM.len()
+    } else {
+        0
+    };
     let mut results = Vec::new();
 
     let mut read_errors = 0;
+    let mut num_non_utf8_lines = 0;
 
     for (mut line_number, line_result) in reader.lines_with_endings().enumerate() {
         line_number += 1; // Ensure line-number is 1-indexed
@@ -607,24 +1496,149 @@ pub fn search_file(
                 continue;
             }
         };
+        let line_start = line_start_byte;
+        line_start_byte += line_bytes.len() + line_ending.as_bytes().len();
+        if line_ranges.is_some_and(|ranges| !ranges.iter().any(|r| r.contains(&line_number))) {
+            continue;
+        }
 
-        if let Ok(line_content) = String::from_utf8(line_bytes)
-            && contains_search(&line_content, search)
+        let Ok(line_content) = String::from_utf8(line_bytes) else {
+            num_non_utf8_lines += 1;
+            continue;
+        };
+        if contains_search_within(&line_content, search, budget.as_ref())?
+            && line_filter.allows(&line_content)
         {
-            let result = SearchResult::new_line(
-                Some(path.to_path_buf()),
-                line_number,
-                line_content,
-                line_ending,
-                true,
-            );
-            results.push(result);
+            if let Some(columns) = &line_filter.columns {
+                let line = Line {
+                    content: line_content,
+                    line_ending,
+                };
+                results.extend(results_in_columns(
+                    path,
+                    (line_number, &line),
+                    line_start,
+                    search,
+                    columns,
+                ));
+            } else {
+                let result = SearchResult::new_line(
+                    Some(path.to_path_buf()),
+                    line_number,
+                    line_content,
+                    line_ending,
+                    true,
+                );
+                results.push(result);
+            }
+        }
+        if let Some(budget) = &budget {
+            budget.check()?;
+        }
+    }
+
+    if num_non_utf8_lines > 0 {
+        match non_utf8_lines {
+            Some(non_utf8_lines) => non_utf8_lines.record(path, num_non_utf8_lines),
+            None => log::warn!(
+                "Skipped {num_non_utf8_lines} line(s) of {} containing invalid UTF-8",
+                path.display()
+            ),
+        }
+    }
+
+    Ok(results)
+}
+
+/// Searches the whole of a file at once, for multiline searches
+fn search_whole_file(
+    path: &Path,
+    search: &SearchType,
+    budget: Option<&RegexBudget>,
+) -> anyhow::Result<Vec<SearchResult>> {
+    let content = std::fs::read_to_string(path).with_context(|| {
+        format!(
+            "Failed to read file as UTF-8 for multiline search: {}",
+            path.display()
+        )
+    })?;
+    // Search without the BOM, but keep byte offsets relative to the start of the file
+    let (content, has_bom) = line_reader::strip_utf8_bom(&content);
+    let mut results = match (budget, search) {
+        (Some(_), SearchType::PatternAdvanced(regex)) => {
+            search_multiline_advanced(content, regex, path)?
+        }
+        _ => search_multiline(content, search, Some(path)),
+    };
+    if let Some(budget) = budget {
+        budget.check()?;
+    }
+    if has_bom {
+        for result in &mut results {
+            if let MatchContent::ByteRange {
+                byte_start,
+                byte_end,
+                ..
+            } = &mut result.content
+            {
+                *byte_start += line_reader::UTF8_BOM.len();
+                *byte_end += line_reader::UTF8_BOM.len();
+            }
         }
     }
+    Ok(results)
+}
 
+/// Searches content for an advanced regex as with [`search_multiline`], failing rather than skipping
+/// matches that backtrack too many times
+fn search_multiline_advanced(
+    content: &str,
+    regex: &FancyRegex,
+    path: &Path,
+) -> Result<Vec<SearchResult>, RegexTooSlow> {
+    let line_index = LineIndex::new(content);
+    let mut results = vec![];
+    for mat in regex.find_iter(content) {
+        match mat {
+            Ok(mat) => results.push(create_search_result_from_bytes(
+                mat.start(),
+                mat.end(),
+                Some(path),
+                &line_index,
+            )),
+            Err(e) => RegexBudget::check_error(&e)?,
+        }
+    }
     Ok(results)
 }
 
+/// A result for each match of `search` on the line that starts within `columns`, so that only
+/// those matches are replaced. `line_start` is the byte offset of the line within the file
+fn results_in_columns(
+    path: &Path,
+    (line_number, line): (usize, &Line),
+    line_start: usize,
+    search: &SearchType,
+    columns: &ColumnRange,
+) -> Vec<SearchResult> {
+    match_spans(&line.content, search)
+        .into_iter()
+        .filter(|span| columns.contains(line.content[..span.start].chars().count() + 1))
+        .map(|span| {
+            SearchResult::new_byte_range(ByteRangeParams {
+                path: Some(path.to_path_buf()),
+                lines: vec![(line_number, line.clone())],
+                match_start_in_first_line: span.start,
+                match_end_in_last_line: span.end,
+                byte_start: line_start + span.start,
+                byte_end: line_start + span.end,
+                content: line.content[span].to_owned(),
+                included: true,
+            })
+        })
+        .collect()
+}
+
 /// Search content for multiline patterns and return `SearchResults`
 pub(crate) fn search_multiline(
     content: &str,
@@ -761,7 +1775,7 @@ impl<'a> LineIndex<'a> {
 
 /// Create a `SearchResult` from byte offsets in the content.
 /// `end_byte` is exclusive (standard Rust range semantics).
-fn create_search_result_from_bytes(
+pub(crate) fn create_search_result_from_bytes(
     start_byte: usize,
     end_byte: usize,
     path: Option<&Path>,
@@ -843,6 +1857,37 @@ mod tests {
         }
     }
 
+    /// A searcher for `foo` in each of `dirs`, with every other option left at its default. Tests
+    /// set the options they need on the searcher's configs
+    fn test_searcher(dirs: &[&Path]) -> FileSearcher {
+        FileSearcher::new(
+            ParsedSearchConfig {
+                search: SearchType::Fixed("foo".to_string()),
+                replace: String::new(),
+                multiline: false,
+                line_filter: LineFilter::default(),
+                delete_empty_lines: false,
+            },
+            ParsedDirConfig {
+                roots: dirs
+                    .iter()
+                    .map(|dir| SearchRoot {
+                        overrides: Override::empty(),
+                        dir: dir.to_path_buf(),
+                    })
+                    .collect(),
+                include_hidden: false,
+                include_binary: false,
+                binary_detection: BinaryDetection::default(),
+                max_depth: None,
+                modified_since: None,
+                tracked_files: None,
+                approved_files: None,
+                regex_timeout: None,
+            },
+        )
+    }
+
     mod test_helpers {
         use super::*;
 
@@ -862,6 +1907,7 @@ mod tests {
                 replacement: "replacement".to_string(),
                 replace_result,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -1271,6 +2317,59 @@ mod tests {
         }
     }
 
+    mod match_spans_tests {
+        use super::*;
+
+        #[test]
+        fn test_match_spans() {
+            let haystack = "foo bar föo foo";
+            let test_cases = [
+                (test_helpers::create_fixed_search("foo"), vec![0..3, 13..16]),
+                (
+                    test_helpers::create_pattern_search("f.o"),
+                    vec![0..3, 8..12, 13..16],
+                ),
+                (
+                    test_helpers::create_advanced_pattern_search(r"f.o(?= )"),
+                    vec![0..3, 8..12],
+                ),
+                (test_helpers::create_fixed_search("baz"), vec![]),
+            ];
+
+            for (search_type, expected) in test_cases {
+                assert_eq!(
+                    match_spans(haystack, &search_type),
+                    expected,
+                    "Spans incorrect for: {search_type:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn test_match_counts() {
+            let results = ["Foo foo", "fooo", "foo bar fooo", "bar"]
+                .into_iter()
+                .enumerate()
+                .map(|(idx, line)| {
+                    SearchResult::new_line(None, idx + 1, line.to_owned(), LineEnding::Lf, true)
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                match_counts(&results, &test_helpers::create_pattern_search("(?i)fo+")),
+                vec![
+                    ("foo".to_owned(), 2),
+                    ("fooo".to_owned(), 2),
+                    ("Foo".to_owned(), 1),
+                ]
+            );
+            assert_eq!(
+                match_counts(&results, &test_helpers::create_fixed_search("baz")),
+                vec![]
+            );
+        }
+    }
+
     mod file_searcher_tests {
         use super::*;
 
@@ -1669,6 +2768,666 @@ mod tests {
         assert_eq!(byte_range_bytes(&results[2]), (20, 23));
     }
 
+    #[test]
+    fn test_search_file_skips_non_utf8_lines() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut temp_file, b"foo one\n\xff\xfe foo\nfoo three\n").unwrap();
+
+        let results = search_file(
+            temp_file.path(),
+            &SearchType::Fixed("foo".to_string()),
+            false,
+            &LineFilter::default(),
+        )
+        .unwrap();
+
+        let line_numbers: Vec<_> = results
+            .iter()
+            .map(SearchResult::start_line_number)
+            .collect();
+        assert_eq!(line_numbers, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_search_file_require_filters_lines() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut temp_file, b"let foo = 1;\nfoo();\nlet bar = foo;\n")
+            .unwrap();
+        let line_filter = LineFilter {
+            require: Some(Regex::new(r"^let ").unwrap()),
+            exclude: None,
+            columns: None,
+            line_ranges: None,
+        };
+
+        let results = search_file(
+            temp_file.path(),
+            &SearchType::Fixed("foo".to_string()),
+            false,
+            &line_filter,
+        )
+        .unwrap();
+
+        let line_numbers: Vec<_> = results
+            .iter()
+            .map(SearchResult::start_line_number)
+            .collect();
+        assert_eq!(line_numbers, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_search_file_columns() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut temp_file, "\u{FEFF}ab ab\r\nébab ab\nxx\n".as_bytes())
+            .unwrap();
+        let line_filter = LineFilter {
+            columns: Some("2:4".parse().unwrap()),
+            ..LineFilter::default()
+        };
+
+        let results = search_file(
+            temp_file.path(),
+            &SearchType::Fixed("ab".to_string()),
+            false,
+            &line_filter,
+        )
+        .unwrap();
+
+        // Columns count characters rather than bytes, and byte offsets include the BOM and line endings
+        let matches: Vec<_> = results
+            .iter()
+            .map(|res| match &res.content {
+                MatchContent::ByteRange {
+                    byte_start,
+                    byte_end,
+                    content,
+                    ..
+                } => (
+                    res.start_line_number(),
+                    *byte_start,
+                    *byte_end,
+                    content.as_str(),
+                ),
+                MatchContent::Line { .. } => panic!("Expected byte range results"),
+            })
+            .collect();
+        assert_eq!(matches, vec![(1, 6, 8, "ab"), (2, 13, 15, "ab")]);
+    }
+
+    #[test]
+    fn test_parse_column_range() {
+        for (input, start, end) in [
+            ("1:4", 1, Some(4)),
+            ("3:3", 3, Some(3)),
+            (":4", 1, Some(4)),
+            ("10:", 10, None),
+        ] {
+            let columns = input.parse::<ColumnRange>().unwrap();
+            assert_eq!(columns, ColumnRange { start, end }, "{input}");
+        }
+        assert_eq!("1:4".parse::<ColumnRange>().unwrap().to_string(), "1:4");
+        assert_eq!(":".parse::<ColumnRange>().unwrap().to_string(), "1:");
+
+        for input in ["4", "0:4", "4:2", "a:4", "1:-1"] {
+            assert!(input.parse::<ColumnRange>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_line_ranges() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for file in ["a.rs", "b.rs", "c.rs"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let line_ranges = LineRanges::parse(
+            "# From the linter\na.rs:10-20,40\n\n./b.rs: 3 - 5 \na.rs:1\n",
+            root,
+        )
+        .unwrap();
+        assert_eq!(
+            line_ranges.for_file(&root.join("a.rs")),
+            Some([10..=20, 40..=40, 1..=1].as_slice())
+        );
+        assert_eq!(
+            line_ranges.for_file(&root.join("b.rs")),
+            Some([3..=5].as_slice())
+        );
+        assert_eq!(line_ranges.for_file(&root.join("c.rs")), None);
+        assert_eq!(line_ranges.source(), None);
+
+        for content in [
+            "a.rs",
+            "a.rs:",
+            "a.rs:0",
+            "a.rs:5-2",
+            "a.rs:1-x",
+            "missing.rs:1",
+        ] {
+            assert!(LineRanges::parse(content, root).is_err(), "{content}");
+        }
+    }
+
+    #[test]
+    fn test_search_file_line_ranges() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("a.txt"), "foo\nfoo\nfoo\nfoo\n").unwrap();
+        std::fs::write(root.join("b.txt"), "foo\n").unwrap();
+        let search = SearchType::Fixed("foo".to_owned());
+        let line_filter = LineFilter {
+            line_ranges: Some(Arc::new(LineRanges::parse("a.txt:2-3", root).unwrap())),
+            ..LineFilter::default()
+        };
+
+        let results = search_file(&root.join("a.txt"), &search, false, &line_filter).unwrap();
+        let line_numbers = results
+            .iter()
+            .map(SearchResult::start_line_number)
+            .collect::<Vec<_>>();
+        assert_eq!(line_numbers, vec![2, 3]);
+
+        // Files that aren't listed aren't searched
+        let results = search_file(&root.join("b.txt"), &search, false, &line_filter).unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_file_strips_bom() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut temp_file, "\u{FEFF}foo\nfoo\n".as_bytes()).unwrap();
+        let search = SearchType::Pattern(Regex::new("^foo$").unwrap());
+
+        let results =
+            search_file(temp_file.path(), &search, false, &LineFilter::default()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].content.matched_text(), "foo");
+
+        let search = SearchType::Pattern(Regex::new(r"\Afoo").unwrap());
+        let results = search_file(temp_file.path(), &search, true, &LineFilter::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(byte_range_bytes(&results[0]), (3, 6));
+        assert_eq!(byte_range_content(&results[0]), "foo");
+    }
+
+    #[test]
+    fn test_walk_files_counts_files_scanned() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "foo\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "bar\n").unwrap();
+        std::fs::write(temp_dir.path().join("c.txt"), "baz foo\n").unwrap();
+        let searcher = test_searcher(&[temp_dir.path()]);
+        let files_scanned = AtomicUsize::new(0);
+        let files_with_matches = std::sync::Arc::new(AtomicUsize::new(0));
+
+        searcher.walk_files(None, None, Some(&files_scanned), || {
+            let files_with_matches = files_with_matches.clone();
+            Box::new(move |_| {
+                files_with_matches.fetch_add(1, Ordering::Relaxed);
+                WalkState::Continue
+            })
+        });
+
+        assert_eq!(files_scanned.load(Ordering::Relaxed), 3);
+        assert_eq!(files_with_matches.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_walk_files_waits_while_paused() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "foo\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "foo bar\n").unwrap();
+        let searcher = test_searcher(&[temp_dir.path()]);
+        let files_scanned = AtomicUsize::new(0);
+        let paused = SearchPause::default();
+        paused.pause();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                searcher.walk_files(None, Some(&paused), Some(&files_scanned), || {
+                    Box::new(|_| WalkState::Continue)
+                });
+            });
+
+            thread::sleep(std::time::Duration::from_millis(100));
+            assert!(paused.is_paused());
+            assert_eq!(files_scanned.load(Ordering::Relaxed), 0);
+            paused.resume();
+        });
+
+        assert_eq!(files_scanned.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_walk_files_searches_each_root() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for dir in ["a", "b", "c"] {
+            std::fs::create_dir(temp_dir.path().join(dir)).unwrap();
+            std::fs::write(temp_dir.path().join(dir).join("file.txt"), "foo\n").unwrap();
+        }
+        let searcher = test_searcher(&[&temp_dir.path().join("a"), &temp_dir.path().join("b")]);
+        let paths = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+        searcher.walk_files(None, None, None, || {
+            let paths = paths.clone();
+            Box::new(move |results| {
+                let mut paths = paths.lock().unwrap();
+                paths.extend(results.into_iter().filter_map(|res| res.path));
+                WalkState::Continue
+            })
+        });
+
+        let mut paths = paths.lock().unwrap().clone();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                temp_dir.path().join("a").join("file.txt"),
+                temp_dir.path().join("b").join("file.txt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_files_and_approved_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for file in ["b.txt", "a.txt", "image.png", "c.txt"] {
+            std::fs::write(temp_dir.path().join(file), "foo\n").unwrap();
+        }
+        let mut searcher = test_searcher(&[temp_dir.path()]);
+        let files = searcher.dir_config.list_files();
+        assert_eq!(
+            files,
+            ["a.txt", "b.txt", "c.txt"]
+                .map(|file| temp_dir.path().join(file))
+                .to_vec()
+        );
+
+        searcher.dir_config.approved_files = Some(Arc::new(HashSet::from([
+            files[0].clone(),
+            files[2].clone(),
+        ])));
+        let paths = Arc::new(Mutex::new(vec![]));
+        searcher.walk_files(None, None, None, || {
+            let paths = paths.clone();
+            Box::new(move |results| {
+                let mut paths = paths.lock().unwrap();
+                paths.extend(results.into_iter().filter_map(|res| res.path));
+                WalkState::Continue
+            })
+        });
+
+        let mut paths = paths.lock().unwrap().clone();
+        paths.sort();
+        assert_eq!(paths, vec![files[0].clone(), files[2].clone()]);
+    }
+
+    fn write_binary_test_files(dir: &Path) {
+        std::fs::write(dir.join("text.txt"), "foo\n").unwrap();
+        std::fs::write(dir.join("image.png"), "foo\n").unwrap();
+        std::fs::write(dir.join("data.dat"), b"\x00\x01foo\n\xFF\xFEfoo\n").unwrap();
+    }
+
+    fn walk_and_collect(searcher: &FileSearcher) -> Vec<SearchResult> {
+        let results = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        searcher.walk_files(None, None, None, || {
+            let results = results.clone();
+            Box::new(move |file_results| {
+                results.lock().unwrap().extend(file_results);
+                WalkState::Continue
+            })
+        });
+        let mut results = std::mem::take(&mut *results.lock().unwrap());
+        results.sort_by(|a, b| a.path.cmp(&b.path));
+        results
+    }
+
+    #[test]
+    fn test_walk_files_skips_binary_files_by_default() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        write_binary_test_files(temp_dir.path());
+        let searcher = test_searcher(&[temp_dir.path()]);
+
+        let results = walk_and_collect(&searcher);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, Some(temp_dir.path().join("text.txt")));
+        assert_eq!(searcher.num_binary_files_included(), 0);
+    }
+
+    #[test]
+    fn test_walk_files_includes_binary_files_when_enabled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        write_binary_test_files(temp_dir.path());
+        let mut searcher = test_searcher(&[temp_dir.path()]);
+        searcher.dir_config.include_binary = true;
+
+        let results = walk_and_collect(&searcher);
+
+        // The line that isn't valid UTF-8 is skipped
+        let paths = results
+            .iter()
+            .map(|r| (r.path.clone().unwrap(), r.start_line_number()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                (temp_dir.path().join("data.dat"), 1),
+                (temp_dir.path().join("image.png"), 1),
+                (temp_dir.path().join("text.txt"), 1),
+            ]
+        );
+        assert_eq!(searcher.num_binary_files_included(), 2);
+
+        // The count is reset on each walk
+        walk_and_collect(&searcher);
+        assert_eq!(searcher.num_binary_files_included(), 2);
+
+        // Files are also counted when replacing, including in multiline mode where each file is
+        // read whole
+        searcher.walk_files_and_replace(None);
+        assert_eq!(searcher.num_binary_files_included(), 2);
+        searcher.search_config.multiline = true;
+        searcher.walk_files_and_replace(None);
+        assert_eq!(searcher.num_binary_files_included(), 2);
+    }
+
+    #[test]
+    fn test_walk_files_counts_non_utf8_lines() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("latin1.txt"), b"foo\ncaf\xE9 foo\n\xE9t\xE9\n").unwrap();
+        std::fs::write(root.join("utf8.txt"), "café foo\n").unwrap();
+        let searcher = test_searcher(&[root]);
+
+        assert_eq!(walk_and_collect(&searcher).len(), 2);
+        assert_eq!(searcher.non_utf8_lines().num_lines(), 2);
+        assert_eq!(searcher.non_utf8_lines().num_files(), 1);
+        assert_eq!(
+            searcher.non_utf8_lines().files(),
+            vec![(root.join("latin1.txt"), 2)]
+        );
+        assert_eq!(
+            non_utf8_lines_message(&[("latin1.txt".to_owned(), 2), ("other.txt".to_owned(), 1)]),
+            "Skipped 3 lines of invalid UTF-8 in 2 files\n  latin1.txt: 2 lines\n  other.txt: 1 line"
+        );
+
+        // The counts are reset on each walk, including when replacing
+        let (files_replaced_in, _) = searcher.walk_files_and_replace(None);
+        assert_eq!(files_replaced_in.len(), 2);
+        assert_eq!(searcher.non_utf8_lines().num_lines(), 2);
+        assert_eq!(searcher.non_utf8_lines().num_files(), 1);
+    }
+
+    #[test]
+    fn test_walk_files_max_depth() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::create_dir_all(root.join("c")).unwrap();
+        for file in ["top.txt", "a/mid.txt", "a/b/deep.txt", "c/mid.txt"] {
+            std::fs::write(root.join(file), "foo\n").unwrap();
+        }
+
+        let walked_paths = |max_depth| {
+            let mut searcher = test_searcher(&[root]);
+            searcher.dir_config.max_depth = max_depth;
+            let paths = walk_and_collect(&searcher)
+                .into_iter()
+                .map(|r| r.path.unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(paths, searcher.dir_config.list_files());
+            (paths, searcher.num_dirs_beyond_max_depth())
+        };
+
+        assert_eq!(walked_paths(Some(1)), (vec![root.join("top.txt")], 2));
+        assert_eq!(
+            walked_paths(Some(2)),
+            (
+                vec![
+                    root.join("a/mid.txt"),
+                    root.join("c/mid.txt"),
+                    root.join("top.txt")
+                ],
+                1
+            )
+        );
+        assert_eq!(walked_paths(None).1, 0);
+        assert_eq!(walked_paths(None).0.len(), 4);
+    }
+
+    #[test]
+    fn test_walk_files_modified_since() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let now = std::time::SystemTime::now();
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        for (file, age) in [("old.txt", 3 * day), ("new.txt", day)] {
+            let path = root.join(file);
+            std::fs::write(&path, "foo\n").unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - age)
+                .unwrap();
+        }
+
+        let mut searcher = test_searcher(&[root]);
+        searcher.dir_config.modified_since = Some(now - 2 * day);
+        let paths = walk_and_collect(&searcher)
+            .into_iter()
+            .map(|r| r.path.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![root.join("new.txt")]);
+        assert_eq!(searcher.dir_config.list_files(), paths);
+
+        searcher.dir_config.modified_since = None;
+        assert_eq!(walk_and_collect(&searcher).len(), 2);
+    }
+
+    #[test]
+    fn test_walk_files_regex_timeout() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for file in ["a.txt", "b.txt"] {
+            std::fs::write(root.join(file), "foo\nfoobar\n").unwrap();
+        }
+
+        let mut searcher = test_searcher(&[root]);
+        searcher.search_config.search =
+            SearchType::PatternAdvanced(FancyRegex::new("foo(?!bar)").unwrap());
+        searcher.search_config.replace = "baz".to_owned();
+        searcher.dir_config.regex_timeout = Some(Duration::ZERO);
+        assert!(walk_and_collect(&searcher).is_empty());
+        assert_eq!(searcher.num_files_regex_too_slow(), 2);
+
+        // Files that are too slow to search aren't reported as errors when replacing
+        let (files_replaced_in, errors) = searcher.walk_files_and_replace(None);
+        assert!(files_replaced_in.is_empty() && errors.is_empty());
+        assert_eq!(searcher.num_files_regex_too_slow(), 2);
+        assert_eq!(
+            std::fs::read_to_string(root.join("a.txt")).unwrap(),
+            "foo\nfoobar\n"
+        );
+
+        searcher.dir_config.regex_timeout = Some(Duration::from_secs(60));
+        assert_eq!(walk_and_collect(&searcher).len(), 2);
+        assert_eq!(searcher.num_files_regex_too_slow(), 0);
+
+        // Other searches can't backtrack catastrophically, so aren't timed
+        searcher.search_config.search = SearchType::Pattern(Regex::new("foo").unwrap());
+        searcher.dir_config.regex_timeout = Some(Duration::ZERO);
+        assert_eq!(walk_and_collect(&searcher).len(), 4);
+        assert_eq!(searcher.num_files_regex_too_slow(), 0);
+    }
+
+    #[test]
+    fn test_search_file_regex_backtracking_catastrophically() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        std::fs::write(&path, format!("{}b\n", "a".repeat(40))).unwrap();
+        // The lookbehind makes fancy-regex backtrack, rather than handing `(a+)+$` to the regex
+        // crate, which doesn't
+        let search = SearchType::PatternAdvanced(FancyRegex::new("(a+)+$(?<!b)").unwrap());
+
+        for multiline in [false, true] {
+            let error = search_file_with_options(
+                &path,
+                &search,
+                multiline,
+                &LineFilter::default(),
+                BinaryFiles::Search,
+                Some(Duration::from_secs(60)),
+                None,
+            )
+            .unwrap_err();
+            assert!(
+                matches!(
+                    error.downcast_ref::<RegexTooSlow>(),
+                    Some(RegexTooSlow::BacktrackLimitExceeded)
+                ),
+                "unexpected error: {error}"
+            );
+
+            // Without a timeout, matches that backtrack too many times are skipped as before
+            let results = search_file_with_options(
+                &path,
+                &search,
+                multiline,
+                &LineFilter::default(),
+                BinaryFiles::Search,
+                None,
+                None,
+            )
+            .unwrap();
+            assert!(results.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_binary_detection_text_extensions() {
+        let detection = BinaryDetection {
+            text_extensions: vec!["png".to_owned(), ".DAT".to_owned()],
+            ..BinaryDetection::default()
+        };
+        assert!(!detection.has_binary_extension(Path::new("image.png")));
+        assert!(!detection.has_binary_extension(Path::new("IMAGE.PNG")));
+        assert!(detection.has_binary_extension(Path::new("archive.zip")));
+
+        let path = Path::new("data.dat");
+        let content = b"\x00\x01foo\n";
+        assert!(!detection.is_binary_content(path, content));
+        assert!(BinaryDetection::default().is_binary_content(path, content));
+    }
+
+    #[test]
+    fn test_binary_detection_sample_size() {
+        let path = Path::new("data.txt");
+        let mut content = "foo\n".repeat(100).into_bytes();
+        content.extend(b"\x00\x01");
+
+        let with_sample_size = |sample_size| BinaryDetection {
+            sample_size,
+            ..BinaryDetection::default()
+        };
+        assert!(with_sample_size(content.len()).is_binary_content(path, &content));
+        assert!(!with_sample_size(100).is_binary_content(path, &content));
+        assert!(!with_sample_size(0).is_binary_content(path, &content));
+    }
+
+    #[test]
+    fn test_walk_files_uses_configured_binary_detection() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        write_binary_test_files(temp_dir.path());
+        let mut searcher = test_searcher(&[temp_dir.path()]);
+        searcher.dir_config.binary_detection.text_extensions = vec!["png".to_owned()];
+
+        let results = walk_and_collect(&searcher);
+
+        let paths = results
+            .iter()
+            .map(|r| r.path.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                temp_dir.path().join("image.png"),
+                temp_dir.path().join("text.txt"),
+            ]
+        );
+    }
+
+    mod line_filter_tests {
+        use super::*;
+
+        fn line_filter(require: Option<&str>, exclude: Option<&str>) -> LineFilter {
+            LineFilter {
+                require: require.map(|re| Regex::new(re).unwrap()),
+                exclude: exclude.map(|re| Regex::new(re).unwrap()),
+                columns: None,
+                line_ranges: None,
+            }
+        }
+
+        #[test]
+        fn test_no_filters_allows_all_lines() {
+            let filter = line_filter(None, None);
+            assert!(filter.allows("let foo = 1;"));
+            assert!(filter.allows("// foo"));
+        }
+
+        #[test]
+        fn test_require_only() {
+            let filter = line_filter(Some("let"), None);
+            assert!(filter.allows("let foo = 1;"));
+            assert!(!filter.allows("foo = 1;"));
+        }
+
+        #[test]
+        fn test_exclude_only() {
+            let filter = line_filter(None, Some("^//"));
+            assert!(filter.allows("let foo = 1;"));
+            assert!(!filter.allows("// let foo = 1;"));
+        }
+
+        #[test]
+        fn test_require_and_exclude() {
+            let filter = line_filter(Some("let"), Some("^//"));
+            assert!(filter.allows("let foo = 1;"));
+            assert!(!filter.allows("foo = 1;"));
+            assert!(!filter.allows("// foo = 1;"));
+            // Exclude takes precedence over require
+            assert!(!filter.allows("// let foo = 1;"));
+        }
+
+        #[test]
+        fn test_search_file_applies_filters_after_search_match() {
+            let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+            std::io::Write::write_all(
+                &mut temp_file,
+                b"let foo = 1;\n// let foo = 2;\nfoo();\nlet bar = 3;\n",
+            )
+            .unwrap();
+            let search = SearchType::Fixed("foo".to_string());
+
+            for (filter, expected) in [
+                (line_filter(None, None), vec![1, 2, 3]),
+                (line_filter(Some("let"), None), vec![1, 2]),
+                (line_filter(None, Some("^//")), vec![1, 3]),
+                (line_filter(Some("let"), Some("^//")), vec![1]),
+            ] {
+                let results = search_file(temp_file.path(), &search, false, &filter).unwrap();
+                let line_numbers: Vec<_> = results
+                    .iter()
+                    .map(SearchResult::start_line_number)
+                    .collect();
+                assert_eq!(line_numbers, expected, "filter: {filter:?}");
+            }
+        }
+    }
+
     #[test]
     fn test_extract_lines_crlf_content_should_not_include_cr() {
         let content = "hello\r\nworld\r\n";
@@ -0,0 +1,506 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    line_reader::{BufReadExt, LineEnding},
+    search::{
+        LineFilter, LineIndex, MatchContent, SearchResult, SearchType, contains_search,
+        create_search_result_from_bytes,
+    },
+};
+
+/// How content piped to stdin is interpreted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StdinFormat {
+    /// A single blob of text, which is transformed and written back out
+    #[default]
+    Plain,
+    /// The contents of several files, each preceded by a `==> path <==` marker, as output by
+    /// `head` when given multiple files. Each section is numbered from the first line of its file
+    Headers,
+    /// One matched line per line of input in the form `path:line:content`, as output by `grep -rn`
+    Grep,
+    /// The JSON Lines output of `rg --json`, from which the lines and submatches of each `match`
+    /// message are taken
+    RgJson,
+}
+
+/// A line of framed stdin, mapped back to the file and line it came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FramedLine {
+    pub path: PathBuf,
+    /// 1-indexed line number within `path`
+    pub line_number: usize,
+    pub content: String,
+    pub line_ending: LineEnding,
+}
+
+fn grep_line_regex() -> &'static Regex {
+    static GREP_LINE: OnceLock<Regex> = OnceLock::new();
+    GREP_LINE.get_or_init(|| Regex::new(r"^(.+?):(\d+):(.*)$").expect("Invalid grep line regex"))
+}
+
+impl StdinFormat {
+    /// Whether stdin content in this format refers to lines in files, rather than being a single blob
+    pub fn is_framed(self) -> bool {
+        self != Self::Plain
+    }
+
+    /// Splits `content` into lines mapped back to their source files. Returns an empty `Vec` for
+    /// [`StdinFormat::Plain`], as plain content has no source files. Lines that aren't valid
+    /// UTF-8 or that don't fit the format (such as lines before the first header) are skipped.
+    ///
+    /// With [`StdinFormat::Headers`], each section is assumed to start at the first line of its
+    /// file, as with the output of `head`.
+    pub fn parse_lines(self, content: &str) -> Vec<FramedLine> {
+        let lines = Cursor::new(content.as_bytes())
+            .lines_with_endings()
+            .filter_map(|line_result| {
+                let (line_bytes, line_ending) = line_result.ok()?;
+                let line = String::from_utf8(line_bytes).ok()?;
+                Some((line, line_ending))
+            });
+
+        match self {
+            Self::Plain => vec![],
+            Self::Headers => parse_header_lines(lines),
+            Self::Grep => lines
+                .filter_map(|(line, line_ending)| {
+                    let caps = grep_line_regex().captures(&line)?;
+                    Some(FramedLine {
+                        path: PathBuf::from(&caps[1]),
+                        line_number: caps[2].parse().ok()?,
+                        content: caps[3].to_owned(),
+                        line_ending,
+                    })
+                })
+                .collect(),
+            Self::RgJson => rg_json_matches(content)
+                .filter_map(|rg_match| {
+                    let path = PathBuf::from(rg_match.path.text.as_ref()?);
+                    Some(rg_match.framed_lines(&path))
+                })
+                .flatten()
+                .collect(),
+        }
+    }
+}
+
+/// The results of searching framed stdin content
+#[derive(Debug, Default)]
+pub struct FramedSearch {
+    pub results: Vec<SearchResult>,
+    /// Number of `rg --json` matches skipped because their path wasn't valid UTF-8
+    pub num_non_utf8_paths: usize,
+}
+
+/// Searches framed stdin content line by line, returning results that carry the path and line
+/// number of the file each matching line came from.
+///
+/// With [`StdinFormat::RgJson`] and an empty search, the results are instead the submatches found
+/// by ripgrep, so that its output can be replaced in without repeating the search.
+pub fn search_framed(
+    content: &str,
+    format: StdinFormat,
+    search: &SearchType,
+    line_filter: &LineFilter,
+) -> FramedSearch {
+    if format == StdinFormat::RgJson {
+        return search_rg_json(content, search, line_filter);
+    }
+    if search.is_empty() {
+        return FramedSearch::default();
+    }
+    let results = format
+        .parse_lines(content)
+        .into_iter()
+        .filter(|line| line_matches(line, search, line_filter))
+        .map(line_result)
+        .collect();
+    FramedSearch {
+        results,
+        num_non_utf8_paths: 0,
+    }
+}
+
+/// Describes the `rg --json` matches skipped because their path wasn't valid UTF-8
+pub fn non_utf8_paths_message(num_matches: usize) -> String {
+    format!(
+        "Skipped {num_matches} match{suffix} in files with paths that aren't valid UTF-8",
+        suffix = if num_matches == 1 { "" } else { "es" },
+    )
+}
+
+fn line_matches(line: &FramedLine, search: &SearchType, line_filter: &LineFilter) -> bool {
+    contains_search(&line.content, search) && line_filter.allows(&line.content)
+}
+
+fn line_result(line: FramedLine) -> SearchResult {
+    SearchResult::new_line(
+        Some(line.path),
+        line.line_number,
+        line.content,
+        line.line_ending,
+        true,
+    )
+}
+
+fn search_rg_json(content: &str, search: &SearchType, line_filter: &LineFilter) -> FramedSearch {
+    let mut framed_search = FramedSearch::default();
+    for rg_match in rg_json_matches(content) {
+        let Some(path) = rg_match.path.text.clone() else {
+            framed_search.num_non_utf8_paths += 1;
+            continue;
+        };
+        let path = PathBuf::from(path);
+        if search.is_empty() {
+            framed_search
+                .results
+                .extend(rg_match.submatch_results(&path, line_filter));
+        } else {
+            framed_search.results.extend(
+                rg_match
+                    .framed_lines(&path)
+                    .into_iter()
+                    .filter(|line| line_matches(line, search, line_filter))
+                    .map(line_result),
+            );
+        }
+    }
+    framed_search
+}
+
+/// A message from `rg --json`. Only `match` messages are used: `begin`, `end`, `context` and
+/// `summary` messages are skipped
+#[derive(Deserialize)]
+struct RgMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct RgMatch {
+    path: RgData,
+    lines: RgData,
+    line_number: Option<usize>,
+    /// Byte offset of the start of `lines` within the file
+    absolute_offset: Option<usize>,
+    #[serde(default)]
+    submatches: Vec<RgSubmatch>,
+}
+
+/// A match found by ripgrep, as byte offsets within the `lines` of its [`RgMatch`]
+#[derive(Deserialize)]
+struct RgSubmatch {
+    start: usize,
+    end: usize,
+}
+
+/// ripgrep gives data as `{"text": ...}` when it is valid UTF-8, and as `{"bytes": ...}` otherwise
+#[derive(Deserialize)]
+struct RgData {
+    text: Option<String>,
+}
+
+fn rg_json_matches(content: &str) -> impl Iterator<Item = RgMatch> + '_ {
+    content.lines().filter_map(parse_rg_json_message)
+}
+
+fn parse_rg_json_message(line: &str) -> Option<RgMatch> {
+    let message: RgMessage = serde_json::from_str(line).ok()?;
+    if message.kind != "match" {
+        return None;
+    }
+    serde_json::from_value(message.data).ok()
+}
+
+impl RgMatch {
+    /// The lines of the match, which span several lines for multiline matches (`rg -U`)
+    fn framed_lines(&self, path: &Path) -> Vec<FramedLine> {
+        let (Some(first_line_number), Some(lines_text)) = (self.line_number, &self.lines.text)
+        else {
+            return vec![];
+        };
+        Cursor::new(lines_text.as_bytes())
+            .lines_with_endings()
+            .zip(first_line_number..)
+            .filter_map(|(line_result, line_number)| {
+                let (line_bytes, line_ending) = line_result.ok()?;
+                Some(FramedLine {
+                    path: path.to_path_buf(),
+                    line_number,
+                    content: String::from_utf8(line_bytes).ok()?,
+                    line_ending,
+                })
+            })
+            .collect()
+    }
+
+    /// A result for each submatch, at its byte range within the file
+    fn submatch_results(&self, path: &Path, line_filter: &LineFilter) -> Vec<SearchResult> {
+        let (Some(first_line_number), Some(absolute_offset), Some(lines_text)) =
+            (self.line_number, self.absolute_offset, &self.lines.text)
+        else {
+            return vec![];
+        };
+        let line_index = LineIndex::new(lines_text);
+        self.submatches
+            .iter()
+            .filter(|submatch| {
+                submatch.start < submatch.end
+                    && lines_text.get(submatch.start..submatch.end).is_some()
+            })
+            .filter_map(|submatch| {
+                let mut result = create_search_result_from_bytes(
+                    submatch.start,
+                    submatch.end,
+                    Some(path),
+                    &line_index,
+                );
+                let MatchContent::ByteRange {
+                    lines,
+                    byte_start,
+                    byte_end,
+                    ..
+                } = &mut result.content
+                else {
+                    unreachable!("Results created from bytes are always byte ranges")
+                };
+                if !line_filter.allows(&lines[0].1.content) {
+                    return None;
+                }
+                for (line_number, _) in lines {
+                    *line_number += first_line_number - 1;
+                }
+                *byte_start += absolute_offset;
+                *byte_end += absolute_offset;
+                Some(result)
+            })
+            .collect()
+    }
+}
+
+fn parse_header_path(line: &str) -> Option<&str> {
+    line.strip_prefix("==> ")?.strip_suffix(" <==")
+}
+
+fn parse_header_lines(lines: impl Iterator<Item = (String, LineEnding)>) -> Vec<FramedLine> {
+    let mut framed_lines = vec![];
+    let mut current_path: Option<PathBuf> = None;
+    let mut line_number = 0;
+
+    let mut lines = lines.peekable();
+    while let Some((line, line_ending)) = lines.next() {
+        if let Some(path) = parse_header_path(&line) {
+            current_path = Some(PathBuf::from(path));
+            line_number = 0;
+            continue;
+        }
+        // `head` and `tail` separate files with a blank line before each header
+        let is_separator = line.is_empty()
+            && lines
+                .peek()
+                .is_some_and(|(next, _)| parse_header_path(next).is_some());
+        if is_separator {
+            continue;
+        }
+
+        line_number += 1;
+        if let Some(path) = &current_path {
+            framed_lines.push(FramedLine {
+                path: path.clone(),
+                line_number,
+                content: line,
+                line_ending,
+            });
+        }
+    }
+    framed_lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framed(path: &str, line_number: usize, content: &str) -> FramedLine {
+        FramedLine {
+            path: PathBuf::from(path),
+            line_number,
+            content: content.to_owned(),
+            line_ending: LineEnding::Lf,
+        }
+    }
+
+    #[test]
+    fn test_plain_has_no_framed_lines() {
+        assert_eq!(StdinFormat::Plain.parse_lines("foo\nbar\n"), vec![]);
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        let content = "==> src/a.rs <==\nfoo\nbar\n\n==> src/b.rs <==\nbaz\n\nqux\n";
+        assert_eq!(
+            StdinFormat::Headers.parse_lines(content),
+            vec![
+                framed("src/a.rs", 1, "foo"),
+                framed("src/a.rs", 2, "bar"),
+                framed("src/b.rs", 1, "baz"),
+                framed("src/b.rs", 2, ""),
+                framed("src/b.rs", 3, "qux"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_headers_skips_lines_before_first_header() {
+        let content = "preamble\n==> a.txt <==\nfoo\n";
+        assert_eq!(
+            StdinFormat::Headers.parse_lines(content),
+            vec![framed("a.txt", 1, "foo")]
+        );
+    }
+
+    #[test]
+    fn test_parse_grep() {
+        let content = "src/a.rs:3:let foo = 1;\nsrc/b.rs:10:a:b\n--\nnot a match\n";
+        assert_eq!(
+            StdinFormat::Grep.parse_lines(content),
+            vec![
+                framed("src/a.rs", 3, "let foo = 1;"),
+                framed("src/b.rs", 10, "a:b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rg_json() {
+        let content = indoc::indoc! {r#"
+            {"type":"begin","data":{"path":{"text":"src/a.rs"}}}
+            {"type":"context","data":{"path":{"text":"src/a.rs"},"lines":{"text":"fn main() {\n"},"line_number":2,"absolute_offset":10,"submatches":[]}}
+            {"type":"match","data":{"path":{"text":"src/a.rs"},"lines":{"text":"let foo = 1;\n"},"line_number":3,"absolute_offset":22,"submatches":[{"match":{"text":"foo"},"start":4,"end":7}]}}
+            {"type":"end","data":{"path":{"text":"src/a.rs"},"binary_offset":null,"stats":{}}}
+            {"type":"match","data":{"path":{"text":"src/b.rs"},"lines":{"text":"foo\r\nbar foo\n"},"line_number":10,"absolute_offset":0,"submatches":[]}}
+            {"type":"match","data":{"path":{"bytes":"3/4="},"lines":{"text":"foo\n"},"line_number":1,"absolute_offset":0,"submatches":[]}}
+            not json
+            {"type":"summary","data":{"elapsed_total":{"secs":0,"nanos":1}}}
+        "#};
+        let lines = StdinFormat::RgJson.parse_lines(content);
+        assert_eq!(
+            lines,
+            vec![
+                framed("src/a.rs", 3, "let foo = 1;"),
+                FramedLine {
+                    line_ending: LineEnding::CrLf,
+                    ..framed("src/b.rs", 10, "foo")
+                },
+                framed("src/b.rs", 11, "bar foo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_framed() {
+        let content = "==> a.txt <==\nfoo\nbar\n\n==> b.txt <==\nbaz foo\n";
+        let results = search_framed(
+            content,
+            StdinFormat::Headers,
+            &SearchType::Fixed("foo".to_owned()),
+            &LineFilter::default(),
+        );
+        let locations = results
+            .results
+            .iter()
+            .map(|r| (r.path.clone().unwrap(), r.start_line_number()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            vec![(PathBuf::from("a.txt"), 1), (PathBuf::from("b.txt"), 1)]
+        );
+    }
+
+    #[test]
+    fn test_search_rg_json_uses_submatches_when_search_is_empty() {
+        let content = indoc::indoc! {r#"
+            {"type":"match","data":{"path":{"text":"a.rs"},"lines":{"text":"let foo = foo;\n"},"line_number":3,"absolute_offset":22,"submatches":[{"match":{"text":"foo"},"start":4,"end":7},{"match":{"text":"foo"},"start":10,"end":13}]}}
+            {"type":"match","data":{"path":{"text":"b.rs"},"lines":{"text":"ab\r\ncd\n"},"line_number":5,"absolute_offset":8,"submatches":[{"match":{"text":"b\r\nc"},"start":1,"end":5}]}}
+        "#};
+        let framed_search = search_framed(
+            content,
+            StdinFormat::RgJson,
+            &SearchType::Fixed(String::new()),
+            &LineFilter::default(),
+        );
+        let matches = framed_search
+            .results
+            .iter()
+            .map(|result| {
+                let MatchContent::ByteRange {
+                    lines,
+                    byte_start,
+                    byte_end,
+                    content,
+                    ..
+                } = &result.content
+                else {
+                    panic!("Expected ByteRange, found {:?}", result.content);
+                };
+                let line_numbers = lines.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+                (
+                    result.path.clone().unwrap(),
+                    line_numbers,
+                    *byte_start..*byte_end,
+                    content.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            vec![
+                (PathBuf::from("a.rs"), vec![3], 26..29, "foo"),
+                (PathBuf::from("a.rs"), vec![3], 32..35, "foo"),
+                (PathBuf::from("b.rs"), vec![5, 6], 9..13, "b\r\nc"),
+            ]
+        );
+        assert_eq!(framed_search.num_non_utf8_paths, 0);
+    }
+
+    #[test]
+    fn test_search_rg_json_counts_non_utf8_paths() {
+        let content = indoc::indoc! {r#"
+            {"type":"match","data":{"path":{"text":"a.rs"},"lines":{"text":"foo\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"foo"},"start":0,"end":3}]}}
+            {"type":"match","data":{"path":{"bytes":"3/4="},"lines":{"text":"foo\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"foo"},"start":0,"end":3}]}}
+            {"type":"match","data":{"path":{"bytes":"3/4="},"lines":{"text":"foo bar\n"},"line_number":2,"absolute_offset":4,"submatches":[{"match":{"text":"foo"},"start":0,"end":3}]}}
+        "#};
+        for search in [
+            SearchType::Fixed(String::new()),
+            SearchType::Fixed("foo".to_owned()),
+        ] {
+            let framed_search = search_framed(
+                content,
+                StdinFormat::RgJson,
+                &search,
+                &LineFilter::default(),
+            );
+            assert_eq!(framed_search.results.len(), 1);
+            assert_eq!(framed_search.num_non_utf8_paths, 2);
+        }
+        assert_eq!(
+            non_utf8_paths_message(2),
+            "Skipped 2 matches in files with paths that aren't valid UTF-8"
+        );
+    }
+
+    #[test]
+    fn test_parse_preserves_line_endings() {
+        let lines = StdinFormat::Grep.parse_lines("a.txt:1:foo\r\na.txt:2:bar");
+        assert_eq!(lines[0].line_ending, LineEnding::CrLf);
+        assert_eq!(lines[1].line_ending, LineEnding::None);
+    }
+}
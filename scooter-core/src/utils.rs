@@ -4,18 +4,18 @@ use std::{
     io::{self, BufReader},
     num::NonZeroUsize,
     ops::{Add, Div, Mul, Rem},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Error, bail};
-use ignore::overrides::OverrideBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
 use two_face::re_exports::syntect::{
     easy::HighlightLines,
     highlighting::{Style, Theme},
     parsing::SyntaxSet,
 };
 
-use crate::line_reader::{BufReadExt, LinesSplitEndings};
+use crate::line_reader::{self, BufReadExt, LinesSplitEndings};
 
 pub fn relative_path(base: &Path, target: &Path) -> String {
     match target.strip_prefix(base) {
@@ -41,6 +41,62 @@ pub fn relative_path(base: &Path, target: &Path) -> String {
         }
     }
 }
+
+/// Like [`relative_path`], but relative to whichever of `roots` contains `target`. When there are
+/// several roots, the path is prefixed with the shortest trailing part of the root it was found in
+/// that no other root ends with, so that results from different roots can be told apart even when
+/// the roots share a name
+pub fn relative_path_in_roots(roots: &[PathBuf], target: &Path) -> String {
+    if let [root] = roots {
+        return relative_path(root, target);
+    }
+    match roots.iter().find(|root| target.starts_with(root)) {
+        Some(root) => distinguishing_suffix(roots, root)
+            .join(relative_path(root, target))
+            .to_string_lossy()
+            .to_string(),
+        None => target.to_string_lossy().to_string(),
+    }
+}
+
+/// The shortest trailing part of `root` that no other of `roots` ends with, such as `a/src` for
+/// `/x/a/src` when `/x/b/src` is also a root, or the whole of `root` if there is none
+fn distinguishing_suffix(roots: &[PathBuf], root: &Path) -> PathBuf {
+    let components: Vec<_> = root.components().collect();
+    (1..=components.len())
+        .map(|len| {
+            components[components.len() - len..]
+                .iter()
+                .collect::<PathBuf>()
+        })
+        .find(|suffix| {
+            roots
+                .iter()
+                .all(|other| other == root || !other.ends_with(suffix))
+        })
+        .unwrap_or_else(|| root.to_path_buf())
+}
+
+/// Removes any directory that is the same as, or nested within, another of `directories`, so that
+/// no file is walked more than once. Directories are compared by their canonical paths (or as given,
+/// if they can't be canonicalized), and the first of several identical directories is kept
+pub fn remove_nested_dirs(directories: &[PathBuf]) -> Vec<PathBuf> {
+    let canonical: Vec<PathBuf> = directories
+        .iter()
+        .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.clone()))
+        .collect();
+    directories
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            !canonical.iter().enumerate().any(|(j, other)| {
+                j != i && canonical[i].starts_with(other) && (j < i || canonical[i] != *other)
+            })
+        })
+        .map(|(_, dir)| dir.clone())
+        .collect()
+}
+
 pub fn group_by<I, T, F>(iter: I, predicate: F) -> Vec<Vec<T>>
 where
     I: IntoIterator<Item = T>,
@@ -101,7 +157,8 @@ pub fn read_lines_range(
     end: usize,
 ) -> io::Result<impl Iterator<Item = (usize, String)>> {
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
+    line_reader::skip_utf8_bom(&mut reader)?;
 
     Ok(surrounding_line_window(reader, start, end))
 }
@@ -220,6 +277,36 @@ pub fn strip_control_chars(text: &str) -> Cow<'_, str> {
     Cow::Owned(result)
 }
 
+/// Replaces control characters in text with a visible escaped form for display purposes.
+/// Returns a `Cow` to avoid allocation when no replacements are needed.
+/// - Tabs are replaced with two spaces, as with [`strip_control_chars`]
+/// - ASCII control characters are shown in caret notation, e.g. `^[` for escape and `^M` for a carriage return
+/// - Other control characters are shown as their code point, e.g. `\u{85}`
+pub fn escape_control_chars(text: &str) -> Cow<'_, str> {
+    use std::fmt::Write;
+
+    if !text.chars().any(char::is_control) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => result.push_str("  "),
+            '\u{7f}' => result.push_str("^?"),
+            c if c.is_ascii_control() => {
+                result.push('^');
+                result.push(char::from_u32(u32::from(c) + 0x40).expect("Invalid caret notation"));
+            }
+            c if c.is_control() => {
+                let _ = write!(result, "\\u{{{:x}}}", u32::from(c));
+            }
+            c => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
 pub fn ceil_div<T>(a: T, b: T) -> T
 where
     T: Add<Output = T>
@@ -266,7 +353,8 @@ impl<'a> HighlightedLinesIterator<'a> {
         }
 
         let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        let mut reader = BufReader::new(file);
+        line_reader::skip_utf8_bom(&mut reader)?;
         let mut lines = reader.lines_with_endings();
 
         // Skip lines if we're not doing full highlighting
@@ -387,6 +475,13 @@ pub fn last_n_chars(s: &str, n: usize) -> &str {
     &s[idx..]
 }
 
+pub fn first_n_chars(s: &str, n: usize) -> &str {
+    match s.char_indices().nth(n) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Either<T, S> {
     Left(T),
@@ -397,6 +492,19 @@ pub fn is_regex_error(e: &Error) -> bool {
     e.downcast_ref::<regex::Error>().is_some() || e.downcast_ref::<fancy_regex::Error>().is_some()
 }
 
+/// Builds `globs`, which are comma-separated with the same semantics as the include files field,
+/// relative to each of `directories`
+pub fn build_overrides(directories: &[PathBuf], globs: &str) -> anyhow::Result<Vec<Override>> {
+    directories
+        .iter()
+        .map(|directory| {
+            let mut overrides = OverrideBuilder::new(directory);
+            add_overrides(&mut overrides, globs, "")?;
+            Ok(overrides.build()?)
+        })
+        .collect()
+}
+
 pub fn add_overrides(
     overrides: &mut OverrideBuilder,
     files: &str,
@@ -529,6 +637,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_relative_path_in_roots() {
+        let single = [PathBuf::from("/foo/bar")];
+        assert_eq!(
+            relative_path_in_roots(&single, Path::new("/foo/bar/baz.rs")),
+            "baz.rs"
+        );
+
+        let multiple = [PathBuf::from("/foo/bar"), PathBuf::from("/foo/qux")];
+        assert_eq!(
+            relative_path_in_roots(&multiple, Path::new("/foo/bar/baz.rs")),
+            "bar/baz.rs"
+        );
+        assert_eq!(
+            relative_path_in_roots(&multiple, Path::new("/foo/qux/src/lib.rs")),
+            "qux/src/lib.rs"
+        );
+        assert_eq!(
+            relative_path_in_roots(&multiple, Path::new("/other/file.rs")),
+            "/other/file.rs"
+        );
+    }
+
+    #[test]
+    fn test_relative_path_in_roots_with_same_name() {
+        let roots = [
+            PathBuf::from("/foo/a/src"),
+            PathBuf::from("/foo/b/src"),
+            PathBuf::from("/foo/lib"),
+        ];
+        assert_eq!(
+            relative_path_in_roots(&roots, Path::new("/foo/a/src/main.rs")),
+            "a/src/main.rs"
+        );
+        assert_eq!(
+            relative_path_in_roots(&roots, Path::new("/foo/b/src/main.rs")),
+            "b/src/main.rs"
+        );
+        assert_eq!(
+            relative_path_in_roots(&roots, Path::new("/foo/lib/mod.rs")),
+            "lib/mod.rs"
+        );
+    }
+
+    #[test]
+    fn test_remove_nested_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let sub = root.join("sub");
+        let sibling = root.join("sibling");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::create_dir(&sibling).unwrap();
+
+        assert_eq!(
+            remove_nested_dirs(&[root.clone(), sub.clone()]),
+            vec![root.clone()]
+        );
+        assert_eq!(
+            remove_nested_dirs(&[sub.clone(), root.clone()]),
+            vec![root.clone()]
+        );
+        assert_eq!(
+            remove_nested_dirs(&[sub.clone(), root.join("sub/../sub"), sibling.clone()]),
+            vec![sub.clone(), sibling.clone()]
+        );
+        assert_eq!(
+            remove_nested_dirs(&[sub.clone(), sibling.clone()]),
+            vec![sub, sibling]
+        );
+    }
+
     #[test]
     fn test_vec() {
         let numbers = vec![1, 2, 2, 3, 4, 4, 4, 5];
@@ -862,6 +1041,16 @@ mod tests {
         assert_eq!(strip_control_chars("\u{1}\u{2}\u{3}\u{4}"), "����");
     }
 
+    #[test]
+    fn test_escape_control_chars() {
+        assert_eq!(escape_control_chars("hello world"), "hello world");
+        assert_eq!(escape_control_chars("\u{1b}[0m"), "^[[0m");
+        assert_eq!(escape_control_chars("a\u{0}b\u{7}c\u{7f}"), "a^@b^Gc^?");
+        assert_eq!(escape_control_chars("line\r"), "line^M");
+        assert_eq!(escape_control_chars("a\tb"), "a  b");
+        assert_eq!(escape_control_chars("next\u{85}line"), "next\\u{85}line");
+    }
+
     #[test]
     fn test_sanitize_carriage_returns() {
         // Carriage returns should be stripped entirely
@@ -1338,6 +1527,16 @@ mod tests {
         assert_eq!(last_n_chars(s, 9), "👋 World 🌍");
     }
 
+    #[test]
+    fn test_first_n_chars() {
+        assert_eq!(first_n_chars("", 5), "");
+        assert_eq!(first_n_chars("hello", 0), "");
+        assert_eq!(first_n_chars("hello", 3), "hel");
+        assert_eq!(first_n_chars("hello", 10), "hello");
+        assert_eq!(first_n_chars("héllö wörld", 5), "héllö");
+        assert_eq!(first_n_chars("Hello 👋 World 🌍", 7), "Hello 👋");
+    }
+
     #[allow(clippy::similar_names)]
     #[test]
     fn test_typescript_syntax_available() {
@@ -3,11 +3,18 @@ use crossterm::style::Stylize;
 use fancy_regex::Regex as FancyRegex;
 use ignore::overrides::OverrideBuilder;
 use regex::Regex;
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use crate::{
-    replace::interpret_escapes,
-    search::{ParsedDirConfig, ParsedSearchConfig, SearchType},
+    replace::{interpret_escapes, wrap_template_replacement},
+    search::{
+        BinaryDetection, LineFilter, ParsedDirConfig, ParsedSearchConfig, SearchRoot, SearchType,
+    },
     utils,
 };
 
@@ -22,18 +29,45 @@ pub struct SearchConfig<'a> {
     pub match_case: bool,
     pub multiline: bool,
     pub interpret_escape_sequences: bool,
+    /// Whether `replacement_text` is a template such as `/* {} */`, in which `{}` is replaced with
+    /// the whole match
+    pub wrap_matches: bool,
+    /// Whether to remove lines that are left empty by the replacement, rather than leaving a blank
+    /// line. Only applies when searching line-by-line
+    pub delete_empty_lines: bool,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct DirConfig<'a> {
+    /// Comma-separated globs that files must match, with `.gitignore` semantics: a pattern without a
+    /// `/` matches the file name at any depth, while a pattern containing a `/` is anchored to the
+    /// searched directory
     pub include_globs: Option<&'a str>,
+    /// Comma-separated globs that files must not match, with the same semantics as `include_globs`
     pub exclude_globs: Option<&'a str>,
-    pub directory: PathBuf,
+    /// The directories to search from. Glob patterns are matched relative to each of these
+    pub directories: Vec<PathBuf>,
     pub include_hidden: bool,
     pub include_git_folders: bool,
+    pub include_binary: bool,
+    pub binary_detection: BinaryDetection,
+    /// How many levels of directories to descend into, where 1 searches only the files directly
+    /// within each directory. `None` means no limit
+    pub max_depth: Option<usize>,
+    /// If set, only files modified at or after this time are searched
+    pub modified_since: Option<SystemTime>,
+    /// If set, only these files are searched, such as the files tracked by git as listed by
+    /// [`crate::git::tracked_files_in`]. Computed once by the caller rather than on each validation, as
+    /// listing them means running git
+    pub tracked_files: Option<Arc<HashSet<PathBuf>>>,
+    /// If set, searching a file with an advanced regex is abandoned once it has taken longer than
+    /// this, and the file is skipped
+    pub regex_timeout: Option<Duration>,
 }
 pub trait ValidationErrorHandler {
     fn handle_search_text_error(&mut self, error: &str, detail: &str);
+    fn handle_replace_text_error(&mut self, error: &str, detail: &str);
     fn handle_include_files_error(&mut self, error: &str, detail: &str);
     fn handle_exclude_files_error(&mut self, error: &str, detail: &str);
 }
@@ -77,6 +111,10 @@ impl ValidationErrorHandler for SimpleErrorHandler {
         self.push_error("Failed to parse search text", detail);
     }
 
+    fn handle_replace_text_error(&mut self, _error: &str, detail: &str) {
+        self.push_error("Failed to parse replacement text", detail);
+    }
+
     fn handle_include_files_error(&mut self, _error: &str, detail: &str) {
         self.push_error("Failed to parse include globs", detail);
     }
@@ -115,7 +153,7 @@ pub fn validate_search_configuration<H: ValidationErrorHandler>(
 
     let parsed_dir_config = match dir_config {
         Some(dir_config) => {
-            let overrides = parse_overrides(dir_config, error_handler)?;
+            let overrides = validate_dir_configuration(dir_config, error_handler)?;
             overrides.map(Some)
         }
         None => ValidationResult::Success(None),
@@ -126,15 +164,26 @@ pub fn validate_search_configuration<H: ValidationErrorHandler>(
         ValidationResult::Success(parsed_dir_config),
     ) = (search_pattern, parsed_dir_config)
     {
-        let replace = if search_config.interpret_escape_sequences {
+        let mut replace = if search_config.interpret_escape_sequences {
             interpret_escapes(search_config.replacement_text)
         } else {
             search_config.replacement_text.to_owned()
         };
+        if search_config.wrap_matches {
+            replace = wrap_template_replacement(&replace, &search_pattern);
+        }
+        if !search_config.fixed_strings
+            && let Err(detail) = validate_group_references(&search_pattern, &replace)
+        {
+            error_handler.handle_replace_text_error("Invalid capture group reference", &detail);
+            return Ok(ValidationResult::ValidationErrors);
+        }
         let parsed_search_config = ParsedSearchConfig {
             search: search_pattern,
             replace,
             multiline: search_config.multiline,
+            line_filter: LineFilter::default(),
+            delete_empty_lines: search_config.delete_empty_lines,
         };
         Ok(ValidationResult::Success((
             parsed_search_config,
@@ -146,6 +195,10 @@ pub fn validate_search_configuration<H: ValidationErrorHandler>(
 }
 
 pub fn parse_search_text(config: &SearchConfig<'_>) -> anyhow::Result<SearchType> {
+    // Kept empty regardless of the other options, so that `SearchType::is_empty` holds
+    if config.search_text.is_empty() {
+        return Ok(SearchType::Fixed(String::new()));
+    }
     if !config.match_whole_word && config.match_case {
         // No conversion required
         let search = if config.fixed_strings {
@@ -180,6 +233,60 @@ pub fn parse_search_text(config: &SearchConfig<'_>) -> anyhow::Result<SearchType
     }
 }
 
+/// Returns the number of capture groups in the search pattern, excluding the implicit group for
+/// the whole match
+fn capture_group_count(search: &SearchType) -> usize {
+    match search {
+        SearchType::Fixed(_) => 0,
+        SearchType::Pattern(regex) => regex.captures_len() - 1,
+        SearchType::PatternAdvanced(regex) => regex.captures_len() - 1,
+    }
+}
+
+/// Returns the numbered capture groups referenced by a replacement template, i.e. `$1` or `${1}`.
+/// Follows the `regex` crate's template syntax, so `$$` is a literal `$` and references such as
+/// `$1a` refer to a group named `1a` rather than group 1.
+fn numbered_group_references(replacement: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut rest = replacement;
+    std::iter::from_fn(move || {
+        loop {
+            let idx = rest.find('$')?;
+            rest = &rest[idx + 1..];
+            let name = if let Some(escaped) = rest.strip_prefix('$') {
+                rest = escaped;
+                continue;
+            } else if let Some(braced) = rest.strip_prefix('{') {
+                let Some(end) = braced.find('}') else {
+                    continue;
+                };
+                rest = &braced[end + 1..];
+                &braced[..end]
+            } else {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let name = &rest[..end];
+                rest = &rest[end..];
+                name
+            };
+            if let Ok(group) = name.parse::<usize>() {
+                return Some(group);
+            }
+        }
+    })
+}
+
+fn validate_group_references(search: &SearchType, replacement: &str) -> Result<(), String> {
+    let num_groups = capture_group_count(search);
+    match numbered_group_references(replacement).max() {
+        Some(group) if group > num_groups => Err(format!(
+            "replacement references group {group} but pattern has {num_groups} group{}",
+            if num_groups == 1 { "" } else { "s" }
+        )),
+        _ => Ok(()),
+    }
+}
+
 fn parse_search_text_with_error_handler<H: ValidationErrorHandler>(
     config: &SearchConfig<'_>,
     error_handler: &mut H,
@@ -197,38 +304,57 @@ fn parse_search_text_with_error_handler<H: ValidationErrorHandler>(
     }
 }
 
-fn parse_overrides<H: ValidationErrorHandler>(
+/// Validates the include and exclude globs of `dir_config`, without needing a search to be
+/// configured. Used to list the files that would be searched before searching them
+pub fn validate_dir_configuration<H: ValidationErrorHandler>(
     dir_config: DirConfig<'_>,
     error_handler: &mut H,
 ) -> anyhow::Result<ValidationResult<ParsedDirConfig>> {
-    let mut overrides = OverrideBuilder::new(&dir_config.directory);
-    let mut success = true;
+    // Roots nested within another are dropped, since walking the outer root already covers them
+    let directories = utils::remove_nested_dirs(&dir_config.directories);
+    let mut roots = Vec::with_capacity(directories.len());
+    for directory in directories {
+        let mut overrides = OverrideBuilder::new(&directory);
+        let mut success = true;
+
+        if let Some(include_globs) = dir_config.include_globs
+            && let Err(e) = utils::add_overrides(&mut overrides, include_globs, "")
+        {
+            error_handler.handle_include_files_error("Couldn't parse glob pattern", &e.to_string());
+            success = false;
+        }
+        if let Some(exclude_globs) = dir_config.exclude_globs
+            && let Err(e) = utils::add_overrides(&mut overrides, exclude_globs, "!")
+        {
+            error_handler.handle_exclude_files_error("Couldn't parse glob pattern", &e.to_string());
+            success = false;
+        }
+        if !dir_config.include_git_folders {
+            overrides
+                .add("!.git")
+                .expect("Failed to add `!.git` exclusion");
+        }
+        // The same globs are used for every root, so errors only need reporting once
+        if !success {
+            return Ok(ValidationResult::ValidationErrors);
+        }
 
-    if let Some(include_globs) = dir_config.include_globs
-        && let Err(e) = utils::add_overrides(&mut overrides, include_globs, "")
-    {
-        error_handler.handle_include_files_error("Couldn't parse glob pattern", &e.to_string());
-        success = false;
-    }
-    if let Some(exclude_globs) = dir_config.exclude_globs
-        && let Err(e) = utils::add_overrides(&mut overrides, exclude_globs, "!")
-    {
-        error_handler.handle_exclude_files_error("Couldn't parse glob pattern", &e.to_string());
-        success = false;
-    }
-    if !dir_config.include_git_folders {
-        overrides
-            .add("!.git")
-            .expect("Failed to add `!.git` exclusion");
-    }
-    if !success {
-        return Ok(ValidationResult::ValidationErrors);
+        roots.push(SearchRoot {
+            overrides: overrides.build()?,
+            dir: directory,
+        });
     }
 
     Ok(ValidationResult::Success(ParsedDirConfig {
-        overrides: overrides.build()?,
-        root_dir: dir_config.directory,
+        roots,
         include_hidden: dir_config.include_hidden,
+        include_binary: dir_config.include_binary,
+        binary_detection: dir_config.binary_detection,
+        max_depth: dir_config.max_depth,
+        modified_since: dir_config.modified_since,
+        tracked_files: dir_config.tracked_files,
+        approved_files: None,
+        regex_timeout: dir_config.regex_timeout,
     }))
 }
 
@@ -246,6 +372,8 @@ mod tests {
             match_case: false,
             multiline: false,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         }
     }
 
@@ -284,9 +412,15 @@ mod tests {
         let dir_config = DirConfig {
             include_globs: Some("[invalid"),
             exclude_globs: None,
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
         let mut error_handler = SimpleErrorHandler::new();
 
@@ -302,6 +436,134 @@ mod tests {
         assert!(error_handler.errors[0].contains("Failed to parse include globs"));
     }
 
+    #[test]
+    fn test_multiple_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let directories = vec![temp_dir.path().join("a"), temp_dir.path().join("b")];
+        let dir_config = |include_globs| DirConfig {
+            include_globs,
+            exclude_globs: None,
+            directories: directories.clone(),
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+
+        let mut error_handler = SimpleErrorHandler::new();
+        let result = validate_search_configuration(
+            create_search_test_config(),
+            Some(dir_config(Some("*.rs"))),
+            &mut error_handler,
+        )
+        .unwrap();
+        let ValidationResult::Success((_, Some(parsed))) = result else {
+            panic!("Expected successful validation, found {result:?}");
+        };
+        let roots = parsed
+            .roots
+            .iter()
+            .map(|root| root.dir.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(roots, directories);
+
+        let mut error_handler = SimpleErrorHandler::new();
+        let result = validate_search_configuration(
+            create_search_test_config(),
+            Some(dir_config(Some("[invalid"))),
+            &mut error_handler,
+        )
+        .unwrap();
+        assert!(matches!(result, ValidationResult::ValidationErrors));
+        assert_eq!(error_handler.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_glob_semantics() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let files = [
+            "file.rs",
+            "foo.rs",
+            "README.md",
+            "src/file.rs",
+            "src/main.rs",
+            "src/nested/foo.rs",
+            "lib/src/lib.rs",
+        ];
+        for file in files {
+            let path = temp_dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let matching_files = |include_globs, exclude_globs| {
+            let dir_config = DirConfig {
+                include_globs,
+                exclude_globs,
+                directories: vec![temp_dir.path().to_path_buf()],
+                include_hidden: false,
+                include_git_folders: false,
+                include_binary: false,
+                binary_detection: BinaryDetection::default(),
+                max_depth: None,
+                modified_since: None,
+                tracked_files: None,
+                regex_timeout: None,
+            };
+            let mut error_handler = SimpleErrorHandler::new();
+            let result = validate_dir_configuration(dir_config, &mut error_handler).unwrap();
+            let ValidationResult::Success(parsed) = result else {
+                panic!("Expected successful validation, found {result:?}");
+            };
+            parsed
+                .list_files()
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(temp_dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Patterns without a slash match the file name at any depth
+        assert_eq!(
+            matching_files(Some("file.rs"), None),
+            vec!["file.rs", "src/file.rs"]
+        );
+        assert_eq!(
+            matching_files(Some("*.rs"), None),
+            vec![
+                "file.rs",
+                "foo.rs",
+                "lib/src/lib.rs",
+                "src/file.rs",
+                "src/main.rs",
+                "src/nested/foo.rs"
+            ]
+        );
+        // Patterns containing a slash are anchored to the searched directory, and `*` doesn't match `/`
+        assert_eq!(
+            matching_files(Some("src/*.rs"), None),
+            vec!["src/file.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            matching_files(Some("**/foo.rs"), None),
+            vec!["foo.rs", "src/nested/foo.rs"]
+        );
+        // Exclude globs follow the same rules
+        assert_eq!(matching_files(None, Some("*.rs")), vec!["README.md"]);
+        assert_eq!(
+            matching_files(Some("*.rs"), Some("src/*.rs")),
+            vec!["file.rs", "foo.rs", "lib/src/lib.rs", "src/nested/foo.rs"]
+        );
+    }
+
     #[test]
     fn test_fixed_strings_mode() {
         let mut config = create_search_test_config();
@@ -316,6 +578,86 @@ mod tests {
         assert!(error_handler.errors_str().is_none());
     }
 
+    #[test]
+    fn test_replacement_references_missing_group() {
+        let mut config = create_search_test_config();
+        config.search_text = r"(\w+)-(\d+)";
+        config.replacement_text = "$2 $3";
+        let mut error_handler = SimpleErrorHandler::new();
+
+        let result = validate_search_configuration(config, None, &mut error_handler);
+
+        assert!(matches!(
+            result.unwrap(),
+            ValidationResult::ValidationErrors
+        ));
+        assert!(error_handler.errors[0].contains("Failed to parse replacement text"));
+        assert!(
+            error_handler.errors[0]
+                .contains("replacement references group 3 but pattern has 2 groups")
+        );
+    }
+
+    #[test]
+    fn test_replacement_group_references_within_bounds() {
+        for replacement in ["$1", "${1}x", "$0", "$$3", "$1a", "${name}", "$", "${3"] {
+            let mut config = create_search_test_config();
+            config.search_text = r"(\w+)";
+            config.replacement_text = replacement;
+            let mut error_handler = SimpleErrorHandler::new();
+
+            let result = validate_search_configuration(config, None, &mut error_handler);
+
+            assert!(
+                matches!(result.unwrap(), ValidationResult::Success(_)),
+                "Expected {replacement:?} to be valid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_replacement_group_references_ignored_for_fixed_strings() {
+        let mut config = create_search_test_config();
+        config.fixed_strings = true;
+        config.replacement_text = "$1";
+        let mut error_handler = SimpleErrorHandler::new();
+
+        let result = validate_search_configuration(config, None, &mut error_handler);
+
+        assert!(matches!(result.unwrap(), ValidationResult::Success(_)));
+    }
+
+    #[test]
+    fn test_wrap_matches() {
+        for (fixed_strings, match_case, expected) in [
+            (true, true, "/* test */ $2"),
+            (true, false, "/* ${0} */ $$2"),
+            (false, true, "/* ${0} */ $$2"),
+        ] {
+            let mut config = create_search_test_config();
+            config.replacement_text = "/* {} */ $2";
+            config.fixed_strings = fixed_strings;
+            config.match_case = match_case;
+            config.wrap_matches = true;
+            let mut error_handler = SimpleErrorHandler::new();
+
+            let result = validate_search_configuration(config, None, &mut error_handler);
+
+            let ValidationResult::Success((parsed, _)) = result.unwrap() else {
+                panic!("Expected valid configuration: {:?}", error_handler.errors);
+            };
+            assert_eq!(parsed.replace, expected);
+        }
+    }
+
+    #[test]
+    fn test_numbered_group_references() {
+        assert_eq!(
+            numbered_group_references("${2}a $1 $$4 $10_ $named ${12}").collect::<Vec<_>>(),
+            vec![2, 1, 12]
+        );
+    }
+
     mod parse_search_text_tests {
         use super::*;
 
@@ -348,6 +690,8 @@ mod tests {
                 multiline: false,
                 advanced_regex: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
 
@@ -368,6 +712,8 @@ mod tests {
                 multiline: false,
                 advanced_regex: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
 
@@ -385,6 +731,8 @@ mod tests {
                 multiline: false,
                 advanced_regex: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
 
@@ -405,6 +753,8 @@ mod tests {
                 multiline: false,
                 advanced_regex: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
 
@@ -422,6 +772,8 @@ mod tests {
                 multiline: false,
                 advanced_regex: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
 
@@ -442,6 +794,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
             test_helpers::assert_pattern_contains(&converted, &[r"\(foo", "(?i)"]);
@@ -458,6 +812,8 @@ mod tests {
                 advanced_regex: false,
                 multiline: false,
                 interpret_escape_sequences: false,
+                wrap_matches: false,
+                delete_empty_lines: false,
             };
             let converted = parse_search_text(&search_config).unwrap();
             test_helpers::assert_pattern_contains(
@@ -4,14 +4,15 @@ use scooter_core::{
         AppEvent, BackgroundProcessingEvent, Event, EventHandlingResult, InputSource, InternalEvent,
     },
     errors::AppError,
-    fields::{FieldValue, SearchFieldValues, SearchFields},
+    fields::{Field, FieldName, FieldValue, SearchFieldValues, SearchFields},
     keyboard::KeyEvent,
     replace::{PerformingReplacementState, ReplaceState},
 };
 use scooter_core::{
     line_reader::LineEnding,
     replace::ReplaceResult,
-    search::{SearchResult, SearchResultWithReplacement},
+    search::{LineFilter, LineRanges, SearchResult, SearchResultWithReplacement},
+    stdin_format::StdinFormat,
 };
 use std::{
     env::current_dir,
@@ -27,8 +28,9 @@ use scooter_core::{
         App, AppRunConfig, FocussedSection, Popup, Screen, SearchFieldsState, SearchPhase,
         SearchState,
     },
-    config::Config,
+    config::{Config, Keys, PathStyle},
     keyboard::{KeyCode as ScooterKeyCode, KeyModifiers as ScooterKeyModifiers},
+    mouse::{MouseEvent, MouseEventKind, ScreenArea},
 };
 
 const EVENT_TIMEOUT: Duration = Duration::from_millis(2_000);
@@ -51,9 +53,11 @@ async fn test_replace_state() {
                 replacement: format!("error replacement {n}"),
                 replace_result: Some(ReplaceResult::Error(format!("Test error {n}"))),
                 preview_error: None,
+                match_spans: vec![],
             })
             .collect::<Vec<_>>(),
         replacement_errors_pos: 0,
+        changed_paths: vec![],
     };
 
     state.scroll_replacement_errors_down();
@@ -69,7 +73,7 @@ async fn test_replace_state() {
 #[tokio::test]
 async fn test_app_reset() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -80,6 +84,7 @@ async fn test_app_reset() {
         num_ignored: 2,
         errors: vec![],
         replacement_errors_pos: 0,
+        changed_paths: vec![],
     });
 
     app.reset();
@@ -90,10 +95,28 @@ async fn test_app_reset() {
     ));
 }
 
+#[tokio::test]
+async fn test_app_git_tracked_only_outside_repository() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let result = App::new(
+        InputSource::Directory(vec![temp_dir.path().to_path_buf()]),
+        &SearchFieldValues::default(),
+        AppRunConfig {
+            git_tracked_only: true,
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    );
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Only searching files tracked by git requires each searched directory to be within a git repository"
+    );
+}
+
 #[tokio::test]
 async fn test_back_from_results() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -139,7 +162,7 @@ async fn test_back_from_results() {
 
 fn test_error_popup_invalid_input_impl(search_fields: &SearchFieldValues<'_>) {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         search_fields,
         AppRunConfig::default(),
         Config::default(),
@@ -216,7 +239,7 @@ async fn test_error_popup_invalid_exclude_files() {
 
 fn test_help_popup_on_screen(initial_screen: Screen) {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -270,6 +293,112 @@ async fn test_help_popup_on_search_results() {
     test_help_popup_on_screen(initial_screen);
 }
 
+#[tokio::test]
+async fn test_command_palette() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    let open_palette = KeyEvent::new(ScooterKeyCode::Char('k'), ScooterKeyModifiers::CONTROL);
+    let press =
+        |app: &mut App, code| app.handle_key_event(KeyEvent::new(code, ScooterKeyModifiers::NONE));
+
+    assert!(matches!(
+        app.handle_key_event(open_palette),
+        EventHandlingResult::Rerender
+    ));
+    let Some(Popup::CommandPalette(palette)) = app.popup() else {
+        panic!("Expected command palette, found {:?}", app.popup());
+    };
+    let descriptions = palette
+        .matching_entries()
+        .into_iter()
+        .map(|entry| entry.description.as_str())
+        .collect::<Vec<_>>();
+    assert!(descriptions.contains(&"toggle multiline"));
+    assert!(descriptions.contains(&"help"));
+    assert!(!descriptions.contains(&"command palette"));
+    assert!(!descriptions.contains(&"toggle checkbox"));
+
+    press(&mut app, ScooterKeyCode::Esc);
+    assert!(app.popup().is_none());
+
+    // Run a command by searching for it
+    app.handle_key_event(open_palette);
+    for c in "multi".chars() {
+        press(&mut app, ScooterKeyCode::Char(c));
+    }
+    assert!(!app.run_config.multiline);
+    press(&mut app, ScooterKeyCode::Enter);
+    assert!(app.popup().is_none());
+    assert!(app.run_config.multiline);
+    // Typing in the palette shouldn't have affected the search fields
+    assert_eq!(app.search_fields.search().text(), "");
+
+    // Commands that open a popup replace the palette
+    app.handle_key_event(open_palette);
+    for c in "hlp".chars() {
+        press(&mut app, ScooterKeyCode::Char(c));
+    }
+    press(&mut app, ScooterKeyCode::Enter);
+    assert!(matches!(app.popup(), Some(Popup::Help)));
+    press(&mut app, ScooterKeyCode::Esc);
+
+    // Nothing is run if no commands match
+    app.handle_key_event(open_palette);
+    for c in "zzz".chars() {
+        press(&mut app, ScooterKeyCode::Char(c));
+    }
+    assert!(matches!(
+        press(&mut app, ScooterKeyCode::Enter),
+        EventHandlingResult::Rerender
+    ));
+    assert!(app.popup().is_none());
+    assert!(app.run_config.multiline);
+}
+
+#[tokio::test]
+async fn test_toggle_pause_search() {
+    let mut app = App::new(
+        InputSource::Stdin(Arc::new(String::new())),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let mut state = SearchFieldsState::default();
+    state.search_state = Some(SearchState::new(sender, receiver, cancelled));
+    app.ui_state.current_screen = Screen::SearchFields(state);
+    let toggle_pause = KeyEvent::new(ScooterKeyCode::Char('z'), ScooterKeyModifiers::ALT);
+
+    assert!(matches!(
+        app.handle_key_event(toggle_pause),
+        EventHandlingResult::Rerender
+    ));
+    assert!(app.search_pause.is_paused());
+    assert!(matches!(
+        app.handle_key_event(toggle_pause),
+        EventHandlingResult::Rerender
+    ));
+    assert!(!app.search_pause.is_paused());
+
+    // A search that has completed can't be paused
+    let Screen::SearchFields(state) = &mut app.ui_state.current_screen else {
+        panic!("Expected SearchFields screen");
+    };
+    state.search_state.as_mut().unwrap().set_complete_now();
+    assert!(matches!(
+        app.handle_key_event(toggle_pause),
+        EventHandlingResult::None
+    ));
+    assert!(!app.search_pause.is_paused());
+}
+
 #[tokio::test]
 async fn test_help_popup_on_performing_replacement() {
     let (_sender, receiver) = mpsc::unbounded_channel();
@@ -290,6 +419,7 @@ async fn test_help_popup_on_results() {
         num_ignored: 2,
         errors: vec![],
         replacement_errors_pos: 0,
+        changed_paths: vec![],
     };
     test_help_popup_on_screen(Screen::Results(results_state));
 }
@@ -297,7 +427,7 @@ async fn test_help_popup_on_results() {
 #[tokio::test]
 async fn test_keymaps_search_fields() {
     let app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -313,10 +443,94 @@ async fn test_keymaps_search_fields() {
     assert_debug_snapshot!("search_fields_all_keymaps", app.keymaps_all());
 }
 
+#[tokio::test]
+async fn test_compact_keymaps_follow_focussed_field() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    assert_debug_snapshot!("text_field_focussed_compact_keymaps", app.keymaps_compact());
+
+    app.search_fields.highlighted = app
+        .search_fields
+        .fields
+        .iter()
+        .position(|field| matches!(field.field, Field::Checkbox(_)))
+        .unwrap();
+    assert_debug_snapshot!("checkbox_focussed_compact_keymaps", app.keymaps_compact());
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut search_state = SearchState::new(sender, receiver, cancelled);
+    search_state.set_complete_now();
+    let mut state = SearchFieldsState::default();
+    state.search_state = Some(search_state);
+    state.focussed_section = FocussedSection::SearchResults;
+    app.ui_state.current_screen = Screen::SearchFields(state);
+    assert_debug_snapshot!(
+        "results_list_focussed_compact_keymaps",
+        app.keymaps_compact()
+    );
+}
+
+#[tokio::test]
+async fn test_keymaps_reflect_remapped_keys() {
+    let mut config = Config::default();
+    config.keys.general.show_help_menu = Keys::new(vec![
+        KeyEvent::new(ScooterKeyCode::F(1), ScooterKeyModifiers::NONE),
+        KeyEvent::new(ScooterKeyCode::Char('?'), ScooterKeyModifiers::ALT),
+    ]);
+    let app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        config,
+    )
+    .unwrap();
+
+    let help_keys = |keymaps: Vec<(String, String)>| {
+        keymaps
+            .into_iter()
+            .find(|(_, name)| name == "help")
+            .map(|(keys, _)| keys)
+    };
+    assert_eq!(
+        help_keys(app.keymaps_all()).as_deref(),
+        Some("<F1> / <A-?>")
+    );
+    assert_eq!(help_keys(app.keymaps_compact()).as_deref(), Some("<F1>"));
+}
+
+#[tokio::test]
+async fn test_plain_char_binding_shows_text_input_warning() {
+    let mut config = Config::default();
+    config.keys.general.show_help_menu = Keys::new(vec![KeyEvent::new(
+        ScooterKeyCode::Char('?'),
+        ScooterKeyModifiers::NONE,
+    )]);
+    let app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        config,
+    )
+    .unwrap();
+
+    let Some(Popup::Text { title, body }) = app.popup() else {
+        panic!("Expected text popup, found {:?}", app.popup());
+    };
+    assert_eq!(title, "Key mapping warning");
+    assert!(body.contains("'?' in [keys.general] (show_help_menu)"));
+}
+
 #[tokio::test]
 async fn test_keymaps_search_complete() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -339,7 +553,7 @@ async fn test_keymaps_search_complete() {
 #[tokio::test]
 async fn test_keymaps_search_progressing() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -361,7 +575,7 @@ async fn test_keymaps_search_progressing() {
 #[tokio::test]
 async fn test_keymaps_performing_replacement() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -387,7 +601,7 @@ async fn test_keymaps_performing_replacement() {
 #[tokio::test]
 async fn test_keymaps_results() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -408,8 +622,10 @@ async fn test_keymaps_results() {
             replacement: "replacement".to_string(),
             replace_result: Some(ReplaceResult::Error("Test error".to_string())),
             preview_error: None,
+            match_spans: vec![],
         }],
         replacement_errors_pos: 0,
+        changed_paths: vec![],
     };
     app.ui_state.current_screen = Screen::Results(replace_state_with_errors);
 
@@ -421,6 +637,7 @@ async fn test_keymaps_results() {
         num_ignored: 2,
         errors: vec![],
         replacement_errors_pos: 0,
+        changed_paths: vec![],
     };
     app.ui_state.current_screen = Screen::Results(replace_state_without_errors);
 
@@ -434,7 +651,7 @@ async fn test_keymaps_results() {
 #[tokio::test]
 async fn test_keymaps_popup() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -462,7 +679,7 @@ async fn test_unlock_prepopulated_fields_via_alt_u() {
     };
 
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &search_field_values,
         AppRunConfig::default(),
         Config::default(),
@@ -502,7 +719,7 @@ async fn test_keybinding_integration_with_disabled_fields() {
     };
 
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &search_field_values,
         AppRunConfig::default(),
         Config::default(),
@@ -549,7 +766,7 @@ async fn test_alt_u_unlocks_all_fields() {
     };
 
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &search_field_values,
         AppRunConfig::default(),
         Config::default(),
@@ -585,7 +802,7 @@ async fn test_alt_u_unlocks_all_fields() {
 #[tokio::test]
 async fn test_handle_key_event_quit_with_ctrl_c_takes_precedence_over_popup() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -609,7 +826,7 @@ async fn test_handle_key_event_quit_with_ctrl_c_takes_precedence_over_popup() {
 #[tokio::test]
 async fn test_handle_key_event_unmapped_key_closes_popup() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -633,92 +850,1209 @@ async fn test_handle_key_event_unmapped_key_closes_popup() {
 }
 
 #[tokio::test]
-async fn test_handle_key_event_unmapped_key_in_search_fields_focus_enters_chars() {
+async fn test_handle_key_event_unmapped_key_in_search_fields_focus_enters_chars() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues {
+            search: FieldValue::new("test", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let Screen::SearchFields(ref state) = app.ui_state.current_screen else {
+        panic!(
+            "Expected Screen::SearchFields, found {:?}",
+            app.ui_state.current_screen
+        );
+    };
+    assert_eq!(state.focussed_section, FocussedSection::SearchFields);
+
+    let initial_text = app.search_fields.search().text().to_string();
+
+    // Press a character that's not mapped to a command
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('x'),
+        ScooterKeyModifiers::NONE,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(
+        app.search_fields.search().text(),
+        format!("{initial_text}x")
+    );
+}
+
+#[tokio::test]
+async fn test_handle_key_event_reset_command() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues {
+            search: FieldValue::new("test", false),
+            replace: FieldValue::new("replacement", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    assert_eq!(app.search_fields.search().text(), "test");
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('r'),
+        ScooterKeyModifiers::CONTROL,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(app.search_fields.search().text(), "");
+    assert_eq!(app.search_fields.replace().text(), "");
+}
+
+#[tokio::test]
+async fn test_handle_key_event_toggle_preview_wrapping() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let initial_wrap = app.config.preview.wrap_text;
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('l'),
+        ScooterKeyModifiers::CONTROL,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(app.config.preview.wrap_text, !initial_wrap);
+}
+
+#[tokio::test]
+async fn test_handle_key_event_toggle_preview() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    assert!(app.config.display.show_preview);
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('v'),
+        ScooterKeyModifiers::ALT,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert!(!app.config.display.show_preview);
+    assert_eq!(app.toast_message(), Some("Preview: OFF"));
+}
+
+#[tokio::test]
+async fn test_binary_files_included_shows_toast() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let result =
+        app.handle_background_processing_event(BackgroundProcessingEvent::BinaryFilesIncluded(3));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(app.toast_message(), Some("Searched 3 binary files"));
+}
+
+#[tokio::test]
+async fn test_non_utf8_lines_skipped_shows_toast_listing_files() {
+    let root = current_dir().unwrap();
+    let mut app = App::new(
+        InputSource::Directory(vec![root.clone()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let result =
+        app.handle_background_processing_event(BackgroundProcessingEvent::NonUtf8LinesSkipped(
+            vec![(root.join("a.txt"), 2), (root.join("dir/b.txt"), 1)],
+        ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(
+        app.toast_message(),
+        Some("Skipped 3 lines of invalid UTF-8 in 2 files\n  a.txt: 2 lines\n  dir/b.txt: 1 line")
+    );
+}
+
+#[tokio::test]
+async fn test_dirs_beyond_max_depth_shows_toast() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig {
+            max_depth: Some(2),
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+
+    let result =
+        app.handle_background_processing_event(BackgroundProcessingEvent::DirsBeyondMaxDepth {
+            num_dirs: 1,
+            max_depth: 2,
+        });
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(
+        app.toast_message(),
+        Some("Skipped 1 directory beyond max depth of 2")
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_multiline_ignored_for_framed_stdin() {
+    let mut app = App::new(
+        InputSource::Stdin(Arc::new("a.txt:1:foo\n".to_owned())),
+        &SearchFieldValues::default(),
+        AppRunConfig {
+            stdin_format: StdinFormat::Grep,
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('m'),
+        ScooterKeyModifiers::ALT,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::None));
+    assert!(!app.run_config.multiline);
+}
+
+#[tokio::test]
+async fn test_toggle_multiline_refused_with_line_ranges() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("a.txt"), "foo\nfoo\nfoo\n").unwrap();
+    let line_ranges = LineRanges::parse("a.txt:1-2\n", temp_dir.path()).unwrap();
+    let mut app = App::new(
+        InputSource::Directory(vec![temp_dir.path().to_path_buf()]),
+        &SearchFieldValues::default(),
+        AppRunConfig {
+            line_filter: LineFilter {
+                line_ranges: Some(Arc::new(line_ranges)),
+                ..LineFilter::default()
+            },
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('m'),
+        ScooterKeyModifiers::ALT,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert!(!app.run_config.multiline);
+    assert_eq!(
+        app.toast_message(),
+        Some("Multiline can't be combined with line filters or deleting empty lines")
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_multiline_refused_when_deleting_empty_lines() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig {
+            delete_empty_lines: true,
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('m'),
+        ScooterKeyModifiers::ALT,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert!(!app.run_config.multiline);
+    assert_eq!(
+        app.toast_message(),
+        Some("Multiline can't be combined with line filters or deleting empty lines")
+    );
+}
+
+#[tokio::test]
+async fn test_rg_json_stdin_searches_submatches_without_search_text() {
+    let content = [
+        r#"{"type":"match","data":{"path":{"text":"a.txt"},"lines":{"text":"foo bar\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"bar"},"start":4,"end":7}]}}"#,
+        r#"{"type":"match","data":{"path":{"bytes":"3/4="},"lines":{"text":"bar\n"},"line_number":1,"absolute_offset":0,"submatches":[{"match":{"text":"bar"},"start":0,"end":3}]}}"#,
+    ]
+    .join("\n");
+    let mut app = App::new(
+        InputSource::Stdin(Arc::new(content)),
+        &SearchFieldValues {
+            replace: FieldValue::new("baz", false),
+            ..Default::default()
+        },
+        AppRunConfig {
+            stdin_format: StdinFormat::RgJson,
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+
+    assert!(!app.search_is_empty());
+    wait_for_search_complete(&mut app).await;
+    let results = &search_fields_state(&app)
+        .search_state
+        .as_ref()
+        .unwrap()
+        .results;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].search_result.path, Some(PathBuf::from("a.txt")));
+    assert_eq!(results[0].search_result.content.matched_text(), "bar");
+    assert_eq!(results[0].replacement, "baz");
+
+    let result =
+        app.handle_background_processing_event(BackgroundProcessingEvent::NonUtf8PathsSkipped(1));
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(
+        app.toast_message(),
+        Some("Skipped 1 match in files with paths that aren't valid UTF-8")
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_advanced_regex() {
+    let mut app = App::new(
+        InputSource::Stdin(Arc::new("foo bar\nfoo baz\n".to_owned())),
+        &SearchFieldValues {
+            search: FieldValue::new("foo(?= bar)", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    // Look-ahead isn't supported by the default regex engine
+    assert!(!app.search_fields.errors().is_empty());
+    assert!(search_fields_state(&app).search_state.is_none());
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('r'),
+        ScooterKeyModifiers::ALT,
+    ));
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert!(app.run_config.advanced_regex);
+    assert_eq!(app.toast_message(), Some("Advanced regex: ON"));
+    assert!(app.search_fields.errors().is_empty());
+
+    wait_for_search_complete(&mut app).await;
+    let results = &search_fields_state(&app)
+        .search_state
+        .as_ref()
+        .unwrap()
+        .results;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].search_result.start_line_number(), 1);
+}
+
+#[tokio::test]
+async fn test_handle_key_event_toggle_nearby_replacements() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    assert!(!app.config.preview.show_nearby_replacements);
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('p'),
+        ScooterKeyModifiers::ALT,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert!(app.config.preview.show_nearby_replacements);
+    assert_eq!(app.toast_message(), Some("Nearby replacements: ON"));
+}
+
+#[tokio::test]
+async fn test_handle_key_event_focus_glob_fields() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues {
+            exclude_files: FieldValue::new("*.lock", true),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut state = SearchFieldsState::default();
+    state.search_state = Some(SearchState::new(sender, receiver, cancelled));
+    state.focussed_section = FocussedSection::SearchResults;
+    app.ui_state.current_screen = Screen::SearchFields(state);
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('i'),
+        ScooterKeyModifiers::ALT,
+    ));
+
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert_eq!(
+        app.search_fields.highlighted_field().name,
+        FieldName::IncludeFiles
+    );
+    let Screen::SearchFields(state) = &app.ui_state.current_screen else {
+        panic!("Expected SearchFields screen");
+    };
+    assert!(matches!(
+        state.focussed_section,
+        FocussedSection::SearchFields
+    ));
+    assert!(state.search_state.is_some());
+
+    // Exclude files was set via the CLI, so is locked
+    app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('o'),
+        ScooterKeyModifiers::ALT,
+    ));
+    assert_eq!(
+        app.search_fields.highlighted_field().name,
+        FieldName::IncludeFiles
+    );
+    assert_eq!(
+        app.toast_message(),
+        Some("Files to exclude was set from the command line")
+    );
+}
+
+#[tokio::test]
+async fn test_handle_key_event_cycle_path_style() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut state = SearchFieldsState::default();
+    state.search_state = Some(SearchState::new(sender, receiver, cancelled));
+    state.focussed_section = FocussedSection::SearchResults;
+    app.ui_state.current_screen = Screen::SearchFields(state);
+
+    assert_eq!(app.config.display.path_style, PathStyle::Relative);
+
+    for expected in [
+        PathStyle::Absolute,
+        PathStyle::Filename,
+        PathStyle::Relative,
+    ] {
+        let result = app.handle_key_event(KeyEvent::new(
+            ScooterKeyCode::Char('p'),
+            ScooterKeyModifiers::NONE,
+        ));
+
+        assert!(matches!(result, EventHandlingResult::Rerender));
+        assert_eq!(app.config.display.path_style, expected);
+        assert_eq!(
+            app.toast_message(),
+            Some(format!("Path style: {}", expected.as_str()).as_str())
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_find_in_results() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut search_state = SearchState::new(sender, receiver, cancelled);
+    for (file, line) in [("a.txt", "foo"), ("b.txt", "bar"), ("c.txt", "foo bar")] {
+        search_state.results.push(SearchResultWithReplacement {
+            search_result: SearchResult::new_line(
+                Some(current_dir().unwrap().join(file)),
+                1,
+                line.to_string(),
+                LineEnding::Lf,
+                true,
+            ),
+            replacement: line.to_string(),
+            replace_result: None,
+            preview_error: None,
+            match_spans: vec![],
+        });
+    }
+    search_state.set_complete_now();
+    let mut state = SearchFieldsState::default();
+    state.focussed_section = FocussedSection::SearchResults;
+    state.search_state = Some(search_state);
+    app.ui_state.current_screen = Screen::SearchFields(state);
+
+    let press = |app: &mut App, c: char| {
+        app.handle_key_event(KeyEvent::new(
+            ScooterKeyCode::Char(c),
+            ScooterKeyModifiers::NONE,
+        ))
+    };
+    let selected_pos = |app: &mut App| {
+        let Screen::SearchFields(state) = &app.ui_state.current_screen else {
+            panic!("Expected SearchFields screen");
+        };
+        state.search_state.as_ref().unwrap().primary_selected_pos()
+    };
+
+    press(&mut app, '/');
+    assert!(matches!(app.popup(), Some(Popup::FindInResults(_))));
+    for c in "bar".chars() {
+        press(&mut app, c);
+    }
+    app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Enter,
+        ScooterKeyModifiers::NONE,
+    ));
+    assert!(app.popup().is_none());
+    assert_eq!(selected_pos(&mut app), 1);
+
+    press(&mut app, 'n');
+    assert_eq!(selected_pos(&mut app), 2);
+    press(&mut app, 'n');
+    assert_eq!(selected_pos(&mut app), 1);
+    press(&mut app, 'N');
+    assert_eq!(selected_pos(&mut app), 2);
+
+    // The path of the result is searched as well as its content
+    press(&mut app, '/');
+    app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('u'),
+        ScooterKeyModifiers::CONTROL,
+    ));
+    for c in "a.t".chars() {
+        press(&mut app, c);
+    }
+    app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Enter,
+        ScooterKeyModifiers::NONE,
+    ));
+    assert_eq!(selected_pos(&mut app), 0);
+
+    press(&mut app, '/');
+    app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('u'),
+        ScooterKeyModifiers::CONTROL,
+    ));
+    for c in "missing".chars() {
+        press(&mut app, c);
+    }
+    app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Enter,
+        ScooterKeyModifiers::NONE,
+    ));
+    assert_eq!(selected_pos(&mut app), 0);
+    assert_eq!(app.toast_message(), Some("No results contain \"missing\""));
+}
+
+#[tokio::test]
+async fn test_select_range() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut search_state = SearchState::new(sender, receiver, cancelled);
+    search_state.results = (0..10).map(|_| dummy_result()).collect();
+    search_state.set_complete_now();
+    let mut state = SearchFieldsState::default();
+    state.focussed_section = FocussedSection::SearchResults;
+    state.search_state = Some(search_state);
+    app.ui_state.current_screen = Screen::SearchFields(state);
+
+    let enter_range = |app: &mut App, range: &str| {
+        type_char(app, ':');
+        assert!(matches!(app.popup(), Some(Popup::SelectRange(_))));
+        for c in range.chars() {
+            type_char(app, c);
+        }
+        app.handle_key_event(KeyEvent::new(
+            ScooterKeyCode::Enter,
+            ScooterKeyModifiers::NONE,
+        ));
+        assert!(app.popup().is_none());
+    };
+    let selected = |app: &App| {
+        let state = search_fields_state(app).search_state.as_ref().unwrap();
+        (0..state.results.len())
+            .filter(|&idx| state.is_selected(idx))
+            .collect::<Vec<_>>()
+    };
+
+    enter_range(&mut app, "3,5");
+    assert_eq!(selected(&app), vec![2, 3, 4]);
+
+    // Out-of-bounds ranges leave the selection unchanged
+    enter_range(&mut app, "8,11");
+    assert_eq!(selected(&app), vec![2, 3, 4]);
+    assert_eq!(app.toast_message(), Some("Range must be between 1 and 10"));
+}
+
+#[tokio::test]
+async fn test_show_match_summary() {
+    let mut app = App::new(
+        InputSource::Stdin(Arc::new("foo fooo\nfoo\nbar fo\n".to_owned())),
+        &SearchFieldValues {
+            search: FieldValue::new("fo+", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    wait_for_search_complete(&mut app).await;
+    focus_search_results(&mut app);
+
+    type_char(&mut app, 'm');
+
+    let Some(Popup::Text { title, body }) = app.popup() else {
+        panic!("Expected text popup, found {:?}", app.popup());
+    };
+    assert_eq!(title, "Match summary");
+    assert_eq!(
+        body,
+        "3 distinct matches across 4 occurrences\n\n2  foo\n1  fo\n1  fooo"
+    );
+}
+
+#[tokio::test]
+async fn test_show_error_details() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    let long_error = "Permission denied: ".to_owned() + &"x".repeat(200);
+    app.ui_state.current_screen = Screen::Results(ReplaceState {
+        num_successes: 1,
+        num_ignored: 0,
+        errors: vec![SearchResultWithReplacement {
+            search_result: SearchResult::new_line(
+                Some(PathBuf::from("error.txt")),
+                3,
+                "test line".to_string(),
+                LineEnding::Lf,
+                true,
+            ),
+            replacement: "replacement".to_string(),
+            replace_result: Some(ReplaceResult::Error(long_error.clone())),
+            preview_error: None,
+            match_spans: vec![],
+        }],
+        replacement_errors_pos: 0,
+        changed_paths: vec![],
+    });
+
+    type_char(&mut app, 'e');
+
+    let Some(Popup::Text { title, body }) = app.popup() else {
+        panic!("Expected text popup, found {:?}", app.popup());
+    };
+    assert_eq!(title, "Error details");
+    assert_eq!(body, &format!("error.txt:3\n\n{long_error}"));
+}
+
+#[tokio::test]
+async fn test_retry_failed_replacements() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("a.txt");
+    std::fs::write(&file_path, "foo\nbar\n").unwrap();
+    let mut app = App::new(
+        InputSource::Directory(vec![temp_dir.path().to_path_buf()]),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("baz", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    wait_for_search_complete(&mut app).await;
+
+    std::fs::write(&file_path, "changed\nbar\n").unwrap();
+    app.perform_replacement();
+    let state = wait_for_replacement_complete(&mut app).await;
+    assert_eq!((state.num_successes, state.errors.len()), (0, 1));
+
+    std::fs::write(&file_path, "foo\nbar\n").unwrap();
+    type_char(&mut app, 'r');
+    let state = wait_for_replacement_complete(&mut app).await;
+    assert_eq!((state.num_successes, state.errors.len()), (1, 0));
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "baz\nbar\n");
+}
+
+#[tokio::test]
+async fn test_replacement_leaves_files_without_changes_untouched() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    for file in ["replaced.txt", "excluded.txt", "changed.txt"] {
+        std::fs::write(temp_dir.path().join(file), "foo\n").unwrap();
+    }
+    let mut app = App::new(
+        InputSource::Directory(vec![temp_dir.path().to_path_buf()]),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("bar", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    wait_for_search_complete(&mut app).await;
+
+    let Screen::SearchFields(SearchFieldsState {
+        search_state: Some(state),
+        ..
+    }) = &mut app.ui_state.current_screen
+    else {
+        panic!("Expected search results");
+    };
+    for res in &mut state.results {
+        if res
+            .search_result
+            .path
+            .as_ref()
+            .unwrap()
+            .ends_with("excluded.txt")
+        {
+            res.search_result.included = false;
+        }
+    }
+    let changed_path = temp_dir.path().join("changed.txt");
+    std::fs::write(&changed_path, "baz\n").unwrap();
+    let modified = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(24 * 60 * 60);
+    for file in ["excluded.txt", "changed.txt"] {
+        std::fs::File::options()
+            .write(true)
+            .open(temp_dir.path().join(file))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    app.perform_replacement();
+    let state = wait_for_replacement_complete(&mut app).await;
+    assert_eq!(
+        (state.num_successes, state.num_ignored, state.errors.len()),
+        (1, 1, 1)
+    );
+
+    let read = |file: &str| std::fs::read_to_string(temp_dir.path().join(file)).unwrap();
+    assert_eq!(read("replaced.txt"), "bar\n");
+    assert_eq!(read("excluded.txt"), "foo\n");
+    assert_eq!(read("changed.txt"), "baz\n");
+    for file in ["excluded.txt", "changed.txt"] {
+        let metadata = std::fs::metadata(temp_dir.path().join(file)).unwrap();
+        assert_eq!(
+            metadata.modified().unwrap(),
+            modified,
+            "{file} was rewritten"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_edit_config() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char(','),
+        ScooterKeyModifiers::ALT,
+    ));
+    assert!(result.is_rerender());
+
+    let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+        .await
+        .expect("Expected edit config event");
+    assert!(matches!(event, Event::EditConfig));
+}
+
+#[tokio::test]
+async fn test_show_headless_command() {
+    let mut app = App::new(
+        InputSource::Directory(vec![PathBuf::from("src"), PathBuf::from("tests")]),
+        &SearchFieldValues {
+            search: FieldValue::new("foo bar", false),
+            replace: FieldValue::new("baz", false),
+            ..Default::default()
+        },
+        AppRunConfig {
+            multiline: true,
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('x'),
+        ScooterKeyModifiers::ALT,
+    ));
+    assert!(matches!(result, EventHandlingResult::Rerender));
+
+    let expected =
+        "scooter --no-tui --search-text='foo bar' --replace-text=baz --multiline src tests";
+    assert_eq!(app.headless_command(), expected);
+    let Some(Popup::Text { title, body }) = app.popup() else {
+        panic!("Expected text popup, found {:?}", app.popup());
+    };
+    assert_eq!(title, "Equivalent command");
+    assert!(body.starts_with(expected));
+
+    let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+        .await
+        .expect("Expected clipboard event");
+    assert!(matches!(event, Event::CopyToClipboard(text) if text == expected));
+}
+
+#[tokio::test]
+async fn test_copy_results_to_clipboard() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join("a.txt"), "let foo = 1;\n").unwrap();
+    let mut app = App::new(
+        InputSource::Directory(vec![temp_dir.path().to_path_buf()]),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("bar", false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    wait_for_search_complete(&mut app).await;
+    let Screen::SearchFields(state) = &mut app.ui_state.current_screen else {
+        panic!("Expected SearchFields screen");
+    };
+    state.focussed_section = FocussedSection::SearchResults;
+
+    for (key, expected_text, expected_toast) in [
+        ('y', "let foo = 1;", "Copied matched text to the clipboard"),
+        ('Y', "let bar = 1;", "Copied replacement to the clipboard"),
+        (
+            'E',
+            "a.txt:1:let foo = 1;\n",
+            "Copied 1 result to the clipboard as grep output",
+        ),
+    ] {
+        let result = app.handle_key_event(KeyEvent::new(
+            ScooterKeyCode::Char(key),
+            ScooterKeyModifiers::NONE,
+        ));
+        assert!(matches!(result, EventHandlingResult::Rerender));
+        assert_eq!(app.toast_message(), Some(expected_toast));
+
+        let text = loop {
+            let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+                .await
+                .expect("Expected clipboard event");
+            if let Event::CopyToClipboard(text) = event {
+                break text;
+            }
+        };
+        assert_eq!(text, expected_text);
+    }
+}
+
+#[tokio::test]
+async fn test_interactive_filter() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    for file in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(temp_dir.path().join(file), "foo\n").unwrap();
+    }
+    let mut app = App::new(
+        InputSource::Directory(vec![temp_dir.path().to_path_buf()]),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            ..Default::default()
+        },
+        AppRunConfig {
+            interactive_filter: true,
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+    assert!(matches!(
+        &app.ui_state.current_screen,
+        Screen::FilePicker(state) if state.files.is_none()
+    ));
+
+    // Confirming is ignored until the files have been listed
+    let enter = KeyEvent::new(ScooterKeyCode::Enter, ScooterKeyModifiers::NONE);
+    assert!(matches!(
+        app.handle_key_event(enter),
+        EventHandlingResult::None
+    ));
+
+    let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+        .await
+        .expect("Expected files to be listed");
+    let Event::Internal(event @ InternalEvent::App(AppEvent::FilesListed(_))) = event else {
+        panic!("Expected FilesListed event, found {event:?}");
+    };
+    app.handle_internal_event(event);
+    let Screen::FilePicker(state) = &app.ui_state.current_screen else {
+        panic!("Expected FilePicker screen");
+    };
+    assert_eq!(state.num_included(), 3);
+
+    for c in ['j', ' '] {
+        app.handle_key_event(KeyEvent::new(
+            ScooterKeyCode::Char(c),
+            ScooterKeyModifiers::NONE,
+        ));
+    }
+    app.handle_key_event(enter);
+
+    loop {
+        if search_fields_state(&app)
+            .search_state
+            .as_ref()
+            .is_some_and(|state| state.phase.is_complete())
+        {
+            break;
+        }
+        let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+            .await
+            .expect("Expected search to complete");
+        if let Event::Internal(event) = event {
+            app.handle_internal_event(event);
+        }
+    }
+    let mut paths = search_fields_state(&app)
+        .search_state
+        .as_ref()
+        .unwrap()
+        .results
+        .iter()
+        .filter_map(|res| res.search_result.path.clone())
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![temp_dir.path().join("a.txt"), temp_dir.path().join("c.txt")]
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_match_case_from_results_keeps_selection() {
+    let mut app = App::new(
+        InputSource::Stdin(Arc::new("foo\nFOO\nfoo bar\n".to_owned())),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            match_case: FieldValue::new(true, false),
+            ..Default::default()
+        },
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    wait_for_search_complete(&mut app).await;
+    focus_search_results(&mut app);
+    type_char(&mut app, 'j');
+
+    let line_numbers = |app: &App| {
+        let state = search_fields_state(app).search_state.as_ref().unwrap();
+        state
+            .results
+            .iter()
+            .map(|res| res.search_result.start_line_number())
+            .collect::<Vec<_>>()
+    };
+    let selected_line = |app: &App| {
+        let state = search_fields_state(app).search_state.as_ref().unwrap();
+        state.results[state.primary_selected_pos()]
+            .search_result
+            .start_line_number()
+    };
+    assert_eq!(line_numbers(&app), vec![1, 3]);
+    assert_eq!(selected_line(&app), 3);
+
+    type_char(&mut app, 'C');
+    assert!(!app.search_fields.match_case().checked);
+    assert_eq!(app.toast_message(), Some("Match case: OFF"));
+    wait_for_search_complete(&mut app).await;
+    assert_eq!(line_numbers(&app), vec![1, 2, 3]);
+    assert_eq!(selected_line(&app), 3);
+    assert_eq!(
+        search_fields_state(&app).focussed_section,
+        FocussedSection::SearchResults
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_fixed_strings_from_results_with_invalid_regex() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Stdin(Arc::new("(foo\n".to_owned())),
         &SearchFieldValues {
-            search: FieldValue::new("test", false),
+            search: FieldValue::new("(", false),
+            fixed_strings: FieldValue::new(true, false),
             ..Default::default()
         },
         AppRunConfig::default(),
         Config::default(),
     )
     .unwrap();
+    wait_for_search_complete(&mut app).await;
+    focus_search_results(&mut app);
 
-    let Screen::SearchFields(ref state) = app.ui_state.current_screen else {
-        panic!(
-            "Expected Screen::SearchFields, found {:?}",
-            app.ui_state.current_screen
-        );
-    };
-    assert_eq!(state.focussed_section, FocussedSection::SearchFields);
-
-    let initial_text = app.search_fields.search().text().to_string();
-
-    // Press a character that's not mapped to a command
-    let result = app.handle_key_event(KeyEvent::new(
-        ScooterKeyCode::Char('x'),
-        ScooterKeyModifiers::NONE,
+    type_char(&mut app, 'F');
+    assert!(!app.search_fields.fixed_strings().checked);
+    assert!(matches!(
+        search_fields_state(&app)
+            .search_state
+            .as_ref()
+            .unwrap()
+            .phase,
+        SearchPhase::Invalid
     ));
-
-    assert!(matches!(result, EventHandlingResult::Rerender));
     assert_eq!(
-        app.search_fields.search().text(),
-        format!("{initial_text}x")
+        search_fields_state(&app).focussed_section,
+        FocussedSection::SearchFields
     );
 }
 
 #[tokio::test]
-async fn test_handle_key_event_reset_command() {
+async fn test_skip_noop() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Stdin(Arc::new("foo\nfooo\nfoo bar\n".to_owned())),
         &SearchFieldValues {
-            search: FieldValue::new("test", false),
-            replace: FieldValue::new("replacement", false),
+            search: FieldValue::new("fo+", false),
+            replace: FieldValue::new("foo", false),
             ..Default::default()
         },
-        AppRunConfig::default(),
+        AppRunConfig {
+            skip_noop: true,
+            ..AppRunConfig::default()
+        },
         Config::default(),
     )
     .unwrap();
-
-    assert_eq!(app.search_fields.search().text(), "test");
-
-    let result = app.handle_key_event(KeyEvent::new(
-        ScooterKeyCode::Char('r'),
-        ScooterKeyModifiers::CONTROL,
+    wait_for_search_complete(&mut app).await;
+    let state = search_fields_state(&app).search_state.as_ref().unwrap();
+    let replacements = state
+        .results
+        .iter()
+        .map(|res| res.replacement.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(replacements, vec!["foo"]);
+    assert_eq!(state.num_noops_skipped, 2);
+
+    // Changing the replacement searches again, as different results may now be no-ops
+    app.search_fields.focus_next(false);
+    assert!(matches!(
+        app.search_fields.highlighted_field().name,
+        FieldName::Replace
     ));
+    type_char(&mut app, 'o');
+    wait_for_search_complete(&mut app).await;
+    let state = search_fields_state(&app).search_state.as_ref().unwrap();
+    assert_eq!(state.results.len(), 2);
+    assert_eq!(state.num_noops_skipped, 1);
+}
 
-    assert!(matches!(result, EventHandlingResult::Rerender));
-    assert_eq!(app.search_fields.search().text(), "");
-    assert_eq!(app.search_fields.replace().text(), "");
+#[tokio::test]
+async fn test_occurrence() {
+    for (occurrence, expected_included, expected_toast) in [
+        (2, vec![false, true, false], None),
+        (
+            4,
+            vec![false, false, false],
+            Some("Found 3 results, so there is no occurrence 4 to replace"),
+        ),
+    ] {
+        let mut app = App::new(
+            InputSource::Stdin(Arc::new("foo\nbar\nfoo\nfoo\n".to_owned())),
+            &SearchFieldValues {
+                search: FieldValue::new("foo", false),
+                replace: FieldValue::new("baz", false),
+                ..Default::default()
+            },
+            AppRunConfig {
+                occurrence: Some(occurrence),
+                ..AppRunConfig::default()
+            },
+            Config::default(),
+        )
+        .unwrap();
+        wait_for_search_complete(&mut app).await;
+        let state = search_fields_state(&app).search_state.as_ref().unwrap();
+        let included = state
+            .results
+            .iter()
+            .map(|res| res.search_result.included)
+            .collect::<Vec<_>>();
+        assert_eq!(included, expected_included);
+        assert_eq!(app.toast_message(), expected_toast);
+    }
 }
 
 #[tokio::test]
-async fn test_handle_key_event_toggle_preview_wrapping() {
+async fn test_select_none() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
-        &SearchFieldValues::default(),
-        AppRunConfig::default(),
+        InputSource::Stdin(Arc::new("foo\nbar\nfoo\n".to_owned())),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("baz", false),
+            ..Default::default()
+        },
+        AppRunConfig {
+            select_none: true,
+            ..AppRunConfig::default()
+        },
         Config::default(),
     )
     .unwrap();
+    wait_for_search_complete(&mut app).await;
+    let state = search_fields_state(&app).search_state.as_ref().unwrap();
+    let included = state
+        .results
+        .iter()
+        .map(|res| res.search_result.included)
+        .collect::<Vec<_>>();
+    assert_eq!(included, vec![false, false]);
+}
 
-    let initial_wrap = app.config.preview.wrap_text;
+async fn auto_included(root: &std::path::Path, globs: &str) -> Vec<(PathBuf, bool)> {
+    let mut app = App::new(
+        InputSource::Directory(vec![root.to_path_buf()]),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("baz", false),
+            ..Default::default()
+        },
+        AppRunConfig {
+            auto_include: Some(globs.to_owned()),
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+    wait_for_search_complete(&mut app).await;
+    let state = search_fields_state(&app).search_state.as_ref().unwrap();
+    let mut included = state
+        .results
+        .iter()
+        .map(|res| {
+            let path = res.search_result.path.as_ref().unwrap();
+            (
+                path.strip_prefix(root).unwrap().to_path_buf(),
+                res.search_result.included,
+            )
+        })
+        .collect::<Vec<_>>();
+    included.sort();
+    included
+}
 
-    let result = app.handle_key_event(KeyEvent::new(
-        ScooterKeyCode::Char('l'),
-        ScooterKeyModifiers::CONTROL,
-    ));
+#[tokio::test]
+async fn test_auto_include() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("tests/fixtures")).unwrap();
+    for file in [
+        "src/lib.rs",
+        "src/lib_test.rs",
+        "tests/app.rs",
+        "tests/fixtures/a.rs",
+    ] {
+        std::fs::write(root.join(file), "foo\n").unwrap();
+    }
 
-    assert!(matches!(result, EventHandlingResult::Rerender));
-    assert_eq!(app.config.preview.wrap_text, !initial_wrap);
+    // Results from other files are still shown, but excluded
+    assert_eq!(
+        auto_included(root, "tests/, *_test.rs").await,
+        vec![
+            (PathBuf::from("src/lib.rs"), false),
+            (PathBuf::from("src/lib_test.rs"), true),
+            (PathBuf::from("tests/app.rs"), true),
+            (PathBuf::from("tests/fixtures/a.rs"), true),
+        ]
+    );
+    // With only negated globs, everything they don't match is included
+    assert_eq!(
+        auto_included(root, "!tests/, !*_test.rs").await,
+        vec![
+            (PathBuf::from("src/lib.rs"), true),
+            (PathBuf::from("src/lib_test.rs"), false),
+            (PathBuf::from("tests/app.rs"), false),
+            (PathBuf::from("tests/fixtures/a.rs"), false),
+        ]
+    );
+    assert_eq!(
+        auto_included(root, "tests/, !tests/fixtures/").await,
+        vec![
+            (PathBuf::from("src/lib.rs"), false),
+            (PathBuf::from("src/lib_test.rs"), false),
+            (PathBuf::from("tests/app.rs"), true),
+            (PathBuf::from("tests/fixtures/a.rs"), false),
+        ]
+    );
 }
 
 #[tokio::test]
 async fn test_toggle_escape_sequences_updates_preview_without_restarting_search() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues {
             search: FieldValue::new("foo", false),
             replace: FieldValue::new(r"X\nY", false),
@@ -744,6 +2078,7 @@ async fn test_toggle_escape_sequences_updates_preview_without_restarting_search(
         replacement: "stale replacement".to_string(),
         replace_result: None,
         preview_error: None,
+        match_spans: vec![],
     });
     search_state.set_complete_now();
     let mut state = SearchFieldsState::default();
@@ -777,7 +2112,7 @@ async fn test_toggle_escape_sequences_updates_preview_without_restarting_search(
 #[tokio::test]
 async fn test_toggle_escape_sequences_without_search_state_only_toggles_flag() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues {
             search: FieldValue::new("foo", false),
             replace: FieldValue::new(r"X\nY", false),
@@ -807,7 +2142,7 @@ async fn test_toggle_escape_sequences_without_search_state_only_toggles_flag() {
 #[tokio::test]
 async fn test_toggle_escape_sequences_keeps_pending_debounced_search() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues {
             replace: FieldValue::new(r"X\nY", false),
             fixed_strings: FieldValue::new(true, false),
@@ -868,7 +2203,7 @@ async fn test_toggle_escape_sequences_keeps_pending_debounced_search() {
 #[tokio::test]
 async fn test_handle_key_event_show_help_menu() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues::default(),
         AppRunConfig::default(),
         Config::default(),
@@ -889,7 +2224,7 @@ async fn test_handle_key_event_show_help_menu() {
 #[tokio::test]
 async fn test_handle_key_event_enter_triggers_search_from_fields() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues {
             search: FieldValue::new("test", false),
             ..Default::default()
@@ -919,7 +2254,7 @@ async fn test_handle_key_event_enter_triggers_search_from_fields() {
 #[tokio::test]
 async fn test_handle_key_event_backspace_in_search_fields() {
     let mut app = App::new(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         &SearchFieldValues {
             search: FieldValue::new("test", false),
             ..Default::default()
@@ -1006,6 +2341,42 @@ fn search_fields_state(app: &App) -> &SearchFieldsState {
     state
 }
 
+async fn wait_for_search_complete(app: &mut App) {
+    while !search_fields_state(app)
+        .search_state
+        .as_ref()
+        .is_some_and(|state| state.phase.is_complete())
+    {
+        let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+            .await
+            .expect("Expected search to complete");
+        if let Event::Internal(event) = event {
+            app.handle_internal_event(event);
+        }
+    }
+}
+
+async fn wait_for_replacement_complete(app: &mut App) -> ReplaceState {
+    loop {
+        if let Screen::Results(state) = &app.ui_state.current_screen {
+            return state.clone();
+        }
+        let event = tokio::time::timeout(EVENT_TIMEOUT, app.event_recv())
+            .await
+            .expect("Expected replacement to complete");
+        if let Event::Internal(event) = event {
+            app.handle_internal_event(event);
+        }
+    }
+}
+
+fn focus_search_results(app: &mut App) {
+    let Screen::SearchFields(state) = &mut app.ui_state.current_screen else {
+        panic!("Expected SearchFields screen");
+    };
+    state.focussed_section = FocussedSection::SearchResults;
+}
+
 fn type_char(app: &mut App, c: char) -> EventHandlingResult {
     app.handle_key_event(KeyEvent::new(
         ScooterKeyCode::Char(c),
@@ -1013,6 +2384,107 @@ fn type_char(app: &mut App, c: char) -> EventHandlingResult {
     ))
 }
 
+#[tokio::test]
+async fn test_mouse_events_in_results_list() {
+    let mut app = App::new(
+        InputSource::Directory(vec![current_dir().unwrap()]),
+        &SearchFieldValues::default(),
+        AppRunConfig::default(),
+        Config::default(),
+    )
+    .unwrap();
+    // Mouse support is off by default
+    app.config.display.mouse = true;
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut search_state = SearchState::new(sender, receiver, cancelled);
+    search_state.results = (0..10).map(|_| dummy_result()).collect();
+    search_state.view_offset = 2;
+    search_state.set_complete_now();
+    let mut state = SearchFieldsState::default();
+    state.search_state = Some(search_state);
+    app.ui_state.current_screen = Screen::SearchFields(state);
+    app.ui_state.results_list_area = Some(ScreenArea {
+        x: 5,
+        y: 10,
+        width: 40,
+        height: 5,
+    });
+
+    let mouse = |kind, column, row| MouseEvent { kind, column, row };
+    let selected_pos = |app: &App| {
+        search_fields_state(app)
+            .search_state
+            .as_ref()
+            .unwrap()
+            .primary_selected_pos()
+    };
+    let included = |app: &App| {
+        search_fields_state(app)
+            .search_state
+            .as_ref()
+            .unwrap()
+            .results
+            .iter()
+            .map(|res| res.search_result.included)
+            .collect::<Vec<_>>()
+    };
+
+    // Clicking a result selects it and focusses the results list, without changing inclusion
+    assert!(matches!(
+        app.handle_mouse_event(mouse(MouseEventKind::LeftClick, 20, 11)),
+        EventHandlingResult::Rerender
+    ));
+    assert_eq!(selected_pos(&app), 3);
+    assert_eq!(
+        search_fields_state(&app).focussed_section,
+        FocussedSection::SearchResults
+    );
+    assert!(included(&app).iter().all(|&included| included));
+
+    // Clicking the checkbox also toggles inclusion
+    app.handle_mouse_event(mouse(MouseEventKind::LeftClick, 6, 13));
+    assert_eq!(selected_pos(&app), 5);
+    assert_eq!(
+        included(&app),
+        (0..10).map(|idx| idx != 5).collect::<Vec<_>>()
+    );
+
+    // Scrolling moves the selection, stopping at the ends of the list
+    app.handle_mouse_event(mouse(MouseEventKind::ScrollDown, 20, 12));
+    assert_eq!(selected_pos(&app), 6);
+    for _ in 0..10 {
+        app.handle_mouse_event(mouse(MouseEventKind::ScrollUp, 20, 12));
+    }
+    assert_eq!(selected_pos(&app), 0);
+
+    // Events outside the list, or below the last result, are ignored
+    for (column, row) in [(4, 11), (20, 9), (45, 11), (20, 15)] {
+        assert!(matches!(
+            app.handle_mouse_event(mouse(MouseEventKind::LeftClick, column, row)),
+            EventHandlingResult::None
+        ));
+    }
+    let Screen::SearchFields(state) = &mut app.ui_state.current_screen else {
+        panic!("Expected SearchFields screen");
+    };
+    state.search_state.as_mut().unwrap().view_offset = 8;
+    assert!(matches!(
+        app.handle_mouse_event(mouse(MouseEventKind::LeftClick, 20, 13)),
+        EventHandlingResult::None
+    ));
+    assert_eq!(selected_pos(&app), 0);
+
+    // Mouse events are ignored if disabled in the config, as they are by default
+    app.config.display.mouse = false;
+    assert!(matches!(
+        app.handle_mouse_event(mouse(MouseEventKind::LeftClick, 20, 11)),
+        EventHandlingResult::None
+    ));
+    assert_eq!(selected_pos(&app), 0);
+}
+
 fn queued_search_generation(event: Event) -> u64 {
     match event {
         Event::Internal(InternalEvent::App(AppEvent::PerformSearch { generation })) => generation,
@@ -1032,6 +2504,7 @@ fn dummy_result() -> SearchResultWithReplacement {
         replacement: "line".to_owned(),
         replace_result: None,
         preview_error: None,
+        match_spans: vec![],
     }
 }
 
@@ -1408,6 +2881,58 @@ async fn test_cancelled_state_drops_incoming_search_results() {
     );
 }
 
+#[tokio::test]
+async fn test_search_results_rerender_at_most_once_per_render_interval() {
+    let started = std::time::Instant::now();
+    let mut app = build_test_app_with_phase(
+        stdin_source(),
+        "foo",
+        SearchPhase::Running { started },
+        vec![],
+    );
+    app.config.ui.render_interval_ms = 500;
+
+    let add_result = |app: &mut App| {
+        app.handle_background_processing_event(BackgroundProcessingEvent::AddSearchResult(
+            SearchResult::new_line(
+                Some(PathBuf::from("file.txt")),
+                1,
+                "foo".to_owned(),
+                LineEnding::Lf,
+                true,
+            ),
+        ))
+    };
+    let set_last_render = |app: &mut App, ago: Duration| {
+        let Screen::SearchFields(SearchFieldsState {
+            search_state: Some(state),
+            ..
+        }) = &mut app.ui_state.current_screen
+        else {
+            panic!("Expected search state");
+        };
+        state.last_render = std::time::Instant::now().checked_sub(ago).unwrap();
+    };
+
+    // The default interval of 92ms has passed, but the configured interval hasn't
+    set_last_render(&mut app, Duration::from_millis(200));
+    assert!(matches!(add_result(&mut app), EventHandlingResult::None));
+
+    set_last_render(&mut app, Duration::from_millis(600));
+    assert!(add_result(&mut app).is_rerender());
+    // The render time is reset, so results arriving straight afterwards don't rerender
+    assert!(matches!(add_result(&mut app), EventHandlingResult::None));
+    assert_eq!(
+        search_fields_state(&app)
+            .search_state
+            .as_ref()
+            .unwrap()
+            .results
+            .len(),
+        3
+    );
+}
+
 #[tokio::test]
 async fn test_invalid_edit_cancels_running_search_and_drops_late_results() {
     let started = std::time::Instant::now();
@@ -1567,7 +3092,7 @@ async fn test_cursor_movement_preserves_pending_search_debounce() {
 async fn test_cursor_movement_skips_redundant_search_debounce_directory_source() {
     let started = std::time::Instant::now();
     let mut app = build_test_app_with_phase(
-        InputSource::Directory(current_dir().unwrap()),
+        InputSource::Directory(vec![current_dir().unwrap()]),
         "abc",
         SearchPhase::Complete {
             started,
@@ -1694,6 +3219,133 @@ async fn test_back_to_fields_keeps_search_running_until_completion() {
     );
 }
 
+#[tokio::test]
+async fn test_refresh_search_preserves_inclusions() {
+    let started = std::time::Instant::now();
+    let result_at = |line_number, included| SearchResultWithReplacement {
+        search_result: SearchResult::new_line(
+            Some(PathBuf::from("a.txt")),
+            line_number,
+            "foo".to_owned(),
+            LineEnding::Lf,
+            included,
+        ),
+        replacement: "foo".to_owned(),
+        replace_result: None,
+        preview_error: None,
+        match_spans: vec![],
+    };
+    let mut app = build_test_app_with_phase(
+        stdin_source(),
+        "foo",
+        SearchPhase::Complete {
+            started,
+            completed: started,
+        },
+        vec![result_at(1, false), result_at(2, true)],
+    );
+    let Screen::SearchFields(state) = &mut app.ui_state.current_screen else {
+        panic!("Expected SearchFields screen");
+    };
+    state.focussed_section = FocussedSection::SearchResults;
+    let old_cancelled = Arc::clone(&state.search_state.as_ref().unwrap().cancelled);
+
+    let result = type_char(&mut app, 'r');
+    assert!(matches!(result, EventHandlingResult::Rerender));
+    assert!(old_cancelled.load(Ordering::Relaxed));
+
+    let state = search_fields_state(&app);
+    assert_eq!(state.focussed_section, FocussedSection::SearchResults);
+    let search_state = state
+        .search_state
+        .as_ref()
+        .expect("search should be re-run");
+    assert!(matches!(search_state.phase, SearchPhase::Running { .. }));
+    assert!(search_state.results.is_empty());
+
+    for line_number in [1, 2, 3] {
+        app.handle_background_processing_event(BackgroundProcessingEvent::AddSearchResult(
+            SearchResult::new_line(
+                Some(PathBuf::from("a.txt")),
+                line_number,
+                "foo".to_owned(),
+                LineEnding::Lf,
+                true,
+            ),
+        ));
+    }
+
+    let included: Vec<_> = search_fields_state(&app)
+        .search_state
+        .as_ref()
+        .unwrap()
+        .results
+        .iter()
+        .map(|res| res.search_result.included)
+        .collect();
+    assert_eq!(included, vec![false, true, true]);
+}
+
+#[tokio::test]
+async fn test_initial_focus_from_run_config() {
+    let app = App::new(
+        stdin_source(),
+        &SearchFieldValues {
+            search: FieldValue::new("foo", true),
+            ..Default::default()
+        },
+        AppRunConfig {
+            initial_focus: Some(FieldName::ExcludeFiles),
+            ..AppRunConfig::default()
+        },
+        Config::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        app.search_fields.highlighted_field().name,
+        FieldName::ExcludeFiles
+    );
+}
+
+#[tokio::test]
+async fn test_clear_fields_empties_fields_and_drops_results() {
+    let started = std::time::Instant::now();
+    let mut app = build_test_app_with_phase(
+        stdin_source(),
+        "foo",
+        SearchPhase::Complete {
+            started,
+            completed: started,
+        },
+        vec![dummy_result()],
+    );
+    app.search_fields = SearchFields::with_values(
+        &SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("bar", false),
+            fixed_strings: FieldValue::new(true, false),
+            include_files: FieldValue::new("*.rs", false),
+            ..Default::default()
+        },
+        false,
+    );
+
+    let result = app.handle_key_event(KeyEvent::new(
+        ScooterKeyCode::Char('c'),
+        ScooterKeyModifiers::ALT,
+    ));
+    assert!(result.is_rerender());
+
+    assert_eq!(
+        app.search_fields,
+        SearchFields::with_values(&SearchFieldValues::default(), false)
+    );
+    let state = search_fields_state(&app);
+    assert_eq!(state.focussed_section, FocussedSection::SearchFields);
+    assert!(state.search_state.is_none());
+    assert!(matches!(app.input_source, InputSource::Stdin(_)));
+}
+
 trait EventHandlingResultExt {
     fn is_rerender(&self) -> bool;
 }
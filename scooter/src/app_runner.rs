@@ -1,7 +1,7 @@
 use anyhow::Context;
 use crossterm::{
     event::{self, Event as CrosstermEvent},
-    style::Stylize as _,
+    style::{self, Stylize as _},
 };
 use futures::{Stream, StreamExt};
 use log::{LevelFilter, error};
@@ -13,13 +13,16 @@ use ratatui::{
 use scooter_core::line_reader::BufReadExt;
 use scooter_core::{
     app::{
-        App, AppRunConfig, Event, EventHandlingResult, ExitAndReplaceState, ExitState, InputSource,
+        App, AppRunConfig, BackgroundProcessingEvent, Event, EventHandlingResult,
+        ExitAndReplaceState, ExitState, InputSource, InternalEvent, Screen, SearchFieldsState,
     },
     config::{self, Config},
     errors::AppError,
     fields::SearchFieldValues,
+    file_content::default_file_content_provider,
     keyboard::KeyEvent,
-    replace::ReplaceState,
+    mouse::MouseEvent,
+    replace::{self, ReplaceState},
 };
 use scooter_core::{
     replace::ReplaceResult,
@@ -28,8 +31,8 @@ use scooter_core::{
 use std::{
     collections::HashMap,
     env,
-    fmt::Write as _,
-    io::{self, Cursor, Write},
+    fmt::{self, Write as _},
+    io::{self, Cursor, IsTerminal as _, Write},
     path::{Path, PathBuf},
     process::Command,
     str::FromStr,
@@ -37,30 +40,140 @@ use std::{
 };
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::{logging::DEFAULT_LOG_LEVEL, tui::Tui};
+use crate::{
+    logging::DEFAULT_LOG_LEVEL,
+    tui::Tui,
+    ui::cache::{self, PreviewCacheClearingProvider},
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct AppConfig<'a> {
-    pub directory: PathBuf,
+    /// Directories to search from, when not processing stdin
+    pub directories: Vec<PathBuf>,
     pub log_level: LevelFilter,
     pub search_field_values: SearchFieldValues<'a>,
     pub app_run_config: AppRunConfig,
     pub stdin_content: Option<String>,
     pub editor_command_override: Option<String>,
     pub interpret_escape_sequences_override: bool,
+    /// Suppress the summary printed after replacing, unless there were errors
+    pub quiet: bool,
+    /// File to write the processed stdin content to, rather than stderr
+    pub output_file: Option<PathBuf>,
+    /// Callbacks for hosts embedding scooter, called as searches and replacements progress
+    pub hooks: LifecycleHooks,
+    /// Whether the summary printed after replacing is styled with ANSI colours
+    pub color: ColorChoice,
+}
+
+/// Whether output printed to the terminal, such as the summary of replacements, is styled with ANSI colours
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Use colours if stdout is a terminal and the `NO_COLOR` environment variable isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
 }
 
+impl ColorChoice {
+    pub fn use_color(self) -> bool {
+        match self {
+            Self::Auto => {
+                io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+
+    /// Enables or disables colours in any styled text that is subsequently formatted. This should only be
+    /// called once the TUI has exited, as it also applies to the TUI
+    pub fn apply(self) {
+        style::force_color_output(self.use_color());
+    }
+}
+
+pub type ReplacementCompletedHook = Arc<dyn Fn(&ReplaceState) + Send + Sync>;
+
+/// Callbacks invoked at points in the lifecycle of a search and replacement, so that an
+/// application embedding scooter can react to them, for instance by updating a title bar
+#[derive(Clone, Default)]
+pub struct LifecycleHooks {
+    /// Called when a search starts, including each time the search is re-run after the search
+    /// fields change
+    pub search_started: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// Called with the number of results when a search completes
+    pub search_completed: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    /// Called when replacements have been performed, with their outcome
+    pub replacement_completed: Option<ReplacementCompletedHook>,
+}
+
+impl LifecycleHooks {
+    /// Adds a hook to be called when replacements have been performed, after any hook already set
+    pub fn add_replacement_completed(&mut self, hook: ReplacementCompletedHook) {
+        self.replacement_completed = Some(match self.replacement_completed.take() {
+            Some(existing) => Arc::new(move |replace_state| {
+                existing(replace_state);
+                hook(replace_state);
+            }),
+            None => hook,
+        });
+    }
+}
+
+impl fmt::Debug for LifecycleHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LifecycleHooks")
+            .field("search_started", &self.search_started.is_some())
+            .field("search_completed", &self.search_completed.is_some())
+            .field(
+                "replacement_completed",
+                &self.replacement_completed.is_some(),
+            )
+            .finish()
+    }
+}
+
+/// Hooks are equal if they call the same functions
+impl PartialEq for LifecycleHooks {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T: ?Sized>(a: Option<&Arc<T>>, b: Option<&Arc<T>>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+        }
+        same(self.search_started.as_ref(), other.search_started.as_ref())
+            && same(
+                self.search_completed.as_ref(),
+                other.search_completed.as_ref(),
+            )
+            && same(
+                self.replacement_completed.as_ref(),
+                other.replacement_completed.as_ref(),
+            )
+    }
+}
+
+impl Eq for LifecycleHooks {}
+
 impl Default for AppConfig<'_> {
     fn default() -> Self {
         Self {
-            directory: env::current_dir().unwrap(),
+            directories: vec![env::current_dir().unwrap()],
             log_level: LevelFilter::from_str(DEFAULT_LOG_LEVEL).unwrap(),
             search_field_values: SearchFieldValues::default(),
             app_run_config: AppRunConfig::default(),
             stdin_content: None,
             editor_command_override: None,
             interpret_escape_sequences_override: false,
+            quiet: false,
+            output_file: None,
+            hooks: LifecycleHooks::default(),
+            color: ColorChoice::default(),
         }
     }
 }
@@ -78,10 +191,15 @@ pub struct AppRunner<B: Backend, E: EventStream, S: SnapshotProvider<B>> {
     tui: Tui<B>,
     event_stream: E,
     snapshot_provider: S,
+    hooks: LifecycleHooks,
 }
 
 pub trait SnapshotProvider<B: Backend> {
     fn send_snapshot(&self, tui: &Tui<B>);
+
+    /// Handles a resize event. Real terminals are resized automatically on the next draw, but test
+    /// backends have a fixed size so must be resized to match
+    fn resize(&self, _tui: &mut Tui<B>, _width: u16, _height: u16) {}
 }
 
 pub struct NoOpSnapshotProvider;
@@ -123,6 +241,10 @@ impl SnapshotProvider<TestBackend> for TestSnapshotProvider {
             .join("");
         let _ = self.sender.send(contents);
     }
+
+    fn resize(&self, tui: &mut Tui<TestBackend>, width: u16, height: u16) {
+        tui.terminal.backend_mut().resize(width, height);
+    }
 }
 
 impl AppRunner<CrosstermBackend<io::Stdout>, CrosstermEventStream, NoOpSnapshotProvider> {
@@ -305,23 +427,31 @@ where
         let input_source = if let Some(stdin_content) = app_config.stdin_content {
             InputSource::Stdin(Arc::new(stdin_content))
         } else {
-            InputSource::Directory(app_config.directory.clone())
+            InputSource::Directory(app_config.directories.clone())
         };
 
-        let app = App::new(
+        cache::set_highlight_cache_limits(
+            config.preview.highlight_cache_entries,
+            config.preview.highlight_cache_lines,
+        );
+        let mut app = App::new(
             input_source,
             &app_config.search_field_values,
             app_config.app_run_config,
             config,
         )?;
+        app.set_file_content_provider(Arc::new(PreviewCacheClearingProvider::new(
+            default_file_content_provider(),
+        )));
         let terminal = Terminal::new(backend)?;
-        let tui = Tui::new(terminal);
+        let tui = Tui::new(terminal, app.config.display.mouse);
 
         Ok(Self {
             app,
             tui,
             event_stream,
             snapshot_provider,
+            hooks: app_config.hooks,
         })
     }
 
@@ -348,7 +478,14 @@ where
                             key_event.canonicalize();
                             self.app.handle_key_event(key_event)
                         },
-                        CrosstermEvent::Resize(_, _) => EventHandlingResult::Rerender,
+                        CrosstermEvent::Mouse(mouse) => match MouseEvent::from_crossterm(mouse) {
+                            Some(mouse_event) => self.app.handle_mouse_event(mouse_event),
+                            None => EventHandlingResult::None,
+                        },
+                        CrosstermEvent::Resize(width, height) => {
+                            self.snapshot_provider.resize(&mut self.tui, width, height);
+                            EventHandlingResult::Rerender
+                        }
                         _ => EventHandlingResult::None,
                     }
                 }
@@ -357,7 +494,11 @@ where
                         Event::LaunchEditor((file_path, line)) => {
                             let mut res = EventHandlingResult::Rerender;
                             self.tui.show_cursor()?;
-                            match self.open_editor(file_path, line) {
+                            match open_editor(
+                                self.app.config.editor_open.command.as_deref(),
+                                file_path,
+                                line,
+                            ) {
                                 Ok(()) => {
                                     if self.app.config.editor_open.exit {
                                         res = EventHandlingResult::Exit(None);
@@ -376,6 +517,31 @@ where
                             self.tui.init()?;
                             res
                         }
+                        Event::EditConfig => {
+                            self.tui.show_cursor()?;
+                            let res = config::create_config_file_if_missing().and_then(|path| {
+                                open_editor(self.app.config.editor_open.command.as_deref(), path, 1)
+                            });
+                            if let Err(e) = res {
+                                self.app.add_error(AppError {
+                                    name: "Failed to open config file".to_string(),
+                                    long: e.to_string(),
+                                });
+                                error!("Failed to open config file: {e}");
+                            }
+                            self.tui.init()?;
+                            EventHandlingResult::Rerender
+                        }
+                        Event::CopyToClipboard(text) => {
+                            if let Err(e) = Tui::<B>::copy_to_clipboard(&text) {
+                                self.app.add_error(AppError {
+                                    name: "Failed to copy to clipboard".to_string(),
+                                    long: e.to_string(),
+                                });
+                                error!("Failed to copy to clipboard: {e}");
+                            }
+                            EventHandlingResult::Rerender
+                        }
                         Event::ExitAndReplace(state) => {
                             return Ok(Some(ExitState::StdinState(state)));
                         }
@@ -383,7 +549,7 @@ where
                             EventHandlingResult::Rerender
                         }
                         Event::Internal(internal_event) => {
-                            self.app.handle_internal_event(internal_event)
+                            self.handle_internal_event(internal_event)
                         }
                     }
                 }
@@ -400,113 +566,150 @@ where
         }
     }
 
+    /// Passes the event to the app, calling any hooks for the lifecycle events it represents
+    fn handle_internal_event(&mut self, event: InternalEvent) -> EventHandlingResult {
+        match &event {
+            InternalEvent::Background(BackgroundProcessingEvent::SearchStarted) => {
+                if let Some(hook) = &self.hooks.search_started {
+                    hook();
+                }
+            }
+            InternalEvent::Background(BackgroundProcessingEvent::ReplacementCompleted(
+                replace_state,
+            )) => {
+                if let Some(hook) = &self.hooks.replacement_completed {
+                    hook(replace_state);
+                }
+            }
+            _ => {}
+        }
+        let search_completed = matches!(
+            event,
+            InternalEvent::Background(BackgroundProcessingEvent::SearchCompleted)
+        );
+
+        let result = self.app.handle_internal_event(event);
+
+        // Called once the app has handled the event, so that the search state is up to date
+        if search_completed
+            && let Some(hook) = &self.hooks.search_completed
+            && let Screen::SearchFields(SearchFieldsState {
+                search_state: Some(search_state),
+                ..
+            }) = &self.app.ui_state.current_screen
+        {
+            hook(search_state.results.len());
+        }
+        result
+    }
+
     pub fn cleanup(&mut self) -> anyhow::Result<()> {
         self.app.cancel_in_progress_tasks();
         self.tui.exit()
     }
+}
 
-    fn open_editor(&self, file_path: PathBuf, line: usize) -> anyhow::Result<()> {
-        match &self.app.config.editor_open.command {
-            Some(command) => {
-                Self::open_editor_from_command(command, &file_path, line)?;
-            }
-            None => {
-                Self::open_default_editor(file_path, line)?;
-            }
-        }
-        Ok(())
+/// Opens `file_path` at `line` using `editor_command` if set, or otherwise the editor set by `$EDITOR` or `$VISUAL`
+pub fn open_editor(
+    editor_command: Option<&str>,
+    file_path: PathBuf,
+    line: usize,
+) -> anyhow::Result<()> {
+    match editor_command {
+        Some(command) => open_editor_from_command(command, &file_path, line),
+        None => open_default_editor(file_path, line),
     }
+}
 
-    fn open_editor_from_command(
-        editor_command: &str,
-        file_path: &Path,
-        line: usize,
-    ) -> anyhow::Result<()> {
-        let editor_command = build_editor_command(editor_command, file_path, line);
-
-        let output = {
-            #[cfg(windows)]
-            {
-                use std::os::windows::process::CommandExt;
-                Command::new("cmd")
-                    .arg("/C")
-                    .raw_arg(&editor_command)
-                    .output()?
-            }
-            #[cfg(not(windows))]
-            {
-                Command::new("sh").arg("-c").arg(&editor_command).output()?
-            }
-        };
-
-        if output.status.success() {
-            Ok(())
-        } else {
-            let status_code = output
-                .status
-                .code()
-                .map_or("<not found>".to_owned(), |r| r.to_string());
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow::anyhow!(
-                "Failed to execute command\nStatus: {status_code}\nOutput: {stderr}",
-            ))
+fn open_editor_from_command(
+    editor_command: &str,
+    file_path: &Path,
+    line: usize,
+) -> anyhow::Result<()> {
+    let editor_command = build_editor_command(editor_command, file_path, line);
+
+    let output = {
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            Command::new("cmd")
+                .arg("/C")
+                .raw_arg(&editor_command)
+                .output()?
+        }
+        #[cfg(not(windows))]
+        {
+            Command::new("sh").arg("-c").arg(&editor_command).output()?
         }
+    };
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let status_code = output
+            .status
+            .code()
+            .map_or("<not found>".to_owned(), |r| r.to_string());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(anyhow::anyhow!(
+            "Failed to execute command\nStatus: {status_code}\nOutput: {stderr}",
+        ))
     }
+}
 
-    fn open_default_editor(file_path: PathBuf, line: usize) -> anyhow::Result<()> {
-        let editor = match env::var("EDITOR") {
+fn open_default_editor(file_path: PathBuf, line: usize) -> anyhow::Result<()> {
+    let editor = match env::var("EDITOR") {
+        Ok(val) if !val.trim().is_empty() => val,
+        _ => match env::var("VISUAL") {
             Ok(val) if !val.trim().is_empty() => val,
-            _ => match env::var("VISUAL") {
-                Ok(val) if !val.trim().is_empty() => val,
-                _ => {
-                    if cfg!(windows) {
-                        "notepad".to_string()
-                    } else {
-                        "vi".to_string()
-                    }
+            _ => {
+                if cfg!(windows) {
+                    "notepad".to_string()
+                } else {
+                    "vi".to_string()
                 }
-            },
-        };
+            }
+        },
+    };
 
-        let parts: Vec<&str> = editor.split_whitespace().collect();
-        let Some(program) = parts.first() else {
-            return Err(anyhow::anyhow!("Found empty editor command"));
-        };
-        let mut cmd = Command::new(program);
-        if parts.len() > 1 {
-            cmd.args(&parts[1..]);
-        }
+    let parts: Vec<&str> = editor.split_whitespace().collect();
+    let Some(program) = parts.first() else {
+        return Err(anyhow::anyhow!("Found empty editor command"));
+    };
+    let mut cmd = Command::new(program);
+    if parts.len() > 1 {
+        cmd.args(&parts[1..]);
+    }
 
-        let editor_name = Path::new(program)
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or(program)
-            .to_lowercase();
-        match editor_name.as_str() {
-            e if ["vi", "vim", "nvim", "kak", "nano"].contains(&e) => {
-                cmd.arg(format!("+{line}")).arg(file_path);
-            }
-            e if ["hx", "helix", "subl", "sublime_text", "zed"].contains(&e) => {
-                cmd.arg(format!("{}:{}", file_path.to_string_lossy(), line));
-            }
-            e if ["code", "code-insiders", "codium", "vscodium"].contains(&e) => {
-                cmd.arg("-g")
-                    .arg(format!("{}:{}", file_path.to_string_lossy(), line));
-            }
-            e if ["emacs", "emacsclient"].contains(&e) => {
-                cmd.arg(format!("+{line}:0")).arg(file_path);
-            }
-            "notepad++" => {
-                cmd.arg(file_path).arg(format!("-n{line}"));
-            }
-            _ => {
-                cmd.arg(file_path);
-            }
+    let editor_name = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+        .to_lowercase();
+    match editor_name.as_str() {
+        e if ["vi", "vim", "nvim", "kak", "nano"].contains(&e) => {
+            cmd.arg(format!("+{line}")).arg(file_path);
+        }
+        e if ["hx", "helix", "subl", "sublime_text", "zed"].contains(&e) => {
+            cmd.arg(format!("{}:{}", file_path.to_string_lossy(), line));
+        }
+        e if ["code", "code-insiders", "codium", "vscodium"].contains(&e) => {
+            cmd.arg("-g")
+                .arg(format!("{}:{}", file_path.to_string_lossy(), line));
+        }
+        e if ["emacs", "emacsclient"].contains(&e) => {
+            cmd.arg(format!("+{line}:0")).arg(file_path);
+        }
+        "notepad++" => {
+            cmd.arg(file_path).arg(format!("-n{line}"));
+        }
+        _ => {
+            cmd.arg(file_path);
         }
-
-        cmd.status()?;
-        Ok(())
     }
+
+    cmd.status()?;
+    Ok(())
 }
 
 pub fn format_replacement_results(
@@ -542,55 +745,62 @@ pub fn format_replacement_results(
 }
 
 pub async fn run_app_tui(app_config: AppConfig<'_>) -> anyhow::Result<Option<String>> {
+    let quiet = app_config.quiet;
+    let output_file = app_config.output_file.clone();
+    let color = app_config.color;
     let mut runner = AppRunner::new_runner(app_config)?;
     runner.init()?;
     let mut exit_state = runner.run_event_loop().await?;
     runner.cleanup()?;
+    color.apply();
 
     let stats = match exit_state {
         Some(ExitState::Stats(stats)) => Some(stats),
         Some(ExitState::StdinState(ref mut state)) => {
-            if runner.app.run_config.print_results {
-                let res = write_results_to_stderr_with_stats(state)?;
-                Some(res)
+            let print_results = runner.app.run_config.print_results;
+            if let Some(output_file) = &output_file {
+                let mut output = Vec::new();
+                let res = write_results_impl(state, print_results, &mut output)?;
+                replace::write_file_atomically(output_file, &output)?;
+                res
             } else {
-                write_results_to_stderr(state)?;
-                None
+                write_results_impl(state, print_results, &mut io::stderr())?
             }
         }
         None => {
             if runner.app.run_config.print_on_exit {
                 match runner.app.input_source {
-                    InputSource::Stdin(stdin) => write!(io::stderr(), "{stdin}")?,
+                    InputSource::Stdin(stdin) => {
+                        if let Some(output_file) = &output_file {
+                            replace::write_file_atomically(output_file, stdin.as_bytes())?;
+                        } else {
+                            write!(io::stderr(), "{stdin}")?;
+                        }
+                    }
                     InputSource::Directory(_) => unreachable!(),
                 }
             }
             None
         }
     }
-    .map(|stats| {
-        format_replacement_results(
+    .and_then(|stats| {
+        let results = format_replacement_results(
             stats.num_successes,
             Some(stats.num_ignored),
             Some(&stats.errors),
-        )
+        );
+        if !quiet {
+            Some(results)
+        } else {
+            if !stats.errors.is_empty() {
+                eprint!("{results}");
+            }
+            None
+        }
     });
     Ok(stats)
 }
 
-fn write_results_to_stderr(state: &mut ExitAndReplaceState) -> anyhow::Result<()> {
-    write_results_impl(state, false, &mut io::stderr()).map(|res| {
-        assert!(res.is_none(), "Found Some stats, expected None");
-    })
-}
-
-fn write_results_to_stderr_with_stats(
-    state: &mut ExitAndReplaceState,
-) -> anyhow::Result<ReplaceState> {
-    write_results_impl(state, true, &mut io::stderr())
-        .map(|res| res.expect("Found None stats, expected Some"))
-}
-
 // Used in integration tests
 #[allow(dead_code)]
 pub fn write_stdin_results(
@@ -618,6 +828,7 @@ fn write_results_impl(
             num_ignored,
             errors: Vec::new(),
             replacement_errors_pos: 0,
+            changed_paths: vec![],
         })
     } else {
         None
@@ -675,7 +886,13 @@ fn write_stdin_line_mode(
             if res.search_result.included {
                 res.replace_result = Some(ReplaceResult::Success);
                 num_successes += 1;
-                write!(writer, "{}{ending}", res.replacement)?;
+                if !replace::removes_line(
+                    &line_content,
+                    &res.replacement,
+                    state.search_config.delete_empty_lines,
+                ) {
+                    write!(writer, "{}{ending}", res.replacement)?;
+                }
             } else {
                 num_ignored += 1;
                 write!(writer, "{line_content}{ending}")?;
@@ -788,6 +1005,7 @@ mod tests {
             replacement: "replacement".to_string(),
             replace_result: Some(ReplaceResult::Error("Test error".to_string())),
             preview_error: None,
+            match_spans: vec![],
         };
 
         let result = format_replacement_results(3, Some(1), Some(&[error_result]));
@@ -798,6 +1016,12 @@ mod tests {
         assert!(result.contains("Test error"));
     }
 
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Always.use_color());
+        assert!(!ColorChoice::Never.use_color());
+    }
+
     #[test]
     fn test_format_replacement_results_no_ignored_count() {
         let result = format_replacement_results(7, None, Some(&[]));
@@ -0,0 +1,322 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::bail;
+use scooter_core::git;
+
+use crate::app_runner::ReplacementCompletedHook;
+
+/// Placeholder in a commit message that is replaced with the number of files changed
+const NUM_FILES_PLACEHOLDER: &str = "{num_files}";
+
+/// Records the files changed by replacements, so that they can be staged, and optionally
+/// committed, in git once scooter exits
+pub struct GitChanges {
+    commit_message: Option<String>,
+    changed_paths: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+impl GitChanges {
+    pub fn new(commit_message: Option<String>) -> Self {
+        Self {
+            commit_message,
+            changed_paths: Arc::default(),
+        }
+    }
+
+    /// A hook that records the files changed by each replacement performed
+    pub fn replacement_completed_hook(&self) -> ReplacementCompletedHook {
+        let changed_paths = Arc::clone(&self.changed_paths);
+        Arc::new(move |replace_state| {
+            changed_paths
+                .lock()
+                .unwrap()
+                .extend(replace_state.changed_paths.iter().cloned());
+        })
+    }
+
+    /// Records files changed other than through a replacement hook, such as by a run without the TUI
+    pub fn record(&self, paths: &[PathBuf]) {
+        self.changed_paths
+            .lock()
+            .unwrap()
+            .extend(paths.iter().cloned());
+    }
+
+    /// Stages the changed files in the git repositories containing them, and commits them to each
+    /// repository if there is a commit message. Returns a message describing what was done, which
+    /// explains why nothing was done if no files were changed or files aren't in a git repository.
+    /// Every repository is attempted even if staging or committing fails in another, and if any
+    /// fail the error describes what was done in each
+    pub fn apply(&self) -> anyhow::Result<String> {
+        let changed_paths = self.changed_paths.lock().unwrap().clone();
+        if changed_paths.is_empty() {
+            return Ok("No files were changed, so nothing was staged in git".to_owned());
+        }
+
+        let mut messages = vec![];
+        let (repositories, skipped) = group_by_repository(changed_paths);
+        if let Some(Skipped { num_paths, error }) = skipped {
+            messages.push(format!(
+                "Skipped staging {} in git: {error}",
+                num_files(num_paths)
+            ));
+        }
+        let num_repositories = repositories.len();
+        let mut failed = false;
+        for (repository, paths) in repositories {
+            let location = if num_repositories > 1 {
+                format!(" at {}", repository.display())
+            } else {
+                String::new()
+            };
+            let num_files = num_files(paths.len());
+            if let Err(e) = git::stage(&repository, &paths) {
+                messages.push(format!("Failed to stage {num_files} in git{location}: {e}"));
+                failed = true;
+                continue;
+            }
+            let Some(commit_message) = &self.commit_message else {
+                messages.push(format!("Staged {num_files} in git{location}"));
+                continue;
+            };
+            let commit_message =
+                commit_message.replace(NUM_FILES_PLACEHOLDER, &paths.len().to_string());
+            if let Err(e) = git::commit(&repository, &paths, &commit_message) {
+                messages.push(format!(
+                    "Staged {num_files} in git{location}, but failed to commit them: {e}"
+                ));
+                failed = true;
+                continue;
+            }
+            messages.push(format!("Committed {num_files} to git{location}"));
+        }
+        let message = messages.join("\n");
+        if failed {
+            bail!(message);
+        }
+        Ok(message)
+    }
+}
+
+/// Paths that aren't in a git repository
+struct Skipped {
+    num_paths: usize,
+    /// Why the first of the paths isn't in a git repository
+    error: anyhow::Error,
+}
+
+/// Groups `paths` by the root of the git repository containing each
+fn group_by_repository(
+    paths: BTreeSet<PathBuf>,
+) -> (BTreeMap<PathBuf, Vec<PathBuf>>, Option<Skipped>) {
+    let mut repositories = BTreeMap::<PathBuf, Vec<PathBuf>>::new();
+    let mut num_skipped = 0;
+    let mut first_error = None;
+    // Files usually share directories, so git is only run once per directory
+    let mut roots = HashMap::<PathBuf, Option<PathBuf>>::new();
+    for path in paths {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let root =
+            roots
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| match git::repository_root(dir) {
+                    Ok(root) => Some(root),
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                        None
+                    }
+                });
+        match root {
+            Some(root) => repositories.entry(root.clone()).or_default().push(path),
+            None => num_skipped += 1,
+        }
+    }
+    let skipped = first_error.map(|error| Skipped {
+        num_paths: num_skipped,
+        error,
+    });
+    (repositories, skipped)
+}
+
+fn num_files(num: usize) -> String {
+    format!("{num} file{}", if num == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use scooter_core::replace::ReplaceState;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn complete_replacement(hook: &ReplacementCompletedHook, changed_paths: Vec<PathBuf>) {
+        hook(&ReplaceState {
+            num_successes: changed_paths.len(),
+            num_ignored: 0,
+            errors: vec![],
+            replacement_errors_pos: 0,
+            changed_paths,
+        });
+    }
+
+    #[test]
+    fn test_commit_changes_from_each_replacement() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git(root, &["init", "-q"]);
+        git(root, &["config", "user.name", "scooter"]);
+        git(root, &["config", "user.email", "scooter@example.com"]);
+        for file in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(root.join(file), "foo\n").unwrap();
+        }
+
+        let git_changes = GitChanges::new(Some("Replace foo in {num_files} files".to_owned()));
+        let hook = git_changes.replacement_completed_hook();
+        complete_replacement(&hook, vec![root.join("a.txt"), root.join("b.txt")]);
+        complete_replacement(&hook, vec![root.join("b.txt")]);
+
+        assert_eq!(git_changes.apply().unwrap(), "Committed 2 files to git");
+        assert_eq!(
+            git(root, &["show", "--name-only", "--format=%s"]),
+            "Replace foo in 2 files\n\na.txt\nb.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_stage_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git(root, &["init", "-q"]);
+        fs::write(root.join("a.txt"), "foo\n").unwrap();
+
+        let git_changes = GitChanges::new(None);
+        assert_eq!(
+            git_changes.apply().unwrap(),
+            "No files were changed, so nothing was staged in git"
+        );
+
+        complete_replacement(
+            &git_changes.replacement_completed_hook(),
+            vec![root.join("a.txt")],
+        );
+        assert_eq!(git_changes.apply().unwrap(), "Staged 1 file in git");
+        assert_eq!(git(root, &["diff", "--cached", "--name-only"]), "a.txt\n");
+    }
+
+    #[test]
+    fn test_commit_to_each_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let repositories = ["first", "second"].map(|name| temp_dir.path().join(name));
+        for repository in &repositories {
+            fs::create_dir(repository).unwrap();
+            git(repository, &["init", "-q"]);
+            git(repository, &["config", "user.name", "scooter"]);
+            git(repository, &["config", "user.email", "scooter@example.com"]);
+            fs::create_dir(repository.join("src")).unwrap();
+        }
+        let [first, second] = &repositories;
+        let changed_paths = vec![
+            first.join("a.txt"),
+            first.join("src/b.txt"),
+            second.join("c.txt"),
+        ];
+        for path in &changed_paths {
+            fs::write(path, "foo\n").unwrap();
+        }
+
+        let git_changes = GitChanges::new(Some("Replace foo in {num_files} files".to_owned()));
+        git_changes.record(&changed_paths);
+
+        let [first, second] = repositories.map(|repository| repository.canonicalize().unwrap());
+        assert_eq!(
+            git_changes.apply().unwrap(),
+            format!(
+                "Committed 2 files to git at {}\nCommitted 1 file to git at {}",
+                first.display(),
+                second.display()
+            )
+        );
+        assert_eq!(
+            git(&first, &["show", "--name-only", "--format=%s"]),
+            "Replace foo in 2 files\n\na.txt\nsrc/b.txt\n"
+        );
+        assert_eq!(
+            git(&second, &["show", "--name-only", "--format=%s"]),
+            "Replace foo in 1 files\n\nc.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_failure_in_one_repository_does_not_stop_others() {
+        let temp_dir = TempDir::new().unwrap();
+        let repositories = ["first", "second"].map(|name| temp_dir.path().join(name));
+        for repository in &repositories {
+            fs::create_dir(repository).unwrap();
+            git(repository, &["init", "-q"]);
+            git(repository, &["config", "user.name", "scooter"]);
+            git(repository, &["config", "user.email", "scooter@example.com"]);
+        }
+        let [first, second] = &repositories;
+        // The file in the first repository doesn't exist, so can't be staged
+        fs::write(second.join("b.txt"), "foo\n").unwrap();
+
+        let git_changes = GitChanges::new(Some("Replace foo".to_owned()));
+        git_changes.record(&[first.join("a.txt"), second.join("b.txt")]);
+
+        let [first, second] = repositories.map(|repository| repository.canonicalize().unwrap());
+        let message = git_changes.apply().unwrap_err().to_string();
+        let (first_message, second_message) = message.split_once('\n').unwrap();
+        assert!(
+            first_message.starts_with(&format!(
+                "Failed to stage 1 file in git at {}: ",
+                first.display()
+            )),
+            "unexpected message: {message}"
+        );
+        assert_eq!(
+            second_message,
+            format!("Committed 1 file to git at {}", second.display())
+        );
+        assert_eq!(
+            git(&second, &["show", "--name-only", "--format=%s"]),
+            "Replace foo\n\nb.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_skipped_outside_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let git_changes = GitChanges::new(None);
+        complete_replacement(
+            &git_changes.replacement_completed_hook(),
+            vec![root.join("a.txt")],
+        );
+
+        let message = git_changes.apply().unwrap();
+        assert!(
+            message.starts_with("Skipped staging 1 file in git: ")
+                && message.contains("not a git repository"),
+            "unexpected message: {message}"
+        );
+    }
+}
@@ -1,18 +1,124 @@
+use std::path::Path;
+
+use crossterm::style::Stylize as _;
 use scooter_core::{
-    run,
+    jobs::Job,
+    replace::SymlinkHandling,
+    replacement_pairs::ReplacementPair,
+    run::{self, FilesUpdated, HeadlessResults, JobsResults},
+    search::LineFilter,
+    stdin_format::StdinFormat,
     validation::{DirConfig, SearchConfig},
 };
 
 pub fn run_headless(
     search_config: SearchConfig<'_>,
     dir_config: DirConfig<'_>,
-) -> anyhow::Result<String> {
-    run::find_and_replace(search_config, dir_config)
+    line_filter: &LineFilter,
+) -> anyhow::Result<HeadlessResults> {
+    run::find_and_replace(search_config, dir_config, line_filter)
+}
+
+pub fn run_headless_in_shadow(
+    search_config: SearchConfig<'_>,
+    dir_config: DirConfig<'_>,
+    line_filter: &LineFilter,
+    shadow_dir: &Path,
+) -> anyhow::Result<HeadlessResults> {
+    run::find_and_replace_in_shadow(search_config, dir_config, line_filter, shadow_dir)
 }
 
 pub fn run_headless_with_stdin(
     stdin_content: &str,
     search_config: SearchConfig<'_>,
+    line_filter: &LineFilter,
 ) -> anyhow::Result<String> {
-    run::find_and_replace_text(stdin_content, search_config)
+    run::find_and_replace_text(stdin_content, search_config, line_filter)
+}
+
+pub fn run_headless_with_framed_stdin(
+    stdin_content: &str,
+    format: StdinFormat,
+    search_config: SearchConfig<'_>,
+    line_filter: &LineFilter,
+    symlinks: SymlinkHandling,
+) -> anyhow::Result<HeadlessResults> {
+    run::find_and_replace_framed_text(stdin_content, format, search_config, line_filter, symlinks)
+}
+
+pub fn run_headless_with_pairs(
+    pairs: &[ReplacementPair],
+    search_config: &SearchConfig<'_>,
+    dir_config: &DirConfig<'_>,
+    line_filter: &LineFilter,
+) -> anyhow::Result<HeadlessResults> {
+    run::find_and_replace_pairs(pairs, search_config, dir_config, line_filter)
+}
+
+pub fn run_headless_with_stdin_and_pairs(
+    stdin_content: &str,
+    pairs: &[ReplacementPair],
+    search_config: &SearchConfig<'_>,
+    line_filter: &LineFilter,
+) -> anyhow::Result<String> {
+    run::find_and_replace_text_pairs(stdin_content, pairs, search_config, line_filter)
+}
+
+pub fn run_headless_jobs(
+    jobs: &[Job],
+    search_config: &SearchConfig<'_>,
+    dir_config: &DirConfig<'_>,
+    line_filter: &LineFilter,
+    stop_on_error: bool,
+) -> anyhow::Result<JobsResults> {
+    run::find_and_replace_jobs(jobs, search_config, dir_config, line_filter, stop_on_error)
+}
+
+/// Formats `results` as the summary printed without the TUI. If `color` is set, the outcome, any
+/// errors and the lines of the diff from `--shadow` are styled with ANSI colours
+pub fn format_headless_results(results: &HeadlessResults, color: bool) -> String {
+    let summary = results.to_string();
+    if !color {
+        return summary;
+    }
+
+    let has_diff =
+        matches!(&results.files_updated, FilesUpdated::Shadow { diff, .. } if !diff.is_empty());
+    let mut styled = String::with_capacity(summary.len());
+    let mut in_errors = false;
+    let mut in_diff = false;
+    for line in summary.split_inclusive('\n') {
+        let (content, ending) = line
+            .strip_suffix('\n')
+            .map_or((line, ""), |content| (content, "\n"));
+        let content = if content.is_empty() {
+            // The diff is separated from the rest of the summary by a blank line
+            in_diff = has_diff;
+            String::new()
+        } else if in_diff {
+            if content.starts_with("--- ") || content.starts_with("+++ ") {
+                content.bold().to_string()
+            } else if content.starts_with("@@") {
+                content.cyan().to_string()
+            } else if content.starts_with('+') {
+                content.green().to_string()
+            } else if content.starts_with('-') {
+                content.red().to_string()
+            } else {
+                content.to_owned()
+            }
+        } else if content.starts_with("Success") {
+            content.green().to_string()
+        } else if content.starts_with("Errors: ") {
+            in_errors = true;
+            content.red().to_string()
+        } else if in_errors && content.starts_with("  ") {
+            content.red().to_string()
+        } else {
+            content.to_owned()
+        };
+        styled.push_str(&content);
+        styled.push_str(ending);
+    }
+    styled
 }
@@ -1,26 +1,49 @@
-use anyhow::bail;
+use anyhow::{Context, bail};
 use clap::Parser;
 use log::LevelFilter;
-use scooter_core::validation::{DirConfig, SearchConfig};
+use regex::Regex;
+use scooter_core::{
+    jobs::{self, Job},
+    replacement_pairs::{self, ReplacementPair},
+    run::HeadlessResults,
+    search::{ColumnRange, LineFilter, LineRanges},
+    validation::{DirConfig, SearchConfig},
+};
 use std::{
+    collections::HashSet,
+    fmt::Write,
+    fs,
     io::{self, IsTerminal, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
+    sync::Arc,
+    time::SystemTime,
 };
 
 use scooter_core::{
     app::AppRunConfig,
-    config,
-    fields::{FieldValue, SearchFieldValues},
+    config::{self, Config},
+    fields::{FieldName, FieldValue, SearchFieldValues},
+    git, modified_since,
+    presets::{self, Preset},
+    replace::{self, SymlinkHandling},
+    stdin_format::StdinFormat,
+    utils,
 };
 
-use app_runner::{AppConfig, run_app_tui};
-use headless::{run_headless, run_headless_with_stdin};
+use app_runner::{AppConfig, ColorChoice, LifecycleHooks, open_editor, run_app_tui};
+use git_changes::GitChanges;
 use logging::{DEFAULT_LOG_LEVEL, setup_logging};
+use scooter::headless::{
+    format_headless_results, run_headless, run_headless_in_shadow, run_headless_jobs,
+    run_headless_with_framed_stdin, run_headless_with_pairs, run_headless_with_stdin,
+    run_headless_with_stdin_and_pairs,
+};
 
 mod app_runner;
-mod headless;
+mod git_changes;
 mod logging;
+mod notification;
 mod tui;
 mod ui;
 
@@ -29,9 +52,11 @@ mod ui;
 #[command(version)]
 #[allow(clippy::struct_excessive_bools)]
 struct Args {
-    /// Directory in which to search
+    /// Directories in which to search. Each is searched separately, so several sibling directories
+    /// can be searched without searching their common parent. Directories within another of those
+    /// given are skipped, as they are already searched as part of it
     #[arg(index = 1, value_parser = parse_search_dir, default_value = ".")]
-    directory: PathBuf,
+    directories: Vec<PathBuf>,
 
     /// Include hidden files and directories, such as those whose name starts with a dot (.)
     #[arg(short = '.', long, action = clap::ArgAction::SetTrue)]
@@ -41,6 +66,27 @@ struct Args {
     #[arg(long, action = clap::ArgAction::SetTrue)]
     include_git_folders: bool,
 
+    /// Search files that look like binary files (by extension or content) rather than skipping them.
+    /// They are read as UTF-8, skipping lines that aren't valid UTF-8. Use with care: replacing text
+    /// in a binary file can corrupt it
+    #[arg(long, visible_alias = "no-binary-filter", action = clap::ArgAction::SetTrue)]
+    text: bool,
+
+    /// How many levels of directories to descend into, where 1 searches only the files directly within
+    /// the searched directory. The number of directories skipped is included in the summary
+    #[arg(long, value_parser = parse_max_depth)]
+    max_depth: Option<usize>,
+
+    /// Only search files modified since this time: either a duration before now, such as `30m`, `12h`,
+    /// `2d` or `1w`, or a UTC date or time, such as `2024-01-31` or `2024-01-31T09:30:00Z`
+    #[arg(long, value_parser = parse_modified_since)]
+    modified_since: Option<SystemTime>,
+
+    /// Only search files that are tracked by git, as listed by `git ls-files`, so that untracked files are
+    /// neither searched nor replaced in. Each searched directory must be within a git repository
+    #[arg(long)]
+    git_tracked_only: bool,
+
     /// Log level (trace, debug, info, warn, error)
     #[arg(
         long,
@@ -57,7 +103,8 @@ struct Args {
     #[arg(short = 'U', long)]
     multiline: bool,
 
-    /// Interpret escape sequences in replacement text (\n becomes newline, \t becomes tab, \\ becomes backslash)
+    /// Interpret escape sequences in replacement text (\n becomes newline, \t becomes tab, \\ becomes backslash,
+    /// \u{200B} becomes a zero-width space)
     #[arg(short = 'e', long)]
     interpret_escape_sequences: bool,
 
@@ -81,10 +128,97 @@ struct Args {
     #[arg(short = 'N', long)]
     no_tui: bool,
 
+    /// When to style the printed summary, and the diff printed with `--shadow`, with colours: `auto`
+    /// (default) uses colours if stdout is a terminal and `NO_COLOR` isn't set, while `always` and
+    /// `never` override this
+    #[arg(long, value_name = "WHEN", value_parser = parse_color_choice, default_value = "auto")]
+    color: ColorChoice,
+
+    /// Don't print a summary of replacements to stdout when using `--no-tui`, `--immediate` or `--print-results`.
+    /// Errors are still reported on stderr
+    #[arg(short = 'q', long)]
+    quiet: bool,
+
     /// Override the config directory (default: ~/.config/scooter on Linux/macOS, %AppData%\scooter on Windows)
     #[arg(short = 'c', long, value_parser = parse_config_dir)]
     config_dir: Option<PathBuf>,
 
+    /// Use the named profile from the config file, overriding its `default_profile`
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Fill in the search fields from the named preset, as saved from the search screen. Flags passed
+    /// alongside it take precedence over the preset's values
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Print the config that would be used, including default values and any profile, then exit
+    /// without searching. Respects `--config-dir`, `--profile` and `--no-local-config`
+    #[arg(long)]
+    print_config: bool,
+
+    /// Open the config file in the editor, creating it with the default options commented out if it
+    /// doesn't exist, then exit without searching. Respects `--config-dir` and `--editor-command`
+    #[arg(long)]
+    edit_config: bool,
+
+    /// Create a config file containing every option, documented and commented out, with its default value,
+    /// then exit without searching. Fails if the config file already exists. Respects `--config-dir`
+    #[arg(long)]
+    init_config: bool,
+
+    /// Ignore any `.scooter.toml` files in the searched directory and its ancestors, which otherwise
+    /// override the options in the global config file. When several directories are searched, these
+    /// are looked for from the nearest directory containing them all
+    #[arg(long)]
+    no_local_config: bool,
+
+    /// List the files that would be searched before searching, so that they can be approved or
+    /// rejected individually. Only the approved files are then searched
+    #[arg(long)]
+    interactive_filter: bool,
+
+    /// Don't show results whose replacement would leave the line unchanged, such as when replacing
+    /// with a back-reference to the matched text. The number of results skipped is shown above the results
+    #[arg(long)]
+    skip_noop: bool,
+
+    /// Once the search completes, include only the Nth result in the replacement and exclude the rest,
+    /// where results are ordered by file path and then line number
+    #[arg(long, value_name = "N", value_parser = parse_occurrence)]
+    occurrence: Option<usize>,
+
+    /// Include every result in the replacement as it is found. This is the default, so this only
+    /// states it explicitly, e.g. in scripts
+    #[arg(long)]
+    select_all: bool,
+
+    /// Exclude every result from the replacement as it is found, so that results can then be
+    /// included individually
+    #[arg(long)]
+    select_none: bool,
+
+    /// Glob patterns, separated by commas (,) and matched like `--files-to-include`, that a result's file must
+    /// match for the result to be included in the replacement when it is found. Results from other files are
+    /// still shown but are excluded, so that they can be included individually
+    #[arg(long, value_name = "GLOBS", value_parser = parse_auto_include)]
+    auto_include: Option<String>,
+
+    /// Show a desktop notification, summarising the replacements made, once replacement completes.
+    /// Uses `notify-send` on Linux and `osascript` on macOS, and does nothing with `--no-tui`
+    #[arg(long)]
+    notify: bool,
+
+    /// Stage the files changed by replacements with `git add`, in the git repository containing each,
+    /// when scooter exits. Files that aren't in a git repository are skipped with a message
+    #[arg(long)]
+    git_stage: bool,
+
+    /// Stage the files changed by replacements and commit them, and only them, with this message when
+    /// scooter exits. Any `{num_files}` in the message is replaced with the number of files changed
+    #[arg(long, value_name = "MESSAGE")]
+    git_commit: Option<String>,
+
     /// Override stdin detection, forcing scooter to process files rather reading from stdin
     #[arg(long)]
     no_stdin: bool,
@@ -93,6 +227,25 @@ struct Args {
     #[arg(long)]
     print_on_exit: bool,
 
+    /// Write the processed stdin content to this file rather than printing it. The file can safely be
+    /// the same one that was piped in, as it is replaced only once the new content has been written
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// How to interpret stdin content: `plain` (default) treats it as a single blob of text, while `headers`
+    /// (`==> path <==` markers, as output by `head`, with each section starting at line 1 of its file), `grep` (`path:line:content` lines, as output by
+    /// `grep -rn`) and `rg-json` (the output of `rg --json`) map each line back to the file it came from, so
+    /// that replacements are made in those files. With `rg-json`, the search text can be left empty to replace
+    /// the matches ripgrep found
+    #[arg(long, value_parser = parse_stdin_format, default_value = "plain")]
+    stdin_format: StdinFormat,
+
+    /// What to do when a file referenced by `--stdin-format` is a symbolic link: `follow` (default) makes the
+    /// replacements in the file that the link points to, leaving the link in place, while `skip` leaves the file
+    /// unchanged and reports an error for each of its results
+    #[arg(long, value_parser = parse_symlinks, default_value = "follow")]
+    symlinks: SymlinkHandling,
+
     /// Override the editor command for opening files (overrides config file setting). Use %file and %line as placeholders.
     #[arg(long)]
     editor_command: Option<String>,
@@ -107,6 +260,17 @@ struct Args {
     #[arg(short = 'r', long)]
     replace_text: Option<String>,
 
+    /// Wrap each match with a prefix and suffix, replacing it with a template in which `{}` is the
+    /// whole match, such as `/* {} */`. Works with fixed strings as well as regex
+    #[arg(long, value_name = "TEMPLATE")]
+    wrap: Option<String>,
+
+    /// Remove lines that are left empty by the replacement, rather than leaving a blank line. Lines
+    /// that still contain text after the matches are replaced, such as whitespace before a match,
+    /// are kept
+    #[arg(long)]
+    delete_empty_result_lines: bool,
+
     /// Search with plain strings, rather than regex
     #[arg(short, long, action = clap::ArgAction::SetTrue)]
     fixed_strings: bool,
@@ -115,23 +279,165 @@ struct Args {
     #[arg(short = 'w', long, action = clap::ArgAction::SetTrue)]
     match_whole_word: bool,
 
-    /// Ignore case when matching the search string
+    /// Ignore case when matching the search string. The replacement is still written exactly as typed
     #[arg(short = 'i', long, action = clap::ArgAction::SetTrue)]
     case_insensitive: bool,
 
-    /// Glob patterns, separated by commas (,), that file paths must match
+    /// Glob patterns, separated by commas (,), that file paths must match. Patterns without a `/`
+    /// match the file name at any depth, while patterns containing a `/` are anchored to the searched directory
     #[arg(short = 'I', long)]
     files_to_include: Option<String>,
 
-    /// Glob patterns, separated by commas (,), that file paths must not match
+    /// Glob patterns, separated by commas (,), that file paths must not match. Follows the same rules
+    /// as `--files-to-include`
     #[arg(short = 'E', long)]
     files_to_exclude: Option<String>,
+
+    /// Path to a CSV file in which each row is a search text and its replacement, e.g. `old,new`.
+    /// Each pair is applied in turn, in the order of the file, so later pairs apply to the output of
+    /// earlier ones. Other search flags, such as `--fixed-strings`, apply to every pair. Requires `--no-tui`
+    #[arg(long)]
+    replace_from_csv: Option<PathBuf>,
+
+    /// Path to a TOML file of jobs to run one after the other, each with its own search text,
+    /// replacement and globs, given as `[[jobs]]` tables with `search`, `replace`, `files_to_include`
+    /// and `files_to_exclude` keys. A summary of each job is printed once complete. Requires `--no-tui`
+    #[arg(long, value_name = "PATH")]
+    jobs: Option<PathBuf>,
+
+    /// Stop running jobs from `--jobs` once a job has replacements that couldn't be made, rather than
+    /// continuing with the remaining jobs
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Rather than replacing in the original files, copy the files with matches into this directory,
+    /// which must be empty or not yet exist, and replace in the copies, so that the changes can be
+    /// inspected first. The summary is followed by a diff of each original against its copy. Requires `--no-tui`
+    #[arg(long)]
+    shadow: Option<PathBuf>,
+
+    /// Rather than a summary, print the path of each file in which replacements were made, one per line,
+    /// e.g. to pass them on to a formatter with `xargs`. Any errors are still printed, to stderr.
+    /// Requires `--no-tui`
+    #[arg(long)]
+    print_changed_files: bool,
+
+    /// Field to focus when the search fields are first shown: `search`, `replace`, `include` or `exclude`
+    #[arg(long, value_parser = parse_focus_field)]
+    focus: Option<FieldName>,
+
+    /// Regex that lines must also match for matches on them to be replaced, e.g. `--require 'fn '` to
+    /// only replace on lines containing function definitions
+    #[arg(long, value_parser = parse_regex)]
+    require: Option<Regex>,
+
+    /// Regex that excludes matching lines from being replaced, e.g. `--exclude-line '^\s*//'` to skip
+    /// commented-out code. Takes precedence over `--require`
+    #[arg(long, value_parser = parse_regex)]
+    exclude_line: Option<Regex>,
+
+    /// Only replace matches that start within this range of columns, given as `START:END` with columns
+    /// counted in characters from 1, e.g. `--columns 1:4`. Either bound can be left out, as in `:4` or `10:`.
+    /// Other matches on the same line are left unchanged
+    #[arg(long, value_name = "START:END", value_parser = parse_columns)]
+    columns: Option<ColumnRange>,
+
+    /// Only search the given lines of the given files, read from a file with one `PATH:RANGES` entry per line,
+    /// where `RANGES` is a comma-separated list of line numbers and inclusive ranges, e.g. `src/main.rs:10-20,40`.
+    /// Files that aren't listed aren't searched. Useful for fixing issues reported by a linter
+    #[arg(long, value_name = "FILE", value_parser = parse_line_ranges)]
+    line_ranges: Option<Arc<LineRanges>>,
 }
 
 fn parse_log_level(s: &str) -> Result<LevelFilter, String> {
     LevelFilter::from_str(s).map_err(|_| format!("Invalid log level: {s}"))
 }
 
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| format!("Invalid regex: {e}"))
+}
+
+fn parse_columns(s: &str) -> Result<ColumnRange, String> {
+    s.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+fn parse_line_ranges(s: &str) -> Result<Arc<LineRanges>, String> {
+    LineRanges::from_file(Path::new(s))
+        .map(Arc::new)
+        .map_err(|e| format!("{e:#}"))
+}
+
+fn parse_auto_include(s: &str) -> Result<String, String> {
+    utils::build_overrides(&[PathBuf::from(".")], s)
+        .map(|_| s.to_owned())
+        .map_err(|e| format!("Invalid glob: {e}"))
+}
+
+fn parse_max_depth(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Max depth must be at least 1".to_owned()),
+        Ok(max_depth) => Ok(max_depth),
+        Err(e) => Err(format!("Invalid max depth: {e}")),
+    }
+}
+
+fn parse_occurrence(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Occurrence must be at least 1".to_owned()),
+        Ok(occurrence) => Ok(occurrence),
+        Err(e) => Err(format!("Invalid occurrence: {e}")),
+    }
+}
+
+fn parse_modified_since(s: &str) -> Result<SystemTime, String> {
+    modified_since::parse_modified_since(s, SystemTime::now()).map_err(|e| e.to_string())
+}
+
+fn parse_focus_field(s: &str) -> Result<FieldName, String> {
+    match s {
+        "search" => Ok(FieldName::Search),
+        "replace" => Ok(FieldName::Replace),
+        "include" => Ok(FieldName::IncludeFiles),
+        "exclude" => Ok(FieldName::ExcludeFiles),
+        _ => Err(format!(
+            "Invalid field: {s} (expected one of search, replace, include, exclude)"
+        )),
+    }
+}
+
+fn parse_stdin_format(s: &str) -> Result<StdinFormat, String> {
+    match s {
+        "plain" => Ok(StdinFormat::Plain),
+        "headers" => Ok(StdinFormat::Headers),
+        "grep" => Ok(StdinFormat::Grep),
+        "rg-json" => Ok(StdinFormat::RgJson),
+        _ => Err(format!(
+            "Invalid stdin format: {s} (expected one of plain, headers, grep, rg-json)"
+        )),
+    }
+}
+
+fn parse_symlinks(s: &str) -> Result<SymlinkHandling, String> {
+    match s {
+        "follow" => Ok(SymlinkHandling::Follow),
+        "skip" => Ok(SymlinkHandling::Skip),
+        _ => Err(format!(
+            "Invalid symlink handling: {s} (expected one of follow, skip)"
+        )),
+    }
+}
+
+fn parse_color_choice(s: &str) -> Result<ColorChoice, String> {
+    match s {
+        "auto" => Ok(ColorChoice::Auto),
+        "always" => Ok(ColorChoice::Always),
+        "never" => Ok(ColorChoice::Never),
+        _ => Err(format!(
+            "Invalid color choice: {s} (expected one of auto, always, never)"
+        )),
+    }
+}
+
 fn parse_search_dir(dir: &str) -> anyhow::Result<PathBuf> {
     let path = PathBuf::from(dir);
     if path.exists() {
@@ -149,11 +455,167 @@ fn parse_config_dir(dir: &str) -> anyhow::Result<PathBuf> {
     Ok(path)
 }
 
+#[allow(clippy::too_many_lines)]
 fn validate_flag_combinations(args: &Args) -> anyhow::Result<()> {
     if args.no_tui && args.immediate {
         bail!("--no-tui cannot be combined with --immediate");
     }
 
+    if args.quiet && !(args.no_tui || args.immediate || args.print_results) {
+        bail!("--quiet requires --no-tui, --immediate or --print-results");
+    }
+
+    if args.focus.is_some() {
+        for (name, enabled) in [
+            ("--no-tui", args.no_tui),
+            ("--immediate", args.immediate),
+            ("--immediate-search", args.immediate_search),
+        ] {
+            if enabled {
+                bail!("--focus cannot be combined with {name}");
+            }
+        }
+    }
+
+    if args.interactive_filter {
+        for (name, enabled) in [
+            ("--no-tui", args.no_tui),
+            ("--immediate", args.immediate),
+            ("--immediate-search", args.immediate_search),
+        ] {
+            if enabled {
+                bail!("--interactive-filter cannot be combined with {name}");
+            }
+        }
+    }
+
+    if args.replace_from_csv.is_some() {
+        if !args.no_tui {
+            bail!("--replace-from-csv requires --no-tui");
+        }
+        for (name, enabled) in [
+            ("--search-text", args.search_text.is_some()),
+            ("--replace-text", args.replace_text.is_some()),
+            ("--wrap", args.wrap.is_some()),
+            ("--stdin-format", args.stdin_format.is_framed()),
+        ] {
+            if enabled {
+                bail!("--replace-from-csv cannot be combined with {name}");
+            }
+        }
+    }
+
+    if args.jobs.is_some() {
+        if !args.no_tui {
+            bail!("--jobs requires --no-tui");
+        }
+        for (name, enabled) in [
+            ("--search-text", args.search_text.is_some()),
+            ("--replace-text", args.replace_text.is_some()),
+            ("--wrap", args.wrap.is_some()),
+            ("--files-to-include", args.files_to_include.is_some()),
+            ("--files-to-exclude", args.files_to_exclude.is_some()),
+            ("--replace-from-csv", args.replace_from_csv.is_some()),
+            ("--shadow", args.shadow.is_some()),
+            ("--stdin-format", args.stdin_format.is_framed()),
+            ("--print-changed-files", args.print_changed_files),
+            ("--git-stage", args.git_stage),
+            ("--git-commit", args.git_commit.is_some()),
+        ] {
+            if enabled {
+                bail!("--jobs cannot be combined with {name}");
+            }
+        }
+    } else if args.fail_fast {
+        bail!("--fail-fast requires --jobs");
+    }
+
+    if args.print_changed_files && !args.no_tui {
+        bail!("--print-changed-files requires --no-tui");
+    }
+
+    if args.shadow.is_some() {
+        if !args.no_tui {
+            bail!("--shadow requires --no-tui");
+        }
+        // Shadow copies aren't in the git repositories of the files they copy
+        for (name, enabled) in [
+            ("--replace-from-csv", args.replace_from_csv.is_some()),
+            ("--git-stage", args.git_stage),
+            ("--git-commit", args.git_commit.is_some()),
+        ] {
+            if enabled {
+                bail!("--shadow cannot be combined with {name}");
+            }
+        }
+    }
+
+    if args.wrap.is_some() && args.replace_text.is_some() {
+        bail!("--wrap cannot be combined with --replace-text");
+    }
+
+    if args.skip_noop && args.no_tui {
+        bail!("--skip-noop cannot be combined with --no-tui");
+    }
+
+    if args.occurrence.is_some() && args.no_tui {
+        bail!("--occurrence cannot be combined with --no-tui");
+    }
+
+    if args.auto_include.is_some() {
+        for (name, enabled) in [
+            ("--select-all", args.select_all),
+            ("--select-none", args.select_none),
+            ("--no-tui", args.no_tui),
+        ] {
+            if enabled {
+                bail!("--auto-include cannot be combined with {name}");
+            }
+        }
+    }
+
+    if args.select_none {
+        for (name, enabled) in [
+            ("--select-all", args.select_all),
+            ("--no-tui", args.no_tui),
+            ("--immediate", args.immediate),
+            ("--immediate-replace", args.immediate_replace),
+            ("--occurrence", args.occurrence.is_some()),
+        ] {
+            if enabled {
+                bail!("--select-none cannot be combined with {name}");
+            }
+        }
+    }
+
+    if args.multiline {
+        for (name, enabled) in [
+            ("--require", args.require.is_some()),
+            ("--exclude-line", args.exclude_line.is_some()),
+            ("--columns", args.columns.is_some()),
+            ("--line-ranges", args.line_ranges.is_some()),
+            ("--stdin-format", args.stdin_format.is_framed()),
+            (
+                "--delete-empty-result-lines",
+                args.delete_empty_result_lines,
+            ),
+        ] {
+            if enabled {
+                bail!("{name} cannot be combined with --multiline");
+            }
+        }
+    }
+
+    if args.symlinks != SymlinkHandling::default() && !args.stdin_format.is_framed() {
+        bail!("--symlinks requires --stdin-format");
+    }
+
+    if args.stdin_format.is_framed() && args.output.is_some() {
+        bail!(
+            "--output cannot be combined with --stdin-format, as replacements are made in the referenced files"
+        );
+    }
+
     if args.immediate_search || args.immediate_replace || args.print_results {
         for (name, enabled) in [("--no-tui", args.no_tui), ("--immediate", args.immediate)] {
             if enabled {
@@ -168,7 +630,11 @@ fn validate_flag_combinations(args: &Args) -> anyhow::Result<()> {
 }
 
 fn validate_search_text_required(args: &Args) -> anyhow::Result<()> {
-    if args.search_text.as_ref().is_none_or(String::is_empty) {
+    if args.search_text.as_ref().is_none_or(String::is_empty)
+        && args.replace_from_csv.is_none()
+        && args.jobs.is_none()
+        && args.stdin_format != StdinFormat::RgJson
+    {
         for (name, enabled) in [
             ("--immediate-search", args.immediate_search),
             ("--immediate", args.immediate),
@@ -188,10 +654,21 @@ fn detect_and_read_stdin(args: &Args) -> anyhow::Result<Option<String>> {
         return Ok(None);
     }
 
-    let mut stdin_content = String::new();
-    io::stdin().read_to_string(&mut stdin_content)?;
+    let mut stdin_content = vec![];
+    io::stdin().read_to_end(&mut stdin_content)?;
+
+    stdin_text(stdin_content).map(Some)
+}
 
-    Ok(Some(stdin_content))
+/// Converts the content read from stdin to text, failing with the first line that isn't valid UTF-8
+fn stdin_text(content: Vec<u8>) -> anyhow::Result<String> {
+    String::from_utf8(content).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        let line_number = valid.split(|&b| b == b'\n').count();
+        anyhow::anyhow!(
+            "Line {line_number} of stdin isn't valid UTF-8, so stdin can't be processed"
+        )
+    })
 }
 
 fn validate_stdin_usage(args: &Args, stdin_content: Option<&str>) -> anyhow::Result<()> {
@@ -203,18 +680,75 @@ fn validate_stdin_usage(args: &Args, stdin_content: Option<&str>) -> anyhow::Res
         if args.include_git_folders {
             bail!("Cannot use --include-git-folders flag when processing stdin");
         }
+        if args.text {
+            bail!("Cannot use --text flag when processing stdin");
+        }
+        if args.max_depth.is_some() {
+            bail!("Cannot use --max-depth when processing stdin");
+        }
+        if args.modified_since.is_some() {
+            bail!("Cannot use --modified-since when processing stdin");
+        }
+        if args.git_tracked_only {
+            bail!("Cannot use --git-tracked-only when processing stdin");
+        }
+        if args.shadow.is_some() {
+            bail!("Cannot use --shadow when processing stdin");
+        }
+        if args.jobs.is_some() {
+            bail!("Cannot use --jobs when processing stdin");
+        }
         if args.files_to_include.is_some() {
             bail!("Cannot use --files-to-include when processing stdin");
         }
         if args.files_to_exclude.is_some() {
             bail!("Cannot use --files-to-exclude when processing stdin");
         }
+        if args.interactive_filter {
+            bail!("Cannot use --interactive-filter when processing stdin");
+        }
+        // Each match is replaced at its offset within a file, which stdin doesn't have
+        if args.columns.is_some() {
+            bail!("Cannot use --columns when processing stdin");
+        }
+        if args.line_ranges.is_some() {
+            bail!("Cannot use --line-ranges when processing stdin");
+        }
+        if args.auto_include.is_some() {
+            bail!("Cannot use --auto-include when processing stdin");
+        }
+        // Plain stdin is replaced and output rather than files being changed
+        for (name, enabled) in [
+            ("--print-changed-files", args.print_changed_files),
+            ("--git-stage", args.git_stage),
+            ("--git-commit", args.git_commit.is_some()),
+        ] {
+            if enabled && !args.stdin_format.is_framed() {
+                bail!("Cannot use {name} when processing stdin without --stdin-format");
+            }
+        }
     } else if args.print_on_exit {
         bail!("Cannot use --print-on-exit when not processing stdin");
+    } else if args.output.is_some() {
+        bail!("Cannot use --output when not processing stdin");
+    } else if args.stdin_format.is_framed() {
+        bail!("Cannot use --stdin-format when not processing stdin");
     }
     Ok(())
 }
 
+/// The files tracked by git in the searched directories when `--git-tracked-only` is used. These are
+/// listed once up front, both as this means running git and so that any error is shown before searching
+fn git_tracked_files(args: &Args) -> anyhow::Result<Option<Arc<HashSet<PathBuf>>>> {
+    if !args.git_tracked_only {
+        return Ok(None);
+    }
+    let files = git::tracked_files_in(&search_directories(args)).context(
+        "--git-tracked-only requires each searched directory to be within a git repository",
+    )?;
+    Ok(Some(Arc::new(files)))
+}
+
 impl<'a> TryFrom<&'a Args> for AppConfig<'a> {
     type Error = anyhow::Error;
 
@@ -228,27 +762,60 @@ impl<'a> TryFrom<&'a Args> for AppConfig<'a> {
         let immediate = args.immediate || args.no_tui;
 
         Ok(Self {
-            directory: args.directory.clone(),
+            directories: search_directories(args),
             log_level: args.log_level,
             search_field_values: args.into(),
             app_run_config: AppRunConfig {
                 include_hidden: args.hidden,
                 include_git_folders: args.include_git_folders,
+                include_binary: args.text,
                 advanced_regex: args.advanced_regex,
                 multiline: args.multiline,
                 immediate_search: args.immediate_search || immediate,
                 immediate_replace: args.immediate_replace || immediate,
                 print_results: args.print_results || immediate,
                 print_on_exit: args.print_on_exit,
+                line_filter: line_filter_from_args(args),
+                initial_focus: args.focus.clone(),
+                stdin_format: args.stdin_format,
+                interactive_filter: args.interactive_filter,
+                skip_noop: args.skip_noop,
+                max_depth: args.max_depth,
+                modified_since: args.modified_since,
+                git_tracked_only: args.git_tracked_only,
+                occurrence: args.occurrence,
+                select_none: args.select_none,
+                auto_include: args.auto_include.clone(),
+                wrap_matches: args.wrap.is_some(),
+                delete_empty_lines: args.delete_empty_result_lines,
+                symlinks: args.symlinks,
                 ..AppRunConfig::default()
             },
             stdin_content,
             editor_command_override: args.editor_command.clone(),
             interpret_escape_sequences_override: args.interpret_escape_sequences,
+            quiet: args.quiet,
+            output_file: args.output.clone(),
+            hooks: lifecycle_hooks(args),
+            color: args.color,
         })
     }
 }
 
+fn lifecycle_hooks(args: &Args) -> LifecycleHooks {
+    LifecycleHooks {
+        replacement_completed: args
+            .notify
+            .then(|| Arc::new(notification::notify_replacement_completed) as _),
+        ..LifecycleHooks::default()
+    }
+}
+
+/// Records the files changed by replacements if they are to be staged in git on exit
+fn git_changes(args: &Args) -> Option<GitChanges> {
+    (args.git_stage || args.git_commit.is_some()).then(|| GitChanges::new(args.git_commit.clone()))
+}
+
 impl<'a> From<&'a Args> for SearchFieldValues<'a> {
     fn from(args: &'a Args) -> Self {
         let mut search_field_values = SearchFieldValues::default();
@@ -257,7 +824,7 @@ impl<'a> From<&'a Args> for SearchFieldValues<'a> {
             search_field_values.search = FieldValue::new(search_text, true);
         }
 
-        if let Some(ref replace_text) = args.replace_text {
+        if let Some(replace_text) = args.wrap.as_ref().or(args.replace_text.as_ref()) {
             search_field_values.replace = FieldValue::new(replace_text, true);
         }
         if args.fixed_strings {
@@ -280,22 +847,89 @@ impl<'a> From<&'a Args> for SearchFieldValues<'a> {
     }
 }
 
+/// Fills in the search fields from `preset`, leaving any set by flags unchanged. Toggles can only be
+/// turned on, as the flags that set them can't turn them off
+fn apply_preset(args: &mut Args, preset: Preset) {
+    let non_empty = |text: String| (!text.is_empty()).then_some(text);
+    args.search_text = args.search_text.take().or(non_empty(preset.search));
+    if args.wrap.is_none() {
+        args.replace_text = args.replace_text.take().or(non_empty(preset.replace));
+    }
+    args.fixed_strings |= preset.fixed_strings;
+    args.match_whole_word |= preset.match_whole_word;
+    args.case_insensitive |= !preset.match_case;
+    args.files_to_include = args
+        .files_to_include
+        .take()
+        .or(non_empty(preset.include_files));
+    args.files_to_exclude = args
+        .files_to_exclude
+        .take()
+        .or(non_empty(preset.exclude_files));
+}
+
+/// Creates the config file with the documented defaults, failing if it already exists
+fn init_config() -> anyhow::Result<()> {
+    let config_file = config::config_file();
+    if config_file.exists() {
+        bail!("Config file already exists at {}", config_file.display());
+    }
+    config::create_config_file_if_missing()?;
+    println!("Created config file at {}", config_file.display());
+    Ok(())
+}
+
+/// Opens the config file in the editor, creating it first if it doesn't exist
+fn edit_config(editor_command_override: Option<String>) -> anyhow::Result<()> {
+    let config_file = config::create_config_file_if_missing()?;
+    // The config may be invalid, which is likely why it's being edited, so fall back to the default editor
+    let editor_command = editor_command_override.or_else(|| {
+        config::load_config()
+            .ok()
+            .and_then(|config| config.editor_open.command)
+    });
+    open_editor(editor_command.as_deref(), config_file, 1)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
     if let Some(config_dir) = &args.config_dir {
         config::set_config_dir_override(config_dir);
     }
-    let config = AppConfig::try_from(&args)?;
+    if let Some(name) = &args.preset {
+        let preset = presets::find_preset(&config::presets_file(), name)?;
+        apply_preset(&mut args, preset);
+    }
+    if let Some(profile) = &args.profile {
+        config::set_config_profile_override(profile);
+    }
+    if !args.no_local_config
+        && let Some(directory) = config::local_config_dir(&args.directories)
+    {
+        config::set_local_config_dir(&directory);
+    }
+    if args.print_config {
+        print!("{}", config::load_config()?.to_toml()?);
+        return Ok(());
+    }
+    if args.init_config {
+        return init_config();
+    }
+    if args.edit_config {
+        return edit_config(args.editor_command.clone());
+    }
+    let mut config = AppConfig::try_from(&args)?;
     setup_logging(config.log_level)?;
+    let git_changes = git_changes(&args);
+    if let Some(git_changes) = &git_changes {
+        config
+            .hooks
+            .add_replacement_completed(git_changes.replacement_completed_hook());
+    }
 
     let results = if args.no_tui {
-        let results = if let Some(stdin_content) = config.stdin_content {
-            run_headless_with_stdin(&stdin_content, search_config_from_args(&args)?)?
-        } else {
-            run_headless(search_config_from_args(&args)?, dir_config_from_args(&args))?
-        };
-        Some(results)
+        run_without_tui(&args, config.stdin_content, git_changes.as_ref())?
     } else {
         run_app_tui(config).await?
     };
@@ -303,25 +937,184 @@ async fn main() -> anyhow::Result<()> {
     if let Some(results) = results {
         print!("{results}");
     }
+    if let Some(git_changes) = git_changes {
+        let message = git_changes.apply()?;
+        if args.no_tui && !args.quiet {
+            // Stdout is kept for the output, such as the paths from `--print-changed-files`
+            eprintln!("{message}");
+        } else if !args.quiet {
+            println!("{message}");
+        }
+    }
 
     Ok(())
 }
 
-fn dir_config_from_args(args: &Args) -> DirConfig<'_> {
+/// Runs the search and replacement without the TUI, returning the summary or output to print
+fn run_without_tui(
+    args: &Args,
+    stdin_content: Option<String>,
+    git_changes: Option<&GitChanges>,
+) -> anyhow::Result<Option<String>> {
+    args.color.apply();
+    let user_config = config::load_config()?;
+    let line_filter = line_filter_from_args(args);
+    let pairs = args
+        .replace_from_csv
+        .as_deref()
+        .map(read_replacement_pairs)
+        .transpose()?;
+    let results = if let Some(stdin_content) = &stdin_content
+        && args.stdin_format.is_framed()
+    {
+        let summary = run_headless_with_framed_stdin(
+            stdin_content,
+            args.stdin_format,
+            search_config_from_args(args, &user_config),
+            &line_filter,
+            args.symlinks,
+        )?;
+        if let Some(git_changes) = git_changes {
+            git_changes.record(&summary.changed_files);
+        }
+        if !summary.errors.is_empty() {
+            // Lines from stdin often go stale as files change, so this is treated as a failure
+            if let Some(output) = headless_output(args, &summary) {
+                print!("{output}");
+            }
+            bail!("Some lines from stdin couldn't be replaced in their files");
+        }
+        headless_output(args, &summary)
+    } else if let Some(stdin_content) = stdin_content {
+        let search_config = search_config_from_args(args, &user_config);
+        let output = match &pairs {
+            Some(pairs) => run_headless_with_stdin_and_pairs(
+                &stdin_content,
+                pairs,
+                &search_config,
+                &line_filter,
+            )?,
+            None => run_headless_with_stdin(&stdin_content, search_config, &line_filter)?,
+        };
+        if let Some(output_file) = &args.output {
+            replace::write_file_atomically(output_file, output.as_bytes())?;
+            None
+        } else {
+            // Replaced stdin content is output rather than a summary, so is printed even when quiet
+            Some(output)
+        }
+    } else if let Some(jobs_path) = &args.jobs {
+        let jobs = read_jobs(jobs_path)?;
+        let results = run_headless_jobs(
+            &jobs,
+            &search_config_from_args(args, &user_config),
+            &dir_config_from_args(args, &user_config, git_tracked_files(args)?),
+            &line_filter,
+            args.fail_fast,
+        )?;
+        if results.has_errors() {
+            // The summary lists the errors, so is printed even when quiet
+            print!("{results}");
+            bail!("Some jobs had replacements that couldn't be made");
+        }
+        (!args.quiet).then(|| results.to_string())
+    } else {
+        let search_config = search_config_from_args(args, &user_config);
+        let dir_config = dir_config_from_args(args, &user_config, git_tracked_files(args)?);
+        let summary = if let Some(pairs) = &pairs {
+            run_headless_with_pairs(pairs, &search_config, &dir_config, &line_filter)?
+        } else if let Some(shadow_dir) = &args.shadow {
+            run_headless_in_shadow(search_config, dir_config, &line_filter, shadow_dir)?
+        } else {
+            run_headless(search_config, dir_config, &line_filter)?
+        };
+        if let Some(git_changes) = git_changes {
+            git_changes.record(&summary.changed_files);
+        }
+        headless_output(args, &summary)
+    };
+    Ok(results)
+}
+
+/// What to print once replacements have been made in files without the TUI: the changed files if
+/// `--print-changed-files` is set, otherwise the summary unless `--quiet` is set
+fn headless_output(args: &Args, results: &HeadlessResults) -> Option<String> {
+    if !args.print_changed_files {
+        if args.quiet {
+            // Errors are still reported when the summary is suppressed
+            for error in &results.errors {
+                eprintln!("{error}");
+            }
+            return None;
+        }
+        return Some(format_headless_results(results, args.color.use_color()));
+    }
+    if !results.errors.is_empty() {
+        // The summary lists the errors, which would otherwise be lost
+        eprint!("{results}");
+    }
+    let mut output = String::new();
+    for path in &results.changed_files {
+        writeln!(output, "{}", path.display()).unwrap();
+    }
+    Some(output)
+}
+
+fn read_replacement_pairs(path: &Path) -> anyhow::Result<Vec<ReplacementPair>> {
+    let csv =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    replacement_pairs::parse_replacement_pairs(&csv)
+}
+
+fn read_jobs(path: &Path) -> anyhow::Result<Vec<Job>> {
+    let toml =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    jobs::parse_jobs(&toml)
+}
+
+fn dir_config_from_args<'a>(
+    args: &'a Args,
+    user_config: &Config,
+    tracked_files: Option<Arc<HashSet<PathBuf>>>,
+) -> DirConfig<'a> {
     DirConfig {
         include_globs: args.files_to_include.as_deref(),
         exclude_globs: args.files_to_exclude.as_deref(),
         include_hidden: args.hidden,
         include_git_folders: args.include_git_folders,
-        directory: args.directory.clone(),
+        include_binary: args.text,
+        binary_detection: user_config.search.binary_detection(),
+        max_depth: args.max_depth,
+        modified_since: args.modified_since,
+        tracked_files,
+        regex_timeout: user_config.search.regex_timeout(),
+        directories: search_directories(args),
     }
 }
 
-fn search_config_from_args(args: &Args) -> anyhow::Result<SearchConfig<'_>> {
-    let user_config = config::load_config()?;
-    Ok(SearchConfig {
+/// The directories to search, with any duplicates or directories nested within another removed so
+/// that files aren't searched twice
+fn search_directories(args: &Args) -> Vec<PathBuf> {
+    utils::remove_nested_dirs(&args.directories)
+}
+
+fn line_filter_from_args(args: &Args) -> LineFilter {
+    LineFilter {
+        require: args.require.clone(),
+        exclude: args.exclude_line.clone(),
+        columns: args.columns,
+        line_ranges: args.line_ranges.clone(),
+    }
+}
+
+fn search_config_from_args<'a>(args: &'a Args, user_config: &Config) -> SearchConfig<'a> {
+    SearchConfig {
         search_text: args.search_text.as_deref().unwrap_or(""),
-        replacement_text: args.replace_text.as_deref().unwrap_or(""),
+        replacement_text: args
+            .wrap
+            .as_deref()
+            .or(args.replace_text.as_deref())
+            .unwrap_or(""),
         fixed_strings: args.fixed_strings,
         advanced_regex: args.advanced_regex,
         match_whole_word: args.match_whole_word,
@@ -329,21 +1122,27 @@ fn search_config_from_args(args: &Args) -> anyhow::Result<SearchConfig<'_>> {
         multiline: args.multiline,
         interpret_escape_sequences: args.interpret_escape_sequences
             || user_config.search.interpret_escape_sequences,
-    })
+        wrap_matches: args.wrap.is_some(),
+        delete_empty_lines: args.delete_empty_result_lines,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use log::LevelFilter;
-    use std::env;
+    use std::{env, ffi::OsStr};
     use tempfile::TempDir;
 
     fn default_args() -> Args {
         Args {
-            directory: env::current_dir().unwrap(),
+            directories: vec![env::current_dir().unwrap()],
             hidden: false,
             include_git_folders: false,
+            text: false,
+            max_depth: None,
+            modified_since: None,
+            git_tracked_only: false,
             log_level: LevelFilter::Info,
             advanced_regex: false,
             multiline: false,
@@ -353,17 +1152,49 @@ mod tests {
             print_results: false,
             immediate: false,
             no_tui: false,
+            color: ColorChoice::Auto,
+            quiet: false,
+            interactive_filter: false,
+            skip_noop: false,
+            occurrence: None,
+            select_all: false,
+            select_none: false,
+            auto_include: None,
+            notify: false,
+            git_stage: false,
+            git_commit: None,
             no_stdin: false,
             print_on_exit: false,
+            output: None,
+            stdin_format: StdinFormat::Plain,
+            symlinks: SymlinkHandling::Follow,
             search_text: None,
             replace_text: None,
+            wrap: None,
+            delete_empty_result_lines: false,
             fixed_strings: false,
             match_whole_word: false,
             case_insensitive: false,
             files_to_include: None,
             files_to_exclude: None,
+            replace_from_csv: None,
+            jobs: None,
+            fail_fast: false,
+            shadow: None,
+            print_changed_files: false,
+            focus: None,
+            require: None,
+            exclude_line: None,
+            columns: None,
             config_dir: None,
+            profile: None,
+            print_config: false,
+            edit_config: false,
+            init_config: false,
+            no_local_config: false,
             editor_command: None,
+            line_ranges: None,
+            preset: None,
         }
     }
 
@@ -391,20 +1222,889 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_flag_combinations_no_tui_with_individual_flags() {
-        let test_cases = [
-            (
-                "--immediate-search",
-                Args {
-                    no_tui: true,
-                    immediate_search: true,
-                    ..default_args()
-                },
-            ),
-            (
-                "--immediate-replace",
-                Args {
-                    no_tui: true,
+    fn test_validate_flag_combinations_line_filters_and_multiline() {
+        let args = Args {
+            multiline: true,
+            require: Some(Regex::new("foo").unwrap()),
+            ..default_args()
+        };
+        let result = validate_flag_combinations(&args);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("--require cannot be combined with --multiline")
+        );
+
+        let args = Args {
+            multiline: true,
+            exclude_line: Some(Regex::new("foo").unwrap()),
+            ..default_args()
+        };
+        let result = validate_flag_combinations(&args);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("--exclude-line cannot be combined with --multiline")
+        );
+
+        let args = Args {
+            multiline: true,
+            columns: Some(parse_columns("1:4").unwrap()),
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--columns cannot be combined with --multiline"
+        );
+        assert_eq!(
+            validate_stdin_usage(
+                &Args {
+                    multiline: false,
+                    ..args
+                },
+                Some("content")
+            )
+            .unwrap_err()
+            .to_string(),
+            "Cannot use --columns when processing stdin"
+        );
+
+        let args = Args {
+            multiline: true,
+            line_ranges: Some(Arc::default()),
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--line-ranges cannot be combined with --multiline"
+        );
+        assert_eq!(
+            validate_stdin_usage(
+                &Args {
+                    multiline: false,
+                    ..args
+                },
+                Some("content")
+            )
+            .unwrap_err()
+            .to_string(),
+            "Cannot use --line-ranges when processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_stdin_text() {
+        assert_eq!(stdin_text(b"foo\nbar\n".to_vec()).unwrap(), "foo\nbar\n");
+        assert_eq!(
+            stdin_text(b"foo\nbar\n\xFFbaz\n".to_vec())
+                .unwrap_err()
+                .to_string(),
+            "Line 3 of stdin isn't valid UTF-8, so stdin can't be processed"
+        );
+    }
+
+    #[test]
+    fn test_apply_preset() {
+        let mut args = Args {
+            search_text: Some("from cli".to_owned()),
+            fixed_strings: true,
+            ..default_args()
+        };
+        apply_preset(
+            &mut args,
+            Preset {
+                name: "my preset".to_owned(),
+                search: "from preset".to_owned(),
+                replace: "bar".to_owned(),
+                fixed_strings: false,
+                match_whole_word: true,
+                match_case: false,
+                include_files: "*.rs".to_owned(),
+                exclude_files: String::new(),
+            },
+        );
+
+        // Flags take precedence over the preset
+        assert_eq!(args.search_text.as_deref(), Some("from cli"));
+        assert!(args.fixed_strings);
+        assert_eq!(args.replace_text.as_deref(), Some("bar"));
+        assert!(args.match_whole_word);
+        assert!(args.case_insensitive);
+        assert_eq!(args.files_to_include.as_deref(), Some("*.rs"));
+        assert_eq!(args.files_to_exclude, None);
+    }
+
+    #[test]
+    fn test_validate_flag_combinations_quiet() {
+        let args = Args {
+            quiet: true,
+            ..default_args()
+        };
+        let result = validate_flag_combinations(&args);
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("--quiet requires --no-tui, --immediate or --print-results")
+        );
+
+        for args in [
+            Args {
+                quiet: true,
+                no_tui: true,
+                ..default_args()
+            },
+            Args {
+                quiet: true,
+                immediate: true,
+                ..default_args()
+            },
+            Args {
+                quiet: true,
+                print_results: true,
+                ..default_args()
+            },
+        ] {
+            assert!(validate_flag_combinations(&args).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_flag_combinations_focus() {
+        for (args, name) in [
+            (
+                Args {
+                    focus: Some(FieldName::Replace),
+                    no_tui: true,
+                    ..default_args()
+                },
+                "--no-tui",
+            ),
+            (
+                Args {
+                    focus: Some(FieldName::Replace),
+                    immediate_search: true,
+                    ..default_args()
+                },
+                "--immediate-search",
+            ),
+        ] {
+            let result = validate_flag_combinations(&args);
+            assert!(
+                result
+                    .unwrap_err()
+                    .to_string()
+                    .contains(&format!("--focus cannot be combined with {name}"))
+            );
+        }
+
+        let args = Args {
+            focus: Some(FieldName::IncludeFiles),
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+    }
+
+    #[test]
+    fn test_validate_interactive_filter() {
+        for (args, name) in [
+            (
+                Args {
+                    interactive_filter: true,
+                    no_tui: true,
+                    ..default_args()
+                },
+                "--no-tui",
+            ),
+            (
+                Args {
+                    interactive_filter: true,
+                    immediate: true,
+                    ..default_args()
+                },
+                "--immediate",
+            ),
+        ] {
+            let result = validate_flag_combinations(&args);
+            assert!(result.unwrap_err().to_string().contains(&format!(
+                "--interactive-filter cannot be combined with {name}"
+            )));
+        }
+
+        let args = Args {
+            interactive_filter: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert!(validate_stdin_usage(&args, None).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&args, Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --interactive-filter when processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_validate_replace_from_csv() {
+        let csv_args = || Args {
+            replace_from_csv: Some(PathBuf::from("pairs.csv")),
+            no_tui: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&csv_args()).is_ok());
+        assert!(validate_search_text_required(&csv_args()).is_ok());
+
+        for (args, expected) in [
+            (
+                Args {
+                    no_tui: false,
+                    ..csv_args()
+                },
+                "--replace-from-csv requires --no-tui",
+            ),
+            (
+                Args {
+                    search_text: Some("foo".to_owned()),
+                    ..csv_args()
+                },
+                "--replace-from-csv cannot be combined with --search-text",
+            ),
+            (
+                Args {
+                    stdin_format: StdinFormat::Grep,
+                    ..csv_args()
+                },
+                "--replace-from-csv cannot be combined with --stdin-format",
+            ),
+        ] {
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_jobs() {
+        let jobs_args = || Args {
+            jobs: Some(PathBuf::from("jobs.toml")),
+            no_tui: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&jobs_args()).is_ok());
+        assert!(
+            validate_flag_combinations(&Args {
+                fail_fast: true,
+                ..jobs_args()
+            })
+            .is_ok()
+        );
+        assert!(validate_search_text_required(&jobs_args()).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&jobs_args(), Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --jobs when processing stdin"
+        );
+
+        for (args, expected) in [
+            (
+                Args {
+                    no_tui: false,
+                    ..jobs_args()
+                },
+                "--jobs requires --no-tui",
+            ),
+            (
+                Args {
+                    files_to_include: Some("*.rs".to_owned()),
+                    ..jobs_args()
+                },
+                "--jobs cannot be combined with --files-to-include",
+            ),
+            (
+                Args {
+                    replace_from_csv: Some(PathBuf::from("pairs.csv")),
+                    ..jobs_args()
+                },
+                "--jobs cannot be combined with --replace-from-csv",
+            ),
+            (
+                Args {
+                    fail_fast: true,
+                    no_tui: true,
+                    ..default_args()
+                },
+                "--fail-fast requires --jobs",
+            ),
+        ] {
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_print_changed_files() {
+        let args = Args {
+            print_changed_files: true,
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--print-changed-files requires --no-tui"
+        );
+
+        let args = Args {
+            no_tui: true,
+            ..args
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&args, Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --print-changed-files when processing stdin without --stdin-format"
+        );
+
+        let args = Args {
+            jobs: Some(PathBuf::from("jobs.toml")),
+            ..args
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--jobs cannot be combined with --print-changed-files"
+        );
+    }
+
+    #[test]
+    fn test_validate_shadow() {
+        let shadow_args = || Args {
+            shadow: Some(PathBuf::from("shadow")),
+            search_text: Some("foo".to_owned()),
+            no_tui: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&shadow_args()).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&shadow_args(), Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --shadow when processing stdin"
+        );
+
+        for (args, expected) in [
+            (
+                Args {
+                    no_tui: false,
+                    ..shadow_args()
+                },
+                "--shadow requires --no-tui",
+            ),
+            (
+                Args {
+                    search_text: None,
+                    replace_from_csv: Some(PathBuf::from("pairs.csv")),
+                    ..shadow_args()
+                },
+                "--shadow cannot be combined with --replace-from-csv",
+            ),
+        ] {
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_notify() {
+        // `--notify` does nothing without the TUI, rather than being rejected
+        let args = Args {
+            notify: true,
+            no_tui: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+
+        assert!(
+            lifecycle_hooks(&default_args())
+                .replacement_completed
+                .is_none()
+        );
+        let args = Args {
+            notify: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert!(lifecycle_hooks(&args).replacement_completed.is_some());
+    }
+
+    #[test]
+    fn test_git_stage_and_commit() {
+        assert!(git_changes(&default_args()).is_none());
+        let git_args = |flag: &str| match flag {
+            "--git-stage" => Args {
+                git_stage: true,
+                ..default_args()
+            },
+            _ => Args {
+                git_commit: Some("Replace foo".to_owned()),
+                ..default_args()
+            },
+        };
+        for flag in ["--git-stage", "--git-commit"] {
+            let args = git_args(flag);
+            assert!(validate_flag_combinations(&args).is_ok());
+            assert!(git_changes(&args).is_some());
+            let args = Args {
+                no_tui: true,
+                ..git_args(flag)
+            };
+            assert!(validate_flag_combinations(&args).is_ok());
+
+            let args = Args {
+                no_tui: true,
+                jobs: Some(PathBuf::from("jobs.toml")),
+                ..git_args(flag)
+            };
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                format!("--jobs cannot be combined with {flag}")
+            );
+            let args = Args {
+                no_tui: true,
+                shadow: Some(PathBuf::from("shadow")),
+                ..git_args(flag)
+            };
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                format!("--shadow cannot be combined with {flag}")
+            );
+
+            assert_eq!(
+                validate_stdin_usage(&git_args(flag), Some("content"))
+                    .unwrap_err()
+                    .to_string(),
+                format!("Cannot use {flag} when processing stdin without --stdin-format")
+            );
+            let args = Args {
+                stdin_format: StdinFormat::Grep,
+                ..git_args(flag)
+            };
+            assert!(validate_stdin_usage(&args, Some("content")).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_skip_noop() {
+        let args = Args {
+            skip_noop: true,
+            no_tui: true,
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--skip-noop cannot be combined with --no-tui"
+        );
+
+        let args = Args {
+            skip_noop: true,
+            immediate: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+    }
+
+    #[test]
+    fn test_wrap() {
+        let args = Args {
+            wrap: Some("/* {} */".to_owned()),
+            replace_text: Some("foo".to_owned()),
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--wrap cannot be combined with --replace-text"
+        );
+
+        let args = Args {
+            wrap: Some("/* {} */".to_owned()),
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        let search_config = search_config_from_args(&args, &Config::default());
+        assert_eq!(search_config.replacement_text, "/* {} */");
+        assert!(search_config.wrap_matches);
+        assert_eq!(SearchFieldValues::from(&args).replace.value, "/* {} */");
+    }
+
+    #[test]
+    fn test_delete_empty_result_lines() {
+        let args = Args {
+            delete_empty_result_lines: true,
+            multiline: true,
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--delete-empty-result-lines cannot be combined with --multiline"
+        );
+
+        let args = Args {
+            delete_empty_result_lines: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert!(search_config_from_args(&args, &Config::default()).delete_empty_lines);
+        assert!(
+            AppConfig::try_from(&args)
+                .unwrap()
+                .app_run_config
+                .delete_empty_lines
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        assert_eq!(parse_max_depth("2"), Ok(2));
+        assert_eq!(
+            parse_max_depth("0"),
+            Err("Max depth must be at least 1".to_owned())
+        );
+        assert!(parse_max_depth("-1").is_err());
+
+        let args = Args {
+            max_depth: Some(2),
+            ..default_args()
+        };
+        assert!(validate_stdin_usage(&args, None).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&args, Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --max-depth when processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_occurrence() {
+        assert_eq!(parse_occurrence("3"), Ok(3));
+        assert_eq!(
+            parse_occurrence("0"),
+            Err("Occurrence must be at least 1".to_owned())
+        );
+        assert!(parse_occurrence("-1").is_err());
+
+        let args = Args {
+            occurrence: Some(3),
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        let args = Args {
+            no_tui: true,
+            ..args
+        };
+        assert_eq!(
+            validate_flag_combinations(&args).unwrap_err().to_string(),
+            "--occurrence cannot be combined with --no-tui"
+        );
+    }
+
+    #[test]
+    fn test_select_none() {
+        let args = Args {
+            select_none: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert!(
+            AppConfig::try_from(&args)
+                .unwrap()
+                .app_run_config
+                .select_none
+        );
+
+        let args = Args {
+            select_all: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert!(
+            !AppConfig::try_from(&args)
+                .unwrap()
+                .app_run_config
+                .select_none
+        );
+
+        for (args, flag) in [
+            (
+                Args {
+                    select_all: true,
+                    select_none: true,
+                    ..default_args()
+                },
+                "--select-all",
+            ),
+            (
+                Args {
+                    immediate_replace: true,
+                    select_none: true,
+                    ..default_args()
+                },
+                "--immediate-replace",
+            ),
+            (
+                Args {
+                    occurrence: Some(2),
+                    select_none: true,
+                    ..default_args()
+                },
+                "--occurrence",
+            ),
+        ] {
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                format!("--select-none cannot be combined with {flag}")
+            );
+        }
+    }
+
+    #[test]
+    fn test_auto_include() {
+        let args = Args {
+            auto_include: Some(parse_auto_include("tests/,*_test.rs").unwrap()),
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+        assert!(
+            parse_auto_include("[invalid")
+                .unwrap_err()
+                .starts_with("Invalid glob")
+        );
+
+        for (args, flag) in [
+            (
+                Args {
+                    select_all: true,
+                    auto_include: Some("tests/".to_owned()),
+                    ..default_args()
+                },
+                "--select-all",
+            ),
+            (
+                Args {
+                    select_none: true,
+                    auto_include: Some("tests/".to_owned()),
+                    ..default_args()
+                },
+                "--select-none",
+            ),
+            (
+                Args {
+                    no_tui: true,
+                    auto_include: Some("tests/".to_owned()),
+                    ..default_args()
+                },
+                "--no-tui",
+            ),
+        ] {
+            assert_eq!(
+                validate_flag_combinations(&args).unwrap_err().to_string(),
+                format!("--auto-include cannot be combined with {flag}")
+            );
+        }
+        assert_eq!(
+            validate_stdin_usage(&args, Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --auto-include when processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_validate_stdin_usage_modified_since() {
+        let args = Args {
+            modified_since: Some(parse_modified_since("1d").unwrap()),
+            ..default_args()
+        };
+        assert!(validate_stdin_usage(&args, None).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&args, Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --modified-since when processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_git_tracked_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let args = Args {
+            directories: vec![temp_dir.path().to_path_buf()],
+            ..default_args()
+        };
+        assert!(git_tracked_files(&args).unwrap().is_none());
+
+        let args = Args {
+            git_tracked_only: true,
+            ..args
+        };
+        assert_eq!(
+            git_tracked_files(&args).unwrap_err().to_string(),
+            "--git-tracked-only requires each searched directory to be within a git repository"
+        );
+        assert_eq!(
+            validate_stdin_usage(&args, Some("content"))
+                .unwrap_err()
+                .to_string(),
+            "Cannot use --git-tracked-only when processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_validate_stdin_usage_output() {
+        let args = Args {
+            output: Some(PathBuf::from("out.txt")),
+            ..default_args()
+        };
+        assert!(validate_stdin_usage(&args, Some("content")).is_ok());
+        let result = validate_stdin_usage(&args, None);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Cannot use --output when not processing stdin"
+        );
+    }
+
+    #[test]
+    fn test_validate_flag_combinations_stdin_format() {
+        let framed = Args {
+            stdin_format: StdinFormat::Grep,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&framed).is_ok());
+        assert!(validate_stdin_usage(&framed, Some("a.txt:1:foo")).is_ok());
+        assert_eq!(
+            validate_stdin_usage(&framed, None).unwrap_err().to_string(),
+            "Cannot use --stdin-format when not processing stdin"
+        );
+
+        let with_multiline = Args {
+            multiline: true,
+            ..framed
+        };
+        assert_eq!(
+            validate_flag_combinations(&with_multiline)
+                .unwrap_err()
+                .to_string(),
+            "--stdin-format cannot be combined with --multiline"
+        );
+
+        let with_output = Args {
+            stdin_format: StdinFormat::Headers,
+            output: Some(PathBuf::from("out.txt")),
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&with_output).is_err());
+
+        let skip_symlinks = Args {
+            symlinks: SymlinkHandling::Skip,
+            ..default_args()
+        };
+        assert_eq!(
+            validate_flag_combinations(&skip_symlinks)
+                .unwrap_err()
+                .to_string(),
+            "--symlinks requires --stdin-format"
+        );
+        assert!(
+            validate_flag_combinations(&Args {
+                stdin_format: StdinFormat::Grep,
+                ..skip_symlinks
+            })
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_parse_stdin_format() {
+        assert_eq!(parse_stdin_format("plain"), Ok(StdinFormat::Plain));
+        assert_eq!(parse_stdin_format("headers"), Ok(StdinFormat::Headers));
+        assert_eq!(parse_stdin_format("grep"), Ok(StdinFormat::Grep));
+        assert_eq!(parse_stdin_format("rg-json"), Ok(StdinFormat::RgJson));
+        assert!(parse_stdin_format("json").is_err());
+    }
+
+    #[test]
+    fn test_parse_symlinks() {
+        assert_eq!(parse_symlinks("follow"), Ok(SymlinkHandling::Follow));
+        assert_eq!(parse_symlinks("skip"), Ok(SymlinkHandling::Skip));
+        assert!(parse_symlinks("ignore").is_err());
+    }
+
+    #[test]
+    fn test_parse_color_choice() {
+        assert_eq!(parse_color_choice("auto"), Ok(ColorChoice::Auto));
+        assert_eq!(parse_color_choice("always"), Ok(ColorChoice::Always));
+        assert_eq!(parse_color_choice("never"), Ok(ColorChoice::Never));
+        assert!(parse_color_choice("yes").is_err());
+    }
+
+    #[test]
+    fn test_color() {
+        let args = Args {
+            color: ColorChoice::Never,
+            ..default_args()
+        };
+        assert_eq!(
+            AppConfig::try_from(&args).unwrap().color,
+            ColorChoice::Never
+        );
+        assert_eq!(
+            AppConfig::try_from(&default_args()).unwrap().color,
+            ColorChoice::Auto
+        );
+
+        let args = Args {
+            color: ColorChoice::Always,
+            no_tui: true,
+            ..default_args()
+        };
+        assert!(validate_flag_combinations(&args).is_ok());
+    }
+
+    #[test]
+    fn test_parse_focus_field() {
+        assert_eq!(parse_focus_field("search"), Ok(FieldName::Search));
+        assert_eq!(parse_focus_field("replace"), Ok(FieldName::Replace));
+        assert_eq!(parse_focus_field("include"), Ok(FieldName::IncludeFiles));
+        assert_eq!(parse_focus_field("exclude"), Ok(FieldName::ExcludeFiles));
+        assert!(parse_focus_field("fixed").is_err());
+    }
+
+    #[test]
+    fn test_validate_flag_combinations_no_tui_with_individual_flags() {
+        let test_cases = [
+            (
+                "--immediate-search",
+                Args {
+                    no_tui: true,
+                    immediate_search: true,
+                    ..default_args()
+                },
+            ),
+            (
+                "--immediate-replace",
+                Args {
+                    no_tui: true,
                     immediate_replace: true,
                     ..default_args()
                 },
@@ -486,6 +2186,14 @@ mod tests {
             ..default_args()
         };
         assert!(validate_search_text_required(&args).is_ok());
+
+        // Matches from `rg --json` can be replaced without any search text
+        let args = Args {
+            no_tui: true,
+            stdin_format: StdinFormat::RgJson,
+            ..default_args()
+        };
+        assert!(validate_search_text_required(&args).is_ok());
     }
 
     #[test]
@@ -532,7 +2240,7 @@ mod tests {
     #[test]
     fn test_app_config_try_from_success() {
         let args = Args {
-            directory: PathBuf::from("/test"),
+            directories: vec![PathBuf::from("/test")],
             search_text: Some("test".to_string()),
             immediate: true,
             ..default_args()
@@ -541,7 +2249,7 @@ mod tests {
         assert!(config.is_ok());
 
         let config = config.unwrap();
-        assert_eq!(config.directory, PathBuf::from("/test"));
+        assert_eq!(config.directories, vec![PathBuf::from("/test")]);
         assert!(config.app_run_config.immediate_search);
         assert!(config.app_run_config.immediate_replace);
         assert!(config.app_run_config.print_results);
@@ -636,6 +2344,59 @@ mod tests {
         assert!(err.contains(nonexistent_path));
     }
 
+    #[test]
+    fn test_multiple_directories() {
+        let temp_dir = setup_test_dir();
+        let dir_a = temp_dir.path().join("a");
+        let dir_b = temp_dir.path().join("b");
+        std::fs::create_dir(&dir_a).unwrap();
+        std::fs::create_dir(&dir_b).unwrap();
+
+        let args = Args::try_parse_from([
+            OsStr::new("scooter"),
+            dir_a.as_os_str(),
+            dir_b.as_os_str(),
+            dir_a.as_os_str(),
+        ])
+        .unwrap();
+        assert_eq!(search_directories(&args), vec![dir_a.clone(), dir_b]);
+
+        let missing = temp_dir.path().join("missing");
+        let result = Args::try_parse_from([
+            OsStr::new("scooter"),
+            dir_a.as_os_str(),
+            missing.as_os_str(),
+        ]);
+        assert!(result.unwrap_err().to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_headless_command_with_hyphenated_values_parses() {
+        let fields = scooter_core::fields::SearchFields::with_values(
+            &scooter_core::fields::SearchFieldValues {
+                search: scooter_core::fields::FieldValue::new("-foo", false),
+                replace: scooter_core::fields::FieldValue::new("--bar", false),
+                ..Default::default()
+            },
+            false,
+        );
+        let command = scooter_core::headless_command::headless_command(
+            &fields,
+            &scooter_core::app::AppRunConfig::default(),
+            Some(&[PathBuf::from(".")]),
+        );
+
+        let args = Args::try_parse_from(command.split(' ')).unwrap();
+        assert_eq!(args.search_text.as_deref(), Some("-foo"));
+        assert_eq!(args.replace_text.as_deref(), Some("--bar"));
+    }
+
+    #[test]
+    fn test_default_directory() {
+        let args = Args::try_parse_from(["scooter"]).unwrap();
+        assert_eq!(search_directories(&args), vec![PathBuf::from(".")]);
+    }
+
     #[test]
     fn test_validate_directory_with_nested_structure() {
         let temp_dir = setup_test_dir();
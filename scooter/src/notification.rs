@@ -0,0 +1,110 @@
+use std::fmt::Write;
+#[cfg(feature = "notify")]
+use std::{process::Command, thread};
+
+use scooter_core::replace::ReplaceState;
+
+/// Shows a desktop notification summarising the replacements performed
+pub fn notify_replacement_completed(replace_state: &ReplaceState) {
+    send_notification(
+        "scooter: replacement complete",
+        &replacement_summary(replace_state),
+    );
+}
+
+fn replacement_summary(replace_state: &ReplaceState) -> String {
+    let mut summary = format!(
+        "Successful replacements (lines): {}",
+        replace_state.num_successes
+    );
+    if replace_state.num_ignored > 0 {
+        write!(summary, "\nIgnored (lines): {}", replace_state.num_ignored).unwrap();
+    }
+    write!(summary, "\nErrors: {}", replace_state.errors.len()).unwrap();
+    summary
+}
+
+/// Shows a desktop notification using the platform's notification command. This happens in the
+/// background, and failures (such as the command not being installed) are logged rather than
+/// reported to the user
+#[cfg(feature = "notify")]
+fn send_notification(title: &str, body: &str) {
+    let Some(mut command) = notification_command(title, body) else {
+        log::warn!("Desktop notifications aren't supported on this platform");
+        return;
+    };
+    thread::spawn(move || match command.output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => log::error!(
+            "Failed to show notification: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(e) => log::error!("Failed to show notification: {e}"),
+    });
+}
+
+#[cfg(not(feature = "notify"))]
+fn send_notification(_title: &str, _body: &str) {
+    log::warn!(
+        "Desktop notifications aren't available, as scooter was built without the `notify` feature"
+    );
+}
+
+#[cfg(feature = "notify")]
+fn notification_command(title: &str, body: &str) -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ));
+        Some(command)
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=scooter").arg(title).arg(body);
+        Some(command)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "notify")]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacement_summary() {
+        let mut replace_state = ReplaceState {
+            num_successes: 5,
+            num_ignored: 0,
+            errors: vec![],
+            replacement_errors_pos: 0,
+            changed_paths: vec![],
+        };
+        assert_eq!(
+            replacement_summary(&replace_state),
+            "Successful replacements (lines): 5\nErrors: 0"
+        );
+
+        replace_state.num_ignored = 2;
+        assert_eq!(
+            replacement_summary(&replace_state),
+            "Successful replacements (lines): 5\nIgnored (lines): 2\nErrors: 0"
+        );
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(
+            applescript_string(r#"say "hi" \ bye"#),
+            r#""say \"hi\" \\ bye""#
+        );
+    }
+}
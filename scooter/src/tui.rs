@@ -2,6 +2,7 @@ use std::any::TypeId;
 use std::io;
 use std::panic;
 
+use crossterm::clipboard::CopyToClipboard;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::Terminal;
@@ -14,14 +15,20 @@ use crate::ui;
 #[derive(Debug)]
 pub struct Tui<B: Backend> {
     pub terminal: Terminal<B>,
+    /// Whether to capture mouse events, which stops the terminal from handling them itself (e.g. to
+    /// select text)
+    mouse_capture: bool,
 }
 
 impl<B: Backend + 'static> Tui<B>
 where
     B::Error: Send + Sync,
 {
-    pub fn new(terminal: Terminal<B>) -> Self {
-        Self { terminal }
+    pub fn new(terminal: Terminal<B>, mouse_capture: bool) -> Self {
+        Self {
+            terminal,
+            mouse_capture,
+        }
     }
 
     pub fn init(&mut self) -> anyhow::Result<()> {
@@ -30,7 +37,10 @@ where
         }
 
         terminal::enable_raw_mode()?;
-        crossterm::execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+        if self.mouse_capture {
+            crossterm::execute!(io::stdout(), EnableMouseCapture)?;
+        }
 
         let panic_hook = panic::take_hook();
         panic::set_hook(Box::new(move |panic| {
@@ -55,6 +65,16 @@ where
         Ok(())
     }
 
+    /// Copies `text` to the system clipboard using an OSC 52 escape sequence, which is ignored by
+    /// terminals that don't support it
+    pub fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+        if TypeId::of::<B>() == TypeId::of::<TestBackend>() {
+            return Ok(());
+        }
+        crossterm::execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text))?;
+        Ok(())
+    }
+
     pub fn show_cursor(&mut self) -> anyhow::Result<()> {
         self.terminal.show_cursor()?;
         Ok(())
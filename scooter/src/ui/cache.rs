@@ -1,14 +1,21 @@
 use lru::LruCache;
-use scooter_core::{search::MatchContent, utils::HighlightedLine};
+use scooter_core::{
+    file_content::FileContentProvider, search::MatchContent, utils::HighlightedLine,
+};
 use std::{
-    collections::hash_map::DefaultHasher,
+    borrow::Borrow,
+    collections::{HashSet, hash_map::DefaultHasher},
     hash::{Hash, Hasher},
     num::NonZeroUsize,
-    path::PathBuf,
-    sync::{Mutex, OnceLock},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
-use crate::ui::view::SearchResultPreview;
+use crate::ui::view::{DiffColours, SearchResultPreview};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub(crate) struct FileWindow {
@@ -43,14 +50,162 @@ define_cache! {
     plain_window_cache: FileWindow => Vec<(usize, String)>
 }
 
-define_cache! {
-    /// Cache of sections of files (with syntax highlighting)
-    highlighted_window_cache: FileWindow => Vec<(usize, HighlightedLine)>
+const DEFAULT_HIGHLIGHT_CACHE_ENTRIES: usize = 200;
+const DEFAULT_HIGHLIGHT_CACHE_LINES: usize = 500_000;
+
+/// An LRU cache of syntax-highlighted lines, bounded both by the number of entries and by the total
+/// number of lines across them, so that a few very large files can't take up too much memory. The
+/// most recently added entry is always kept, even if it alone has more lines than the limit
+pub(crate) struct HighlightCache<K: Hash + Eq> {
+    entries: LruCache<K, Vec<(usize, HighlightedLine)>>,
+    num_lines: usize,
+    max_lines: usize,
 }
 
-define_cache! {
-    /// Cache of entire files (with syntax highlighting)
-    highlighted_file_cache: PathBuf => Vec<(usize, HighlightedLine)>
+impl<K: Hash + Eq> HighlightCache<K> {
+    fn new(max_entries: NonZeroUsize, max_lines: usize) -> Self {
+        Self {
+            entries: LruCache::new(max_entries),
+            num_lines: 0,
+            max_lines,
+        }
+    }
+
+    pub(crate) fn get<Q>(&mut self, key: &Q) -> Option<&Vec<(usize, HighlightedLine)>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key)
+    }
+
+    pub(crate) fn put(&mut self, key: K, lines: Vec<(usize, HighlightedLine)>) {
+        self.num_lines += lines.len();
+        // Returns the previous value for `key`, or the entry evicted to make space
+        if let Some((_, removed)) = self.entries.push(key, lines) {
+            self.num_lines -= removed.len();
+        }
+        self.evict_excess_lines();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.num_lines = 0;
+    }
+
+    fn set_limits(&mut self, max_entries: NonZeroUsize, max_lines: usize) {
+        while self.entries.len() > max_entries.get() {
+            self.pop_lru();
+        }
+        self.entries.resize(max_entries);
+        self.max_lines = max_lines;
+        self.evict_excess_lines();
+    }
+
+    fn evict_excess_lines(&mut self) {
+        while self.num_lines > self.max_lines && self.entries.len() > 1 {
+            self.pop_lru();
+        }
+    }
+
+    fn pop_lru(&mut self) {
+        if let Some((_, removed)) = self.entries.pop_lru() {
+            self.num_lines -= removed.len();
+        }
+    }
+}
+
+type HighlightedWindowCache = Mutex<HighlightCache<FileWindow>>;
+type HighlightedFileCache = Mutex<HighlightCache<PathBuf>>;
+
+static HIGHLIGHTED_WINDOW_CACHE: OnceLock<HighlightedWindowCache> = OnceLock::new();
+static HIGHLIGHTED_FILE_CACHE: OnceLock<HighlightedFileCache> = OnceLock::new();
+
+fn default_highlight_cache<K: Hash + Eq>() -> Mutex<HighlightCache<K>> {
+    let max_entries = NonZeroUsize::new(DEFAULT_HIGHLIGHT_CACHE_ENTRIES).unwrap();
+    Mutex::new(HighlightCache::new(
+        max_entries,
+        DEFAULT_HIGHLIGHT_CACHE_LINES,
+    ))
+}
+
+/// Cache of sections of files (with syntax highlighting)
+pub(crate) fn highlighted_window_cache() -> &'static HighlightedWindowCache {
+    HIGHLIGHTED_WINDOW_CACHE.get_or_init(default_highlight_cache)
+}
+
+/// Cache of entire files (with syntax highlighting)
+pub(crate) fn highlighted_file_cache() -> &'static HighlightedFileCache {
+    HIGHLIGHTED_FILE_CACHE.get_or_init(default_highlight_cache)
+}
+
+/// Sets the limits of each of the syntax highlighting caches, from `preview.highlight_cache_entries`
+/// and `preview.highlight_cache_lines`, dropping the least recently used entries that no longer fit
+pub(crate) fn set_highlight_cache_limits(max_entries: usize, max_lines: usize) {
+    let Some(max_entries) = NonZeroUsize::new(max_entries) else {
+        log::warn!("Ignoring highlight cache limit of 0 entries");
+        return;
+    };
+    highlighted_window_cache()
+        .lock()
+        .unwrap()
+        .set_limits(max_entries, max_lines);
+    highlighted_file_cache()
+        .lock()
+        .unwrap()
+        .set_limits(max_entries, max_lines);
+}
+
+static PREVIEW_CACHE_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Incremented each time the preview caches are cleared. Background jobs that populate the caches
+/// should check that this hasn't changed since they started reading the file, as otherwise the
+/// file may have changed and the lines they read could be stale
+pub(crate) fn preview_cache_generation() -> usize {
+    PREVIEW_CACHE_GENERATION.load(Ordering::Acquire)
+}
+
+/// Clears the cached file sections and syntax highlighting used by the preview
+pub(crate) fn clear_preview_caches() {
+    PREVIEW_CACHE_GENERATION.fetch_add(1, Ordering::AcqRel);
+    plain_window_cache().lock().unwrap().clear();
+    highlighted_window_cache().lock().unwrap().clear();
+    highlighted_file_cache().lock().unwrap().clear();
+}
+
+/// Wraps a [`FileContentProvider`] so that the preview caches are cleared along with it, which
+/// happens when the app is reset or a new search is started
+pub(crate) struct PreviewCacheClearingProvider {
+    inner: Arc<dyn FileContentProvider>,
+}
+
+impl PreviewCacheClearingProvider {
+    pub(crate) fn new(inner: Arc<dyn FileContentProvider>) -> Self {
+        Self { inner }
+    }
+}
+
+impl FileContentProvider for PreviewCacheClearingProvider {
+    fn read_to_string(&self, path: &Path) -> anyhow::Result<Arc<String>> {
+        self.inner.read_to_string(path)
+    }
+
+    fn invalidate(&self, path: &Path) {
+        self.inner.invalidate(path);
+    }
+
+    fn clear(&self) {
+        self.inner.clear();
+        clear_preview_caches();
+    }
+}
+
+static FULL_FILE_HIGHLIGHTS_IN_PROGRESS: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+
+/// Files currently being highlighted in full in the background, so that re-rendering the preview
+/// while a file is being highlighted doesn't start duplicate jobs
+pub(crate) fn full_file_highlights_in_progress() -> &'static Mutex<HashSet<PathBuf>> {
+    FULL_FILE_HIGHLIGHTS_IN_PROGRESS.get_or_init(|| Mutex::new(HashSet::new()))
 }
 
 define_cache! {
@@ -62,11 +217,23 @@ define_cache! {
 pub(crate) struct DiffCacheFullKey {
     pub(crate) old: MatchContent,
     pub(crate) replacement: String,
+    pub(crate) match_spans: Vec<Range<usize>>,
+    pub(crate) colours: DiffColours,
 }
 
 impl DiffCacheFullKey {
-    pub(crate) fn new(old: MatchContent, replacement: String) -> Self {
-        Self { old, replacement }
+    pub(crate) fn new(
+        old: MatchContent,
+        replacement: String,
+        match_spans: Vec<Range<usize>>,
+        colours: DiffColours,
+    ) -> Self {
+        Self {
+            old,
+            replacement,
+            match_spans,
+            colours,
+        }
     }
 }
 
@@ -82,9 +249,79 @@ pub(crate) struct DiffCacheRecord {
     pub(crate) entry: DiffTaskCacheEntry,
 }
 
-pub(crate) fn diff_cache_hash(old: &MatchContent, replacement: &str) -> u64 {
+pub(crate) fn diff_cache_hash(
+    old: &MatchContent,
+    replacement: &str,
+    match_spans: &[Range<usize>],
+    colours: DiffColours,
+) -> u64 {
     let mut hasher = DefaultHasher::new();
     old.hash(&mut hasher);
     replacement.hash(&mut hasher);
+    match_spans.hash(&mut hasher);
+    colours.hash(&mut hasher);
     hasher.finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize) -> Vec<(usize, HighlightedLine)> {
+        (0..n)
+            .map(|i| (i, vec![(None, format!("line {i}"))]))
+            .collect()
+    }
+
+    fn keys(cache: &HighlightCache<&'static str>) -> Vec<&'static str> {
+        let mut keys = cache.entries.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_highlight_cache_evicts_by_entries() {
+        let mut cache = HighlightCache::new(NonZeroUsize::new(2).unwrap(), 100);
+        cache.put("a", lines(1));
+        cache.put("b", lines(1));
+        assert!(cache.get("a").is_some());
+        cache.put("c", lines(1));
+        assert_eq!(keys(&cache), vec!["a", "c"]);
+        assert_eq!(cache.num_lines, 2);
+    }
+
+    #[test]
+    fn test_highlight_cache_evicts_by_lines() {
+        let mut cache = HighlightCache::new(NonZeroUsize::new(10).unwrap(), 10);
+        cache.put("a", lines(4));
+        cache.put("b", lines(4));
+        cache.put("a", lines(2));
+        assert_eq!(cache.num_lines, 6);
+
+        cache.put("c", lines(5));
+        assert_eq!(keys(&cache), vec!["a", "c"]);
+        assert_eq!(cache.num_lines, 7);
+
+        // An entry larger than the limit is kept on its own
+        cache.put("d", lines(20));
+        assert_eq!(keys(&cache), vec!["d"]);
+        assert_eq!(cache.num_lines, 20);
+    }
+
+    #[test]
+    fn test_highlight_cache_set_limits_and_clear() {
+        let mut cache = HighlightCache::new(NonZeroUsize::new(10).unwrap(), 100);
+        for key in ["a", "b", "c", "d"] {
+            cache.put(key, lines(3));
+        }
+        cache.set_limits(NonZeroUsize::new(3).unwrap(), 100);
+        assert_eq!(keys(&cache), vec!["b", "c", "d"]);
+        cache.set_limits(NonZeroUsize::new(3).unwrap(), 5);
+        assert_eq!(keys(&cache), vec!["d"]);
+        assert_eq!(cache.num_lines, 3);
+
+        cache.clear();
+        assert!(keys(&cache).is_empty());
+        assert_eq!(cache.num_lines, 0);
+    }
+}
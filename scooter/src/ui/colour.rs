@@ -1,4 +1,5 @@
 use ratatui::style::Color;
+use scooter_core::config::Colour;
 use two_face::re_exports::syntect::highlighting::Color as SyntectColour;
 
 // Finds the index (0-5) for an RGB component corresponding to the closest xterm cube level:
@@ -50,6 +51,29 @@ pub fn to_ratatui_colour(colour: SyntectColour, true_colour: bool) -> Color {
     }
 }
 
+/// Converts a colour from the config file, using the terminal's own palette for named colours
+pub fn config_colour_to_ratatui(colour: Colour, true_colour: bool) -> Color {
+    match colour {
+        Colour::Black => Color::Black,
+        Colour::Red => Color::Red,
+        Colour::Green => Color::Green,
+        Colour::Yellow => Color::Yellow,
+        Colour::Blue => Color::Blue,
+        Colour::Magenta => Color::Magenta,
+        Colour::Cyan => Color::Cyan,
+        Colour::Gray => Color::Gray,
+        Colour::DarkGray => Color::DarkGray,
+        Colour::LightRed => Color::LightRed,
+        Colour::LightGreen => Color::LightGreen,
+        Colour::LightYellow => Color::LightYellow,
+        Colour::LightBlue => Color::LightBlue,
+        Colour::LightMagenta => Color::LightMagenta,
+        Colour::LightCyan => Color::LightCyan,
+        Colour::White => Color::White,
+        Colour::Rgb(r, g, b) => to_ratatui_colour(SyntectColour { r, g, b, a: 255 }, true_colour),
+    }
+}
+
 #[allow(clippy::identity_op, clippy::erasing_op)]
 #[cfg(test)]
 mod tests {
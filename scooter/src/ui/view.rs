@@ -3,29 +3,33 @@ use itertools::Itertools;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Flex, Layout, Position, Rect},
-    style::{Color, Style, Stylize},
+    style::{Color, Modifier, Style, Stylize},
     text::{Line, Span, Text},
     widgets::{Block, Cell, Clear, List, ListItem, Padding, Paragraph, Row, Table, Wrap},
 };
 use scooter_core::{
     app::{App, Event, FocussedSection, InputSource, Popup, Screen, SearchPhase, SearchState},
+    command_palette::CommandPaletteState,
     diff::{Diff, DiffColour, line_diff},
     errors::AppError,
-    fields::{Field, NUM_SEARCH_FIELDS, SearchField, SearchFields},
+    fields::{Field, FieldName, SearchField, SearchFields, TextField},
+    file_picker::FilePickerState,
+    mouse::ScreenArea,
     replace::{PerformingReplacementState, ReplaceState},
     search,
     utils::{
-        self, HighlightedLine, last_n_chars, read_lines_range_highlighted, relative_path,
-        strip_control_chars,
+        self, HighlightedLine, first_n_chars, last_n_chars, read_lines_range_highlighted,
+        relative_path_in_roots,
     },
 };
 use std::{
     borrow::Cow,
-    cmp::min,
+    cmp::{self, min},
+    collections::HashMap,
     fs,
     io::Cursor,
     iter,
-    ops::Div,
+    ops::{Div, Range},
     path::{Path, PathBuf},
     sync::{Arc, OnceLock, atomic::Ordering},
     time::Duration,
@@ -39,11 +43,17 @@ use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 use scooter_core::search::{MatchContent, SearchResultWithReplacement};
-use scooter_core::{config::Config, utils::read_lines_range};
+use scooter_core::{
+    config::{
+        ColorsConfig, Config, ControlChars, DisplayConfig, FieldsPosition, PathStyle,
+        PreviewLayout, SplitRatio, Truncate,
+    },
+    utils::read_lines_range,
+};
 
 use crate::ui::cache::{self, FileWindow};
 
-use super::colour::to_ratatui_colour;
+use super::colour::{config_colour_to_ratatui, to_ratatui_colour};
 
 fn create_title_spans<'a>(
     field: &SearchField,
@@ -119,30 +129,42 @@ pub fn render_search_field(
 }
 
 static SEARCH_FIELD_HEIGHT: u16 = 3;
-static NUM_SEARCH_FIELDS_TRUNCATED: u16 = 2;
+
+/// The fields to show above the search results: all visible fields when the fields are focussed,
+/// otherwise only the search and replace fields
+fn search_fields_to_render<'a>(
+    search_fields: &'a SearchFields,
+    focussed_section: &FocussedSection,
+) -> Vec<(usize, &'a SearchField)> {
+    search_fields
+        .visible_fields()
+        .filter(|(_, field)| {
+            *focussed_section == FocussedSection::SearchFields
+                || matches!(field.name, FieldName::Search | FieldName::Replace)
+        })
+        .collect()
+}
 
 fn render_search_fields(
     frame: &mut Frame<'_>,
     search_fields: &SearchFields,
+    fields_to_render: &[(usize, &SearchField)],
     config: &Config,
     show_popup: bool,
-    num_search_fields_to_render: u16,
     is_focussed: bool,
     area: Rect,
 ) {
     let areas = Layout::vertical(iter::repeat_n(
         Constraint::Length(SEARCH_FIELD_HEIGHT),
-        num_search_fields_to_render as usize,
+        fields_to_render.len(),
     ))
     .flex(Flex::Center)
     .split(area);
 
-    search_fields
-        .fields
+    fields_to_render
         .iter()
         .zip(areas.iter())
-        .enumerate()
-        .for_each(|(idx, (search_field, &field_area))| {
+        .for_each(|(&(idx, search_field), &field_area)| {
             render_search_field(
                 search_field,
                 frame,
@@ -156,8 +178,11 @@ fn render_search_fields(
         let field = search_fields.highlighted_field();
         if !(field.set_by_cli && config.search.disable_prepopulated_fields)
             && let Some(cursor_pos) = field.cursor_pos()
+            && let Some(area_idx) = fields_to_render
+                .iter()
+                .position(|(idx, _)| *idx == search_fields.highlighted)
         {
-            let highlighted_area = areas[search_fields.highlighted];
+            let highlighted_area = areas[area_idx];
 
             frame.set_cursor_position(Position {
                 x: highlighted_area.x + u16::try_from(cursor_pos).unwrap_or(0) + 1,
@@ -179,18 +204,52 @@ fn width(area: Rect, percentage: u16) -> Rect {
     area
 }
 
-fn diff_col_to_ratatui(colour: &DiffColour) -> Color {
+/// The colours used to show removed and added lines in the preview
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct DiffColours {
+    old: Color,
+    new: Color,
+    highlight: Color,
+    unchanged: Color,
+}
+
+impl DiffColours {
+    pub(crate) fn from_config(colors: &ColorsConfig, true_colour: bool) -> Self {
+        Self {
+            old: config_colour_to_ratatui(colors.diff_old, true_colour),
+            new: config_colour_to_ratatui(colors.diff_new, true_colour),
+            highlight: config_colour_to_ratatui(colors.diff_highlight, true_colour),
+            unchanged: colors.diff_unchanged.map_or(Color::Reset, |colour| {
+                config_colour_to_ratatui(colour, true_colour)
+            }),
+        }
+    }
+
+    /// The style of text in a removed or added line that is unaffected by the replacement
+    fn unchanged_style(self) -> Style {
+        Style::new().fg(self.unchanged)
+    }
+}
+
+impl Default for DiffColours {
+    fn default() -> Self {
+        Self::from_config(&ColorsConfig::default(), false)
+    }
+}
+
+fn diff_col_to_ratatui(colour: &DiffColour, colours: DiffColours) -> Color {
     match colour {
-        DiffColour::Red => Color::Red,
-        DiffColour::Green => Color::Green,
-        DiffColour::Black => Color::Black,
+        DiffColour::Red => colours.old,
+        DiffColour::Green => colours.new,
+        DiffColour::Black => colours.highlight,
+        DiffColour::Neutral => colours.unchanged,
     }
 }
 
-fn diff_to_style(d: &Diff) -> Style {
-    let mut style = Style::new().fg(diff_col_to_ratatui(&d.fg_colour));
+fn diff_to_style(d: &Diff, colours: DiffColours) -> Style {
+    let mut style = Style::new().fg(diff_col_to_ratatui(&d.fg_colour, colours));
     if let Some(bg) = &d.bg_colour {
-        style = style.bg(diff_col_to_ratatui(bg));
+        style = style.bg(diff_col_to_ratatui(bg, colours));
     }
     style
 }
@@ -210,36 +269,88 @@ impl DiffLineKind {
         }
     }
 
-    fn color(self) -> Color {
+    fn color(self, colours: DiffColours) -> Color {
         match self {
-            Self::Added => Color::Green,
-            Self::Removed => Color::Red,
+            Self::Added => colours.new,
+            Self::Removed => colours.old,
         }
     }
 
-    fn style(self) -> Style {
-        Style::new().fg(self.color())
+    fn style(self, colours: DiffColours) -> Style {
+        Style::new().fg(self.color(colours))
     }
 
-    fn prefix_segment(self) -> StyledSegment {
-        styled_segment(self.prefix(), self.style())
+    fn prefix_segment(self, colours: DiffColours) -> StyledSegment {
+        styled_segment(self.prefix(), self.style(colours))
     }
 }
 
 /// Converts diffs to multiple styled lines, splitting on newlines.
 /// Each output line starts with the appropriate prefix.
 /// Strips trailing \r from lines to handle CRLF line endings cleanly.
-fn diffs_to_lines(diffs: &[Diff], kind: DiffLineKind) -> Vec<StyledLine> {
-    let mut lines = Vec::new();
-    let mut current_line = vec![kind.prefix_segment()];
+fn diffs_to_lines(diffs: &[Diff], kind: DiffLineKind, colours: DiffColours) -> Vec<StyledLine> {
+    styled_parts_to_lines(
+        diffs
+            .iter()
+            .map(|d| (d.text.as_str(), diff_to_style(d, colours))),
+        kind,
+        colours,
+    )
+}
 
+/// Splits diffs at the boundaries of `match_spans`, which are byte ranges into the
+/// concatenated text of `diffs`, and underlines the parts that fall within a match.
+fn diffs_with_match_spans<'a>(
+    diffs: &'a [Diff],
+    match_spans: &[Range<usize>],
+    colours: DiffColours,
+) -> Vec<(&'a str, Style)> {
+    let mut parts = Vec::new();
+    let mut pos = 0;
     for d in diffs {
-        let style = diff_to_style(d);
+        let style = diff_to_style(d, colours);
+        let (start, end) = (pos, pos + d.text.len());
+        pos = end;
+
+        let mut boundaries = vec![start, end];
+        boundaries.extend(
+            match_spans
+                .iter()
+                .flat_map(|span| [span.start, span.end])
+                .filter(|&b| start < b && b < end),
+        );
+        boundaries.sort_unstable();
+        boundaries.dedup();
 
-        for (i, part) in d.text.split('\n').enumerate() {
+        for window in boundaries.windows(2) {
+            let (part_start, part_end) = (window[0], window[1]);
+            let in_match = match_spans
+                .iter()
+                .any(|span| span.start <= part_start && part_end <= span.end);
+            let style = if in_match {
+                style.add_modifier(Modifier::UNDERLINED)
+            } else {
+                style
+            };
+            parts.push((&d.text[part_start - start..part_end - start], style));
+        }
+    }
+    parts
+}
+
+fn styled_parts_to_lines<'a>(
+    parts: impl IntoIterator<Item = (&'a str, Style)>,
+    kind: DiffLineKind,
+    colours: DiffColours,
+) -> Vec<StyledLine> {
+    let mut lines = Vec::new();
+    let mut current_line = vec![kind.prefix_segment(colours)];
+
+    for (text, style) in parts {
+        for (i, part) in text.split('\n').enumerate() {
             if i > 0 {
                 lines.push(current_line);
-                current_line = vec![kind.prefix_segment()];
+                current_line = vec![kind.prefix_segment(colours)];
             }
             let part = part.strip_suffix('\r').unwrap_or(part);
             if !part.is_empty() {
@@ -263,25 +374,39 @@ enum BannerStatus {
     Empty,
     Invalid,
     InProgress,
+    Paused,
     Complete,
 }
 
-#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+#[allow(
+    clippy::too_many_arguments,
+    clippy::too_many_lines,
+    clippy::fn_params_excessive_bools
+)]
 fn render_search_results(
     frame: &mut Frame<'_>,
     input_source: &InputSource,
     search_state: &mut SearchState,
+    search_paused: bool,
     area: Rect,
     theme: Option<&Theme>,
     true_colour: bool,
+    diff_colours: DiffColours,
+    control_chars: ControlChars,
     event_sender: UnboundedSender<Event>,
     area_is_focussed: bool,
     preview_update_status: Option<(usize, usize)>,
     wrap: bool,
-) {
-    let small_screen = area.width <= 110;
-
-    let [num_results_area, results_area, _] = Layout::vertical([
+    show_nearby_replacements: bool,
+    line_numbers: bool,
+    line_prefix: &str,
+    wrapped_prefix: &str,
+    layout: PreviewLayout,
+    active_filters: Option<&str>,
+    find_term: Option<&str>,
+    display: &DisplayConfig,
+) -> Rect {
+    let [num_results_area, results_area, filters_area] = Layout::vertical([
         Constraint::Length(2),
         Constraint::Fill(1),
         Constraint::Length(1),
@@ -293,72 +418,68 @@ fn render_search_results(
     let status = match search_state.phase {
         SearchPhase::Invalid => BannerStatus::Invalid,
         _ if search_state.phase.is_complete() => BannerStatus::Complete,
+        _ if search_paused => BannerStatus::Paused,
         _ => BannerStatus::InProgress,
     };
     render_num_results(
         frame,
         num_results_area,
         num_results,
+        search_state.num_included(),
+        search_state.num_noops_skipped,
         status,
         search_state.phase.elapsed(),
+        search_state.files_scanned,
         preview_update_status,
     );
 
-    let num_to_render = if small_screen {
-        5
-    } else {
-        results_area.height as usize
-    };
-
-    search_state.num_displayed = Some(num_to_render);
-
-    if search_state.primary_selected_pos() < search_state.view_offset + 1 {
-        search_state.view_offset = search_state.primary_selected_pos().saturating_sub(1);
-    } else if search_state.primary_selected_pos()
-        > (search_state.view_offset + num_to_render).saturating_sub(2)
-        || search_state.view_offset + num_to_render > num_results
-    {
-        search_state.view_offset = min(
-            (search_state.primary_selected_pos() + 2).saturating_sub(num_to_render),
-            num_results.saturating_sub(num_to_render),
+    if let Some(active_filters) = active_filters {
+        frame.render_widget(
+            Paragraph::new(active_filters).fg(Color::DarkGray),
+            filters_area,
         );
     }
 
-    let (list_area, preview_area) = if small_screen {
-        let [list_area, _, preview_area] = Layout::vertical([
-            #[allow(clippy::cast_possible_truncation)]
-            Constraint::Length(num_to_render as u16),
-            Constraint::Length(1),
-            Constraint::Fill(1),
-        ])
-        .areas(results_area);
-        (list_area, preview_area)
-    } else {
-        let [list_area, _, preview_area] = Layout::horizontal([
-            Constraint::Fill(2),
-            Constraint::Length(1),
-            Constraint::Fill(3),
-        ])
-        .areas(results_area);
-        (list_area, preview_area)
-    };
+    let (list_area, preview_area) = list_and_preview_areas(results_area, display);
+    let num_to_render = list_area.height as usize;
+    let mut preview_scroll_offset = search_state.preview_scroll_offset();
 
-    let base_path = match &input_source {
-        InputSource::Directory(dir) => dir,
-        InputSource::Stdin(_) => &PathBuf::from("."),
+    search_state.num_displayed = Some(num_to_render);
+    search_state.view_offset = updated_view_offset(
+        search_state.view_offset,
+        search_state.primary_selected_pos(),
+        num_to_render,
+        num_results,
+    );
+
+    let roots = match &input_source {
+        InputSource::Directory(directories) => directories.as_slice(),
+        InputSource::Stdin(_) => &[PathBuf::from(".")],
+    };
+    let nearby_replacements = match preview_area {
+        Some(preview_area) if show_nearby_replacements => {
+            nearby_replacements(search_state, preview_area.height as usize)
+        }
+        _ => HashMap::new(),
     };
     let search_results = build_search_results(
         search_state,
-        base_path,
+        roots,
+        display.path_style,
+        display.truncate,
         list_area.width,
         num_to_render,
         area_is_focussed,
+        find_term,
     );
     let search_results_list = search_results
         .iter()
         .map(|SearchResultListItem { file_path, .. }| ListItem::new(file_path.clone()));
     frame.render_widget(List::new(search_results_list), list_area);
 
+    let Some(preview_area) = preview_area else {
+        return list_area;
+    };
     if !search_results.is_empty() {
         let selected = search_results
             .iter()
@@ -371,25 +492,42 @@ fn render_search_results(
                 Paragraph::new(format!("Error generating preview: {error}")).fg(Color::Red),
                 preview_area,
             );
+        } else if let Err(e) = validate_preview_prefixes(
+            line_prefix,
+            wrap.then_some(wrapped_prefix),
+            preview_area.width,
+        ) {
+            frame.render_widget(
+                Paragraph::new(format!("Error generating preview: {e}")).fg(Color::Red),
+                preview_area,
+            );
         } else {
-            let preview = build_search_result_preview(selected.result, event_sender.clone());
-
+            let preview =
+                build_search_result_preview(selected.result, diff_colours, event_sender.clone())
+                    .with_side_by_side(SideBySide::new(layout, preview_area.width, control_chars));
             match build_preview_list(
                 input_source,
                 lines_to_show,
+                &mut preview_scroll_offset,
                 selected.result,
                 &preview,
+                &nearby_replacements,
                 theme,
                 true_colour,
+                diff_colours,
+                control_chars,
                 event_sender,
                 if wrap {
                     WrapText::Width {
                         width: preview_area.width,
                         num_lines: lines_to_show,
+                        prefix: wrapped_prefix,
                     }
                 } else {
                     WrapText::None
                 },
+                line_numbers,
+                line_prefix,
             ) {
                 Ok(preview) => {
                     frame.render_widget(preview, preview_area);
@@ -403,6 +541,169 @@ fn render_search_results(
             }
         }
     }
+    search_state.set_preview_scroll_offset(preview_scroll_offset);
+    list_area
+}
+
+/// Checks that the configured prefixes leave room for the content of each preview line, where
+/// `wrapped_prefix` is `None` if lines aren't wrapped
+fn validate_preview_prefixes(
+    line_prefix: &str,
+    wrapped_prefix: Option<&str>,
+    width: u16,
+) -> anyhow::Result<()> {
+    for (name, prefix) in [
+        ("line_prefix", Some(line_prefix)),
+        ("wrapped_prefix", wrapped_prefix),
+    ] {
+        if let Some(prefix) = prefix
+            && UnicodeWidthStr::width(prefix) >= width as usize
+        {
+            bail!("`preview.{name}` must be narrower than the preview");
+        }
+    }
+    Ok(())
+}
+
+/// Scrolls a list so that the selected item is in view, keeping an item of context above and below
+/// it where possible
+fn updated_view_offset(
+    view_offset: usize,
+    selected: usize,
+    num_to_render: usize,
+    num_items: usize,
+) -> usize {
+    if selected < view_offset + 1 {
+        selected.saturating_sub(1)
+    } else if selected > (view_offset + num_to_render).saturating_sub(2)
+        || view_offset + num_to_render > num_items
+    {
+        min(
+            (selected + 2).saturating_sub(num_to_render),
+            num_items.saturating_sub(num_to_render),
+        )
+    } else {
+        view_offset
+    }
+}
+
+/// Splits the search results area into the results list and the preview, returning `None` for the
+/// preview if it is hidden. On narrow terminals the preview is shown below the list rather than beside it,
+/// and below `min_preview_width` it is hidden entirely.
+fn list_and_preview_areas(area: Rect, display: &DisplayConfig) -> (Rect, Option<Rect>) {
+    let small_screen = area.width <= 110;
+    let ratio = if small_screen {
+        display.vertical_list_preview_ratio
+    } else {
+        Some(display.list_preview_ratio)
+    };
+    if !display.show_preview
+        || area.width < display.min_preview_width
+        || ratio.is_some_and(|r| r.preview == 0)
+    {
+        return (area, None);
+    }
+
+    let (list, preview) = match ratio {
+        Some(SplitRatio { list, preview }) => (Constraint::Fill(list), Constraint::Fill(preview)),
+        None => (Constraint::Length(5), Constraint::Fill(1)),
+    };
+    let layout = if small_screen {
+        Layout::vertical([list, Constraint::Length(1), preview])
+    } else {
+        Layout::horizontal([list, Constraint::Length(1), preview])
+    };
+    let [list_area, _, preview_area] = layout.areas(area);
+    (list_area, Some(preview_area))
+}
+
+/// Splits the search screen into the search fields, which are `fields_height` lines tall, and the search
+/// results, with a blank line between them
+fn search_fields_and_results_areas(
+    area: Rect,
+    fields_height: u16,
+    fields_position: FieldsPosition,
+) -> (Rect, Rect) {
+    let fields = Constraint::Length(fields_height);
+    let results = Constraint::Fill(1);
+    match fields_position {
+        FieldsPosition::Top => {
+            let [fields_area, _, results_area] =
+                Layout::vertical([fields, Constraint::Length(1), results]).areas(area);
+            (fields_area, results_area)
+        }
+        FieldsPosition::Bottom => {
+            let [results_area, _, fields_area] =
+                Layout::vertical([results, Constraint::Length(1), fields]).areas(area);
+            (fields_area, results_area)
+        }
+    }
+}
+
+/// Summary of the filters constraining which files are searched, e.g. `globs: *.rs | excl: target/* | hidden: on`.
+/// Returns `None` if no filters are active, or if searching stdin (where file filters don't apply).
+fn active_filters_summary(
+    input_source: &InputSource,
+    search_fields: &SearchFields,
+    include_hidden: bool,
+) -> Option<String> {
+    if matches!(input_source, InputSource::Stdin(_)) {
+        return None;
+    }
+
+    let include_files = search_fields.include_files().text();
+    let exclude_files = search_fields.exclude_files().text();
+    let mut filters = vec![];
+    if !include_files.is_empty() {
+        filters.push(format!("globs: {include_files}"));
+    }
+    if !exclude_files.is_empty() {
+        filters.push(format!("excl: {exclude_files}"));
+    }
+    if include_hidden {
+        filters.push("hidden: on".to_owned());
+    }
+
+    if filters.is_empty() {
+        None
+    } else {
+        Some(filters.join(" | "))
+    }
+}
+
+/// Replacements for the other included results in the same file as the primary selected result, keyed by
+/// 0-indexed line number. Only results within `num_lines` of the selected result are considered, as these are
+/// the only ones that can appear in the preview. Results from a single file are contiguous and ordered by line.
+fn nearby_replacements(search_state: &SearchState, num_lines: usize) -> HashMap<usize, String> {
+    let selected_pos = search_state.primary_selected_pos();
+    let Some(selected) = search_state.results.get(selected_pos) else {
+        return HashMap::new();
+    };
+    let line_idx = selected.search_result.start_line_number() - 1;
+    let window = line_idx.saturating_sub(num_lines)..=line_idx + num_lines;
+    let in_window = |res: &SearchResultWithReplacement| {
+        res.search_result.path == selected.search_result.path
+            && window.contains(&(res.search_result.start_line_number() - 1))
+    };
+
+    let before = search_state.results[..selected_pos]
+        .iter()
+        .rev()
+        .take_while(|res| in_window(res));
+    let after = search_state.results[selected_pos + 1..]
+        .iter()
+        .take_while(|res| in_window(res));
+
+    before
+        .chain(after)
+        .filter(|res| res.search_result.included && res.preview_error.is_none())
+        .filter_map(|res| match &res.search_result.content {
+            MatchContent::Line { line_number, .. } => {
+                Some((line_number - 1, res.replacement.clone()))
+            }
+            MatchContent::ByteRange { .. } => None,
+        })
+        .collect()
 }
 
 fn render_empty_search_banner(
@@ -416,38 +717,66 @@ fn render_empty_search_banner(
         frame,
         num_results_area,
         0,
+        0,
+        0,
         BannerStatus::Empty,
         None,
+        None,
         num_replacements_updates_in_progress,
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_num_results(
     frame: &mut Frame<'_>,
     area: Rect,
     num_results: usize,
+    num_included: usize,
+    num_noops_skipped: usize,
     status: BannerStatus,
     time_taken: Option<Duration>,
+    files_scanned: Option<usize>,
     num_replacements_updates_in_progress: Option<(usize, usize)>,
 ) {
-    let left_content_1 = format!("Results: {num_results}");
+    let mut details = vec![];
+    if num_results > 0 {
+        details.push(format!("included: {num_included}/{num_results}"));
+    }
+    if num_noops_skipped > 0 {
+        details.push(format!("{num_noops_skipped} no-ops skipped"));
+    }
+    let left_content_1 = if details.is_empty() {
+        format!("Results: {num_results}")
+    } else {
+        format!("Results: {num_results} ({})", details.join(", "))
+    };
     let (left_content_2, accessory_colour) = match status {
-        BannerStatus::Empty => (" [Search is empty]", Color::Red),
-        BannerStatus::Invalid => (" [Invalid search]", Color::Red),
-        BannerStatus::InProgress => (" [Still searching...]", Color::Blue),
-        BannerStatus::Complete => (" [Search complete]", Color::Green),
+        BannerStatus::Empty => (" [Search is empty]".to_owned(), Color::Red),
+        BannerStatus::Invalid => (" [Invalid search]".to_owned(), Color::Red),
+        BannerStatus::InProgress => (
+            search_progress_status(time_taken, files_scanned),
+            Color::Blue,
+        ),
+        BannerStatus::Paused => (search_paused_status(files_scanned), Color::Yellow),
+        BannerStatus::Complete => (" [Search complete]".to_owned(), Color::Green),
     };
     let mid_content = preview_update_status(num_replacements_updates_in_progress);
     let right_content = time_taken
         .map(|t| format!(" [Time taken: {}]", display_duration(t)))
         .unwrap_or_default();
     let num_total_spacers = (area.width as usize).saturating_sub(
-        left_content_1.len() + left_content_2.len() + mid_content.len() + right_content.len(),
+        left_content_1.len()
+            + left_content_2.chars().count()
+            + mid_content.len()
+            + right_content.len(),
     );
     let spacers_each_side = " ".repeat(num_total_spacers / 2);
     let time_colour = match status {
         BannerStatus::Complete => Color::Green,
-        BannerStatus::Empty | BannerStatus::Invalid | BannerStatus::InProgress => Color::Blue,
+        BannerStatus::Empty
+        | BannerStatus::Invalid
+        | BannerStatus::InProgress
+        | BannerStatus::Paused => Color::Blue,
     };
 
     frame.render_widget(
@@ -463,6 +792,30 @@ fn render_num_results(
     );
 }
 
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// The spinner frame to show after `elapsed`, advancing every 100ms
+fn spinner_frame(elapsed: Duration) -> char {
+    SPINNER_FRAMES[(elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len()]
+}
+
+fn search_progress_status(elapsed: Option<Duration>, files_scanned: Option<usize>) -> String {
+    let spinner = elapsed.map_or(SPINNER_FRAMES[0], spinner_frame);
+    match files_scanned {
+        Some(files_scanned) => {
+            format!(" [{spinner} Still searching... {files_scanned} files scanned]")
+        }
+        None => format!(" [{spinner} Still searching...]"),
+    }
+}
+
+fn search_paused_status(files_scanned: Option<usize>) -> String {
+    match files_scanned {
+        Some(files_scanned) => format!(" [Search paused, {files_scanned} files scanned]"),
+        None => " [Search paused]".to_owned(),
+    }
+}
+
 fn preview_update_status(num_replacements_updates_in_progress: Option<(usize, usize)>) -> String {
     if let Some((complete, total)) = num_replacements_updates_in_progress {
         // Avoid flickering - only show if it will take some time
@@ -478,12 +831,16 @@ fn preview_update_status(num_replacements_updates_in_progress: Option<(usize, us
     String::new()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_search_results<'a>(
     search_state: &'a mut SearchState,
-    base_path: &Path,
+    roots: &[PathBuf],
+    path_style: PathStyle,
+    truncate: Truncate,
     width: u16,
     num_to_render: usize,
     area_is_focussed: bool,
+    find_term: Option<&str>,
 ) -> Vec<SearchResultListItem<'a>> {
     search_state
         .results
@@ -492,14 +849,28 @@ fn build_search_results<'a>(
         .skip(search_state.view_offset)
         .take(num_to_render)
         .map(|(idx, result)| {
+            // Held back results are counted after the last result shown from their file
+            let is_last_in_file = search_state
+                .results
+                .get(idx + 1)
+                .is_none_or(|next| next.search_result.path != result.search_result.path);
+            let num_held_back = if is_last_in_file {
+                search_state.num_held_back(&result.search_result.path)
+            } else {
+                0
+            };
             search_result(
                 idx,
                 search_state.is_selected(idx),
                 search_state.is_primary_selected(idx),
                 result,
-                base_path,
+                num_held_back,
+                roots,
+                path_style,
+                truncate,
                 width,
                 area_is_focussed,
+                find_term,
             )
         })
         .collect()
@@ -527,14 +898,64 @@ fn convert_syntect_to_ratatui_style(syntect_style: &SyntectStyle, true_colour: b
 type StyledSegment = (Cow<'static, str>, Option<Style>);
 type StyledLine = Vec<StyledSegment>;
 
-static PREVIEW_LINE_PREFIX: &str = "  ";
-static WRAPPED_LINE_PREFIX: &str = "  ↪ ";
+/// The line numbers shown to the left of each preview line when `preview.line_numbers` is enabled
+#[derive(Clone, Copy, Debug)]
+struct LineNumberGutter {
+    width: usize,
+}
+
+impl LineNumberGutter {
+    /// Returns `None` if line numbers are disabled, otherwise a gutter wide enough for the numbers of
+    /// lines up to `last_line_idx`
+    fn new(line_numbers: bool, last_line_idx: usize) -> Option<Self> {
+        line_numbers.then(|| Self {
+            width: (last_line_idx + 1).to_string().len(),
+        })
+    }
+
+    /// The number of columns taken up by the gutter, including the space after each number
+    fn total_width(self) -> usize {
+        self.width + 1
+    }
+
+    /// Prefixes `line` with the number of the line at `line_idx`, or with blank space for lines that
+    /// aren't in the file, such as added lines
+    fn prefix(self, line: StyledLine, line_idx: Option<usize>) -> StyledLine {
+        let number = line_idx.map_or(String::new(), |idx| (idx + 1).to_string());
+        let gutter = format!("{number:>width$} ", width = self.width);
+        iter::once((Cow::Owned(gutter), Some(Style::new().fg(Color::DarkGray))))
+            .chain(line)
+            .collect()
+    }
+}
+
+/// Prefixes `lines` with line numbers if `gutter` is set, where the first line is at `first_line_idx`
+/// and only the first `num_numbered` lines exist in the file
+fn number_lines(
+    lines: Vec<StyledLine>,
+    first_line_idx: usize,
+    num_numbered: usize,
+    gutter: Option<LineNumberGutter>,
+) -> Vec<StyledLine> {
+    let Some(gutter) = gutter else {
+        return lines;
+    };
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| gutter.prefix(line, (i < num_numbered).then_some(first_line_idx + i)))
+        .collect()
+}
 
-fn regions_to_line(line: &[(Option<SyntectStyle>, String)], true_colour: bool) -> StyledLine {
-    iter::once((Cow::Borrowed(PREVIEW_LINE_PREFIX), None))
+fn regions_to_line(
+    line: &[(Option<SyntectStyle>, String)],
+    line_prefix: &str,
+    true_colour: bool,
+) -> StyledLine {
+    iter::once((Cow::Owned(line_prefix.to_owned()), None))
         .chain(line.iter().map(|(style, s)| {
             (
-                Cow::Owned(strip_control_chars(s).into_owned()),
+                Cow::Owned(s.clone()),
                 style
                     .as_ref()
                     .map(|style| convert_syntect_to_ratatui_style(style, true_colour)),
@@ -543,54 +964,131 @@ fn regions_to_line(line: &[(Option<SyntectStyle>, String)], true_colour: bool) -
         .collect()
 }
 
-fn to_line_plain(line: &str) -> StyledLine {
-    vec![(
-        Cow::Owned(format!(
-            "{PREVIEW_LINE_PREFIX}{}",
-            strip_control_chars(line)
-        )),
-        None,
-    )]
+fn to_line_plain(line: &str, line_prefix: &str) -> StyledLine {
+    vec![(Cow::Owned(format!("{line_prefix}{line}")), None)]
+}
+
+/// Converts context lines around the selected result into styled lines, swapping in the replacement for
+/// any line that has one in `replacements`
+fn context_lines<T>(
+    lines: &[(usize, T)],
+    replacements: &HashMap<usize, String>,
+    colours: DiffColours,
+    gutter: Option<LineNumberGutter>,
+    to_line: impl Fn(&T) -> StyledLine,
+) -> Vec<StyledLine> {
+    lines
+        .iter()
+        .flat_map(|(idx, line)| {
+            let styled = match replacements.get(idx) {
+                Some(replacement) => replacement
+                    .split('\n')
+                    .map(|l| simple_styled_line(DiffLineKind::Added, l, colours))
+                    .collect(),
+                None => vec![to_line(line)],
+            };
+            // Only the first line of a replacement takes the place of the line in the file
+            number_lines(styled, *idx, 1, gutter)
+        })
+        .collect()
+}
+
+/// As with `context_lines`, but keeps only the lines closest to the selected result if replacements
+/// have added lines, so that the selected result stays in the same position in the preview
+fn context_lines_before<T>(
+    lines: &[(usize, T)],
+    replacements: &HashMap<usize, String>,
+    colours: DiffColours,
+    gutter: Option<LineNumberGutter>,
+    to_line: impl Fn(&T) -> StyledLine,
+) -> Vec<StyledLine> {
+    let styled = context_lines(lines, replacements, colours, gutter, to_line);
+    utils::last_n(&styled, lines.len()).to_vec()
 }
 
+/// Highlight the whole of the file at `path` in the background, caching the result so that the
+/// preview can be upgraded from the partial highlighting of the window around the current result.
+/// Does nothing if the file is already being highlighted
 fn spawn_highlight_full_file(path: PathBuf, theme: Theme, event_sender: UnboundedSender<Event>) {
+    if !cache::full_file_highlights_in_progress()
+        .lock()
+        .unwrap()
+        .insert(path.clone())
+    {
+        return;
+    }
+    log::debug!(
+        "Showing partial highlighting for {} while highlighting full file",
+        path.display()
+    );
+
+    let generation = cache::preview_cache_generation();
     tokio::spawn(async move {
-        match fs::metadata(&path) {
-            Ok(metadata) => {
-                const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB limit
-                if metadata.len() > MAX_FILE_SIZE {
-                    log::info!(
-                        "File {} too large for caching ({} bytes)",
-                        path.display(),
-                        metadata.len()
-                    );
-                    return;
-                }
-            }
-            Err(e) => {
-                log::error!("Error reading file metadata for {}: {e}", path.display());
+        let full = highlight_full_file(&path, &theme);
+        let mut cached = false;
+        if let Some(full) = full {
+            let mut cache_guard = cache::highlighted_file_cache().lock().unwrap();
+            // Skip caching if the caches were cleared while highlighting, as the file may have changed
+            if cache::preview_cache_generation() == generation {
+                log::debug!(
+                    "Finished highlighting full file {}, upgrading preview",
+                    path.display()
+                );
+                cache_guard.put(path.clone(), full);
+                cached = true;
             }
         }
+        // Only remove once cached, so that a render in between doesn't start another job
+        cache::full_file_highlights_in_progress()
+            .lock()
+            .unwrap()
+            .remove(&path);
+
+        if cached {
+            // Ignore error - likely app has closed
+            let _ = event_sender.send(Event::Rerender);
+        }
+    });
+}
 
-        let syntax_set = SYNTAX_SET.get_or_init(two_face::syntax::extra_no_newlines);
-        let full = match read_lines_range_highlighted(&path, None, None, &theme, syntax_set, true) {
-            Ok(full) => full.collect(),
-            Err(e) => {
-                log::error!("Error highlighting file {}: {e}", path.display());
-                return;
+fn highlight_full_file(path: &Path, theme: &Theme) -> Option<Vec<(usize, HighlightedLine)>> {
+    match fs::metadata(path) {
+        Ok(metadata) => {
+            const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB limit
+            if metadata.len() > MAX_FILE_SIZE {
+                log::info!(
+                    "File {} too large for caching ({} bytes)",
+                    path.display(),
+                    metadata.len()
+                );
+                return None;
             }
-        };
-
-        let mut cache_guard = cache::highlighted_file_cache().lock().unwrap();
-        cache_guard.put(path, full);
+        }
+        Err(e) => {
+            log::error!("Error reading file metadata for {}: {e}", path.display());
+        }
+    }
 
-        // Ignore error - likely app has closed
-        let _ = event_sender.send(Event::Rerender);
-    });
+    let syntax_set = SYNTAX_SET.get_or_init(two_face::syntax::extra_no_newlines);
+    match read_lines_range_highlighted(path, None, None, theme, syntax_set, true) {
+        Ok(full) => Some(full.collect()),
+        Err(e) => {
+            log::error!("Error highlighting file {}: {e}", path.display());
+            None
+        }
+    }
 }
 
 /// Compute a full character-level diff between original and replacement content.
-fn compute_detailed_diff(old: &MatchContent, new: &str) -> SearchResultPreview {
+///
+/// For line-mode results, `match_spans` are the byte ranges of each match within the original
+/// line, which are underlined so that it's clear exactly what the search matched.
+fn compute_detailed_diff(
+    old: &MatchContent,
+    new: &str,
+    match_spans: &[Range<usize>],
+    colours: DiffColours,
+) -> SearchResultPreview {
     match old {
         MatchContent::ByteRange {
             lines,
@@ -602,13 +1100,18 @@ fn compute_detailed_diff(old: &MatchContent, new: &str) -> SearchResultPreview {
             *match_start_in_first_line,
             *match_end_in_last_line,
             new,
+            colours,
         ),
         MatchContent::Line { content, .. } => {
             let (old_diffs, new_diffs) = line_diff(content, new);
-            SearchResultPreview {
-                old_line_diffs: diffs_to_lines(&old_diffs, DiffLineKind::Removed),
-                new_line_diffs: diffs_to_lines(&new_diffs, DiffLineKind::Added),
-            }
+            SearchResultPreview::new(
+                styled_parts_to_lines(
+                    diffs_with_match_spans(&old_diffs, match_spans, colours),
+                    DiffLineKind::Removed,
+                    colours,
+                ),
+                diffs_to_lines(&new_diffs, DiffLineKind::Added, colours),
+            )
         }
     }
 }
@@ -619,12 +1122,17 @@ fn diff_cache_entry_for_key(
     hash_key: u64,
     old_content: &MatchContent,
     replacement: &str,
+    match_spans: &[Range<usize>],
+    colours: DiffColours,
 ) -> Option<cache::DiffTaskCacheEntry> {
     cache_guard.get_mut(&hash_key).and_then(|records| {
         records
             .iter()
             .find(|record| {
-                record.full_key.old == *old_content && record.full_key.replacement == replacement
+                record.full_key.old == *old_content
+                    && record.full_key.replacement == replacement
+                    && record.full_key.match_spans == match_spans
+                    && record.full_key.colours == colours
             })
             .map(|record| record.entry.clone())
     })
@@ -681,7 +1189,12 @@ fn spawn_compute_diff(
     tokio::spawn(async move {
         let compute_key = full_key.clone();
         let compute_result = tokio::task::spawn_blocking(move || {
-            compute_detailed_diff(&compute_key.old, &compute_key.replacement)
+            compute_detailed_diff(
+                &compute_key.old,
+                &compute_key.replacement,
+                &compute_key.match_spans,
+                compute_key.colours,
+            )
         })
         .await;
 
@@ -740,7 +1253,9 @@ fn read_lines_range_highlighted_with_cache(
     }
     drop(cache_guard);
 
-    // Not in cache, so kick off background job to populate
+    // Not in cache, so kick off background job to populate. Until it completes, the window around
+    // the result is highlighted on its own, with the same line numbers as the full file so that
+    // the preview doesn't shift when it is upgraded
     spawn_highlight_full_file(path.to_path_buf(), theme.clone(), event_sender.clone());
 
     // Check highlighted window cache
@@ -817,6 +1332,7 @@ fn spawn_highlight_window(
     theme: Theme,
     event_sender: UnboundedSender<Event>,
 ) {
+    let generation = cache::preview_cache_generation();
     tokio::spawn(async move {
         let syntax_set = SYNTAX_SET.get_or_init(two_face::syntax::extra_no_newlines);
         let lines = match read_lines_range_highlighted(
@@ -835,6 +1351,9 @@ fn spawn_highlight_window(
         };
 
         let mut cache_guard = cache::highlighted_window_cache().lock().unwrap();
+        if cache::preview_cache_generation() != generation {
+            return;
+        }
         cache_guard.put(FileWindow { path, start, end }, lines);
 
         // Ignore error - likely app has closed
@@ -848,6 +1367,7 @@ fn spawn_read_plain_window(
     end: usize,
     event_sender: UnboundedSender<Event>,
 ) {
+    let generation = cache::preview_cache_generation();
     tokio::spawn(async move {
         let lines = match read_lines_range(&path, start, end) {
             Ok(lines) => lines.collect(),
@@ -858,6 +1378,9 @@ fn spawn_read_plain_window(
         };
 
         let mut cache_guard = cache::plain_window_cache().lock().unwrap();
+        if cache::preview_cache_generation() != generation {
+            return;
+        }
         cache_guard.put(FileWindow { path, start, end }, lines);
 
         // Ignore error - likely app has closed
@@ -866,53 +1389,114 @@ fn spawn_read_plain_window(
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-enum WrapText {
+enum WrapText<'a> {
     None,
-    Width { width: u16, num_lines: u16 },
+    Width {
+        width: u16,
+        num_lines: u16,
+        /// Shown at the start of each continuation line
+        prefix: &'a str,
+    },
 }
 
 #[allow(clippy::too_many_arguments)]
 fn build_preview_list<'a>(
     input_source: &InputSource,
     num_lines_to_show: u16,
+    scroll_offset: &mut isize,
     result: &SearchResultWithReplacement,
     preview: &SearchResultPreview,
+    nearby_replacements: &HashMap<usize, String>,
     syntax_highlighting_theme: Option<&Theme>, // None means no syntax higlighting
     true_colour: bool,
+    diff_colours: DiffColours,
+    control_chars: ControlChars,
     event_sender: UnboundedSender<Event>,
-    wrap: WrapText,
+    wrap: WrapText<'_>,
+    line_numbers: bool,
+    line_prefix: &str,
 ) -> anyhow::Result<List<'a>> {
     match input_source {
-        InputSource::Directory(_) => build_preview_from_file(
+        // Results from framed stdin carry the path of the file they came from
+        InputSource::Stdin(stdin) if result.search_result.path.is_none() => build_preview_from_str(
+            stdin,
+            num_lines_to_show,
+            scroll_offset,
+            result,
+            preview,
+            nearby_replacements,
+            diff_colours,
+            control_chars,
+            wrap,
+            line_numbers,
+            line_prefix,
+        ),
+        InputSource::Directory(_) | InputSource::Stdin(_) => build_preview_from_file(
             num_lines_to_show,
+            scroll_offset,
             result,
             preview,
+            nearby_replacements,
             syntax_highlighting_theme,
             true_colour,
+            diff_colours,
+            control_chars,
             event_sender,
             wrap,
+            line_numbers,
+            line_prefix,
         ),
-        InputSource::Stdin(stdin) => {
-            build_preview_from_str(stdin, num_lines_to_show, result, preview, wrap)
-        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_preview_from_str<'a>(
     stdin: &Arc<String>,
     num_lines_to_show: u16,
+    scroll_offset: &mut isize,
     result: &SearchResultWithReplacement,
     preview: &SearchResultPreview,
-    wrap: WrapText,
+    nearby_replacements: &HashMap<usize, String>,
+    diff_colours: DiffColours,
+    control_chars: ControlChars,
+    wrap: WrapText<'_>,
+    line_numbers: bool,
+    line_prefix: &str,
 ) -> anyhow::Result<List<'a>> {
     // Line numbers are 1-indexed
     let line_idx = result.search_result.start_line_number() - 1;
-    let start = line_idx.saturating_sub(num_lines_to_show as usize);
-    let end = line_idx + num_lines_to_show as usize;
+    let centre = line_idx.saturating_add_signed(*scroll_offset);
+    let start = centre.saturating_sub(num_lines_to_show as usize);
+    let end = centre + num_lines_to_show as usize;
+    let gutter = LineNumberGutter::new(line_numbers, end);
 
     let cursor = Cursor::new(stdin.as_bytes());
     let lines = utils::surrounding_line_window(cursor, start, end).collect();
 
+    if *scroll_offset != 0 {
+        let (before, centre, after) = scrolled_preview_lines(
+            lines,
+            scroll_offset,
+            num_lines_to_show,
+            result,
+            preview,
+            nearby_replacements,
+            diff_colours,
+            gutter,
+            |l| to_line_plain(l, line_prefix),
+            |l| l == expected_first_line_content(result),
+        )?;
+        return line_list(
+            before,
+            centre,
+            after,
+            num_lines_to_show,
+            wrap,
+            control_chars,
+        )
+        .map_err(|e| anyhow!("failed to combine lines: {e}"));
+    }
+
     let (before, cur, after) = utils::split_indexed_lines(
         lines,
         line_idx,
@@ -925,10 +1509,21 @@ fn build_preview_from_str<'a>(
     );
 
     let after = filter_after_for_multiline(after, result);
-    let before = before.iter().map(|(_, l)| to_line_plain(l));
-    let after = after.iter().map(|(_, l)| to_line_plain(l));
-    line_list(before, preview.diff_lines(), after, num_lines_to_show, wrap)
-        .map_err(|e| anyhow!("failed to combine lines: {e}"))
+    let before = context_lines_before(&before, nearby_replacements, diff_colours, gutter, |l| {
+        to_line_plain(l, line_prefix)
+    });
+    let after = context_lines(&after, nearby_replacements, diff_colours, gutter, |l| {
+        to_line_plain(l, line_prefix)
+    });
+    line_list(
+        before,
+        preview.diff_lines(line_idx, gutter),
+        after,
+        num_lines_to_show,
+        wrap,
+        control_chars,
+    )
+    .map_err(|e| anyhow!("failed to combine lines: {e}"))
 }
 
 fn styled_line_to_ratatui_line(line: StyledLine) -> ListItem<'static> {
@@ -949,8 +1544,9 @@ fn wrap_lines(
     line: impl IntoIterator<Item = StyledLine>,
     width: u16,
     num_lines: Option<u16>,
+    wrapped_line_prefix: &str,
 ) -> Vec<StyledLine> {
-    let wrapped_line_prefix_len = UnicodeWidthStr::width(WRAPPED_LINE_PREFIX);
+    let wrapped_line_prefix_len = UnicodeWidthStr::width(wrapped_line_prefix);
     if width as usize <= wrapped_line_prefix_len || num_lines.is_some_and(|n| n == 0) {
         return vec![];
     }
@@ -980,8 +1576,11 @@ fn wrap_lines(
         let mut cur_line_wrapped: StyledLine = vec![];
         let mut cur_line_wrapped_len: usize = 0;
         if include_prefix {
-            cur_line_wrapped.push((WRAPPED_LINE_PREFIX.into(), Some(Style::default().dim())));
-            cur_line_wrapped_len += UnicodeWidthStr::width(WRAPPED_LINE_PREFIX);
+            cur_line_wrapped.push((
+                Cow::Owned(wrapped_line_prefix.to_owned()),
+                Some(Style::default().dim()),
+            ));
+            cur_line_wrapped_len += wrapped_line_prefix_len;
         }
 
         #[allow(clippy::needless_continue)]
@@ -1085,27 +1684,55 @@ fn extract_first_n_width(text: &str, max_width: usize) -> (&str, &str) {
     (text, "")
 }
 
+/// Replaces or escapes control characters in each segment of `line`, according to `control_chars`
+fn apply_control_chars(line: StyledLine, control_chars: ControlChars) -> StyledLine {
+    line.into_iter()
+        .map(|(text, style)| {
+            let text = match control_chars.apply(&text) {
+                Cow::Borrowed(_) => text,
+                Cow::Owned(applied) => Cow::Owned(applied),
+            };
+            (text, style)
+        })
+        .collect()
+}
+
 #[allow(clippy::needless_pass_by_value)]
 fn line_list(
     before: impl IntoIterator<Item = StyledLine>,
     diff: impl IntoIterator<Item = StyledLine>,
     after: impl IntoIterator<Item = StyledLine>,
     num_lines_to_show: u16,
-    wrap: WrapText,
+    wrap: WrapText<'_>,
+    control_chars: ControlChars,
 ) -> anyhow::Result<List<'static>> {
+    // Applied before wrapping, as escaping changes the width of each line
+    let apply = move |line| apply_control_chars(line, control_chars);
+    let (before, diff, after) = (
+        before.into_iter().map(apply),
+        diff.into_iter().map(apply),
+        after.into_iter().map(apply),
+    );
     let lines: Box<dyn Iterator<Item = StyledLine>> = match wrap {
-        WrapText::Width { width, num_lines } => {
-            let wrapped_diff = wrap_lines(diff, width, Some(num_lines));
+        WrapText::Width {
+            width,
+            num_lines,
+            prefix,
+        } => {
+            let wrapped_diff = wrap_lines(diff, width, Some(num_lines), prefix);
 
             let remaining_lines = num_lines_to_show
                 .saturating_sub(u16::try_from(wrapped_diff.len()).unwrap_or(u16::MAX));
 
             // TODO: ideally we'd process from the back to avoid the need for the `last_n` call, but this
             // adds a lot of complexity. Can revisit if needed
-            let wrapped_before =
-                utils::last_n(&wrap_lines(before, width, None), remaining_lines as usize).to_vec();
+            let wrapped_before = utils::last_n(
+                &wrap_lines(before, width, None, prefix),
+                remaining_lines as usize,
+            )
+            .to_vec();
 
-            let wrapped_after = wrap_lines(after, width, Some(remaining_lines));
+            let wrapped_after = wrap_lines(after, width, Some(remaining_lines), prefix);
 
             // Get a window centered around the diff
             let line_idx = wrapped_before.len() + wrapped_diff.len().div(2).saturating_sub(1);
@@ -1158,15 +1785,21 @@ fn has_long_lines(result: &SearchResultWithReplacement) -> bool {
     content_len.max(result.replacement.len()) > LONG_LINE_THRESHOLD
 }
 
-#[allow(clippy::too_many_lines)]
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
 fn build_preview_from_file<'a>(
     num_lines_to_show: u16,
+    scroll_offset: &mut isize,
     result: &SearchResultWithReplacement,
     preview: &SearchResultPreview,
+    nearby_replacements: &HashMap<usize, String>,
     syntax_highlighting_theme: Option<&Theme>,
     true_colour: bool,
+    diff_colours: DiffColours,
+    control_chars: ControlChars,
     event_sender: UnboundedSender<Event>,
-    wrap: WrapText,
+    wrap: WrapText<'_>,
+    line_numbers: bool,
+    line_prefix: &str,
 ) -> anyhow::Result<List<'a>> {
     let path = result
         .search_result
@@ -1176,8 +1809,10 @@ fn build_preview_from_file<'a>(
 
     // Line numbers are 1-indexed
     let line_idx = result.search_result.start_line_number() - 1;
-    let start = line_idx.saturating_sub(num_lines_to_show as usize);
-    let end = line_idx + num_lines_to_show as usize;
+    let centre = line_idx.saturating_add_signed(*scroll_offset);
+    let start = centre.saturating_sub(num_lines_to_show as usize);
+    let end = centre + num_lines_to_show as usize;
+    let gutter = LineNumberGutter::new(line_numbers, end);
 
     if let Some(theme) = syntax_highlighting_theme {
         match read_lines_range_highlighted_with_cache(
@@ -1189,6 +1824,37 @@ fn build_preview_from_file<'a>(
             event_sender,
         )? {
             LinesOrLoading::Loading => Ok(loading_lines(Some(theme), true_colour)),
+            LinesOrLoading::Lines(lines) if *scroll_offset != 0 => {
+                let (before, centre, after) = scrolled_preview_lines(
+                    lines,
+                    scroll_offset,
+                    num_lines_to_show,
+                    result,
+                    preview,
+                    nearby_replacements,
+                    diff_colours,
+                    gutter,
+                    |l| regions_to_line(l, line_prefix, true_colour),
+                    |l| l.iter().map(|(_, s)| s).join("") == expected_first_line_content(result),
+                )?;
+                let mut list = line_list(
+                    before,
+                    centre,
+                    after,
+                    num_lines_to_show,
+                    wrap,
+                    control_chars,
+                )
+                .map_err(|e| anyhow!("failed to combine lines: {e}"))?;
+                if let Some(bg) = theme
+                    .settings
+                    .background
+                    .map(|c| to_ratatui_colour(c, true_colour))
+                {
+                    list = list.bg(bg);
+                }
+                Ok(list)
+            }
             LinesOrLoading::Lines(lines) => {
                 let Ok((before, cur, after)) = utils::split_indexed_lines(
                     lines,
@@ -1203,12 +1869,23 @@ fn build_preview_from_file<'a>(
                 }
 
                 let after = filter_after_for_multiline(after, result);
-                let before = before.iter().map(|(_, l)| regions_to_line(l, true_colour));
-                let after = after.iter().map(|(_, l)| regions_to_line(l, true_colour));
-
-                let mut list =
-                    line_list(before, preview.diff_lines(), after, num_lines_to_show, wrap)
-                        .map_err(|e| anyhow!("failed to combine lines: {e}"))?;
+                let before =
+                    context_lines_before(&before, nearby_replacements, diff_colours, gutter, |l| {
+                        regions_to_line(l, line_prefix, true_colour)
+                    });
+                let after = context_lines(&after, nearby_replacements, diff_colours, gutter, |l| {
+                    regions_to_line(l, line_prefix, true_colour)
+                });
+
+                let mut list = line_list(
+                    before,
+                    preview.diff_lines(line_idx, gutter),
+                    after,
+                    num_lines_to_show,
+                    wrap,
+                    control_chars,
+                )
+                .map_err(|e| anyhow!("failed to combine lines: {e}"))?;
                 if let Some(bg) = theme
                     .settings
                     .background
@@ -1228,6 +1905,29 @@ fn build_preview_from_file<'a>(
             event_sender,
         )? {
             LinesOrLoading::Loading => Ok(loading_lines(None, true_colour)),
+            LinesOrLoading::Lines(lines) if *scroll_offset != 0 => {
+                let (before, centre, after) = scrolled_preview_lines(
+                    lines,
+                    scroll_offset,
+                    num_lines_to_show,
+                    result,
+                    preview,
+                    nearby_replacements,
+                    diff_colours,
+                    gutter,
+                    |l| to_line_plain(l, line_prefix),
+                    |l| l == expected_first_line_content(result),
+                )?;
+                line_list(
+                    before,
+                    centre,
+                    after,
+                    num_lines_to_show,
+                    wrap,
+                    control_chars,
+                )
+                .map_err(|e| anyhow!("failed to combine lines: {e}"))
+            }
             LinesOrLoading::Lines(lines) => {
                 let Ok((before, cur, after)) = utils::split_indexed_lines(
                     lines,
@@ -1242,15 +1942,86 @@ fn build_preview_from_file<'a>(
                 }
 
                 let after = filter_after_for_multiline(after, result);
-                let before = before.iter().map(|(_, l)| to_line_plain(l));
-                let after = after.iter().map(|(_, l)| to_line_plain(l));
-                line_list(before, preview.diff_lines(), after, num_lines_to_show, wrap)
-                    .map_err(|e| anyhow!("failed to combine lines: {e}"))
+                let before =
+                    context_lines_before(&before, nearby_replacements, diff_colours, gutter, |l| {
+                        to_line_plain(l, line_prefix)
+                    });
+                let after = context_lines(&after, nearby_replacements, diff_colours, gutter, |l| {
+                    to_line_plain(l, line_prefix)
+                });
+                line_list(
+                    before,
+                    preview.diff_lines(line_idx, gutter),
+                    after,
+                    num_lines_to_show,
+                    wrap,
+                    control_chars,
+                )
+                .map_err(|e| anyhow!("failed to combine lines: {e}"))
             }
         }
     }
 }
 
+/// Splits the lines of a preview that has been scrolled `scroll_offset` lines from the selected
+/// result into the styled lines before the centre of the preview, those of the line at its centre,
+/// and those after. The result's diff is shown in place of its lines whenever they are in view.
+/// `scroll_offset` is clamped so that the centre of the preview stays within the file
+#[allow(clippy::too_many_arguments)]
+fn scrolled_preview_lines<T>(
+    lines: Vec<(usize, T)>,
+    scroll_offset: &mut isize,
+    num_lines_to_show: u16,
+    result: &SearchResultWithReplacement,
+    preview: &SearchResultPreview,
+    nearby_replacements: &HashMap<usize, String>,
+    diff_colours: DiffColours,
+    gutter: Option<LineNumberGutter>,
+    to_line: impl Fn(&T) -> StyledLine,
+    is_expected_first_line: impl Fn(&T) -> bool,
+) -> anyhow::Result<(Vec<StyledLine>, Vec<StyledLine>, Vec<StyledLine>)> {
+    let line_idx = result.search_result.start_line_number() - 1;
+    let end_line_idx = result.search_result.end_line_number() - 1;
+    let Some(&(last_idx, _)) = lines.last() else {
+        bail!("File has changed since search (lines have changed)");
+    };
+    let centre = line_idx.saturating_add_signed(*scroll_offset).min(last_idx);
+    *scroll_offset = centre.cast_signed() - line_idx.cast_signed();
+    // The lines of the result are shown as a single diff, so centre on its first line when within it
+    let centre = if (line_idx..=end_line_idx).contains(&centre) {
+        line_idx
+    } else {
+        centre
+    };
+
+    let (before, cur, after) = utils::split_indexed_lines(lines, centre, num_lines_to_show)?;
+    let (mut before_centre, mut at_centre, mut after_centre) = (vec![], vec![], vec![]);
+    for (idx, line) in before.into_iter().chain(iter::once(cur)).chain(after) {
+        let styled = if idx == line_idx {
+            if !is_expected_first_line(&line) {
+                bail!("File has changed since search (lines don't match)");
+            }
+            preview.diff_lines(line_idx, gutter)
+        } else if (line_idx..=end_line_idx).contains(&idx) {
+            continue;
+        } else {
+            context_lines(
+                &[(idx, line)],
+                nearby_replacements,
+                diff_colours,
+                gutter,
+                &to_line,
+            )
+        };
+        match idx.cmp(&centre) {
+            cmp::Ordering::Less => before_centre.extend(styled),
+            cmp::Ordering::Equal => at_centre.extend(styled),
+            cmp::Ordering::Greater => after_centre.extend(styled),
+        }
+    }
+    Ok((before_centre, at_centre, after_centre))
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct SearchResultListItem<'a> {
     file_path: Line<'a>,
@@ -1258,29 +2029,133 @@ struct SearchResultListItem<'a> {
     is_primary_selected: bool,
 }
 
+/// Previews narrower than this show the old and new lines of a result stacked, even if
+/// `preview.layout` is side-by-side
+const SIDE_BY_SIDE_MIN_WIDTH: u16 = 80;
+const SIDE_BY_SIDE_SEPARATOR: &str = "│";
+
+/// Shows the old lines of a result on the left of the preview, aligned with the new lines on the right
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SideBySide {
+    width: u16,
+    control_chars: ControlChars,
+}
+
+impl SideBySide {
+    /// Returns `None` unless `layout` is side-by-side and a preview `width` columns wide has room for it
+    fn new(layout: PreviewLayout, width: u16, control_chars: ControlChars) -> Option<Self> {
+        (layout == PreviewLayout::SideBySide && width >= SIDE_BY_SIDE_MIN_WIDTH).then_some(Self {
+            width,
+            control_chars,
+        })
+    }
+
+    /// Combines each old line with the new line at the same position into a single row, leaving
+    /// `gutter_width` columns at the start of each row for line numbers
+    fn rows(self, old: &[StyledLine], new: &[StyledLine], gutter_width: usize) -> Vec<StyledLine> {
+        let column_width =
+            (self.width as usize).saturating_sub(gutter_width + SIDE_BY_SIDE_SEPARATOR.width()) / 2;
+        // Control characters are handled before the columns are measured, as escaping them changes
+        // the width of each line
+        let column = |line: Option<&StyledLine>| {
+            let line = apply_control_chars(line.cloned().unwrap_or_default(), self.control_chars);
+            fit_to_width(line, column_width)
+        };
+        (0..old.len().max(new.len()))
+            .map(|idx| {
+                let mut row = column(old.get(idx));
+                row.push((
+                    Cow::Borrowed(SIDE_BY_SIDE_SEPARATOR),
+                    Some(Style::new().fg(Color::DarkGray)),
+                ));
+                row.extend(column(new.get(idx)));
+                row
+            })
+            .collect()
+    }
+}
+
+/// Cuts off `line` if it is wider than `width`, or pads it with spaces if it is narrower
+fn fit_to_width(line: StyledLine, width: usize) -> StyledLine {
+    let mut remaining = width;
+    let mut fitted = StyledLine::new();
+    for (text, style) in line {
+        if remaining == 0 {
+            break;
+        }
+        let (kept, cut) = extract_first_n_width(&text, remaining);
+        remaining -= kept.width();
+        let kept = if cut.is_empty() {
+            text
+        } else {
+            Cow::Owned(kept.to_owned())
+        };
+        fitted.push((kept, style));
+    }
+    if remaining > 0 {
+        fitted.push((Cow::Owned(" ".repeat(remaining)), None));
+    }
+    fitted
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct SearchResultPreview {
     old_line_diffs: Vec<StyledLine>,
     new_line_diffs: Vec<StyledLine>,
+    side_by_side: Option<SideBySide>,
 }
 
 impl SearchResultPreview {
+    fn new(old_line_diffs: Vec<StyledLine>, new_line_diffs: Vec<StyledLine>) -> Self {
+        Self {
+            old_line_diffs,
+            new_line_diffs,
+            side_by_side: None,
+        }
+    }
+
+    fn with_side_by_side(self, side_by_side: Option<SideBySide>) -> Self {
+        Self {
+            side_by_side,
+            ..self
+        }
+    }
+
     /// Compute how many context lines to request, accounting for diff height.
     /// Always returns at least 1 to avoid errors in downstream processing.
     fn context_lines_to_show(&self, num_lines_to_show: u16) -> u16 {
-        let diff_height = self.old_line_diffs.len() + self.new_line_diffs.len();
+        let diff_height = if self.side_by_side.is_some() {
+            self.old_line_diffs.len().max(self.new_line_diffs.len())
+        } else {
+            self.old_line_diffs.len() + self.new_line_diffs.len()
+        };
         let extra = diff_height.saturating_sub(1);
         num_lines_to_show
             .saturating_sub(extra.try_into().unwrap_or(u16::MAX))
             .max(1)
     }
 
-    /// Returns all diff lines (old then new) for display
-    fn diff_lines(&self) -> impl Iterator<Item = StyledLine> + '_ {
-        self.old_line_diffs
-            .iter()
-            .cloned()
-            .chain(self.new_line_diffs.iter().cloned())
+    /// Returns all diff lines (old then new, or old beside new) for display, where the old lines start
+    /// at `first_line_idx` in the file
+    fn diff_lines(
+        &self,
+        first_line_idx: usize,
+        gutter: Option<LineNumberGutter>,
+    ) -> Vec<StyledLine> {
+        let lines = match self.side_by_side {
+            Some(side_by_side) => side_by_side.rows(
+                &self.old_line_diffs,
+                &self.new_line_diffs,
+                gutter.map_or(0, LineNumberGutter::total_width),
+            ),
+            None => self
+                .old_line_diffs
+                .iter()
+                .cloned()
+                .chain(self.new_line_diffs.iter().cloned())
+                .collect(),
+        };
+        number_lines(lines, first_line_idx, self.old_line_diffs.len(), gutter)
     }
 }
 
@@ -1318,9 +2193,10 @@ fn build_three_region_line(
     highlight_start: usize,
     highlight_end: usize,
     highlight_style: Style,
+    colours: DiffColours,
 ) -> StyledLine {
-    let text_style = kind.style();
-    let mut line = vec![kind.prefix_segment()];
+    let text_style = colours.unchanged_style();
+    let mut line = vec![kind.prefix_segment(colours)];
     push_styled_segment(&mut line, &content[..highlight_start], text_style);
     push_styled_segment(
         &mut line,
@@ -1372,6 +2248,7 @@ fn build_multiline_diff(
     match_start_in_first_line: usize,
     match_end_in_last_line: usize,
     replacement: &str,
+    colours: DiffColours,
 ) -> SearchResultPreview {
     assert!(!lines.is_empty(), "lines must not be empty");
 
@@ -1401,8 +2278,10 @@ fn build_multiline_diff(
 
     let removed = DiffLineKind::Removed;
     let added = DiffLineKind::Added;
-    let red_bg = Style::new().fg(Color::Black).bg(removed.color());
-    let green_bg = Style::new().fg(Color::Black).bg(added.color());
+    let old_bg = Style::new()
+        .fg(colours.highlight)
+        .bg(removed.color(colours));
+    let new_bg = Style::new().fg(colours.highlight).bg(added.color(colours));
 
     // Build OLD lines - style: [prefix] [matched portion] [suffix]
     let old_line_diffs: Vec<StyledLine> = lines
@@ -1421,7 +2300,14 @@ fn build_multiline_diff(
             } else {
                 line.content.len()
             };
-            build_three_region_line(removed, &line.content, match_start, match_end, red_bg)
+            build_three_region_line(
+                removed,
+                &line.content,
+                match_start,
+                match_end,
+                old_bg,
+                colours,
+            )
         })
         .collect();
 
@@ -1437,30 +2323,27 @@ fn build_multiline_diff(
     let mut new_line_diffs = Vec::new();
     let mut pos = 0;
     for line_content in full_new.split('\n') {
-        let mut line = vec![added.prefix_segment()];
+        let mut line = vec![added.prefix_segment(colours)];
         push_styled_segment(
             &mut line,
             slice_in_global_range(line_content, pos, 0, prefix_end),
-            added.style(),
+            colours.unchanged_style(),
         );
         push_styled_segment(
             &mut line,
             slice_in_global_range(line_content, pos, prefix_end, replacement_end),
-            green_bg,
+            new_bg,
         );
         push_styled_segment(
             &mut line,
             slice_in_global_range(line_content, pos, replacement_end, usize::MAX),
-            added.style(),
+            colours.unchanged_style(),
         );
         new_line_diffs.push(line);
         pos += line_content.len() + 1;
     }
 
-    SearchResultPreview {
-        old_line_diffs,
-        new_line_diffs,
-    }
+    SearchResultPreview::new(old_line_diffs, new_line_diffs)
 }
 
 /// Reconstructs the full new content after replacement for multiline matches.
@@ -1476,17 +2359,14 @@ fn reconstruct_new_content(
     (format!("{prefix}{replacement}{suffix}"), prefix.len())
 }
 
-/// Creates a styled segment with control characters stripped
+/// Creates a styled segment. Control characters are handled by `line_list` when the preview is built
 fn styled_segment(text: &str, style: Style) -> (Cow<'static, str>, Option<Style>) {
-    (
-        Cow::Owned(strip_control_chars(text).into_owned()),
-        Some(style),
-    )
+    (Cow::Owned(text.to_owned()), Some(style))
 }
 
 /// Creates a simple styled line (prefix + content, all same color)
-fn simple_styled_line(kind: DiffLineKind, content: &str) -> StyledLine {
-    let style = kind.style();
+fn simple_styled_line(kind: DiffLineKind, content: &str, colours: DiffColours) -> StyledLine {
+    let style = kind.style(colours);
     vec![
         styled_segment(kind.prefix(), style),
         styled_segment(content, style),
@@ -1494,7 +2374,7 @@ fn simple_styled_line(kind: DiffLineKind, content: &str) -> StyledLine {
 }
 
 /// Creates a simple diff without character-level granularity - just shows entire lines as red/green
-fn simple_diff(old: &MatchContent, new_content: &str) -> SearchResultPreview {
+fn simple_diff(old: &MatchContent, new_content: &str, colours: DiffColours) -> SearchResultPreview {
     match old {
         MatchContent::ByteRange {
             lines,
@@ -1504,7 +2384,7 @@ fn simple_diff(old: &MatchContent, new_content: &str) -> SearchResultPreview {
         } => {
             let old_line_diffs: Vec<StyledLine> = lines
                 .iter()
-                .map(|(_, line)| simple_styled_line(DiffLineKind::Removed, &line.content))
+                .map(|(_, line)| simple_styled_line(DiffLineKind::Removed, &line.content, colours))
                 .collect();
 
             let (full_new_content, _) = reconstruct_new_content(
@@ -1516,48 +2396,57 @@ fn simple_diff(old: &MatchContent, new_content: &str) -> SearchResultPreview {
 
             let new_line_diffs: Vec<StyledLine> = full_new_content
                 .split('\n')
-                .map(|line| simple_styled_line(DiffLineKind::Added, line))
+                .map(|line| simple_styled_line(DiffLineKind::Added, line, colours))
                 .collect();
 
-            SearchResultPreview {
-                old_line_diffs,
-                new_line_diffs,
-            }
+            SearchResultPreview::new(old_line_diffs, new_line_diffs)
         }
         MatchContent::Line { content, .. } => {
             let new_line_diffs: Vec<StyledLine> = new_content
                 .split('\n')
-                .map(|line| simple_styled_line(DiffLineKind::Added, line))
+                .map(|line| simple_styled_line(DiffLineKind::Added, line, colours))
                 .collect();
-            SearchResultPreview {
-                old_line_diffs: vec![simple_styled_line(DiffLineKind::Removed, content)],
+            SearchResultPreview::new(
+                vec![simple_styled_line(DiffLineKind::Removed, content, colours)],
                 new_line_diffs,
-            }
+            )
         }
     }
 }
 
 fn build_search_result_preview(
     result: &SearchResultWithReplacement,
+    colours: DiffColours,
     event_sender: UnboundedSender<Event>,
 ) -> SearchResultPreview {
     let old_content = &result.search_result.content;
     let replacement = &result.replacement;
-    let hash_key = cache::diff_cache_hash(old_content, replacement);
+    let match_spans = &result.match_spans;
+    let hash_key = cache::diff_cache_hash(old_content, replacement, match_spans, colours);
 
     let mut cache_guard = cache::diff_cache().lock().unwrap();
 
-    if let Some(entry) =
-        diff_cache_entry_for_key(&mut cache_guard, hash_key, old_content, replacement)
-    {
+    if let Some(entry) = diff_cache_entry_for_key(
+        &mut cache_guard,
+        hash_key,
+        old_content,
+        replacement,
+        match_spans,
+        colours,
+    ) {
         return match entry {
             cache::DiffTaskCacheEntry::InProgress { simple_preview } => simple_preview,
             cache::DiffTaskCacheEntry::Ready { preview } => preview,
         };
     }
 
-    let full_key = cache::DiffCacheFullKey::new(old_content.clone(), replacement.clone());
-    let simple_preview = simple_diff(old_content, replacement);
+    let full_key = cache::DiffCacheFullKey::new(
+        old_content.clone(),
+        replacement.clone(),
+        match_spans.clone(),
+        colours,
+    );
+    let simple_preview = simple_diff(old_content, replacement, colours);
     upsert_diff_cache_entry(
         &mut cache_guard,
         hash_key,
@@ -1571,24 +2460,33 @@ fn build_search_result_preview(
     simple_preview
 }
 
+#[allow(clippy::too_many_arguments)]
 fn search_result<'a>(
     idx: usize,
     is_selected: bool,
     is_primary_selected: bool,
     result: &'a SearchResultWithReplacement,
-    base_path: &Path,
+    num_held_back: usize,
+    roots: &[PathBuf],
+    path_style: PathStyle,
+    truncate: Truncate,
     list_area_width: u16,
     area_is_focussed: bool,
+    find_term: Option<&str>,
 ) -> SearchResultListItem<'a> {
     SearchResultListItem {
         file_path: file_path_line(
             idx,
             result,
-            base_path,
+            num_held_back,
+            roots,
+            path_style,
+            truncate,
             is_selected,
             is_primary_selected,
             list_area_width,
             area_is_focussed,
+            find_term,
         ),
         result,
         is_primary_selected,
@@ -1597,19 +2495,95 @@ fn search_result<'a>(
 
 static TRUNCATION_PREFIX: &str = "…";
 
+/// Formats `path` for display in the search results list. This is only used for display: the real path
+/// is always used when e.g. opening the file in an editor
+fn display_path(roots: &[PathBuf], path: &Path, path_style: PathStyle) -> String {
+    match path_style {
+        PathStyle::Relative => relative_path_in_roots(roots, path),
+        PathStyle::Absolute => std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .to_string(),
+        PathStyle::Filename => path.file_name().map_or_else(
+            || path.to_string_lossy().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn file_path_line<'a>(
     idx: usize,
     result: &SearchResultWithReplacement,
-    base_path: &Path,
+    num_held_back: usize,
+    roots: &[PathBuf],
+    path_style: PathStyle,
+    truncate: Truncate,
+    is_selected: bool,
+    is_primary_selected: bool,
+    list_area_width: u16,
+    area_is_focussed: bool,
+    find_term: Option<&str>,
+) -> Line<'a> {
+    let path = match &result.search_result.path {
+        Some(path) => display_path(roots, path, path_style),
+        None => "stdin".to_string(),
+    };
+    let line_number = result.search_result.start_line_number();
+    let suffix = if num_held_back > 0 {
+        format!(":{line_number} (+{num_held_back} more in this file)")
+    } else {
+        format!(":{line_number}")
+    };
+    checkbox_list_line(
+        idx,
+        result.search_result.included,
+        path,
+        &suffix,
+        is_selected,
+        is_primary_selected,
+        list_area_width,
+        truncate,
+        area_is_focussed,
+        find_term,
+    )
+}
+
+/// Shortens `path` to `num_chars` characters plus the `…` that replaces the part cut off
+fn truncate_path(path: &str, num_chars: usize, truncate: Truncate) -> String {
+    match truncate {
+        Truncate::Start => format!("{TRUNCATION_PREFIX}{}", last_n_chars(path, num_chars)),
+        Truncate::End => format!("{}{TRUNCATION_PREFIX}", first_n_chars(path, num_chars)),
+        Truncate::Middle => {
+            let num_end_chars = num_chars / 2;
+            format!(
+                "{}{TRUNCATION_PREFIX}{}",
+                first_n_chars(path, num_chars - num_end_chars),
+                last_n_chars(path, num_end_chars)
+            )
+        }
+    }
+}
+
+/// Builds a line of a list whose items can be included or excluded, such as the search results
+/// list, in the form `[x] <path><suffix>     (<number>)`
+#[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
+fn checkbox_list_line<'a>(
+    idx: usize,
+    included: bool,
+    mut path: String,
+    suffix: &str,
     is_selected: bool,
     is_primary_selected: bool,
     list_area_width: u16,
+    truncate: Truncate,
     area_is_focussed: bool,
+    find_term: Option<&str>,
 ) -> Line<'a> {
     let mut file_path_style = Style::new();
     if area_is_focussed && is_selected {
         file_path_style = file_path_style
-            .bg(match (result.search_result.included, is_primary_selected) {
+            .bg(match (included, is_primary_selected) {
                 (true, true) => Color::Blue,
                 (true, false) => Color::Indexed(26),
                 (false, true) => Color::Red,
@@ -1620,34 +2594,22 @@ fn file_path_line<'a>(
 
     let right_content = format!(" ({})", idx + 1);
     let right_content_len = right_content.chars().count();
-    let left_content = format!(
-        "[{}] ",
-        if result.search_result.included {
-            'x'
-        } else {
-            ' '
-        },
-    );
+    let left_content = format!("[{}] ", if included { 'x' } else { ' ' });
     let left_content_len = left_content.chars().count();
-    let mut path = match &result.search_result.path {
-        Some(path) => relative_path(base_path, path),
-        None => "stdin".to_string(),
-    };
-    let line_num = format!(":{}", result.search_result.start_line_number());
-    let line_num_len = line_num.chars().count();
+    let suffix_len = suffix.chars().count();
     let path_space = (list_area_width as usize)
-        .saturating_sub(left_content_len + line_num_len + right_content_len);
+        .saturating_sub(left_content_len + suffix_len + right_content_len);
     if UnicodeWidthStr::width(path.as_str()) > path_space {
-        let truncated = last_n_chars(
+        path = truncate_path(
             &path,
             path_space.saturating_sub(TRUNCATION_PREFIX.chars().count()),
+            truncate,
         );
-        path = format!("{TRUNCATION_PREFIX}{truncated}").to_string();
     }
     let path_len = UnicodeWidthStr::width(path.as_str());
     let spacers = " ".repeat(
         (list_area_width as usize)
-            .saturating_sub(left_content_len + path_len + line_num_len + right_content_len),
+            .saturating_sub(left_content_len + path_len + suffix_len + right_content_len),
     );
 
     let accessory_colour = if area_is_focussed && is_selected {
@@ -1655,14 +2617,97 @@ fn file_path_line<'a>(
     } else {
         Color::Blue
     };
-    Line::from(vec![
-        Span::raw(left_content).style(accessory_colour),
-        Span::raw(path),
-        Span::raw(line_num).style(accessory_colour),
+    let mut spans = vec![Span::raw(left_content).style(accessory_colour)];
+    spans.extend(highlight_find_term(&path, find_term));
+    spans.extend([
+        Span::raw(suffix.to_owned()).style(accessory_colour),
         Span::raw(spacers),
         Span::raw(right_content).style(accessory_colour),
-    ])
-    .style(file_path_style)
+    ]);
+    Line::from(spans).style(file_path_style)
+}
+
+/// Splits `text` into spans, highlighting each occurrence of `find_term`
+fn highlight_find_term<'a>(text: &str, find_term: Option<&str>) -> Vec<Span<'a>> {
+    let Some(term) = find_term.filter(|term| !term.is_empty()) else {
+        return vec![Span::raw(text.to_owned())];
+    };
+    let highlight_style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
+    let mut spans = vec![];
+    let mut rest = text;
+    while let Some(idx) = rest.find(term) {
+        if idx > 0 {
+            spans.push(Span::raw(rest[..idx].to_owned()));
+        }
+        spans.push(Span::styled(term.to_owned(), highlight_style));
+        rest = &rest[idx + term.len()..];
+    }
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest.to_owned()));
+    }
+    spans
+}
+
+fn render_file_picker(
+    frame: &mut Frame<'_>,
+    state: &mut FilePickerState,
+    roots: &[PathBuf],
+    path_style: PathStyle,
+    truncate: Truncate,
+    area: Rect,
+) {
+    let [header_area, list_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(area);
+
+    let num_included = state.num_included();
+    let Some(num_files) = state.files.as_ref().map(Vec::len) else {
+        frame.render_widget(
+            Line::from(vec![
+                Span::raw("Files to search"),
+                Span::raw(" [Listing files...]").fg(Color::Blue),
+            ]),
+            header_area,
+        );
+        return;
+    };
+    frame.render_widget(
+        Line::from(vec![
+            Span::raw(format!("Files to search: {num_included}/{num_files}")),
+            Span::raw(" [Select the files to search]").fg(Color::Blue),
+        ]),
+        header_area,
+    );
+
+    let num_to_render = list_area.height as usize;
+    state.num_displayed = Some(num_to_render);
+    state.view_offset = updated_view_offset(
+        state.view_offset,
+        state.selected(),
+        num_to_render,
+        num_files,
+    );
+
+    let selected = state.selected();
+    let files = state.files.iter().flatten().enumerate();
+    let lines = files
+        .skip(state.view_offset)
+        .take(num_to_render)
+        .map(|(idx, file)| {
+            ListItem::new(checkbox_list_line(
+                idx,
+                file.included,
+                display_path(roots, &file.path, path_style),
+                "",
+                idx == selected,
+                idx == selected,
+                list_area.width,
+                truncate,
+                true,
+                None,
+            ))
+        });
+    frame.render_widget(List::new(lines), list_area);
 }
 
 fn render_results_view(frame: &mut Frame<'_>, replace_state: &ReplaceState, area: Rect) {
@@ -1772,15 +2817,20 @@ fn render_performing_replacement_view(
     .flex(Flex::Center)
     .areas(area);
 
-    let text = Paragraph::new(Line::from(Span::raw("Performing replacement...")))
-        .block(Block::default())
-        .alignment(Alignment::Center);
-
-    frame.render_widget(text, progress_area);
-
     let num_completed = state.num_replacements_completed.load(Ordering::Relaxed);
     let time_taken = state.replacement_started.elapsed();
 
+    // The spinner keeps moving even when no replacements have completed recently, e.g. on slow
+    // disks, so that it's clear the app hasn't hung
+    let text = Paragraph::new(Line::from(vec![
+        Span::raw(format!("{} ", spinner_frame(time_taken))).fg(Color::Blue),
+        Span::raw("Performing replacement..."),
+    ]))
+    .block(Block::default())
+    .alignment(Alignment::Center);
+
+    frame.render_widget(text, progress_area);
+
     #[allow(clippy::cast_precision_loss)]
     let stats_text = format!(
         "Completed: {}/{} ({:.2}%)\nTime: {}",
@@ -1809,6 +2859,7 @@ fn error_result(result: &SearchResultWithReplacement) -> [ratatui::widgets::List
     .map(|(s, style)| ListItem::new(Text::styled(s, style)))
 }
 
+#[allow(clippy::too_many_lines)]
 pub fn render(app: &mut App, frame: &mut Frame<'_>) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -1831,49 +2882,77 @@ pub fn render(app: &mut App, frame: &mut Frame<'_>) {
     render_key_hints(app, frame, footer_area);
 
     let show_popup = app.show_popup();
+    let search_paused = app.search_pause.is_paused();
+    let search_is_empty = app.search_is_empty();
+    let mut results_list_area = None;
     match &mut app.ui_state.current_screen {
-        Screen::SearchFields(search_fields_state) => {
-            let num_search_fields_to_render = match search_fields_state.focussed_section {
-                FocussedSection::SearchFields => NUM_SEARCH_FIELDS,
-                FocussedSection::SearchResults => NUM_SEARCH_FIELDS_TRUNCATED,
+        Screen::FilePicker(state) => {
+            let roots = match &app.input_source {
+                InputSource::Directory(directories) => directories.as_slice(),
+                InputSource::Stdin(_) => &[],
             };
-            let area = default_width(content_area);
-            let [fields, _, results] = Layout::vertical([
-                Constraint::Length(num_search_fields_to_render * SEARCH_FIELD_HEIGHT),
-                Constraint::Length(1),
-                Constraint::Fill(1),
-            ])
-            .flex(Flex::Center)
-            .areas(area);
+            render_file_picker(
+                frame,
+                state,
+                roots,
+                app.config.display.path_style,
+                app.config.display.truncate,
+                default_width(content_area),
+            );
+        }
+        Screen::SearchFields(search_fields_state) => {
+            let fields_to_render =
+                search_fields_to_render(&app.search_fields, &search_fields_state.focussed_section);
+            let num_fields_to_render = u16::try_from(fields_to_render.len()).unwrap_or(u16::MAX);
+            let (fields, results) = search_fields_and_results_areas(
+                default_width(content_area),
+                num_fields_to_render * SEARCH_FIELD_HEIGHT,
+                app.config.display.fields_position,
+            );
 
             render_search_fields(
                 frame,
                 &app.search_fields,
+                &fields_to_render,
                 &app.config,
                 show_popup,
-                num_search_fields_to_render,
                 search_fields_state.focussed_section == FocussedSection::SearchFields,
                 fields,
             );
 
             let replacements_in_progress = search_fields_state.replacements_in_progress();
-            let search_is_empty = app.search_fields.search().text().is_empty();
+            let active_filters = active_filters_summary(
+                &app.input_source,
+                &app.search_fields,
+                app.run_config.include_hidden,
+            );
             if let Some(state) = &mut search_fields_state.search_state {
                 // Invariant held by `enter_chars_into_field` /
                 // `perform_search_already_validated`: whenever `search_state`
                 // is `Some`, the search text is non-empty.
-                render_search_results(
+                results_list_area = Some(render_search_results(
                     frame,
                     &app.input_source,
                     state,
+                    search_paused,
                     results,
                     app.config.get_theme(),
                     app.config.style.true_color,
+                    DiffColours::from_config(&app.config.colors, app.config.style.true_color),
+                    app.config.preview.control_chars,
                     app.event_channels.sender.clone(),
                     search_fields_state.focussed_section == FocussedSection::SearchResults,
                     replacements_in_progress,
                     app.config.preview.wrap_text,
-                );
+                    app.config.preview.show_nearby_replacements,
+                    app.config.preview.line_numbers,
+                    &app.config.preview.line_prefix,
+                    &app.config.preview.wrapped_prefix,
+                    app.config.preview.layout,
+                    active_filters.as_deref(),
+                    app.ui_state.find_term.as_deref(),
+                    &app.config.display,
+                ));
             } else if search_is_empty {
                 render_empty_search_banner(frame, results, replacements_in_progress);
             }
@@ -1886,12 +2965,36 @@ pub fn render(app: &mut App, frame: &mut Frame<'_>) {
         }
     }
 
+    app.ui_state.results_list_area = results_list_area.map(|area: Rect| ScreenArea {
+        x: area.x,
+        y: area.y,
+        width: area.width,
+        height: area.height,
+    });
+
     match app.popup() {
         Some(Popup::Error) => render_error_popup(&app.errors(), frame, content_area),
         Some(Popup::Help) => render_help_popup(app.keymaps_all(), frame, content_area),
         Some(Popup::Text { title, body }) => {
             render_text_popup(title, body, frame, content_area);
         }
+        Some(Popup::SavePreset(name_field)) => {
+            render_text_field_popup("Save preset as", name_field, frame, content_area);
+        }
+        Some(Popup::FindInResults(term_field)) => {
+            render_text_field_popup("Find in results", term_field, frame, content_area);
+        }
+        Some(Popup::SelectRange(range_field)) => {
+            render_text_field_popup(
+                "Select results (e.g. 50,80)",
+                range_field,
+                frame,
+                content_area,
+            );
+        }
+        Some(Popup::CommandPalette(palette)) => {
+            render_command_palette_popup(palette, frame, content_area);
+        }
 
         None => {}
     }
@@ -1906,11 +3009,18 @@ fn render_toast(message: &str, frame: &mut Frame<'_>, area: Rect) {
         return;
     }
 
-    let message_width = UnicodeWidthStr::width(message);
+    // Messages such as the files with lines of invalid UTF-8 span several lines
+    let message_width = message
+        .lines()
+        .map(UnicodeWidthStr::width)
+        .max()
+        .unwrap_or(0);
     let toast_width = u16::try_from(message_width.saturating_add(4))
         .unwrap_or(u16::MAX)
         .min(area.width);
-    let toast_height = 3.min(area.height);
+    let toast_height = u16::try_from(message.lines().count().max(1).saturating_add(2))
+        .unwrap_or(u16::MAX)
+        .min(area.height);
 
     let toast_area = Rect {
         x: area.x + (area.width.saturating_sub(toast_width)) / 2,
@@ -1936,6 +3046,87 @@ fn render_text_popup(title: &str, body: &str, frame: &mut Frame<'_>, area: Rect)
     render_paragraph_popup(title, lines, frame, area);
 }
 
+fn render_text_field_popup(title: &str, field: &TextField, frame: &mut Frame<'_>, area: Rect) {
+    let popup_area = get_popup_area(area, 3);
+    let block = create_popup_block(title);
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(Paragraph::new(field.text()).block(block), popup_area);
+    frame.set_cursor_position(Position {
+        // 1 for the border and 1 for the horizontal padding
+        x: popup_area.x + u16::try_from(field.visual_cursor_pos()).unwrap_or(0) + 2,
+        y: popup_area.y + 1,
+    });
+}
+
+fn render_command_palette_popup(palette: &CommandPaletteState, frame: &mut Frame<'_>, area: Rect) {
+    let entries = palette.matching_entries();
+    // 2 for the borders and 1 for the query, with space for at least one entry
+    let max_height = (area.height * 80 / 100).max(4);
+    let content_height = u16::try_from(entries.len().max(1) + 3)
+        .unwrap_or(u16::MAX)
+        .min(max_height);
+    let popup_area = get_popup_area(area, content_height);
+    let block = create_popup_block("Commands");
+    let inner_area = block.inner(popup_area);
+    let [query_area, list_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner_area);
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+    frame.render_widget(
+        Line::from(vec![
+            Span::raw("> ").fg(Color::Blue),
+            Span::raw(palette.query.text()),
+        ]),
+        query_area,
+    );
+    frame.set_cursor_position(Position {
+        x: query_area.x + u16::try_from(palette.query.visual_cursor_pos()).unwrap_or(0) + 2,
+        y: query_area.y,
+    });
+
+    if entries.is_empty() {
+        frame.render_widget(
+            Line::from("No matching commands").fg(Color::DarkGray),
+            list_area,
+        );
+        return;
+    }
+
+    let num_to_render = list_area.height as usize;
+    let selected = palette.selected();
+    let view_offset = (selected + 1).saturating_sub(num_to_render);
+    let max_keys_width = entries
+        .iter()
+        .map(|entry| entry.keys.len())
+        .max()
+        .unwrap_or(0);
+    let rows = entries
+        .into_iter()
+        .enumerate()
+        .skip(view_offset)
+        .take(num_to_render)
+        .map(|(idx, entry)| {
+            let keys = format!("{:>max_keys_width$}", entry.keys);
+            if idx == selected {
+                Row::new(vec![
+                    Cell::from(entry.description.as_str()),
+                    Cell::from(keys),
+                ])
+                .style(Style::default().bg(Color::Blue).fg(Color::Indexed(255)))
+            } else {
+                Row::new(vec![
+                    Cell::from(entry.description.as_str()),
+                    Cell::from(Span::raw(keys).fg(Color::Blue)),
+                ])
+            }
+        });
+    let keys_column_width = u16::try_from(max_keys_width).unwrap_or(u16::MAX);
+    let widths = [Constraint::Fill(1), Constraint::Length(keys_column_width)];
+    frame.render_widget(Table::new(rows, widths).column_spacing(1), list_area);
+}
+
 fn render_key_hints(app: &App, frame: &mut Frame<'_>, chunk: Rect) {
     let keys_hint = Span::styled(
         app.keymaps_compact()
@@ -2065,11 +3256,16 @@ fn create_popup_block(title: &str) -> Block<'_> {
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use ratatui::Terminal;
     use ratatui::backend::TestBackend;
 
     use super::*;
 
+    const PREVIEW_LINE_PREFIX: &str = "  ";
+    const WRAPPED_LINE_PREFIX: &str = "  ↪ ";
+
     #[test]
     fn test_split_lines_centered() {
         let lines: Vec<(usize, String)> =
@@ -2420,6 +3616,38 @@ mod tests {
         assert_eq!(extract_first_n_width("\ttest", 0), ("", "\ttest"));
     }
 
+    #[test]
+    fn test_search_progress_status_with_files_scanned() {
+        assert_eq!(
+            search_progress_status(Some(Duration::from_millis(250)), Some(42)),
+            " [⠹ Still searching... 42 files scanned]"
+        );
+    }
+
+    #[test]
+    fn test_truncate_path() {
+        let path = "src/components/button.rs";
+        assert_eq!(truncate_path(path, 9, Truncate::Start), "…button.rs");
+        assert_eq!(truncate_path(path, 9, Truncate::End), "src/compo…");
+        assert_eq!(truncate_path(path, 9, Truncate::Middle), "src/c…n.rs");
+        assert_eq!(truncate_path(path, 0, Truncate::Middle), "…");
+    }
+
+    #[test]
+    fn test_spinner_frame() {
+        assert_eq!(spinner_frame(Duration::ZERO), '⠋');
+        assert_eq!(spinner_frame(Duration::from_millis(199)), '⠙');
+        assert_eq!(spinner_frame(Duration::from_millis(1050)), '⠋');
+    }
+
+    #[test]
+    fn test_search_progress_status_without_files_scanned() {
+        assert_eq!(
+            search_progress_status(None, None),
+            " [⠋ Still searching...]"
+        );
+    }
+
     mod wrap_lines_tests {
         use super::*;
 
@@ -2451,7 +3679,7 @@ mod tests {
         #[test]
         fn test_no_wrapping_needed() {
             let input = vec![line("foo"), line("bar")];
-            let result = wrap_lines(input, 10, None);
+            let result = wrap_lines(input, 10, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["foo", "bar"]);
         }
@@ -2459,7 +3687,7 @@ mod tests {
         #[test]
         fn test_basic_wrapping() {
             let input = vec![line("foo bar baz")];
-            let result = wrap_lines(input, 8, None);
+            let result = wrap_lines(input, 8, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["foo bar ", "  ↪ baz"]);
         }
@@ -2468,7 +3696,7 @@ mod tests {
         fn test_multiple_lines_wrapping() {
             // Two input lines that both need wrapping
             let input = vec![line("foo bar baz"), line("qux quux corge")];
-            let result = wrap_lines(input, 8, None);
+            let result = wrap_lines(input, 8, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(
                 to_strings(result),
@@ -2479,7 +3707,7 @@ mod tests {
         #[test]
         fn test_wrap_lines_width_zero() {
             let input = vec![line("foo")];
-            let result = wrap_lines(input, 0, None);
+            let result = wrap_lines(input, 0, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(result.len(), 0);
         }
@@ -2487,7 +3715,7 @@ mod tests {
         #[test]
         fn test_wrap_lines_width_one() {
             let input = vec![line("foo")];
-            let result = wrap_lines(input, 1, None);
+            let result = wrap_lines(input, 1, None, WRAPPED_LINE_PREFIX);
 
             // Should return empty since we can't fit meaningful content with continuation prefix
             assert_eq!(result.len(), 0);
@@ -2498,7 +3726,7 @@ mod tests {
             // Width equal to WRAPPED_LINE_PREFIX length (4)
             // Should return nothing as we can't fit continuation prefix
             let input = vec![line("foo bar")];
-            let result = wrap_lines(input, 4, None);
+            let result = wrap_lines(input, 4, None, WRAPPED_LINE_PREFIX);
             assert_eq!(result.len(), 0);
         }
 
@@ -2507,7 +3735,7 @@ mod tests {
             // Width of 5 (one more than WRAPPED_LINE_PREFIX length)
             // Should be able to wrap with 1 char per continuation line
             let input = vec![line("hello world")];
-            let result = wrap_lines(input, 5, None);
+            let result = wrap_lines(input, 5, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(
                 to_strings(result),
@@ -2521,7 +3749,7 @@ mod tests {
         fn test_wrap_lines_num_lines_limit() {
             // Should stop after num_lines
             let input = vec![line("foo bar baz qux")];
-            let result = wrap_lines(input, 8, Some(2));
+            let result = wrap_lines(input, 8, Some(2), WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["foo bar ", "  ↪ baz "]);
         }
@@ -2530,7 +3758,7 @@ mod tests {
         fn test_wrap_lines_num_lines_zero() {
             // num_lines of 0 should return empty
             let input = vec![line("foo")];
-            let result = wrap_lines(input, 10, Some(0));
+            let result = wrap_lines(input, 10, Some(0), WRAPPED_LINE_PREFIX);
 
             assert_eq!(result.len(), 0);
         }
@@ -2538,7 +3766,7 @@ mod tests {
         #[test]
         fn test_wrap_lines_empty_input() {
             let input: Vec<StyledLine> = vec![];
-            let result = wrap_lines(input, 10, None);
+            let result = wrap_lines(input, 10, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(result.len(), 0);
         }
@@ -2546,7 +3774,7 @@ mod tests {
         #[test]
         fn test_wrap_lines_single_space() {
             let input = vec![line(" ")];
-            let result = wrap_lines(input, 10, None);
+            let result = wrap_lines(input, 10, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(result, vec![line(" ")]);
         }
@@ -2555,7 +3783,7 @@ mod tests {
         fn test_very_long_word() {
             // A single word longer than width should be broken up
             let input = vec![line("verylongword")];
-            let result = wrap_lines(input, 8, None);
+            let result = wrap_lines(input, 8, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["verylong", "  ↪ word"]);
         }
@@ -2564,7 +3792,7 @@ mod tests {
         fn test_exact_width_fit() {
             // Text that exactly fits the width
             let input = vec![line("12345678")];
-            let result = wrap_lines(input, 8, None);
+            let result = wrap_lines(input, 8, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["12345678"]);
         }
@@ -2572,7 +3800,7 @@ mod tests {
         #[test]
         fn test_one_char_over_width() {
             let input = vec![line("123456789")];
-            let result = wrap_lines(input, 8, None);
+            let result = wrap_lines(input, 8, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["12345678", "  ↪ 9"]);
         }
@@ -2587,7 +3815,7 @@ mod tests {
                 (" qux", None),
             ])];
 
-            let result = wrap_lines(input, 6, None);
+            let result = wrap_lines(input, 6, None, WRAPPED_LINE_PREFIX);
 
             let dim_style = Some(Style::default().dim());
 
@@ -2613,7 +3841,7 @@ mod tests {
             // Emojis should not be split
             // "Hello 👨‍👩‍👧‍👦 World" - family emoji is width 2
             let input = vec![line("Hello 👨‍👩‍👧‍👦 World")];
-            let result = wrap_lines(input, 12, None);
+            let result = wrap_lines(input, 12, None, WRAPPED_LINE_PREFIX);
 
             // "Hello 👨‍👩‍👧‍👦 " = 5 + 1 + 2 + 1 = 9 cols, "World" = 5 cols
             // Should wrap: "Hello 👨‍👩‍👧‍👦 " + "  ↪ World"
@@ -2626,7 +3854,7 @@ mod tests {
             // CJK characters that need wrapping
             // "你好世界朋友" = 6 chars = 12 columns, width = 10
             let input = vec![line("你好世界朋友")];
-            let result = wrap_lines(input, 10, None);
+            let result = wrap_lines(input, 10, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["你好世界朋", "  ↪ 友"]);
         }
@@ -2635,7 +3863,7 @@ mod tests {
         fn test_mixed_narrow_wide_characters() {
             // "Hello 世界" = 5 ASCII + 1 space + 2 CJK (4 cols) = 10 cols
             let input = vec![line("Hello 世界")];
-            let result = wrap_lines(input, 10, None);
+            let result = wrap_lines(input, 10, None, WRAPPED_LINE_PREFIX);
 
             // Should fit exactly
             assert_eq!(to_strings(result), vec!["Hello 世界"]);
@@ -2645,7 +3873,7 @@ mod tests {
         fn test_wrap_lines_multiple_input_lines_with_limit() {
             // Multiple input lines with num_lines limit
             let input = vec![line("foo bar"), line("baz qux")];
-            let result = wrap_lines(input, 6, Some(3));
+            let result = wrap_lines(input, 6, Some(3), WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["foo ba", "  ↪ r", "baz qu"]);
         }
@@ -2653,7 +3881,7 @@ mod tests {
         #[test]
         fn test_wrap_lines_multiple_spaces() {
             let input = vec![line("     ")];
-            let result = wrap_lines(input, 10, None);
+            let result = wrap_lines(input, 10, None, WRAPPED_LINE_PREFIX);
 
             assert_eq!(to_strings(result), vec!["     "]);
         }
@@ -2662,7 +3890,7 @@ mod tests {
         fn test_wrap_lines_multiple_wraps_same_line() {
             // A line that needs to wrap multiple times
             let input = vec![line("one two three four five six")];
-            let result = wrap_lines(input, 8, None);
+            let result = wrap_lines(input, 8, None, WRAPPED_LINE_PREFIX);
 
             // Due to chunking, wrapping happens differently than word boundaries
             assert_eq!(
@@ -2824,6 +4052,7 @@ mod tests {
                 replacement: "b".repeat(15_000),
                 replace_result: None,
                 preview_error: None,
+                match_spans: vec![],
             }
         }
 
@@ -2858,7 +4087,7 @@ mod tests {
             ];
 
             // Match spans "123\ndef" (partial first and last lines)
-            let preview = build_multiline_diff(&lines, 3, 3, "XYZ\nQ");
+            let preview = build_multiline_diff(&lines, 3, 3, "XYZ\nQ", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 2);
             assert_eq!(preview.new_line_diffs.len(), 2);
@@ -2868,17 +4097,17 @@ mod tests {
             let new_1 = &preview.new_line_diffs[0];
             let new_2 = &preview.new_line_diffs[1];
 
-            // Old side: unchanged text should be red foreground only, changed text black on red.
-            assert_line_has_segment(old_1, "abc", Style::new().fg(Color::Red));
+            // Old side: unchanged text should be in the neutral colour, changed text black on red.
+            assert_line_has_segment(old_1, "abc", Style::new().fg(Color::Reset));
             assert_line_has_segment(old_1, "123", Style::new().fg(Color::Black).bg(Color::Red));
             assert_line_has_segment(old_2, "def", Style::new().fg(Color::Black).bg(Color::Red));
-            assert_line_has_segment(old_2, "456", Style::new().fg(Color::Red));
+            assert_line_has_segment(old_2, "456", Style::new().fg(Color::Reset));
 
-            // New side: unchanged text should be green foreground only, changed text black on green.
-            assert_line_has_segment(new_1, "abc", Style::new().fg(Color::Green));
+            // New side: unchanged text should be in the neutral colour, changed text black on green.
+            assert_line_has_segment(new_1, "abc", Style::new().fg(Color::Reset));
             assert_line_has_segment(new_1, "XYZ", Style::new().fg(Color::Black).bg(Color::Green));
             assert_line_has_segment(new_2, "Q", Style::new().fg(Color::Black).bg(Color::Green));
-            assert_line_has_segment(new_2, "456", Style::new().fg(Color::Green));
+            assert_line_has_segment(new_2, "456", Style::new().fg(Color::Reset));
         }
 
         #[test]
@@ -2906,7 +4135,13 @@ mod tests {
                 .join("\n");
 
             let last_line_len = lines.last().unwrap().1.content.len();
-            let preview = build_multiline_diff(&lines, 0, last_line_len, &replacement);
+            let preview = build_multiline_diff(
+                &lines,
+                0,
+                last_line_len,
+                &replacement,
+                DiffColours::default(),
+            );
 
             assert_eq!(
                 preview.old_line_diffs.len(),
@@ -2931,13 +4166,13 @@ mod tests {
 
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
 
-                let first = build_search_result_preview(&result, event_tx.clone());
+                let first = build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 assert!(
                     !preview_has_background(&first),
                     "First render should be simple diff while full diff is pending",
                 );
 
-                let second = build_search_result_preview(&result, event_tx.clone());
+                let second = build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 assert!(
                     !preview_has_background(&second),
                     "Repeated request while pending should still return simple diff",
@@ -2948,7 +4183,7 @@ mod tests {
                     "Expected background task to emit at least one rerender event",
                 );
 
-                let third = build_search_result_preview(&result, event_tx);
+                let third = build_search_result_preview(&result, DiffColours::default(), event_tx);
                 assert!(
                     preview_has_background(&third),
                     "After background completion, preview should contain full-diff background styling",
@@ -2966,11 +4201,11 @@ mod tests {
                 let result = heavy_line_result();
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
 
-                let _ = build_search_result_preview(&result, event_tx.clone());
+                let _ = build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 let mut warmed = false;
                 let wait_start = tokio::time::Instant::now();
                 while wait_start.elapsed() < Duration::from_secs(4) {
-                    if preview_has_background(&build_search_result_preview(&result, event_tx.clone()))
+                    if preview_has_background(&build_search_result_preview(&result, DiffColours::default(), event_tx.clone()))
                     {
                         warmed = true;
                         break;
@@ -2995,9 +4230,10 @@ mod tests {
                     replacement: "b".repeat(15_000),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 };
 
-                let ready_preview = build_search_result_preview(&equivalent, event_tx);
+                let ready_preview = build_search_result_preview(&equivalent, DiffColours::default(), event_tx);
                 assert!(
                     preview_has_background(&ready_preview),
                     "Equivalent key should hit ready cache and return full diff immediately",
@@ -3022,7 +4258,8 @@ mod tests {
                 let result = heavy_line_result();
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
 
-                let _ = build_search_result_preview(&result, event_tx.clone());
+                let _ =
+                    build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 assert!(
                     wait_for_rerender_events(&mut event_rx, 1, Duration::from_secs(4)).await >= 1,
                     "First key should complete background compute",
@@ -3032,7 +4269,8 @@ mod tests {
                     replacement: "c".repeat(15_000),
                     ..result
                 };
-                let preview = build_search_result_preview(&distinct, event_tx);
+                let preview =
+                    build_search_result_preview(&distinct, DiffColours::default(), event_tx);
                 assert!(
                     !preview_has_background(&preview),
                     "Distinct key should miss cache initially and return simple diff",
@@ -3057,7 +4295,7 @@ mod tests {
                 // Multiple requests for same key while first computation is still in-flight
                 // should produce just one background compute completion event.
                 for _ in 0..8 {
-                    let _ = build_search_result_preview(&result, event_tx.clone());
+                    let _ = build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 }
 
                 let mut rerender_events =
@@ -3123,12 +4361,13 @@ mod tests {
                     replacement,
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 };
 
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
 
                 for _ in 0..6 {
-                    let _ = build_search_result_preview(&result, event_tx.clone());
+                    let _ = build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 }
 
                 let mut rerender_events =
@@ -3194,10 +4433,11 @@ mod tests {
                     replacement: replacement.clone(),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 };
 
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
-                let _ = build_search_result_preview(&result, event_tx.clone());
+                let _ = build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 let mut rerender_events = 0usize;
                 let wait_start = tokio::time::Instant::now();
                 while wait_start.elapsed() < Duration::from_secs(3) && rerender_events == 0 {
@@ -3225,9 +4465,10 @@ mod tests {
                     replacement,
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 };
 
-                let ready_preview = build_search_result_preview(&equivalent, event_tx);
+                let ready_preview = build_search_result_preview(&equivalent, DiffColours::default(), event_tx);
                 assert!(
                     preview_has_background(&ready_preview),
                     "Equivalent ByteRange payload should hit ready cache and return full diff immediately",
@@ -3291,10 +4532,15 @@ mod tests {
                     replacement: replacement.clone(),
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 };
 
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
-                let _ = build_search_result_preview(&result_a, event_tx.clone());
+                let _ = build_search_result_preview(
+                    &result_a,
+                    DiffColours::default(),
+                    event_tx.clone(),
+                );
                 assert!(
                     wait_for_rerender_events(&mut event_rx, 1, Duration::from_secs(4)).await >= 1,
                     "First key should complete background compute",
@@ -3317,9 +4563,11 @@ mod tests {
                     replacement,
                     replace_result: None,
                     preview_error: None,
+                    match_spans: vec![],
                 };
 
-                let preview = build_search_result_preview(&result_b, event_tx);
+                let preview =
+                    build_search_result_preview(&result_b, DiffColours::default(), event_tx);
                 assert!(
                     !preview_has_background(&preview),
                     "Distinct ByteRange metadata should miss cache and return simple diff first",
@@ -3343,10 +4591,16 @@ mod tests {
                 let result = heavy_line_result();
                 let old_content = result.search_result.content.clone();
                 let replacement = result.replacement.clone();
-                let full_key =
-                    cache::DiffCacheFullKey::new(old_content.clone(), replacement.clone());
-                let hash_key = cache::diff_cache_hash(&old_content, &replacement);
-                let seeded_simple_preview = simple_diff(&old_content, &replacement);
+                let full_key = cache::DiffCacheFullKey::new(
+                    old_content.clone(),
+                    replacement.clone(),
+                    vec![],
+                    DiffColours::default(),
+                );
+                let hash_key =
+                    cache::diff_cache_hash(&old_content, &replacement, &[], DiffColours::default());
+                let seeded_simple_preview =
+                    simple_diff(&old_content, &replacement, DiffColours::default());
 
                 {
                     let mut cache_guard = cache::diff_cache().lock().unwrap();
@@ -3362,7 +4616,8 @@ mod tests {
 
                 let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
 
-                let stale_preview = build_search_result_preview(&result, event_tx.clone());
+                let stale_preview =
+                    build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 assert!(
                     !preview_has_background(&stale_preview),
                     "Stale in-progress entry should still return simple preview",
@@ -3377,7 +4632,8 @@ mod tests {
                     );
                 }
 
-                let retried_preview = build_search_result_preview(&result, event_tx.clone());
+                let retried_preview =
+                    build_search_result_preview(&result, DiffColours::default(), event_tx.clone());
                 assert!(
                     !preview_has_background(&retried_preview),
                     "Retry should start from simple preview while new background compute runs",
@@ -3388,7 +4644,8 @@ mod tests {
                     "Retry should trigger a fresh background compute rerender event",
                 );
 
-                let ready_preview = build_search_result_preview(&result, event_tx);
+                let ready_preview =
+                    build_search_result_preview(&result, DiffColours::default(), event_tx);
                 assert!(
                     preview_has_background(&ready_preview),
                     "After retry completes, ready preview should include full background styling",
@@ -3410,7 +4667,7 @@ mod tests {
                 let (event_tx, _event_rx) = mpsc::unbounded_channel::<Event>();
 
                 let start = std::time::Instant::now();
-                let _ = build_search_result_preview(&result, event_tx);
+                let _ = build_search_result_preview(&result, DiffColours::default(), event_tx);
 
                 // Yield so spawned task can be scheduled.
                 tokio::task::yield_now().await;
@@ -3441,7 +4698,7 @@ mod tests {
         #[test]
         fn test_simple_diff_single_line_replacement() {
             let old = line_match("hello world");
-            let preview = simple_diff(&old, "hello rust");
+            let preview = simple_diff(&old, "hello rust", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             assert_eq!(preview.new_line_diffs.len(), 1);
@@ -3450,7 +4707,7 @@ mod tests {
         #[test]
         fn test_simple_diff_replacement_with_newline() {
             let old = line_match("hello world");
-            let preview = simple_diff(&old, "hello\nworld");
+            let preview = simple_diff(&old, "hello\nworld", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             assert_eq!(
@@ -3463,7 +4720,7 @@ mod tests {
         #[test]
         fn test_simple_diff_replacement_with_multiple_newlines() {
             let old = line_match("hello");
-            let preview = simple_diff(&old, "one\ntwo\nthree");
+            let preview = simple_diff(&old, "one\ntwo\nthree", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             assert_eq!(
@@ -3476,7 +4733,7 @@ mod tests {
         #[test]
         fn test_simple_diff_replacement_with_crlf() {
             let old = line_match("hello");
-            let preview = simple_diff(&old, "one\r\ntwo");
+            let preview = simple_diff(&old, "one\r\ntwo", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             // \r\n splits on \n and strips \r - 2 clean lines
@@ -3486,7 +4743,7 @@ mod tests {
         #[test]
         fn test_simple_diff_replacement_ending_with_newline() {
             let old = line_match("hello");
-            let preview = simple_diff(&old, "hello\n");
+            let preview = simple_diff(&old, "hello\n", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             // "hello\n" splits into ["hello", ""] - 2 lines
@@ -3518,7 +4775,7 @@ mod tests {
                 byte_end: 12,
                 content: "first\nsecond".to_string(),
             };
-            let preview = simple_diff(&old, "replaced\ntext");
+            let preview = simple_diff(&old, "replaced\ntext", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 2);
             assert_eq!(preview.new_line_diffs.len(), 2);
@@ -3527,7 +4784,7 @@ mod tests {
         #[test]
         fn test_simple_diff_empty_replacement() {
             let old = line_match("hello");
-            let preview = simple_diff(&old, "");
+            let preview = simple_diff(&old, "", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             // Empty string still produces one line (just the prefix)
@@ -3537,7 +4794,7 @@ mod tests {
         #[test]
         fn test_simple_diff_replacement_only_newlines() {
             let old = line_match("hello");
-            let preview = simple_diff(&old, "\n\n");
+            let preview = simple_diff(&old, "\n\n", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             // "\n\n" splits into ["", "", ""] - 3 lines
@@ -3547,7 +4804,7 @@ mod tests {
         #[test]
         fn test_simple_diff_unicode_content() {
             let old = line_match("héllo 世界");
-            let preview = simple_diff(&old, "hëllo 世間");
+            let preview = simple_diff(&old, "hëllo 世間", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             assert_eq!(preview.new_line_diffs.len(), 1);
@@ -3556,7 +4813,7 @@ mod tests {
         #[test]
         fn test_simple_diff_unicode_with_newlines() {
             let old = line_match("日本語");
-            let preview = simple_diff(&old, "日本\n語");
+            let preview = simple_diff(&old, "日本\n語", DiffColours::default());
 
             assert_eq!(preview.old_line_diffs.len(), 1);
             assert_eq!(preview.new_line_diffs.len(), 2);
@@ -3583,7 +4840,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ hello"]);
         }
 
@@ -3595,7 +4852,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ hello", "+ world"]);
         }
 
@@ -3607,7 +4864,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ one", "+ two", "+ three"]);
         }
 
@@ -3631,7 +4888,7 @@ mod tests {
                 },
             ];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ hello", "+ world"]);
         }
 
@@ -3643,7 +4900,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ a", "+ ", "+ b"]);
         }
 
@@ -3651,7 +4908,7 @@ mod tests {
         fn test_empty_diffs() {
             let diffs: Vec<Diff> = vec![];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ "]);
         }
 
@@ -3670,7 +4927,7 @@ mod tests {
                 },
             ];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ hello world"]);
         }
 
@@ -3682,7 +4939,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ hello", "+ "]);
         }
 
@@ -3694,7 +4951,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             // \r should be stripped, resulting in clean lines
             assert_eq!(lines_to_text(&lines), vec!["+ hello", "+ world"]);
         }
@@ -3707,7 +4964,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ hello", "+ "]);
         }
 
@@ -3719,7 +4976,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ ", "+ ", "+ ", "+ "]);
         }
 
@@ -3731,7 +4988,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["+ héllo", "+ 世界"]);
         }
 
@@ -3743,7 +5000,7 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Removed);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Removed, DiffColours::default());
             assert_eq!(lines_to_text(&lines), vec!["- hello", "- world"]);
         }
 
@@ -3755,12 +5012,486 @@ mod tests {
                 bg_colour: None,
             }];
 
-            let lines = diffs_to_lines(&diffs, DiffLineKind::Added);
+            let lines = diffs_to_lines(&diffs, DiffLineKind::Added, DiffColours::default());
             assert_eq!(
                 lines_to_text(&lines),
                 vec!["+ line1", "+ line2", "+ line3", "+ "]
             );
         }
+
+        #[test]
+        fn test_match_spans_are_underlined_across_segments() {
+            let (old_diffs, _) = line_diff("let foo = foo_bar;", "let baz = baz_bar;");
+            let lines = styled_parts_to_lines(
+                diffs_with_match_spans(&old_diffs, &[4..7, 10..13], DiffColours::default()),
+                DiffLineKind::Removed,
+                DiffColours::default(),
+            );
+            assert_eq!(lines_to_text(&lines), vec!["- let foo = foo_bar;"]);
+
+            let underlined: String = lines[0]
+                .iter()
+                .filter(|(_, style)| {
+                    style.is_some_and(|s| s.add_modifier.contains(Modifier::UNDERLINED))
+                })
+                .map(|(text, _)| text.as_ref())
+                .collect();
+            assert_eq!(underlined, "foofoo");
+        }
+
+        #[test]
+        fn test_no_match_spans_leaves_diffs_unchanged() {
+            let (old_diffs, _) = line_diff("hello world", "hello earth");
+            assert_eq!(
+                styled_parts_to_lines(
+                    diffs_with_match_spans(&old_diffs, &[], DiffColours::default()),
+                    DiffLineKind::Removed,
+                    DiffColours::default(),
+                ),
+                diffs_to_lines(&old_diffs, DiffLineKind::Removed, DiffColours::default())
+            );
+        }
+
+        #[test]
+        fn test_configured_colours_are_used() {
+            let colours = DiffColours {
+                old: Color::Magenta,
+                new: Color::Rgb(0, 175, 95),
+                highlight: Color::White,
+                unchanged: Color::Gray,
+            };
+            let (old_diffs, new_diffs) = line_diff("hello foo", "hello bar");
+
+            let old_lines = diffs_to_lines(&old_diffs, DiffLineKind::Removed, colours);
+            assert_eq!(old_lines[0][0].1, Some(Style::new().fg(Color::Magenta)));
+            assert!(old_lines[0].contains(&styled_segment(
+                "foo",
+                Style::new().fg(Color::White).bg(Color::Magenta)
+            )));
+
+            let new_lines = diffs_to_lines(&new_diffs, DiffLineKind::Added, colours);
+            assert!(new_lines[0].contains(&styled_segment("hello ", Style::new().fg(Color::Gray))));
+        }
+
+        #[test]
+        fn test_only_changed_text_uses_diff_colours() {
+            let colours = DiffColours::default();
+            let (_, new_diffs) = line_diff("let foo = 1;", "let bar = 1;");
+            let new_lines = diffs_to_lines(&new_diffs, DiffLineKind::Added, colours);
+            assert_eq!(
+                new_lines[0],
+                vec![
+                    styled_segment("+ ", Style::new().fg(Color::Green)),
+                    styled_segment("let ", Style::new().fg(Color::Reset)),
+                    styled_segment("bar", Style::new().fg(Color::Black).bg(Color::Green)),
+                    styled_segment(" = 1;", Style::new().fg(Color::Reset)),
+                ]
+            );
+        }
+    }
+
+    mod context_lines_tests {
+        use super::*;
+
+        fn lines_to_text(lines: &[StyledLine]) -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| line.iter().map(|(text, _)| text.as_ref()).collect())
+                .collect()
+        }
+
+        fn indexed(lines: &[&str]) -> Vec<(usize, String)> {
+            lines
+                .iter()
+                .enumerate()
+                .map(|(idx, l)| (idx, (*l).to_string()))
+                .collect()
+        }
+
+        #[test]
+        fn test_no_replacements_shows_original_lines() {
+            let lines = indexed(&["foo", "bar"]);
+            let result =
+                context_lines(&lines, &HashMap::new(), DiffColours::default(), None, |l| {
+                    to_line_plain(l, PREVIEW_LINE_PREFIX)
+                });
+            assert_eq!(lines_to_text(&result), vec!["  foo", "  bar"]);
+        }
+
+        #[test]
+        fn test_replacements_are_applied() {
+            let lines = indexed(&["foo", "bar", "baz"]);
+            let replacements = HashMap::from([(1, "qux".to_string())]);
+            let result = context_lines(&lines, &replacements, DiffColours::default(), None, |l| {
+                to_line_plain(l, PREVIEW_LINE_PREFIX)
+            });
+            assert_eq!(lines_to_text(&result), vec!["  foo", "+ qux", "  baz"]);
+        }
+
+        #[test]
+        fn test_multiline_replacement_before_keeps_closest_lines() {
+            let lines = indexed(&["foo", "bar", "baz"]);
+            let replacements = HashMap::from([(1, "a\nb".to_string())]);
+            let result =
+                context_lines_before(&lines, &replacements, DiffColours::default(), None, |l| {
+                    to_line_plain(l, PREVIEW_LINE_PREFIX)
+                });
+            assert_eq!(lines_to_text(&result), vec!["+ a", "+ b", "  baz"]);
+        }
+
+        #[test]
+        fn test_line_numbers() {
+            let lines = (7..11)
+                .map(|idx| (idx, format!("line {idx}")))
+                .collect::<Vec<_>>();
+            let replacements = HashMap::from([(8, "a\nb".to_string())]);
+            let gutter = LineNumberGutter::new(true, 10);
+            let result =
+                context_lines(&lines, &replacements, DiffColours::default(), gutter, |l| {
+                    to_line_plain(l, PREVIEW_LINE_PREFIX)
+                });
+            assert_eq!(
+                lines_to_text(&result),
+                vec![
+                    " 8   line 7",
+                    " 9 + a",
+                    "   + b",
+                    "10   line 9",
+                    "11   line 10",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_side_by_side_diff_lines() {
+            let colours = DiffColours::default();
+            let preview = SearchResultPreview::new(
+                vec![simple_styled_line(
+                    DiffLineKind::Removed,
+                    "foo bar",
+                    colours,
+                )],
+                vec![
+                    simple_styled_line(DiffLineKind::Added, "foo baz", colours),
+                    simple_styled_line(
+                        DiffLineKind::Added,
+                        "a long line that gets cut off",
+                        colours,
+                    ),
+                ],
+            )
+            .with_side_by_side(SideBySide::new(
+                PreviewLayout::SideBySide,
+                81,
+                ControlChars::default(),
+            ));
+            assert_eq!(preview.context_lines_to_show(10), 9);
+
+            let gutter = LineNumberGutter::new(true, 9);
+            let column = |text: &str| format!("{text:<38}");
+            assert_eq!(
+                lines_to_text(&preview.diff_lines(8, gutter)),
+                vec![
+                    format!(" 9 {}│{}", column("- foo bar"), column("+ foo baz")),
+                    format!(
+                        "   {}│{}",
+                        column(""),
+                        column("+ a long line that gets cut off")
+                    ),
+                ]
+            );
+
+            let narrow = preview.with_side_by_side(Some(SideBySide {
+                width: 21,
+                control_chars: ControlChars::default(),
+            }));
+            assert_eq!(
+                lines_to_text(&narrow.diff_lines(8, None)),
+                vec!["- foo bar │+ foo baz ", "          │+ a long l"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_side_by_side_only_when_wide_enough() {
+        let control_chars = ControlChars::default();
+        assert!(SideBySide::new(PreviewLayout::SideBySide, 80, control_chars).is_some());
+        assert!(SideBySide::new(PreviewLayout::SideBySide, 79, control_chars).is_none());
+        assert!(SideBySide::new(PreviewLayout::Stacked, 200, control_chars).is_none());
+    }
+
+    #[test]
+    fn test_validate_preview_prefixes() {
+        assert!(validate_preview_prefixes("  ", Some("  ↪ "), 5).is_ok());
+        assert!(validate_preview_prefixes("  ", None, 3).is_ok());
+        assert_eq!(
+            validate_preview_prefixes("  ", Some("  ↪ "), 4)
+                .unwrap_err()
+                .to_string(),
+            "`preview.wrapped_prefix` must be narrower than the preview"
+        );
+        assert_eq!(
+            validate_preview_prefixes("界界", None, 4)
+                .unwrap_err()
+                .to_string(),
+            "`preview.line_prefix` must be narrower than the preview"
+        );
+    }
+
+    mod active_filters_summary_tests {
+        use super::*;
+        use scooter_core::fields::{FieldValue, SearchFieldValues};
+
+        fn search_fields(include_files: &str, exclude_files: &str) -> SearchFields {
+            SearchFields::with_values(
+                &SearchFieldValues {
+                    include_files: FieldValue::new(include_files, false),
+                    exclude_files: FieldValue::new(exclude_files, false),
+                    ..SearchFieldValues::default()
+                },
+                false,
+            )
+        }
+
+        fn dir() -> InputSource {
+            InputSource::Directory(vec![PathBuf::from(".")])
+        }
+
+        #[test]
+        fn test_no_filters() {
+            assert_eq!(
+                active_filters_summary(&dir(), &search_fields("", ""), false),
+                None
+            );
+        }
+
+        #[test]
+        fn test_all_filters() {
+            assert_eq!(
+                active_filters_summary(&dir(), &search_fields("*.rs", "target/*"), true),
+                Some("globs: *.rs | excl: target/* | hidden: on".to_owned())
+            );
+        }
+
+        #[test]
+        fn test_only_hidden() {
+            assert_eq!(
+                active_filters_summary(&dir(), &search_fields("", ""), true),
+                Some("hidden: on".to_owned())
+            );
+        }
+
+        #[test]
+        fn test_stdin_has_no_filters() {
+            let stdin = InputSource::Stdin(Arc::new("foo".to_owned()));
+            assert_eq!(
+                active_filters_summary(&stdin, &search_fields("*.rs", ""), true),
+                None
+            );
+        }
+    }
+
+    mod list_and_preview_areas_tests {
+        use super::*;
+
+        fn ratio(list: u16, preview: u16) -> SplitRatio {
+            SplitRatio { list, preview }
+        }
+
+        #[test]
+        fn test_default_wide_layout() {
+            let (list, preview) =
+                list_and_preview_areas(Rect::new(0, 0, 201, 20), &DisplayConfig::default());
+            assert_eq!(list, Rect::new(0, 0, 80, 20));
+            assert_eq!(preview, Some(Rect::new(81, 0, 120, 20)));
+        }
+
+        #[test]
+        fn test_custom_wide_ratio() {
+            let display = DisplayConfig {
+                list_preview_ratio: ratio(1, 1),
+                ..DisplayConfig::default()
+            };
+            let (list, preview) = list_and_preview_areas(Rect::new(0, 0, 201, 20), &display);
+            assert_eq!(list, Rect::new(0, 0, 100, 20));
+            assert_eq!(preview, Some(Rect::new(101, 0, 100, 20)));
+        }
+
+        #[test]
+        fn test_default_narrow_layout() {
+            let (list, preview) =
+                list_and_preview_areas(Rect::new(0, 0, 80, 20), &DisplayConfig::default());
+            assert_eq!(list, Rect::new(0, 0, 80, 5));
+            assert_eq!(preview, Some(Rect::new(0, 6, 80, 14)));
+        }
+
+        #[test]
+        fn test_custom_narrow_ratio() {
+            let display = DisplayConfig {
+                vertical_list_preview_ratio: Some(ratio(1, 2)),
+                ..DisplayConfig::default()
+            };
+            let (list, preview) = list_and_preview_areas(Rect::new(0, 0, 80, 31), &display);
+            assert_eq!(list, Rect::new(0, 0, 80, 10));
+            assert_eq!(preview, Some(Rect::new(0, 11, 80, 20)));
+        }
+
+        #[test]
+        fn test_hidden_preview_gives_list_full_area() {
+            let area = Rect::new(0, 0, 200, 20);
+            let narrow_area = Rect::new(0, 0, 80, 20);
+            for (area, display) in [
+                (
+                    area,
+                    DisplayConfig {
+                        show_preview: false,
+                        ..DisplayConfig::default()
+                    },
+                ),
+                (
+                    narrow_area,
+                    DisplayConfig {
+                        show_preview: false,
+                        ..DisplayConfig::default()
+                    },
+                ),
+                (
+                    area,
+                    DisplayConfig {
+                        list_preview_ratio: ratio(1, 0),
+                        ..DisplayConfig::default()
+                    },
+                ),
+                (
+                    narrow_area,
+                    DisplayConfig {
+                        vertical_list_preview_ratio: Some(ratio(1, 0)),
+                        ..DisplayConfig::default()
+                    },
+                ),
+            ] {
+                assert_eq!(list_and_preview_areas(area, &display), (area, None));
+            }
+        }
+
+        #[test]
+        fn test_min_preview_width_boundaries() {
+            let display = DisplayConfig {
+                min_preview_width: 80,
+                ..DisplayConfig::default()
+            };
+
+            let area = Rect::new(0, 0, 79, 20);
+            assert_eq!(list_and_preview_areas(area, &display), (area, None));
+
+            let (list, preview) = list_and_preview_areas(Rect::new(0, 0, 80, 20), &display);
+            assert_eq!(list, Rect::new(0, 0, 80, 5));
+            assert_eq!(preview, Some(Rect::new(0, 6, 80, 14)));
+
+            let wide_display = DisplayConfig {
+                min_preview_width: 150,
+                ..DisplayConfig::default()
+            };
+            let area = Rect::new(0, 0, 149, 20);
+            assert_eq!(list_and_preview_areas(area, &wide_display), (area, None));
+
+            let (list, preview) = list_and_preview_areas(Rect::new(0, 0, 150, 20), &wide_display);
+            let preview = preview.expect("preview should be shown at the minimum width");
+            assert_eq!(list.height, 20);
+            assert_eq!(list.width + 1 + preview.width, 150);
+        }
+
+        #[test]
+        fn test_min_preview_width_disabled_by_default() {
+            let (_, preview) =
+                list_and_preview_areas(Rect::new(0, 0, 1, 20), &DisplayConfig::default());
+            assert!(preview.is_some());
+        }
+    }
+
+    mod search_fields_and_results_areas_tests {
+        use super::*;
+
+        #[test]
+        fn test_fields_at_top() {
+            let (fields, results) =
+                search_fields_and_results_areas(Rect::new(0, 2, 80, 30), 6, FieldsPosition::Top);
+            assert_eq!(fields, Rect::new(0, 2, 80, 6));
+            assert_eq!(results, Rect::new(0, 9, 80, 23));
+        }
+
+        #[test]
+        fn test_fields_at_bottom() {
+            let (fields, results) =
+                search_fields_and_results_areas(Rect::new(0, 2, 80, 30), 6, FieldsPosition::Bottom);
+            assert_eq!(results, Rect::new(0, 2, 80, 23));
+            assert_eq!(fields, Rect::new(0, 26, 80, 6));
+        }
+    }
+
+    mod display_path_tests {
+        use super::*;
+
+        #[test]
+        fn test_relative() {
+            assert_eq!(
+                display_path(
+                    &[PathBuf::from("/foo")],
+                    Path::new("/foo/bar/baz.rs"),
+                    PathStyle::Relative
+                ),
+                "bar/baz.rs"
+            );
+        }
+
+        #[test]
+        fn test_relative_with_multiple_roots() {
+            assert_eq!(
+                display_path(
+                    &[PathBuf::from("/foo/a"), PathBuf::from("/foo/b")],
+                    Path::new("/foo/b/bar/baz.rs"),
+                    PathStyle::Relative
+                ),
+                "b/bar/baz.rs"
+            );
+        }
+
+        #[test]
+        fn test_absolute() {
+            assert_eq!(
+                display_path(
+                    &[PathBuf::from("/foo")],
+                    Path::new("/foo/bar/baz.rs"),
+                    PathStyle::Absolute
+                ),
+                "/foo/bar/baz.rs"
+            );
+        }
+
+        #[test]
+        fn test_absolute_resolves_relative_paths() {
+            let expected = std::env::current_dir().unwrap().join("bar").join("baz.rs");
+            assert_eq!(
+                display_path(
+                    &[PathBuf::from(".")],
+                    Path::new("bar/baz.rs"),
+                    PathStyle::Absolute
+                ),
+                expected.to_string_lossy()
+            );
+        }
+
+        #[test]
+        fn test_filename() {
+            assert_eq!(
+                display_path(
+                    &[PathBuf::from("/foo")],
+                    Path::new("/foo/bar/baz.rs"),
+                    PathStyle::Filename
+                ),
+                "baz.rs"
+            );
+        }
     }
 
     mod strip_control_chars_cow_tests {
@@ -3795,4 +5526,114 @@ mod tests {
             assert_eq!(result, "héllo 世界");
         }
     }
+
+    mod apply_control_chars_tests {
+        use super::*;
+
+        fn line() -> StyledLine {
+            vec![
+                (Cow::Borrowed(PREVIEW_LINE_PREFIX), None),
+                (Cow::Owned("\u{1b}[31mred\tx".to_owned()), None),
+            ]
+        }
+
+        fn to_text(line: &StyledLine) -> String {
+            line.iter().map(|(text, _)| text.as_ref()).collect()
+        }
+
+        #[test]
+        fn test_strip() {
+            let line = apply_control_chars(line(), ControlChars::Strip);
+            assert_eq!(to_text(&line), "  �[31mred  x");
+        }
+
+        #[test]
+        fn test_escape() {
+            let line = apply_control_chars(line(), ControlChars::Escape);
+            assert_eq!(to_text(&line), "  ^[[31mred  x");
+        }
+
+        #[test]
+        fn test_raw() {
+            let line = apply_control_chars(line(), ControlChars::Raw);
+            assert_eq!(to_text(&line), "  \u{1b}[31mred\tx");
+        }
+    }
+
+    mod highlight_find_term_tests {
+        use super::*;
+
+        fn highlighted(spans: &[Span<'_>]) -> Vec<(String, bool)> {
+            spans
+                .iter()
+                .map(|span| {
+                    (
+                        span.content.to_string(),
+                        span.style.bg == Some(Color::Yellow),
+                    )
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_highlights_each_occurrence() {
+            let spans = highlight_find_term("foo bar foo", Some("foo"));
+            assert_eq!(
+                highlighted(&spans),
+                vec![
+                    ("foo".to_owned(), true),
+                    (" bar ".to_owned(), false),
+                    ("foo".to_owned(), true),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_no_term() {
+            for find_term in [None, Some("")] {
+                let spans = highlight_find_term("foo bar", find_term);
+                assert_eq!(highlighted(&spans), vec![("foo bar".to_owned(), false)]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_highlighting_upgraded_to_full_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "{}", (0..20).map(|i| format!("line {i}\n")).join("")).unwrap();
+        let path = file.path();
+        let theme = Theme::default();
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        let text = |lines: LinesOrLoading<HighlightedLine>| match lines {
+            LinesOrLoading::Lines(lines) => lines
+                .into_iter()
+                .map(|(idx, line)| (idx, line.into_iter().map(|(_, s)| s).join("")))
+                .collect::<Vec<_>>(),
+            LinesOrLoading::Loading => panic!("Expected lines"),
+        };
+
+        let partial = text(
+            read_lines_range_highlighted_with_cache(path, 5, 8, &theme, false, sender.clone())
+                .unwrap(),
+        );
+        // Re-rendering before the full file is highlighted doesn't start another job
+        read_lines_range_highlighted_with_cache(path, 5, 8, &theme, false, sender.clone()).unwrap();
+
+        assert!(matches!(receiver.recv().await, Some(Event::Rerender)));
+        assert!(receiver.try_recv().is_err());
+        assert!(
+            cache::highlighted_file_cache()
+                .lock()
+                .unwrap()
+                .get(path)
+                .is_some()
+        );
+
+        let full = text(
+            read_lines_range_highlighted_with_cache(path, 5, 8, &theme, false, sender).unwrap(),
+        );
+        assert_eq!(partial, full);
+        assert_eq!(partial.first(), Some(&(5, "line 5".to_owned())));
+    }
 }
@@ -1,14 +1,23 @@
 use anyhow::bail;
 
-use crossterm::event::{Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
 use futures::Stream;
 use insta::assert_snapshot;
 use rand::RngExt;
 use ratatui::backend::TestBackend;
 use regex::Regex;
-use scooter::app_runner::{AppConfig, AppRunner};
+use scooter::app_runner::{AppConfig, AppRunner, LifecycleHooks};
 use serial_test::serial;
-use std::{env, io, path::Path, pin::Pin, task::Poll};
+use std::{
+    env, io,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
 use tempfile::TempDir;
 use tokio::{
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
@@ -18,8 +27,11 @@ use tokio::{
 
 use scooter_core::{
     app::{AppRunConfig, ExitState},
-    config::{Config, KeysConfig, KeysSearch, KeysSearchFocusFields, KeysSearchFocusResults},
-    fields::{FieldValue, SearchFieldValues},
+    config::{
+        Config, FieldsPosition, KeysConfig, KeysSearch, KeysSearchFocusFields,
+        KeysSearchFocusResults,
+    },
+    fields::{FieldName, FieldValue, SearchFieldValues},
     keyboard::{
         KeyCode as CoreKeyCode, KeyEvent as CoreKeyEvent, KeyModifiers as CoreKeyModifiers,
     },
@@ -191,7 +203,7 @@ const DEFAULT_TEST_WIDTH: u16 = 30;
 
 fn build_test_runner(directory: Option<&Path>, advanced_regex: bool) -> anyhow::Result<TestRunner> {
     let app_config = AppConfig {
-        directory: directory.map_or(env::current_dir().unwrap(), Path::to_path_buf),
+        directories: vec![directory.map_or(env::current_dir().unwrap(), Path::to_path_buf)],
         app_run_config: AppRunConfig {
             advanced_regex,
             ..AppRunConfig::default()
@@ -207,7 +219,7 @@ fn build_test_runner_with_width(
     width: u16,
 ) -> anyhow::Result<TestRunner> {
     let app_config = AppConfig {
-        directory: directory.map_or(env::current_dir().unwrap(), Path::to_path_buf),
+        directories: vec![directory.map_or(env::current_dir().unwrap(), Path::to_path_buf)],
         app_run_config: AppRunConfig {
             advanced_regex,
             ..AppRunConfig::default()
@@ -325,7 +337,7 @@ async fn run_tui_file_replacement_case(
     };
 
     let config = AppConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         search_field_values,
         app_run_config: AppRunConfig {
             advanced_regex,
@@ -374,7 +386,7 @@ async fn run_tui_stdin_replacement_case(
     };
 
     let config = AppConfig {
-        directory: std::env::temp_dir(),
+        directories: vec![std::env::temp_dir()],
         search_field_values,
         app_run_config: AppRunConfig {
             advanced_regex,
@@ -1084,6 +1096,91 @@ async fn test_multi_select_mode() -> anyhow::Result<()> {
     shutdown(event_sender, run_handle).await
 }
 
+#[tokio::test]
+#[serial]
+async fn test_toggle_focus_keeps_search_results() -> anyhow::Result<()> {
+    let temp_dir = &create_test_files!(
+        "file1.txt" => text!("let a = 1;", "let b = 2;"),
+        "file2.txt" => text!("let c = 3;"),
+    );
+
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner(Some(temp_dir.path()), true)?;
+
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search text"), 100).await?;
+
+    send_chars("let", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search complete"), 1000).await?;
+
+    send_key(KeyCode::Char('a'), &event_sender); // Toggle all off
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("included: 0/3"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    // Ctrl-O moves focus to the fields and back, leaving the results untouched
+    send_key_with_modifiers(KeyCode::Char('o'), KeyModifiers::CONTROL, &event_sender);
+    send_key_with_modifiers(KeyCode::Char('o'), KeyModifiers::CONTROL, &event_sender);
+    send_key(KeyCode::Char(' '), &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("included: 1/3"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_max_matches_per_file() -> anyhow::Result<()> {
+    let temp_dir = &create_test_files!(
+        "file.txt" => text!("foo 1", "foo 2", "foo 3", "foo 4"),
+    );
+    let app_config = AppConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.display.max_matches_per_file = Some(2);
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(app_config, user_config)?;
+
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search text"), 100).await?;
+
+    send_chars("foo", &event_sender);
+    send_key(KeyCode::Tab, &event_sender);
+    send_chars("bar", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    let snapshot =
+        wait_for_match(&mut snapshot_rx, Pattern::string("Search complete"), 1000).await?;
+    assert!(snapshot.contains("Results: 2"), "{snapshot}");
+    assert!(snapshot.contains("(+2 more in this file)"), "{snapshot}");
+
+    send_key(KeyCode::Char('X'), &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 4"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    assert!(!snapshot.contains("more in this file"), "{snapshot}");
+
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(&mut snapshot_rx, Pattern::string("Success!"), 2000).await?;
+
+    assert_test_files!(
+        &temp_dir,
+        "file.txt" => text!("bar 1", "bar 2", "bar 3", "bar 4"),
+    );
+
+    shutdown(event_sender, run_handle).await
+}
+
 #[tokio::test]
 #[serial]
 async fn test_results_calculation_mixed() -> anyhow::Result<()> {
@@ -1595,7 +1692,7 @@ async fn test_prepopulated_fields() -> anyhow::Result<()> {
     };
 
     let config = AppConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         search_field_values,
         ..AppConfig::default()
     };
@@ -1638,6 +1735,369 @@ async fn test_prepopulated_fields() -> anyhow::Result<()> {
     shutdown(event_sender, run_handle).await
 }
 
+#[tokio::test]
+#[serial]
+async fn test_hidden_fields() -> anyhow::Result<()> {
+    let temp_dir = &create_test_files!(
+        "src/lib.rs" => text!(
+            "let old_value = 0;",
+        ),
+        "src/foo.py" => text!(
+            "old_value = 0",
+        ),
+    );
+
+    let config = AppConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.search.hidden_fields = vec![
+        FieldName::FixedStrings,
+        FieldName::WholeWord,
+        FieldName::MatchCase,
+    ];
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+
+    send_chars("old_value", &event_sender);
+    // Hidden fields should be skipped when tabbing, so we should be at `include_files` after two tabs
+    send_key(KeyCode::Tab, &event_sender);
+    send_key(KeyCode::Tab, &event_sender);
+    send_chars("*.py", &event_sender);
+
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile(r"Files to include(.|\n)*\*\.py"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    for title in ["Fixed strings", "Match whole word", "Match case"] {
+        assert!(!snapshot.contains(title), "{snapshot}");
+    }
+    assert!(snapshot.contains("Replace text"), "{snapshot}");
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_field_order() -> anyhow::Result<()> {
+    let temp_dir = &create_test_files!(
+        "src/lib.rs" => text!(
+            "let old_value = 0;",
+        ),
+        "src/foo.py" => text!(
+            "old_value = 0",
+        ),
+    );
+
+    let config = AppConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.search.field_order = vec![FieldName::IncludeFiles, FieldName::ExcludeFiles];
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+
+    // The search field should still be focussed initially, with the include and exclude fields now
+    // before it
+    send_chars("old_value", &event_sender);
+    send_key(KeyCode::BackTab, &event_sender);
+    send_key(KeyCode::BackTab, &event_sender);
+    send_chars("*.py", &event_sender);
+
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile(
+            r"Files to include(.|\n)*\*\.py(.|\n)*Files to exclude(.|\n)*Search text(.|\n)*old_value(.|\n)*Replace text",
+        ),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+async fn test_fields_position_bottom() -> anyhow::Result<()> {
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some("foo\nbar\n".to_owned()),
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.display.fields_position = FieldsPosition::Bottom;
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+
+    send_chars("foo", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+
+    // The results should be shown above the search fields
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile(
+            r"Results: 1(.|\n)*Search complete(.|\n)*Search text(.|\n)*foo",
+        ),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_mouse_click_toggles_result() -> anyhow::Result<()> {
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some("foo\nfoo\nfoo\n".to_owned()),
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.display.mouse = true;
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+
+    send_chars("foo", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 3 (included: 3/3)"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    let (row, column) = snapshot
+        .lines()
+        .enumerate()
+        .find_map(|(row, line)| {
+            let column = line.find("[x] stdin:2")?;
+            Some((row, line[..column].chars().count()))
+        })
+        .expect("Second result should be shown");
+    event_sender.send(CrosstermEvent::Mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: u16::try_from(column + 1)?,
+        row: u16::try_from(row)?,
+        modifiers: KeyModifiers::empty(),
+    }))?;
+
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("[ ] stdin:2"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_command_palette_runs_selected_command() -> anyhow::Result<()> {
+    let (run_handle, event_sender, mut snapshot_rx) = build_test_runner(None, false)?;
+
+    send_key_with_modifiers(KeyCode::Char('k'), KeyModifiers::CONTROL, &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("toggle hidden files"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    send_chars("equiv", &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("> equiv"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    assert!(snapshot.contains("show equivalent command"));
+    assert!(!snapshot.contains("toggle hidden files"));
+
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Equivalent command"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_preview_line_numbers() -> anyhow::Result<()> {
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some("alpha\nbeta\ngamma\n".to_owned()),
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.preview.line_numbers = true;
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+
+    send_chars("beta", &event_sender);
+    send_key(KeyCode::Tab, &event_sender);
+    send_chars("BETA", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 1"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    for pattern in [
+        r"\b1 +alpha",
+        r"\b2 - beta",
+        r"[^\d] +\+ BETA",
+        r"\b3 +gamma",
+    ] {
+        assert!(
+            Regex::new(pattern)?.is_match(&snapshot),
+            "Expected {pattern} to match:\n{snapshot}"
+        );
+    }
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_preview_custom_prefixes() -> anyhow::Result<()> {
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some(format!("alpha\nbeta{}\ngamma\n", " lorem".repeat(20))),
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.preview.wrap_text = true;
+    user_config.preview.line_prefix = "│ ".to_owned();
+    user_config.preview.wrapped_prefix = "  … ".to_owned();
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+
+    send_chars("beta", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 1"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    for expected in ["│ alpha", "│ gamma", "  … "] {
+        assert!(
+            snapshot.contains(expected),
+            "Expected {expected} in:\n{snapshot}"
+        );
+    }
+    assert!(!snapshot.contains('↪'), "{snapshot}");
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_resize_reflows_wrapped_preview() -> anyhow::Result<()> {
+    let long_line = format!("START{} END", " lorem".repeat(18));
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some(format!("{long_line}\n")),
+        ..AppConfig::default()
+    };
+    let mut user_config = Config::default();
+    user_config.preview.wrap_text = true;
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_custom_config(config, user_config)?;
+    let has_unwrapped_line = |snapshot: &str| {
+        snapshot
+            .lines()
+            .any(|l| l.contains("START") && l.contains("END"))
+    };
+
+    send_chars("START", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 1"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    assert!(snapshot.contains("END"));
+    assert!(!has_unwrapped_line(&snapshot));
+
+    event_sender.send(CrosstermEvent::Resize(250, 30))?;
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile(r"START( lorem)+ END"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    assert_eq!(
+        snapshot.lines().next().map(|l| l.chars().count()),
+        Some(250)
+    );
+
+    event_sender.send(CrosstermEvent::Resize(100, 30))?;
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile(r"\A[^\n]{100}\n"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+    assert!(snapshot.contains("END"));
+    assert!(!has_unwrapped_line(&snapshot));
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_preview_reflects_file_changed_before_reset() -> anyhow::Result<()> {
+    let temp_dir = &create_test_files!(
+        "file.rs" => text!(
+            "// original context",
+            "fn foo() {}",
+        ),
+    );
+
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner(Some(temp_dir.path()), false)?;
+
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search text"), 100).await?;
+
+    send_chars("foo", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("original context"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    std::fs::write(
+        temp_dir.path().join("file.rs"),
+        "// updated context\nfn foo() {}\n",
+    )?;
+
+    send_key_with_modifiers(KeyCode::Char('r'), KeyModifiers::CONTROL, &event_sender);
+    send_chars("foo", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("updated context"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
 #[tokio::test]
 #[serial]
 async fn test_replacement_progress_display() -> anyhow::Result<()> {
@@ -1676,7 +2136,7 @@ async fn test_replacement_progress_display() -> anyhow::Result<()> {
     wait_for_match(
         &mut snapshot_rx,
         Pattern::regex_must_compile(
-            r"Performing replacement\.\.\.\s*\n\s*Completed: \d+/8 \(\d+\.\d{2}%\)\s*\n\s*Time: \d+\.\d{3}s",
+            r"[⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏] Performing replacement\.\.\.\s*\n\s*Completed: \d+/8 \(\d+\.\d{2}%\)\s*\n\s*Time: \d+\.\d{3}s",
         ),
         1000,
     )
@@ -1727,7 +2187,7 @@ test_with_both_regex_modes!(
             ..SearchFieldValues::default()
         };
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             search_field_values,
             app_run_config: AppRunConfig {
                 advanced_regex,
@@ -1787,7 +2247,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 immediate_replace: true,
@@ -1856,7 +2316,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -1926,7 +2386,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -1999,7 +2459,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2077,7 +2537,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2155,7 +2615,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2222,7 +2682,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2292,7 +2752,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2362,7 +2822,7 @@ async fn test_advanced_regex_negative_lookahead() -> anyhow::Result<()> {
     );
 
     let config = AppConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         app_run_config: AppRunConfig {
             advanced_regex: true,
             ..AppRunConfig::default()
@@ -2440,7 +2900,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2534,7 +2994,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2632,7 +3092,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2745,7 +3205,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2835,7 +3295,7 @@ test_with_both_regex_modes!(test_ignores_gif_file, |advanced_regex: bool| async
     );
 
     let config = AppConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         app_run_config: AppRunConfig {
             advanced_regex,
             ..AppRunConfig::default()
@@ -2889,7 +3349,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -2944,7 +3404,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 include_hidden: true,
                 advanced_regex,
@@ -3000,7 +3460,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -3078,7 +3538,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -3177,7 +3637,7 @@ test_with_both_regex_modes!(
         )];
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -3273,7 +3733,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 interpret_escape_sequences: true, // Enabled from start
@@ -3468,7 +3928,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: false,
@@ -3508,7 +3968,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: false,
@@ -3548,7 +4008,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -3588,7 +4048,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -3640,7 +4100,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 include_hidden: true, // Include hidden to ensure .git exclusion is separate
                 advanced_regex,
@@ -3709,7 +4169,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 include_hidden: true,
                 include_git_folders: true,
@@ -3785,7 +4245,7 @@ test_with_both_regex_modes!(
         copy_dir_all(format!("{fixtures_dir}/initial"), temp_dir.path())?;
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -3942,7 +4402,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -4071,7 +4531,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -4213,7 +4673,7 @@ test_with_both_regex_modes!(
         };
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -4275,7 +4735,7 @@ test_with_both_regex_modes!(test_multiline_search_preview, |advanced_regex| asyn
     );
 
     let app_config = AppConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         app_run_config: AppRunConfig {
             advanced_regex,
             multiline: true,
@@ -4336,7 +4796,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4390,7 +4850,7 @@ async fn test_tui_multiline_advanced_regex_lookaround_replacement() -> anyhow::R
     );
 
     let app_config = AppConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         app_run_config: AppRunConfig {
             advanced_regex: true,
             multiline: true,
@@ -4480,7 +4940,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4535,7 +4995,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4586,7 +5046,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4639,7 +5099,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4692,7 +5152,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4746,7 +5206,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4792,7 +5252,7 @@ test_with_both_regex_modes!(
         let stdin_content = "foo bar\nbaz blah\nqux\n".to_string();
 
         let app_config = AppConfig {
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4846,6 +5306,103 @@ test_with_both_regex_modes!(
     }
 );
 
+#[tokio::test]
+async fn test_scroll_preview() -> anyhow::Result<()> {
+    let stdin_content = (1..=100)
+        .map(|i| {
+            if i == 30 {
+                format!("line {i} target\n")
+            } else {
+                format!("line {i}\n")
+            }
+        })
+        .collect::<String>();
+
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some(stdin_content),
+        ..AppConfig::default()
+    };
+    let (run_handle, event_sender, mut snapshot_rx) =
+        build_test_runner_with_config_and_width(config, 50)?;
+
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search text"), 100).await?;
+    send_chars("target", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search complete"), 1000).await?;
+
+    // Scrolling down shows lines below the initial preview
+    for _ in 0..30 {
+        send_key_with_modifiers(KeyCode::Char('e'), KeyModifiers::CONTROL, &event_sender);
+    }
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("line 75"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    // Scrolling up past the selected result shows the start of the input
+    for _ in 0..45 {
+        send_key_with_modifiers(KeyCode::Char('y'), KeyModifiers::CONTROL, &event_sender);
+    }
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile(r"line 1\b"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+async fn test_num_included_in_results_header() -> anyhow::Result<()> {
+    let config = AppConfig {
+        directories: vec![std::env::temp_dir()],
+        stdin_content: Some("foo 1\nbar\nfoo 2\nfoo 3\n".to_owned()),
+        ..AppConfig::default()
+    };
+    let (run_handle, event_sender, mut snapshot_rx) = build_test_runner_with_config(config)?;
+
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search text"), 100).await?;
+    send_chars("foo", &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search complete"), 1000).await?;
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 3 (included: 3/3)"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    send_key(KeyCode::Char(' '), &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 3 (included: 2/3)"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    send_key(KeyCode::Char('a'), &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 3 (included: 3/3)"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    send_key(KeyCode::Char('a'), &event_sender);
+    wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Results: 3 (included: 0/3)"),
+        UI_WAIT_TIMEOUT_MS,
+    )
+    .await?;
+
+    shutdown(event_sender, run_handle).await
+}
+
 // Stdin preview tests - verify preview rendering for stdin input with escape/multiline combinations
 
 test_with_both_regex_modes!(
@@ -4854,7 +5411,7 @@ test_with_both_regex_modes!(
         let stdin_content = "hello world\nanother line\n".to_string();
 
         let config = AppConfig {
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: false,
@@ -4890,7 +5447,7 @@ test_with_both_regex_modes!(
         let stdin_content = "hello world\nanother line\n".to_string();
 
         let config = AppConfig {
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: false,
@@ -4926,7 +5483,7 @@ test_with_both_regex_modes!(
         let stdin_content = "hello world\nanother line\n".to_string();
 
         let config = AppConfig {
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4962,7 +5519,7 @@ test_with_both_regex_modes!(
         let stdin_content = "hello world\nanother line\n".to_string();
 
         let config = AppConfig {
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -4998,7 +5555,7 @@ test_with_both_regex_modes!(
         let stdin_content = "hello world\r\nanother line\r\n".to_string();
 
         let config = AppConfig {
-            directory: std::env::temp_dir(),
+            directories: vec![std::env::temp_dir()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -5059,7 +5616,7 @@ test_with_both_regex_modes!(
         );
 
         let app_config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 multiline: true,
@@ -5116,7 +5673,7 @@ test_with_both_regex_modes!(
         );
 
         let config = AppConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             app_run_config: AppRunConfig {
                 advanced_regex,
                 ..AppRunConfig::default()
@@ -5188,3 +5745,135 @@ test_with_both_regex_modes!(
         shutdown(event_sender, run_handle).await
     }
 );
+
+#[tokio::test]
+#[serial]
+async fn test_interactive_filter_only_replaces_in_approved_files() -> anyhow::Result<()> {
+    let temp_dir = &create_test_files!(
+        "file1.txt" => text!("foo"),
+        "file2.txt" => text!("foo"),
+        "file3.txt" => text!("foo"),
+    );
+    let config = AppConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        search_field_values: SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("bar", false),
+            ..SearchFieldValues::default()
+        },
+        app_run_config: AppRunConfig {
+            interactive_filter: true,
+            ..AppRunConfig::default()
+        },
+        ..AppConfig::default()
+    };
+    let (run_handle, event_sender, mut snapshot_rx) = build_test_runner_with_config(config)?;
+
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Files to search: 3/3"),
+        1000,
+    )
+    .await?;
+    for file in ["file1.txt", "file2.txt", "file3.txt"] {
+        assert!(snapshot.contains(&format!("[x] {file}")), "{snapshot}");
+    }
+
+    send_key(KeyCode::Down, &event_sender);
+    send_chars(" ", &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::string("Files to search: 2/3"),
+        1000,
+    )
+    .await?;
+    assert!(snapshot.contains("[ ] file2.txt"), "{snapshot}");
+
+    send_key(KeyCode::Enter, &event_sender);
+    let snapshot = wait_for_match(
+        &mut snapshot_rx,
+        Pattern::regex_must_compile("Results: 2.*Search complete"),
+        1000,
+    )
+    .await?;
+    assert!(!snapshot.contains("file2.txt"), "{snapshot}");
+
+    send_key(KeyCode::Enter, &event_sender);
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(&mut snapshot_rx, Pattern::string("Success!"), 2000).await?;
+
+    assert_test_files!(
+        temp_dir,
+        "file1.txt" => text!("bar"),
+        "file2.txt" => text!("foo"),
+        "file3.txt" => text!("bar"),
+    );
+
+    shutdown(event_sender, run_handle).await
+}
+
+#[tokio::test]
+#[serial]
+async fn test_lifecycle_hooks() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!("foo", "bar foo"),
+        "file2.txt" => text!("baz"),
+    );
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let hooks = LifecycleHooks {
+        search_started: Some(Arc::new({
+            let events = Arc::clone(&events);
+            move || events.lock().unwrap().push("search started".to_owned())
+        })),
+        search_completed: Some(Arc::new({
+            let events = Arc::clone(&events);
+            move |num_results| {
+                events
+                    .lock()
+                    .unwrap()
+                    .push(format!("search completed: {num_results}"));
+            }
+        })),
+        replacement_completed: Some(Arc::new({
+            let events = Arc::clone(&events);
+            move |replace_state| {
+                events.lock().unwrap().push(format!(
+                    "replacement completed: {}",
+                    replace_state.num_successes
+                ));
+            }
+        })),
+    };
+
+    let config = AppConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        search_field_values: SearchFieldValues {
+            search: FieldValue::new("foo", false),
+            replace: FieldValue::new("qux", false),
+            ..SearchFieldValues::default()
+        },
+        app_run_config: AppRunConfig {
+            immediate_search: true,
+            ..AppRunConfig::default()
+        },
+        hooks,
+        ..AppConfig::default()
+    };
+    let (run_handle, event_sender, mut snapshot_rx) = build_test_runner_with_config(config)?;
+
+    wait_for_match(&mut snapshot_rx, Pattern::string("Search complete"), 1000).await?;
+    send_key(KeyCode::Enter, &event_sender);
+    wait_for_match(&mut snapshot_rx, Pattern::string("Success!"), 2000).await?;
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            "search started",
+            "search completed: 2",
+            "replacement completed: 2",
+        ]
+    );
+
+    shutdown(event_sender, run_handle).await
+}
@@ -1,7 +1,25 @@
+use crossterm::style::Stylize as _;
 use indoc::indoc;
-use scooter::headless::{run_headless, run_headless_with_stdin};
-use scooter_core::validation::{DirConfig, SearchConfig};
+use regex::Regex;
+use scooter::{
+    app_runner::ColorChoice,
+    headless::{
+        format_headless_results, run_headless, run_headless_in_shadow, run_headless_jobs,
+        run_headless_with_pairs, run_headless_with_stdin,
+    },
+};
+use scooter_core::{
+    git, jobs, modified_since, replacement_pairs,
+    search::{BinaryDetection, LineFilter, LineRanges},
+    validation::{DirConfig, SearchConfig},
+};
 use serial_test::serial;
+use std::{
+    fs::File,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 mod utils;
 
@@ -29,16 +47,24 @@ async fn run_headless_file_case(
         match_whole_word: false,
         advanced_regex,
         interpret_escape_sequences,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
     let dir_config = DirConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         include_globs: Some(""),
         exclude_globs: Some(""),
         include_hidden: false,
         include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
     };
 
-    let result = run_headless(search_config, dir_config);
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
     assert!(result.is_ok());
 
     assert_test_files!(
@@ -78,18 +104,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 3 files updated\n".to_owned());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 3 files updated\n".to_owned()
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -141,18 +178,29 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 3 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 3 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -175,6 +223,267 @@ test_with_both_regex_modes!(
     }
 );
 
+test_with_both_regex_modes!(
+    test_headless_require_filters_lines,
+    |advanced_regex| async move {
+        let temp_dir = create_test_files!(
+            "file1.txt" => text!(
+                "let foo = 1;",
+                "// foo is unused",
+                "let bar = foo;",
+            ),
+            "file2.txt" => text!(
+                "// only comments mention foo",
+            ),
+        );
+
+        let search_config = SearchConfig {
+            search_text: "foo",
+            replacement_text: "baz",
+            fixed_strings: false,
+            match_case: true,
+            multiline: false,
+            match_whole_word: false,
+            advanced_regex,
+            interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
+        };
+        let dir_config = DirConfig {
+            directories: vec![temp_dir.path().to_path_buf()],
+            include_globs: Some(""),
+            exclude_globs: Some(""),
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+        let line_filter = LineFilter {
+            require: Some(Regex::new("^let ").unwrap()),
+            exclude: None,
+            columns: None,
+            line_ranges: None,
+        };
+
+        let result = run_headless(search_config, dir_config, &line_filter);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string()
+        );
+
+        assert_test_files!(
+            &temp_dir,
+            "file1.txt" => text!(
+                "let baz = 1;",
+                "// foo is unused",
+                "let bar = baz;",
+            ),
+            "file2.txt" => text!(
+                "// only comments mention foo",
+            ),
+        );
+
+        Ok(())
+    }
+);
+
+test_with_both_regex_modes!(test_headless_columns, |advanced_regex| async move {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!(
+            "--foo--",
+            "    --bar --",
+            "no dashes",
+        ),
+        "file2.txt" => text!(
+            "  foo --",
+        ),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "-+",
+        replacement_text: "#",
+        fixed_strings: false,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+    let line_filter = LineFilter {
+        columns: Some("1:5".parse().unwrap()),
+        ..LineFilter::default()
+    };
+
+    let result = run_headless(search_config, dir_config, &line_filter);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 1 file updated\n".to_string()
+    );
+
+    // Only matches starting within the first five columns are replaced
+    assert_test_files!(
+        &temp_dir,
+        "file1.txt" => text!(
+            "#foo--",
+            "    #bar --",
+            "no dashes",
+        ),
+        "file2.txt" => text!(
+            "  foo --",
+        ),
+    );
+
+    Ok(())
+});
+
+test_with_both_regex_modes!(
+    test_headless_columns_with_context_dependent_pattern,
+    |advanced_regex| async move {
+        let temp_dir = create_test_files!(
+            "file1.txt" => text!(
+                "xab ab",
+                "ab xab",
+                "abab",
+            ),
+        );
+
+        let search_config = SearchConfig {
+            search_text: r"\Bab",
+            replacement_text: "Z",
+            fixed_strings: false,
+            match_case: true,
+            multiline: false,
+            match_whole_word: false,
+            advanced_regex,
+            interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
+        };
+        let dir_config = DirConfig {
+            directories: vec![temp_dir.path().to_path_buf()],
+            include_globs: Some(""),
+            exclude_globs: Some(""),
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+        let line_filter = LineFilter {
+            columns: Some("1:4".parse().unwrap()),
+            ..LineFilter::default()
+        };
+
+        let result = run_headless(search_config, dir_config, &line_filter);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string()
+        );
+
+        // The word boundary is checked against the whole line rather than just the matched text
+        assert_test_files!(
+            &temp_dir,
+            "file1.txt" => text!(
+                "xZ ab",
+                "ab xab",
+                "abZ",
+            ),
+        );
+
+        Ok(())
+    }
+);
+
+test_with_both_regex_modes!(test_headless_line_ranges, |advanced_regex| async move {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!(
+            "foo 1",
+            "foo 2",
+            "foo 3",
+            "foo 4",
+        ),
+        "file2.txt" => text!(
+            "foo 1",
+        ),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "bar",
+        fixed_strings: false,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+    let line_ranges = LineRanges::parse("file1.txt:1,3-4\n", temp_dir.path())?;
+    let line_filter = LineFilter {
+        line_ranges: Some(Arc::new(line_ranges)),
+        ..LineFilter::default()
+    };
+
+    let result = run_headless(search_config, dir_config, &line_filter);
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 1 file updated\n".to_string()
+    );
+
+    // Only the listed lines of the listed files are replaced in
+    assert_test_files!(
+        &temp_dir,
+        "file1.txt" => text!(
+            "bar 1",
+            "foo 2",
+            "bar 3",
+            "bar 4",
+        ),
+        "file2.txt" => text!(
+            "foo 1",
+        ),
+    );
+
+    Ok(())
+});
+
 test_with_both_regex_modes!(
     test_headless_regex_with_capture_groups,
     |advanced_regex| async move {
@@ -198,18 +507,29 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string(),
+        );
 
         let search_config = SearchConfig {
             search_text: r"\[(\d{4})-(\d{2})-(\d{2})\]",
@@ -220,18 +540,29 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("logs.txt"),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -250,6 +581,7 @@ test_with_both_regex_modes!(
 );
 
 #[tokio::test]
+#[allow(clippy::too_many_lines)]
 async fn test_headless_advanced_regex_features() -> anyhow::Result<()> {
     let temp_dir = create_test_files!(
         "code.rs" => text!(
@@ -281,17 +613,28 @@ async fn test_headless_advanced_regex_features() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: true,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
     let dir_config = DirConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         include_globs: Some("code.rs"),
         exclude_globs: Some(""),
         include_hidden: false,
         include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
     };
 
-    let result = run_headless(search_config, dir_config);
-    assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 1 file updated\n".to_string(),
+    );
 
     // Positive lookbehind - match numbers after headings
     let search_config = SearchConfig {
@@ -303,17 +646,28 @@ async fn test_headless_advanced_regex_features() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: true,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
     let dir_config = DirConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         include_globs: Some("*.md"),
         exclude_globs: Some(""),
         include_hidden: false,
         include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
     };
 
-    let result = run_headless(search_config, dir_config);
-    assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 1 file updated\n".to_string(),
+    );
 
     // Add spaces after commas in CSV file
     let search_config = SearchConfig {
@@ -325,17 +679,28 @@ async fn test_headless_advanced_regex_features() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: true,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
     let dir_config = DirConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         include_globs: Some("*.csv"),
         exclude_globs: Some(""),
         include_hidden: false,
         include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
     };
 
-    let result = run_headless(search_config, dir_config);
-    assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 1 file updated\n".to_string(),
+    );
 
     assert_test_files!(
         &temp_dir,
@@ -397,18 +762,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("**/*.rs"),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 4 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 4 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -444,18 +820,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("**/*.rs"),
             exclude_globs: Some("tests/**"),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -491,18 +878,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("**/*.md,**/*.txt"),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -556,18 +954,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: true,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -611,18 +1020,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir1.path().to_path_buf(),
+            directories: vec![temp_dir1.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir1,
@@ -659,18 +1079,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir2.path().to_path_buf(),
+            directories: vec![temp_dir2.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir2,
@@ -709,18 +1140,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -735,6 +1177,72 @@ test_with_both_regex_modes_and_fixed_strings!(
     }
 );
 
+test_with_both_regex_modes_and_fixed_strings!(
+    test_headless_binary_files_included,
+    |advanced_regex, fixed_strings| async move {
+        let temp_dir = create_test_files!(
+            "text.txt" => text!(
+                "This is a text file with PATTERN."
+            ),
+            "binary.bin" => b"This is a binary file with PATTERN".as_slice(),
+            "contains_binary.txt" => binary!(
+                b"\x00\x01 PATTERN",
+                b"with \xFF invalid PATTERN UTF-8",
+            ),
+        );
+
+        let search_config = SearchConfig {
+            search_text: "PATTERN",
+            replacement_text: "REPLACEMENT",
+            fixed_strings,
+            match_case: true,
+            multiline: false,
+            match_whole_word: false,
+            advanced_regex,
+            interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
+        };
+        let dir_config = DirConfig {
+            directories: vec![temp_dir.path().to_path_buf()],
+            include_globs: Some(""),
+            exclude_globs: Some(""),
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: true,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
+        assert_eq!(
+            result.unwrap().to_string(),
+            format!(
+                "Success: 3 files updated\nSearched 2 binary files\n\
+                 Skipped 1 line of invalid UTF-8 in 1 file\n  {}: 1 line\n",
+                temp_dir.path().join("contains_binary.txt").display()
+            ),
+        );
+
+        assert_test_files!(
+            &temp_dir,
+            "text.txt" => text!(
+                "This is a text file with REPLACEMENT."
+            ),
+            "binary.bin" => b"This is a binary file with REPLACEMENT".as_slice(),
+            "contains_binary.txt" => binary!(
+                b"\x00\x01 REPLACEMENT",
+                b"with \xFF invalid PATTERN UTF-8",
+            ),
+        );
+
+        Ok(())
+    }
+);
+
 test_with_both_regex_modes_and_fixed_strings!(
     test_headless_output_formatting,
     |advanced_regex, fixed_strings| async move {
@@ -762,18 +1270,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -809,18 +1328,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: true,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -861,18 +1391,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false, // Default behavior
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string(),
+        );
 
         // Only visible file should be modified, hidden files untouched
         assert_test_files!(
@@ -898,18 +1439,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: true, // Include hidden files
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_string(),
+        );
 
         // Now all files should be modified
         assert_test_files!(
@@ -960,18 +1512,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: true, // Include hidden to ensure .git exclusion is separate
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 1 file updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_string(),
+        );
 
         // Only visible file should be modified, .git folders and files untouched
         assert_test_files!(
@@ -1028,18 +1591,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: true,
             include_git_folders: true,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 5 files updated\n".to_string(),);
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 5 files updated\n".to_string(),
+        );
 
         // All files should be modified including .git folders and files
         assert_test_files!(
@@ -1083,16 +1657,24 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_err());
         let err_str = result.unwrap_err().to_string();
         assert!(err_str.contains("Failed to parse search text"));
@@ -1126,16 +1708,24 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("{{"), // Invalid glob pattern
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("glob"));
@@ -1201,18 +1791,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("*.txt"),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_owned());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_owned()
+        );
 
         // Verify only .txt files were modified
         assert_test_files!(
@@ -1262,18 +1863,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("*.txt"),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 0 files updated\n".to_owned());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 0 files updated\n".to_owned()
+        );
 
         Ok(())
     }
@@ -1329,18 +1941,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some("*.txt"),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_owned());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 2 files updated\n".to_owned()
+        );
 
         // Verify non-.txt files were modified
         assert_test_files!(
@@ -1478,18 +2101,29 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some("**/*.rs"),
             exclude_globs: Some("tests/**"),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 3 files updated\n".to_owned());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 3 files updated\n".to_owned()
+        );
 
         // Verify only source .rs files were modified, not test files or docs
         assert_test_files!(
@@ -1617,18 +2251,32 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "Success: 2 files updated\n".to_owned());
+        assert_eq!(
+            result.unwrap().to_string(),
+            format!(
+                "Success: 2 files updated\nSkipped 1 line of invalid UTF-8 in 1 file\n  {}: 1 line\n",
+                temp_dir.path().join("contains_binary.txt").display()
+            )
+        );
 
         assert_test_files!(
             &temp_dir,
@@ -1682,9 +2330,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -1715,9 +2365,11 @@ test_with_both_regex_modes!(test_text_regex_replacement, |advanced_regex| async
         match_whole_word: false,
         advanced_regex,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
 
-    let result = run_headless_with_stdin(input_text, search_config);
+    let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
     assert!(result.is_ok());
     assert_eq!(
         result.unwrap(),
@@ -1748,9 +2400,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -1774,9 +2428,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result2 = run_headless_with_stdin(input_text2, search_config2);
+        let result2 = run_headless_with_stdin(input_text2, search_config2, &LineFilter::default());
         assert!(result2.is_ok());
         assert_eq!(
             result2.unwrap(),
@@ -1808,9 +2464,11 @@ async fn test_text_advanced_regex_features() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: true,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
 
-    let result = run_headless_with_stdin(input_text, search_config);
+    let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
     assert!(result.is_ok());
     assert_eq!(
         result.unwrap(),
@@ -1837,9 +2495,11 @@ async fn test_text_advanced_regex_features() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: true,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
 
-    let result2 = run_headless_with_stdin(input_text2, search_config2);
+    let result2 = run_headless_with_stdin(input_text2, search_config2, &LineFilter::default());
     assert!(result2.is_ok());
     assert_eq!(
         result2.unwrap(),
@@ -1870,9 +2530,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: true,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -1904,9 +2566,12 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_sensitive = run_headless_with_stdin(input_text, search_config_sensitive);
+        let result_sensitive =
+            run_headless_with_stdin(input_text, search_config_sensitive, &LineFilter::default());
         assert!(result_sensitive.is_ok());
         assert_eq!(
             result_sensitive.unwrap(),
@@ -1926,9 +2591,15 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_insensitive = run_headless_with_stdin(input_text, search_config_insensitive);
+        let result_insensitive = run_headless_with_stdin(
+            input_text,
+            search_config_insensitive,
+            &LineFilter::default(),
+        );
         assert!(result_insensitive.is_ok());
         assert_eq!(
             result_insensitive.unwrap(),
@@ -1956,9 +2627,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(empty_text, search_config);
+        let result = run_headless_with_stdin(empty_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "");
 
@@ -1973,9 +2646,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(single_line, search_config);
+        let result = run_headless_with_stdin(single_line, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "This line has REPLACEMENT in it");
 
@@ -1990,9 +2665,12 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(single_line_no_match, search_config);
+        let result =
+            run_headless_with_stdin(single_line_no_match, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "This line has no matches");
 
@@ -2017,9 +2695,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -2047,9 +2727,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_err());
         let err_str = result.unwrap_err().to_string();
         assert!(err_str.contains("Failed to parse search text"));
@@ -2081,9 +2763,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -2122,9 +2806,11 @@ test_with_both_regex_modes!(test_text_multiline_matches, |advanced_regex| async
         match_whole_word: false,
         advanced_regex,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
 
-    let result = run_headless_with_stdin(input_text, search_config);
+    let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
     assert!(result.is_ok());
     assert_eq!(
         result.unwrap(),
@@ -2156,16 +2842,24 @@ async fn test_headless_multiline_crlf_file_replacement() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: false,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
     let dir_config = DirConfig {
-        directory: temp_dir.path().to_path_buf(),
+        directories: vec![temp_dir.path().to_path_buf()],
         include_globs: Some(""),
         exclude_globs: Some(""),
         include_hidden: false,
         include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
     };
 
-    let result = run_headless(search_config, dir_config);
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
     assert!(result.is_ok());
 
     assert_test_files!(
@@ -2190,9 +2884,11 @@ async fn test_headless_multiline_advanced_regex_lookaround() -> anyhow::Result<(
         match_whole_word: false,
         advanced_regex: true,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
 
-    let result = run_headless_with_stdin(input_text, search_config);
+    let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), "start\nREPLACED\nend\n");
 
@@ -2213,9 +2909,11 @@ async fn test_headless_multiline_unicode_boundary() -> anyhow::Result<()> {
         match_whole_word: false,
         advanced_regex: false,
         interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
     };
 
-    let result = run_headless_with_stdin(input_text, search_config);
+    let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), "αβXεζ\n");
 
@@ -2236,9 +2934,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_lf = run_headless_with_stdin(input_lf, search_config);
+        let result_lf = run_headless_with_stdin(input_lf, search_config, &LineFilter::default());
         assert!(result_lf.is_ok());
         assert_eq!(
             result_lf.unwrap(),
@@ -2256,9 +2956,12 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_crlf = run_headless_with_stdin(input_crlf, search_config_crlf);
+        let result_crlf =
+            run_headless_with_stdin(input_crlf, search_config_crlf, &LineFilter::default());
         assert!(result_crlf.is_ok());
         assert_eq!(
             result_crlf.unwrap(),
@@ -2276,9 +2979,12 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_mixed = run_headless_with_stdin(input_mixed, search_config_mixed);
+        let result_mixed =
+            run_headless_with_stdin(input_mixed, search_config_mixed, &LineFilter::default());
         assert!(result_mixed.is_ok());
         assert_eq!(
             result_mixed.unwrap(),
@@ -2296,10 +3002,15 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_no_trailing =
-            run_headless_with_stdin(input_no_trailing, search_config_no_trailing);
+        let result_no_trailing = run_headless_with_stdin(
+            input_no_trailing,
+            search_config_no_trailing,
+            &LineFilter::default(),
+        );
         assert!(result_no_trailing.is_ok());
         assert_eq!(
             result_no_trailing.unwrap(),
@@ -2318,9 +3029,15 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result_empty_lines = run_headless_with_stdin(input_empty_lines, search_config_empty);
+        let result_empty_lines = run_headless_with_stdin(
+            input_empty_lines,
+            search_config_empty,
+            &LineFilter::default(),
+        );
         assert!(result_empty_lines.is_ok());
         assert_eq!(
             result_empty_lines.unwrap(),
@@ -2349,9 +3066,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
@@ -2382,9 +3101,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(&input_text, search_config);
+        let result = run_headless_with_stdin(&input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         let expected = format!(
             "Short line\n{}REPLACED{}\nAnother short line",
@@ -2411,9 +3132,11 @@ test_with_multiline_modes!(
             match_whole_word: false,
             advanced_regex: false,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "REPLACED\nbaz qux\nREPLACED again");
 
@@ -2435,9 +3158,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "line REPLACED\nline three");
 
@@ -2459,9 +3184,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "line one\nline two\nline three");
 
@@ -2483,9 +3210,11 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "REPLACED\nbaz.*qux\nREPLACED again");
 
@@ -2496,30 +3225,120 @@ test_with_both_regex_modes!(
 // Interpret escape sequences tests
 
 test_with_both_regex_modes_and_fixed_strings!(
-    test_text_interpret_escape_sequences_replacement,
+    test_text_wrap_matches,
     |advanced_regex, fixed_strings| async move {
-        let input_text = "foo bar\nbaz qux";
+        let input_text = "// TODO: tidy\nlet cost = 5; // TODO\n";
 
         let search_config = SearchConfig {
-            search_text: "bar",
-            replacement_text: r"bar\nbaz replaced",
+            search_text: "TODO",
+            replacement_text: "/* {} ($) */",
             fixed_strings,
             match_case: true,
             multiline: false,
             match_whole_word: false,
             advanced_regex,
-            interpret_escape_sequences: true,
+            interpret_escape_sequences: false,
+            wrap_matches: true,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "foo bar\nbaz replaced\nbaz qux");
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
+        assert_eq!(
+            result.unwrap(),
+            "// /* TODO ($) */: tidy\nlet cost = 5; // /* TODO ($) */\n"
+        );
 
         Ok(())
     }
 );
 
-test_with_both_regex_modes_and_fixed_strings!(
+test_with_both_regex_modes!(
+    test_headless_delete_empty_result_lines,
+    |advanced_regex| async move {
+        let temp_dir = create_test_files!(
+            "main.rs" => text!(
+                "fn main() {",
+                "    print(x);",
+                "    let y = 1; print(y);",
+                "",
+                "}",
+            ),
+        );
+
+        let search_config = SearchConfig {
+            search_text: r"\s*print\([^)]*\);",
+            replacement_text: "",
+            fixed_strings: false,
+            match_case: true,
+            multiline: false,
+            match_whole_word: false,
+            advanced_regex,
+            interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: true,
+        };
+        let dir_config = DirConfig {
+            directories: vec![temp_dir.path().to_path_buf()],
+            include_globs: Some(""),
+            exclude_globs: Some(""),
+            include_hidden: false,
+            include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
+        };
+
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
+        assert_eq!(
+            result.unwrap().to_string(),
+            "Success: 1 file updated\n".to_owned()
+        );
+
+        // Lines that still contain text after the replacement, or were already empty, are kept
+        assert_test_files!(
+            &temp_dir,
+            "main.rs" => text!(
+                "fn main() {",
+                "    let y = 1;",
+                "",
+                "}",
+            ),
+        );
+
+        Ok(())
+    }
+);
+
+test_with_both_regex_modes_and_fixed_strings!(
+    test_text_interpret_escape_sequences_replacement,
+    |advanced_regex, fixed_strings| async move {
+        let input_text = "foo bar\nbaz qux";
+
+        let search_config = SearchConfig {
+            search_text: "bar",
+            replacement_text: r"bar\nbaz replaced",
+            fixed_strings,
+            match_case: true,
+            multiline: false,
+            match_whole_word: false,
+            advanced_regex,
+            interpret_escape_sequences: true,
+            wrap_matches: false,
+            delete_empty_lines: false,
+        };
+
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), "foo bar\nbaz replaced\nbaz qux");
+
+        Ok(())
+    }
+);
+
+test_with_both_regex_modes_and_fixed_strings!(
     test_text_interpret_escape_sequences_tab,
     |advanced_regex, fixed_strings| async move {
         let input_text = "key=value";
@@ -2533,9 +3352,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: true,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "key\tvalue");
 
@@ -2557,9 +3378,11 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
 
-        let result = run_headless_with_stdin(input_text, search_config);
+        let result = run_headless_with_stdin(input_text, search_config, &LineFilter::default());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), r"foo bar\nbaz");
 
@@ -2586,16 +3409,24 @@ test_with_both_regex_modes_and_fixed_strings!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: true,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
 
         assert_test_files!(
@@ -2632,9 +3463,12 @@ async fn test_headless_multiline_escape_matrix() -> anyhow::Result<()> {
                     match_whole_word: false,
                     advanced_regex,
                     interpret_escape_sequences,
+                    wrap_matches: false,
+                    delete_empty_lines: false,
                 };
 
-                let result = run_headless_with_stdin("foo\nbar\nbaz", search_config);
+                let result =
+                    run_headless_with_stdin("foo\nbar\nbaz", search_config, &LineFilter::default());
                 assert!(result.is_ok());
                 let expected = if multiline {
                     if interpret_escape_sequences {
@@ -2661,9 +3495,12 @@ async fn test_headless_multiline_escape_matrix() -> anyhow::Result<()> {
                     match_whole_word: false,
                     advanced_regex,
                     interpret_escape_sequences,
+                    wrap_matches: false,
+                    delete_empty_lines: false,
                 };
 
-                let result = run_headless_with_stdin("foo bar", search_config);
+                let result =
+                    run_headless_with_stdin("foo bar", search_config, &LineFilter::default());
                 assert!(result.is_ok());
                 let expected = if interpret_escape_sequences {
                     "foo X\nY"
@@ -2770,16 +3607,24 @@ test_with_both_regex_modes!(
             match_whole_word: false,
             advanced_regex,
             interpret_escape_sequences: false,
+            wrap_matches: false,
+            delete_empty_lines: false,
         };
         let dir_config = DirConfig {
-            directory: temp_dir.path().to_path_buf(),
+            directories: vec![temp_dir.path().to_path_buf()],
             include_globs: Some(""),
             exclude_globs: Some(""),
             include_hidden: false,
             include_git_folders: false,
+            include_binary: false,
+            binary_detection: BinaryDetection::default(),
+            max_depth: None,
+            modified_since: None,
+            tracked_files: None,
+            regex_timeout: None,
         };
 
-        let result = run_headless(search_config, dir_config);
+        let result = run_headless(search_config, dir_config, &LineFilter::default());
         assert!(result.is_ok());
 
         assert_test_files!(
@@ -2793,3 +3638,741 @@ test_with_both_regex_modes!(
         Ok(())
     }
 );
+
+#[tokio::test]
+#[serial]
+async fn test_headless_multiple_directories() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "a/src/file.txt" => text!("foo"),
+        "a/other.txt" => text!("foo"),
+        "b/src/file.txt" => text!("foo"),
+        "c/src/file.txt" => text!("foo"),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "bar",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().join("a"), temp_dir.path().join("b")],
+        // Globs are matched relative to each directory
+        include_globs: Some("src/*"),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 2 files updated\n".to_string()
+    );
+
+    assert_test_files!(
+        temp_dir,
+        "a/src/file.txt" => text!("bar"),
+        "a/other.txt" => text!("foo"),
+        "b/src/file.txt" => text!("bar"),
+        "c/src/file.txt" => text!("foo"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_nested_directories() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "a.txt" => text!("foo"),
+        "sub/b.txt" => text!("foo"),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "foofoo",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        // `sub` is already walked as part of the root, so its files must only be replaced once
+        directories: vec![
+            temp_dir.path().to_path_buf(),
+            temp_dir.path().join("sub"),
+            temp_dir.path().join("sub/../sub"),
+        ],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 2 files updated\n".to_string()
+    );
+
+    assert_test_files!(
+        temp_dir,
+        "a.txt" => text!("foofoo"),
+        "sub/b.txt" => text!("foofoo"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_replacement_pairs() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!("old_name and other_name"),
+        "file2.txt" => text!("other_name"),
+        "file3.txt" => text!("unrelated"),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "",
+        replacement_text: "",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+    let pairs = replacement_pairs::parse_replacement_pairs(indoc! {"
+        old_name,new_name
+        other_name,old_name
+    "})?;
+
+    // Pairs are applied in order, so `other_name` becomes `old_name` without then becoming `new_name`
+    let result =
+        run_headless_with_pairs(&pairs, &search_config, &dir_config, &LineFilter::default())?;
+    assert_eq!(
+        result.to_string(),
+        indoc! {"
+            Success:
+              old_name -> new_name: 1 file updated
+              other_name -> old_name: 2 files updated
+        "}
+    );
+    // Files changed by several pairs are only listed once
+    assert_eq!(
+        result.changed_files,
+        [
+            temp_dir.path().join("file1.txt"),
+            temp_dir.path().join("file2.txt")
+        ]
+    );
+
+    assert_test_files!(
+        temp_dir,
+        "file1.txt" => text!("new_name and old_name"),
+        "file2.txt" => text!("old_name"),
+        "file3.txt" => text!("unrelated"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_jobs() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "src/lib.rs" => text!("use old_crate::Client;"),
+        "docs/guide.md" => text!("See old_crate::Client and OldClient"),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "",
+        replacement_text: "",
+        fixed_strings: false,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: None,
+        exclude_globs: None,
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+    let jobs = jobs::parse_jobs(indoc! {r#"
+        [[jobs]]
+        name = "Rename crate in code"
+        search = "old_crate::"
+        replace = "new_crate::"
+        fixed_strings = true
+        files_to_include = "*.rs"
+
+        [[jobs]]
+        search = '\bOld(\w+)'
+        replace = "New$1"
+        files_to_exclude = "*.rs"
+    "#})?;
+
+    let result = run_headless_jobs(
+        &jobs,
+        &search_config,
+        &dir_config,
+        &LineFilter::default(),
+        false,
+    );
+    assert_eq!(
+        result.unwrap().to_string(),
+        indoc! {r"
+            Rename crate in code: 1 file updated
+            \bOld(\w+) -> New$1: 1 file updated
+            Total: 2 files updated
+        "}
+    );
+
+    assert_test_files!(
+        temp_dir,
+        "src/lib.rs" => text!("use new_crate::Client;"),
+        "docs/guide.md" => text!("See old_crate::Client and NewClient"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_max_depth() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "top.txt" => text!("foo"),
+        "a/mid.txt" => text!("foo"),
+        "a/b/deep.txt" => text!("foo"),
+        "c/d/deep.txt" => text!("foo"),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "bar",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: Some(2),
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 2 files updated\nSkipped 2 directories beyond max depth of 2\n".to_string()
+    );
+
+    assert_test_files!(
+        temp_dir,
+        "top.txt" => text!("bar"),
+        "a/mid.txt" => text!("bar"),
+        "a/b/deep.txt" => text!("foo"),
+        "c/d/deep.txt" => text!("foo"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_modified_since() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "old.txt" => text!("foo"),
+        "new.txt" => text!("foo"),
+    );
+    let now = SystemTime::now();
+    let day = Duration::from_secs(24 * 60 * 60);
+    File::options()
+        .write(true)
+        .open(temp_dir.path().join("old.txt"))?
+        .set_modified(now - 3 * day)?;
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "bar",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: Some(modified_since::parse_modified_since("2d", now)?),
+        tracked_files: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 1 file updated\n".to_string()
+    );
+
+    assert_test_files!(
+        temp_dir,
+        "old.txt" => text!("foo"),
+        "new.txt" => text!("bar"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_git_tracked_only() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "tracked.txt" => text!("foo"),
+        "src/tracked.txt" => text!("foo"),
+        "untracked.txt" => text!("foo"),
+    );
+    for args in [
+        &["init", "-q"][..],
+        &["add", "tracked.txt", "src/tracked.txt"],
+    ] {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(temp_dir.path())
+            .status()?;
+        assert!(status.success());
+    }
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "bar",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let directories = vec![temp_dir.path().to_path_buf()];
+    let dir_config = DirConfig {
+        tracked_files: Some(Arc::new(git::tracked_files_in(&directories)?)),
+        directories,
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless(search_config, dir_config, &LineFilter::default());
+    assert_eq!(
+        result.unwrap().to_string(),
+        "Success: 2 files updated\n".to_string()
+    );
+
+    // `assert_test_files!` can't be used, as it would also expect the `.git` directory to be listed
+    let read = |file: &str| std::fs::read(temp_dir.path().join(file)).unwrap();
+    assert_eq!(read("tracked.txt"), text!("bar"));
+    assert_eq!(read("src/tracked.txt"), text!("bar"));
+    assert_eq!(read("untracked.txt"), text!("foo"));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_changed_files() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "b.txt" => text!("foo", "foo"),
+        "a/c.txt" => text!("foo"),
+        "d.txt" => text!("bar"),
+    );
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "baz",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless(search_config, dir_config, &LineFilter::default())?;
+    assert_eq!(
+        result.changed_files,
+        [
+            temp_dir.path().join("a").join("c.txt"),
+            temp_dir.path().join("b.txt")
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_shadow() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!("foo"),
+        "src/file2.txt" => text!("foo", "bar"),
+        "src/file3.txt" => text!("bar"),
+    );
+    let shadow_dir = create_test_files!();
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "baz",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+
+    let result = run_headless_in_shadow(
+        search_config.clone(),
+        dir_config.clone(),
+        &LineFilter::default(),
+        shadow_dir.path(),
+    )?;
+    assert_eq!(
+        result.changed_files,
+        [
+            shadow_dir.path().join("file1.txt"),
+            shadow_dir.path().join("src").join("file2.txt")
+        ]
+    );
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("src").join("file2.txt");
+    assert_eq!(
+        result.to_string(),
+        format!(
+            "Success: 2 files updated in shadow copy at {shadow}\n  file1.txt\n  {file2_relative}\n\n\
+             --- {file1}\n+++ {file1_copy}\n@@ -1 +1 @@\n-foo\n+baz\n\
+             --- {file2}\n+++ {file2_copy}\n@@ -1,2 +1,2 @@\n-foo\n+baz\n bar\n",
+            shadow = shadow_dir.path().display(),
+            file2_relative = Path::new("src").join("file2.txt").display(),
+            file1 = file1.display(),
+            file1_copy = shadow_dir.path().join("file1.txt").display(),
+            file2 = file2.display(),
+            file2_copy = shadow_dir.path().join("src").join("file2.txt").display(),
+        )
+    );
+
+    // Only copies of the files with matches are made, and the originals are left untouched
+    assert_test_files!(
+        shadow_dir,
+        "file1.txt" => text!("baz"),
+        "src/file2.txt" => text!("baz", "bar"),
+    );
+    assert_test_files!(
+        temp_dir,
+        "file1.txt" => text!("foo"),
+        "src/file2.txt" => text!("foo", "bar"),
+        "src/file3.txt" => text!("bar"),
+    );
+
+    // The shadow directory must be empty, so that existing files aren't overwritten
+    let result = run_headless_in_shadow(
+        search_config,
+        dir_config,
+        &LineFilter::default(),
+        shadow_dir.path(),
+    );
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        format!(
+            "Shadow directory {} must be empty",
+            shadow_dir.path().display()
+        )
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_color() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!("foo", "bar"),
+    );
+    let shadow_dir = create_test_files!();
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "baz",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+    let result = run_headless_in_shadow(
+        search_config,
+        dir_config,
+        &LineFilter::default(),
+        shadow_dir.path(),
+    )?;
+    let plain = result.to_string();
+
+    // `--color never`
+    let never = format_headless_results(&result, ColorChoice::Never.use_color());
+    assert_eq!(never, plain);
+    assert!(!never.contains('\x1b'));
+
+    // `--color always`
+    ColorChoice::Always.apply();
+    let always = format_headless_results(&result, ColorChoice::Always.use_color());
+    let file1 = temp_dir.path().join("file1.txt");
+    let file1_copy = shadow_dir.path().join("file1.txt");
+    assert_eq!(
+        always,
+        format!(
+            "{success}\n  file1.txt\n\n{old}\n{new}\n{hunk}\n{removed}\n{added}\n bar\n",
+            success = format!(
+                "Success: 1 file updated in shadow copy at {}",
+                shadow_dir.path().display()
+            )
+            .green(),
+            old = format!("--- {}", file1.display()).bold(),
+            new = format!("+++ {}", file1_copy.display()).bold(),
+            hunk = "@@ -1,2 +1,2 @@".cyan(),
+            removed = "-foo".red(),
+            added = "+baz".green(),
+        )
+    );
+    let ansi = Regex::new(r"\x1b\[[0-9;]*m")?;
+    assert_eq!(ansi.replace_all(&always, ""), plain);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_headless_shadow_line_ranges() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "file1.txt" => text!("foo", "foo", "foo"),
+        "file2.txt" => text!("foo"),
+    );
+    let shadow_dir = create_test_files!();
+
+    let search_config = SearchConfig {
+        search_text: "foo",
+        replacement_text: "baz",
+        fixed_strings: true,
+        match_case: true,
+        multiline: false,
+        match_whole_word: false,
+        advanced_regex: false,
+        interpret_escape_sequences: false,
+        wrap_matches: false,
+        delete_empty_lines: false,
+    };
+    let dir_config = DirConfig {
+        directories: vec![temp_dir.path().to_path_buf()],
+        include_globs: Some(""),
+        exclude_globs: Some(""),
+        include_hidden: false,
+        include_git_folders: false,
+        include_binary: false,
+        binary_detection: BinaryDetection::default(),
+        max_depth: None,
+        modified_since: None,
+        tracked_files: None,
+        regex_timeout: None,
+    };
+    let line_filter = LineFilter {
+        line_ranges: Some(Arc::new(LineRanges::parse(
+            "file1.txt:2-3\n",
+            temp_dir.path(),
+        )?)),
+        ..LineFilter::default()
+    };
+
+    let result =
+        run_headless_in_shadow(search_config, dir_config, &line_filter, shadow_dir.path())?;
+    assert_eq!(result.changed_files, [shadow_dir.path().join("file1.txt")]);
+
+    // Copies are replaced in using the line ranges of the originals
+    assert_test_files!(
+        shadow_dir,
+        "file1.txt" => text!("foo", "baz", "baz"),
+    );
+    assert_test_files!(
+        temp_dir,
+        "file1.txt" => text!("foo", "foo", "foo"),
+        "file2.txt" => text!("foo"),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_git_stage_with_print_changed_files() -> anyhow::Result<()> {
+    let temp_dir = create_test_files!(
+        "changed.txt" => text!("foo"),
+        "unchanged.txt" => text!("baz"),
+    );
+    let config_dir = tempfile::TempDir::new()?;
+    for args in [&["init", "-q"][..], &["add", "."]] {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(temp_dir.path())
+            .status()?;
+        assert!(status.success());
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_scooter"))
+        .args([
+            "--no-tui",
+            "--no-stdin",
+            "--no-local-config",
+            "--search-text=foo",
+            "--replace-text=bar",
+        ])
+        .args(["--print-changed-files", "--git-stage", "--config-dir"])
+        .arg(config_dir.path())
+        .arg(temp_dir.path())
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Only the changed paths are printed to stdout, so that they can be piped elsewhere
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        format!("{}\n", temp_dir.path().join("changed.txt").display())
+    );
+    assert_eq!(String::from_utf8(output.stderr)?, "Staged 1 file in git\n");
+    Ok(())
+}
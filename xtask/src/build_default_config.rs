@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use syn::{Fields, ItemStruct};
+
+use crate::build_readme::{extract_doc_comment, get_type_name, parse_config_structs};
+
+const HEADER: &str = "\
+# Uncomment any of the options below to change them from their default values.
+# Lines starting with `##` describe the option or section that follows them.
+";
+
+/// Options whose default values are detected from the environment, as `(section, option)`. These are
+/// written without a value, so that the generated config is the same wherever it's generated
+const DETECTED_OPTIONS: &[(&str, &str)] = &[("style", "true_color")];
+
+pub fn generate_default_config(
+    output_path: &Path,
+    config_path: &Path,
+    check_only: bool,
+) -> Result<()> {
+    println!("Generating default config: {}", output_path.display());
+
+    let structs = parse_config_structs(config_path)?;
+    let config_struct = structs
+        .get("Config")
+        .context("Config struct not found in the source file")?;
+    let mut defaults = toml::Table::try_from(scooter_core::config::Config::default())
+        .context("Failed to serialize default config to TOML")?;
+    for (section, option) in DETECTED_OPTIONS {
+        if let Some(toml::Value::Table(section)) = defaults.get_mut(*section) {
+            section.remove(*option);
+        }
+    }
+
+    let mut content = HEADER.to_owned();
+    write_struct(&mut content, config_struct, &structs, &defaults, "")?;
+
+    let existing = fs::read_to_string(output_path).unwrap_or_default();
+    if content != existing {
+        if check_only {
+            println!("Default config is out of date and needs regenerating");
+            std::process::exit(1);
+        }
+        fs::write(output_path, content).context(format!(
+            "Failed to write default config: {}",
+            output_path.display()
+        ))?;
+
+        println!("Default config updated successfully");
+    } else {
+        println!("Default config is already up to date");
+    }
+
+    Ok(())
+}
+
+/// Writes each field of `struct_item` with its doc comment and default value, all commented out. Fields
+/// with a struct type become sections, and fields without a default value are left for the user to fill in
+fn write_struct(
+    content: &mut String,
+    struct_item: &ItemStruct,
+    all_structs: &HashMap<String, ItemStruct>,
+    defaults: &toml::Table,
+    toml_prefix: &str,
+) -> Result<()> {
+    let Fields::Named(ref fields) = struct_item.fields else {
+        return Ok(());
+    };
+
+    for field in &fields.named {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let field_name = ident.to_string();
+        let field_doc = extract_doc_comment(&field.attrs);
+
+        if let Some(nested_struct) = all_structs
+            .get(&get_type_name(field))
+            .filter(|s| matches!(s.fields, Fields::Named(_)))
+        {
+            let toml_path = if toml_prefix.is_empty() {
+                field_name.clone()
+            } else {
+                format!("{toml_prefix}.{field_name}")
+            };
+            let nested_defaults = match defaults.get(&field_name) {
+                Some(toml::Value::Table(table)) => table.clone(),
+                _ => toml::Table::new(),
+            };
+
+            writeln!(content)?;
+            write_doc(content, &field_doc)?;
+            writeln!(content, "# [{toml_path}]")?;
+            write_struct(
+                content,
+                nested_struct,
+                all_structs,
+                &nested_defaults,
+                &toml_path,
+            )?;
+        } else {
+            writeln!(content)?;
+            write_doc(content, &field_doc)?;
+            match defaults.get(&field_name) {
+                Some(value) => {
+                    let mut table = toml::Table::new();
+                    table.insert(field_name, value.clone());
+                    let line = toml::to_string(&table)
+                        .context("Failed to serialize default value to TOML")?;
+                    for line in line.lines() {
+                        writeln!(content, "# {line}")?;
+                    }
+                }
+                None => writeln!(content, "# {field_name} =")?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_doc(content: &mut String, doc: &str) -> Result<()> {
+    for line in doc.lines() {
+        if line.is_empty() {
+            writeln!(content, "##")?;
+        } else {
+            writeln!(content, "## {line}")?;
+        }
+    }
+    Ok(())
+}
@@ -202,7 +202,7 @@ fn process_struct(
     }
 }
 
-fn get_type_name(field: &Field) -> String {
+pub(crate) fn get_type_name(field: &Field) -> String {
     field
         .ty
         .to_token_stream()
@@ -212,7 +212,7 @@ fn get_type_name(field: &Field) -> String {
         .to_string()
 }
 
-fn extract_doc_comment(attrs: &[Attribute]) -> String {
+pub(crate) fn extract_doc_comment(attrs: &[Attribute]) -> String {
     let mut doc_lines = Vec::new();
 
     for attr in attrs {
@@ -383,13 +383,14 @@ fn generate_keys_docs(content: &str) -> Result<String> {
 
     // Parse TOML into sections for alignment
     let mut sections: Vec<(String, Vec<String>)> = Vec::new();
-    let mut current_section = String::new();
+    // Fields before the first section header (e.g. `preset`) belong to `[keys]` itself
+    let mut current_section = "keys".to_owned();
     let mut current_lines: Vec<String> = Vec::new();
 
     for line in toml_str.lines() {
         if line.starts_with('[') && !line.starts_with("[[") {
-            // Save previous section if it exists
-            if !current_section.is_empty() {
+            // Save previous section if it has any fields or is a nested section
+            if !current_lines.is_empty() || current_section != "keys" {
                 sections.push((current_section.clone(), current_lines.clone()));
                 current_lines.clear();
             }
@@ -403,9 +404,7 @@ fn generate_keys_docs(content: &str) -> Result<String> {
     }
 
     // Don't forget the last section
-    if !current_section.is_empty() {
-        sections.push((current_section, current_lines));
-    }
+    sections.push((current_section, current_lines));
 
     // Generate output with aligned comments
     let mut keys_str_with_comments = String::new();
@@ -456,12 +455,20 @@ fn generate_keys_docs(content: &str) -> Result<String> {
 }
 
 /// Parse config.rs and all its submodules, extracting all struct definitions
-fn parse_config_structs(config_path: &Path) -> Result<HashMap<String, ItemStruct>> {
+pub(crate) fn parse_config_structs(config_path: &Path) -> Result<HashMap<String, ItemStruct>> {
     let mut structs = HashMap::new();
     parse_file_and_modules(config_path, &mut structs)?;
     Ok(structs)
 }
 
+/// Whether a struct is deserialized by converting from another type, e.g. `#[serde(try_from = "String")]`,
+/// in which case it is a single value in the config file rather than a section
+fn is_deserialized_via_conversion(struct_item: &ItemStruct) -> bool {
+    struct_item.attrs.iter().any(|attr| {
+        attr.path().is_ident("serde") && attr.meta.to_token_stream().to_string().contains("from =")
+    })
+}
+
 /// Recursively parse a Rust file and its submodules, collecting all struct definitions
 fn parse_file_and_modules(
     file_path: &Path,
@@ -476,7 +483,7 @@ fn parse_file_and_modules(
     // Collect structs and find submodules
     for item in &syntax.items {
         match item {
-            Item::Struct(s) => {
+            Item::Struct(s) if !is_deserialized_via_conversion(s) => {
                 structs.insert(s.ident.to_string(), s.clone());
             }
             Item::Mod(module) => {
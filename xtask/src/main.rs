@@ -1,3 +1,4 @@
+mod build_default_config;
 mod build_readme;
 
 use anyhow::Result;
@@ -27,6 +28,20 @@ enum Commands {
         #[arg(long)]
         check: bool,
     },
+    /// Generate the documented default config file, which is written when creating a config file
+    DefaultConfig {
+        /// Path to write the default config to
+        #[arg(long, default_value = "scooter-core/src/default_config.toml")]
+        output: PathBuf,
+
+        /// Path to config.rs file
+        #[arg(long, default_value = "scooter-core/src/config.rs")]
+        config: PathBuf,
+
+        /// Only check if the default config is up to date, without modifying it
+        #[arg(long)]
+        check: bool,
+    },
 }
 
 fn main() -> Result<()> {
@@ -40,6 +55,13 @@ fn main() -> Result<()> {
         } => {
             build_readme::generate_readme(readme, config, *check)?;
         }
+        Commands::DefaultConfig {
+            output,
+            config,
+            check,
+        } => {
+            build_default_config::generate_default_config(output, config, *check)?;
+        }
     }
 
     Ok(())